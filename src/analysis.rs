@@ -0,0 +1,458 @@
+/// Per-turn records of the AI's probability estimates, for analysing them after the game or for
+/// training policies on.
+use crate::bet::*;
+use crate::error::*;
+use crate::game::*;
+use crate::logging;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref ANALYSIS_OUT: Mutex<Option<File>> = Mutex::new(None);
+    static ref TURNS_CSV: Mutex<Option<File>> = Mutex::new(None);
+    static ref COLLECTED: Mutex<Option<Vec<TurnRecord>>> = Mutex::new(None);
+}
+
+/// How many bins estimates are put into when checking calibration.
+pub const NUM_CALIBRATION_BINS: usize = 10;
+
+/// The columns of the per-turn CSV, in order.
+pub const TURNS_CSV_HEADER: &str = "game_id,round,player_id,human,num_players,total_num_items,\
+                                    num_own_items,num_bets,action,bet,bet_size,estimated_prob,\
+                                    correct";
+
+/// Starts writing turn records to the given path.
+pub fn init_analysis(analysis_path: &str) -> Result<(), ScrabrudoError> {
+    let f = File::create(analysis_path)?;
+    let mut analysis_out = ANALYSIS_OUT.lock().unwrap();
+    *analysis_out = Some(f);
+    Ok(())
+}
+
+/// Starts writing turn records to the given path as CSV, one row per turn.
+pub fn init_turns_csv(csv_path: &str) -> Result<(), ScrabrudoError> {
+    let mut f = File::create(csv_path)?;
+    writeln!(f, "{}", TURNS_CSV_HEADER)?;
+    *TURNS_CSV.lock().unwrap() = Some(f);
+    Ok(())
+}
+
+/// Starts keeping turn records in memory, to be taken once the games are played.
+pub fn init_collection() {
+    *COLLECTED.lock().unwrap() = Some(vec![]);
+}
+
+/// Takes the turn records kept so far.
+pub fn take_collected() -> Vec<TurnRecord> {
+    COLLECTED
+        .lock()
+        .unwrap()
+        .as_mut()
+        .map_or(vec![], std::mem::take)
+}
+
+/// Whether turn records are being written.
+pub fn analysis_enabled() -> bool {
+    ANALYSIS_OUT.lock().unwrap().is_some()
+        || TURNS_CSV.lock().unwrap().is_some()
+        || COLLECTED.lock().unwrap().is_some()
+}
+
+/// A single turn: what was done, how likely the player thought it was to succeed, and whether it
+/// actually did.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnRecord {
+    /// The game and round the turn was in, as tagged in the log.
+    #[serde(default)]
+    pub game_id: Option<u32>,
+    #[serde(default)]
+    pub round: usize,
+
+    pub player_id: usize,
+    pub human: bool,
+
+    /// What the player could see of the table: how many players and items there were, how many
+    /// of the items were their own, and how many bets had been made this round.
+    #[serde(default)]
+    pub num_players: usize,
+    #[serde(default)]
+    pub total_num_items: usize,
+    #[serde(default)]
+    pub num_own_items: usize,
+    #[serde(default)]
+    pub num_bets: usize,
+
+    /// One of bet, perudo or palafico.
+    pub action: String,
+
+    /// The bet made, or the bet being challenged.
+    pub bet: String,
+
+    /// The size of that bet, as for Bet::size.
+    #[serde(default)]
+    pub bet_size: usize,
+
+    /// The probability of the action succeeding as estimated by the player.
+    pub estimated_prob: f64,
+
+    /// Whether the action succeeded given every item on the table.
+    pub correct: bool,
+}
+
+impl TurnRecord {
+    /// Creates the record for the current player of the given game choosing the given outcome.
+    pub fn new<G: Game>(game: &G, outcome: &TurnOutcome<G::B>) -> Result<Self, ScrabrudoError> {
+        let state = game.state();
+        let player = &game.players()[game.current_index()];
+        let all_items = game.all_items();
+        let last_bet = game.last_bet()?;
+        let (action, bet, estimated_prob, correct) = match outcome {
+            TurnOutcome::Bet(bet) => (
+                "bet",
+                bet,
+                bet.prob(&state, ProbVariant::Bet, player.cloned()),
+                bet.is_correct(&all_items, false),
+            ),
+            TurnOutcome::Perudo => (
+                "perudo",
+                &last_bet,
+                last_bet.prob(&state, ProbVariant::Perudo, player.cloned()),
+                !last_bet.is_correct(&all_items, false),
+            ),
+            TurnOutcome::Palafico => (
+                "palafico",
+                &last_bet,
+                last_bet.prob(&state, ProbVariant::Palafico, player.cloned()),
+                last_bet.is_correct(&all_items, true),
+            ),
+            outcome => {
+                return Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome)));
+            }
+        };
+        let context = logging::context();
+        Ok(Self {
+            game_id: context.game_id,
+            round: game.round(),
+            player_id: player.id(),
+            human: player.human(),
+            num_players: state.num_items_per_player.len(),
+            total_num_items: state.total_num_items,
+            num_own_items: player.num_items(),
+            num_bets: state.history.values().map(|bets| bets.len()).sum(),
+            action: action.into(),
+            bet: format!("{}", bet),
+            bet_size: bet.size(),
+            estimated_prob: estimated_prob,
+            correct: correct,
+        })
+    }
+
+    /// The record as a row of the per-turn CSV.
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.game_id.map_or(String::new(), |id| format!("{:08x}", id)),
+            self.round,
+            self.player_id,
+            self.human,
+            self.num_players,
+            self.total_num_items,
+            self.num_own_items,
+            self.num_bets,
+            self.action,
+            csv_quote(&self.bet),
+            self.bet_size,
+            self.estimated_prob,
+            self.correct
+        )
+    }
+}
+
+/// Quotes a CSV field, doubling any quotes inside it.
+fn csv_quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+/// Records the turn if analysis is enabled.
+pub fn record_turn<G: Game>(game: &G, outcome: &TurnOutcome<G::B>) -> Result<(), ScrabrudoError> {
+    if !analysis_enabled() {
+        return Ok(());
+    }
+    let record = TurnRecord::new(game, outcome)?;
+    if let Some(f) = ANALYSIS_OUT.lock().unwrap().as_mut() {
+        writeln!(f, "{}", serde_json::to_string(&record)?)?;
+    }
+    if let Some(f) = TURNS_CSV.lock().unwrap().as_mut() {
+        writeln!(f, "{}", record.csv_row())?;
+    }
+    if let Some(records) = COLLECTED.lock().unwrap().as_mut() {
+        records.push(record);
+    }
+    Ok(())
+}
+
+/// Reads all the records from an analysis file.
+pub fn load_records(analysis_path: &str) -> Result<Vec<TurnRecord>, ScrabrudoError> {
+    let f = File::open(analysis_path)?;
+    let mut records = vec![];
+    for line in BufReader::new(f).lines() {
+        records.push(serde_json::from_str(&line?)?);
+    }
+    Ok(records)
+}
+
+/// The mean squared difference between estimated probabilities and what actually happened.
+/// Zero is perfect; always guessing 0.5 scores 0.25.
+pub fn brier_score(records: &[TurnRecord]) -> f64 {
+    if records.is_empty() {
+        return 0.0;
+    }
+    records
+        .iter()
+        .map(|r| {
+            let actual = if r.correct { 1.0 } else { 0.0 };
+            (r.estimated_prob - actual).powi(2)
+        })
+        .sum::<f64>()
+        / records.len() as f64
+}
+
+/// Estimates falling in one bin, and how often they came true.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationBin {
+    pub count: usize,
+    pub mean_estimate: f64,
+    pub frequency: f64,
+}
+
+/// Bins the given records by estimated probability, leaving out empty bins.
+pub fn calibration(records: &[TurnRecord]) -> Vec<CalibrationBin> {
+    let mut bins = vec![vec![]; NUM_CALIBRATION_BINS];
+    for record in records {
+        let i = (record.estimated_prob * NUM_CALIBRATION_BINS as f64) as usize;
+        bins[i.min(NUM_CALIBRATION_BINS - 1)].push(record);
+    }
+    bins.into_iter()
+        .filter(|bin| !bin.is_empty())
+        .map(|bin| CalibrationBin {
+            count: bin.len(),
+            mean_estimate: bin.iter().map(|r| r.estimated_prob).sum::<f64>() / bin.len() as f64,
+            frequency: bin.iter().filter(|r| r.correct).count() as f64 / bin.len() as f64,
+        })
+        .collect()
+}
+
+/// The expected calibration error: how far estimates are from how often they come true, on
+/// average over the bins, weighted by how many estimates each holds. Zero is perfect.
+pub fn calibration_error(records: &[TurnRecord]) -> f64 {
+    if records.is_empty() {
+        return 0.0;
+    }
+    calibration(records)
+        .iter()
+        .map(|bin| bin.count as f64 * (bin.mean_estimate - bin.frequency).abs())
+        .sum::<f64>()
+        / records.len() as f64
+}
+
+/// Logs how well calibrated the AI's estimates of its own bets are, overall and by the size of
+/// the bet.
+pub fn report_calibration(records: &[TurnRecord]) {
+    let bets = records
+        .iter()
+        .filter(|r| r.action == "bet")
+        .cloned()
+        .collect::<Vec<TurnRecord>>();
+    info!(
+        "Calibration error over {} bets: {:.4}",
+        bets.len(),
+        calibration_error(&bets)
+    );
+    for bin in calibration(&bets) {
+        info!(
+            "  estimated {:.2}, came true {:.2} ({} bets)",
+            bin.mean_estimate, bin.frequency, bin.count
+        );
+    }
+    let mut by_size: BTreeMap<usize, Vec<TurnRecord>> = BTreeMap::new();
+    for record in bets {
+        by_size
+            .entry(record.bet_size)
+            .or_insert(vec![])
+            .push(record);
+    }
+    for (size, size_records) in &by_size {
+        info!(
+            "Calibration error over {} bets of size {}: {:.4}",
+            size_records.len(),
+            size,
+            calibration_error(size_records)
+        );
+    }
+}
+
+/// Logs the Brier score of the AI's estimates overall and for each action, and how well
+/// calibrated its bets are.
+pub fn report(analysis_path: &str) -> Result<(), ScrabrudoError> {
+    report_records(&load_records(analysis_path)?);
+    Ok(())
+}
+
+/// Logs as for report, for the given records.
+pub fn report_records(records: &[TurnRecord]) {
+    let records = records
+        .iter()
+        .filter(|r| !r.human)
+        .cloned()
+        .collect::<Vec<TurnRecord>>();
+    let mut by_action: BTreeMap<String, Vec<TurnRecord>> = BTreeMap::new();
+    for record in &records {
+        by_action
+            .entry(record.action.clone())
+            .or_insert(vec![])
+            .push(record.clone());
+    }
+    info!(
+        "Brier score over {} turns: {:.4}",
+        records.len(),
+        brier_score(&records)
+    );
+    for (action, action_records) in &by_action {
+        info!(
+            "Brier score over {} {} turns: {:.4}",
+            action_records.len(),
+            action,
+            brier_score(action_records)
+        );
+    }
+    report_calibration(&records);
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::config::*;
+    use crate::die::*;
+    use crate::hand::*;
+    use crate::opening::*;
+    use crate::player::*;
+
+    use std::sync::Arc;
+
+    fn record(action: &str, estimated_prob: f64, correct: bool) -> TurnRecord {
+        TurnRecord {
+            game_id: None,
+            round: 1,
+            player_id: 0,
+            human: false,
+            num_players: 2,
+            total_num_items: 10,
+            num_own_items: 5,
+            num_bets: 1,
+            action: action.into(),
+            bet: "'cat'".into(),
+            bet_size: 3,
+            estimated_prob: estimated_prob,
+            correct: correct,
+        }
+    }
+
+    describe "analysis" {
+        it "scores perfect estimates" {
+            assert_eq!(0.0, brier_score(&[record("bet", 1.0, true), record("perudo", 0.0, false)]));
+        }
+
+        it "scores estimates" {
+            assert_eq!(0.25, brier_score(&[record("bet", 0.5, true), record("bet", 0.5, false)]));
+            assert_eq!(1.0, brier_score(&[record("bet", 0.0, true)]));
+        }
+
+        it "records challenges against the table" {
+            let game = PerudoGame {
+                players: vec![
+                    Arc::new(PerudoPlayer {
+                        id: 0,
+                        human: false,
+                        opening: OpeningPolicy::Likeliest,
+                        hand: Hand::<Die> {
+                            items: vec![Die::Two, Die::Two],
+                            revealed: 0,
+                            exchanged: false,
+                        },
+                    }),
+                    Arc::new(PerudoPlayer {
+                        id: 1,
+                        human: false,
+                        opening: OpeningPolicy::Likeliest,
+                        hand: Hand::<Die> {
+                            items: vec![Die::Three],
+                            revealed: 0,
+                            exchanged: false,
+                        },
+                    }),
+                ],
+                current_index: 1,
+                current_outcome: TurnOutcome::Bet(PerudoBet {
+                    value: Die::Two,
+                    quantity: 3,
+                }),
+                history: hashmap!{},
+                community: vec![],
+                constraint: None,
+                config: GameConfig::default(),
+                clocks: hashmap!{},
+                eliminated: vec![],
+                traded: false,
+                round: 1,
+                honesty: hashmap!{},
+            };
+
+            // Three twos can't be found in two other dice, and aren't there.
+            let record = TurnRecord::new(&game, &TurnOutcome::Perudo).unwrap();
+            assert_eq!(1, record.player_id);
+            assert_eq!("perudo", record.action);
+            assert_eq!(1.0, record.estimated_prob);
+            assert!(record.correct);
+        }
+
+        it "writes records as CSV rows" {
+            let record = TurnRecord { game_id: Some(255), ..record("bet", 0.5, true) };
+            assert_eq!("000000ff,1,0,false,2,10,5,1,bet,\"'cat'\",3,0.5,true", record.csv_row());
+            assert_eq!(TURNS_CSV_HEADER.split(',').count(), record.csv_row().split(',').count());
+            assert_eq!("\"a\"\"b\"", csv_quote("a\"b"));
+        }
+
+        it "bins estimates by how often they come true" {
+            let records = vec![
+                record("bet", 0.82, true),
+                record("bet", 0.88, false),
+                record("bet", 1.0, true),
+                record("bet", 0.05, false),
+            ];
+            let bins = calibration(&records);
+            assert_eq!(3, bins.len());
+            assert_eq!(2, bins[1].count);
+            assert!((0.85 - bins[1].mean_estimate).abs() < 1e-9);
+            assert_eq!(0.5, bins[1].frequency);
+            assert_eq!(1.0, bins[2].mean_estimate);
+
+            // Half the estimates are 0.35 out, a quarter 0.05 and a quarter spot on.
+            assert!((0.1875 - calibration_error(&records)).abs() < 1e-9);
+            assert_eq!(0.0, calibration_error(&[record("bet", 1.0, true)]));
+        }
+
+        it "loads records" {
+            let records = vec![record("bet", 0.3, true), record("palafico", 0.1, false)];
+            let mut f = File::create("/tmp/analysis1.jsonl").unwrap();
+            for r in &records {
+                writeln!(f, "{}", serde_json::to_string(r).unwrap()).unwrap();
+            }
+            assert_eq!(records, load_records("/tmp/analysis1.jsonl").unwrap());
+        }
+    }
+}
@@ -0,0 +1,90 @@
+/// Any-raise estimates: the chance that at least one raise the rules allow above a bet is right
+/// on the table, as a player might judge it by dealing the hands they can't see. When none is
+/// likely to be, raising is futile and the spot forces a challenge.
+use crate::belief::*;
+use crate::bet::*;
+use crate::game::*;
+use crate::rng::*;
+use crate::sampler::*;
+
+#[cfg(test)]
+use speculate::speculate;
+
+/// How many deals to estimate the chance of any raise being right from.
+pub const NUM_RAISE_SAMPLES: usize = 100;
+
+/// Below this chance of any raise being right, the AI doesn't weigh raises up but challenges.
+pub const FORCED_CHALLENGE: f64 = 0.01;
+
+/// The seed deals are drawn from at an unseeded table, so that a position is always given the
+/// same estimate.
+const RAISE_SEED: u64 = 0x7a15e;
+
+/// The chance that at least one of the given raises is right, as the player at the current seat
+/// holding the given hand might estimate it from the given number of deals. Each deal is checked
+/// a raise at a time until one is right, so those likeliest to be are best given first.
+pub fn estimate_any_raise<B: Bet>(
+    state: &GameState<B>,
+    hand: &[B::V],
+    belief: Option<&BeliefPolicy>,
+    raises: &[Box<B>],
+    num_samples: usize,
+) -> f64 {
+    if raises.is_empty() || num_samples == 0 {
+        return 0.0;
+    }
+    // Dealt from a generator of their own, so that a seeded game deals as it would without them.
+    with_seed(seed().unwrap_or(RAISE_SEED), || {
+        let num_right = (0..num_samples)
+            .filter(|_| {
+                let mut table = sample_hands(state, state.current_index, hand, belief).concat();
+                table.extend(state.community.iter().cloned());
+                raises.iter().any(|raise| raise.is_correct(&table, false))
+            })
+            .count();
+        num_right as f64 / num_samples as f64
+    })
+}
+
+/// Whether a challenge is forced, given the chance of any raise being right.
+pub fn is_forced(any_raise: f64) -> bool {
+    any_raise < FORCED_CHALLENGE
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::*;
+    use crate::testing::*;
+
+    describe "any-raise estimates" {
+        fn estimate(state: &GameState<PerudoBet>, hand: &[Die], bet: PerudoBet, num_samples: usize) -> f64 {
+            estimate_any_raise(state, hand, None, &bet.all_above(state).collect::<Vec<_>>(), num_samples)
+        }
+
+        it "is certain of a raise the hand already makes" {
+            let state = game_state::<PerudoBet>(vec![2, 2]);
+            let bet = PerudoBet { value: Die::Two, quantity: 1 };
+            let hand = vec![Die::Six, Die::Six];
+            assert_eq!(1.0, estimate(&state, &hand, bet, NUM_RAISE_SAMPLES));
+        }
+
+        it "finds no raise above the highest bet" {
+            let state = game_state::<PerudoBet>(vec![1, 1]);
+            let bet = PerudoBet { value: Die::One, quantity: 2 };
+            let hand = vec![Die::Two];
+            assert!(bet.all_above(&state).next().is_none());
+            assert_eq!(0.0, estimate(&state, &hand, bet, NUM_RAISE_SAMPLES));
+            assert!(is_forced(0.0));
+        }
+
+        it "estimates the chance of some raise coming off" {
+            // Above 2 Sixes from two dice, only Ones are left, so the other die must be a One.
+            let state = game_state::<PerudoBet>(vec![1, 1]);
+            let bet = PerudoBet { value: Die::Six, quantity: 2 };
+            let p = estimate(&state, &[Die::Two], bet.clone(), 1000);
+            assert!((p - 1.0 / 6.0).abs() < 0.05);
+            assert_eq!(p, estimate(&state, &[Die::Two], bet.clone(), 1000));
+            assert_eq!(1.0, estimate(&state, &[Die::One], bet, 1000));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+/// Building the lookup in the background: when the lookup asked for is missing or doesn't cover
+/// the tiles in play, a game can start at once with a stand-in, playing only the commonest words,
+/// while the full lookup is built on a thread of its own, and switch to it between rounds once
+/// it's ready.
+use crate::config::*;
+use crate::dict;
+use crate::dict::*;
+use crate::error::*;
+use crate::lookup::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+lazy_static! {
+    static ref BUILDING: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// The profile the words played with while the full lookup is built are kept by.
+pub const STAND_IN_PROFILE: DictProfile = DictProfile::Kids;
+
+/// How many trials each probability in the stand-in lookup is estimated from: few, so that it
+/// is built in moments.
+pub const STAND_IN_NUM_TRIALS: u32 = 200;
+
+/// How many trials each probability in a lookup built in the background is estimated from.
+pub const BUILD_NUM_TRIALS: u32 = 1000;
+
+/// Why the lookup at the given path can't be played with for the given number of tiles, if it
+/// can't: it can't be opened, doesn't go that far, or was built from other words.
+pub fn stale_reason(lookup_path: &str, num_tiles: usize) -> Option<String> {
+    let lookup = match Lookup::open(lookup_path) {
+        Ok(lookup) => lookup,
+        Err(e) => return Some(e.to_string()),
+    };
+    match lookup.max_num_tiles() {
+        Some(max_num_tiles) if max_num_tiles >= num_tiles => (),
+        max_num_tiles => {
+            return Some(format!(
+                "it covers up to {} tiles, but there are {} in play",
+                max_num_tiles.unwrap_or(0),
+                num_tiles
+            ))
+        }
+    }
+    if lookup.meta().dict_profile != dict_profile() {
+        return Some(format!(
+            "it was built with the {} dictionary profile",
+            lookup.meta().dict_profile
+        ));
+    }
+    None
+}
+
+/// Plays with a stand-in dictionary and lookup of the commonest words from the dictionary at the
+/// given path, built on the spot, while building the lookup for the whole dictionary at the
+/// given path in the background, for games of the given config to switch to from the round after
+/// it's ready. The stand-in needs word frequencies loaded.
+pub fn build_in_background(
+    config: &GameConfig,
+    dict_path: &str,
+    lookup_path: &str,
+    num_tiles: usize,
+) -> Result<(), ScrabrudoError> {
+    let stand_in = Dict::load(dict_path, &STAND_IN_PROFILE)?;
+    let stand_in_path =
+        env::temp_dir().join(format!("scrabrudo_stand_in_{}.sstable", process::id()));
+    let stand_in_path = stand_in_path.to_string_lossy();
+    info!(
+        "Building a stand-in lookup of {} words...",
+        stand_in.words().len()
+    );
+    // The lookup is written over what's there, so clear anything left from before.
+    let _ = fs::remove_file(stand_in_path.as_ref());
+    create_lookup(
+        &stand_in_path,
+        stand_in.words(),
+        num_tiles,
+        STAND_IN_NUM_TRIALS,
+    );
+    dict::init_loaded(stand_in, Lookup::open(&stand_in_path)?);
+
+    let config = config.clone();
+    let dict_path = dict_path.to_string();
+    let lookup_path = lookup_path.to_string();
+    *BUILDING.lock().unwrap() = Some(thread::spawn(move || {
+        match build(&config, &dict_path, &lookup_path, num_tiles) {
+            Ok(()) => info!(
+                "Built lookup {}, switching to it from the next round",
+                lookup_path
+            ),
+            Err(e) => error!("Couldn't build lookup {}: {}", lookup_path, e),
+        }
+    }));
+    Ok(())
+}
+
+/// Waits for the lookup being built in the background, if there is one, so that it isn't lost
+/// when the games are over before it is.
+pub fn finish_building() {
+    if let Some(building) = BUILDING.lock().unwrap().take() {
+        info!("Finishing building the lookup, which can be interrupted to give it up");
+        if building.join().is_err() {
+            error!("Building the lookup panicked");
+        }
+    }
+}
+
+/// Builds the lookup for the dictionary at the given path, writing it elsewhere first so that
+/// it only ever appears whole, and has games of the given config switch to it at the start of
+/// their next round.
+fn build(
+    config: &GameConfig,
+    dict_path: &str,
+    lookup_path: &str,
+    num_tiles: usize,
+) -> Result<(), ScrabrudoError> {
+    let dict = Dict::load(dict_path, &dict_profile())?;
+    let partial_path = format!("{}.partial", lookup_path);
+    let _ = fs::remove_file(&partial_path);
+    info!(
+        "Building lookup {} for {} tiles in the background...",
+        lookup_path, num_tiles
+    );
+    create_lookup(&partial_path, dict.words(), num_tiles, BUILD_NUM_TRIALS);
+    fs::rename(&partial_path, lookup_path)?;
+    dict::request_reload(config, dict_path, lookup_path, num_tiles)
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "building in the background" {
+        it "tells why a lookup can't be played with" {
+            assert!(stale_reason("/tmp/no_such_lookup.sstable", 5).is_some());
+            create_lookup("/tmp/lookup_stale.sstable", &hashset!{ "an".into() }, 5, 10);
+            assert_eq!(None, stale_reason("/tmp/lookup_stale.sstable", 5));
+            assert_eq!(
+                Some("it covers up to 5 tiles, but there are 6 in play".to_string()),
+                stale_reason("/tmp/lookup_stale.sstable", 6));
+        }
+    }
+}
@@ -0,0 +1,282 @@
+/// Belief policies: how far the AI takes its opponents' bets this round at their word.
+use crate::bet::*;
+use crate::config::*;
+use crate::rng::*;
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref DEFAULT_POLICY: Mutex<BeliefPolicy> = Mutex::new(BeliefPolicy::default());
+}
+
+/// How many of each player's bets have been revealed by a challenge in a game, and how many of
+/// those turned out right, as (right, revealed), by player ID.
+pub type Honesty = HashMap<usize, (usize, usize)>;
+
+/// How likely an opponent is to be taken at their word, unless a policy says otherwise.
+pub const DEFAULT_BELIEF_P: f64 = 0.5;
+
+/// A policy for how much of what opponents have bet this round to believe they hold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BeliefPolicy {
+    /// Believe each opponent with the given probability.
+    Fraction(f64),
+
+    /// Believe as by default, but never more than this many items in all.
+    Cap(usize),
+
+    /// Believe each opponent as often as their bets have turned out right when challenged.
+    Honesty,
+}
+
+impl Default for BeliefPolicy {
+    fn default() -> Self {
+        BeliefPolicy::Fraction(DEFAULT_BELIEF_P)
+    }
+}
+
+impl BeliefPolicy {
+    /// Parses a policy from its command-line name, e.g. "half" or "cap:2".
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut split = name.split(':');
+        match (split.next(), split.next().map(|k| k.parse::<usize>())) {
+            (Some("half"), None) => Some(BeliefPolicy::Fraction(DEFAULT_BELIEF_P)),
+            (Some("all"), None) => Some(BeliefPolicy::Fraction(1.0)),
+            (Some("none"), None) => Some(BeliefPolicy::Fraction(0.0)),
+            (Some("cap"), Some(Ok(k))) => Some(BeliefPolicy::Cap(k)),
+            (Some("honesty"), None) => Some(BeliefPolicy::Honesty),
+            _ => None,
+        }
+    }
+
+    /// The probability of taking the given opponent at their word, given how honest everyone
+    /// has been this game.
+    pub fn belief_p(&self, opponent_id: usize, honesty: &Honesty) -> f64 {
+        match self {
+            BeliefPolicy::Fraction(p) => *p,
+            BeliefPolicy::Cap(_) => DEFAULT_BELIEF_P,
+            BeliefPolicy::Honesty => honesty_of(honesty, opponent_id),
+        }
+    }
+
+    /// The most items this policy will believe in all, if limited.
+    pub fn cap(&self) -> Option<usize> {
+        match self {
+            BeliefPolicy::Cap(k) => Some(*k),
+            _ => None,
+        }
+    }
+
+    /// Chooses which of the items each opponent has claimed to believe they hold, taking a random
+    /// share of each opponent's claims in proportion to how far they're believed.
+    pub fn believe<T: Clone>(&self, claims: Vec<(usize, Vec<T>)>, honesty: &Honesty) -> Vec<T> {
+        self.believe_each(claims, honesty)
+            .into_iter()
+            .flat_map(|(_, items)| items)
+            .collect()
+    }
+
+    /// The items claimed by opponents that this policy always takes at their word, and so can be
+    /// counted on without choosing any.
+    pub fn believe_surely<T: Clone>(
+        &self,
+        claims: Vec<(usize, Vec<T>)>,
+        honesty: &Honesty,
+    ) -> Vec<T> {
+        if self.cap().is_some() {
+            return vec![];
+        }
+        claims
+            .into_iter()
+            .filter(|(opponent_id, _)| self.belief_p(*opponent_id, honesty) >= 1.0)
+            .flat_map(|(_, items)| items)
+            .collect()
+    }
+
+    /// Chooses which items to believe as for believe, keeping them with the opponent who claimed
+    /// them.
+    pub fn believe_each<T: Clone>(
+        &self,
+        claims: Vec<(usize, Vec<T>)>,
+        honesty: &Honesty,
+    ) -> Vec<(usize, Vec<T>)> {
+        let mut rng = rng_for("belief");
+        let mut believed = claims
+            .into_iter()
+            .map(|(opponent_id, items)| {
+                let num_to_believe =
+                    (self.belief_p(opponent_id, honesty) * items.len() as f64) as usize;
+                let items = items
+                    .choose_multiple(&mut rng, num_to_believe)
+                    .cloned()
+                    .collect::<Vec<T>>();
+                (opponent_id, items)
+            })
+            .collect::<Vec<(usize, Vec<T>)>>();
+        if let Some(cap) = self.cap() {
+            // Keep a random selection of everything believed, up to the cap.
+            let opponent_ids = believed.iter().map(|(id, _)| *id).collect::<Vec<usize>>();
+            let mut tagged = believed
+                .into_iter()
+                .flat_map(|(id, items)| items.into_iter().map(move |item| (id, item)))
+                .collect::<Vec<(usize, T)>>();
+            tagged.shuffle(&mut rng);
+            tagged.truncate(cap);
+            believed = opponent_ids
+                .into_iter()
+                .map(|id| {
+                    let items = tagged
+                        .iter()
+                        .filter(|(i, _)| *i == id)
+                        .map(|(_, item)| item.clone())
+                        .collect();
+                    (id, items)
+                })
+                .collect();
+        }
+        believed
+    }
+
+    /// The distribution of how many of the given opponents are believed, as the probability of
+    /// each number from none to all of them.
+    pub fn num_believed(&self, opponent_ids: &[usize], honesty: &Honesty) -> Vec<f64> {
+        let mut dist = vec![1.0];
+        for opponent_id in opponent_ids {
+            let p = self.belief_p(*opponent_id, honesty);
+            let mut next = vec![0.0; dist.len() + 1];
+            for (n, mass) in dist.iter().enumerate() {
+                next[n] += mass * (1.0 - p);
+                next[n + 1] += mass * p;
+            }
+            dist = next;
+        }
+        if let Some(cap) = self.cap() {
+            if dist.len() > cap + 1 {
+                let over = dist.split_off(cap + 1).iter().sum::<f64>();
+                dist[cap] += over;
+            }
+        }
+        dist
+    }
+}
+
+/// Sets the belief policy for every player without one of their own in their game's config.
+pub fn init_belief(policy: BeliefPolicy) {
+    *DEFAULT_POLICY.lock().unwrap() = policy;
+}
+
+/// Gets the belief policy the given player plays by in a game of the given config.
+pub fn belief(config: &GameConfig, player_id: usize) -> BeliefPolicy {
+    match config.seat(player_id).and_then(|seat| seat.belief.clone()) {
+        Some(policy) => policy,
+        None => DEFAULT_POLICY.lock().unwrap().clone(),
+    }
+}
+
+/// Parses a policy for one player or for all from its command-line form, e.g. "1:cap:2" or
+/// "honesty".
+pub fn parse_belief(assignment: &str) -> Option<(Option<usize>, BeliefPolicy)> {
+    let mut split = assignment.splitn(2, ':');
+    match (split.next().map(|id| id.parse::<usize>()), split.next()) {
+        (Some(Ok(player_id)), Some(name)) => {
+            Some((Some(player_id), BeliefPolicy::from_name(name)?))
+        }
+        _ => Some((None, BeliefPolicy::from_name(assignment)?)),
+    }
+}
+
+/// Adds to the given record whether each bet made this round was right given everything on the
+/// table, once a challenge has revealed it. The history is keyed by the player who made each bet.
+pub fn record_round<B: Bet>(
+    honesty: &Honesty,
+    history: &HashMap<usize, Vec<B>>,
+    all_items: &Vec<B::V>,
+) -> Honesty {
+    let mut record = honesty.clone();
+    for (player_id, bets) in history {
+        let entry = record.entry(*player_id).or_insert((0, 0));
+        for bet in bets {
+            if bet.is_correct(all_items, false) {
+                entry.0 += 1;
+            }
+            entry.1 += 1;
+        }
+    }
+    record
+}
+
+/// How often the given player's bets have turned out right in the given record, starting from
+/// even odds before any have been seen.
+pub fn honesty_of(honesty: &Honesty, player_id: usize) -> f64 {
+    let (num_right, num_bets) = honesty_record(honesty, player_id);
+    (num_right + 1) as f64 / (num_bets + 2) as f64
+}
+
+/// How many of the given player's bets in the given record have been revealed by a challenge,
+/// and how many of those turned out right, as (right, revealed).
+pub fn honesty_record(honesty: &Honesty, player_id: usize) -> (usize, usize) {
+    honesty.get(&player_id).cloned().unwrap_or((0, 0))
+}
+
+#[cfg(test)]
+speculate! {
+    describe "belief policies" {
+        it "parses policies" {
+            assert_eq!(Some(BeliefPolicy::Fraction(1.0)), BeliefPolicy::from_name("all"));
+            assert_eq!(Some(BeliefPolicy::Cap(2)), BeliefPolicy::from_name("cap:2"));
+            assert_eq!(None, BeliefPolicy::from_name("cap"));
+            assert_eq!(Some((None, BeliefPolicy::Honesty)), parse_belief("honesty"));
+            assert_eq!(Some((Some(1), BeliefPolicy::Cap(3))), parse_belief("1:cap:3"));
+            assert_eq!(None, parse_belief("1:sometimes"));
+        }
+
+        it "caps the items believed" {
+            let claims = vec![(1, vec!['a', 'b', 'c']), (2, vec!['d', 'e'])];
+            let honesty = hashmap!{};
+            assert_eq!(5, BeliefPolicy::Fraction(1.0).believe(claims.clone(), &honesty).len());
+            assert_eq!(0, BeliefPolicy::Fraction(0.0).believe(claims.clone(), &honesty).len());
+            assert_eq!(1, BeliefPolicy::Cap(1).believe(claims.clone(), &honesty).len());
+            let believed = BeliefPolicy::Cap(4).believe_each(claims, &honesty);
+            assert_eq!(vec![1, 2], believed.iter().map(|(id, _)| *id).collect::<Vec<usize>>());
+            assert!(believed[0].1.iter().all(|c| "abc".contains(*c)));
+            assert!(believed[1].1.iter().all(|c| "de".contains(*c)));
+        }
+
+        it "counts only on opponents it always believes" {
+            let claims = vec![(1, vec!['a', 'b']), (2, vec!['c'])];
+            let honesty = hashmap!{};
+            assert_eq!(vec!['a', 'b', 'c'], BeliefPolicy::Fraction(1.0).believe_surely(claims.clone(), &honesty));
+            assert!(BeliefPolicy::Fraction(0.5).believe_surely(claims.clone(), &honesty).is_empty());
+            assert!(BeliefPolicy::Cap(3).believe_surely(claims, &honesty).is_empty());
+        }
+
+        it "caps the opponents believed" {
+            let honesty = hashmap!{};
+            assert_eq!(vec![0.25, 0.5, 0.25], BeliefPolicy::Fraction(0.5).num_believed(&[1, 2], &honesty));
+            assert_eq!(vec![0.25, 0.75], BeliefPolicy::Cap(1).num_believed(&[1, 2], &honesty));
+            assert_eq!(vec![1.0], BeliefPolicy::Cap(0).num_believed(&[], &honesty));
+        }
+
+        it "believes opponents as far as they have been right" {
+            assert_eq!(0.5, honesty_of(&hashmap!{}, 1));
+            let two = crate::die::Die::Two;
+            let four = crate::die::Die::Four;
+            let bet = |q| PerudoBet { value: two.clone(), quantity: q };
+            let honesty = record_round(
+                &hashmap!{},
+                &hashmap!{ 1 => vec![bet(1), bet(3)], 2 => vec![bet(2)] },
+                &vec![two.clone(), two.clone(), four],
+            );
+            assert_eq!(0.5, honesty_of(&honesty, 1));
+            assert_eq!(2.0 / 3.0, honesty_of(&honesty, 2));
+            assert_eq!(2.0 / 3.0, BeliefPolicy::Honesty.belief_p(2, &honesty));
+            assert_eq!((1, 2), honesty_record(&honesty, 1));
+            assert_eq!((2, 3), honesty_record(&record_round(&honesty, &hashmap!{ 1 => vec![bet(2)] }, &vec![two.clone(), two]), 1));
+        }
+    }
+}
@@ -1,52 +1,256 @@
 /// Bet definitions and related logic.
+use crate::belief::*;
+use crate::blitz::*;
+use crate::cache::*;
+use crate::caution::*;
+use crate::config::*;
+use crate::constraint::*;
 use crate::dict;
 use crate::dict::*;
 use crate::die::*;
+use crate::error::*;
 use crate::game::*;
 use crate::hand::*;
+use crate::lang::*;
+use crate::monte_carlo::*;
+use crate::multiset::*;
+use crate::notation::*;
+use crate::opening::*;
 use crate::player::*;
+use crate::prob::*;
+use crate::prob_store::*;
+use crate::schema::*;
+use crate::symbol::*;
 use crate::testing;
+use crate::tie_break::*;
 use crate::tile::*;
 
 use probability::prelude::*;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
 use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
 use speculate::speculate;
 use std::cmp::Ord;
 use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::iter;
 
+/// A lazily generated sequence of bets.
+pub type Bets<B> = Box<dyn Iterator<Item = Box<B>>>;
+
 /// Trait implemented by any type of bet.
-pub trait Bet: Ord + Clone + fmt::Display {
+pub trait Bet:
+    Ord
+    + Clone
+    + Hash
+    + fmt::Debug
+    + fmt::Display
+    + Serialize
+    + DeserializeOwned
+    + Schema
+    + Notation
+    + Send
+    + Sync
+    + 'static
+{
     type V: Holdable;
 
+    /// The name of the game played with these bets, as in the position schema.
+    const VARIANT: &'static str;
+
     /// Return all possible bets given the current game state.
-    fn all(state: &GameState<Self>) -> Vec<Box<Self>>;
+    fn all(state: &GameState<Self>) -> Bets<Self>;
 
-    /// Get all bets above this bet.
-    fn all_above(&self, state: &GameState<Self>) -> Vec<Box<Self>> {
-        // Generate all bets and filter down to only those which are greater than the one given.
+    /// Return a superset of the bets above this bet, pruning early any that obviously cannot be.
+    fn candidates_above(&self, state: &GameState<Self>) -> Bets<Self> {
         Self::all(state)
-            .into_iter()
-            .filter(|b| **b > *self)
-            .collect::<Vec<Box<Self>>>()
+    }
+
+    /// Get all bets above this bet.
+    fn all_above(&self, state: &GameState<Self>) -> Bets<Self> {
+        // Generate candidate bets and filter down to only those which are greater than the one
+        // given, and which the rules allow.
+        let bet = self.clone();
+        let min_raise = state.config.min_raise.clone();
+        let constraint = state.constraint.clone();
+        let forbidden = if state.config.no_repeated_words {
+            state.history.values().flatten().cloned().collect()
+        } else {
+            vec![]
+        };
+        Box::new(self.candidates_above(state).filter(move |b| {
+            **b > bet
+                && b.check_raise(&bet, &min_raise).is_ok()
+                && !forbidden.contains(&**b)
+                && constraint.as_ref().is_none_or(|c| b.meets(c))
+        }))
+    }
+
+    /// All bets the given items alone make right, whatever else is on the table, without
+    /// weighing any up.
+    fn covered_by(state: &GameState<Self>, items: &[Self::V]) -> Bets<Self> {
+        let items = items.to_vec();
+        Box::new(Self::all(state).filter(move |b| b.is_correct(&items, false)))
+    }
+
+    /// The items the given player takes on trust whenever they weigh up a bet, beyond those they
+    /// can see. Perudo bets are weighed on what is seen alone.
+    fn surely_believed(_state: &GameState<Self>, _player_id: usize) -> Vec<Self::V> {
+        vec![]
+    }
+
+    /// The bets that may be made next which the given player is certain of, as what they hold,
+    /// what is face up and what they take on trust already make them right.
+    fn guaranteed_bets(
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> Vec<Box<Self>> {
+        let mut items = player.items().clone();
+        items.extend(state.known_items());
+        items.extend(Self::surely_believed(state, player.id()));
+        Self::covered_by(state, &items)
+            .filter(|b| b.validate(state).is_ok())
+            .collect()
+    }
+
+    /// Checks that this bet raises the given one by at least the minimum, giving the reason if
+    /// not. Only Scrabrudo bets have a distance between them, so by default any raise will do.
+    fn check_raise(
+        &self,
+        _standing_bet: &Self,
+        _min_raise: &MinRaise,
+    ) -> Result<(), ScrabrudoError> {
+        Ok(())
+    }
+
+    /// Checks that this bet may be made next, giving the reason if not.
+    fn validate(&self, state: &GameState<Self>) -> Result<(), ScrabrudoError> {
+        if let Some(standing_bet) = state.standing_bet() {
+            if self <= standing_bet {
+                return Err(ScrabrudoError::InvalidBet(Msg::NotHighEnough.to_string()));
+            }
+            self.check_raise(standing_bet, &state.config.min_raise)?;
+        }
+        if state.config.no_repeated_words && state.has_been_bet(self) {
+            return Err(ScrabrudoError::InvalidBet(Msg::AlreadyBet.to_string()));
+        }
+        if self.is_banned(&state.config.banned_words) {
+            return Err(ScrabrudoError::InvalidBet(
+                Msg::Banned(self.to_string()).to_string(),
+            ));
+        }
+        if let Some(constraint) = &state.constraint {
+            if !self.meets(constraint) {
+                return Err(ScrabrudoError::InvalidBet(
+                    Msg::ThisRound(constraint.to_string()).to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether this bet uses any of the given banned words.
+    /// Only Scrabrudo bets are words, so by default none is.
+    fn is_banned(&self, _banned_words: &BTreeSet<String>) -> bool {
+        false
+    }
+
+    /// Whether this bet satisfies the round's constraint card.
+    /// Cards only constrain Scrabrudo words, so by default every bet does.
+    fn meets(&self, _constraint: &Constraint) -> bool {
+        true
+    }
+
+    /// Whether or not this bet may be made next.
+    fn is_valid(&self, state: &GameState<Self>) -> bool {
+        self.validate(state).is_ok()
+    }
+
+    /// How common this bet is as a word, 0 being the most common.
+    /// None if it isn't a known word.
+    fn word_rank(&self) -> Option<usize> {
+        None
+    }
+
+    /// The items a player making this bet is claiming to hold some of.
+    fn claimed_items(&self) -> Vec<Self::V>;
+
+    /// The given items that do nothing towards this bet, and so are worth exchanging.
+    fn dead_items(&self, items: &[Self::V]) -> Vec<Self::V>;
+
+    /// How much the given item is worth to the hand holding it, for choosing what to trade
+    /// away: by default how many of the hand's items are the same, so that lone items go first.
+    fn trade_value(hand: &[Self::V], item: &Self::V) -> f64 {
+        hand.iter().filter(|i| *i == item).count() as f64
+    }
+
+    /// How big the bet is: the quantity of a Perudo bet, or the number of letters of a Scrabrudo
+    /// one.
+    fn size(&self) -> usize;
+
+    /// What a summary of the bet says beyond the bet itself, if anything.
+    fn detail(&self) -> Option<String> {
+        None
     }
 
     /// Gets the smallest possible bet.
     fn smallest() -> Box<Self>;
 
-    /// Pick the best bet from those available for a first go.
-    /// TODO: Better than random choice from equally likely bets.
+    /// Pick the best bet from those available for a first go, according to the player's opening
+    /// policy.
     fn best_first_bet(
         state: &GameState<Self>,
         player: Box<dyn Player<V = Self::V, B = Self>>,
-    ) -> Box<Self>;
+    ) -> Box<Self> {
+        Self::best_first_bet_within(state, player, &Deadline::none())
+    }
+
+    /// Picks a first bet as for best_first_bet, from those it had time to rank by the deadline.
+    fn best_first_bet_within(
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+        deadline: &Deadline,
+    ) -> Box<Self> {
+        let bets = Self::ranked_first_bets_within(state, player.cloned(), deadline);
+        Self::opening_bet_from(state, bets, player.opening(), player.id())
+    }
+
+    /// Picks a first bet from those given, ranked lowest first, by the given opening policy. Should
+    /// the round's constraint or the deadline have left none ranked, the cheapest bet that may be
+    /// made is picked instead.
+    fn opening_bet_from(
+        state: &GameState<Self>,
+        ranked: Vec<(Box<Self>, f64)>,
+        opening: &OpeningPolicy,
+        player_id: usize,
+    ) -> Box<Self> {
+        opening
+            .choose(
+                ranked,
+                &state.config.style,
+                &tie_break(&state.config, player_id),
+            )
+            .or_else(|| Self::cheapest_bet(state))
+            .unwrap_or_else(Self::smallest)
+    }
+
+    /// The lowest bet that may be made next, if any may.
+    fn cheapest_bet(state: &GameState<Self>) -> Option<Box<Self>> {
+        Self::all(state).filter(|b| b.validate(state).is_ok()).min()
+    }
+
+    /// The bets that may open a round paired with their probabilities, lowest first, of those
+    /// there was time to rank by the deadline.
+    fn ranked_first_bets_within(
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+        deadline: &Deadline,
+    ) -> Vec<(Box<Self>, f64)>;
 
     /// Whether or not this bet is correct given the items on the table.
     /// If 'exact' is true then it makes a Palafico evaluation.
@@ -66,6 +270,16 @@ pub trait Bet: Ord + Clone + fmt::Display {
         }
     }
 
+    /// A cheap bound that bet_prob can never exceed, whatever the player believes, for ruling
+    /// bets out without working out their probability. 1.0 if there is no better bound.
+    fn prob_upper_bound(
+        &self,
+        _state: &GameState<Self>,
+        _player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> f64 {
+        1.0
+    }
+
     /// Get the probability of the bet being correct.
     /// This is akin to the mass of this bet, plus all those with the same value and higher
     /// quantity.
@@ -95,41 +309,56 @@ pub trait Bet: Ord + Clone + fmt::Display {
         player: Box<dyn Player<V = Self::V, B = Self>>,
     ) -> f64;
 
-    /// Gets all bets ordered by probability from the perspective of the given player.
-    fn ordered_bets(
+    /// Gets all bets paired with their probability, ranked by probability from the perspective
+    /// of the given player.
+    fn ranked_bets(
         state: &GameState<Self>,
         player: Box<dyn Player<V = Self::V, B = Self>>,
-    ) -> Vec<Box<Self>> {
+    ) -> Vec<(Box<Self>, f64)> {
+        Self::ranked_bets_within(state, player, &Deadline::none())
+    }
+
+    /// Ranks bets as for ranked_bets, but only those there was time to rank by the deadline.
+    fn ranked_bets_within(
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+        deadline: &Deadline,
+    ) -> Vec<(Box<Self>, f64)> {
         let word_counter = Arc::new(Mutex::new(0));
-        let mut bets = Self::all(state)
-            .into_iter()
-            // TODO: Remove awful hack to get around lack of Ord on f64 and therefore no sort().
-            .map(|b| {
+        let ranking = Ranking::new(
+            Box::new(
+                Self::all(state).filter(|b| state.constraint.as_ref().is_none_or(|c| b.meets(c))),
+            ),
+            |b| {
                 *word_counter.lock().unwrap() += 1;
                 debug! {"{} bets evaluated", word_counter.lock().unwrap()};
-                (
-                    (100000.0 * b.prob(state, ProbVariant::Bet, player.cloned())) as u64,
-                    b,
-                )
-            })
-            .collect::<Vec<(u64, Box<Self>)>>();
-        bets.sort_by(|a, b| a.0.cmp(&b.0));
-        bets.into_iter().map(|x| x.1).collect::<Vec<Box<Self>>>()
+                b.prob(state, ProbVariant::Bet, player.cloned())
+            },
+        );
+        search_until(ranking, deadline)
     }
 
-    /// Return one of the highest probability bets from those given.
-    fn best_bet_from(
+    /// Gets all bets ordered by probability from the perspective of the given player. Should
+    /// any be certain, only those are given, highest last, as nothing else could beat them.
+    fn ordered_bets(
         state: &GameState<Self>,
         player: Box<dyn Player<V = Self::V, B = Self>>,
-        bets: Vec<Box<Self>>,
-    ) -> Box<Self> {
-        let max_prob = bets[bets.len() - 1].prob(state, ProbVariant::Bet, player.cloned());
-        let best_bets = bets
+    ) -> Vec<Box<Self>> {
+        let mut guaranteed = Self::guaranteed_bets(state, player.cloned());
+        if !guaranteed.is_empty() {
+            guaranteed.sort();
+            return guaranteed;
+        }
+        Self::ranked_bets(state, player)
             .into_iter()
-            .filter(|b| b.prob(state, ProbVariant::Bet, player.cloned()) == max_prob)
-            .collect::<Vec<Box<Self>>>();
-        let mut rng = thread_rng();
-        best_bets.choose(&mut rng).unwrap().clone()
+            .map(|x| x.0)
+            .collect::<Vec<Box<Self>>>()
+    }
+
+    /// Return one of the highest probability bets from those given, which must be ranked,
+    /// breaking ties as given, or None if none were given.
+    fn best_bet_from(bets: Vec<(Box<Self>, f64)>, tie_break: &TieBreak) -> Option<Box<Self>> {
+        tie_break.pick_bet(best_outcomes(bets))
     }
 }
 
@@ -141,7 +370,7 @@ pub enum ProbVariant {
     Palafico,
 }
 
-#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub struct PerudoBet {
     pub value: Die,
     pub quantity: usize,
@@ -149,16 +378,45 @@ pub struct PerudoBet {
 
 impl Bet for PerudoBet {
     type V = Die;
+    const VARIANT: &'static str = "perudo";
+
+    fn all(state: &GameState<Self>) -> Bets<Self> {
+        Box::new(
+            iproduct!(Die::all().into_iter(), 1..=state.total_num_items).map(
+                |(value, quantity)| {
+                    Box::new(PerudoBet {
+                        value: value,
+                        quantity: quantity,
+                    })
+                },
+            ),
+        )
+    }
 
-    fn all(state: &GameState<Self>) -> Vec<Box<Self>> {
-        iproduct!(Die::all().into_iter(), 1..=state.total_num_items)
-            .map(|(value, quantity)| {
-                Box::new(PerudoBet {
-                    value: value,
-                    quantity: quantity,
-                })
-            })
-            .collect::<Vec<Box<PerudoBet>>>()
+    fn claimed_items(&self) -> Vec<Self::V> {
+        vec![self.value.clone()]
+    }
+
+    fn size(&self) -> usize {
+        self.quantity
+    }
+
+    fn dead_items(&self, items: &[Self::V]) -> Vec<Self::V> {
+        // Ones are wild, so only dice of neither the bet's value nor one are dead.
+        items
+            .iter()
+            .filter(|d| **d != self.value && **d != Die::One)
+            .cloned()
+            .collect()
+    }
+
+    fn trade_value(hand: &[Self::V], item: &Self::V) -> f64 {
+        // Ones are wild, so count towards every value and are worth the whole hand.
+        let num_ones = hand.iter().filter(|d| **d == Die::One).count();
+        if *item == Die::One {
+            return hand.len() as f64;
+        }
+        (hand.iter().filter(|d| *d == item).count() + num_ones) as f64
     }
 
     fn smallest() -> Box<Self> {
@@ -169,19 +427,33 @@ impl Bet for PerudoBet {
     }
 
     /// TODO: Too much cloning here.
-    fn best_first_bet(
+    fn ranked_first_bets_within(
         state: &GameState<Self>,
         player: Box<dyn Player<V = Self::V, B = Self>>,
-    ) -> Box<Self> {
-        let bets = Self::ordered_bets(state, player.cloned())
-            .into_iter()
-            .filter(|b| b.value != Die::One)
-            .collect::<Vec<Box<Self>>>();
-        Self::best_bet_from(state, player, bets)
+        deadline: &Deadline,
+    ) -> Vec<(Box<Self>, f64)> {
+        // Ones can't open, so leave them out before ranking rather than after, in case the
+        // deadline leaves time for nothing else.
+        let ranking = Ranking::new(
+            Box::new(Self::all(state).filter(|b| b.value != Die::One)),
+            |b| b.prob(state, ProbVariant::Bet, player.cloned()),
+        );
+        search_until(ranking, deadline)
     }
 
     fn is_correct(&self, all_items: &Vec<Self::V>, exact: bool) -> bool {
-        unimplemented!("This is currently handed in game.rs");
+        // Ones are wild, so count towards every value.
+        let counts = Multiset::of(all_items);
+        let actual_quantity = if self.value == Die::One {
+            counts.count(&Die::One)
+        } else {
+            counts.count(&self.value) + counts.count(&Die::One)
+        };
+        if exact {
+            actual_quantity == self.quantity
+        } else {
+            actual_quantity >= self.quantity
+        }
     }
 
     fn palafico_prob(
@@ -189,7 +461,7 @@ impl Bet for PerudoBet {
         state: &GameState<Self>,
         player: Box<dyn Player<V = Self::V, B = Self>>,
     ) -> f64 {
-        let guaranteed_quantity = player.num_logical_items(self.value.clone());
+        let (guaranteed_quantity, num_other_dice) = self.seen(state, player.as_ref());
         if guaranteed_quantity > self.quantity {
             return 0.0;
         }
@@ -199,7 +471,6 @@ impl Bet for PerudoBet {
         } else {
             1.0 / 3.0
         };
-        let num_other_dice = state.total_num_items - player.num_items();
         // This is a single Binomial trial - what's the probability of finding the rest of the dice
         // in the remaining dice.
         // TODO: &This occasionally crashes in the mass() func, possibly due to overflow.
@@ -213,7 +484,73 @@ impl Bet for PerudoBet {
     ) -> f64 {
         // If we have the bet in-hand, then we're good; otherwise we only have to look for the diff
         // in the other probabilities.
-        let guaranteed_quantity = player.num_logical_items(self.value.clone());
+        let (guaranteed_quantity, num_other_dice) = self.seen(state, player.as_ref());
+        self.prob_given(guaranteed_quantity, num_other_dice)
+    }
+
+    /// Weighs the chance that the bet is wrong by how far we believe the opponents who have bet
+    /// on its value this round, as the player's belief policy says. A believed opponent holds at
+    /// least one of the value, so that die is no longer unknown.
+    fn perudo_prob(
+        &self,
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> f64 {
+        let (guaranteed_quantity, num_other_dice) = self.seen(state, player.as_ref());
+        let claimants = state
+            .history
+            .iter()
+            .filter(|(pid, bets)| {
+                **pid != player.id() && bets.iter().any(|b| b.value == self.value)
+            })
+            .map(|(pid, _)| *pid)
+            .take(num_other_dice)
+            .collect::<Vec<usize>>();
+        let bet_p = belief(&state.config, player.id())
+            .num_believed(&claimants, &state.honesty)
+            .into_iter()
+            .enumerate()
+            .map(|(num_believed, p)| {
+                p * self.prob_given(
+                    guaranteed_quantity + num_believed,
+                    num_other_dice - num_believed,
+                )
+            })
+            .sum::<f64>();
+        debug!(
+            "Player {} weighs claims on {:?}s by {:?}, giving P({}) = {}",
+            player.id(),
+            claimants,
+            self.value,
+            self,
+            bet_p
+        );
+        1.0 - bet_p
+    }
+}
+
+impl PerudoBet {
+    /// How many of the bet's value the player can see, in their own hand and among the dice
+    /// others have shown or in the middle, and how many dice are left unseen.
+    fn seen(
+        &self,
+        state: &GameState<Self>,
+        player: &dyn Player<V = Die, B = Self>,
+    ) -> (usize, usize) {
+        let known = state.known_items();
+        let num_known = known
+            .iter()
+            .filter(|d| **d == self.value || (self.value != Die::One && **d == Die::One))
+            .count();
+        (
+            player.num_logical_items(self.value.clone()) + num_known,
+            state.num_unseen_items(player.num_items()),
+        )
+    }
+
+    /// The probability of there being at least the bet's quantity, given how many of its value
+    /// are known to be out and how many dice are still unknown.
+    fn prob_given(&self, guaranteed_quantity: usize, num_other_dice: usize) -> f64 {
         if self.quantity <= guaranteed_quantity {
             return 1.0;
         }
@@ -227,7 +564,6 @@ impl Bet for PerudoBet {
         } else {
             1.0 / 3.0
         };
-        let num_other_dice = state.total_num_items - player.num_items();
         ((self.quantity - guaranteed_quantity)..=num_other_dice)
             .map(|q| Binomial::new(num_other_dice, trial_p).mass(q))
             .sum::<f64>()
@@ -285,78 +621,193 @@ impl PartialOrd for PerudoBet {
 }
 
 /// A single bet consisting of Scrabble tiles.
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrabrudoBet {
-    /// The list of tiles that make up the proposed word.
+    /// The list of tiles that make up the proposed word, or each word of a phrase in turn.
     pub tiles: Vec<Tile>,
+
+    /// Where each word after the first starts among the tiles, if the bet is a phrase.
+    #[serde(default)]
+    pub breaks: Vec<usize>,
 }
 
 impl Bet for ScrabrudoBet {
     type V = Tile;
+    const VARIANT: &'static str = "scrabrudo";
 
-    fn all(state: &GameState<Self>) -> Vec<Box<Self>> {
-        dict::words_with_max_length(state.total_num_items)
-            .into_iter()
-            .map(|w| Box::new(Self::from_word(&w)))
+    fn all(state: &GameState<Self>) -> Bets<Self> {
+        Self::without_banned(
+            Self::with_lengths(&state.config.dict, 1, state.total_num_items),
+            state,
+        )
+    }
+
+    fn covered_by(state: &GameState<Self>, items: &[Self::V]) -> Bets<Self> {
+        // Only words spelled from the tiles themselves can be covered, so rather than check the
+        // whole dictionary, find them from the tiles.
+        let letters = items
+            .iter()
+            .filter(|t| **t != Tile::Blank)
+            .map(|t| t.spelling())
+            .collect::<String>();
+        let num_blanks = items.iter().filter(|t| **t == Tile::Blank).count();
+        let words = state.config.dict.get().formable_words(&letters, num_blanks);
+        // A letter pair's letters may be spelled by other tiles, so check the words' own tiles.
+        let items = items.to_vec();
+        Self::without_banned(
+            Box::new(
+                words
+                    .into_iter()
+                    .map(|w| Box::new(Self::from_word(&w)))
+                    .filter(move |b| b.is_correct(&items, false)),
+            ),
+            state,
+        )
+    }
+
+    fn surely_believed(state: &GameState<Self>, player_id: usize) -> Vec<Self::V> {
+        belief(&state.config, player_id)
+            .believe_surely(Self::opponent_tiles(state, player_id), &state.honesty)
+    }
+
+    fn candidates_above(&self, state: &GameState<Self>) -> Bets<Self> {
+        // Shorter words always lose, so we need only look at words at least this long, or longer
+        // still if the rules demand it.
+        let min_length = match state.config.min_raise {
+            MinRaise::Letters(k) => self.tiles.len() + k,
+            _ => self.tiles.len(),
+        };
+        Self::without_banned(
+            Self::with_lengths(&state.config.dict, min_length, state.total_num_items),
+            state,
+        )
+    }
+
+    fn is_banned(&self, banned_words: &BTreeSet<String>) -> bool {
+        self.words().iter().any(|w| banned_words.contains(w))
+    }
+
+    fn meets(&self, constraint: &Constraint) -> bool {
+        constraint.allows(&self.words())
+    }
+
+    fn check_raise(&self, standing_bet: &Self, min_raise: &MinRaise) -> Result<(), ScrabrudoError> {
+        match min_raise {
+            MinRaise::Letters(k) if self.tiles.len() < standing_bet.tiles.len() + k => {
+                Err(ScrabrudoError::InvalidBet(
+                    Msg::LettersLonger {
+                        letters: *k,
+                        bet: standing_bet.to_string(),
+                    }
+                    .to_string(),
+                ))
+            }
+            MinRaise::Score(k) if self.score() < standing_bet.score() + k => {
+                Err(ScrabrudoError::InvalidBet(
+                    Msg::ScoresMore {
+                        score: *k,
+                        bet: standing_bet.to_string(),
+                    }
+                    .to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn word_rank(&self) -> Option<usize> {
+        dict::word_rank(&self.as_word())
+    }
+
+    fn claimed_items(&self) -> Vec<Self::V> {
+        self.tiles.clone()
+    }
+
+    fn size(&self) -> usize {
+        self.tiles.len()
+    }
+
+    fn detail(&self) -> Option<String> {
+        let detail = Msg::LettersWorth {
+            letters: self.tiles.len(),
+            score: self.score(),
+        };
+        Some(detail.to_string())
+    }
+
+    fn dead_items(&self, items: &[Self::V]) -> Vec<Self::V> {
+        // Each tile of the word is used once, and blanks can stand in for anything.
+        let mut tiles_to_use = Multiset::of(&self.tiles);
+        items
+            .iter()
+            .filter(|tile| !tiles_to_use.remove(tile) && **tile != Tile::Blank)
+            .cloned()
             .collect()
     }
 
+    fn trade_value(_hand: &[Self::V], item: &Self::V) -> f64 {
+        // Blanks make any word, and otherwise the commoner the letter the more words it's in.
+        match item {
+            Tile::Blank => 1.0,
+            tile => tile.prob(),
+        }
+    }
+
     fn smallest() -> Box<Self> {
-        Box::new(Self { tiles: vec![] })
+        Box::new(Self {
+            tiles: vec![],
+            breaks: vec![],
+        })
     }
 
-    fn best_first_bet(
+    fn ranked_first_bets_within(
         state: &GameState<Self>,
         player: Box<dyn Player<V = Self::V, B = Self>>,
-    ) -> Box<Self> {
-        // TODO: If we make a distinction for the first bet here then we should incorporate it
-        // here.
-        let bets = Self::ordered_bets(state, player.cloned());
-        Self::best_bet_from(state, player, bets)
+        deadline: &Deadline,
+    ) -> Vec<(Box<Self>, f64)> {
+        Self::ranked_bets_within(state, player, deadline)
     }
 
     fn is_correct(&self, all_items: &Vec<Self::V>, exact: bool) -> bool {
         // We cannot check against the fucking dictionary here!
         // If we do we omit all the substrinngs that aren't in the dict.
-        // This simply says: can you make this bet with the tiles.
-
-        // We need to extract the blanks here and kind of "cout them down" as we find the bet is
-        // missing letters. If we run out of blanks, we lose.
-        let tile_counts = count_map(&self.tiles);
-        let all_tile_counts = count_map(&all_items);
-        let num_blanks = all_items.iter().filter(|t| *t == &Tile::Blank).count();
+        // This simply says: can you make this bet with the tiles, with blanks counted down as
+        // they stand in for missing letters.
+        is_formable(
+            &letter_counts(&self.tiles),
+            &letter_counts(all_items),
+            exact,
+        )
+    }
 
-        if exact {
-            // Palafico pathway
-            let mut num_chars_missing = 0;
-            let mut any_over = false;
-            for (tile, count) in &tile_counts {
-                let actual_count = match all_tile_counts.get(tile) {
-                    Some(c) => *c,
-                    None => 0,
-                };
-                if actual_count > *count {
-                    any_over = true;
-                    break;
-                } else if actual_count < *count {
-                    num_chars_missing += *count - actual_count;
+    /// The most any belief policy could take on trust is every tile each opponent has bet, so
+    /// the bet needs at least the tiles left over once those and the tiles seen are removed, and
+    /// is no likelier than the likeliest letters of that length in the lookup.
+    fn prob_upper_bound(
+        &self,
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> f64 {
+        let mut found = Multiset::of(player.items());
+        for tile in state.known_items() {
+            found.add(tile);
+        }
+        for (player_id, bets) in &state.history {
+            if *player_id != player.id() {
+                let claimed = bets.iter().flat_map(|b| b.tiles.iter().cloned());
+                for tile in claimed.collect::<Multiset<Tile>>().distinct() {
+                    found.add(tile);
                 }
             }
-
-            !any_over && (num_chars_missing <= num_blanks)
+        }
+        let tiles_to_find = Multiset::of(&self.tiles).difference(&found);
+        let num_tiles = state.num_unseen_items(player.num_items());
+        if tiles_to_find.is_empty() {
+            1.0
+        } else if tiles_to_find.len() > num_tiles {
+            0.0
         } else {
-            // Perudo pathway
-            let mut num_chars_missing = 0;
-            for (tile, count) in &tile_counts {
-                let actual_count = match all_tile_counts.get(tile) {
-                    Some(c) => *c,
-                    None => 0,
-                };
-                if actual_count < *count {
-                    num_chars_missing += *count - actual_count;
-                }
-            }
-            num_chars_missing <= num_blanks
+            state.config.lookup.get().bound(tiles_to_find.len(), num_tiles)
         }
     }
 
@@ -365,6 +816,85 @@ impl Bet for ScrabrudoBet {
         state: &GameState<Self>,
         player: Box<dyn Player<V = Self::V, B = Self>>,
     ) -> f64 {
+        self.sampled_bet_prob(state, player).0
+    }
+
+    /// A cautious player challenges on the pessimistic end of the chance of the bet being wrong,
+    /// where that chance was sampled.
+    fn perudo_prob(
+        &self,
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> f64 {
+        let z = caution(&state.config, player.id());
+        let (p, num_trials) = self.sampled_bet_prob(state, player);
+        lower_bound(1.0 - p, num_trials, z)
+    }
+
+    fn palafico_prob(
+        &self,
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> f64 {
+        let z = caution(&state.config, player.id());
+
+        // Our own tiles and those we can see elsewhere count towards the exact total, so if we
+        // can see more of a letter than the word has, it can't be exact.
+        let mut seen_tiles = player.items().clone();
+        seen_tiles.extend(state.known_items());
+        let word_counts = Multiset::of(&self.tiles);
+        let seen_counts = Multiset::of(&seen_tiles);
+        let mut tiles_covered = vec![];
+        for (tile, count) in word_counts.iter() {
+            let held = seen_counts.count(tile);
+            if held > count {
+                return 0.0;
+            } else if held == count {
+                tiles_covered.push(tile.clone());
+            }
+        }
+        let tiles_to_find = word_counts.difference(&seen_counts);
+
+        let num_tiles = state.num_unseen_items(player.num_items());
+        if tiles_to_find.len() > num_tiles {
+            return 0.0;
+        }
+
+        // Letters we already hold all of mustn't turn up anywhere else. This is treated as
+        // independent of finding the rest, which is close enough for a handful of tiles.
+        let p_none_covered: f64 = tiles_covered
+            .iter()
+            .map(|t| (1.0 - t.prob()).powi(num_tiles as i32))
+            .product();
+        if tiles_to_find.is_empty() {
+            return p_none_covered;
+        }
+
+        // Stores without exact probabilities leave us unable to judge, so we never call it.
+        let store = prob_store(&state.config.lookup);
+        if !store.has_exact() {
+            return 0.0;
+        }
+        let substring = tile_key(&tiles_to_find.to_vec());
+        // A cautious player takes the pessimistic end of the sampled chance.
+        let (p, num_trials) = store.get(&substring, num_tiles, true).unwrap_or_else(|| {
+            (
+                cached_probs(&substring, num_tiles).1,
+                Some(CACHE_NUM_TRIALS),
+            )
+        });
+        lower_bound(p, num_trials, z) * p_none_covered
+    }
+}
+
+impl ScrabrudoBet {
+    /// The chance of the bet being right, as for bet_prob, with the number of Monte Carlo trials
+    /// it was estimated from if it was sampled rather than certain.
+    fn sampled_bet_prob(
+        &self,
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Tile, B = Self>>,
+    ) -> (f64, Option<u32>) {
         // Rough algorithm for calculating probability of bet correctness:
         // for e.g. target = [A, T, T, A, C, K], n = 20, hand = [X, X, A, K]
         // Take the difference of the target and the hand. This leaves the letters we seek from the
@@ -395,144 +925,149 @@ impl Bet for ScrabrudoBet {
         // Could look at Monte Carlo precomputation...
 
         // First get the set of tiles we need to find.
-        let mut tiles_to_find = self.tiles.clone();
-        debug!("Player is trying to find {:?}", tiles_to_find);
-        for tile in player.items() {
-            match tiles_to_find.iter().position(|x| x == tile) {
-                Some(i) => {
-                    debug!("Removing {:?} from {:?}", tile, tiles_to_find);
-                    tiles_to_find.remove(i);
-                }
-                None => {
-                    debug!("Could not find {:?} in {:?}", tile, tiles_to_find);
-                }
-            };
-        }
-        debug!("After removing tiles in our hand, we need to find {:?}", tiles_to_find);
+        // Tiles others have shown, and those in the middle, count as ours, as we know they're
+        // there.
+        debug!("Player is trying to find {:?}", self.tiles);
+        let mut seen_tiles = player.items().clone();
+        seen_tiles.extend(state.known_items());
+        let tiles_to_find = Multiset::of(&self.tiles).difference(&Multiset::of(&seen_tiles));
+        debug!("After removing tiles in our hand, we need to find {:?}", tiles_to_find.to_vec());
 
         // Get the number of tiles we have to search in.
-        let num_tiles = state.total_num_items - player.num_items();
+        let num_tiles = state.num_unseen_items(player.num_items());
 
-        // Get the set of all tiles bet by other players.
+        // Get the set of all tiles bet by each other player.
         // TODO: We don't accept duplicates from any player here - should we? This will make us
         // more pessimistic.
         // We believe a different set of tiles for every single word - this could definitely be
         // split out.
-        let all_opponent_tiles = state.history.clone()
-            .into_iter()
-            .filter(|(pid, _)| pid != &player.id())
-            .map(|(_, bets)| bets.into_iter().map(|b| b.tiles).flatten().collect::<HashSet<Tile>>())
-            .flatten()
-            .collect::<Vec<Tile>>();
-
-        let belief_p = 0.5;  // TODO: This value should be tweaked.
-
-        let num_to_believe = (belief_p * all_opponent_tiles.len() as f64) as usize;
-        let mut rng = &mut rand::thread_rng();
-        let belief_tiles: Vec<&Tile> = all_opponent_tiles.choose_multiple(&mut rng, num_to_believe).collect();
+        let belief_tiles = belief(&state.config, player.id())
+            .believe(Self::opponent_tiles(state, player.id()), &state.honesty);
 
         debug!("Player {} holds {:?} and believes {:?}", player.id(), player.hand(), &belief_tiles);
 
         // Remove all the belief tiles from that which we have to find.
-        for tile in belief_tiles {
-            match tiles_to_find.iter().position(|x| x == tile) {
-                Some(i) => {
-                    debug!("Removing {:?} from {:?}", tile, tiles_to_find);
-                    tiles_to_find.remove(i);
-                }
-                None => {
-                    debug!("Could not find {:?} in {:?}", tile, tiles_to_find);
-                }
-            };
-        }
+        let tiles_to_find = tiles_to_find.difference(&Multiset::of(&belief_tiles));
 
-        debug!("Player {} needs to find {:?} to make word {}", player.id(), &tiles_to_find, self.as_word());
+        debug!("Player {} needs to find {:?} to make word {}", player.id(), tiles_to_find.to_vec(), self.as_word());
 
         // If we have all the tiles, it's a guaranteed hit.
         if tiles_to_find.is_empty() {
-            return 1.0;
+            return (1.0, None);
         }
 
         // If we need to find more tiles than there are on the table, it's a guaranteed flop.
         if tiles_to_find.len() > num_tiles {
-            return 0.0
+            return (0.0, None)
         }
 
-        // Sort the tiles to find and turn into a word to match the lookup.
-        tiles_to_find.sort_by(|a, b| a.char().cmp(&b.char()));
-        let substring = tiles_to_find
-            .into_iter()
-            .map(|t| t.char())
-            .collect::<String>();
-        if !dict::lookup_has(&substring) {
-            debug!("Couldn't find {} in the lookup", substring);
-            0.0 // If we somehow didn't compute this length yet then 0.0
-                // We can prob remove the above
-        } else {
-            debug!("Looking up {} in the lookup", substring);
-            match dict::lookup_probs(&substring) {
-                Some(ps) => {
-                    debug!("P({}) = {:?}", substring, ps);
-                    ps[num_tiles]
-                }
-                None => panic!("Couldn't find '{}' in lookup", substring),
+        // The tiles to find are sorted as the lookup is keyed.
+        let substring = tile_key(&tiles_to_find.to_vec());
+        match prob_store(&state.config.lookup).get(&substring, num_tiles, false) {
+            Some(found) => {
+                debug!("P({}) = {:?}", substring, found);
+                found
+            }
+            None => {
+                // The store doesn't go this far, so estimate it instead.
+                debug!("Couldn't find {} in {} tiles in the store", substring, num_tiles);
+                (cached_probs(&substring, num_tiles).0, Some(CACHE_NUM_TRIALS))
             }
         }
     }
 
-    fn palafico_prob(
-        &self,
-        _state: &GameState<Self>,
-        _player: Box<dyn Player<V = Self::V, B = Self>>,
-    ) -> f64 {
-        // TODO: Not quite ready for full Palafico yet, we have the exact-find prob but we also
-        // need the logic to make sure our own hand doesn't spill over.
-        0.0
+    /// The distinct tiles each of the given player's opponents has bet on this round.
+    fn opponent_tiles(state: &GameState<Self>, player_id: usize) -> Vec<(usize, Vec<Tile>)> {
+        state
+            .history
+            .iter()
+            .filter(|(pid, _)| **pid != player_id)
+            .map(|(pid, bets)| {
+                let tiles = bets
+                    .iter()
+                    .flat_map(|b| b.tiles.iter().cloned())
+                    .collect::<Multiset<Tile>>();
+                (*pid, tiles.distinct())
+            })
+            .collect()
     }
-}
 
-/// Gets a map of tiles to their counts.
-pub fn count_map(tiles: &Vec<Tile>) -> HashMap<&Tile, usize> {
-    let mut count_map = HashMap::new();
-    for tile in tiles {
-        let count = count_map.entry(tile).or_insert(0 as usize);
-        *count += 1;
+    /// Lazily generates bets for all the dictionary's words with lengths in the given range.
+    pub fn with_lengths(dict: &DictHandle, min_length: usize, max_length: usize) -> Bets<Self> {
+        let words = dict.get().words_by_length();
+        if tile_set().pairs().is_empty() {
+            let range = dict::length_range(&words, min_length, max_length);
+            return Box::new(range.map(move |i| Box::new(Self::from_word(&words[i]))));
+        }
+        // A letter pair spells two letters on one tile, so a word may have up to twice as many
+        // letters as tiles, and only its tiles say how long a bet it makes.
+        let range = dict::length_range(&words, min_length, max_length.saturating_mul(2));
+        Box::new(
+            range
+                .map(move |i| Box::new(Self::from_word(&words[i])))
+                .filter(move |b| (min_length..=max_length).contains(&b.tiles.len())),
+        )
     }
-    count_map
-}
 
-/// Runs MC simulation to get rough probability of success.
-/// TODO: Move to a monte_carlo module.
-pub fn monte_carlo(n: u32, word: &String, num_trials: u32) -> f64 {
-    if n == 0 {
-        // Cannot find a word in no tiles.
-        return 0.0;
+    /// Parses a bet from a word, or from a phrase of words joined by '+' such as "cat+dog",
+    /// in any case and with any accents, failing if any character isn't a tile once normalized
+    /// or a phrase has an empty word.
+    pub fn parse(word: &str) -> Result<Self, ScrabrudoError> {
+        let word = &normalize_word(word);
+        let mut tiles = vec![];
+        let mut breaks = vec![];
+        for (i, part) in word.split('+').enumerate() {
+            if part.is_empty() && word.contains('+') {
+                return Err(ScrabrudoError::InvalidBet(
+                    Msg::EmptyWord(word.to_string()).to_string(),
+                ));
+            }
+            if i > 0 {
+                breaks.push(tiles.len());
+            }
+            tiles.extend(spell_tiles(part)?);
+        }
+        Ok(Self {
+            tiles: tiles,
+            breaks: breaks,
+        })
     }
 
-    let bet = ScrabrudoBet::from_word(word);
+    /// The given bets, leaving out any the table has banned.
+    fn without_banned(bets: Bets<Self>, state: &GameState<Self>) -> Bets<Self> {
+        if state.config.banned_words.is_empty() {
+            return bets;
+        }
+        let banned_words = state.config.banned_words.clone();
+        Box::new(bets.filter(move |b| !b.is_banned(&banned_words)))
+    }
 
-    let mut success = 0;
-    for i in 0..num_trials {
-        let all_tiles = Hand::<Tile>::new(n).items;
-        if bet.is_correct(&all_tiles, false) {
-            success += 1;
+    /// Builds a bet from a word known to be made of tiles, such as one from the dictionary.
+    pub fn from_word(word: &String) -> Self {
+        match Self::parse(word) {
+            Ok(bet) => bet,
+            Err(e) => panic!("Couldn't make a bet from '{}': {}", word, e),
         }
     }
 
-    success as f64 / num_trials as f64
-}
+    /// The words making up the bet, only the one unless it is a phrase.
+    pub fn words(&self) -> Vec<String> {
+        let starts = iter::once(0).chain(self.breaks.iter().cloned());
+        let ends = self.breaks.iter().cloned().chain(iter::once(self.tiles.len()));
+        starts
+            .zip(ends)
+            .map(|(start, end)| self.tiles[start..end].iter().map(|t| t.spelling()).collect())
+            .collect()
+    }
 
-impl ScrabrudoBet {
-    pub fn from_word(word: &String) -> Self {
-        let tiles = word
-            .chars()
-            .map(|c| Tile::from_char(c))
-            .collect::<Vec<Tile>>();
-        Self { tiles }
+    /// Whether the bet is a phrase of several words.
+    pub fn is_phrase(&self) -> bool {
+        !self.breaks.is_empty()
     }
 
+    /// The bet as it would be typed, with the words of a phrase joined by '+'.
     pub fn as_word(&self) -> String {
-        self.tiles.iter().map(|t| t.char()).collect()
+        self.words().join("+")
     }
 
     pub fn score(&self) -> u32 {
@@ -577,7 +1112,201 @@ impl PartialEq for ScrabrudoBet {
 
 impl Eq for ScrabrudoBet {}
 
+impl Hash for ScrabrudoBet {
+    // Anagrams are equal, so must hash the same.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut tiles = self.tiles.clone();
+        tiles.sort_by_key(|t| t.char());
+        tiles.hash(state);
+    }
+}
+
+/// A single bet of an arithmetic identity made of equation symbols, e.g. 2+3=5.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EquationBet {
+    /// The symbols making up the identity, in order.
+    pub symbols: Vec<Symbol>,
+}
+
+impl Bet for EquationBet {
+    type V = Symbol;
+    const VARIANT: &'static str = "equation";
+
+    fn all(_state: &GameState<Self>) -> Bets<Self> {
+        Box::new(
+            identities()
+                .into_iter()
+                .map(|symbols| Box::new(Self { symbols: symbols })),
+        )
+    }
+
+    fn surely_believed(state: &GameState<Self>, player_id: usize) -> Vec<Self::V> {
+        belief(&state.config, player_id)
+            .believe_surely(Self::opponent_symbols(state, player_id), &state.honesty)
+    }
+
+    fn claimed_items(&self) -> Vec<Self::V> {
+        self.symbols.clone()
+    }
+
+    fn size(&self) -> usize {
+        self.symbols.len()
+    }
+
+    fn dead_items(&self, items: &[Self::V]) -> Vec<Self::V> {
+        // Each symbol of the identity is used once.
+        let mut symbols_to_use = Multiset::of(&self.symbols);
+        items
+            .iter()
+            .filter(|symbol| !symbols_to_use.remove(symbol))
+            .cloned()
+            .collect()
+    }
+
+    fn smallest() -> Box<Self> {
+        Box::new(Self { symbols: vec![] })
+    }
+
+    fn ranked_first_bets_within(
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+        deadline: &Deadline,
+    ) -> Vec<(Box<Self>, f64)> {
+        Self::ranked_bets_within(state, player, deadline)
+    }
+
+    fn is_correct(&self, all_items: &Vec<Self::V>, exact: bool) -> bool {
+        // Only the symbols matter here; that they make a true identity was checked on betting.
+        let found = Multiset::of(all_items);
+        let needed = Multiset::of(&self.symbols);
+        if exact {
+            needed
+                .iter()
+                .all(|(symbol, count)| found.count(symbol) == count)
+        } else {
+            needed.is_subset(&found)
+        }
+    }
+
+    /// With few enough kinds of symbol, the chance of finding those still needed among the
+    /// symbols unseen is worked out exactly, having taken on trust what the player believes.
+    fn bet_prob(
+        &self,
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> f64 {
+        let mut seen_symbols = player.items().clone();
+        seen_symbols.extend(state.known_items());
+        seen_symbols.extend(
+            belief(&state.config, player.id())
+                .believe(Self::opponent_symbols(state, player.id()), &state.honesty),
+        );
+        let symbols_to_find = Multiset::of(&self.symbols).difference(&Multiset::of(&seen_symbols));
+        let num_symbols = state.num_unseen_items(player.num_items());
+        if symbols_to_find.is_empty() {
+            return 1.0;
+        }
+        if symbols_to_find.len() > num_symbols {
+            return 0.0;
+        }
+        let sought = symbols_to_find
+            .iter()
+            .map(|(symbol, count)| (symbol.clone(), count))
+            .collect::<Vec<(Symbol, usize)>>();
+        symbol_prob(&sought, num_symbols, false)
+    }
+
+    fn palafico_prob(
+        &self,
+        state: &GameState<Self>,
+        player: Box<dyn Player<V = Self::V, B = Self>>,
+    ) -> f64 {
+        // Seeing more of a symbol than the identity has rules it out; otherwise exactly the rest
+        // of each of its symbols must be among those unseen, even if that is none.
+        let mut seen_symbols = player.items().clone();
+        seen_symbols.extend(state.known_items());
+        let seen_counts = Multiset::of(&seen_symbols);
+        let mut sought = vec![];
+        for (symbol, count) in Multiset::of(&self.symbols).iter() {
+            let seen = seen_counts.count(symbol);
+            if seen > count {
+                return 0.0;
+            }
+            sought.push((symbol.clone(), count - seen));
+        }
+        let num_symbols = state.num_unseen_items(player.num_items());
+        if sought.iter().map(|(_, count)| count).sum::<usize>() > num_symbols {
+            return 0.0;
+        }
+        symbol_prob(&sought, num_symbols, true)
+    }
+}
+
+impl EquationBet {
+    /// The distinct symbols each of the given player's opponents has bet on this round.
+    fn opponent_symbols(state: &GameState<Self>, player_id: usize) -> Vec<(usize, Vec<Symbol>)> {
+        state
+            .history
+            .iter()
+            .filter(|(pid, _)| **pid != player_id)
+            .map(|(pid, bets)| {
+                let symbols = bets
+                    .iter()
+                    .flat_map(|b| b.symbols.iter().cloned())
+                    .collect::<Multiset<Symbol>>();
+                (*pid, symbols.distinct())
+            })
+            .collect()
+    }
+
+    /// Parses a bet from an identity such as "2+3=5", ignoring spaces, failing if any character
+    /// isn't a symbol or the identity isn't true.
+    pub fn parse(identity: &str) -> Result<Self, ScrabrudoError> {
+        let symbols = identity
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(Symbol::from_char)
+            .collect::<Result<Vec<Symbol>, ScrabrudoError>>()?;
+        if !is_identity(&symbols) {
+            return Err(ScrabrudoError::InvalidBet(
+                Msg::NotIdentity(identity.to_string()).to_string(),
+            ));
+        }
+        Ok(Self { symbols: symbols })
+    }
+
+    /// The identity as it would be typed.
+    pub fn as_identity(&self) -> String {
+        self.symbols.iter().map(|s| s.char()).collect()
+    }
+}
+
+impl fmt::Display for EquationBet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_identity())
+    }
+}
+
+impl Ord for EquationBet {
+    fn cmp(&self, other: &EquationBet) -> Ordering {
+        // Longer identities win, then as for words.
+        self.symbols
+            .len()
+            .cmp(&other.symbols.len())
+            .then_with(|| self.as_identity().cmp(&other.as_identity()))
+    }
+}
+
+impl PartialOrd for EquationBet {
+    fn partial_cmp(&self, other: &EquationBet) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
 speculate! {
+    use std::collections::HashSet;
+
     before {
         testing::set_up();
     }
@@ -586,17 +1315,38 @@ speculate! {
         it "converts bet to word and back" {
             let bet = ScrabrudoBet{
                 tiles: vec![Tile::C, Tile::A, Tile::T],
+                breaks: vec![],
             };
             assert_eq!("cat", bet.as_word());
             assert_eq!(ScrabrudoBet::from_word(&"cat".into()), bet);
         }
 
+        it "parses phrases" {
+            let phrase = ScrabrudoBet::parse("cat+dog").unwrap();
+            assert!(phrase.is_phrase());
+            assert_eq!(vec!["cat".to_string(), "dog".to_string()], phrase.words());
+            assert_eq!("cat+dog", phrase.as_word());
+            assert_eq!(6, phrase.tiles.len());
+            assert!(!ScrabrudoBet::from_word(&"cat".into()).is_phrase());
+            assert!(ScrabrudoBet::parse("cat+").is_err());
+            assert!(ScrabrudoBet::parse("+").is_err());
+
+            // A phrase is ordered by its length in all, and is right if all its tiles are there.
+            assert!(phrase > ScrabrudoBet::from_word(&"hello".into()));
+            assert!(phrase < ScrabrudoBet::from_word(&"kitchen".into()));
+            let table = ScrabrudoBet::from_word(&"catdogt".into()).tiles;
+            assert!(phrase.is_correct(&table, false));
+            assert!(!phrase.is_correct(&table, true));
+        }
+
+        it "parses words in any case or with accents" {
+            assert_eq!(ScrabrudoBet::from_word(&"cat".into()), ScrabrudoBet::from_word(&"CAT".into()));
+            assert_eq!("cafe", ScrabrudoBet::parse("café").unwrap().as_word());
+            assert_eq!("cat+dog", ScrabrudoBet::parse("Cat+DOG").unwrap().as_word());
+        }
+
         it "can load all bets for a certain number of tiles" {
-            let bets = ScrabrudoBet::all(&GameState::<ScrabrudoBet>{
-                total_num_items: 4,
-                num_items_per_player: vec![4],
-                history: hashmap!{},
-            });
+            let bets = ScrabrudoBet::all(&testing::game_state::<ScrabrudoBet>(vec![4])).collect::<Vec<Box<ScrabrudoBet>>>();
             assert_eq!(2193, bets.len());
             for bet in bets {
                 assert!(bet.tiles.len() <= 4);
@@ -616,6 +1366,113 @@ speculate! {
         }
         */
 
+        it "only generates longer bets above a bet" {
+            let state = &testing::game_state::<ScrabrudoBet>(vec![4]);
+            let bet = ScrabrudoBet::from_word(&"cat".into());
+            let candidates = bet.candidates_above(state).collect::<Vec<Box<ScrabrudoBet>>>();
+            assert!(!candidates.is_empty());
+            for candidate in &candidates {
+                assert!(candidate.tiles.len() >= 3 && candidate.tiles.len() <= 4);
+            }
+            assert_eq!(
+                candidates.into_iter().filter(|b| **b > bet).count(),
+                bet.all_above(state).count());
+        }
+
+        it "forbids repeated words when configured" {
+            let bet = ScrabrudoBet::from_word(&"act".into());
+            let anagram = ScrabrudoBet::from_word(&"cat".into());
+            let mut state = GameState::<ScrabrudoBet> {
+                current_index: 1,
+                history: hashmap!{ 0 => vec![bet.clone()] },
+                ..testing::game_state(vec![2, 2])
+            };
+            assert!(anagram.is_valid(&state));
+            assert!(!bet.is_valid(&state));
+            assert!(bet.all_above(&state).any(|b| b.as_word() == "cat"));
+
+            state.config.no_repeated_words = true;
+            assert!(!anagram.is_valid(&state));
+            assert!(ScrabrudoBet::from_word(&"cats".into()).is_valid(&state));
+            assert!(!bet.all_above(&state).any(|b| *b == anagram));
+        }
+
+        it "requires raises of a minimum distance when configured" {
+            let bet = ScrabrudoBet::from_word(&"at".into());
+            let mut state = GameState::<ScrabrudoBet> {
+                current_index: 1,
+                history: hashmap!{ 0 => vec![bet.clone()] },
+                ..testing::game_state(vec![3, 2])
+            };
+            state.config.min_raise = MinRaise::Letters(2);
+            assert!(!ScrabrudoBet::from_word(&"cat".into()).is_valid(&state));
+            assert!(ScrabrudoBet::from_word(&"cart".into()).is_valid(&state));
+            assert!(bet.all_above(&state).all(|b| b.tiles.len() >= 4));
+
+            // 'at' scores 2, 'cat' 5 and 'tax' 10.
+            state.config.min_raise = MinRaise::Score(5);
+            assert!(!ScrabrudoBet::from_word(&"cat".into()).is_valid(&state));
+            assert!(ScrabrudoBet::from_word(&"tax".into()).is_valid(&state));
+            assert!(bet.all_above(&state).all(|b| b.score() >= 7));
+        }
+
+        it "follows the round's constraint card" {
+            let bet = ScrabrudoBet::from_word(&"at".into());
+            let mut state = GameState::<ScrabrudoBet> {
+                current_index: 1,
+                history: hashmap!{ 0 => vec![bet.clone()] },
+                constraint: Some(crate::constraint::Constraint::Without(Tile::S)),
+                ..testing::game_state(vec![3, 2])
+            };
+            assert!(!ScrabrudoBet::from_word(&"cats".into()).is_valid(&state));
+            assert!(ScrabrudoBet::from_word(&"cat".into()).is_valid(&state));
+            assert!(bet.all_above(&state).all(|b| !b.tiles.contains(&Tile::S)));
+
+            state.constraint = Some(crate::constraint::Constraint::VowelPair);
+            assert!(!ScrabrudoBet::from_word(&"cat".into()).is_valid(&state));
+            assert!(ScrabrudoBet::from_word(&"boat".into()).is_valid(&state));
+        }
+
+        it "opens with the cheapest allowed bet when none could be ranked" {
+            let state = GameState::<ScrabrudoBet> {
+                constraint: Some(crate::constraint::Constraint::Containing(Tile::R)),
+                ..crate::testing::game_state(vec![3, 2])
+            };
+            let bet = ScrabrudoBet::opening_bet_from(&state, vec![], &OpeningPolicy::Likeliest, 0);
+            assert!(bet.is_valid(&state));
+            assert!(bet.tiles.contains(&Tile::R));
+            assert_eq!(ScrabrudoBet::cheapest_bet(&state), Some(bet));
+        }
+
+        it "leaves out banned words" {
+            let bet = ScrabrudoBet::from_word(&"at".into());
+            let mut state = GameState::<ScrabrudoBet> {
+                current_index: 1,
+                history: hashmap!{ 0 => vec![bet.clone()] },
+                ..testing::game_state(vec![3, 2])
+            };
+            state.config.banned_words = btreeset!{ "cat".into() };
+            state.config.phrases = true;
+            assert!(!ScrabrudoBet::from_word(&"cat".into()).is_valid(&state));
+            assert!(!ScrabrudoBet::parse("cat+dog").unwrap().is_valid(&state));
+            assert!(ScrabrudoBet::from_word(&"cart".into()).is_valid(&state));
+            assert!(bet.all_above(&state).all(|b| b.as_word() != "cat"));
+            assert!(ScrabrudoBet::all(&state).all(|b| b.as_word() != "cat"));
+        }
+
+        it "bets from the game's own dictionary" {
+            std::fs::write("/tmp/own_dict.txt", "cat\ndog\n").unwrap();
+            let mut state = testing::game_state::<ScrabrudoBet>(vec![3, 2]);
+            let other_state = state.clone();
+            state.config.dict = DictHandle::new(
+                Dict::load("/tmp/own_dict.txt", &DictProfile::Full).unwrap());
+            assert_ne!(state.config, other_state.config);
+            assert_eq!(
+                hashset!{ "cat".to_string(), "dog".to_string() },
+                ScrabrudoBet::all(&state).map(|b| b.as_word()).collect::<HashSet<String>>());
+            assert!(ScrabrudoBet::all(&other_state).any(|b| b.as_word() == "the"));
+        }
+
         it "orders bets correctly" {
             // These happen to be correct whether score or length ordered.
             let bets = vec![
@@ -650,6 +1507,50 @@ speculate! {
             assert!(ScrabrudoBet::from_word(&"chat".into()).is_correct(&vec![Tile::Blank, Tile::A, Tile::T, Tile::H], false));
         }
 
+        it "never expects a palafico it holds too many letters for" {
+            let state = &testing::game_state::<ScrabrudoBet>(vec![3, 3]);
+            let player = ScrabrudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Tile>{ items: vec![Tile::C, Tile::C, Tile::A], revealed: 0, exchanged: false },
+            };
+            let bet = ScrabrudoBet::from_word(&"cat".into());
+            assert_eq!(0.0, bet.prob(state, ProbVariant::Palafico, player.cloned()));
+        }
+
+        it "estimates what the lookup wasn't computed for" {
+            // The test lookup only goes up to 5 tiles.
+            let state = &testing::game_state::<ScrabrudoBet>(vec![3, 20]);
+            let player = ScrabrudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Tile>{ items: vec![Tile::C, Tile::X, Tile::Q], revealed: 0, exchanged: false },
+            };
+            let bet = ScrabrudoBet::from_word(&"cat".into());
+            assert!(bet.prob(state, ProbVariant::Bet, player.cloned()) > 0.5);
+        }
+
+        it "challenges cautiously on sampled chances" {
+            let state = &testing::game_state::<ScrabrudoBet>(vec![3, 20]);
+            let cautious = &mut state.clone();
+            cautious.config.seat_mut(0).caution = Some(99);
+            let player = ScrabrudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Tile>{ items: vec![Tile::C, Tile::X, Tile::Q], revealed: 0, exchanged: false },
+            };
+            let bet = ScrabrudoBet::from_word(&"cat".into());
+            let p = bet.prob(state, ProbVariant::Perudo, player.cloned());
+            let cautious_p = bet.prob(cautious, ProbVariant::Perudo, player.cloned());
+            assert!(cautious_p < p && cautious_p >= 0.0);
+            assert_eq!(
+                bet.prob(state, ProbVariant::Bet, player.cloned()),
+                bet.prob(cautious, ProbVariant::Bet, player.cloned()));
+        }
+
         it "checks exact bet correctness" {
             // TODO: implement
         }
@@ -744,11 +1645,7 @@ speculate! {
                     bet(Die::Six, 1),
                     bet(Die::Six, 2),
                 ],
-                original.all_above(&GameState::<PerudoBet>{
-                    total_num_items: 2,
-                    num_items_per_player: vec![1, 1],
-                    history: hashmap!{},
-                }));
+                original.all_above(&testing::game_state::<PerudoBet>(vec![1, 1])).collect::<Vec<Box<PerudoBet>>>());
         }
 
         fn approx(x: f64, y: f64) {
@@ -757,11 +1654,31 @@ speculate! {
             }
         }
 
+        it "checks bet correctness" {
+            let dice = vec![Die::One, Die::Two, Die::Two, Die::Six];
+            assert!(bet(Die::Two, 3).is_correct(&dice, false));
+            assert!(bet(Die::Two, 3).is_correct(&dice, true));
+            assert!(bet(Die::Two, 2).is_correct(&dice, false));
+            assert!(!bet(Die::Two, 2).is_correct(&dice, true));
+            assert!(!bet(Die::Two, 4).is_correct(&dice, false));
+            assert!(bet(Die::One, 1).is_correct(&dice, true));
+            assert!(!bet(Die::One, 2).is_correct(&dice, false));
+        }
+
+        it "finds dice that do nothing for a bet" {
+            let dice = vec![Die::One, Die::Two, Die::Three, Die::Six];
+            assert_eq!(vec![Die::Three, Die::Six], bet(Die::Two, 3).dead_items(&dice));
+            assert_eq!(vec![Die::Two, Die::Three, Die::Six], bet(Die::One, 1).dead_items(&dice));
+            let tiles = vec![Tile::C, Tile::A, Tile::A, Tile::Blank, Tile::Z];
+            assert_eq!(vec![Tile::A, Tile::Z], ScrabrudoBet::from_word(&"cat".into()).dead_items(&tiles));
+        }
+
         it "computes probability for bets" {
             // Create a player with a few of each.
             let player = Box::new(PerudoPlayer {
                 id: 0,
                 human: false,
+                opening: OpeningPolicy::Likeliest,
                 hand: Hand::<Die> {
                     items: vec![
                         Die::One ,
@@ -770,14 +1687,12 @@ speculate! {
                         Die::Four ,
                         Die::Five
                     ],
+                    revealed: 0,
+                    exchanged: false,
                 },
             });
 
-            let state = &GameState::<PerudoBet>{
-                total_num_items: 6,
-                num_items_per_player: vec![5, 1],
-                history: hashmap!{},
-            };
+            let state = &testing::game_state::<PerudoBet>(vec![5, 1]);
 
             // Bets on Ones, given one in the hand.
             approx(1.0, bet(Die::One, 0).prob(state, ProbVariant::Bet, player.cloned()));
@@ -792,14 +1707,108 @@ speculate! {
 
             // TODO: More tests for the prob-calcs.
         }
+
+        it "orders only certain bets when there are any" {
+            let player = Box::new(PerudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Die> {
+                    items: vec![Die::Three, Die::Three],
+                    revealed: 0,
+                    exchanged: false,
+                },
+            });
+            let mut state = GameState::<PerudoBet> {
+                history: hashmap!{ 1 => vec![*bet(Die::Two, 1)] },
+                ..testing::game_state(vec![2, 2])
+            };
+            assert_eq!(
+                vec![bet(Die::Three, 1), bet(Die::Three, 2)],
+                PerudoBet::ordered_bets(&state, player.cloned()));
+
+            // Once nothing is certain, every bet is ranked.
+            state.history = hashmap!{ 1 => vec![*bet(Die::Six, 2)] };
+            assert_eq!(6 * 4, PerudoBet::ordered_bets(&state, player.cloned()).len());
+        }
+
+        it "discounts a challenge against repeated claims" {
+            let player = Box::new(PerudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Die> { items: vec![Die::Four; 5], revealed: 0, exchanged: false },
+            });
+            let mut state = testing::game_state::<PerudoBet>(vec![5, 2]);
+            approx(8.0 / 9.0, bet(Die::Two, 2).prob(&state, ProbVariant::Perudo, player.cloned()));
+
+            // Player 1 has been raising Twos, so half the time they hold one and we need just one
+            // more from their other die.
+            state.history = hashmap!{ 1 => vec![*bet(Die::Two, 1), *bet(Die::Two, 2)] };
+            approx(1.0 - (0.5 / 9.0 + 0.5 / 3.0), bet(Die::Two, 2).prob(&state, ProbVariant::Perudo, player.cloned()));
+
+            // Our own claims tell us nothing.
+            state.history = hashmap!{ 0 => vec![*bet(Die::Two, 2)] };
+            approx(8.0 / 9.0, bet(Die::Two, 2).prob(&state, ProbVariant::Perudo, player.cloned()));
+        }
+
+        it "counts what others have shown as known" {
+            let player = Box::new(PerudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Die> { items: vec![Die::Four; 5], revealed: 0, exchanged: false },
+            });
+            let state = GameState::<PerudoBet> {
+                revealed: vec![vec![], vec![Die::One]],
+                ..testing::game_state(vec![5, 2])
+            };
+
+            // The shown One counts as a Two, leaving one unknown die to find another in.
+            approx(1.0 / 3.0, bet(Die::Two, 2).prob(&state, ProbVariant::Bet, player.cloned()));
+            approx(1.0, bet(Die::Two, 1).prob(&state, ProbVariant::Bet, player.cloned()));
+            approx(2.0 / 3.0, bet(Die::Two, 1).prob(&state, ProbVariant::Palafico, player.cloned()));
+
+            // As does a One in the middle, which hides nothing from us.
+            let state = GameState { revealed: vec![], community: vec![Die::One], ..state };
+            approx(1.0 - 4.0 / 9.0, bet(Die::Two, 2).prob(&state, ProbVariant::Bet, player.cloned()));
+        }
     }
 
-    describe "monte carlo" {
-        it "approximates the chance of a bet" {
-            let p = monte_carlo(20, &"cat".into(), 10000);
+    describe "equation bets" {
+        fn bet(identity: &str) -> EquationBet {
+            EquationBet::parse(identity).unwrap()
+        }
+
+        fn approx(x: f64, y: f64) {
+            if (x - y).abs() > 1e-9 {
+                panic!("{} != {}", x, y);
+            }
+        }
+
+        it "takes only true identities, ordering longer ones higher" {
+            assert!(EquationBet::parse("2+3=6").is_err());
+            assert!(EquationBet::parse("2+a=6").is_err());
+            assert_eq!("7*8=56", bet("7 x 8 = 56").notate());
+            assert!(bet("1+1=2") < bet("2+3=5"));
+            assert!(bet("9+9=18") > bet("2+3=5"));
+        }
 
-            // We should definitely find it a bunch of times in 20 die.
-            assert!(p > 0.0);
+        it "works out the chance of an identity exactly" {
+            let symbols = |s: &str| s.chars().map(|c| Symbol::from_char(c).unwrap()).collect::<Vec<Symbol>>();
+            let player = Box::new(EquationPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Symbol> { items: symbols("2+3="), revealed: 0, exchanged: false },
+            });
+            let state = testing::game_state::<EquationBet>(vec![4, 1]);
+            approx(Symbol::Five.prob(), bet("2+3=5").prob(&state, ProbVariant::Bet, player.cloned()));
+            approx(Symbol::Five.prob(), bet("2+3=5").prob(&state, ProbVariant::Palafico, player.cloned()));
+            approx(0.0, bet("3+3=6").prob(&state, ProbVariant::Bet, player.cloned()));
+            approx(0.0, bet("2+3=5").prob(&GameState { total_num_items: 4, num_items_per_player: vec![4, 0], ..state }, ProbVariant::Bet, player.cloned()));
+            assert!(bet("2+3=5").is_correct(&symbols("5=3+2"), true));
+            assert!(!bet("2+3=5").is_correct(&symbols("5=3+22"), true));
         }
     }
 }
@@ -0,0 +1,283 @@
+/// Blitz: holding AI players to a strict time limit each turn, with the anytime searches that let
+/// them stop when it runs out and answer with the best they've found so far.
+use crate::config::*;
+use crate::prob::*;
+use crate::rng;
+
+use rayon::prelude::*;
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many candidates a bounded ranking scores at a time.
+pub const BOUNDED_CHUNK: usize = 16;
+
+lazy_static! {
+    static ref DEFAULT_LIMIT: Mutex<Option<u64>> = Mutex::new(None);
+}
+
+/// The time by which a search must answer, if there is one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deadline {
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    /// No deadline: searches run to completion.
+    pub fn none() -> Self {
+        Self { at: None }
+    }
+
+    /// The given number of milliseconds from now.
+    pub fn after_millis(millis: u64) -> Self {
+        Self {
+            at: Some(Instant::now() + Duration::from_millis(millis)),
+        }
+    }
+
+    /// Whether the deadline has passed.
+    pub fn passed(&self) -> bool {
+        self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// How long is left, if there is a deadline at all.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// A search that improves its answer a step at a time, and can be stopped after any step.
+pub trait AnytimeSearch {
+    type Answer;
+
+    /// Takes one more step, returning false if there was nothing left to search.
+    fn step(&mut self) -> bool;
+
+    /// The best answer found so far.
+    fn answer(self) -> Self::Answer;
+}
+
+/// Searches until there is nothing left to search or the deadline passes, then answers with the
+/// best found so far. At least one step is always taken, so that there is something to answer.
+pub fn search_until<S: AnytimeSearch>(mut search: S, deadline: &Deadline) -> S::Answer {
+    while search.step() && !deadline.passed() {}
+    search.answer()
+}
+
+/// Scores candidates one at a time, answering with those scored so far, ranked lowest first.
+pub struct Ranking<'a, T> {
+    candidates: Box<dyn Iterator<Item = T> + 'a>,
+    score: Box<dyn Fn(&T) -> f64 + 'a>,
+    scored: Vec<(T, f64)>,
+    exhausted: bool,
+}
+
+impl<'a, T> Ranking<'a, T> {
+    pub fn new(
+        candidates: Box<dyn Iterator<Item = T> + 'a>,
+        score: impl Fn(&T) -> f64 + 'a,
+    ) -> Self {
+        Self {
+            candidates: candidates,
+            score: Box::new(score),
+            scored: vec![],
+            exhausted: false,
+        }
+    }
+}
+
+impl<'a, T> AnytimeSearch for Ranking<'a, T> {
+    type Answer = Vec<(T, f64)>;
+
+    fn step(&mut self) -> bool {
+        match self.candidates.next() {
+            Some(candidate) => {
+                let score = (self.score)(&candidate);
+                self.scored.push((candidate, score));
+                true
+            }
+            None => {
+                self.exhausted = true;
+                false
+            }
+        }
+    }
+
+    fn answer(self) -> Self::Answer {
+        if !self.exhausted {
+            debug!("Out of time after ranking {} candidates", self.scored.len());
+        }
+        rank_outcomes(self.scored)
+    }
+}
+
+/// Scores candidates in the order given, a chunk at a time, and stops once upper bounds on the
+/// scores of those left show that none could come within the margin of the best so far. Each
+/// chunk is scored in parallel, unless the game is seeded, since scoring may draw at random and
+/// a seeded game has to draw in the same order every time.
+pub struct BoundedRanking<'a, T> {
+    candidates: std::vec::IntoIter<T>,
+    remaining_bounds: Vec<f64>,
+    num_taken: usize,
+    score: Box<dyn Fn(&T) -> f64 + Send + Sync + 'a>,
+    scored: Vec<(T, f64)>,
+    best: f64,
+    margin: f64,
+}
+
+impl<'a, T> BoundedRanking<'a, T> {
+    /// Ranks the given candidates, each paired with an upper bound on its score, where anything
+    /// scoring below the floor is of no interest.
+    pub fn new(
+        candidates: Vec<(T, f64)>,
+        score: impl Fn(&T) -> f64 + Send + Sync + 'a,
+        floor: f64,
+        margin: f64,
+    ) -> Self {
+        // The highest bound of each candidate and all those after it.
+        let mut remaining_bounds = candidates.iter().map(|c| c.1).collect::<Vec<f64>>();
+        for i in (1..remaining_bounds.len()).rev() {
+            remaining_bounds[i - 1] = remaining_bounds[i - 1].max(remaining_bounds[i]);
+        }
+        Self {
+            candidates: candidates
+                .into_iter()
+                .map(|c| c.0)
+                .collect::<Vec<T>>()
+                .into_iter(),
+            remaining_bounds: remaining_bounds,
+            num_taken: 0,
+            score: Box::new(score),
+            scored: vec![],
+            best: floor,
+            margin: margin,
+        }
+    }
+}
+
+impl<'a, T: Send + Sync> AnytimeSearch for BoundedRanking<'a, T> {
+    type Answer = Vec<(T, f64)>;
+
+    fn step(&mut self) -> bool {
+        let bound = match self.remaining_bounds.get(self.num_taken) {
+            Some(bound) => *bound,
+            None => return false,
+        };
+        if bound + self.margin < self.best - TIE_EPSILON {
+            debug!(
+                "Pruned {} candidates that can't beat {}",
+                self.remaining_bounds.len() - self.num_taken,
+                self.best
+            );
+            return false;
+        }
+        let chunk = self
+            .candidates
+            .by_ref()
+            .take(BOUNDED_CHUNK)
+            .collect::<Vec<T>>();
+        let score = &self.score;
+        let scores = if rng::seed().is_none() {
+            chunk.par_iter().map(score).collect::<Vec<f64>>()
+        } else {
+            chunk.iter().map(score).collect::<Vec<f64>>()
+        };
+        self.num_taken += chunk.len();
+        for (candidate, score) in chunk.into_iter().zip(scores) {
+            self.best = self.best.max(score);
+            self.scored.push((candidate, score));
+        }
+        true
+    }
+
+    fn answer(self) -> Self::Answer {
+        rank_outcomes(self.scored)
+    }
+}
+
+/// Holds every AI player without a limit of their own in their game's config to the given number
+/// of milliseconds each turn.
+pub fn init_blitz(millis: u64) {
+    *DEFAULT_LIMIT.lock().unwrap() = Some(millis);
+}
+
+/// The deadline for the given player's turn in a game of the given config, starting now.
+pub fn deadline(config: &GameConfig, player_id: usize) -> Deadline {
+    let limit = match config.seat(player_id).and_then(|seat| seat.blitz) {
+        Some(millis) => Some(millis),
+        None => *DEFAULT_LIMIT.lock().unwrap(),
+    };
+    match limit {
+        Some(millis) => Deadline::after_millis(millis),
+        None => Deadline::none(),
+    }
+}
+
+/// Parses a limit for one player or for all from its command-line form, e.g. "1:200" or "500".
+pub fn parse_blitz(assignment: &str) -> Option<(Option<usize>, u64)> {
+    let mut split = assignment.splitn(2, ':');
+    match (split.next(), split.next()) {
+        (Some(player_id), Some(millis)) => {
+            Some((Some(player_id.parse().ok()?), millis.parse().ok()?))
+        }
+        _ => Some((None, assignment.parse().ok()?)),
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "blitz" {
+        it "parses limits" {
+            assert_eq!(Some((None, 500)), parse_blitz("500"));
+            assert_eq!(Some((Some(1), 200)), parse_blitz("1:200"));
+            assert_eq!(None, parse_blitz("fast"));
+            assert_eq!(None, parse_blitz("1:"));
+        }
+
+        it "keeps to deadlines" {
+            assert!(!Deadline::none().passed());
+            assert_eq!(None, Deadline::none().remaining());
+            assert!(Deadline::after_millis(0).passed());
+            assert!(!Deadline::after_millis(60_000).passed());
+        }
+
+        it "ranks everything without a deadline" {
+            let ranking = Ranking::new(Box::new(vec![3, 1, 2].into_iter()), |x: &i32| *x as f64);
+            assert_eq!(vec![(1, 1.0), (2, 2.0), (3, 3.0)], search_until(ranking, &Deadline::none()));
+        }
+
+        it "stops ranking once nothing left can beat the best" {
+            // The first chunk scores up to 0.9, and everything after is bounded by 0.5.
+            let candidates = (0..40)
+                .map(|x| (x, if x < BOUNDED_CHUNK { 0.9 } else { 0.5 }))
+                .collect::<Vec<(usize, f64)>>();
+            let score = |x: &usize| if *x == 3 { 0.9 } else { 0.1 };
+            let ranked = search_until(
+                BoundedRanking::new(candidates.clone(), score, 0.0, 0.0),
+                &Deadline::none());
+            assert_eq!(BOUNDED_CHUNK, ranked.len());
+            assert_eq!((3, 0.9), ranked[ranked.len() - 1]);
+
+            // Within the margin, the rest still have to be scored.
+            let ranked = search_until(
+                BoundedRanking::new(candidates.clone(), score, 0.0, 0.5),
+                &Deadline::none());
+            assert_eq!(40, ranked.len());
+
+            // Nor can anything be ruled out before there is a best to beat.
+            let ranked = search_until(
+                BoundedRanking::new(candidates, |_: &usize| 0.1, 0.0, 0.0),
+                &Deadline::none());
+            assert_eq!(40, ranked.len());
+        }
+
+        it "answers with what it has once the deadline passes" {
+            let ranking = Ranking::new(Box::new(0..), |x: &i32| -*x as f64);
+            assert_eq!(vec![(0, 0.0)], search_until(ranking, &Deadline::after_millis(0)));
+            let ranking = Ranking::new(Box::new(0..), |x: &i32| *x as f64);
+            assert!(!search_until(ranking, &Deadline::after_millis(5)).is_empty());
+        }
+    }
+}
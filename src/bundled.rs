@@ -0,0 +1,73 @@
+/// The bundled dictionary and lookup: a few hundred common short words and a small lookup built
+/// from them, embedded in the binary with the bundled-data feature, so that Scrabrudo can be
+/// played without either being given. Any given paths are played with instead.
+use crate::error::*;
+
+#[cfg(test)]
+use speculate::speculate;
+#[cfg(feature = "bundled-data")]
+use std::env;
+#[cfg(feature = "bundled-data")]
+use std::fs;
+#[cfg(feature = "bundled-data")]
+use std::process;
+#[cfg(feature = "bundled-data")]
+use std::sync::Mutex;
+
+#[cfg(feature = "bundled-data")]
+lazy_static! {
+    static ref BUNDLED_PATHS: Mutex<Option<(String, String)>> = Mutex::new(None);
+}
+
+/// The bundled dictionary, one word to a line.
+#[cfg(feature = "bundled-data")]
+pub const BUNDLED_WORDS: &str = include_str!("../data/bundled_words.txt");
+
+/// The bundled lookup of the bundled words, for up to 10 tiles.
+#[cfg(feature = "bundled-data")]
+pub const BUNDLED_LOOKUP: &[u8] = include_bytes!("../data/bundled_10_1000.sstable");
+
+/// The paths of the bundled dictionary and lookup, written out on first asking since lookups are
+/// read from disk, or None without the bundled-data feature.
+#[cfg(feature = "bundled-data")]
+pub fn bundled_paths() -> Result<Option<(String, String)>, ScrabrudoError> {
+    let mut paths = BUNDLED_PATHS.lock().unwrap();
+    if paths.is_none() {
+        let dir = env::temp_dir();
+        let dict_path = dir.join(format!("scrabrudo_bundled_{}.txt", process::id()));
+        let lookup_path = dir.join(format!("scrabrudo_bundled_{}.sstable", process::id()));
+        fs::write(&dict_path, BUNDLED_WORDS)?;
+        fs::write(&lookup_path, BUNDLED_LOOKUP)?;
+        *paths = Some((
+            dict_path.to_string_lossy().into(),
+            lookup_path.to_string_lossy().into(),
+        ));
+    }
+    Ok(paths.clone())
+}
+
+/// Without the bundled-data feature, there's nothing bundled.
+#[cfg(not(feature = "bundled-data"))]
+pub fn bundled_paths() -> Result<Option<(String, String)>, ScrabrudoError> {
+    Ok(None)
+}
+
+#[cfg(test)]
+speculate! {
+    describe "bundled data" {
+        #[cfg(not(feature = "bundled-data"))]
+        it "bundles nothing without the feature" {
+            assert_eq!(None, bundled_paths().unwrap());
+        }
+
+        #[cfg(feature = "bundled-data")]
+        it "plays with the bundled words" {
+            let (dict_path, lookup_path) = bundled_paths().unwrap().unwrap();
+            let dict = crate::dict::Dict::load(&dict_path, &crate::dict::DictProfile::Full).unwrap();
+            assert!(dict.words().contains("cat"));
+            let lookup = crate::dict::Lookup::open(&lookup_path).unwrap();
+            assert_eq!(Some(10), lookup.max_num_tiles());
+            assert_eq!(Some((dict_path, lookup_path)), bundled_paths().unwrap());
+        }
+    }
+}
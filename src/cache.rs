@@ -0,0 +1,200 @@
+/// A cache of probabilities the lookup doesn't hold, estimated on the fly and optionally kept on
+/// disk so that later games don't have to estimate them again.
+use crate::error::*;
+use crate::metrics;
+use crate::monte_carlo::*;
+use crate::tile::*;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+use std::fs::File;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref CACHE: Mutex<ProbCache> = Mutex::new(ProbCache::new(DEFAULT_CACHE_SIZE));
+    static ref CACHE_PATH: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// How many probabilities are cached unless told otherwise.
+pub const DEFAULT_CACHE_SIZE: usize = 100_000;
+
+/// How many trials to run when estimating a probability the lookup doesn't hold.
+pub const CACHE_NUM_TRIALS: u32 = 1000;
+
+/// The chances of finding some tiles in a number of tiles, at least and exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub prob: f64,
+    pub exact_prob: f64,
+    last_used: u64,
+}
+
+/// Probabilities keyed by the sorted tiles sought and the number of tiles they're sought in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbCache {
+    tile_set_hash: u64,
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// The key of the given tiles sought in n tiles, e.g. "act:12".
+fn key(s: &str, n: usize) -> String {
+    format!("{}:{}", s, n)
+}
+
+impl ProbCache {
+    /// Creates an empty cache for the current tile set, holding up to the given number of
+    /// probabilities.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            tile_set_hash: tile_set().hash(),
+            capacity: capacity,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Loads the cache saved at the given path, starting afresh if there is none yet or it was
+    /// estimated with a different tile set.
+    pub fn load(path: &str, capacity: usize) -> Result<Self, ScrabrudoError> {
+        let f = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => return Ok(Self::new(capacity)),
+        };
+        let mut cache: Self = serde_json::from_reader(f)?;
+        if cache.tile_set_hash != tile_set().hash() {
+            warn!("Ignoring {}, estimated for a different tile set", path);
+            return Ok(Self::new(capacity));
+        }
+        cache.capacity = capacity;
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// Saves the cache to the given path.
+    pub fn save(&self, path: &str) -> Result<(), ScrabrudoError> {
+        let f = File::create(path)?;
+        serde_json::to_writer(f, self)?;
+        Ok(())
+    }
+
+    /// Gets the chances of finding the given sorted tiles in n tiles, if cached.
+    pub fn get(&mut self, s: &str, n: usize) -> Option<(f64, f64)> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(&key(s, n)).map(|entry| {
+            entry.last_used = clock;
+            (entry.prob, entry.exact_prob)
+        })
+    }
+
+    /// Caches the chances of finding the given sorted tiles in n tiles.
+    pub fn insert(&mut self, s: &str, n: usize, prob: f64, exact_prob: f64) {
+        self.clock += 1;
+        let entry = CacheEntry {
+            prob: prob,
+            exact_prob: exact_prob,
+            last_used: self.clock,
+        };
+        self.entries.insert(key(s, n), entry);
+        self.evict();
+    }
+
+    /// Once over capacity, drops the least recently used probabilities until only three quarters
+    /// of the capacity is used, so that eviction happens now and then rather than on every
+    /// insert.
+    fn evict(&mut self) {
+        if self.entries.len() <= self.capacity {
+            return;
+        }
+        let mut last_used = self
+            .entries
+            .iter()
+            .map(|(k, entry)| (entry.last_used, k.clone()))
+            .collect::<Vec<(u64, String)>>();
+        last_used.sort();
+        let num_to_drop = self.entries.len() - self.capacity * 3 / 4;
+        debug!("Probability cache full, dropping {} entries", num_to_drop);
+        for (_, k) in last_used.into_iter().take(num_to_drop) {
+            self.entries.remove(&k);
+        }
+    }
+
+    /// The number of probabilities cached.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Keeps the cache in the given file between games, holding up to the given number of
+/// probabilities.
+pub fn init_prob_cache(path: Option<&str>, capacity: usize) -> Result<(), ScrabrudoError> {
+    let cache = match path {
+        Some(path) => ProbCache::load(path, capacity)?,
+        None => ProbCache::new(capacity),
+    };
+    *CACHE.lock().unwrap() = cache;
+    *CACHE_PATH.lock().unwrap() = path.map(|p| p.into());
+    Ok(())
+}
+
+/// The chances of finding the given sorted tiles in n tiles, at least and exactly, estimated by
+/// Monte Carlo the first time they're asked for.
+pub fn cached_probs(s: &str, n: usize) -> (f64, f64) {
+    let cached = CACHE.lock().unwrap().get(s, n);
+    metrics::record_cache(cached.is_some());
+    if let Some(probs) = cached {
+        return probs;
+    }
+    debug!("Estimating P({}) in {} tiles", s, n);
+    let prob = monte_carlo(n as u32, s, CACHE_NUM_TRIALS, false);
+    let exact_prob = monte_carlo(n as u32, s, CACHE_NUM_TRIALS, true);
+    CACHE.lock().unwrap().insert(s, n, prob, exact_prob);
+    (prob, exact_prob)
+}
+
+/// Saves the cache if it is kept on disk.
+/// Failing to save shouldn't stop the game, so errors are only logged.
+pub fn save_prob_cache() {
+    if let Some(path) = CACHE_PATH.lock().unwrap().as_ref() {
+        match CACHE.lock().unwrap().save(path) {
+            Ok(()) => debug!("Probability cache saved to {}", path),
+            Err(e) => error!("Couldn't save probability cache to {}: {}", path, e),
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "probability caches" {
+        it "evicts the least recently used" {
+            let mut cache = ProbCache::new(4);
+            for n in 0..4 {
+                cache.insert("a", n, 0.5, 0.1);
+            }
+            assert_eq!(Some((0.5, 0.1)), cache.get("a", 0));
+            cache.insert("b", 1, 0.2, 0.0);
+            assert_eq!(3, cache.len());
+            assert!(cache.get("a", 0).is_some());
+            assert!(cache.get("b", 1).is_some());
+            assert!(cache.get("a", 1).is_none());
+        }
+
+        it "keeps probabilities between games" {
+            let mut cache = ProbCache::new(10);
+            cache.insert("act", 12, 0.3, 0.01);
+            cache.save("/tmp/prob_cache1.json").unwrap();
+            let mut loaded = ProbCache::load("/tmp/prob_cache1.json", 10).unwrap();
+            assert_eq!(Some((0.3, 0.01)), loaded.get("act", 12));
+            assert!(ProbCache::load("/tmp/no_such_cache.json", 10).unwrap().is_empty());
+        }
+    }
+}
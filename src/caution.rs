@@ -0,0 +1,96 @@
+/// Caution: having the AI challenge only as far as sampled probabilities can be trusted. Chances
+/// estimated by Monte Carlo are noisy, and the AI calls on whichever looks best, so it tends to
+/// call on those the noise flattered. A cautious player takes the pessimistic end of a confidence
+/// interval around each sampled chance of a challenge succeeding instead.
+use crate::compare::*;
+use crate::config::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref DEFAULT_CAUTION: Mutex<Option<u32>> = Mutex::new(None);
+}
+
+/// The number of standard deviations spanned by a two-sided interval of the given confidence, in
+/// percent, for the levels understood.
+pub fn z_for_level(level: u32) -> Option<f64> {
+    match level {
+        80 => Some(1.282),
+        90 => Some(1.645),
+        95 => Some(1.96),
+        98 => Some(2.326),
+        99 => Some(2.576),
+        _ => None,
+    }
+}
+
+/// Has every player without a level of their own in their game's config challenge on the
+/// pessimistic end of intervals of the given confidence.
+pub fn init_caution(level: u32) {
+    *DEFAULT_CAUTION.lock().unwrap() = Some(level);
+}
+
+/// The number of standard deviations the given player allows for in a game of the given config,
+/// if they are cautious at all.
+pub fn caution(config: &GameConfig, player_id: usize) -> Option<f64> {
+    let level = match config.seat(player_id).and_then(|seat| seat.caution) {
+        Some(level) => Some(level),
+        None => *DEFAULT_CAUTION.lock().unwrap(),
+    };
+    level.and_then(z_for_level)
+}
+
+/// Parses a confidence level for one player or for all from its command-line form, e.g. "95" or
+/// "1:99".
+pub fn parse_caution(assignment: &str) -> Option<(Option<usize>, u32)> {
+    let mut split = assignment.splitn(2, ':');
+    let (player_id, level) = match (split.next(), split.next()) {
+        (Some(player_id), Some(level)) => (Some(player_id.parse().ok()?), level.parse().ok()?),
+        _ => (None, assignment.parse().ok()?),
+    };
+    z_for_level(level)?;
+    Some((player_id, level))
+}
+
+/// The pessimistic end of the Wilson interval around a chance estimated from the given number of
+/// trials, spanning the given number of standard deviations. The chance is taken as it is if it
+/// wasn't sampled, or if there's no caution to take.
+pub fn lower_bound(p: f64, num_trials: Option<u32>, z: Option<f64>) -> f64 {
+    match (num_trials, z) {
+        (Some(num_trials), Some(z)) if num_trials > 0 => {
+            let successes = (p * num_trials as f64).round() as usize;
+            wilson_interval(successes, num_trials as usize, z).0
+        }
+        _ => p,
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "caution" {
+        it "parses levels" {
+            assert_eq!(Some((None, 95)), parse_caution("95"));
+            assert_eq!(Some((Some(1), 99)), parse_caution("1:99"));
+            assert_eq!(None, parse_caution("97"));
+            assert_eq!(None, parse_caution("1:"));
+        }
+
+        it "takes the pessimistic end of sampled chances" {
+            let p = lower_bound(0.5, Some(100), Some(1.96));
+            assert!(p > 0.39 && p < 0.41);
+            assert!(lower_bound(0.5, Some(10000), Some(1.96)) > p);
+            assert_eq!(0.5, lower_bound(0.5, None, Some(1.96)));
+            assert_eq!(0.5, lower_bound(0.5, Some(100), None));
+        }
+
+        it "is cautious per player in each game" {
+            let mut config = GameConfig::default();
+            config.seat_mut(1).caution = Some(90);
+            assert_eq!(Some(1.645), caution(&config, 1));
+            assert_eq!(None, caution(&config, 2));
+            assert_eq!(None, caution(&GameConfig::default(), 1));
+        }
+    }
+}
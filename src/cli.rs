@@ -0,0 +1,447 @@
+/// The command line: a subcommand for each thing the program does, each taking only the arguments
+/// it uses, and helpers for reading them.
+use crate::error::*;
+use crate::variant;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+#[cfg(test)]
+use speculate::speculate;
+use std::fs;
+
+/// The argument naming a config file to read the rest from.
+pub const CONFIG_ARGS: &str =
+    "--config=[CONFIG] 'a TOML file of arguments for this subcommand, which those given here override'";
+
+/// Arguments for where a crash is reported, for every subcommand.
+pub const CRASH_ARGS: &str =
+    "--crash_dump=[CRASH_DUMP] 'where to write what was going on should the program crash, by default scrabrudo_crash.json'";
+
+/// Arguments for how the AI plays.
+pub const AI_ARGS: &str = "-n, --num_players=[NUM_PLAYERS] 'the number of players'
+    -o, --opening=[OPENING] 'likeliest, safe, hidden or bluff'
+    --belief=[BELIEF]... 'how far the AI believes bets: half, all, none, cap:K or honesty, or ID:POLICY for one player'
+    --mcts=[MCTS]... 'have the AI search each turn within a budget, e.g. iterations:200 or millis:500, or ID:BUDGET for one player'
+    --blitz=[BLITZ]... 'hold AI players to this many milliseconds a turn, answering with the best found so far, or ID:MILLIS for one player'
+    --caution=[CAUTION]... 'have the AI challenge on the pessimistic end of sampled scrabrudo chances, at 80, 90, 95, 98 or 99% confidence, or ID:LEVEL for one player'
+    --learned=[LEARNED]... 'ID:PATH, have a player choose with the linear model of weights in a JSON file, or an ONNX model built with the onnx feature'
+    --tie_break=[TIE_BREAK]... 'random or canonical, how the AI chooses between equally good moves, or ID:POLICY for one player'";
+
+/// Arguments for the house rules a game is played under.
+pub const RULE_ARGS: &str = "--no_repeated_words 'forbid repeating a bet already made this round'
+    --phrases 'allow scrabrudo bets of several words joined by +, e.g. cat+dog'
+    --reveal_on_loss 'show one more item to everyone on losing a challenge, rather than losing it'
+    --community=[COMMUNITY] 'deal this many items face up in the middle each round, counting towards every bet'
+    --constraints 'draw a card each round that every scrabrudo bet must satisfy, e.g. no S allowed'
+    --banned_words=[BANNED_WORDS] 'a file of words, one to a line, that no scrabrudo bet may contain'
+    --exchanges 'allow redrawing some of your hand once a round, giving up the right to challenge'
+    --trades 'before each round, let neighbours who both offer a hidden item swap them'
+    --min_raise=[MIN_RAISE] 'how far scrabrudo bets must rise, e.g. letters:2 or score:5'
+    --first_player=[FIRST_PLAYER] 'first, random or dice_off'
+    --round_starter=[ROUND_STARTER] 'loser, winner or left_of_loser'
+    --forfeit=[FORFEIT] 'ai or remove, what happens to a human who quits'
+    --timebank=[TIMEBANK] 'give each human this many milliseconds of thinking for the game, then safe or forfeit, e.g. 60000:forfeit'
+    --max_rounds=[MAX_ROUNDS] 'end the game after this many rounds, the most items left winning and sudden death breaking ties'
+    --style=[STYLE] 'scrabbly or common, whether the AI prefers common words'";
+
+/// Arguments for the dictionary, lookup and tiles Scrabrudo is played with.
+pub const WORD_ARGS: &str = "-d, --dictionary_path=[DICTIONARY] 'the path to the .txt dict to use'
+    -l, --lookup_path=[LOOKUP] 'the path to the lookup to read'
+    --tile_set=[TILE_SET] 'a JSON file of tile counts to deal from, matching the lookup'
+    --prob_cache=[PROB_CACHE] 'a file to keep probabilities missing from the lookup in between games'
+    --prob_cache_size=[PROB_CACHE_SIZE] 'the most probabilities to cache'
+    --prob_store=[PROB_STORE] 'sstable, memory or exact, where probabilities are read from, or cached:STORE to remember them'
+    --frequency_path=[FREQUENCIES] 'a word list, most common first, for the common style and profiles'
+    --dict_profile=[DICT_PROFILE] 'full, kids, common or tournament, the words in play'";
+
+/// Arguments for recording the games played.
+pub const RECORD_ARGS: &str = "--analysis_out=[ANALYSIS_OUT] 'where to record the AI's estimate of each turn'
+    --notation_out=[NOTATION_OUT] 'where to write each round in game notation'
+    --turns_csv=[TURNS_CSV] 'where to write one CSV row per turn, with what the player could see, for training policies'
+    --explain_out=[EXPLAIN_OUT] 'where to write why each AI move was made, one JSON object a line'
+    --leaderboard=[LEADERBOARD] 'a sqlite database to record finished games in, built with the leaderboard feature'
+    --name=[NAME]... 'ID:NAME, who sits in a seat, as recorded on the leaderboard'";
+
+/// Arguments for playing at the terminal.
+pub const PLAY_ARGS: &str = "--variant=[VARIANT] 'perudo, scrabrudo or equation, the game to play'
+    --daily 'play the daily scrabrudo puzzle'
+    -h, --human_index=[HUMAN_INDEX] 'which, if any, is the human'
+    --autosave=[AUTOSAVE] 'where to save the game when a human quits'
+    --resume=[RESUME] 'a saved game to carry on with'
+    --rack_order=[RACK_ORDER] 'dealt, alphabetical or score, how your tiles are shown'
+    --lang=[LANG] 'en, es or de, the language prompts and messages are shown in'
+    --practice 'list the words your rack can make each turn'
+    --tracker 'show how many of each letter are likely still in play each turn'
+    --hints 'show what can be deduced from the bets and reveals so far each turn'
+    --color 'colour tiles by letter and score and bets by seat, built with the color feature'
+    --accessible 'describe the game in plain, linear sentences for screen readers, and accept commands typed out in full'
+    --bell 'ring the terminal bell on your turn, on challenges and when you lose a tile'
+    --summary=[SUMMARY] 'off, brief or full, what is said after each AI move: the move, and how much of the table it claims'
+    --step 'pause before every turn to inspect the game'
+    --explain 'show why each AI move was made: what it weighed up, believed and ruled out'
+    --player_log=[PLAYER_LOG]... 'ID:PATH, send a player's hand to a file of their own'
+    --pace=[PACE] 'TURN_MS or TURN_MS:DEAL_MS, how long to pause after each AI move and each deal'
+    --match_games=[MATCH_GAMES] 'play a best-of-N match rather than one game'
+    --match_file=[MATCH_FILE] 'where to save the match, resuming it if present'
+    --build_lookup 'if the lookup is missing or stale, build it while playing with common words'";
+
+/// Arguments for all-AI games played to measure the AI.
+pub const SIMULATE_ARGS: &str = "<TASK> 'calibrate, compare or golden'
+    --variant=[VARIANT] 'perudo, scrabrudo or equation, the game to simulate'
+    --calibrate_games=[CALIBRATE_GAMES] 'how many all-AI games to calibrate on'
+    --strategy_a=[STRATEGY_A] 'the first strategy to compare, greedy or e.g. belief:none or mcts:iterations:200'
+    --strategy_b=[STRATEGY_B] 'the second strategy to compare'
+    --compare_games=[COMPARE_GAMES] 'how many games to compare strategies over'
+    --golden=[GOLDEN] 'the record of a seeded game to check a golden game against'
+    --seed=[SEED] 'the seed to play a golden game from'
+    --bless 'write the golden game to its record rather than checking it'";
+
+/// Arguments for a demo game.
+pub const DEMO_ARGS: &str =
+    "--variant=[VARIANT] 'perudo, scrabrudo or equation, the game to demonstrate'
+    --seed=[SEED] 'the seed to deal the demo game from, by default 1'
+    --items=[ITEMS] 'how many items each player starts with, by default 3'
+    --lang=[LANG] 'en, es or de, the language the game and its commentary are told in'";
+
+/// Arguments for building a lookup.
+pub const PRECOMPUTE_ARGS: &str = "-n, --num_tiles=<NUM_TILES> 'the max number of tiles to compute'
+    -t, --num_trials=<NUM_TRIALS> 'the number of trials to run'
+    -d, --dictionary_path=<DICTIONARY> 'the path to the .txt dict to use'
+    -l, --lookup_path=<LOOKUP> 'the path to the lookup DB to write'
+    --tile_set=[TILE_SET] 'a JSON file of tile counts to deal from'
+    --dict_profile=[DICT_PROFILE] 'full, kids, common or tournament, the words to compute for'
+    --frequency_path=[FREQUENCIES] 'a word list, most common first, for the kids and common profiles'";
+
+/// Arguments for estimating the chance of a single word.
+pub const ESTIMATE_ARGS: &str = "--word=<WORD> 'the word to find, e.g. attack'
+    --tiles=<TILES> 'the number of tiles on the table'
+    --trials=[TRIALS] 'the number of tables to sample'
+    --exact 'the chance of exactly the word, as for palafico, rather than at least'
+    --confidence=[CONFIDENCE] '80, 90, 95, 98 or 99, the confidence of the interval given'
+    --tile_set=[TILE_SET] 'a JSON file of tile counts to deal from'";
+
+/// Arguments for analysing recorded games and the odds.
+pub const ANALYZE_ARGS: &str = "<TASK> 'report or heatmap'
+    --analysis_in=[ANALYSIS_IN] 'the recorded turns to report on'
+    --hand=[HAND] 'the rack to export a heatmap of bet probabilities for, e.g. cat'
+    --table_size=[TABLE_SIZE] 'the number of tiles around the table, the rack's included, for the heatmap'
+    --heatmap_out=[HEATMAP_OUT] 'where to write the heatmap as CSV'
+    --exact 'export the chances of exactly each word, as for palafico, rather than at least'";
+
+/// Arguments for playing many games at once.
+pub const SERVE_ARGS: &str = "--variant=[VARIANT] 'perudo, scrabrudo or equation, the game to serve'
+    --games=[GAMES] 'how many games to play'
+    --threads=[THREADS] 'how many games to play at once'
+    -h, --human_index=[HUMAN_INDEX] 'which seat, if any, a human takes in every game'
+    --player_log=[PLAYER_LOG]... 'ID:PATH, send a player's hand to a file of their own'
+    --pace=[PACE] 'TURN_MS or TURN_MS:DEAL_MS, how long to pause after each AI move and each deal'
+    --metrics_addr=[METRICS_ADDR] 'an address such as 127.0.0.1:9100 to serve Prometheus metrics at /metrics on'
+    --dashboard_addr=[DASHBOARD_ADDR] 'an address such as 127.0.0.1:8080 to serve a page for watching the games on, built with the dashboard feature'
+    --reveal_delay=[REVEAL_DELAY] 'hold each challenge, and everything after it, back from the dashboard's events for this many seconds'
+    --event_batch=[EVENT_BATCH] 'send the dashboard's events in batches this many milliseconds apart'
+    --game_records=[GAME_RECORDS] 'a directory to record each game's seed, hands and moves in, for verify'
+    --lobby_addr=[LOBBY_ADDR] 'an address such as 127.0.0.1:8090 to serve lobbies on for people to find and play games over the network in, rather than playing --games, built with the net feature'
+    --grace=[GRACE] 'how many seconds someone playing over the network has to come back after their connection drops before forfeiting, 60 by default'
+    --on_disconnect=[ON_DISCONNECT] 'ai or pause, whether the AI plays for someone whose connection has dropped or the game waits for them'
+    --admin_token=[ADMIN_TOKEN] 'a secret to take admin commands to the lobbies with, such as reload to switch dictionary; none are taken without it'";
+
+/// Arguments for checking recorded games still play out as they did.
+pub const VERIFY_ARGS: &str = "<RECORD>... 'the game records to verify'
+    --variant=[VARIANT] 'perudo, scrabrudo or equation, the game recorded'";
+
+/// Arguments for going back over a game in notation.
+pub const REPLAY_ARGS: &str = "<NOTATION_IN> 'the games to replay, in game notation'
+    --variant=[VARIANT] 'perudo, scrabrudo or equation, the game the notation is of'
+    --explain 'show what the AI would have done in each challenger's place, and why'";
+
+/// Arguments for reporting on a leaderboard.
+pub const STATS_ARGS: &str = "<TASK> 'players or strategies'
+    --leaderboard=<LEADERBOARD> 'the sqlite database of finished games'
+    --top=[TOP] 'how many players to show, best first'
+    --period=[PERIOD] 'day, week or month, how to break down strategy win rates over time'";
+
+/// Arguments for reporting on a dictionary.
+pub const DICT_ARGS: &str = "<TASK> 'stats'
+    -d, --dictionary_path=<DICTIONARY> 'the path to the .txt dict to report on'
+    -n, --num_tiles=[NUM_TILES] 'the most tiles to count lookup keys for'
+    --tile_set=[TILE_SET] 'a JSON file of tile counts to compare letters with'
+    --dict_profile=[DICT_PROFILE] 'full, kids, common or tournament, the words to report on'
+    --frequency_path=[FREQUENCIES] 'a word list, most common first, for the kids and common profiles'";
+
+/// The subcommand of precompute estimating the chance of a single word.
+pub fn estimate_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("estimate")
+        .about("Estimates the chance of finding a word among some tiles")
+        .args_from_usage(ESTIMATE_ARGS)
+}
+
+/// The arguments every variant takes of its own, for the subcommands a variant is chosen for.
+pub fn variant_args() -> Vec<Arg<'static, 'static>> {
+    variant::variants()
+        .iter()
+        .flat_map(|variant| variant.args.lines())
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(Arg::from_usage)
+        .collect()
+}
+
+/// The command line of the main binary.
+pub fn app() -> App<'static, 'static> {
+    App::new("Scrabrudo")
+        .version("0.1")
+        .about("A mixture of Scrabble and Perudo")
+        .author("Harry Askham")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("play")
+                .about("Plays a game or match at the terminal")
+                .args_from_usage(PLAY_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .args_from_usage(AI_ARGS)
+                .args_from_usage(RULE_ARGS)
+                .args(&variant_args())
+                .args_from_usage(RECORD_ARGS),
+        )
+        .subcommand(
+            SubCommand::with_name("simulate")
+                .about("Plays all-AI games to calibrate, compare or check the AI")
+                .args_from_usage(SIMULATE_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .args_from_usage(AI_ARGS)
+                .args_from_usage(RULE_ARGS)
+                .args(&variant_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("demo")
+                .about("Plays a short all-AI game, saying why each move was made")
+                .args_from_usage(DEMO_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .args_from_usage(AI_ARGS)
+                .args_from_usage(RULE_ARGS)
+                .args(&variant_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("precompute")
+                .about("Builds a lookup of probabilities for Scrabrudo")
+                .setting(AppSettings::SubcommandsNegateReqs)
+                .args_from_usage(PRECOMPUTE_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .subcommand(estimate_subcommand()),
+        )
+        .subcommand(
+            SubCommand::with_name("analyze")
+                .about("Reports on recorded turns, or exports the odds of bets")
+                .args_from_usage(ANALYZE_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .args_from_usage(WORD_ARGS),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Plays many games at once, as a server would")
+                .args_from_usage(SERVE_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .args_from_usage(AI_ARGS)
+                .args_from_usage(RULE_ARGS)
+                .args(&variant_args())
+                .args_from_usage(RECORD_ARGS),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Plays recorded games again, checking they end as they did")
+                .args_from_usage(VERIFY_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .args_from_usage(AI_ARGS)
+                .args(&variant_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Reports the top players and strategy win rates on a leaderboard")
+                .args_from_usage(STATS_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS),
+        )
+        .subcommand(
+            SubCommand::with_name("dict")
+                .about("Reports on a dictionary, for sizing a precompute")
+                .args_from_usage(DICT_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Goes back over games in notation, with what the AI makes of each challenge")
+                .args_from_usage(REPLAY_ARGS)
+                .args_from_usage(CONFIG_ARGS)
+                .args_from_usage(CRASH_ARGS)
+                .args_from_usage(AI_ARGS)
+                .args_from_usage(RULE_ARGS)
+                .args(&variant_args()),
+        )
+}
+
+/// Parses the given command line, filling in whatever it leaves out from the config file it
+/// names, if any.
+pub fn matches_from(args: Vec<String>) -> Result<ArgMatches<'static>, ScrabrudoError> {
+    let matches = app().get_matches_from(args.clone());
+    let config_path = match matches.subcommand() {
+        (_, Some(subcommand)) => subcommand.value_of("config"),
+        _ => None,
+    };
+    let config_path = match config_path {
+        Some(path) => path.to_string(),
+        None => return Ok(matches),
+    };
+    let (_, subcommand) = matches.subcommand();
+    let extra_args = config_args(&fs::read_to_string(&config_path)?, subcommand.unwrap())?;
+    app()
+        .get_matches_from_safe(args.into_iter().chain(extra_args))
+        .map_err(|e| {
+            ScrabrudoError::InvalidOption(format!(
+                "In {}: {}",
+                config_path,
+                e.message.lines().next().unwrap_or("")
+            ))
+        })
+}
+
+/// The arguments a TOML config file gives, written as on the command line, leaving out any the
+/// command line already gives. Each key is the long name of an argument, and may be grouped in
+/// tables, e.g. [rules] or [players], for readability; lists give an argument more than once.
+pub fn config_args(text: &str, given: &ArgMatches) -> Result<Vec<String>, ScrabrudoError> {
+    let table = match text.parse::<toml::Value>() {
+        Ok(toml::Value::Table(table)) => table,
+        Ok(_) => {
+            return Err(ScrabrudoError::Format(
+                "A config file must be a table".into(),
+            ))
+        }
+        Err(e) => {
+            return Err(ScrabrudoError::Format(format!(
+                "Invalid config file: {}",
+                e
+            )))
+        }
+    };
+    let mut args = vec![];
+    push_config_args(&table, given, &mut args)?;
+    Ok(args)
+}
+
+/// Adds the arguments for every key in the given table, and in any tables within it.
+fn push_config_args(
+    table: &toml::value::Table,
+    given: &ArgMatches,
+    args: &mut Vec<String>,
+) -> Result<(), ScrabrudoError> {
+    for (name, value) in table {
+        if let toml::Value::Table(group) = value {
+            push_config_args(group, given, args)?;
+            continue;
+        }
+        if given.occurrences_of(name) > 0 {
+            continue;
+        }
+        let values = match value {
+            toml::Value::Array(values) => values.clone(),
+            value => vec![value.clone()],
+        };
+        for value in values {
+            match value {
+                toml::Value::Boolean(true) => args.push(format!("--{}", name)),
+                toml::Value::Boolean(false) => (),
+                toml::Value::String(s) => args.push(format!("--{}={}", name, s)),
+                toml::Value::Integer(n) => args.push(format!("--{}={}", name, n)),
+                toml::Value::Float(x) => args.push(format!("--{}={}", name, x)),
+                value => {
+                    return Err(ScrabrudoError::InvalidOption(format!(
+                        "Invalid {} in config file: {}",
+                        name, value
+                    )))
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses the value of an option, failing if it isn't understood.
+pub fn parse_option<T>(
+    name: &str,
+    value: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<T, ScrabrudoError> {
+    match parse(value) {
+        Some(parsed) => Ok(parsed),
+        None => Err(ScrabrudoError::InvalidOption(format!(
+            "Invalid {}: {}",
+            name, value
+        ))),
+    }
+}
+
+/// Gets the value of an option that must be given for this subcommand.
+pub fn required_option<'a>(matches: &'a ArgMatches, name: &str) -> Result<&'a str, ScrabrudoError> {
+    match matches.value_of(name) {
+        Some(value) => Ok(value),
+        None => Err(ScrabrudoError::InvalidOption(format!(
+            "--{} is required",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "the command line" {
+        fn parse(args: &[&str]) -> clap::Result<ArgMatches<'static>> {
+            app().get_matches_from_safe(args.iter())
+        }
+
+        it "takes each subcommand's own arguments" {
+            let matches = parse(&["scrabrudo", "play", "--variant=perudo", "--num_players=4"]).unwrap();
+            let (name, play) = matches.subcommand();
+            assert_eq!("play", name);
+            assert_eq!(Some("4"), play.unwrap().value_of("num_players"));
+            assert!(parse(&["scrabrudo", "simulate", "compare", "--strategy_a=greedy"]).is_ok());
+            assert!(parse(&["scrabrudo", "demo", "--seed=2", "--num_players=2"]).is_ok());
+        }
+
+        it "refuses arguments a subcommand doesn't take" {
+            assert!(parse(&["scrabrudo", "precompute", "--num_players=2"]).is_err());
+            assert!(parse(&["scrabrudo", "play", "--num_tiles=5"]).is_err());
+            assert!(parse(&["scrabrudo", "--mode=perudo"]).is_err());
+        }
+
+        it "requires what a subcommand can't do without" {
+            assert!(parse(&["scrabrudo", "precompute", "--num_tiles=5"]).is_err());
+            assert!(parse(&["scrabrudo", "precompute", "-n", "5", "-t", "10", "-d", "d.txt", "-l", "l.sstable"]).is_ok());
+            assert!(parse(&["scrabrudo", "precompute", "estimate", "--word=attack", "--tiles=20"]).is_ok());
+            assert!(parse(&["scrabrudo", "precompute", "estimate", "--tiles=20"]).is_err());
+            assert!(parse(&["scrabrudo", "dict", "stats"]).is_err());
+            assert!(parse(&["scrabrudo", "simulate"]).is_err());
+            assert!(parse(&["scrabrudo", "replay"]).is_err());
+        }
+
+        it "fills in arguments from a config file" {
+            let matches = parse(&["scrabrudo", "play", "--num_players=4"]).unwrap();
+            let play = matches.subcommand_matches("play").unwrap();
+            let text = "variant = \"perudo\"\nbelief = [\"half\", \"1:honesty\"]\n\n[players]\nnum_players = 3\nhuman_index = 0\n\n[rules]\nphrases = true\nexchanges = false\n";
+            assert_eq!(
+                vec!["--belief=half", "--belief=1:honesty", "--human_index=0", "--phrases", "--variant=perudo"],
+                config_args(text, play).unwrap());
+        }
+
+        it "refuses config files it can't read" {
+            let matches = parse(&["scrabrudo", "play"]).unwrap();
+            let play = matches.subcommand_matches("play").unwrap();
+            assert!(config_args("num_players = ", play).is_err());
+            assert!(config_args("when = 1979-05-27T07:32:00Z", play).is_err());
+        }
+    }
+}
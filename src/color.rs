@@ -0,0 +1,108 @@
+/// Colour at the terminal, to make hands and bets easier to scan: vowels and consonants in
+/// different colours, high-scoring tiles in bold, and each seat's bets in a colour of its own.
+/// Colour needs the color feature; without it, asking for colour is an error and nothing is ever
+/// painted.
+use crate::error::*;
+use crate::tile::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref COLOR: Mutex<bool> = Mutex::new(false);
+}
+
+/// The colours bets are shown in, a seat at a time: red, green, yellow, blue, magenta and cyan.
+const SEAT_COLORS: [&str; 6] = ["31", "32", "33", "34", "35", "36"];
+
+/// Tiles scoring at least this many points are shown in bold.
+const HIGH_SCORE: u32 = 4;
+
+/// Tiles scoring at least this many points are underlined as well.
+const TOP_SCORE: u32 = 8;
+
+/// Turns colour on or off.
+#[cfg(feature = "color")]
+pub fn init_color(on: bool) -> Result<(), ScrabrudoError> {
+    let mut color = COLOR.lock().unwrap();
+    *color = on;
+    Ok(())
+}
+
+/// Refuses to turn colour on, lacking the color feature.
+#[cfg(not(feature = "color"))]
+pub fn init_color(on: bool) -> Result<(), ScrabrudoError> {
+    if on {
+        return Err(ScrabrudoError::InvalidOption(
+            "Build with --features color to colour the output".into(),
+        ));
+    }
+    let mut color = COLOR.lock().unwrap();
+    *color = false;
+    Ok(())
+}
+
+/// Whether output is being coloured.
+pub fn color() -> bool {
+    *COLOR.lock().unwrap()
+}
+
+/// Wraps the text in the given ANSI style, if colour is on.
+#[cfg(feature = "color")]
+fn paint(text: &str, style: &str) -> String {
+    if color() {
+        format!("\x1b[{}m{}\x1b[0m", style, text)
+    } else {
+        text.into()
+    }
+}
+
+/// Leaves the text as it is, lacking the color feature.
+#[cfg(not(feature = "color"))]
+fn paint(text: &str, _style: &str) -> String {
+    text.into()
+}
+
+/// The ANSI style a tile is shown in: green for vowels, cyan for consonants and dim for blanks,
+/// in bold if it scores highly and underlined too if it's among the highest scoring.
+pub fn tile_style(tile: &Tile) -> String {
+    let hue = match tile {
+        Tile::Blank => "2",
+        _ if "aeiou".contains(&tile.spelling()[..1]) => "32",
+        _ => "36",
+    };
+    match tile.score() {
+        s if s >= TOP_SCORE => format!("{};1;4", hue),
+        s if s >= HIGH_SCORE => format!("{};1", hue),
+        _ => hue.into(),
+    }
+}
+
+/// Shows the given text, standing for the given tile, in the tile's style.
+pub fn paint_tile(tile: &Tile, text: &str) -> String {
+    paint(text, &tile_style(tile))
+}
+
+/// Shows the given text, about the player in the given seat, in the seat's colour.
+pub fn paint_seat(seat: usize, text: &str) -> String {
+    paint(text, SEAT_COLORS[seat % SEAT_COLORS.len()])
+}
+
+#[cfg(test)]
+speculate! {
+    describe "colour" {
+        it "styles tiles by letter and score" {
+            assert_eq!("32", tile_style(&Tile::A));
+            assert_eq!("36", tile_style(&Tile::T));
+            assert_eq!("36;1", tile_style(&Tile::K));
+            assert_eq!("36;1;4", tile_style(&Tile::Z));
+            assert_eq!("2", tile_style(&Tile::Blank));
+        }
+
+        it "leaves text alone with colour off" {
+            assert_eq!("A1", paint_tile(&Tile::A, "A1"));
+            assert_eq!("Player 1 bets cat", paint_seat(1, "Player 1 bets cat"));
+        }
+    }
+}
@@ -0,0 +1,261 @@
+/// Comparing strategies: playing two ways of choosing against each other over many games, to
+/// tell whether a change to the AI really makes it stronger.
+use crate::belief::*;
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+use crate::learned;
+use crate::mcts;
+use crate::opening::*;
+use crate::tie_break::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::fmt;
+
+/// How many standard deviations the confidence interval spans, for 95% confidence.
+pub const Z_95: f64 = 1.96;
+
+/// A way for an AI player to choose: how far it believes its opponents, whether it searches or
+/// follows a learned policy, and how it breaks ties.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strategy {
+    pub belief: BeliefPolicy,
+    pub mcts: Option<mcts::Budget>,
+    pub learned: Option<String>,
+    pub tie_break: TieBreak,
+}
+
+impl Strategy {
+    /// The usual AI, taking the likeliest outcome.
+    pub fn greedy() -> Self {
+        Self {
+            belief: BeliefPolicy::default(),
+            mcts: None,
+            learned: None,
+            tie_break: TieBreak::default(),
+        }
+    }
+
+    /// Parses a strategy from its command-line form, "greedy" or settings joined by commas, e.g.
+    /// "belief:none", "mcts:iterations:200,belief:honesty", "learned:policy.json" or
+    /// "tie_break:canonical".
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut strategy = Self::greedy();
+        if name == "greedy" {
+            return Some(strategy);
+        }
+        for setting in name.split(',') {
+            let mut split = setting.splitn(2, ':');
+            match (split.next(), split.next()) {
+                (Some("belief"), Some(policy)) => {
+                    strategy.belief = BeliefPolicy::from_name(policy)?;
+                }
+                (Some("mcts"), Some(budget)) => {
+                    strategy.mcts = Some(mcts::Budget::from_name(budget)?);
+                }
+                (Some("learned"), Some(path)) if !path.is_empty() => {
+                    strategy.learned = Some(path.into());
+                }
+                (Some("tie_break"), Some(policy)) => {
+                    strategy.tie_break = TieBreak::from_name(policy)?;
+                }
+                _ => return None,
+            }
+        }
+        Some(strategy)
+    }
+
+    /// Has the given player play by this strategy in games of the given config.
+    pub fn assign(&self, config: &mut GameConfig, player_id: usize) -> Result<(), ScrabrudoError> {
+        let learned = match &self.learned {
+            Some(path) => Some(learned::LearnedPolicy::load(path)?),
+            None => None,
+        };
+        let seat = config.seat_mut(player_id);
+        seat.belief = Some(self.belief.clone());
+        seat.tie_break = Some(self.tie_break.clone());
+        seat.mcts = Some(self.mcts.clone());
+        seat.learned = learned;
+        Ok(())
+    }
+}
+
+/// Whether the given seat plays the first strategy (0) or the second (1) in the given game.
+/// Strategies alternate around the table, swapping seats every game so neither keeps the
+/// advantage of a seat.
+pub fn strategy_index(seat: usize, game: usize) -> usize {
+    (seat + game) % 2
+}
+
+/// The Wilson score interval for the true rate of something seen the given number of times in
+/// the given number of trials, spanning the given number of standard deviations.
+pub fn wilson_interval(successes: usize, trials: usize, z: f64) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+    let denominator = 1.0 + z2 / n;
+    let centre = (p + z2 / (2.0 * n)) / denominator;
+    let half_width = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+    (
+        (centre - half_width).max(0.0),
+        (centre + half_width).min(1.0),
+    )
+}
+
+/// The outcome of playing two strategies against each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// The number of games played.
+    pub games: usize,
+
+    /// The number of those games won by a player of the first strategy.
+    pub wins_a: usize,
+}
+
+impl Comparison {
+    /// How much more often the first strategy won than the second.
+    pub fn difference(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        2.0 * self.wins_a as f64 / self.games as f64 - 1.0
+    }
+
+    /// The 95% confidence interval for the difference.
+    pub fn interval(&self) -> (f64, f64) {
+        let (low, high) = wilson_interval(self.wins_a, self.games, Z_95);
+        (2.0 * low - 1.0, 2.0 * high - 1.0)
+    }
+
+    /// Which strategy, if either, is stronger with 95% confidence.
+    pub fn verdict(&self) -> &'static str {
+        match self.interval() {
+            (low, _) if low > 0.0 => "A is stronger",
+            (_, high) if high < 0.0 => "B is stronger",
+            _ => "no significant difference",
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (low, high) = self.interval();
+        write!(
+            f,
+            "A won {} of {} games, B won {}: win rate difference {:+.3} (95% CI {:+.3} to {:+.3}), {}",
+            self.wins_a,
+            self.games,
+            self.games - self.wins_a,
+            self.difference(),
+            low,
+            high,
+            self.verdict()
+        )
+    }
+}
+
+/// Plays the given number of all-AI games between two strategies, alternating around an even
+/// number of seats and rotating both the seats and the first player every game.
+pub fn compare<G: Game>(
+    num_players: usize,
+    items_per_player: usize,
+    num_games: usize,
+    strategies: &[Strategy; 2],
+    opening: &OpeningPolicy,
+    config: &GameConfig,
+) -> Result<Comparison, ScrabrudoError> {
+    if num_players < 2 || num_players % 2 == 1 {
+        return Err(ScrabrudoError::InvalidOption(format!(
+            "Comparing needs an even number of players, not {}",
+            num_players
+        )));
+    }
+    let mut comparison = Comparison {
+        games: 0,
+        wins_a: 0,
+    };
+    for game in 0..num_games {
+        let mut config = config.clone();
+        for seat in 0..num_players {
+            strategies[strategy_index(seat, game)].assign(&mut config, seat)?;
+        }
+        let finished = G::new(
+            num_players,
+            items_per_player,
+            hashset! {},
+            opening.clone(),
+            config,
+        )
+        .with_first_player(game % num_players)
+        .run()?;
+        comparison.games += 1;
+        if strategy_index(finished.winner().unwrap(), game) == 0 {
+            comparison.wins_a += 1;
+        }
+        info!("After game {} of {}: {}", game + 1, num_games, comparison);
+    }
+    Ok(comparison)
+}
+
+#[cfg(test)]
+speculate! {
+    describe "comparing strategies" {
+        it "parses strategies" {
+            assert_eq!(Some(Strategy::greedy()), Strategy::from_name("greedy"));
+            assert_eq!(
+                Some(Strategy {
+                    belief: BeliefPolicy::Honesty,
+                    mcts: Some(mcts::Budget::Iterations(200)),
+                    learned: None,
+                    tie_break: TieBreak::Random,
+                }),
+                Strategy::from_name("mcts:iterations:200,belief:honesty")
+            );
+            assert_eq!(
+                TieBreak::Canonical,
+                Strategy::from_name("tie_break:canonical").unwrap().tie_break
+            );
+            assert_eq!(
+                Some("policy.json".into()),
+                Strategy::from_name("learned:policy.json").unwrap().learned
+            );
+            assert_eq!(None, Strategy::from_name("belief:sometimes"));
+            assert_eq!(None, Strategy::from_name("mcts"));
+        }
+
+        it "swaps seats every game" {
+            assert_eq!(vec![0, 1, 0, 1], (0..4).map(|seat| strategy_index(seat, 0)).collect::<Vec<usize>>());
+            assert_eq!(vec![1, 0, 1, 0], (0..4).map(|seat| strategy_index(seat, 1)).collect::<Vec<usize>>());
+        }
+
+        it "computes wilson intervals" {
+            let (low, high) = wilson_interval(50, 100, Z_95);
+            assert!((0.4038 - low).abs() < 1e-4);
+            assert!((0.5962 - high).abs() < 1e-4);
+            let (low, high) = wilson_interval(0, 10, Z_95);
+            assert_eq!(0.0, low);
+            assert!((0.2775 - high).abs() < 1e-4);
+            assert_eq!((0.0, 1.0), wilson_interval(0, 0, Z_95));
+        }
+
+        it "reports only significant differences" {
+            assert_eq!("no significant difference", Comparison { games: 100, wins_a: 55 }.verdict());
+            assert_eq!("A is stronger", Comparison { games: 100, wins_a: 65 }.verdict());
+            assert_eq!("B is stronger", Comparison { games: 100, wins_a: 35 }.verdict());
+            assert!((0.3 - Comparison { games: 100, wins_a: 65 }.difference()).abs() < 1e-9);
+        }
+
+        it "plays a short comparison" {
+            let strategies = [Strategy::greedy(), Strategy::greedy()];
+            let comparison = compare::<PerudoGame>(
+                2, 1, 4, &strategies, &OpeningPolicy::Likeliest, &GameConfig::default()).unwrap();
+            assert_eq!(4, comparison.games);
+            assert!(compare::<PerudoGame>(
+                3, 1, 1, &strategies, &OpeningPolicy::Likeliest, &GameConfig::default()).is_err());
+        }
+    }
+}
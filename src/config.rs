@@ -0,0 +1,464 @@
+/// Rule options that vary from table to table.
+use crate::belief::*;
+use crate::dict::*;
+use crate::die::*;
+use crate::error::*;
+use crate::hand::*;
+use crate::input::*;
+use crate::learned::*;
+use crate::mcts::Budget;
+use crate::prob::*;
+use crate::rng::*;
+use crate::tie_break::*;
+use crate::timebank::*;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader};
+
+/// The house rules a game is played under, and how the AI plays it.
+/// The default is the standard game.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// Whether a bet may repeat one already made this round.
+    /// Bets must rise, so for Scrabrudo this forbids anagrams of earlier words.
+    pub no_repeated_words: bool,
+
+    /// How far each Scrabrudo bet must raise the last, to shorten games.
+    pub min_raise: MinRaise,
+
+    /// Whether a Scrabrudo bet may be a phrase of several words, such as "cat+dog".
+    pub phrases: bool,
+
+    /// Whether a player who loses a challenge shows one more of their items to everyone from then
+    /// on, rather than losing it.
+    pub reveal_on_loss: bool,
+
+    /// How many items are dealt face up in the middle of the table each round, counting towards
+    /// every player's bets.
+    pub community: usize,
+
+    /// Whether a constraint card is drawn each round that every Scrabrudo bet must satisfy.
+    pub constraints: bool,
+
+    /// Whether a player may, once a round, exchange some of their hidden items for new ones
+    /// before betting, giving up the right to challenge for the rest of the round.
+    pub exchanges: bool,
+
+    /// Whether neighbours may swap a hidden item each before every round, if both offer one.
+    #[serde(default)]
+    pub trades: bool,
+
+    /// How the player to make the first bet of the game is chosen.
+    pub first_player: FirstPlayer,
+
+    /// Who starts the next round after a challenge.
+    pub round_starter: RoundStarter,
+
+    /// What happens to the seat of a human who quits.
+    pub forfeit: Forfeit,
+
+    /// Which words the AI prefers to bet in Scrabrudo.
+    pub style: WordStyle,
+
+    /// Words no Scrabrudo bet may contain, by house rule.
+    #[serde(default)]
+    pub banned_words: BTreeSet<String>,
+
+    /// The bank of thinking time each human has for the whole game, if they're timed at all.
+    #[serde(default)]
+    pub timebank: Option<Timebank>,
+
+    /// The most rounds the game lasts, if it's capped at all. After the last, whoever has the
+    /// most items still hidden wins, those tied for the most playing on in sudden death.
+    #[serde(default)]
+    pub max_rounds: Option<usize>,
+
+    /// The dictionary Scrabrudo bets are made from, the process's unless the game has its own.
+    /// Never saved, so a loaded game uses whichever is loaded.
+    #[serde(skip)]
+    pub dict: DictHandle,
+
+    /// The lookup of probabilities for Scrabrudo bets, the process's unless the game has its own.
+    #[serde(skip)]
+    pub lookup: LookupHandle,
+
+    /// How each seat given settings of its own is played, by player ID. Seats without take the
+    /// process's defaults.
+    #[serde(default)]
+    pub seats: Seats,
+
+    /// Where the humans' moves come from, the terminal unless a frontend gives the game its own.
+    #[serde(skip)]
+    pub input: InputHandle,
+}
+
+impl GameConfig {
+    /// The settings of the given seat, if it has any of its own.
+    pub fn seat(&self, player_id: usize) -> Option<&SeatConfig> {
+        self.seats.0.get(&player_id)
+    }
+
+    /// The settings of the given seat, to change.
+    pub fn seat_mut(&mut self, player_id: usize) -> &mut SeatConfig {
+        self.seats.0.entry(player_id).or_default()
+    }
+}
+
+/// How one seat's AI plays and where its private messages go, each overriding the process's
+/// default where given.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeatConfig {
+    /// How far the seat takes its opponents' bets at their word.
+    #[serde(default)]
+    pub belief: Option<BeliefPolicy>,
+
+    /// How the seat chooses between moves it rates equally.
+    #[serde(default)]
+    pub tie_break: Option<TieBreak>,
+
+    /// The budget the seat searches within, or Some(None) if it doesn't search whatever the
+    /// default.
+    #[serde(default)]
+    pub mcts: Option<Option<Budget>>,
+
+    /// How many milliseconds the seat has to answer each turn.
+    #[serde(default)]
+    pub blitz: Option<u64>,
+
+    /// The confidence level, in percent, the seat challenges on the pessimistic end of.
+    #[serde(default)]
+    pub caution: Option<u32>,
+
+    /// The policy the seat chooses with rather than by probability alone.
+    #[serde(default)]
+    pub learned: Option<LearnedPolicy>,
+
+    /// The file the seat's private messages are written to.
+    #[serde(default)]
+    pub log: Option<String>,
+}
+
+/// Every seat's own settings, by player ID.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Seats(pub BTreeMap<usize, SeatConfig>);
+
+impl Eq for Seats {}
+
+/// Nothing is hashed, since the settings never change over a game, so never tell positions
+/// apart.
+impl Hash for Seats {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Loads a list of banned words, one to a line, ignoring case and blank lines.
+pub fn load_banned_words(path: &str) -> Result<BTreeSet<String>, ScrabrudoError> {
+    let f = File::open(path)?;
+    let mut banned = BTreeSet::new();
+    for line in BufReader::new(f).lines() {
+        let word = line?.trim().to_lowercase();
+        if !word.is_empty() {
+            banned.insert(word);
+        }
+    }
+    Ok(banned)
+}
+
+/// How far a Scrabrudo bet must raise the one before it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum MinRaise {
+    /// Any higher bet will do.
+    #[default]
+    Any,
+
+    /// The bet must be at least this many letters longer.
+    Letters(usize),
+
+    /// The bet must score at least this many more points.
+    Score(u32),
+}
+
+impl MinRaise {
+    /// Parses a minimum raise from its command-line form, e.g. "letters:2" or "score:5".
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut split = name.split(':');
+        match (split.next(), split.next().map(|k| k.parse::<usize>())) {
+            (Some("any"), None) => Some(MinRaise::Any),
+            (Some("letters"), Some(Ok(k))) => Some(MinRaise::Letters(k)),
+            (Some("score"), Some(Ok(k))) => Some(MinRaise::Score(k as u32)),
+            _ => None,
+        }
+    }
+}
+
+/// How the player to make the first bet of the game is chosen.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum FirstPlayer {
+    /// The first seat always starts.
+    #[default]
+    First,
+
+    /// A seat chosen at random starts.
+    Random,
+
+    /// Everyone rolls a die and the highest roll starts, rolling again to break ties.
+    DiceOff,
+}
+
+impl FirstPlayer {
+    /// Parses a first player policy from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "first" => Some(FirstPlayer::First),
+            "random" => Some(FirstPlayer::Random),
+            "dice_off" => Some(FirstPlayer::DiceOff),
+            _ => None,
+        }
+    }
+
+    /// Chooses the index of the first player from the given number of players.
+    pub fn choose(&self, num_players: usize) -> usize {
+        match self {
+            FirstPlayer::First => 0,
+            FirstPlayer::Random => rng_for("first_player").gen_range(0, num_players),
+            FirstPlayer::DiceOff => {
+                let mut contenders = (0..num_players).collect::<Vec<usize>>();
+                while contenders.len() > 1 {
+                    let rolls = contenders
+                        .iter()
+                        .map(|i| (*i, labelled("first_player", Die::get_random)))
+                        .collect::<Vec<(usize, Die)>>();
+                    info!("Dice-off rolls: {:?}", rolls);
+                    let highest = rolls.iter().map(|r| r.1.int()).max().unwrap();
+                    contenders = rolls
+                        .into_iter()
+                        .filter(|r| r.1.int() == highest)
+                        .map(|r| r.0)
+                        .collect();
+                }
+                info!("Player {} wins the dice-off", contenders[0]);
+                contenders[0]
+            }
+        }
+    }
+}
+
+/// Who starts the next round after a challenge.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum RoundStarter {
+    /// The loser of the challenge, or the next player along if they were knocked out.
+    #[default]
+    Loser,
+
+    /// The winner of the challenge.
+    Winner,
+
+    /// The player to the left of the loser.
+    LeftOfLoser,
+}
+
+impl RoundStarter {
+    /// Parses a round starter policy from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "loser" => Some(RoundStarter::Loser),
+            "winner" => Some(RoundStarter::Winner),
+            "left_of_loser" => Some(RoundStarter::LeftOfLoser),
+            _ => None,
+        }
+    }
+
+    /// The index of the player who starts the next round after a challenge between the given
+    /// players. Indices are as they are now, before any player is knocked out.
+    pub fn next_index(
+        &self,
+        loser_index: usize,
+        winner_index: usize,
+        num_players: usize,
+        loser_eliminated: bool,
+    ) -> usize {
+        match self {
+            RoundStarter::Loser if !loser_eliminated => loser_index,
+            RoundStarter::Loser | RoundStarter::LeftOfLoser => (loser_index + 1) % num_players,
+            RoundStarter::Winner => winner_index,
+        }
+    }
+}
+
+/// What happens to the seat of a human who quits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum Forfeit {
+    /// The AI takes over the seat, keeping the hand.
+    #[default]
+    ToAi,
+
+    /// The player leaves the table and the round is dealt again.
+    Remove,
+}
+
+impl Forfeit {
+    /// Parses a forfeit rule from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ai" => Some(Forfeit::ToAi),
+            "remove" => Some(Forfeit::Remove),
+            _ => None,
+        }
+    }
+}
+
+/// How much less likely than the likeliest bet a common word may be and still be preferred.
+pub const COMMON_WORD_TOLERANCE: f64 = 0.02;
+
+/// Which of several similarly likely words the AI prefers to bet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum WordStyle {
+    /// Whichever is likeliest, however obscure.
+    #[default]
+    Scrabbly,
+
+    /// The most common word of those nearly as likely as the likeliest, so that bets read like
+    /// a human's.
+    Common,
+}
+
+impl WordStyle {
+    /// Parses a word style from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "scrabbly" => Some(WordStyle::Scrabbly),
+            "common" => Some(WordStyle::Common),
+            _ => None,
+        }
+    }
+
+    /// How much less likely than the likeliest an outcome may be and still be chosen.
+    pub fn tolerance(&self) -> f64 {
+        match self {
+            WordStyle::Scrabbly => 0.0,
+            WordStyle::Common => COMMON_WORD_TOLERANCE,
+        }
+    }
+
+    /// Gets the outcomes to choose between from those ranked by ascending probability, given how
+    /// common each is as a word, 0 being the most common.
+    /// Falls back to those tied for the highest probability if none nearly as likely is a known
+    /// word.
+    pub fn best_outcomes<T>(
+        &self,
+        ranked: Vec<(T, f64)>,
+        word_rank: impl Fn(&T) -> Option<usize>,
+    ) -> Vec<T> {
+        match self {
+            WordStyle::Scrabbly => best_outcomes(ranked),
+            WordStyle::Common => {
+                let min_p = match ranked.last() {
+                    Some(o) => o.1 - self.tolerance(),
+                    None => return vec![],
+                };
+                let most_common = ranked
+                    .iter()
+                    .filter(|o| o.1 >= min_p)
+                    .filter_map(|o| word_rank(&o.0))
+                    .min();
+                match most_common {
+                    Some(rank) => ranked
+                        .into_iter()
+                        .filter(|o| o.1 >= min_p && word_rank(&o.0) == Some(rank))
+                        .map(|o| o.0)
+                        .collect(),
+                    None => best_outcomes(ranked),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "minimum raises" {
+        it "parses minimum raises" {
+            assert_eq!(Some(MinRaise::Any), MinRaise::from_name("any"));
+            assert_eq!(Some(MinRaise::Letters(2)), MinRaise::from_name("letters:2"));
+            assert_eq!(Some(MinRaise::Score(5)), MinRaise::from_name("score:5"));
+            assert_eq!(None, MinRaise::from_name("letters"));
+            assert_eq!(None, MinRaise::from_name("points:3"));
+        }
+    }
+
+    describe "first players" {
+        it "parses first player policies" {
+            assert_eq!(Some(FirstPlayer::DiceOff), FirstPlayer::from_name("dice_off"));
+            assert_eq!(None, FirstPlayer::from_name("last"));
+        }
+
+        it "chooses a player at the table" {
+            assert_eq!(0, FirstPlayer::First.choose(4));
+            for _ in 0..20 {
+                assert!(FirstPlayer::Random.choose(4) < 4);
+                assert!(FirstPlayer::DiceOff.choose(4) < 4);
+            }
+        }
+    }
+
+    describe "round starters" {
+        it "parses round starter policies" {
+            assert_eq!(Some(RoundStarter::LeftOfLoser), RoundStarter::from_name("left_of_loser"));
+            assert_eq!(None, RoundStarter::from_name("dealer"));
+        }
+
+        it "starts the next round" {
+            assert_eq!(1, RoundStarter::Loser.next_index(1, 0, 3, false));
+            assert_eq!(2, RoundStarter::Loser.next_index(1, 0, 3, true));
+            assert_eq!(0, RoundStarter::Winner.next_index(1, 0, 3, true));
+            assert_eq!(0, RoundStarter::LeftOfLoser.next_index(2, 1, 3, false));
+        }
+    }
+
+    describe "word styles" {
+        fn ranked() -> Vec<(&'static str, f64)> {
+            rank_outcomes(vec![("cat", 0.5), ("qi", 0.89), ("the", 0.88), ("xu", 0.9)])
+        }
+
+        fn word_rank(word: &&str) -> Option<usize> {
+            match *word {
+                "the" => Some(0),
+                "cat" => Some(1000),
+                _ => None,
+            }
+        }
+
+        it "parses word styles" {
+            assert_eq!(Some(WordStyle::Common), WordStyle::from_name("common"));
+            assert_eq!(None, WordStyle::from_name("obscure"));
+        }
+
+        it "prefers the likeliest word when scrabbly" {
+            assert_eq!(vec!["xu"], WordStyle::Scrabbly.best_outcomes(ranked(), word_rank));
+        }
+
+        it "prefers a nearly as likely common word" {
+            assert_eq!(vec!["the"], WordStyle::Common.best_outcomes(ranked(), word_rank));
+        }
+
+        it "falls back to the likeliest without a nearly as likely common word" {
+            let ranked = rank_outcomes(vec![("cat", 0.5), ("xu", 0.9)]);
+            assert_eq!(vec!["xu"], WordStyle::Common.best_outcomes(ranked, word_rank));
+        }
+    }
+
+    describe "banned words" {
+        it "loads banned words" {
+            std::fs::write("/tmp/banned_words.txt", "Cat\n\n  dog \n").unwrap();
+            assert_eq!(
+                btreeset!{ "cat".into(), "dog".into() },
+                load_banned_words("/tmp/banned_words.txt").unwrap());
+            assert!(load_banned_words("/tmp/no_such_banned_words.txt").is_err());
+        }
+    }
+}
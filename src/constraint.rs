@@ -0,0 +1,106 @@
+/// Constraint cards: a party-game rule where each round a card is drawn that every Scrabrudo bet
+/// in the round must satisfy.
+use crate::rng::*;
+use crate::tile::*;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A rule that every word bet this round must follow.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Constraint {
+    /// Every word must have two vowels in a row.
+    VowelPair,
+
+    /// No word may use the given letter.
+    Without(Tile),
+
+    /// Every word must use the given letter.
+    Containing(Tile),
+
+    /// No letter may appear more than once in the bet.
+    NoRepeats,
+}
+
+impl Constraint {
+    /// The cards in the deck.
+    pub fn deck() -> Vec<Self> {
+        vec![
+            Constraint::VowelPair,
+            Constraint::Without(Tile::S),
+            Constraint::Without(Tile::E),
+            Constraint::Containing(Tile::R),
+            Constraint::Containing(Tile::O),
+            Constraint::NoRepeats,
+        ]
+    }
+
+    /// Draws a card from the deck.
+    pub fn draw() -> Self {
+        let deck = Self::deck();
+        deck[rng_for("constraint").gen_range(0, deck.len())].clone()
+    }
+
+    /// Whether a bet of the given words follows the rule.
+    pub fn allows(&self, words: &[String]) -> bool {
+        match self {
+            Constraint::VowelPair => words.iter().all(|w| has_vowel_pair(w)),
+            Constraint::Without(t) => words.iter().all(|w| !w.contains(t.char())),
+            Constraint::Containing(t) => words.iter().all(|w| w.contains(t.char())),
+            Constraint::NoRepeats => {
+                let mut seen = HashSet::new();
+                words.iter().flat_map(|w| w.chars()).all(|c| seen.insert(c))
+            }
+        }
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiou".contains(c)
+}
+
+fn has_vowel_pair(word: &str) -> bool {
+    let chars = word.chars().collect::<Vec<char>>();
+    chars.windows(2).any(|w| is_vowel(w[0]) && is_vowel(w[1]))
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constraint::VowelPair => write!(f, "words must contain two vowels in a row"),
+            Constraint::Without(t) => write!(f, "no {} allowed", t.char().to_ascii_uppercase()),
+            Constraint::Containing(t) => {
+                write!(f, "words must contain {}", t.char().to_ascii_uppercase())
+            }
+            Constraint::NoRepeats => write!(f, "no letter may be used twice"),
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    fn words(ws: &[&str]) -> Vec<String> {
+        ws.iter().map(|w| w.to_string()).collect()
+    }
+
+    describe "constraints" {
+        it "checks words" {
+            assert!(Constraint::VowelPair.allows(&words(&["boat"])));
+            assert!(!Constraint::VowelPair.allows(&words(&["boat", "cat"])));
+            assert!(Constraint::Without(Tile::S).allows(&words(&["cat"])));
+            assert!(!Constraint::Without(Tile::S).allows(&words(&["cats"])));
+            assert!(Constraint::Containing(Tile::R).allows(&words(&["rat"])));
+            assert!(!Constraint::Containing(Tile::R).allows(&words(&["cat"])));
+            assert!(Constraint::NoRepeats.allows(&words(&["cat", "dog"])));
+            assert!(!Constraint::NoRepeats.allows(&words(&["cat", "tog"])));
+        }
+
+        it "draws from the deck" {
+            assert!(Constraint::deck().contains(&Constraint::draw()));
+        }
+    }
+}
@@ -0,0 +1,170 @@
+/// Crash dumps: what was going on when the program panicked, written to a file that can be
+/// attached to a bug report, with the game as saved so that it can be resumed from where it went
+/// wrong.
+use crate::explain::*;
+use crate::game::*;
+use crate::logging;
+use crate::rng;
+use crate::save::*;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::panic;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref CRASH_DUMP_PATH: Mutex<Option<String>> = Mutex::new(None);
+}
+
+// Every thread tracks its own game, so that the dump is of the game that panicked.
+thread_local! {
+    static TRACKED: RefCell<Tracked> = RefCell::new(Tracked::default());
+}
+
+/// Where a crash is reported unless asked otherwise.
+pub const DEFAULT_CRASH_DUMP_PATH: &str = "scrabrudo_crash.json";
+
+/// How many of the last moves are kept for a dump.
+pub const NUM_LAST_MOVES: usize = 20;
+
+/// What is known on this thread of the game being played.
+#[derive(Debug, Clone, Default)]
+struct Tracked {
+    game: Option<serde_json::Value>,
+    round: usize,
+    seed: Option<u64>,
+    last_moves: VecDeque<String>,
+}
+
+/// What was going on when the program panicked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashDump {
+    /// What the panic said.
+    pub message: String,
+
+    /// Where in the source it panicked, if known.
+    pub location: Option<String>,
+
+    /// The seed randomness was being drawn from, if any.
+    pub seed: Option<u64>,
+
+    /// The game being played, if any.
+    pub game_id: Option<u32>,
+
+    /// The round of the game, counting from 1.
+    pub round: usize,
+
+    /// The last moves made, oldest first.
+    pub last_moves: Vec<String>,
+
+    /// The game as it was before the move being made, as saved, so that it can be resumed.
+    pub game: Option<serde_json::Value>,
+}
+
+/// Writes a crash dump to the given path should the program panic, as well as reporting the
+/// panic as usual.
+pub fn install_panic_hook(crash_dump_path: &str) {
+    *CRASH_DUMP_PATH.lock().unwrap() = Some(crash_dump_path.into());
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => "unknown panic".into(),
+            },
+        };
+        let location = info.location().map(|l| format!("{}:{}", l.file(), l.line()));
+        // The lock may be held by whatever panicked, so don't wait on it.
+        let path = match CRASH_DUMP_PATH.try_lock() {
+            Ok(path) => path.clone(),
+            Err(_) => None,
+        };
+        if let Some(path) = path {
+            let written = File::create(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|f| {
+                    serde_json::to_writer_pretty(f, &crash_dump(message, location))
+                        .map_err(|e| e.to_string())
+                });
+            match written {
+                Ok(()) => eprintln!("Wrote what was going on to {}", path),
+                Err(e) => eprintln!("Couldn't write a crash dump to {}: {}", path, e),
+            }
+        }
+    }));
+}
+
+/// Whether games are being tracked for crash dumps.
+pub fn enabled() -> bool {
+    CRASH_DUMP_PATH.lock().unwrap().is_some()
+}
+
+/// Notes the game on this thread as it is before a move, and the seed it is drawing from.
+pub fn track_game<G: Game>(game: &G) {
+    let saved = serde_json::to_value(GameSave::new(game)).ok();
+    TRACKED.with(|tracked| {
+        let mut tracked = tracked.borrow_mut();
+        tracked.game = saved;
+        tracked.round = game.round();
+        tracked.seed = rng::seed();
+    });
+}
+
+/// Notes a move made in the game on this thread.
+pub fn track_move<G: Game>(player_id: usize, outcome: &TurnOutcome<G::B>) {
+    TRACKED.with(|tracked| {
+        let mut tracked = tracked.borrow_mut();
+        tracked
+            .last_moves
+            .push_back(format!("Player {}: {}", player_id, describe(outcome)));
+        if tracked.last_moves.len() > NUM_LAST_MOVES {
+            tracked.last_moves.pop_front();
+        }
+    });
+}
+
+/// The crash dump for a panic on this thread with the given message and location.
+pub fn crash_dump(message: String, location: Option<String>) -> CrashDump {
+    let context = logging::context();
+    let tracked = TRACKED.with(|tracked| tracked.borrow().clone());
+    CrashDump {
+        message: message,
+        location: location,
+        seed: tracked.seed,
+        game_id: context.game_id,
+        round: tracked.round,
+        last_moves: tracked.last_moves.into_iter().collect(),
+        game: tracked.game,
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "crash dumps" {
+        it "dumps the game being played and the last moves" {
+            let game = PerudoGame::new(
+                2,
+                1,
+                hashset!{},
+                crate::opening::OpeningPolicy::Likeliest,
+                crate::config::GameConfig::default());
+            track_game(&game);
+            for i in 0..(NUM_LAST_MOVES + 1) {
+                track_move::<PerudoGame>(i, &TurnOutcome::Perudo);
+            }
+            let dump = crash_dump("overflow".into(), Some("src/prob.rs:1".into()));
+            assert_eq!("overflow", dump.message);
+            assert_eq!(1, dump.round);
+            assert_eq!(NUM_LAST_MOVES, dump.last_moves.len());
+            assert_eq!("Player 1: perudo!", dump.last_moves[0]);
+            let saved: GameSave<crate::bet::PerudoBet> = serde_json::from_value(dump.game.unwrap()).unwrap();
+            assert_eq!(2, saved.players.len());
+        }
+    }
+}
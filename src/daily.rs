@@ -0,0 +1,219 @@
+/// Daily challenges: the same game for everyone on a given day, with a result to share.
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+use crate::opening::*;
+use crate::rng;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of seats at a daily challenge, the human's being the first.
+pub const DAILY_NUM_PLAYERS: usize = 3;
+
+/// The number of tiles each player starts a daily challenge with.
+pub const DAILY_ITEMS_PER_PLAYER: usize = 5;
+
+/// The ID of the human at a daily challenge.
+const HUMAN_ID: usize = 0;
+
+/// The number of days since 1970-01-01 in UTC, which numbers and seeds the daily challenge.
+pub fn today() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() / 86400,
+        Err(_) => 0,
+    }
+}
+
+/// The calendar date of the given day since 1970-01-01, as (year, month, day).
+pub fn date(day: u64) -> (i64, u32, u32) {
+    // Howard Hinnant's days-to-civil algorithm, counting in eras of 400 years from 0000-03-01.
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day_of_month)
+}
+
+/// How the human got on in a daily challenge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyResult {
+    /// The day of the challenge, counted from 1970-01-01.
+    pub day: u64,
+
+    /// The number of players at the table.
+    pub num_players: usize,
+
+    /// Where the human finished, 1 being the winner.
+    pub place: usize,
+
+    /// The number of rounds the human saw out without being knocked out.
+    pub rounds_survived: usize,
+
+    /// Whether each challenge the human made, in order, was right.
+    pub challenges: Vec<bool>,
+
+    /// Whether the human quit before the end.
+    pub quit: bool,
+}
+
+impl DailyResult {
+    /// The number of challenges the human got right.
+    pub fn challenges_right(&self) -> usize {
+        self.challenges.iter().filter(|c| **c).count()
+    }
+}
+
+/// Shows a place as e.g. "1st" or "3rd".
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+impl fmt::Display for DailyResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = date(self.day);
+        writeln!(f, "Scrabrudo daily {:04}-{:02}-{:02}", year, month, day)?;
+        if self.quit {
+            write!(f, "Quit")?;
+        } else {
+            write!(
+                f,
+                "Finished {} of {}",
+                ordinal(self.place),
+                self.num_players
+            )?;
+        }
+        writeln!(f, ", survived {} rounds", self.rounds_survived)?;
+        write!(
+            f,
+            "Challenges {}/{}",
+            self.challenges_right(),
+            self.challenges.len()
+        )?;
+        if !self.challenges.is_empty() {
+            let squares = self
+                .challenges
+                .iter()
+                .map(|c| if *c { "🟩" } else { "🟥" })
+                .collect::<String>();
+            write!(f, " {}", squares)?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of items the human holds, or None once they have left the table or handed their
+/// seat to the AI.
+fn human_items<G: Game>(game: &G) -> Option<usize> {
+    game.players()
+        .iter()
+        .find(|p| p.id() == HUMAN_ID && p.human())
+        .map(|p| p.num_items())
+}
+
+/// Plays the daily challenge for the given day, seeding all randomness from it so that everyone
+/// is dealt the same game.
+pub fn play_daily<G: Game>(day: u64) -> Result<DailyResult, ScrabrudoError> {
+    rng::init_seed(day);
+    let mut result = DailyResult {
+        day: day,
+        num_players: DAILY_NUM_PLAYERS,
+        place: DAILY_NUM_PLAYERS,
+        rounds_survived: 0,
+        challenges: vec![],
+        quit: false,
+    };
+    let (game, _) = G::new(
+        DAILY_NUM_PLAYERS,
+        DAILY_ITEMS_PER_PLAYER,
+        hashset! {HUMAN_ID},
+        OpeningPolicy::Likeliest,
+        GameConfig::default(),
+    )
+    .run_with_observer(None, |turn| {
+        let human_turn = turn.player_id == HUMAN_ID;
+        let players_before = turn.before.players().len();
+        let items_before = human_items(turn.before);
+        let items_after = human_items(turn.after);
+        match turn.outcome {
+            TurnOutcome::Quit if human_turn => {
+                result.quit = true;
+                result.place = players_before;
+                return false;
+            }
+            TurnOutcome::Perudo | TurnOutcome::Palafico => {
+                if human_turn {
+                    // A right challenge never costs the challenger a tile.
+                    result
+                        .challenges
+                        .push(items_after.is_some() && items_after >= items_before);
+                }
+                if items_after.is_none() {
+                    result.place = players_before;
+                    return false;
+                }
+                result.rounds_survived += 1;
+            }
+            _ => (),
+        }
+        true
+    })?;
+    // Still holding items once the game is over, the human won it.
+    if game.is_over() && human_items(&game).is_some() {
+        result.place = 1;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+speculate! {
+    describe "daily challenges" {
+        it "dates days" {
+            assert_eq!((1970, 1, 1), date(0));
+            assert_eq!((2000, 2, 29), date(11016));
+            assert_eq!((2026, 10, 16), date(20742));
+        }
+
+        it "ranks places" {
+            assert_eq!("1st", ordinal(1));
+            assert_eq!("2nd", ordinal(2));
+            assert_eq!("3rd", ordinal(3));
+            assert_eq!("4th", ordinal(4));
+            assert_eq!("11th", ordinal(11));
+            assert_eq!("22nd", ordinal(22));
+        }
+
+        it "summarises a result" {
+            let result = DailyResult {
+                day: 20742,
+                num_players: 3,
+                place: 2,
+                rounds_survived: 6,
+                challenges: vec![true, false, true],
+                quit: false,
+            };
+            assert_eq!(
+                "Scrabrudo daily 2026-10-16\nFinished 2nd of 3, survived 6 rounds\nChallenges 2/3 🟩🟥🟩",
+                format!("{}", result));
+        }
+    }
+}
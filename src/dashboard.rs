@@ -0,0 +1,321 @@
+/// The spectator dashboard: a page served alongside the games that shows each one as it is
+/// played, from what everyone at the table can see, fed by a stream of events over a WebSocket.
+/// Serving it needs the dashboard feature; without it, asking for one is an error.
+///
+/// So that bots can't act on what a challenge revealed faster than a human could take it in,
+/// challenges can be held back from the stream for a while, and events sent in batches.
+use crate::error::*;
+use crate::explain::*;
+use crate::game::*;
+use crate::logging;
+
+use serde_json::json;
+#[cfg(test)]
+use speculate::speculate;
+#[cfg(feature = "dashboard")]
+use std::collections::HashMap;
+use std::collections::VecDeque;
+#[cfg(feature = "dashboard")]
+use std::io::{BufRead, BufReader, Write};
+use std::net::SocketAddr;
+#[cfg(feature = "dashboard")]
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "dashboard")]
+use std::sync::Mutex;
+#[cfg(feature = "dashboard")]
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "dashboard")]
+use tungstenite::{Message, WebSocket};
+
+/// The page spectators load, which connects back for the events.
+#[cfg(feature = "dashboard")]
+pub const DASHBOARD_PAGE: &str = include_str!("dashboard.html");
+
+static SERVING: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "dashboard")]
+lazy_static! {
+    static ref SPECTATORS: Mutex<Vec<WebSocket<TcpStream>>> = Mutex::new(vec![]);
+
+    // The last event of each game still being played, to catch new spectators up with.
+    static ref LATEST: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+
+    static ref SCHEDULE: Mutex<Schedule> = Mutex::new(Schedule::new(
+        Duration::from_millis(0),
+        Duration::from_millis(0)
+    ));
+}
+
+/// How often events waiting to be sent are checked for, sending in batches or not.
+pub const MIN_BATCH: Duration = Duration::from_millis(10);
+
+/// When each event is to be sent: challenges are held back for a while, and every event after
+/// one waits for it, so that the next round gives nothing away before the challenge is shown.
+#[derive(Debug)]
+pub struct Schedule {
+    /// How long each challenge is held back.
+    pub reveal_delay: Duration,
+
+    /// How long to gather events for before sending them together, if at all.
+    pub batch: Duration,
+
+    /// The events waiting to be sent, in order, with when each may be.
+    pending: VecDeque<(Instant, serde_json::Value)>,
+}
+
+impl Schedule {
+    /// A schedule holding challenges back for the given time and sending in the given batches.
+    pub fn new(reveal_delay: Duration, batch: Duration) -> Self {
+        Schedule {
+            reveal_delay: reveal_delay,
+            batch: batch,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Whether events are sent as soon as they happen.
+    pub fn immediate(&self) -> bool {
+        self.reveal_delay == Duration::from_millis(0) && self.batch == Duration::from_millis(0)
+    }
+
+    /// Queues the event, as of the given time.
+    pub fn push(&mut self, event: &serde_json::Value, now: Instant) {
+        let mut due = now;
+        if event["challenge"] == true {
+            due += self.reveal_delay;
+        }
+        if let Some((last_due, _)) = self.pending.back() {
+            due = due.max(*last_due);
+        }
+        self.pending.push_back((due, event.clone()));
+    }
+
+    /// Takes the events that may be sent as of the given time, in order.
+    pub fn take_due(&mut self, now: Instant) -> Vec<serde_json::Value> {
+        let mut due = vec![];
+        while self.pending.front().is_some_and(|(at, _)| *at <= now) {
+            due.extend(self.pending.pop_front().map(|(_, event)| event));
+        }
+        due
+    }
+}
+
+/// The event for a turn as it was played, holding only what everyone at the table can see: the
+/// move, the items each player has left and has shown, who is out or has won, and what the turn
+/// changed, for frontends that keep the table up to date a change at a time.
+pub fn turn_event<G: Game>(turn: &PlayedTurn<G>) -> serde_json::Value {
+    let game = turn.after;
+    let players = game
+        .players()
+        .iter()
+        .map(|p| {
+            json!({
+                "id": p.id(),
+                "num_items": p.num_items(),
+                "revealed": p.items()[..p.hand().revealed].to_vec(),
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+    let challenge = matches!(turn.outcome, TurnOutcome::Perudo | TurnOutcome::Palafico);
+    json!({
+        "game": logging::context().game_id.map(|id| format!("{:08x}", id)),
+        "round": turn.round,
+        "player_id": turn.player_id,
+        "move": describe(turn.outcome),
+        "challenge": challenge,
+        "players": players,
+        "community": game.community(),
+        "eliminated": game.eliminated(),
+        "winner": game.winner(),
+        "changes": turn.before.state().diff(&game.state()).to_json(),
+    })
+}
+
+/// Whether a dashboard is being served.
+pub fn enabled() -> bool {
+    SERVING.load(Ordering::Relaxed)
+}
+
+/// Sends the turn to every spectator, if a dashboard is being served.
+pub fn track_turn<G: Game>(turn: &PlayedTurn<G>) {
+    if enabled() {
+        broadcast(&turn_event(turn));
+    }
+}
+
+/// Sends the event to every spectator, when the schedule says to.
+#[cfg(feature = "dashboard")]
+pub fn broadcast(event: &serde_json::Value) {
+    let mut schedule = SCHEDULE.lock().unwrap();
+    if schedule.immediate() {
+        send(event);
+    } else {
+        schedule.push(event, Instant::now());
+    }
+}
+
+/// Sends the event to every spectator now, letting go of those who have left.
+#[cfg(feature = "dashboard")]
+fn send(event: &serde_json::Value) {
+    let text = event.to_string();
+    if let Some(game) = event["game"].as_str() {
+        let mut latest = LATEST.lock().unwrap();
+        if event["winner"].is_null() {
+            latest.insert(game.to_string(), text.clone());
+        } else {
+            latest.remove(game);
+        }
+    }
+    SPECTATORS
+        .lock()
+        .unwrap()
+        .retain_mut(|spectator| spectator.write_message(Message::Text(text.clone())).is_ok());
+}
+
+/// Sends nothing, lacking the dashboard feature.
+#[cfg(not(feature = "dashboard"))]
+pub fn broadcast(_event: &serde_json::Value) {}
+
+/// Serves the dashboard at / on the given address, e.g. 127.0.0.1:8080, and its events at
+/// /events as the given schedule says, from threads of its own, returning the address bound.
+#[cfg(feature = "dashboard")]
+pub fn serve_dashboard(addr: &str, schedule: Schedule) -> Result<SocketAddr, ScrabrudoError> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    info!("Serving the dashboard at http://{}/", bound);
+    let batch = schedule.batch.max(MIN_BATCH);
+    if !schedule.immediate() {
+        thread::spawn(move || loop {
+            thread::sleep(batch);
+            let due = SCHEDULE.lock().unwrap().take_due(Instant::now());
+            for event in due {
+                send(&event);
+            }
+        });
+    }
+    *SCHEDULE.lock().unwrap() = schedule;
+    SERVING.store(true, Ordering::Relaxed);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if let Err(e) = stream.map_err(|e| e.to_string()).and_then(respond) {
+                warn!("Couldn't serve the dashboard: {}", e);
+            }
+        }
+    });
+    Ok(bound)
+}
+
+/// Refuses to serve a dashboard, lacking the dashboard feature.
+#[cfg(not(feature = "dashboard"))]
+pub fn serve_dashboard(_addr: &str, _schedule: Schedule) -> Result<SocketAddr, ScrabrudoError> {
+    Err(ScrabrudoError::InvalidOption(
+        "Build with --features dashboard to serve a dashboard".into(),
+    ))
+}
+
+/// Answers a request for the page, or takes on a spectator asking for the events.
+#[cfg(feature = "dashboard")]
+fn respond(mut stream: TcpStream) -> Result<(), String> {
+    // Only look at the request line, leaving the handshake to be read in full.
+    let mut start = [0; 12];
+    let peeked = stream.peek(&mut start).map_err(|e| e.to_string())?;
+    if start[..peeked].starts_with(b"GET /events ") {
+        let mut spectator = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+        for event in LATEST.lock().unwrap().values() {
+            spectator
+                .write_message(Message::Text(event.clone()))
+                .map_err(|e| e.to_string())?;
+        }
+        SPECTATORS.lock().unwrap().push(spectator);
+        return Ok(());
+    }
+    let mut request_line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut request_line)
+        .map_err(|e| e.to_string())?;
+    let (status, content_type, body) = if request_line.starts_with("GET / ") {
+        ("200 OK", "text/html; charset=utf-8", DASHBOARD_PAGE)
+    } else {
+        ("404 Not Found", "text/plain", "Not found\n")
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::config::GameConfig;
+    use crate::opening::OpeningPolicy;
+
+    describe "the dashboard" {
+        it "shows spectators only what the table sees" {
+            let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, GameConfig::default());
+            let bet = crate::bet::PerudoBet { value: crate::die::Die::Three, quantity: 1 };
+            let outcome = TurnOutcome::Bet(bet);
+            let next = game.apply(outcome.clone()).unwrap();
+            let event = turn_event(&PlayedTurn {
+                round: 1,
+                player_id: 0,
+                before: &game,
+                outcome: &outcome,
+                after: &next,
+            });
+            assert_eq!("1 Threes", event["move"]);
+            assert_eq!(false, event["challenge"]);
+            assert_eq!(json!({ "id": 1, "num_items": 2, "revealed": [] }), event["players"][1]);
+            assert!(event["winner"].is_null());
+            assert_eq!(json!([0, 1]), event["changes"]["index_advanced"]);
+        }
+
+        it "holds back challenges and what follows them" {
+            let mut schedule = Schedule::new(Duration::from_secs(2), Duration::from_millis(0));
+            let now = Instant::now();
+            schedule.push(&json!({ "move": "2 Threes", "challenge": false }), now);
+            schedule.push(&json!({ "move": "perudo!", "challenge": true }), now);
+            schedule.push(&json!({ "move": "1 Fours", "challenge": false }), now);
+            assert_eq!(1, schedule.take_due(now).len());
+            assert!(schedule.take_due(now + Duration::from_secs(1)).is_empty());
+            let due = schedule.take_due(now + Duration::from_secs(2));
+            assert_eq!(2, due.len());
+            assert_eq!("perudo!", due[0]["move"]);
+        }
+
+        #[cfg(feature = "dashboard")]
+        it "serves the page and streams events" {
+            use std::io::Read;
+
+            let addr = serve_dashboard("127.0.0.1:0", Schedule::new(Duration::from_millis(0), Duration::from_millis(0))).unwrap();
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.contains("<title>Scrabrudo</title>"));
+
+            let stream = TcpStream::connect(addr).unwrap();
+            let (mut spectator, _) =
+                tungstenite::client(format!("ws://{}/events", addr), stream).unwrap();
+            // The spectator is only taken on once the handshake is over, so keep sending, and
+            // games played by other tests may be streamed meanwhile.
+            let event = json!({ "game": null, "move": "perudo!", "winner": null });
+            let sent = event.to_string();
+            spectator.get_mut().set_nonblocking(true).unwrap();
+            loop {
+                broadcast(&event);
+                match spectator.read_message() {
+                    Ok(Message::Text(text)) if text == sent => break,
+                    _ => thread::sleep(Duration::from_millis(10)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/// Deductions: what a human can work out for themselves about the other hands from the bets made
+/// this round, what is face up, and how well each player's bets have held up when challenged.
+use crate::belief::*;
+use crate::bet::*;
+use crate::game::*;
+use crate::multiset::*;
+
+#[cfg(test)]
+use speculate::speculate;
+
+/// The deductions the given player can make from the game as they see it, holding the given
+/// items, most telling first.
+pub fn deductions<B: Bet>(state: &GameState<B>, player_id: usize, hand: &[B::V]) -> Vec<String> {
+    let mut deductions = vec![];
+
+    // Anything that can be seen already making the standing bet means it can't be lost.
+    if let Some(bet) = state.standing_bet() {
+        let mut seen = hand.to_vec();
+        seen.extend(state.known_items());
+        if bet.is_correct(&seen, false) {
+            deductions.push(format!(
+                "What you can see already makes {}, so challenging it would lose",
+                bet
+            ));
+        }
+    }
+
+    let mut opponent_ids = state
+        .history
+        .keys()
+        .filter(|id| **id != player_id)
+        .cloned()
+        .collect::<Vec<usize>>();
+    opponent_ids.sort();
+    for opponent_id in opponent_ids {
+        // Each bet counts once towards an item however many of it the bet claims.
+        let times_claimed = state.history[&opponent_id]
+            .iter()
+            .flat_map(|bet| Multiset::of(&bet.claimed_items()).distinct())
+            .collect::<Multiset<B::V>>();
+        let trusted = honesty_of(&state.honesty, opponent_id) >= 0.5;
+        for (item, n) in times_claimed.iter().filter(|(_, n)| *n > 1) {
+            deductions.push(format!(
+                "Player {} has bet on {:?} {}; at least one {:?} is {} in their hand",
+                opponent_id,
+                item,
+                times(n),
+                item,
+                if trusted { "likely" } else { "possible" }
+            ));
+        }
+        let (num_right, num_revealed) = honesty_record(&state.honesty, opponent_id);
+        if num_revealed > 0 {
+            deductions.push(format!(
+                "Player {}'s bets have held up {} of {} times when challenged",
+                opponent_id, num_right, num_revealed
+            ));
+        }
+    }
+    deductions
+}
+
+/// How many times something has happened, in words.
+fn times(n: usize) -> String {
+    match n {
+        1 => "once".into(),
+        2 => "twice".into(),
+        n => format!("{} times", n),
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::*;
+    use crate::testing::*;
+
+    describe "deductions" {
+        fn state(history: std::collections::HashMap<usize, Vec<PerudoBet>>) -> GameState<PerudoBet> {
+            GameState {
+                history: history,
+                revealed: vec![vec![], vec![Die::Two]],
+                ..game_state(vec![3, 3])
+            }
+        }
+
+        it "notices bets on the same item again" {
+            let deductions = deductions(
+                &state(hashmap!{ 0 => vec![perudo_bet(1, Die::Five)], 1 => vec![perudo_bet(2, Die::Five), perudo_bet(3, Die::Five)] }),
+                0,
+                &[Die::Three]);
+            assert_eq!(
+                vec!["Player 1 has bet on Five twice; at least one Five is likely in their hand".to_string()],
+                deductions);
+        }
+
+        it "notes how often bets have held up" {
+            let deductions = deductions(
+                &GameState {
+                    honesty: hashmap!{ 1 => (0, 2) },
+                    ..state(hashmap!{ 1 => vec![perudo_bet(2, Die::Five), perudo_bet(3, Die::Five)] })
+                },
+                0,
+                &[Die::Three]);
+            assert_eq!(
+                vec![
+                    "Player 1 has bet on Five twice; at least one Five is possible in their hand".to_string(),
+                    "Player 1's bets have held up 0 of 2 times when challenged".to_string(),
+                ],
+                deductions);
+        }
+
+        it "notices when what can be seen makes the standing bet" {
+            let deductions = deductions(
+                &state(hashmap!{ 1 => vec![perudo_bet(2, Die::Two)] }),
+                0,
+                &[Die::One, Die::Four]);
+            assert_eq!(
+                vec!["What you can see already makes 2 Twos, so challenging it would lose".to_string()],
+                deductions);
+        }
+    }
+}
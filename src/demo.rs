@@ -0,0 +1,146 @@
+/// Demo games: a short seeded all-AI game played at the terminal, each move followed by why the
+/// AI made it, read from the same explanations --coach prints. A demo plays the same way every
+/// time, so it serves both as a tutorial and as a check that a whole game still runs end to end.
+use crate::any_raise::*;
+use crate::config::*;
+use crate::error::*;
+use crate::explain::*;
+use crate::game::*;
+use crate::lang::*;
+use crate::opening::*;
+use crate::rng;
+
+#[cfg(test)]
+use speculate::speculate;
+
+/// The lines of commentary explaining the given decision.
+pub fn commentary(decision: &ExplainedDecision) -> Vec<String> {
+    let mut lines = vec![Msg::DemoChose {
+        player_id: decision.player_id,
+        chosen: decision.chosen.clone(),
+        method: decision.method.clone(),
+    }
+    .to_string()];
+    if !decision.candidates.is_empty() {
+        let weighed = decision
+            .candidates
+            .iter()
+            .map(|(candidate, p)| format!("{} at {:.0}%", candidate, 100.0 * p))
+            .collect::<Vec<String>>();
+        lines.push(Msg::DemoWeighed(weighed.join(", ")).to_string());
+    }
+    if !decision.pruned.is_empty() {
+        lines.push(Msg::DemoPruned(decision.pruned.join("; ")).to_string());
+    }
+    if let Some(p) = decision.any_raise {
+        let any_raise = Msg::DemoAnyRaise {
+            chance: p,
+            forced: is_forced(p),
+        };
+        lines.push(any_raise.to_string());
+    }
+    lines
+}
+
+/// Plays an all-AI game from the given seed, showing why each move was made as it goes, and
+/// returns the commentary. Fails should any move go unexplained. The game draws from a generator
+/// of its own, as a golden game does.
+pub fn play_demo<G: Game>(
+    seed: u64,
+    num_players: usize,
+    items_per_player: usize,
+    opening: &OpeningPolicy,
+    config: &GameConfig,
+) -> Result<Vec<String>, ScrabrudoError> {
+    let intro = Msg::DemoIntro {
+        num_players: num_players,
+        items_per_player: items_per_player,
+        seed: seed,
+    }
+    .to_string();
+    info!("{}", intro);
+    let mut lines = vec![intro];
+    let mut unexplained = None;
+    init_collection();
+    let played = rng::with_seed(seed, || {
+        G::new(
+            num_players,
+            items_per_player,
+            hashset! {},
+            opening.clone(),
+            config.clone(),
+        )
+        .run_with_observer(None, |turn| {
+            let decisions = take_collected();
+            if decisions.is_empty() {
+                unexplained = Some((turn.round, turn.player_id));
+                return false;
+            }
+            for line in decisions.iter().flat_map(commentary) {
+                info!("{}", line);
+                lines.push(line);
+            }
+            true
+        })
+    });
+    // Left on, the collection would keep every later move on this thread.
+    take_collected();
+    let (game, _) = played?;
+    if let Some((round, player_id)) = unexplained {
+        return Err(ScrabrudoError::ScenarioFailed(format!(
+            "Player {}'s move in round {} went unexplained",
+            player_id, round
+        )));
+    }
+    match game.winner() {
+        Some(winner) => {
+            lines.push(Msg::Wins(winner).to_string());
+            Ok(lines)
+        }
+        None => Err(ScrabrudoError::ScenarioFailed(
+            "The demo game ended without a winner".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "demo games" {
+        it "explains every move of a seeded game" {
+            let lines = play_demo::<PerudoGame>(1, 2, 2, &OpeningPolicy::Likeliest, &GameConfig::default()).unwrap();
+            assert!(lines.len() > 2);
+            assert!(lines.iter().any(|l| l.contains("Player 0 chose")));
+            assert!(lines.iter().any(|l| l.contains("Player 1 chose")));
+            assert!(lines.last().unwrap().ends_with("wins!"));
+            assert_eq!(
+                lines,
+                play_demo::<PerudoGame>(1, 2, 2, &OpeningPolicy::Likeliest, &GameConfig::default()).unwrap());
+        }
+
+        it "explains a decision in full" {
+            let decision = ExplainedDecision {
+                game_id: None,
+                round: 1,
+                player_id: 1,
+                method: "greedy".into(),
+                chosen: "Perudo".into(),
+                candidates: vec![("Perudo".into(), 0.75), ("3 Fours".into(), 0.5)],
+                belief: crate::belief::BeliefPolicy::default(),
+                pruned: vec!["raising, as no raise is likely to be right".into()],
+                any_raise: Some(0.0),
+            };
+            assert_eq!(
+                vec![
+                    "  Player 1 chose Perudo, as the move likeliest to come off",
+                    "  It weighed up Perudo at 75%, 3 Fours at 50%",
+                    "  It ruled out raising, as no raise is likely to be right",
+                    "  Any raise had only a 0% chance of being right, so it had to challenge",
+                ],
+                commentary(&decision));
+        }
+    }
+}
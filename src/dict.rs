@@ -1,86 +1,879 @@
+use crate::config::*;
+use crate::error::*;
+use crate::metrics;
+use crate::multiset::*;
+#[cfg(test)]
+use crate::testing;
+use crate::tile::*;
+
+use serde::{Deserialize, Serialize};
+use speculate::speculate;
 use sstable::{Options, SSIterator, Table};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{BufRead, BufReader};
+use std::ops::Range;
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::Arc;
+use std::sync::{Mutex, RwLock};
 
 type Dictionary = HashSet<String>;
 
+/// Words keyed by their sorted letters, so that all anagrams share a key.
+type AnagramIndex = HashMap<String, Vec<String>>;
+
 lazy_static! {
-    static ref DICT: Mutex<Option<Dictionary>> = Mutex::new(None);
-    static ref LOOKUP: Mutex<Option<String>> = Mutex::new(None);
+    static ref DICT: RwLock<Option<Arc<Dict>>> = RwLock::new(None);
+    static ref LOOKUP: RwLock<Option<Arc<Lookup>>> = RwLock::new(None);
+    static ref FREQUENCIES: Mutex<Option<HashMap<String, usize>>> = Mutex::new(None);
+    static ref DICT_PROFILE: Mutex<DictProfile> = Mutex::new(DictProfile::default());
 }
 
-pub fn init_dict(dict_path: &str) {
-    let mut dict = DICT.lock().unwrap();
-    *dict = Some(load_dict(dict_path));
+/// The most common words kept for younger players.
+pub const KIDS_MAX_RANK: usize = 2000;
+
+/// The longest words kept for younger players.
+pub const KIDS_MAX_LENGTH: usize = 5;
+
+/// The most common words kept for casual games.
+pub const COMMON_MAX_RANK: usize = 10000;
+
+/// The shortest and longest words allowed in tournament play, as on a Scrabble board.
+pub const TOURNAMENT_LENGTHS: (usize, usize) = (2, 15);
+
+/// Which of the dictionary's words are in play, by how hard a game is wanted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DictProfile {
+    /// Every word in the dictionary.
+    #[default]
+    Full,
+
+    /// Short, common words only.
+    Kids,
+
+    /// Common words of any length.
+    Common,
+
+    /// Words that would fit on a Scrabble board, however obscure.
+    Tournament,
 }
 
-pub fn init_lookup(lookup_path: &str) {
-    let mut lookup = LOOKUP.lock().unwrap();
-    *lookup = Some(lookup_path.into());
+impl DictProfile {
+    /// Parses a profile from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(DictProfile::Full),
+            "kids" => Some(DictProfile::Kids),
+            "common" => Some(DictProfile::Common),
+            "tournament" => Some(DictProfile::Tournament),
+            _ => None,
+        }
+    }
+
+    /// Whether the profile keeps words by how common they are, needing word frequencies.
+    pub fn needs_frequencies(&self) -> bool {
+        match self {
+            DictProfile::Kids | DictProfile::Common => true,
+            DictProfile::Full | DictProfile::Tournament => false,
+        }
+    }
+
+    /// Whether the given word is in play, given how common it is, 0 being the most common.
+    pub fn keeps(&self, word: &str, rank: Option<usize>) -> bool {
+        match self {
+            DictProfile::Full => true,
+            DictProfile::Kids => {
+                word.len() <= KIDS_MAX_LENGTH && rank.is_some_and(|r| r < KIDS_MAX_RANK)
+            }
+            DictProfile::Common => rank.is_some_and(|r| r < COMMON_MAX_RANK),
+            DictProfile::Tournament => {
+                word.len() >= TOURNAMENT_LENGTHS.0 && word.len() <= TOURNAMENT_LENGTHS.1
+            }
+        }
+    }
 }
 
-pub fn dict() -> Dictionary {
-    DICT.lock().unwrap().clone().unwrap()
+impl fmt::Display for DictProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            DictProfile::Full => "full",
+            DictProfile::Kids => "kids",
+            DictProfile::Common => "common",
+            DictProfile::Tournament => "tournament",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The key under which a lookup records what it holds, which sorts before every word.
+pub const LOOKUP_META_KEY: &str = "!meta";
+
+/// What a lookup holds, stored as JSON under its own key.
+/// Lookups written before this was recorded have none, and hold only at-least probabilities.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LookupMeta {
+    /// Whether each row also holds the probabilities of finding exactly its letters, for
+    /// Palafico.
+    #[serde(default)]
+    pub exact: bool,
+
+    /// The fingerprint of the tile set the probabilities were computed with.
+    /// Lookups without one were computed with the standard tile set.
+    #[serde(default)]
+    pub tile_set_hash: Option<u64>,
+
+    /// The profile the dictionary was filtered by when the lookup was built.
+    /// Lookups without one were built from the full dictionary.
+    #[serde(default)]
+    pub dict_profile: DictProfile,
+
+    /// How many Monte Carlo trials each probability was estimated from, so that consumers can
+    /// tell how far to trust it. Lookups without one didn't record it.
+    #[serde(default)]
+    pub num_trials: Option<u32>,
+}
+
+/// A row of a lookup holding exact probabilities, each indexed by the number of tiles searched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LookupRow {
+    /// The probabilities of finding at least these letters.
+    pub probs: Vec<f64>,
+
+    /// The probabilities of finding exactly these letters, with blanks making up the rest.
+    pub exact_probs: Vec<f64>,
+}
+
+/// Filters every dictionary loaded from now on by the given profile.
+/// Profiles that keep words by how common they are need word frequencies loaded first.
+pub fn init_dict_profile(profile: DictProfile) {
+    *DICT_PROFILE.lock().unwrap() = profile;
+}
+
+/// The profile the dictionary is filtered by.
+pub fn dict_profile() -> DictProfile {
+    DICT_PROFILE.lock().unwrap().clone()
+}
+
+/// A dictionary and the indices built from it. Everything reading it shares it, so that one
+/// swapped in for another stays whole for as long as anything still holds it.
+#[derive(Debug)]
+pub struct Dict {
+    words: Dictionary,
+    words_by_length: Arc<Vec<String>>,
+    anagrams: Arc<AnagramIndex>,
+}
+
+impl Dict {
+    /// Loads the dictionary at the given path, keeping only the words in play under the given
+    /// profile.
+    pub fn load(dict_path: &str, profile: &DictProfile) -> Result<Self, ScrabrudoError> {
+        let words = filter_dict(load_dict(dict_path)?, profile)?;
+        Ok(Self {
+            words_by_length: Arc::new(sort_by_length(&words)),
+            anagrams: Arc::new(index_anagrams(&words)),
+            words: words,
+        })
+    }
+
+    /// Every word in the dictionary.
+    pub fn words(&self) -> &Dictionary {
+        &self.words
+    }
+
+    pub fn has_word(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    /// All the words, shortest first, shared so they can be streamed without copying.
+    pub fn words_by_length(&self) -> Arc<Vec<String>> {
+        self.words_by_length.clone()
+    }
+
+    /// All the words grouped by their sorted letters.
+    pub fn anagrams(&self) -> Arc<AnagramIndex> {
+        self.anagrams.clone()
+    }
+
+    /// All the words starting with the given letters, shortest first.
+    pub fn words_starting_with(&self, prefix: &str) -> Vec<String> {
+        self.words_by_length
+            .iter()
+            .filter(|word| word.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+
+    /// All the words that can be spelled from the given letters plus up to the given number of
+    /// wildcards, shortest first.
+    pub fn formable_words(&self, letters: &str, num_wildcards: usize) -> Vec<String> {
+        let available = letters.chars().collect::<Multiset<char>>();
+        let max_length = letters.len() + num_wildcards;
+        let mut words = self
+            .anagrams
+            .iter()
+            .filter(|(key, _)| key.len() <= max_length)
+            .filter(|(key, _)| {
+                let needed = key.chars().collect::<Multiset<char>>();
+                needed.difference(&available).len() <= num_wildcards
+            })
+            .flat_map(|(_, words)| words.iter().cloned())
+            .collect::<Vec<String>>();
+        words.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+        words
+    }
+}
+
+/// The dictionary a game is played with. Games given the same one share it, and one given none
+/// follows whichever the process has loaded, as the command-line binaries do. Games sharing a
+/// handle also share any dictionary it's asked to switch to.
+#[derive(Debug, Clone, Default)]
+pub struct DictHandle {
+    dict: Option<Arc<Dict>>,
+    reload: Arc<Mutex<Option<Arc<Dict>>>>,
+}
+
+impl DictHandle {
+    pub fn new(dict: Dict) -> Self {
+        Self {
+            dict: Some(Arc::new(dict)),
+            reload: Arc::default(),
+        }
+    }
+
+    /// Has every game with this handle switch to the given dictionary from its next round.
+    pub fn reload(&self, dict: Dict) {
+        *self.reload.lock().unwrap() = Some(Arc::new(dict));
+    }
+
+    /// A handle to the dictionary this one was last asked to switch to, sharing its switches, if
+    /// it isn't already that dictionary's.
+    pub fn reloaded(&self) -> Option<Self> {
+        match self.reload.lock().unwrap().as_ref() {
+            Some(dict) if !matches!(&self.dict, Some(d) if Arc::ptr_eq(d, dict)) => Some(Self {
+                dict: Some(dict.clone()),
+                reload: self.reload.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The dictionary to read, for as long as it is needed.
+    pub fn get(&self) -> Arc<Dict> {
+        match &self.dict {
+            Some(dict) => dict.clone(),
+            None => current_dict(),
+        }
+    }
+}
+
+/// Handles are the same if they share a dictionary, or both follow the process's.
+impl PartialEq for DictHandle {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.dict, &other.dict) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for DictHandle {}
+
+/// Nothing is hashed, since the words would take too long and would only ever tell apart
+/// handles that are rarely both in play.
+impl Hash for DictHandle {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// A lookup of precomputed probabilities and what it holds.
+#[derive(Debug)]
+pub struct Lookup {
+    path: String,
+    meta: LookupMeta,
+    bounds: Mutex<Option<Arc<Vec<Vec<f64>>>>>,
+}
+
+/// The lookup a game is played with, shared as for a DictHandle, and following the process's if
+/// none is given.
+#[derive(Debug, Clone, Default)]
+pub struct LookupHandle {
+    lookup: Option<Arc<Lookup>>,
+    reload: Arc<Mutex<Option<Arc<Lookup>>>>,
+}
+
+impl LookupHandle {
+    pub fn new(lookup: Lookup) -> Self {
+        Self {
+            lookup: Some(Arc::new(lookup)),
+            reload: Arc::default(),
+        }
+    }
+
+    /// Has every game with this handle switch to the given lookup from its next round.
+    pub fn reload(&self, lookup: Lookup) {
+        *self.reload.lock().unwrap() = Some(Arc::new(lookup));
+    }
+
+    /// A handle to the lookup this one was last asked to switch to, sharing its switches, if it
+    /// isn't already that lookup's.
+    pub fn reloaded(&self) -> Option<Self> {
+        match self.reload.lock().unwrap().as_ref() {
+            Some(lookup) if !matches!(&self.lookup, Some(l) if Arc::ptr_eq(l, lookup)) => {
+                Some(Self {
+                    lookup: Some(lookup.clone()),
+                    reload: self.reload.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The lookup to read, for as long as it is needed.
+    pub fn get(&self) -> Arc<Lookup> {
+        match &self.lookup {
+            Some(lookup) => lookup.clone(),
+            None => current_lookup(),
+        }
+    }
+}
+
+impl PartialEq for LookupHandle {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.lookup, &other.lookup) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LookupHandle {}
+
+impl Hash for LookupHandle {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl Lookup {
+    /// Opens the lookup at the given path, failing if it was computed for another tile set.
+    pub fn open(lookup_path: &str) -> Result<Self, ScrabrudoError> {
+        // The table is opened afresh for every read, so check up front that there is one to open.
+        File::open(lookup_path)?;
+        let table = Table::new_from_file(Options::default(), Path::new(lookup_path))
+            .map_err(|e| ScrabrudoError::Format(format!("{:?}", e)))?;
+        let meta: LookupMeta = match table.get(LOOKUP_META_KEY.as_bytes()) {
+            Ok(Some(encoded_meta)) => serde_json::from_slice(&encoded_meta)?,
+            Ok(None) => LookupMeta::default(),
+            Err(e) => return Err(ScrabrudoError::Format(format!("{:?}", e))),
+        };
+        // Probabilities for one tile set are meaningless for another.
+        let lookup_hash = meta
+            .tile_set_hash
+            .unwrap_or_else(|| TileSet::default().hash());
+        if lookup_hash != tile_set().hash() {
+            return Err(ScrabrudoError::InvalidOption(format!(
+                "Lookup {} was computed for a different tile set",
+                lookup_path
+            )));
+        }
+        // A lookup built from other words still works, but may be missing some in play.
+        if meta.dict_profile != dict_profile() {
+            warn!(
+                "Lookup {} was built with the {} dictionary profile, but the game uses {}",
+                lookup_path,
+                meta.dict_profile,
+                dict_profile()
+            );
+        }
+        Ok(Self {
+            path: lookup_path.into(),
+            meta: meta,
+            bounds: Mutex::new(None),
+        })
+    }
+
+    fn table(&self) -> Table {
+        Table::new_from_file(Options::default(), Path::new(&self.path)).unwrap()
+    }
+
+    /// The keys of every row, not counting the metadata.
+    fn keys(&self) -> Vec<String> {
+        let mut keys = vec![];
+        let mut iter = self.table().iter();
+        while let Some((key, _)) = iter.next() {
+            if key != LOOKUP_META_KEY.as_bytes() {
+                keys.push(String::from_utf8_lossy(&key).into_owned());
+            }
+        }
+        keys
+    }
+
+    /// The most tiles the lookup has probabilities for, or None if it has none.
+    pub fn max_num_tiles(&self) -> Option<usize> {
+        let mut iter = self.table().iter();
+        while let Some((key, _)) = iter.next() {
+            if key != LOOKUP_META_KEY.as_bytes() {
+                let probs = self.probs(&String::from_utf8_lossy(&key))?;
+                return Some(probs.len().saturating_sub(1));
+            }
+        }
+        None
+    }
+}
+
+pub fn init_dict(dict_path: &str) -> Result<(), ScrabrudoError> {
+    let loaded = Dict::load(dict_path, &dict_profile())?;
+    *DICT.write().unwrap() = Some(Arc::new(loaded));
+    Ok(())
+}
+
+pub fn init_lookup(lookup_path: &str) -> Result<(), ScrabrudoError> {
+    let opened = Lookup::open(lookup_path)?;
+    *LOOKUP.write().unwrap() = Some(Arc::new(opened));
+    Ok(())
+}
+
+/// Plays with the given dictionary and lookup, as already loaded, from now on.
+pub fn init_loaded(dict: Dict, lookup: Lookup) {
+    *DICT.write().unwrap() = Some(Arc::new(dict));
+    *LOOKUP.write().unwrap() = Some(Arc::new(lookup));
+}
+
+/// Loads a dictionary and lookup for games of the given config to swap in for their current ones
+/// at the start of their next round, checking first that the lookup covers the given number of
+/// tiles, the most there are in play. Nothing changes if either is found wanting.
+pub fn request_reload(
+    config: &GameConfig,
+    dict_path: &str,
+    lookup_path: &str,
+    num_tiles: usize,
+) -> Result<(), ScrabrudoError> {
+    let dict = Dict::load(dict_path, &dict_profile())?;
+    if dict.words.is_empty() {
+        return Err(ScrabrudoError::InvalidOption(format!(
+            "Dictionary {} has no words in play",
+            dict_path
+        )));
+    }
+    let lookup = Lookup::open(lookup_path)?;
+    match lookup.max_num_tiles() {
+        Some(max_num_tiles) if max_num_tiles >= num_tiles => (),
+        max_num_tiles => {
+            return Err(ScrabrudoError::InvalidOption(format!(
+                "Lookup {} covers up to {} tiles, but there are {} in play",
+                lookup_path,
+                max_num_tiles.unwrap_or(0),
+                num_tiles
+            )))
+        }
+    }
+    config.lookup.reload(lookup);
+    config.dict.reload(dict);
+    Ok(())
 }
 
-fn lookup() -> Table {
-    let lookup_path = LOOKUP.lock().unwrap().clone().unwrap();
-    Table::new_from_file(Options::default(), Path::new(&lookup_path)).unwrap()
+/// The given config with the dictionary and lookup it's waiting to switch to swapped in, if it's
+/// waiting to switch at all. Only to be called between rounds, so that every round is played
+/// with one dictionary throughout.
+pub fn apply_reload(config: &GameConfig) -> Option<GameConfig> {
+    let dict = config.dict.reloaded();
+    let lookup = config.lookup.reloaded();
+    if dict.is_none() && lookup.is_none() {
+        return None;
+    }
+    let mut config = config.clone();
+    if let Some(dict) = dict {
+        config.dict = dict;
+    }
+    if let Some(lookup) = lookup {
+        config.lookup = lookup;
+    }
+    Some(config)
+}
+
+/// The dictionary the process has loaded, which games use unless given their own.
+pub fn current_dict() -> Arc<Dict> {
+    DICT.read().unwrap().clone().unwrap()
+}
+
+/// The lookup the process has loaded, which games use unless given their own.
+pub fn current_lookup() -> Arc<Lookup> {
+    LOOKUP.read().unwrap().clone().unwrap()
+}
+
+/// Loads a word list ordered from most to least common, such as the google-10000 list.
+pub fn init_frequencies(frequency_path: &str) -> Result<(), ScrabrudoError> {
+    info!("Loading word frequencies...");
+    let f = File::open(frequency_path)?;
+    let mut ranks = HashMap::new();
+    for (rank, line) in BufReader::new(f).lines().enumerate() {
+        ranks.entry(line?).or_insert(rank);
+    }
+    let mut frequencies = FREQUENCIES.lock().unwrap();
+    *frequencies = Some(ranks);
+    Ok(())
+}
+
+pub fn dict() -> Dictionary {
+    current_dict().words.clone()
 }
 
 pub fn has_word(word: &String) -> bool {
-    dict().contains(word)
+    current_dict().has_word(word)
 }
 
-/// All the words up to a certain length.
-pub fn words_with_max_length(max_length: usize) -> Dictionary {
-    dict()
-        .clone()
-        .into_iter()
-        .filter(|w| w.len() <= max_length)
-        .collect()
+/// How common the word is, 0 being the most common.
+/// None if it isn't in the word list or none was loaded.
+pub fn word_rank(word: &str) -> Option<usize> {
+    match FREQUENCIES.lock().unwrap().as_ref() {
+        Some(frequencies) => frequencies.get(word).cloned(),
+        None => None,
+    }
+}
+
+/// All the words in the dictionary, shortest first, shared so they can be streamed without
+/// copying the dictionary.
+pub fn words_by_length() -> Arc<Vec<String>> {
+    current_dict().words_by_length()
+}
+
+/// All the words in the dictionary grouped by their sorted letters.
+pub fn anagrams() -> Arc<AnagramIndex> {
+    current_dict().anagrams()
+}
+
+/// The letters of a word in sorted order, the key for its anagrams.
+pub fn sorted_letters(word: &str) -> String {
+    let mut letters = word.chars().collect::<Vec<char>>();
+    letters.sort();
+    letters.into_iter().collect()
+}
+
+/// All the dictionary words starting with the given letters, shortest first.
+pub fn words_starting_with(prefix: &str) -> Vec<String> {
+    current_dict().words_starting_with(prefix)
+}
+
+/// All the dictionary words that can be spelled from the given letters plus up to the given
+/// number of wildcards, shortest first.
+pub fn formable_words(letters: &str, num_wildcards: usize) -> Vec<String> {
+    current_dict().formable_words(letters, num_wildcards)
+}
+
+/// The range of indices into a length-sorted word list holding words of the given lengths.
+pub fn length_range(words: &[String], min_length: usize, max_length: usize) -> Range<usize> {
+    let start = first_index_longer_than(words, min_length.saturating_sub(1));
+    let end = first_index_longer_than(words, max_length);
+    start..end.max(start)
+}
+
+/// The index of the first word longer than the given length.
+fn first_index_longer_than(words: &[String], length: usize) -> usize {
+    match words.binary_search_by(|w| {
+        if w.len() <= length {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }) {
+        Ok(i) | Err(i) => i,
+    }
+}
+
+/// Sorts the dictionary by word length and then lexicographically.
+fn sort_by_length(dict: &Dictionary) -> Vec<String> {
+    let mut words = dict.iter().cloned().collect::<Vec<String>>();
+    words.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+    words
+}
+
+/// Groups the dictionary by sorted letters.
+fn index_anagrams(dict: &Dictionary) -> AnagramIndex {
+    let mut index = AnagramIndex::new();
+    for word in dict {
+        index
+            .entry(sorted_letters(word))
+            .or_default()
+            .push(word.clone());
+    }
+    index
 }
 
 /// A set of all words in the dictionary.
-fn load_dict(dict_path: &str) -> Dictionary {
+fn load_dict(dict_path: &str) -> Result<Dictionary, ScrabrudoError> {
     info!("Loading dictionary...");
-    let f = match File::open(dict_path) {
-        Ok(file) => file,
-        Err(e) => panic!("Couldn't open dictionary: {:?}", e),
-    };
-    BufReader::new(f).lines().map(|l| l.unwrap()).collect()
+    let f = File::open(dict_path)?;
+    let mut dict = Dictionary::new();
+    for line in BufReader::new(f).lines() {
+        dict.insert(normalize_word(&line?).into_owned());
+    }
+    Ok(dict)
 }
 
-/// Does the lookup contain the word?
-pub fn lookup_has(s: &str) -> bool {
-    match lookup().get(s.as_bytes()).unwrap() {
-        Some(_) => true,
-        None => false,
+/// Keeps only the words in play under the given profile.
+fn filter_dict(dict: Dictionary, profile: &DictProfile) -> Result<Dictionary, ScrabrudoError> {
+    if profile == &DictProfile::Full {
+        return Ok(dict);
     }
+    if profile.needs_frequencies() && FREQUENCIES.lock().unwrap().is_none() {
+        return Err(ScrabrudoError::InvalidOption(format!(
+            "The {} dictionary profile needs word frequencies",
+            profile
+        )));
+    }
+    let num_words = dict.len();
+    let filtered = dict
+        .into_iter()
+        .filter(|w| profile.keeps(w, word_rank(w)))
+        .collect::<Dictionary>();
+    info!(
+        "Kept {} of {} words for the {} profile",
+        filtered.len(),
+        num_words,
+        profile
+    );
+    Ok(filtered)
 }
 
-/// Pull the encoded list out of the storage.
-/// None if we don't have probs for this.
+impl Lookup {
+    /// Does the lookup contain the word?
+    pub fn has(&self, s: &str) -> bool {
+        self.table().get(s.as_bytes()).unwrap().is_some()
+    }
+
+    /// What the lookup holds.
+    pub fn meta(&self) -> &LookupMeta {
+        &self.meta
+    }
+
+    /// Pull the encoded list out of the storage.
+    /// None if we don't have probs for this.
+    pub fn probs(&self, s: &str) -> Option<Vec<f64>> {
+        let encoded_probs = self.table().get(s.as_bytes()).unwrap();
+        metrics::record_lookup(encoded_probs.is_some());
+        let encoded_probs = encoded_probs?;
+        if self.meta.exact {
+            let row: LookupRow = bincode::deserialize(&encoded_probs).unwrap();
+            Some(row.probs)
+        } else {
+            Some(bincode::deserialize(&encoded_probs).unwrap())
+        }
+    }
+
+    /// Pull the exact (Palafico) probabilities out of the storage.
+    /// None if we don't have probs for this, or the lookup predates them.
+    pub fn exact_probs(&self, s: &str) -> Option<Vec<f64>> {
+        if !self.meta.exact {
+            return None;
+        }
+        let encoded_probs = self.table().get(s.as_bytes()).unwrap();
+        metrics::record_lookup(encoded_probs.is_some());
+        let encoded_probs = encoded_probs?;
+        let row: LookupRow = bincode::deserialize(&encoded_probs).unwrap();
+        Some(row.exact_probs)
+    }
+
+    /// The highest probability of finding any letters of the given length in the given number
+    /// of tiles, which no bet needing that many letters can beat. 1.0 if the lookup doesn't go
+    /// that far. Worked out on first use by reading the whole lookup.
+    pub fn bound(&self, length: usize, num_tiles: usize) -> f64 {
+        let mut bounds = self.bounds.lock().unwrap();
+        if bounds.is_none() {
+            let rows = self
+                .keys()
+                .into_iter()
+                .filter_map(|letters| Some((letters.len(), self.probs(&letters)?)))
+                .collect();
+            *bounds = Some(Arc::new(max_probs_by_length(rows)));
+        }
+        bounds
+            .as_ref()
+            .unwrap()
+            .get(length)
+            .and_then(|by_num_tiles| by_num_tiles.get(num_tiles))
+            .cloned()
+            .unwrap_or(1.0)
+    }
+
+    /// How many keys, not counting the metadata?
+    pub fn len(&self) -> usize {
+        self.keys().len()
+    }
+
+    /// Whether the lookup holds no keys at all.
+    pub fn is_empty(&self) -> bool {
+        self.keys().is_empty()
+    }
+
+    /// Every row, as its letters with the probabilities of finding at least them and exactly
+    /// them, the latter empty if the lookup predates them. Reads the whole lookup.
+    pub fn rows(&self) -> Vec<(String, Vec<f64>, Vec<f64>)> {
+        let mut rows = vec![];
+        let mut iter = self.table().iter();
+        while let Some((key, encoded_probs)) = iter.next() {
+            if key == LOOKUP_META_KEY.as_bytes() {
+                continue;
+            }
+            let letters = String::from_utf8_lossy(&key).into_owned();
+            if self.meta.exact {
+                let row: LookupRow = bincode::deserialize(&encoded_probs).unwrap();
+                rows.push((letters, row.probs, row.exact_probs));
+            } else {
+                rows.push((
+                    letters,
+                    bincode::deserialize(&encoded_probs).unwrap(),
+                    vec![],
+                ));
+            }
+        }
+        rows
+    }
+}
+
+/// Does the current lookup contain the word?
+pub fn lookup_has(s: &str) -> bool {
+    current_lookup().has(s)
+}
+
+/// What the current lookup holds.
+pub fn lookup_meta() -> LookupMeta {
+    current_lookup().meta().clone()
+}
+
+/// The current lookup's probabilities of finding at least the given letters.
 pub fn lookup_probs(s: &str) -> Option<Vec<f64>> {
-    let encoded_probs = match lookup().get(s.as_bytes()).unwrap() {
-        Some(ps) => ps,
-        None => return None,
-    };
-    Some(bincode::deserialize(&encoded_probs).unwrap())
+    current_lookup().probs(s)
+}
+
+/// The current lookup's probabilities of finding exactly the given letters.
+pub fn lookup_exact_probs(s: &str) -> Option<Vec<f64>> {
+    current_lookup().exact_probs(s)
 }
 
-/// How many keys?
+/// The current lookup's bound on finding any letters of the given length in the given number of
+/// tiles.
+pub fn lookup_bound(length: usize, num_tiles: usize) -> f64 {
+    current_lookup().bound(length, num_tiles)
+}
+
+/// The highest of the given probabilities for each length of letters and number of tiles, from
+/// rows of the length and the probabilities indexed by number of tiles. Where rows of one
+/// length disagree on how many tiles they go up to, only those all of them cover are bounded.
+pub fn max_probs_by_length(rows: Vec<(usize, Vec<f64>)>) -> Vec<Vec<f64>> {
+    let mut bounds: Vec<Option<Vec<f64>>> = vec![];
+    for (length, probs) in rows {
+        if bounds.len() <= length {
+            bounds.resize(length + 1, None);
+        }
+        bounds[length] = Some(match bounds[length].take() {
+            Some(bound) => bound
+                .iter()
+                .zip(probs.iter())
+                .map(|(b, p)| b.max(*p))
+                .collect(),
+            None => probs,
+        });
+    }
+    bounds.into_iter().map(|b| b.unwrap_or_default()).collect()
+}
+
+/// How many keys the current lookup has, not counting the metadata.
 pub fn lookup_len() -> usize {
-    let mut len = 0;
-    let mut iter = lookup().iter();
-    loop {
-        match iter.next() {
-            Some(_) => len += 1,
-            None => return len,
+    current_lookup().len()
+}
+
+speculate! {
+    before {
+        testing::set_up();
+    }
+
+    describe "word frequencies" {
+        it "ranks words by how common they are" {
+            assert_eq!(Some(0), word_rank("the"));
+            assert!(word_rank("cat") > word_rank("act"));
+            assert_eq!(None, word_rank("qi"));
+        }
+    }
+
+    describe "dictionary profiles" {
+        it "keeps words by length and how common they are" {
+            assert_eq!(Some(DictProfile::Kids), DictProfile::from_name("kids"));
+            assert_eq!(None, DictProfile::from_name("hard"));
+            assert!(DictProfile::Full.keeps("quixotic", None));
+            assert!(DictProfile::Kids.keeps("cat", Some(10)));
+            assert!(!DictProfile::Kids.keeps("elephant", Some(10)));
+            assert!(!DictProfile::Kids.keeps("cat", Some(KIDS_MAX_RANK)));
+            assert!(DictProfile::Common.keeps("elephant", Some(5000)));
+            assert!(!DictProfile::Common.keeps("qi", None));
+            assert!(DictProfile::Tournament.keeps("qi", None));
+            assert!(!DictProfile::Tournament.keeps("a", Some(0)));
+        }
+
+        it "filters the dictionary" {
+            let words = hashset!{ "a".into(), "the".into(), "zymurgy".into() };
+            assert_eq!(words, filter_dict(words.clone(), &DictProfile::Full).unwrap());
+            assert_eq!(
+                hashset!{ "the".into() },
+                filter_dict(words.clone(), &DictProfile::Common).unwrap());
+            assert_eq!(
+                hashset!{ "the".into(), "zymurgy".into() },
+                filter_dict(words, &DictProfile::Tournament).unwrap());
+        }
+
+        it "records the profile in lookup metadata" {
+            let meta: LookupMeta = serde_json::from_str(r#"{"exact": true}"#).unwrap();
+            assert_eq!(DictProfile::Full, meta.dict_profile);
+            let meta: LookupMeta = serde_json::from_str(r#"{"dict_profile": "kids"}"#).unwrap();
+            assert_eq!(DictProfile::Kids, meta.dict_profile);
+        }
+    }
+
+    describe "lookup bounds" {
+        it "takes the highest probability for each length" {
+            let bounds = max_probs_by_length(vec![
+                (1, vec![0.0, 0.2, 0.4]),
+                (1, vec![0.0, 0.3, 0.35]),
+                (3, vec![0.0, 0.0, 0.1]),
+                (3, vec![0.0, 0.0]),
+            ]);
+            assert_eq!(4, bounds.len());
+            assert_eq!(vec![0.0, 0.3, 0.4], bounds[1]);
+            assert!(bounds[2].is_empty());
+            assert_eq!(vec![0.0, 0.0], bounds[3]);
+        }
+    }
+
+    describe "anagrams" {
+        it "groups words by their letters" {
+            assert_eq!("act", sorted_letters("cat"));
+            let mut words = anagrams().get("act").unwrap().clone();
+            words.sort();
+            assert_eq!(vec!["act".to_string(), "cat".to_string()], words);
+        }
+
+        it "finds the words formable from some letters" {
+            let words = formable_words("tca", 0);
+            assert!(words.contains(&"cat".to_string()));
+            assert!(words.contains(&"act".to_string()));
+            assert!(words.contains(&"at".to_string()));
+            assert!(!words.contains(&"cats".to_string()));
+        }
+
+        it "finds the words starting with some letters" {
+            let words = words_starting_with("cat");
+            assert_eq!("cat", words[0]);
+            assert!(words.contains(&"catalog".to_string()));
+            assert!(words.iter().all(|word| word.starts_with("cat")));
+        }
+
+        it "fills gaps with wildcards" {
+            assert!(!formable_words("ca", 0).contains(&"cat".to_string()));
+            assert!(formable_words("ca", 1).contains(&"cat".to_string()));
+            assert!(formable_words("ca", 2).contains(&"cats".to_string()));
         }
     }
 }
@@ -0,0 +1,107 @@
+/// Dictionary statistics: how the words in play break down by length and by letter, and how many
+/// keys a lookup of them would hold, for sizing a precompute before running it.
+use crate::lookup::*;
+use crate::tile::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// How a dictionary breaks down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictStats {
+    /// The number of words of each length.
+    pub words_by_length: BTreeMap<usize, usize>,
+
+    /// Each tile's share of the letters of the words, beside its share of the tiles dealt.
+    pub letter_frequencies: Vec<(Tile, f64, f64)>,
+
+    /// The number of distinct keys of each length a lookup would hold, up to the most tiles
+    /// asked about.
+    pub keys_by_length: BTreeMap<usize, usize>,
+}
+
+impl fmt::Display for DictStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} words", self.words_by_length.values().sum::<usize>())?;
+        writeln!(f, "Words by length:")?;
+        for (length, num_words) in &self.words_by_length {
+            writeln!(f, "  {}: {}", length, num_words)?;
+        }
+        writeln!(f, "Letters in the words vs in the tiles:")?;
+        for (tile, in_words, in_tiles) in &self.letter_frequencies {
+            writeln!(
+                f,
+                "  {:?}: {:.2}% vs {:.2}%",
+                tile,
+                100.0 * in_words,
+                100.0 * in_tiles
+            )?;
+        }
+        write!(f, "Lookup keys by number of tiles:")?;
+        let mut num_keys = 0;
+        for (length, keys) in &self.keys_by_length {
+            num_keys += keys;
+            write!(f, "\n  {}: {}", length, num_keys)?;
+        }
+        Ok(())
+    }
+}
+
+/// Breaks down the given words, counting lookup keys for up to max_num_tiles tiles and comparing
+/// letters with the tiles dealt from the current tile set.
+pub fn dict_stats(words: &HashSet<String>, max_num_tiles: usize) -> DictStats {
+    let mut words_by_length = BTreeMap::new();
+    let mut letter_counts = vec![0usize; Tile::all().len()];
+    let mut keys = HashSet::new();
+    for word in words {
+        *words_by_length.entry(word.chars().count()).or_insert(0) += 1;
+        for c in word.chars() {
+            if let Ok(tile) = Tile::from_char(c) {
+                letter_counts[tile.as_usize()] += 1;
+            }
+        }
+        add_sorted_substrings(word, None, max_num_tiles, &mut keys);
+    }
+    let num_letters = letter_counts.iter().sum::<usize>().max(1);
+    let letter_frequencies = Tile::all()
+        .into_iter()
+        .map(|t| {
+            let in_words = letter_counts[t.as_usize()] as f64 / num_letters as f64;
+            let in_tiles = t.prob();
+            (t, in_words, in_tiles)
+        })
+        .collect();
+    let mut keys_by_length = BTreeMap::new();
+    for key in keys {
+        *keys_by_length.entry(key.chars().count()).or_insert(0) += 1;
+    }
+    DictStats {
+        words_by_length: words_by_length,
+        letter_frequencies: letter_frequencies,
+        keys_by_length: keys_by_length,
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "dictionary statistics" {
+        it "lists the keys a lookup holds for a word" {
+            assert_eq!(15, all_sorted_substrings(&"hate".into(), 4).len());
+            assert_eq!(
+                hashset!{"a".to_string(), "b".into(), "aa".into(), "ab".into()},
+                all_sorted_substrings(&"aba".into(), 2));
+        }
+
+        it "breaks down the words" {
+            let stats = dict_stats(&hashset!{"cat".to_string(), "act".into(), "at".into()}, 2);
+            assert_eq!(btreemap!{2 => 1, 3 => 2}, stats.words_by_length);
+            assert_eq!(btreemap!{1 => 3, 2 => 3}, stats.keys_by_length);
+            let (tile, in_words, _) = &stats.letter_frequencies[0];
+            assert_eq!(&Tile::A, tile);
+            assert_eq!(3.0 / 8.0, *in_words);
+            assert!(stats.to_string().ends_with("\n  1: 3\n  2: 6"));
+        }
+    }
+}
@@ -1,11 +1,14 @@
 /// Definition of a single tile.
+use crate::error::*;
 use crate::hand::*;
+use crate::rng::*;
 
 use rand::distributions::Standard;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ord;
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Hash, Serialize, Deserialize)]
 pub enum Die {
     One,
     Two,
@@ -17,7 +20,7 @@ pub enum Die {
 
 impl Holdable for Die {
     fn get_random() -> Self {
-        rand::random()
+        rng_for("deal").gen()
     }
 }
 
@@ -44,9 +47,11 @@ impl Die {
         ]
     }
 
-    pub fn from_usize(x: usize) -> Die {
-        let all = Die::all();
-        all[x - 1].clone()
+    pub fn from_usize(x: usize) -> Result<Die, ScrabrudoError> {
+        if !(1..=6).contains(&x) {
+            return Err(ScrabrudoError::InvalidDie(x));
+        }
+        Ok(Die::all()[x - 1].clone())
     }
 }
 
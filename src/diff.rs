@@ -0,0 +1,258 @@
+/// State diffs: what changed at the table between two snapshots of a game, such as either side of
+/// a turn, so that spectators can be sent only what changed and tests can say exactly what a turn
+/// did. Only what everyone can see is compared, so that no hand is ever given away.
+use crate::bet::*;
+use crate::constraint::*;
+use crate::game::*;
+use crate::schema::*;
+
+use serde_json::{json, Map, Value};
+#[cfg(test)]
+use speculate::speculate;
+
+/// What changed between two snapshots of a game, as seen by everyone at the table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateDiff<B: Bet> {
+    /// How many items each seat lost, by seat, for the seats that lost any. Seats can't be
+    /// matched up without the players' IDs, so this is left empty when a seat has gone.
+    pub items_lost: Vec<(usize, usize)>,
+
+    /// How many items each seat gained, as a Palafico winner may, by seat.
+    pub items_gained: Vec<(usize, usize)>,
+
+    /// The number of items left with each seat, if any seat's changed or a seat has gone.
+    pub num_items_per_player: Option<Vec<usize>>,
+
+    /// The seat whose turn it was and the seat whose turn it is, if the turn moved on.
+    pub index_advanced: Option<(usize, usize)>,
+
+    /// Whether the bets made before are gone, a new round having begun.
+    pub history_cleared: bool,
+
+    /// The bets made since, lowest first, with the ID of the player who made each.
+    pub history_appended: Vec<(usize, B)>,
+
+    /// The items each seat has shown to everyone, if any of them changed.
+    pub revealed: Option<Vec<Vec<B::V>>>,
+
+    /// The items in the middle of the table, if they changed.
+    pub community: Option<Vec<B::V>>,
+
+    /// The constraint card for the round, if it changed.
+    pub constraint: Option<Option<Constraint>>,
+}
+
+impl<B: Bet> StateDiff<B> {
+    /// Whether nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.num_items_per_player.is_none()
+            && self.index_advanced.is_none()
+            && !self.history_cleared
+            && self.history_appended.is_empty()
+            && self.revealed.is_none()
+            && self.community.is_none()
+            && self.constraint.is_none()
+    }
+
+    /// The diff in JSON, holding only what changed, with items and bets written as positions
+    /// are.
+    pub fn to_json(&self) -> Value {
+        let mut changes = Map::new();
+        if !self.items_lost.is_empty() {
+            changes.insert("items_lost".into(), json!(self.items_lost));
+        }
+        if !self.items_gained.is_empty() {
+            changes.insert("items_gained".into(), json!(self.items_gained));
+        }
+        if let Some(num_items) = &self.num_items_per_player {
+            changes.insert("num_items_per_player".into(), json!(num_items));
+        }
+        if let Some((from, to)) = self.index_advanced {
+            changes.insert("index_advanced".into(), json!([from, to]));
+        }
+        if self.history_cleared {
+            changes.insert("history_cleared".into(), json!(true));
+        }
+        if !self.history_appended.is_empty() {
+            let appended = self
+                .history_appended
+                .iter()
+                .map(|(player_id, bet)| json!({"player": player_id, "bet": bet.to_schema()}))
+                .collect::<Vec<Value>>();
+            changes.insert("history_appended".into(), json!(appended));
+        }
+        if let Some(revealed) = &self.revealed {
+            let revealed = revealed
+                .iter()
+                .map(|items| items_json(items))
+                .collect::<Vec<Value>>();
+            changes.insert("revealed".into(), json!(revealed));
+        }
+        if let Some(community) = &self.community {
+            changes.insert("community".into(), items_json(community));
+        }
+        if let Some(constraint) = &self.constraint {
+            changes.insert("constraint".into(), json!(constraint));
+        }
+        Value::Object(changes)
+    }
+}
+
+/// The given items in JSON, as positions hold them.
+fn items_json<T: Schema>(items: &[T]) -> Value {
+    Value::Array(items.iter().map(|i| i.to_schema()).collect())
+}
+
+impl<B: Bet> GameState<B> {
+    /// What changed between this and the given later snapshot of the same game.
+    pub fn diff(&self, other: &Self) -> StateDiff<B> {
+        let reseated = self.num_items_per_player.len() != other.num_items_per_player.len();
+        let (mut items_lost, mut items_gained) = (vec![], vec![]);
+        if !reseated {
+            let counts = self
+                .num_items_per_player
+                .iter()
+                .zip(other.num_items_per_player.iter());
+            for (seat, (before, after)) in counts.enumerate() {
+                if after < before {
+                    items_lost.push((seat, before - after));
+                } else if after > before {
+                    items_gained.push((seat, after - before));
+                }
+            }
+        }
+        // Bets are only ever added within a round, so any that have gone mean a new one.
+        let history_cleared = self.history.iter().any(|(player_id, bets)| {
+            other
+                .history
+                .get(player_id)
+                .is_none_or(|later| !later.starts_with(bets))
+        });
+        let mut history_appended = other
+            .history
+            .iter()
+            .flat_map(|(player_id, bets)| {
+                let num_seen = match self.history.get(player_id) {
+                    Some(earlier) if !history_cleared => earlier.len(),
+                    _ => 0,
+                };
+                bets[num_seen..]
+                    .iter()
+                    .map(move |bet| (*player_id, bet.clone()))
+            })
+            .collect::<Vec<(usize, B)>>();
+        history_appended.sort_by(|a, b| a.1.cmp(&b.1));
+        StateDiff {
+            items_lost: items_lost,
+            items_gained: items_gained,
+            num_items_per_player: changed(&self.num_items_per_player, &other.num_items_per_player),
+            index_advanced: if self.current_index != other.current_index {
+                Some((self.current_index, other.current_index))
+            } else {
+                None
+            },
+            history_cleared: history_cleared,
+            history_appended: history_appended,
+            revealed: changed(&self.revealed, &other.revealed),
+            community: changed(&self.community, &other.community),
+            constraint: changed(&self.constraint, &other.constraint),
+        }
+    }
+}
+
+/// The later value, if it differs from the earlier.
+fn changed<T: Clone + PartialEq>(before: &T, after: &T) -> Option<T> {
+    if before == after {
+        None
+    } else {
+        Some(after.clone())
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::config::*;
+    use crate::die::*;
+    use crate::opening::*;
+    use crate::testing::*;
+
+    describe "state diffs" {
+        fn state(
+            num_items_per_player: Vec<usize>,
+            current_index: usize,
+            history: Vec<(usize, PerudoBet)>,
+        ) -> GameState<PerudoBet> {
+            let mut bets = hashmap!{};
+            for (player_id, bet) in history {
+                bets.entry(player_id).or_insert(vec![]).push(bet);
+            }
+            GameState {
+                current_index: current_index,
+                history: bets,
+                ..game_state(num_items_per_player)
+            }
+        }
+
+        it "finds nothing changed in the same state" {
+            let before = state(vec![3, 3], 1, vec![(0, perudo_bet(2, Die::Four))]);
+            assert!(before.diff(&before).is_empty());
+            assert_eq!(json!({}), before.diff(&before).to_json());
+        }
+
+        it "finds a bet made" {
+            let before = state(vec![3, 3], 1, vec![(0, perudo_bet(2, Die::Four))]);
+            let after = state(vec![3, 3], 0, vec![(0, perudo_bet(2, Die::Four)), (1, perudo_bet(3, Die::Two))]);
+            let diff = before.diff(&after);
+            assert_eq!(Some((1, 0)), diff.index_advanced);
+            assert_eq!(vec![(1, perudo_bet(3, Die::Two))], diff.history_appended);
+            assert!(!diff.history_cleared);
+            assert!(diff.items_lost.is_empty());
+            assert_eq!(None, diff.num_items_per_player);
+            assert_eq!(
+                json!({
+                    "index_advanced": [1, 0],
+                    "history_appended": [{"player": 1, "bet": {"quantity": 3, "value": 2}}],
+                }),
+                diff.to_json());
+        }
+
+        it "finds an item lost and a new round begun" {
+            let before = state(vec![3, 3], 0, vec![(0, perudo_bet(2, Die::Four)), (1, perudo_bet(3, Die::Two))]);
+            let after = state(vec![2, 3], 0, vec![]);
+            let diff = before.diff(&after);
+            assert_eq!(vec![(0, 1)], diff.items_lost);
+            assert_eq!(Some(vec![2, 3]), diff.num_items_per_player);
+            assert_eq!(None, diff.index_advanced);
+            assert!(diff.history_cleared);
+            assert!(diff.history_appended.is_empty());
+        }
+
+        it "leaves seats unmatched once one has gone" {
+            let before = state(vec![1, 2, 2], 1, vec![(1, perudo_bet(1, Die::Six))]);
+            let after = state(vec![2, 2], 0, vec![]);
+            let diff = before.diff(&after);
+            assert!(diff.items_lost.is_empty());
+            assert_eq!(Some(vec![2, 2]), diff.num_items_per_player);
+            assert_eq!(Some(vec![vec![], vec![]]), diff.revealed);
+        }
+
+        it "says exactly what a turn did" {
+            let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, GameConfig::default());
+            let next = game.apply(TurnOutcome::Bet(perudo_bet(1, Die::Three))).unwrap();
+            let diff = game.state().diff(&next.state());
+            assert_eq!(
+                StateDiff {
+                    items_lost: vec![],
+                    items_gained: vec![],
+                    num_items_per_player: None,
+                    index_advanced: Some((0, 1)),
+                    history_cleared: false,
+                    history_appended: vec![(0, perudo_bet(1, Die::Three))],
+                    revealed: None,
+                    community: None,
+                    constraint: None,
+                },
+                diff);
+        }
+    }
+}
@@ -0,0 +1,346 @@
+/// Presenting hands to human players at the terminal.
+use crate::color::*;
+use crate::lang::*;
+use crate::tile::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref RACK_ORDER: Mutex<RackOrder> = Mutex::new(RackOrder::Alphabetical);
+    static ref PRACTICE: Mutex<bool> = Mutex::new(false);
+    static ref TRACKER: Mutex<bool> = Mutex::new(false);
+    static ref HINTS: Mutex<bool> = Mutex::new(false);
+    static ref ACCESSIBLE: Mutex<bool> = Mutex::new(false);
+}
+
+/// The order in which a human's tiles are shown to them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RackOrder {
+    /// The order the tiles were dealt in.
+    Dealt,
+
+    /// A to Z, with blanks last.
+    Alphabetical,
+
+    /// Highest scoring first, then A to Z.
+    Score,
+}
+
+impl RackOrder {
+    /// Parses a rack order from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dealt" => Some(RackOrder::Dealt),
+            "alphabetical" => Some(RackOrder::Alphabetical),
+            "score" => Some(RackOrder::Score),
+            _ => None,
+        }
+    }
+
+    /// Sorts the given tiles into this order.
+    pub fn sort(&self, tiles: &mut [Tile]) {
+        match self {
+            RackOrder::Dealt => (),
+            RackOrder::Alphabetical => tiles.sort(),
+            RackOrder::Score => tiles.sort_by(|a, b| b.score().cmp(&a.score()).then(a.cmp(b))),
+        }
+    }
+}
+
+/// Sets the order in which racks are shown.
+pub fn init_rack_order(order: RackOrder) {
+    let mut rack_order = RACK_ORDER.lock().unwrap();
+    *rack_order = order;
+}
+
+/// Gets the order in which racks are shown.
+pub fn rack_order() -> RackOrder {
+    RACK_ORDER.lock().unwrap().clone()
+}
+
+/// Turns practice mode on or off, in which humans are shown the words their rack can make.
+pub fn init_practice(on: bool) {
+    let mut practice = PRACTICE.lock().unwrap();
+    *practice = on;
+}
+
+/// Whether humans are being shown the words their rack can make.
+pub fn practice() -> bool {
+    *PRACTICE.lock().unwrap()
+}
+
+/// Turns the letters-remaining tracker on or off.
+pub fn init_tracker(on: bool) {
+    let mut tracker = TRACKER.lock().unwrap();
+    *tracker = on;
+}
+
+/// Whether humans are shown the letters-remaining tracker each turn.
+pub fn tracker() -> bool {
+    *TRACKER.lock().unwrap()
+}
+
+/// Turns deduction hints on or off.
+pub fn init_hints(on: bool) {
+    let mut hints = HINTS.lock().unwrap();
+    *hints = on;
+}
+
+/// Whether humans are shown what can be deduced from the bets and reveals so far each turn.
+pub fn hints() -> bool {
+    *HINTS.lock().unwrap()
+}
+
+/// Turns accessible mode on or off, in which humans are shown plain, linear descriptions suited
+/// to screen readers and may type commands out in full.
+pub fn init_accessible(on: bool) {
+    let mut accessible = ACCESSIBLE.lock().unwrap();
+    *accessible = on;
+}
+
+/// Whether humans are shown descriptions suited to screen readers.
+pub fn accessible() -> bool {
+    *ACCESSIBLE.lock().unwrap()
+}
+
+/// Shows a single tile with its score, e.g. "Q10", "Qu10" for a letter pair, or "_0" for a blank.
+pub fn format_tile(tile: &Tile) -> String {
+    let letter = match tile {
+        Tile::Blank => "_".to_string(),
+        _ => capitalised(tile),
+    };
+    format!("{}{}", letter, tile.score())
+}
+
+/// The letters on a tile with the first capitalised, e.g. "Q" or "Qu".
+fn capitalised(tile: &Tile) -> String {
+    let spelling = tile.spelling();
+    spelling[..1].to_uppercase() + &spelling[1..]
+}
+
+/// Shows a rack of tiles in the order given, coloured if colour is on.
+pub fn format_rack(tiles: &[Tile]) -> String {
+    tiles
+        .iter()
+        .map(|tile| paint_tile(tile, &format_tile(tile)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Tabulates, for each tile, how many the human holds and how many of the given number of unseen
+/// tiles are likely to be that tile, assuming they were dealt from the usual distribution.
+pub fn format_tracker(rack: &[Tile], num_unseen: usize) -> String {
+    let mut lines = vec!["Tile  Yours  Expected elsewhere  Chance of any elsewhere".to_string()];
+    // Letter pairs the tile set doesn't deal are left out.
+    let pairs = tile_set().pairs();
+    for tile in Tile::all() {
+        if Tile::pairs().contains(&tile) && !pairs.contains(&tile) {
+            continue;
+        }
+        let yours = rack.iter().filter(|t| **t == tile).count();
+        let p = tile.prob();
+        let expected = num_unseen as f64 * p;
+        let chance_of_any = 1.0 - (1.0 - p).powi(num_unseen as i32);
+        lines.push(format!(
+            "{:<4}  {:>5}  {:>18.2}  {:>22.0}%",
+            format_tile(&tile),
+            yours,
+            expected,
+            100.0 * chance_of_any
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Spells out a single tile with its score, e.g. "Q worth 10" or "blank worth 0".
+pub fn describe_tile(tile: &Tile) -> String {
+    let letter = match tile {
+        Tile::Blank => Msg::BlankTile.to_string(),
+        _ => capitalised(tile),
+    };
+    Msg::TileWorth {
+        tile: letter,
+        score: tile.score(),
+    }
+    .to_string()
+}
+
+/// Spells out a rack of tiles in the order given, a tile at a time.
+pub fn describe_rack(tiles: &[Tile]) -> String {
+    tiles
+        .iter()
+        .map(describe_tile)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Names each of the given items in turn, e.g. "Two, Five, Six", rather than listing them.
+pub fn describe_items<V: Debug>(items: &[V]) -> String {
+    items
+        .iter()
+        .map(|item| format!("{:?}", item))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Says how many items each seat has, a seat at a time.
+pub fn describe_counts(counts: &[usize]) -> String {
+    counts
+        .iter()
+        .enumerate()
+        .map(|(seat, count)| {
+            Msg::SeatHas {
+                seat: seat,
+                count: *count,
+            }
+            .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// The letters-remaining tracker as a sentence per tile rather than a table.
+pub fn describe_tracker(rack: &[Tile], num_unseen: usize) -> String {
+    let pairs = tile_set().pairs();
+    Tile::all()
+        .into_iter()
+        .filter(|tile| !Tile::pairs().contains(tile) || pairs.contains(tile))
+        .map(|tile| {
+            let p = tile.prob();
+            Msg::TrackerRow {
+                tile: describe_tile(&tile),
+                yours: rack.iter().filter(|t| **t == tile).count(),
+                expected: num_unseen as f64 * p,
+                chance: 100.0 * (1.0 - (1.0 - p).powi(num_unseen as i32)),
+            }
+            .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Rewrites what a human typed out in full into the shorthand the prompt reads: "perudo" or
+/// "challenge" for "p", "palafico" for "pal", "forfeit" for "quit", "likely" for "table",
+/// "exchange" for "x", faces of dice as in "2 sixes" for "2.6", and operators as in
+/// "2 plus 3 equals 5" for "2+3=5". Commands take the given prefix, so that in Scrabrudo
+/// "*palafico" is read as "*pal" while words are left alone.
+pub fn expand_alias(line: &str, prefix: &str) -> String {
+    let rest = match line.strip_prefix(prefix) {
+        Some(rest) => rest.trim(),
+        None => return line.into(),
+    };
+    let command = match rest {
+        "perudo" | "challenge" => Some("p"),
+        "palafico" => Some("pal"),
+        "forfeit" => Some("quit"),
+        "likely" => Some("table"),
+        _ => None,
+    };
+    if let Some(command) = command {
+        return format!("{}{}", prefix, command);
+    }
+    if let Some(items) = rest.strip_prefix("exchange ") {
+        return format!("{}x {}", prefix, items.trim());
+    }
+    if !prefix.is_empty() {
+        return line.into();
+    }
+    let faces = [
+        ("one", "ones"),
+        ("two", "twos"),
+        ("three", "threes"),
+        ("four", "fours"),
+        ("five", "fives"),
+        ("six", "sixes"),
+    ];
+    let words = rest.split_whitespace().collect::<Vec<&str>>();
+    if let [quantity, face] = words[..] {
+        if let Some(value) = faces
+            .iter()
+            .position(|(one, many)| face == *one || face == *many)
+        {
+            if quantity.parse::<usize>().is_ok() {
+                return format!("{}.{}", quantity, value + 1);
+            }
+        }
+    }
+    if words
+        .iter()
+        .any(|w| ["plus", "minus", "times", "equals"].contains(w))
+    {
+        return words
+            .iter()
+            .map(|w| match *w {
+                "plus" => "+",
+                "minus" => "-",
+                "times" => "*",
+                "equals" => "=",
+                w => w,
+            })
+            .collect::<String>();
+    }
+    line.into()
+}
+
+#[cfg(test)]
+speculate! {
+    describe "racks" {
+        fn rack() -> Vec<Tile> {
+            vec![Tile::T, Tile::Blank, Tile::Q, Tile::A]
+        }
+
+        it "formats tiles with their scores" {
+            assert_eq!("T1 _0 Q10 A1", format_rack(&rack()));
+        }
+
+        it "sorts alphabetically" {
+            let mut tiles = rack();
+            RackOrder::Alphabetical.sort(&mut tiles);
+            assert_eq!(vec![Tile::A, Tile::Q, Tile::T, Tile::Blank], tiles);
+        }
+
+        it "sorts by score" {
+            let mut tiles = rack();
+            RackOrder::Score.sort(&mut tiles);
+            assert_eq!(vec![Tile::Q, Tile::A, Tile::T, Tile::Blank], tiles);
+        }
+
+        it "tracks the letters left in play" {
+            let tracker = format_tracker(&rack(), 0);
+            assert_eq!(28, tracker.lines().count());
+            assert!(tracker.contains("Q10       1                0.00                       0%"));
+            let tracker = format_tracker(&[], 1000);
+            assert!(tracker.contains("100%"));
+        }
+
+        it "spells out racks and the tracker" {
+            assert_eq!("T worth 1, blank worth 0, Q worth 10, A worth 1", describe_rack(&rack()));
+            let tracker = describe_tracker(&rack(), 0);
+            assert_eq!(27, tracker.lines().count());
+            assert!(tracker.contains("Q worth 10: you hold 1, 0.00 expected elsewhere, 0% chance of any elsewhere"));
+            assert_eq!("seat 0 has 5, seat 1 has 3", describe_counts(&[5, 3]));
+        }
+
+        it "reads commands typed out in full" {
+            assert_eq!("pal", expand_alias("palafico", ""));
+            assert_eq!("x 2 5", expand_alias("exchange 2 5", ""));
+            assert_eq!("2.6", expand_alias("2 sixes", ""));
+            assert_eq!("3.1", expand_alias("3 one", ""));
+            assert_eq!("2+3=5", expand_alias("2 plus 3 equals 5", ""));
+            assert_eq!("*p", expand_alias("*challenge", "*"));
+            assert_eq!("*x ab", expand_alias("*exchange ab", "*"));
+            // Words stay bets in Scrabrudo.
+            assert_eq!("palafico", expand_alias("palafico", "*"));
+            assert_eq!("2.6", expand_alias("2.6", ""));
+        }
+
+        it "leaves dealt order alone" {
+            let mut tiles = rack();
+            RackOrder::Dealt.sort(&mut tiles);
+            assert_eq!(rack(), tiles);
+        }
+    }
+}
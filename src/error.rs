@@ -0,0 +1,136 @@
+/// Errors surfaced by the engine.
+use crate::lang::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong when driving a game, as opposed to broken invariants, which
+/// still panic.
+#[derive(Debug)]
+pub enum ScrabrudoError {
+    /// A character that isn't a tile.
+    InvalidTile(char),
+
+    /// A number that isn't a face of a die.
+    InvalidDie(usize),
+
+    /// A bet that can't be made right now, with the reason.
+    InvalidBet(String),
+
+    /// A challenge made before any bet.
+    NoBetToChallenge,
+
+    /// A challenge made by a player who gave up the right by exchanging this round.
+    NoRightToChallenge,
+
+    /// An exchange that can't be made right now, with the reason.
+    InvalidExchange(String),
+
+    /// A trade that can't be made right now, with the reason.
+    InvalidTrade(String),
+
+    /// An outcome chosen by the player in the given seat when it isn't their turn.
+    OutOfTurn(usize),
+
+    /// An outcome that makes no sense at this point in the game.
+    UnexpectedOutcome(String),
+
+    /// The game has already been won.
+    GameOver,
+
+    /// A command-line option that couldn't be understood.
+    InvalidOption(String),
+
+    /// A dictionary, lookup or save file that couldn't be read or written.
+    Io(io::Error),
+
+    /// A save or record that couldn't be encoded or decoded.
+    Format(String),
+
+    /// A scenario that didn't play out as it said it would, with how.
+    ScenarioFailed(String),
+
+    /// A seeded game that no longer plays out as its golden record says, with where it differs.
+    Drift(String),
+
+    /// A leaderboard database that couldn't be read or written.
+    Database(String),
+
+    /// A player in the given seat whose connection dropped while their move was awaited.
+    Disconnected(usize),
+
+    /// A player in the given seat whose thinking time ran out while their move was awaited.
+    OutOfTime(usize),
+
+    /// A lobby command that can't be carried out, with the reason.
+    Lobby(String),
+}
+
+impl fmt::Display for ScrabrudoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScrabrudoError::InvalidTile(c) => write!(f, "{}", Msg::NotATile(*c)),
+            ScrabrudoError::InvalidDie(x) => write!(f, "{}", Msg::NotADie(x.to_string())),
+            ScrabrudoError::InvalidBet(reason) => write!(f, "{}", reason),
+            ScrabrudoError::NoBetToChallenge => write!(f, "{}", Msg::NoBetToChallenge),
+            ScrabrudoError::NoRightToChallenge => write!(f, "{}", Msg::NoRightToChallenge),
+            ScrabrudoError::InvalidExchange(reason) => write!(f, "{}", reason),
+            ScrabrudoError::InvalidTrade(reason) => write!(f, "{}", reason),
+            ScrabrudoError::OutOfTurn(seat) => write!(f, "{}", Msg::OutOfTurn(*seat)),
+            ScrabrudoError::UnexpectedOutcome(outcome) => {
+                write!(f, "{}", Msg::UnexpectedOutcome(outcome.clone()))
+            }
+            ScrabrudoError::GameOver => write!(f, "{}", Msg::GameOver),
+            ScrabrudoError::InvalidOption(reason) => write!(f, "{}", reason),
+            ScrabrudoError::Io(e) => write!(f, "{}", e),
+            ScrabrudoError::Format(reason) => write!(f, "{}", reason),
+            ScrabrudoError::ScenarioFailed(reason) => {
+                write!(f, "{}", Msg::ScenarioFailed(reason.clone()))
+            }
+            ScrabrudoError::Drift(reason) => write!(f, "{}", Msg::Drift(reason.clone())),
+            ScrabrudoError::Database(reason) => write!(f, "{}", Msg::Leaderboard(reason.clone())),
+            ScrabrudoError::Disconnected(id) => write!(f, "{}", Msg::Disconnected(*id)),
+            ScrabrudoError::OutOfTime(id) => write!(f, "{}", Msg::OutOfTime(*id)),
+            ScrabrudoError::Lobby(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl error::Error for ScrabrudoError {}
+
+impl From<io::Error> for ScrabrudoError {
+    fn from(e: io::Error) -> Self {
+        ScrabrudoError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ScrabrudoError {
+    fn from(e: serde_json::Error) -> Self {
+        ScrabrudoError::Format(format!("{}", e))
+    }
+}
+
+#[cfg(feature = "leaderboard")]
+impl From<rusqlite::Error> for ScrabrudoError {
+    fn from(e: rusqlite::Error) -> Self {
+        ScrabrudoError::Database(format!("{}", e))
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "errors" {
+        it "describes errors" {
+            assert_eq!("'!' is not a tile", format!("{}", ScrabrudoError::InvalidTile('!')));
+            assert_eq!("Bet wasn't high enough", format!("{}", ScrabrudoError::InvalidBet("Bet wasn't high enough".into())));
+        }
+
+        it "wraps IO errors" {
+            let e: ScrabrudoError = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+            assert_eq!("missing", format!("{}", e));
+        }
+    }
+}
@@ -0,0 +1,278 @@
+/// Explanations: what the AI weighed up in choosing each move and what it ruled out along the
+/// way, so that a surprising move, such as calling Perudo on a near-certain word, can be traced
+/// back to its cause.
+use crate::belief::*;
+use crate::bet::*;
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+use crate::logging;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref EXPLAIN_OUT: Mutex<Option<File>> = Mutex::new(None);
+    static ref COACH: Mutex<bool> = Mutex::new(false);
+}
+
+// Kept per thread, so that replaying a game collects what its own AI decided alone.
+thread_local! {
+    static COLLECTED: RefCell<Option<Vec<ExplainedDecision>>> = const { RefCell::new(None) };
+}
+
+/// The most candidates kept in an explanation.
+pub const NUM_CANDIDATES: usize = 5;
+
+/// Why an AI player made the move it did.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExplainedDecision {
+    /// The game the move was made in, if one was being played.
+    pub game_id: Option<u32>,
+
+    /// The round of the game, counting from 1.
+    pub round: usize,
+
+    /// The ID of the player who moved.
+    pub player_id: usize,
+
+    /// How the move was chosen: greedy, mcts or learned.
+    pub method: String,
+
+    /// The move made.
+    pub chosen: String,
+
+    /// The likeliest moves weighed up, best first, with their chances of coming off. A
+    /// challenge's chance is weighted by what is at stake should it succeed or fail.
+    pub candidates: Vec<(String, f64)>,
+
+    /// How far the player took the others' bets on trust.
+    pub belief: BeliefPolicy,
+
+    /// What was ruled out without being weighed up, and why.
+    pub pruned: Vec<String>,
+
+    /// The chance that any raise of the bet standing was right, if there was one.
+    #[serde(default)]
+    pub any_raise: Option<f64>,
+}
+
+impl ExplainedDecision {
+    pub fn new<B: Bet>(
+        config: &GameConfig,
+        player_id: usize,
+        method: &str,
+        chosen: &TurnOutcome<B>,
+        candidates: Vec<(String, f64)>,
+        pruned: Vec<String>,
+        any_raise: Option<f64>,
+    ) -> Self {
+        let context = logging::context();
+        Self {
+            game_id: context.game_id,
+            round: context.round,
+            player_id: player_id,
+            method: method.into(),
+            chosen: describe(chosen),
+            candidates: candidates,
+            belief: belief(config, player_id),
+            pruned: pruned,
+            any_raise: any_raise,
+        }
+    }
+}
+
+impl fmt::Display for ExplainedDecision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Player {} chose {} ({}, believing {:?})",
+            self.player_id, self.chosen, self.method, self.belief
+        )?;
+        for (candidate, p) in &self.candidates {
+            write!(f, "\n  {} at {:.3}", candidate, p)?;
+        }
+        for reason in &self.pruned {
+            write!(f, "\n  ruled out {}", reason)?;
+        }
+        if let Some(p) = self.any_raise {
+            write!(f, "\n  any raise right at {:.3}", p)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints an explanation of every AI move from now on, or stops.
+pub fn init_coach(on: bool) {
+    *COACH.lock().unwrap() = on;
+}
+
+/// Starts writing an explanation of every AI move to the given path, one JSON object a line.
+pub fn init_explain_out(explain_path: &str) -> Result<(), ScrabrudoError> {
+    *EXPLAIN_OUT.lock().unwrap() = Some(File::create(explain_path)?);
+    Ok(())
+}
+
+/// Starts keeping explanations of the AI moves made on this thread in memory, to be taken later.
+pub fn init_collection() {
+    COLLECTED.with(|collected| *collected.borrow_mut() = Some(vec![]));
+}
+
+/// Takes the explanations kept on this thread so far.
+pub fn take_collected() -> Vec<ExplainedDecision> {
+    COLLECTED.with(|collected| {
+        collected
+            .borrow_mut()
+            .as_mut()
+            .map_or(vec![], std::mem::take)
+    })
+}
+
+/// Whether AI moves are being explained at all, so that the work can be skipped if not.
+pub fn explaining() -> bool {
+    *COACH.lock().unwrap()
+        || EXPLAIN_OUT.lock().unwrap().is_some()
+        || COLLECTED.with(|collected| collected.borrow().is_some())
+}
+
+/// Prints, writes or keeps the given explanation, as asked.
+pub fn emit(decision: &ExplainedDecision) -> Result<(), ScrabrudoError> {
+    if *COACH.lock().unwrap() {
+        info!("{}", decision);
+    }
+    if let Some(f) = EXPLAIN_OUT.lock().unwrap().as_mut() {
+        writeln!(f, "{}", serde_json::to_string(decision)?)?;
+    }
+    COLLECTED.with(|collected| {
+        if let Some(decisions) = collected.borrow_mut().as_mut() {
+            decisions.push(decision.clone());
+        }
+    });
+    Ok(())
+}
+
+/// A move as a human would write it.
+pub fn describe<B: Bet>(outcome: &TurnOutcome<B>) -> String {
+    match outcome {
+        TurnOutcome::Bet(bet) => bet.to_string(),
+        TurnOutcome::Perudo => "perudo!".into(),
+        TurnOutcome::Palafico => "palafico!".into(),
+        TurnOutcome::Exchange(items) => format!("an exchange of {:?}", items),
+        outcome => format!("{:?}", outcome),
+    }
+}
+
+/// The best of the given outcomes, ranked lowest first, best first.
+pub fn top_candidates<B: Bet>(ranked: &[(TurnOutcome<B>, f64)]) -> Vec<(String, f64)> {
+    ranked
+        .iter()
+        .rev()
+        .take(NUM_CANDIDATES)
+        .map(|(outcome, p)| (describe(outcome), *p))
+        .collect()
+}
+
+/// What was ruled out of raising the given bet: how many raises each house rule forbade, and
+/// how many of those allowed weren't weighed up, given how many were.
+pub fn pruned_raises<B: Bet>(state: &GameState<B>, bet: &B, num_weighed: usize) -> Vec<String> {
+    let repeated = state
+        .history
+        .values()
+        .flatten()
+        .cloned()
+        .collect::<Vec<B>>();
+    let (mut num_small, mut num_repeated, mut num_unmet, mut num_allowed) = (0, 0, 0, 0);
+    for raise in bet.candidates_above(state).filter(|b| **b > *bet) {
+        if raise.check_raise(bet, &state.config.min_raise).is_err() {
+            num_small += 1;
+        } else if state.config.no_repeated_words && repeated.contains(&raise) {
+            num_repeated += 1;
+        } else if !state.constraint.as_ref().is_none_or(|c| raise.meets(c)) {
+            num_unmet += 1;
+        } else {
+            num_allowed += 1;
+        }
+    }
+    let mut pruned = vec![];
+    if num_small > 0 {
+        pruned.push(format!(
+            "{} raises too small for the minimum raise",
+            num_small
+        ));
+    }
+    if num_repeated > 0 {
+        pruned.push(format!(
+            "{} raises repeating bets already made",
+            num_repeated
+        ));
+    }
+    if num_unmet > 0 {
+        pruned.push(format!(
+            "{} raises breaking the round's constraint",
+            num_unmet
+        ));
+    }
+    if num_allowed > num_weighed {
+        pruned.push(format!(
+            "{} raises unweighed, as unable to beat the best found or out of time",
+            num_allowed - num_weighed
+        ));
+    }
+    pruned
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::*;
+    use crate::testing::*;
+
+    describe "explanations" {
+        it "keeps the best candidates, best first" {
+            let ranked = vec![
+                (TurnOutcome::Bet(perudo_bet(3, Die::Two)), 0.2),
+                (TurnOutcome::Perudo, 0.5),
+                (TurnOutcome::Bet(perudo_bet(2, Die::Six)), 0.7),
+            ];
+            assert_eq!(
+                vec![("2 Sixs".to_string(), 0.7), ("perudo!".to_string(), 0.5), ("3 Twos".to_string(), 0.2)],
+                top_candidates(&ranked));
+        }
+
+        it "counts the raises each rule rules out" {
+            let state = GameState {
+                history: hashmap!{ 1 => vec![perudo_bet(2, Die::Six)] },
+                config: GameConfig { no_repeated_words: true, ..GameConfig::default() },
+                ..game_state(vec![1, 1])
+            };
+            // Above 2 Fives from two dice are 2 Sixes, already bet, then 1 One and 2 Ones.
+            assert_eq!(
+                vec!["1 raises repeating bets already made".to_string(), "2 raises unweighed, as unable to beat the best found or out of time".to_string()],
+                pruned_raises(&state, &perudo_bet(2, Die::Five), 0));
+        }
+
+        it "shows what was weighed up" {
+            let decision = ExplainedDecision {
+                game_id: None,
+                round: 1,
+                player_id: 1,
+                method: "greedy".into(),
+                chosen: "perudo!".into(),
+                candidates: vec![("perudo!".into(), 0.75)],
+                belief: BeliefPolicy::Honesty,
+                pruned: vec!["2 raises too small for the minimum raise".into()],
+                any_raise: None,
+            };
+            assert_eq!(
+                "Player 1 chose perudo! (greedy, believing Honesty)\n  perudo! at 0.750\n  ruled out 2 raises too small for the minimum raise",
+                decision.to_string());
+            let decision = ExplainedDecision { any_raise: Some(0.005), ..decision };
+            assert!(decision.to_string().ends_with("\n  any raise right at 0.005"));
+        }
+    }
+}
@@ -1,30 +1,136 @@
 /// Game logic.
+use crate::analysis::*;
+use crate::belief::*;
 use crate::bet::*;
+use crate::cache;
+use crate::color::*;
+use crate::config::*;
+use crate::constraint::*;
+use crate::crash;
+use crate::dashboard;
+use crate::dict;
 use crate::dict::*;
 use crate::die::*;
+use crate::error::*;
 use crate::hand::*;
+use crate::input::*;
+use crate::lang::*;
+use crate::leaderboard;
+use crate::logging;
+use crate::metrics;
+use crate::multiset::*;
+use crate::net;
+use crate::notation;
+use crate::notify::*;
+use crate::opening::*;
+use crate::pacing;
 use crate::player::*;
+use crate::save::*;
+use crate::schema;
+use crate::step::*;
+use crate::summary::*;
+use crate::symbol::*;
 use crate::testing;
 use crate::tile::*;
+use crate::timebank::*;
+use crate::trade::*;
+use crate::verify;
 
+use serde::{Deserialize, Serialize};
 use speculate::speculate;
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // TODO: PerudoTurnOutcome and make a more general version when making Game variant-agnostic.
-#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum TurnOutcome<B: Bet> {
     First,
     Bet(B),
     Perudo,
     Palafico,
+    /// Swapping the given hidden items for new ones before betting.
+    Exchange(Vec<B::V>),
+    /// The choices each seat made in the trading phase before the round's first bet, by seat.
+    Trade(Vec<TradeAction<B::V>>),
+    Quit,
     Win,
 }
 
 type History<B: Bet> = HashMap<usize, Vec<B>>;
 
+/// A turn as it was played, shown to the observer of a running game.
+pub struct PlayedTurn<'a, G: Game> {
+    /// The round the turn was played in, counting from 1.
+    pub round: usize,
+
+    /// The ID of the player whose turn it was.
+    pub player_id: usize,
+
+    /// The game as it was before the turn.
+    pub before: &'a G,
+
+    /// What the player chose.
+    pub outcome: &'a TurnOutcome<G::B>,
+
+    /// The game as the turn left it.
+    pub after: &'a G,
+}
+
+/// A summary of a game that has been run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameResult {
+    /// The ID of the winning player, unless the game was stopped before it was over.
+    pub winner: Option<usize>,
+
+    /// The IDs of the players from first place to last, as they stood when the game ended.
+    pub standings: Vec<usize>,
+
+    /// The number of rounds played to the end.
+    pub num_rounds: usize,
+
+    /// The number of turns played, exchanges and quits included.
+    pub num_turns: usize,
+}
+
+impl GameResult {
+    /// The summary of the given game after the given number of rounds and turns.
+    pub fn of<G: Game>(game: &G, num_rounds: usize, num_turns: usize) -> Self {
+        GameResult {
+            winner: game.winner(),
+            standings: game.standings(),
+            num_rounds: num_rounds,
+            num_turns: num_turns,
+        }
+    }
+}
+
+/// The most bets listed among a player's legal actions; Scrabrudo can allow far more.
+pub const MAX_LEGAL_BETS: usize = 1000;
+
+/// The moves open to the current player, for clients to offer and bots to choose among.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegalActions<B: Bet> {
+    /// The bets that may be made, lowest first, as many as MAX_LEGAL_BETS.
+    pub bets: Vec<B>,
+
+    /// Whether more bets may be made than are listed, which check_action tells apart.
+    pub more_bets: bool,
+
+    /// The challenges that may be called, both Perudo and Palafico or neither.
+    pub challenges: Vec<TurnOutcome<B>>,
+
+    /// Whether some of the player's hidden items may be exchanged before betting.
+    pub exchange: bool,
+}
+
 /// An export of the state of the game required by Bets/Players to make progress.
+#[derive(Clone)]
 pub struct GameState<B: Bet> {
     /// The total number of items left around the table.
     pub total_num_items: usize,
@@ -32,9 +138,152 @@ pub struct GameState<B: Bet> {
     /// The number of items remaining with each player.
     pub num_items_per_player: Vec<usize>,
 
+    /// The index of the player whose turn it is.
+    pub current_index: usize,
+
+    /// The ID of the player in each seat.
+    pub player_ids: Vec<usize>,
+
     /// The history of bets so far in the round.
     /// This is keyed by the player ID.
     pub history: History<B>,
+
+    /// The rules the game is being played under.
+    pub config: GameConfig,
+
+    /// The items each player has shown to everyone, by seat.
+    pub revealed: Vec<Vec<B::V>>,
+
+    /// The items face up in the middle of the table, which count towards every bet.
+    pub community: Vec<B::V>,
+
+    /// The constraint card drawn for this round, if playing with them.
+    pub constraint: Option<Constraint>,
+
+    /// Each player's hand, by seat. A view for one player holds only their own, leaving the rest
+    /// empty.
+    pub hands: Vec<Vec<B::V>>,
+
+    /// The thinking time each timed player has left, in milliseconds, by player ID.
+    pub clocks: Clocks,
+
+    /// How often each player's bets have held up when challenged this game, by player ID.
+    pub honesty: Honesty,
+}
+
+impl<B: Bet> GameState<B> {
+    /// The seat of the player with the given ID, if they are still at the table.
+    pub fn seat_of(&self, player_id: usize) -> Option<usize> {
+        self.player_ids.iter().position(|id| *id == player_id)
+    }
+
+    /// Gets the bet currently standing, if any has been made this round.
+    /// Bets only ever rise, so this is the highest in the history.
+    pub fn standing_bet(&self) -> Option<&B> {
+        self.history.values().flatten().max()
+    }
+
+    /// A snapshot of the game as the player in the given seat sees it: their own hand and
+    /// everything public, with the other hands redacted.
+    pub fn view_for(&self, player_index: usize) -> Self {
+        Self {
+            hands: self
+                .hands
+                .iter()
+                .enumerate()
+                .map(|(i, hand)| {
+                    if i == player_index {
+                        hand.clone()
+                    } else {
+                        vec![]
+                    }
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether the snapshot shows no hand but that of the player in the given seat.
+    pub fn is_view_for(&self, player_index: usize) -> bool {
+        self.hands
+            .iter()
+            .enumerate()
+            .all(|(i, hand)| i == player_index || hand.is_empty())
+    }
+
+    /// Whether the given bet has already been made this round.
+    pub fn has_been_bet(&self, bet: &B) -> bool {
+        self.history.values().any(|bets| bets.contains(bet))
+    }
+
+    /// The items the player whose turn it is can see beyond their own hand: those others have
+    /// shown, and those in the middle of the table.
+    pub fn known_items(&self) -> Vec<B::V> {
+        self.revealed
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != self.current_index)
+            .flat_map(|(_, items)| items.iter().cloned())
+            .chain(self.community.iter().cloned())
+            .collect()
+    }
+
+    /// The number of items in other players' hands that the player whose turn it is can't see,
+    /// given how many they hold themselves.
+    pub fn num_unseen_items(&self, num_own_items: usize) -> usize {
+        let num_revealed = self
+            .revealed
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != self.current_index)
+            .map(|(_, items)| items.len())
+            .sum::<usize>();
+        self.total_num_items - num_own_items - num_revealed
+    }
+
+    /// Hashes the position as seen by a player holding the given hand, for spotting repeated
+    /// positions while planning. The hash is the same however the hand is ordered, and however
+    /// the history was built up, so long as each player made the same bets and the same items
+    /// are face up.
+    pub fn canonical_hash(&self, hand: &[B::V]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.total_num_items.hash(&mut hasher);
+        self.num_items_per_player.hash(&mut hasher);
+        self.current_index.hash(&mut hasher);
+        let mut history = self
+            .history
+            .iter()
+            .filter(|(_, bets)| !bets.is_empty())
+            .map(|(player_id, bets)| {
+                let mut bets = bets.clone();
+                bets.sort();
+                (*player_id, bets)
+            })
+            .collect::<Vec<(usize, Vec<B>)>>();
+        history.sort_by_key(|(player_id, _)| *player_id);
+        history.hash(&mut hasher);
+        self.config.hash(&mut hasher);
+        let revealed = self
+            .revealed
+            .iter()
+            .enumerate()
+            .filter(|(_, items)| !items.is_empty())
+            .map(|(i, items)| {
+                let mut items = items.clone();
+                items.sort();
+                (i, items)
+            })
+            .collect::<Vec<(usize, Vec<B::V>)>>();
+        revealed.hash(&mut hasher);
+        let mut community = self.community.clone();
+        community.sort();
+        community.hash(&mut hasher);
+        self.constraint.hash(&mut hasher);
+        let mut hand = hand.to_vec();
+        hand.sort();
+        hand.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
 /// Trait implemented by all game types.
@@ -50,24 +299,52 @@ pub trait Game: Sized + fmt::Display {
     type P: Player<B = Self::B, V = Self::V>;
 
     /// Creates a new instance of the game.
-    fn new(num_players: usize, items_per_player: usize, human_indices: HashSet<usize>) -> Self {
+    fn new(
+        num_players: usize,
+        items_per_player: usize,
+        human_indices: HashSet<usize>,
+        opening: OpeningPolicy,
+        config: GameConfig,
+    ) -> Self {
         let mut players = Vec::new();
         for id in 0..num_players {
-            players.push(Self::create_player(
-                id,
-                items_per_player,
-                human_indices.contains(&id),
-            ));
+            players.push(
+                Self::create_player(
+                    id,
+                    items_per_player,
+                    human_indices.contains(&id),
+                    opening.clone(),
+                )
+                .into(),
+            );
         }
-        Self::new_with(players, 0, TurnOutcome::First, hashmap!{})
+        let first_index = config.first_player.choose(num_players);
+        let community = Hand::<Self::V>::new(config.community as u32).items;
+        let constraint = if config.constraints {
+            Some(Constraint::draw())
+        } else {
+            None
+        };
+        Self::new_with(
+            players,
+            first_index,
+            TurnOutcome::First,
+            hashmap!{},
+            community,
+            constraint,
+            config,
+        )
     }
 
     /// Creates a new instance with the given fields.
     fn new_with(
-        players: Vec<Box<dyn Player<B = Self::B, V = Self::V>>>,
+        players: Vec<Arc<dyn Player<B = Self::B, V = Self::V>>>,
         current_index: usize,
         current_outcome: TurnOutcome<Self::B>,
         history: History<Self::B>,
+        community: Vec<Self::V>,
+        constraint: Option<Constraint>,
+        config: GameConfig,
     ) -> Self;
 
     /// Creates a new player.
@@ -75,10 +352,41 @@ pub trait Game: Sized + fmt::Display {
         id: usize,
         items_per_player: usize,
         human: bool,
+        opening: OpeningPolicy,
     ) -> Box<dyn Player<B = Self::B, V = Self::V>>;
 
+    /// The thinking time timed players have left.
+    fn clocks(&self) -> &Clocks;
+
+    /// The game with the given thinking time left on the clocks.
+    fn with_clocks(self, clocks: Clocks) -> Self;
+
+    /// The IDs of the players out of the game, in the order they went out.
+    fn eliminated(&self) -> &Vec<usize>;
+
+    /// The game with the given players out of it, in the order they went out.
+    fn with_eliminated(self, eliminated: Vec<usize>) -> Self;
+
+    /// Whether the trading phase has been played this round.
+    fn traded(&self) -> bool;
+
+    /// The game with the trading phase played this round, or still to play.
+    fn with_traded(self, traded: bool) -> Self;
+
+    /// The round being played, counting from 1.
+    fn round(&self) -> usize;
+
+    /// The game at the given round.
+    fn with_round(self, round: usize) -> Self;
+
+    /// How often each player's bets have held up when challenged this game.
+    fn honesty(&self) -> &Honesty;
+
+    /// The game with the given record of how often bets have held up.
+    fn with_honesty(self, honesty: Honesty) -> Self;
+
     /// Gets a list of all the players.
-    fn players(&self) -> &Vec<Box<dyn Player<B = Self::B, V = Self::V>>>;
+    fn players(&self) -> &Vec<Arc<dyn Player<B = Self::B, V = Self::V>>>;
 
     /// Gets the outcome of the turn currently being represented.
     fn current_outcome(&self) -> &TurnOutcome<Self::B>;
@@ -95,9 +403,38 @@ pub trait Game: Sized + fmt::Display {
     /// Whether or not the given bet is precisely (Palafico-satisfyingly) correct at the current state.
     fn is_exactly_correct(&self, bet: &Self::B) -> bool;
 
+    /// Tells how the given bet, checked exactly or not, counts against the items on the table.
+    fn tally(&self, bet: &Self::B, exactly: bool) -> Msg;
+
     /// Gets the betting history for this game.
     fn history(&self) -> &History<Self::B>;
 
+    /// Gets the items face up in the middle of the table this round.
+    fn community(&self) -> &Vec<Self::V>;
+
+    /// Gets the constraint card drawn for this round, if any.
+    fn constraint(&self) -> Option<&Constraint>;
+
+    /// Gets the rules the game is being played under.
+    fn config(&self) -> &GameConfig;
+
+    /// The game played under the given rules from now on.
+    fn with_config(self, config: GameConfig) -> Self;
+
+    /// Deals the items to lay face up in the middle of the table for a new round.
+    fn deal_community(&self) -> Vec<Self::V> {
+        Hand::<Self::V>::new(self.config().community as u32).items
+    }
+
+    /// Draws the constraint card for a new round, if playing with them.
+    fn draw_constraint(&self) -> Option<Constraint> {
+        if self.config().constraints {
+            Some(Constraint::draw())
+        } else {
+            None
+        }
+    }
+
     /// Gets the current history with the current bet appended.
     fn history_with_bet(&self, player_id: usize, bet: &Self::B) -> History<Self::B> {
         let mut history = self.history().clone();
@@ -106,20 +443,39 @@ pub trait Game: Sized + fmt::Display {
         history
     }
 
-    /// Gets a state representation of the game.
+    /// Gets a state representation of the game, showing every hand.
+    /// Players are only ever shown a view of it for their own seat.
     fn state(&self) -> GameState<Self::B> {
         GameState {
             total_num_items: self.total_num_items(),
             num_items_per_player: self.num_items_per_player(),
+            current_index: self.current_index(),
+            player_ids: self.players().iter().map(|p| p.id()).collect(),
             history: self.history().clone(),
+            config: self.config().clone(),
+            revealed: self
+                .players()
+                .iter()
+                .map(|p| p.hand().revealed_items().to_vec())
+                .collect(),
+            community: self.community().clone(),
+            constraint: self.constraint().cloned(),
+            hands: self.players().iter().map(|p| p.items().clone()).collect(),
+            clocks: match &self.config().timebank {
+                Some(timebank) => self
+                    .players()
+                    .iter()
+                    .filter(|p| p.human())
+                    .map(|p| (p.id(), timebank.remaining(self.clocks(), p.id())))
+                    .collect(),
+                None => hashmap! {},
+            },
+            honesty: self.honesty().clone(),
         }
     }
 
     fn num_items_with(&self, val: Self::V) -> usize {
-        self.players()
-            .iter()
-            .map(|p| p.num_items_with(val.clone()))
-            .sum()
+        Multiset::of(&self.all_items()).count(&val)
     }
 
     /// Gets the number of items remaining per player by index.
@@ -132,39 +488,58 @@ pub trait Game: Sized + fmt::Display {
         self.num_items_per_player().iter().sum()
     }
 
-    /// Gets a vec of the actual items on the table.
+    /// Gets a vec of the actual items on the table, including those in the middle.
     fn all_items(&self) -> Vec<Self::V> {
         self.players()
             .iter()
             .map(|p| p.items())
             .flatten()
+            .chain(self.community().iter())
             .map(|i| i.clone())
             .collect::<Vec<Self::V>>()
     }
 
     /// Gets a cloned refreshed view on the players.
-    fn refreshed_players(&self) -> Vec<Box<dyn Player<B = Self::B, V = Self::V>>> {
-        self.players().iter().map(|p| p.refresh()).collect()
+    fn refreshed_players(&self) -> Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
+        self.players().iter().map(|p| p.refresh().into()).collect()
     }
 
     /// Clones players without touching their hands.
-    fn cloned_players(&self) -> Vec<Box<dyn Player<B = Self::B, V = Self::V>>> {
-        self.players().iter().map(|p| p.cloned()).collect()
+    fn cloned_players(&self) -> Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
+        self.players().iter().map(|p| p.cloned().into()).collect()
     }
 
     /// Gets the players refreshed with one player losing.
     fn refreshed_players_with_loss(
         &self,
         loser_index: usize,
-    ) -> Vec<Box<dyn Player<B = Self::B, V = Self::V>>> {
+    ) -> Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
+        self.players()
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                if i == loser_index {
+                    p.without_one().into()
+                } else {
+                    p.refresh().into()
+                }
+            })
+            .collect()
+    }
+
+    /// Gets the players refreshed with one player showing another item.
+    fn refreshed_players_with_reveal(
+        &self,
+        loser_index: usize,
+    ) -> Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
         self.players()
             .iter()
             .enumerate()
             .map(|(i, p)| {
                 if i == loser_index {
-                    p.without_one()
+                    p.revealing_one().into()
                 } else {
-                    p.refresh()
+                    p.refresh().into()
                 }
             })
             .collect()
@@ -174,136 +549,1136 @@ pub trait Game: Sized + fmt::Display {
     fn refreshed_players_with_gain(
         &self,
         winner_index: usize,
-    ) -> Vec<Box<dyn Player<B = Self::B, V = Self::V>>> {
+    ) -> Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
         self.players()
             .iter()
             .enumerate()
             .map(|(i, p)| {
                 if i == winner_index && p.num_items() < 5 {
-                    p.with_one()
+                    p.with_one().into()
                 } else {
-                    p.refresh()
+                    p.refresh().into()
                 }
             })
             .collect()
     }
 
-    /// Gets the last bet issued.
-    fn last_bet(&self) -> Self::B {
+    /// Gets the last bet issued, or the smallest bet if none has been made this round.
+    fn last_bet(&self) -> Result<Self::B, ScrabrudoError> {
         match self.current_outcome() {
-            TurnOutcome::First => *Self::B::smallest(),
-            TurnOutcome::Bet(bet) => bet.clone(),
-            _ => panic!(),
+            TurnOutcome::First => Ok(*Self::B::smallest()),
+            TurnOutcome::Bet(bet) => Ok(bet.clone()),
+            TurnOutcome::Win => Err(ScrabrudoError::GameOver),
+            outcome => Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome))),
         }
     }
 
-    /// Ends the turn and returns the new game state.
-    fn with_end_turn(&self, loser_index: usize) -> Self {
+    /// Ends the turn after a challenge and returns the new game state.
+    fn with_end_turn(&self, loser_index: usize, winner_index: usize) -> Self {
         let loser = &self.players()[loser_index];
-        if loser.num_items() == 1 {
-            info!("Player {} is disqualified", loser.id());
+        let reveal = self.config().reveal_on_loss;
 
+        // Under reveal_on_loss the loser shows an item instead, and is out once they would have
+        // nothing left hidden.
+        let items_left = if reveal {
+            loser.num_items() - loser.hand().revealed - 1
+        } else {
+            loser.num_items() - 1
+        };
+        let loser_eliminated = items_left == 0;
+        let next_index = self.config().round_starter.next_index(
+            loser_index,
+            winner_index,
+            self.players().len(),
+            loser_eliminated,
+        );
+        if loser_eliminated {
             // Clone the players with new hands, without the loser.
             let mut players = self.refreshed_players();
             players.remove(loser_index);
-            let current_index = (loser_index % players.len()) as usize;
+            let current_index = if next_index > loser_index {
+                next_index - 1
+            } else {
+                next_index
+            };
+
+            if players.len() > 1 {
+                return Self::new_with(
+                    players,
+                    current_index,
+                    TurnOutcome::First,
+                    hashmap!{},
+                    self.deal_community(),
+                    self.draw_constraint(),
+                    self.config().clone(),
+                );
+            } else {
+                return Self::new_with(
+                    players,
+                    0,
+                    TurnOutcome::Win,
+                    hashmap!{},
+                    self.deal_community(),
+                    self.draw_constraint(),
+                    self.config().clone(),
+                );
+            }
+        } else if reveal {
+            // Refresh all players, loser shows one more item.
+            let players = self.refreshed_players_with_reveal(loser_index);
+            Self::new_with(
+                players,
+                next_index,
+                TurnOutcome::First,
+                hashmap!{},
+                self.deal_community(),
+                self.draw_constraint(),
+                self.config().clone(),
+            )
+        } else {
+            // Refresh all players, loser loses an item.
+            let players = self.refreshed_players_with_loss(loser_index);
+            // Reset and prepare for the next turn.
+            return Self::new_with(
+                players,
+                next_index,
+                TurnOutcome::First,
+                hashmap!{},
+                self.deal_community(),
+                self.draw_constraint(),
+                self.config().clone(),
+            );
+        }
+    }
+
+    /// Ends the turn in Palafico and returns the new game state.
+    fn with_end_turn_palafico(&self, winner_index: usize) -> Self {
+        // Refresh all players, winner maybe gains a item.
+        let players = self.refreshed_players_with_gain(winner_index);
+        Self::new_with(
+            players,
+            winner_index,
+            TurnOutcome::First,
+            hashmap!{},
+            self.deal_community(),
+            self.draw_constraint(),
+            self.config().clone(),
+        )
+    }
+
+    /// Gives up the seat of the given player, who is either replaced by the AI or removed from
+    /// the table as the rules say.
+    fn with_forfeit(&self, index: usize) -> Self {
+        match self.config().forfeit {
+            Forfeit::ToAi => {
+                let players = self
+                    .players()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| {
+                        if i == index {
+                            p.copy_with(None, Some(false), None).into()
+                        } else {
+                            p.cloned().into()
+                        }
+                    })
+                    .collect();
+                Self::new_with(
+                    players,
+                    self.current_index(),
+                    self.current_outcome().clone(),
+                    self.history().clone(),
+                    self.community().clone(),
+                    self.constraint().cloned(),
+                    self.config().clone(),
+                )
+            }
+            Forfeit::Remove => {
+                // The round is dealt again without them.
+                let mut players = self.refreshed_players();
+                players.remove(index);
+                if players.len() > 1 {
+                    let current_index = index % players.len();
+                    Self::new_with(
+                        players,
+                        current_index,
+                        TurnOutcome::First,
+                        hashmap!{},
+                        self.deal_community(),
+                        self.draw_constraint(),
+                        self.config().clone(),
+                    )
+                } else {
+                    Self::new_with(
+                        players,
+                        0,
+                        TurnOutcome::Win,
+                        hashmap!{},
+                        self.deal_community(),
+                        self.draw_constraint(),
+                        self.config().clone(),
+                    )
+                }
+            }
+        }
+    }
+
+    /// Swaps the given hidden items of the current player for new ones, leaving them still to
+    /// bet.
+    fn with_exchange(&self, items: &[Self::V]) -> Result<Self, ScrabrudoError> {
+        let player = &self.players()[self.current_index()];
+        let hand = exchanged_hand(self.config(), player.hand(), items)?;
+        let players = self
+            .players()
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                if i == self.current_index() {
+                    p.copy_with(None, None, Some(hand.clone())).into()
+                } else {
+                    p.cloned().into()
+                }
+            })
+            .collect();
+        Ok(Self::new_with(
+            players,
+            self.current_index(),
+            self.current_outcome().clone(),
+            self.history().clone(),
+            self.community().clone(),
+            self.constraint().cloned(),
+            self.config().clone(),
+        ))
+    }
+
+    /// Whether the round is waiting on the trading phase before its first bet.
+    fn is_trading(&self) -> bool {
+        self.config().trades && !self.traded() && self.current_outcome() == &TurnOutcome::First
+    }
+
+    /// Swaps a hidden item between each pair of neighbours who both offered one, given each
+    /// seat's choice, leaving the round to start.
+    fn with_trades(&self, actions: &[TradeAction<Self::V>]) -> Result<Self, ScrabrudoError> {
+        let mut hands = self
+            .players()
+            .iter()
+            .map(|p| p.hand().clone())
+            .collect::<Vec<Hand<Self::V>>>();
+        for (seat, left) in trading_seats(actions, self.current_index()) {
+            if let (TradeAction::Offer(given), TradeAction::Offer(received)) =
+                (&actions[seat], &actions[left])
+            {
+                // Offers were checked, so both are held face down.
+                hands[seat] = hands[seat].trading(given, received.clone()).unwrap();
+                hands[left] = hands[left].trading(received, given.clone()).unwrap();
+            }
+        }
+        let players = self
+            .players()
+            .iter()
+            .zip(hands)
+            .map(|(p, hand)| p.copy_with(None, None, Some(hand)).into())
+            .collect();
+        Ok(Self::new_with(
+            players,
+            self.current_index(),
+            self.current_outcome().clone(),
+            self.history().clone(),
+            self.community().clone(),
+            self.constraint().cloned(),
+            self.config().clone(),
+        ))
+    }
+
+    /// Ends a round past the game's last: the player with the most items still hidden wins, and
+    /// if several are tied for the most, only they play on, in sudden death with one item each.
+    fn with_tiebreak(&self) -> Self {
+        let hidden =
+            |p: &Arc<dyn Player<B = Self::B, V = Self::V>>| p.num_items() - p.hand().revealed;
+        let most = self.players().iter().map(hidden).max().unwrap_or(0);
+        let num_players = self.players().len();
+        // Whoever was to start the round starts it, or else the next of those tied after them.
+        let seats = (0..num_players)
+            .map(|i| (self.current_index() + i) % num_players)
+            .filter(|i| hidden(&self.players()[*i]) == most)
+            .collect::<Vec<usize>>();
+        if seats.len() == 1 {
+            let winner = &self.players()[seats[0]];
+            return Self::new_with(
+                vec![winner.cloned().into()],
+                0,
+                TurnOutcome::Win,
+                hashmap!{},
+                self.community().clone(),
+                self.constraint().cloned(),
+                self.config().clone(),
+            );
+        }
+        let mut seats = seats;
+        let starter = seats[0];
+        seats.sort();
+        let players = seats
+            .iter()
+            .map(|i| {
+                self.players()[*i]
+                    .copy_with(None, None, Some(Hand::<Self::V>::new(1)))
+                    .into()
+            })
+            .collect::<Vec<Arc<dyn Player<B = Self::B, V = Self::V>>>>();
+        Self::new_with(
+            players,
+            seats.iter().position(|i| *i == starter).unwrap_or(0),
+            TurnOutcome::First,
+            hashmap!{},
+            self.community().clone(),
+            self.constraint().cloned(),
+            self.config().clone(),
+        )
+    }
+
+    /// Gets a copy of the game with the given player to make the first bet.
+    fn with_first_player(&self, index: usize) -> Self {
+        Self::new_with(
+            self.cloned_players(),
+            index,
+            TurnOutcome::First,
+            hashmap!{},
+            self.deal_community(),
+            self.draw_constraint(),
+            self.config().clone(),
+        )
+    }
+
+    /// The position in the versioned JSON form described by schema::SCHEMA, with every hand.
+    fn to_json(&self) -> serde_json::Value {
+        self.state().to_json()
+    }
+
+    /// Sets up a game of AI players from a position written by to_json.
+    fn from_json(value: &serde_json::Value) -> Result<Self, ScrabrudoError> {
+        schema::game_from_json(value)
+    }
+
+    /// Gets the ID of the winning player, if the game is over.
+    fn winner(&self) -> Option<usize> {
+        match self.current_outcome() {
+            TurnOutcome::Win => Some(self.players()[0].id()),
+            _ => None,
+        }
+    }
+
+    /// Whether only one player is left, so that no more turns can be played.
+    fn is_over(&self) -> bool {
+        self.current_outcome() == &TurnOutcome::Win
+    }
+
+    /// The IDs of the players from first place to last: those still in, by seat, then those out
+    /// of the game, the last to go out first. Once the game is over, these are its final
+    /// standings.
+    fn standings(&self) -> Vec<usize> {
+        self.players()
+            .iter()
+            .map(|p| p.id())
+            .chain(self.eliminated().iter().rev().cloned())
+            .collect()
+    }
+
+    /// Runs the game to completion immutably, returning the finished game.
+    fn run(self) -> Result<Self, ScrabrudoError> {
+        Ok(self.run_with_observer(None, |_| true)?.0)
+    }
+
+    /// Runs the game immutably until it is over, it has played the given number of rounds, or the
+    /// observer, shown every turn as it is played, returns false. Returns the game as it was left
+    /// with a summary of how it went.
+    fn run_with_observer<F: FnMut(&PlayedTurn<Self>) -> bool>(
+        self,
+        max_rounds: Option<usize>,
+        mut observer: F,
+    ) -> Result<(Self, GameResult), ScrabrudoError> {
+        if self.is_over() {
+            let result = GameResult::of(&self, 0, 0);
+            return Ok((self, result));
+        }
+        logging::begin_game();
+        logging::set_round(self.round());
+        pacing::pace(&pacing::Moment::Dealt {
+            round: self.round(),
+        });
+        let seats = self
+            .players()
+            .iter()
+            .map(|p| (p.id(), p.human()))
+            .collect::<Vec<(usize, bool)>>();
+        let active = metrics::ActiveGame::begin();
+        let mut game = self;
+        let mut num_turns = 0;
+        loop {
+            if stepping() {
+                pause(&game)?;
+            }
+            if crash::enabled() {
+                crash::track_game(&game);
+            }
+            verify::track_hands(&game);
+            net::track_game(&game);
+            let round = game.round();
+            let player_id = game.players()[game.current_index()].id();
+            let (outcome, next) = game.play_turn()?;
+            crash::track_move::<Self>(player_id, &outcome);
+            verify::track_move::<Self>(player_id, &outcome);
+            num_turns += 1;
+            let turn = PlayedTurn {
+                round: round,
+                player_id: player_id,
+                before: &game,
+                outcome: &outcome,
+                after: &next,
+            };
+            dashboard::track_turn(&turn);
+            net::track_turn(&turn);
+            let carry_on = observer(&turn);
+            game = next;
+            // The round is only counted as played once it's over.
+            let num_rounds = if game.is_over() {
+                game.round()
+            } else {
+                game.round() - 1
+            };
+            if game.is_over() {
+                leaderboard::record_game(&game, &seats)?;
+                active.finish();
+            } else if carry_on && max_rounds.is_none_or(|max| num_rounds < max) {
+                continue;
+            }
+            let result = GameResult::of(&game, num_rounds, num_turns);
+            return Ok((game, result));
+        }
+    }
+
+    /// Runs a turn and either finishes or sets up for the next turn, returning a full copy of
+    /// the game in the new state.
+    fn run_turn(&self) -> Result<Self, ScrabrudoError> {
+        Ok(self.play_turn()?.1)
+    }
+
+    /// Runs a turn as for run_turn, also returning the outcome the current player chose.
+    fn play_turn(&self) -> Result<(TurnOutcome<Self::B>, Self), ScrabrudoError> {
+        if self.is_over() {
+            return Err(ScrabrudoError::GameOver);
+        }
+        // Every player chooses at once whether to trade, before anyone bets.
+        if self.is_trading() {
+            let actions = self
+                .players()
+                .iter()
+                .map(|p| p.trade(self.config()))
+                .collect::<Result<Vec<TradeAction<Self::V>>, ScrabrudoError>>()?;
+            let outcome = TurnOutcome::Trade(actions);
+            let game = self.apply(outcome.clone())?;
+            self.announce(&outcome, &game);
+            return Ok((outcome, game));
+        }
+        // Get the current state based on this player's move.
+        let player = &self.players()[self.current_index()];
+        logging::set_player(player.id());
+        if player.human() {
+            notify(&Notification::YourTurn {
+                player_id: player.id(),
+            });
+        }
+        let view = self.state().view_for(self.current_index());
+        let mut clocks = self.clocks().clone();
+        let turn_started = Instant::now();
+        let current_outcome = match &self.config().timebank {
+            Some(timebank) if player.human() => {
+                let remaining = timebank.remaining(&clocks, player.id());
+                if remaining > 0 {
+                    info!(
+                        "{}",
+                        Msg::TimeLeft {
+                            player_id: player.id(),
+                            millis: remaining,
+                        }
+                    );
+                    let started = Instant::now();
+                    let due = started + Duration::from_millis(remaining);
+                    let outcome = with_deadline(due, || self.play_current(&view));
+                    clocks = timebank.draw_down(&clocks, player.id(), started.elapsed());
+                    match outcome {
+                        Ok(outcome) if timebank.remaining(&clocks, player.id()) > 0 => outcome,
+                        Ok(_) | Err(ScrabrudoError::OutOfTime(_)) => {
+                            clocks.insert(player.id(), 0);
+                            self.out_of_time(&timebank.penalty, &view)?
+                        }
+                        Err(e) => return Err(e),
+                    }
+                } else {
+                    self.out_of_time(&timebank.penalty, &view)?
+                }
+            }
+            _ => self.play_current(&view)?,
+        };
+        metrics::record_turn(player.human(), turn_started.elapsed());
+        if !player.human() {
+            summarize_move(&view, player.id(), &current_outcome);
+        }
+        pacing::pace(&pacing::Moment::Moved {
+            player_id: player.id(),
+            human: player.human(),
+        });
+        match current_outcome {
+            TurnOutcome::Perudo => metrics::record_challenge(!self.is_correct(&self.last_bet()?)),
+            TurnOutcome::Palafico => {
+                metrics::record_challenge(self.is_exactly_correct(&self.last_bet()?))
+            }
+            _ => (),
+        }
+        match current_outcome {
+            TurnOutcome::Quit | TurnOutcome::Exchange(_) => (),
+            _ => record_turn(self, &current_outcome)?,
+        }
+        debug!("{}", self);
+        let mut game = self.apply(current_outcome.clone())?.with_clocks(clocks);
+        self.announce(&current_outcome, &game);
+        if current_outcome == TurnOutcome::Quit {
+            autosave(&game);
+        }
+        if current_outcome == TurnOutcome::Perudo || current_outcome == TurnOutcome::Palafico {
+            notation::write_round(self, &current_outcome)?;
+            cache::save_prob_cache();
+        }
+        if game.round() > self.round() {
+            logging::set_round(game.round());
+            pacing::pace(&pacing::Moment::Dealt {
+                round: game.round(),
+            });
+            if let Some(config) = dict::apply_reload(game.config()) {
+                game = game.with_config(config);
+                info!("{}", Msg::SwitchedDictionary);
+            }
+        }
+        Ok((current_outcome, game))
+    }
+
+    /// What the current player chooses, seeing the given view of the game. A human away from the
+    /// table has the AI choose for them until they're back, and one gone for good forfeits.
+    fn play_current(
+        &self,
+        view: &GameState<Self::B>,
+    ) -> Result<TurnOutcome<Self::B>, ScrabrudoError> {
+        let player = &self.players()[self.current_index()];
+        loop {
+            if player.human() {
+                match self.config().input.presence(player.id()) {
+                    Presence::Here => {}
+                    Presence::Away => {
+                        let stand_in = player.copy_with(None, Some(false), None);
+                        return stand_in.play(view, self.current_outcome());
+                    }
+                    Presence::Gone => return Ok(TurnOutcome::Quit),
+                }
+            }
+            // A human who leaves while their move is awaited is asked again as they now are.
+            match player.play(view, self.current_outcome()) {
+                Err(ScrabrudoError::Disconnected(_)) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// What the current player plays once out of thinking time, rather than choosing for
+    /// themselves: the AI's choice for them, or forfeiting their seat.
+    fn out_of_time(
+        &self,
+        penalty: &Penalty,
+        view: &GameState<Self::B>,
+    ) -> Result<TurnOutcome<Self::B>, ScrabrudoError> {
+        let player = &self.players()[self.current_index()];
+        info!("{}", Msg::OutOfTime(player.id()));
+        match penalty {
+            Penalty::Safe => player
+                .copy_with(None, Some(false), None)
+                .play(view, self.current_outcome()),
+            Penalty::Forfeit => Ok(TurnOutcome::Quit),
+        }
+    }
+
+    /// Applies an outcome chosen by the player in the given seat, for players who act from
+    /// outside the game loop, rejecting it if it isn't their turn.
+    fn apply_as(
+        &self,
+        seat: usize,
+        current_outcome: TurnOutcome<Self::B>,
+    ) -> Result<Self, ScrabrudoError> {
+        self.check_turn(seat)?;
+        self.apply(current_outcome)
+    }
+
+    /// Rejects a move from the player in the given seat unless it's theirs to make: everyone
+    /// chooses at once whether to trade, and otherwise only the current player moves.
+    fn check_turn(&self, seat: usize) -> Result<(), ScrabrudoError> {
+        if !self.is_trading() && seat != self.current_index() {
+            return Err(ScrabrudoError::OutOfTurn(seat));
+        }
+        Ok(())
+    }
+
+    /// The moves open to the current player. Only the lowest MAX_LEGAL_BETS bets are listed, so
+    /// check_action should be used to tell whether any other bet may be made.
+    fn legal_actions(&self) -> LegalActions<Self::B> {
+        let state = self.state();
+        let player = &self.players()[self.current_index()];
+        let (bets, challenges) = match self.current_outcome() {
+            // Nobody bets until the trading phase has been played.
+            TurnOutcome::First if !self.is_trading() => (Self::B::all(&state), vec![]),
+            TurnOutcome::Bet(bet) => (
+                bet.all_above(&state),
+                if may_challenge(&state, player.hand(), bet) {
+                    vec![TurnOutcome::Perudo, TurnOutcome::Palafico]
+                } else {
+                    vec![]
+                },
+            ),
+            _ => {
+                return LegalActions {
+                    bets: vec![],
+                    more_bets: false,
+                    challenges: vec![],
+                    exchange: false,
+                }
+            }
+        };
+        let mut bets = bets
+            .filter(|b| b.is_valid(&state))
+            .take(MAX_LEGAL_BETS + 1)
+            .map(|b| *b)
+            .collect::<Vec<Self::B>>();
+        let more_bets = bets.len() > MAX_LEGAL_BETS;
+        bets.truncate(MAX_LEGAL_BETS);
+        bets.sort();
+        LegalActions {
+            exchange: can_exchange(self.config(), player.hand()),
+            bets: bets,
+            more_bets: more_bets,
+            challenges: challenges,
+        }
+    }
+
+    /// Checks that the current player may play the given outcome now, giving the reason if not.
+    fn check_action(&self, outcome: &TurnOutcome<Self::B>) -> Result<(), ScrabrudoError> {
+        let player = &self.players()[self.current_index()];
+        match (outcome, self.current_outcome()) {
+            (_, TurnOutcome::Win) => Err(ScrabrudoError::GameOver),
+            (TurnOutcome::Bet(_), _) if self.is_trading() => Err(ScrabrudoError::InvalidTrade(
+                Msg::TradeBeforeBet.to_string(),
+            )),
+            (TurnOutcome::Bet(bet), _) => bet.validate(&self.state()),
+            (TurnOutcome::Perudo, TurnOutcome::Bet(bet))
+            | (TurnOutcome::Palafico, TurnOutcome::Bet(bet)) => {
+                if may_challenge(&self.state(), player.hand(), bet) {
+                    Ok(())
+                } else {
+                    Err(ScrabrudoError::NoRightToChallenge)
+                }
+            }
+            (TurnOutcome::Perudo, _) | (TurnOutcome::Palafico, _) => {
+                Err(ScrabrudoError::NoBetToChallenge)
+            }
+            (TurnOutcome::Exchange(items), _) => {
+                exchanged_hand(self.config(), player.hand(), items).map(|_| ())
+            }
+            (TurnOutcome::Trade(actions), _) => {
+                if !self.is_trading() {
+                    return Err(ScrabrudoError::InvalidTrade(
+                        Msg::TradeOnlyFirst.to_string(),
+                    ));
+                }
+                let hands = self
+                    .players()
+                    .iter()
+                    .map(|p| p.hand())
+                    .collect::<Vec<&Hand<Self::V>>>();
+                check_offers(&hands, actions)
+            }
+            (TurnOutcome::Quit, _) => Ok(()),
+            (outcome, _) => Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome))),
+        }
+    }
+
+    /// Applies an outcome chosen by the current player, returning a full copy of the game in the
+    /// new state, or an error if the outcome can't be played now.
+    fn apply(&self, current_outcome: TurnOutcome<Self::B>) -> Result<Self, ScrabrudoError> {
+        let last_bet = self.last_bet()?;
+        self.check_action(&current_outcome)?;
+        let player = &self.players()[self.current_index()];
+        let num_players = self.players().len();
+        let previous_index = (self.current_index() + num_players - 1) % num_players;
+        let is_challenge = matches!(current_outcome, TurnOutcome::Perudo | TurnOutcome::Palafico);
+        // The trading phase is played once a round, so the flag only carries on with the round.
+        let traded = match current_outcome {
+            TurnOutcome::Trade(_) => true,
+            TurnOutcome::Bet(_) | TurnOutcome::Exchange(_) | TurnOutcome::Quit => self.traded(),
+            _ => false,
+        };
+        let game = match current_outcome {
+            TurnOutcome::Bet(bet) => Ok(Self::new_with(
+                self.cloned_players(),
+                (self.current_index() + 1) % num_players,
+                TurnOutcome::Bet(bet.clone()),
+                self.history_with_bet(player.id(), &bet),
+                self.community().clone(),
+                self.constraint().cloned(),
+                self.config().clone(),
+            )),
+            TurnOutcome::Perudo => {
+                if self.is_correct(&last_bet) {
+                    Ok(self.with_end_turn(self.current_index(), previous_index))
+                } else {
+                    Ok(self.with_end_turn(previous_index, self.current_index()))
+                }
+            }
+            TurnOutcome::Palafico => {
+                if self.is_exactly_correct(&last_bet) {
+                    Ok(self.with_end_turn_palafico(self.current_index()))
+                } else {
+                    Ok(self.with_end_turn(self.current_index(), previous_index))
+                }
+            }
+            TurnOutcome::Exchange(items) => self.with_exchange(&items),
+            TurnOutcome::Trade(actions) => self.with_trades(&actions),
+            TurnOutcome::Quit => Ok(self.with_forfeit(self.current_index())),
+            outcome => Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome))),
+        };
+
+        let game = game?;
+
+        // Note who went out, which the new game doesn't know.
+        let mut eliminated = self.eliminated().clone();
+        eliminated.extend(gone(self, &game));
+        let game = if is_challenge && self.round_limit_reached() && !game.is_over() {
+            let next = game.with_tiebreak();
+            eliminated.extend(gone(&game, &next));
+            next
+        } else {
+            game
+        };
+        // A player leaving the table has the round dealt again, to be traded afresh.
+        let redealt = game.players().len() < self.players().len();
+        let traded = traded && !redealt;
+        // A new round starts once a challenge or a player leaving the table deals again.
+        let round = if game.current_outcome() == &TurnOutcome::First && (is_challenge || redealt) {
+            self.round() + 1
+        } else {
+            self.round()
+        };
+        // Once challenged, every bet this round is seen to have been right or not.
+        let honesty = if is_challenge {
+            record_round(self.honesty(), self.history(), &self.all_items())
+        } else {
+            self.honesty().clone()
+        };
+        let game = game
+            .with_clocks(self.clocks().clone())
+            .with_eliminated(eliminated)
+            .with_traded(traded)
+            .with_round(round)
+            .with_honesty(honesty);
+        Ok(game)
+    }
+
+    /// Whether the game has played as many rounds as its rules allow.
+    fn round_limit_reached(&self) -> bool {
+        self.config()
+            .max_rounds
+            .is_some_and(|max_rounds| self.round() >= max_rounds)
+    }
+
+    /// Tells the table what the current player playing the given outcome did, given the game it
+    /// led to. The game loop does this once apply returns, as apply is also used on copies of
+    /// the game that are only being searched.
+    fn announce(&self, current_outcome: &TurnOutcome<Self::B>, next: &Self) {
+        let player = &self.players()[self.current_index()];
+        match current_outcome {
+            TurnOutcome::Bet(bet) => info!(
+                "{}",
+                paint_seat(
+                    self.current_index(),
+                    &Msg::Bets {
+                        player_id: player.id(),
+                        bet: bet.to_string(),
+                    }
+                    .to_string()
+                )
+            ),
+            TurnOutcome::Perudo | TurnOutcome::Palafico => {
+                self.announce_challenge(current_outcome == &TurnOutcome::Palafico, next)
+            }
+            TurnOutcome::Exchange(items) => info!(
+                "{}",
+                Msg::Exchanges {
+                    player_id: player.id(),
+                    num_items: items.len(),
+                }
+            ),
+            TurnOutcome::Trade(actions) => {
+                for (seat, left) in trading_seats(actions, self.current_index()) {
+                    if let (TradeAction::Offer(_), TradeAction::Offer(_)) =
+                        (&actions[seat], &actions[left])
+                    {
+                        let player_ids = (self.players()[seat].id(), self.players()[left].id());
+                        info!("{}", Msg::Trade(player_ids.0, player_ids.1));
+                        notify(&Notification::Traded {
+                            player_ids: player_ids,
+                        });
+                    }
+                }
+            }
+            TurnOutcome::Quit => match self.config().forfeit {
+                Forfeit::ToAi => info!("{}", Msg::ForfeitsToAi(player.id())),
+                Forfeit::Remove => {
+                    info!("{}", Msg::ForfeitsAndLeaves(player.id()));
+                    if next.is_over() {
+                        info!("{}", Msg::Wins(next.players()[0].id()));
+                    }
+                }
+            },
+            _ => (),
+        }
+        if next.is_over() {
+            notify(&Notification::GameFinished {
+                standings: next.standings(),
+            });
+        }
+    }
+
+    /// Tells the table how the challenge the current player called went, given the game it led
+    /// to.
+    fn announce_challenge(&self, palafico: bool, next: &Self) {
+        let player = &self.players()[self.current_index()];
+        let num_players = self.players().len();
+        let previous_index = (self.current_index() + num_players - 1) % num_players;
+        notify(&Notification::ChallengeCalled {
+            player_id: player.id(),
+            palafico: palafico,
+        });
+        if palafico {
+            info!("{}", Msg::CallsPalafico(player.id()));
+        } else {
+            info!("{}", Msg::CallsPerudo(player.id()));
+        }
+        let last_bet = match self.last_bet() {
+            Ok(bet) => bet,
+            Err(_) => return,
+        };
+        info!("{}", self.tally(&last_bet, palafico));
+        let loser_index = if palafico {
+            if self.is_exactly_correct(&last_bet) {
+                None
+            } else {
+                Some(self.current_index())
+            }
+        } else if self.is_correct(&last_bet) {
+            Some(self.current_index())
+        } else {
+            Some(previous_index)
+        };
+        let mut players_left = num_players;
+        match loser_index {
+            None => {
+                let num_items = player.num_items();
+                info!(
+                    "{}",
+                    Msg::WinsPalafico {
+                        player_id: player.id(),
+                        num_items: if num_items < 5 {
+                            num_items + 1
+                        } else {
+                            num_items
+                        },
+                    }
+                );
+            }
+            Some(loser_index) => {
+                let loser = &self.players()[loser_index];
+                let reveal = self.config().reveal_on_loss;
+                let items_left = if reveal {
+                    loser.num_items() - loser.hand().revealed - 1
+                } else {
+                    loser.num_items() - 1
+                };
+                notify(&Notification::LostItem {
+                    player_id: loser.id(),
+                    human: loser.human(),
+                    items_left: items_left,
+                });
+                if items_left == 0 {
+                    info!("{}", Msg::Disqualified(loser.id()));
+                    players_left -= 1;
+                    if players_left == 1 {
+                        info!("{}", Msg::Wins(next.players()[0].id()));
+                    }
+                } else if reveal {
+                    info!(
+                        "{}",
+                        Msg::Reveals {
+                            player_id: loser.id(),
+                            num_shown: loser.hand().revealed + 1,
+                            num_items: loser.num_items(),
+                        }
+                    );
+                } else {
+                    info!(
+                        "{}",
+                        Msg::Loses {
+                            player_id: loser.id(),
+                            num_items: loser.num_items() - 1,
+                        }
+                    );
+                }
+            }
+        }
+        // Past the last round, the round just played was the tiebreak's.
+        if players_left > 1 && self.round_limit_reached() {
+            if next.is_over() {
+                info!("{}", Msg::WinsWithMostLeft(next.players()[0].id()));
+            } else {
+                info!(
+                    "{}",
+                    Msg::SuddenDeath(next.players().iter().map(|p| p.id()).collect())
+                );
+            }
+        }
+    }
+}
+
+/// The IDs of the players in the first game but not the second, those with fewest items hidden
+/// first.
+fn gone<G: Game>(before: &G, after: &G) -> Vec<usize> {
+    let mut gone = before
+        .players()
+        .iter()
+        .filter(|p| !after.players().iter().any(|q| q.id() == p.id()))
+        .collect::<Vec<_>>();
+    gone.sort_by_key(|p| p.num_items() - p.hand().revealed);
+    gone.iter().map(|p| p.id()).collect()
+}
+
+pub struct PerudoGame {
+    pub players: Vec<Arc<dyn Player<B = PerudoBet, V = Die>>>,
+    pub current_index: usize,
+    pub current_outcome: TurnOutcome<PerudoBet>,
+    pub history: History<PerudoBet>,
+    pub community: Vec<Die>,
+    pub constraint: Option<Constraint>,
+    pub config: GameConfig,
+    pub clocks: Clocks,
+    pub eliminated: Vec<usize>,
+    pub traded: bool,
+    pub round: usize,
+    pub honesty: Honesty,
+}
+
+impl fmt::Display for PerudoGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Hands: {:?}",
+            (&self.players)
+                .into_iter()
+                .map(|p| format!("{}", p))
+                .collect::<Vec<String>>()
+                .join(" | ")
+        )
+    }
+}
+
+impl Game for PerudoGame {
+    type V = Die;
+    type B = PerudoBet;
+    type P = PerudoPlayer;
+
+    fn create_player(
+        id: usize,
+        items_per_player: usize,
+        human: bool,
+        opening: OpeningPolicy,
+    ) -> Box<dyn Player<B = Self::B, V = Self::V>> {
+        Box::new(PerudoPlayer {
+            id: id,
+            human: human,
+            opening: opening,
+            hand: Hand::<Die>::new(items_per_player as u32),
+        })
+    }
+
+    fn players(&self) -> &Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
+        &self.players
+    }
+
+    fn current_outcome(&self) -> &TurnOutcome<Self::B> {
+        &self.current_outcome
+    }
+
+    fn current_index(&self) -> usize {
+        self.current_index
+    }
+
+    fn history(&self) -> &History<Self::B> {
+        &self.history
+    }
+
+    fn community(&self) -> &Vec<Self::V> {
+        &self.community
+    }
+
+    fn constraint(&self) -> Option<&Constraint> {
+        self.constraint.as_ref()
+    }
+
+    fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    fn with_config(self, config: GameConfig) -> Self {
+        Self {
+            config: config,
+            ..self
+        }
+    }
+
+    fn new_with(
+        players: Vec<Arc<dyn Player<B = Self::B, V = Self::V>>>,
+        current_index: usize,
+        current_outcome: TurnOutcome<Self::B>,
+        history: History<Self::B>,
+        community: Vec<Self::V>,
+        constraint: Option<Constraint>,
+        config: GameConfig,
+    ) -> Self {
+        Self {
+            players: players,
+            current_index: current_index,
+            current_outcome: current_outcome,
+            history: history,
+            community: community,
+            constraint: constraint,
+            config: config,
+            clocks: hashmap! {},
+            eliminated: vec![],
+            traded: false,
+            round: 1,
+            honesty: hashmap! {},
+        }
+    }
+
+    fn clocks(&self) -> &Clocks {
+        &self.clocks
+    }
+
+    fn with_clocks(self, clocks: Clocks) -> Self {
+        Self {
+            clocks: clocks,
+            ..self
+        }
+    }
+
+    fn eliminated(&self) -> &Vec<usize> {
+        &self.eliminated
+    }
+
+    fn with_eliminated(self, eliminated: Vec<usize>) -> Self {
+        Self {
+            eliminated: eliminated,
+            ..self
+        }
+    }
+
+    fn traded(&self) -> bool {
+        self.traded
+    }
+
+    fn with_traded(self, traded: bool) -> Self {
+        Self {
+            traded: traded,
+            ..self
+        }
+    }
+
+    fn round(&self) -> usize {
+        self.round
+    }
 
-            if players.len() > 1 {
-                return Self::new_with(players, current_index, TurnOutcome::First, hashmap!{});
-            } else {
-                info!("Player {} wins!", players[0].id());
-                return Self::new_with(players, 0, TurnOutcome::Win, hashmap!{});
-            }
-        } else {
-            // Refresh all players, loser loses an item.
-            let players = self.refreshed_players_with_loss(loser_index);
-            info!(
-                "Player {} loses an item, now has {}",
-                players[loser_index].id(),
-                players[loser_index].num_items()
-            );
-            // Reset and prepare for the next turn.
-            return Self::new_with(players, loser_index, TurnOutcome::First, hashmap!{});
+    fn with_round(self, round: usize) -> Self {
+        Self {
+            round: round,
+            ..self
         }
     }
 
-    /// Ends the turn in Palafico and returns the new game state.
-    fn with_end_turn_palafico(&self, winner_index: usize) -> Self {
-        // Refresh all players, winner maybe gains a item.
-        let players = self.refreshed_players_with_gain(winner_index);
-        let winner = &players[winner_index];
-        info!(
-            "Player {} wins Palafico, now has {}",
-            winner.id(),
-            winner.num_items()
-        );
-        Self::new_with(players, winner_index, TurnOutcome::First, hashmap!{})
+    fn honesty(&self) -> &Honesty {
+        &self.honesty
     }
 
-    /// Runs the game to completion immutably.
-    fn run(self) {
-        let mut game = self;
-        loop {
-            game = game.run_turn();
-            match game.current_outcome() {
-                TurnOutcome::Win => return,
-                _ => continue,
-            }
+    fn with_honesty(self, honesty: Honesty) -> Self {
+        Self {
+            honesty: honesty,
+            ..self
         }
     }
 
-    /// Runs a turn and either finishes or sets up for the next turn, returning a full copy of
-    /// the game in the new state.
-    fn run_turn(&self) -> Self {
-        let last_bet = self.last_bet();
+    fn num_logical_items(&self, val: Die) -> usize {
+        if val == Die::One {
+            self.num_items_with(Die::One)
+        } else {
+            self.num_items_with(Die::One) + self.num_items_with(val)
+        }
+    }
 
-        // Get the current state based on this player's move.
-        let player = &self.players()[self.current_index()];
-        let current_outcome = player.play(&self.state(), &self.current_outcome());
+    fn is_correct(&self, bet: &PerudoBet) -> bool {
+        let max_correct_bet = PerudoBet {
+            value: bet.value.clone(),
+            quantity: self.num_logical_items(bet.value.clone()),
+        };
+        bet <= &max_correct_bet
+    }
 
-        debug!("{}", self);
-        match current_outcome {
-            TurnOutcome::Bet(bet) => {
-                info!("Player {} bets {}", player.id(), bet);
-                Self::new_with(
-                    self.cloned_players(),
-                    (self.current_index() + 1) % self.players().len(),
-                    TurnOutcome::Bet(bet.clone()),
-                    self.history_with_bet(self.current_index(), &bet),
-                )
-            }
-            TurnOutcome::Perudo => {
-                info!("Player {} calls Perudo", player.id());
-                let loser_index: usize;
-                if self.is_correct(&last_bet) {
-                    loser_index = self.current_index();
-                } else {
-                    loser_index =
-                        (self.current_index() + self.players().len() - 1) % self.players().len();
-                };
-                self.with_end_turn(loser_index)
-            }
-            TurnOutcome::Palafico => {
-                info!("Player {} calls Palafico", player.id());
-                if self.is_exactly_correct(&last_bet) {
-                    self.with_end_turn_palafico(self.current_index())
-                } else {
-                    self.with_end_turn(self.current_index())
-                }
-            }
-            _ => panic!(),
+    fn is_exactly_correct(&self, bet: &PerudoBet) -> bool {
+        self.num_logical_items(bet.value.clone()) == bet.quantity
+    }
+
+    fn tally(&self, bet: &PerudoBet, exactly: bool) -> Msg {
+        let verdict = if exactly && self.is_exactly_correct(bet) {
+            Verdict::ExactlyCorrect
+        } else if !exactly && self.is_correct(bet) {
+            Verdict::Correct
+        } else {
+            Verdict::Incorrect
+        };
+        Msg::Counted {
+            verdict: verdict,
+            count: self.num_logical_items(bet.value.clone()),
+            value: format!("{:?}", bet.value),
         }
     }
 }
 
-pub struct PerudoGame {
-    pub players: Vec<Box<dyn Player<B = PerudoBet, V = Die>>>,
+pub struct ScrabrudoGame {
+    pub players: Vec<Arc<dyn Player<B = ScrabrudoBet, V = Tile>>>,
     pub current_index: usize,
-    pub current_outcome: TurnOutcome<PerudoBet>,
-    pub history: History<PerudoBet>,
+    pub current_outcome: TurnOutcome<ScrabrudoBet>,
+    pub history: History<ScrabrudoBet>,
+    pub community: Vec<Tile>,
+    pub constraint: Option<Constraint>,
+    pub config: GameConfig,
+    pub clocks: Clocks,
+    pub eliminated: Vec<usize>,
+    pub traded: bool,
+    pub round: usize,
+    pub honesty: Honesty,
 }
 
-impl fmt::Display for PerudoGame {
+impl fmt::Display for ScrabrudoGame {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
@@ -317,24 +1692,26 @@ impl fmt::Display for PerudoGame {
     }
 }
 
-impl Game for PerudoGame {
-    type V = Die;
-    type B = PerudoBet;
-    type P = PerudoPlayer;
+impl Game for ScrabrudoGame {
+    type V = Tile;
+    type B = ScrabrudoBet;
+    type P = ScrabrudoPlayer;
 
     fn create_player(
         id: usize,
         items_per_player: usize,
         human: bool,
+        opening: OpeningPolicy,
     ) -> Box<dyn Player<B = Self::B, V = Self::V>> {
-        Box::new(PerudoPlayer {
+        Box::new(ScrabrudoPlayer {
             id: id,
             human: human,
-            hand: Hand::<Die>::new(items_per_player as u32),
+            opening: opening,
+            hand: Hand::<Tile>::new(items_per_player as u32),
         })
     }
 
-    fn players(&self) -> &Vec<Box<dyn Player<B = Self::B, V = Self::V>>> {
+    fn players(&self) -> &Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
         &self.players
     }
 
@@ -350,75 +1727,146 @@ impl Game for PerudoGame {
         &self.history
     }
 
+    fn community(&self) -> &Vec<Self::V> {
+        &self.community
+    }
+
+    fn constraint(&self) -> Option<&Constraint> {
+        self.constraint.as_ref()
+    }
+
+    fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    fn with_config(self, config: GameConfig) -> Self {
+        Self {
+            config: config,
+            ..self
+        }
+    }
+
     fn new_with(
-        players: Vec<Box<dyn Player<B = Self::B, V = Self::V>>>,
+        players: Vec<Arc<dyn Player<B = Self::B, V = Self::V>>>,
         current_index: usize,
         current_outcome: TurnOutcome<Self::B>,
         history: History<Self::B>,
+        community: Vec<Self::V>,
+        constraint: Option<Constraint>,
+        config: GameConfig,
     ) -> Self {
         Self {
             players: players,
             current_index: current_index,
             current_outcome: current_outcome,
             history: history,
+            community: community,
+            constraint: constraint,
+            config: config,
+            clocks: hashmap! {},
+            eliminated: vec![],
+            traded: false,
+            round: 1,
+            honesty: hashmap! {},
         }
     }
 
-    fn num_logical_items(&self, val: Die) -> usize {
-        if val == Die::One {
-            self.num_items_with(Die::One)
-        } else {
-            self.num_items_with(Die::One) + self.num_items_with(val)
+    fn clocks(&self) -> &Clocks {
+        &self.clocks
+    }
+
+    fn with_clocks(self, clocks: Clocks) -> Self {
+        Self {
+            clocks: clocks,
+            ..self
         }
     }
 
-    fn is_correct(&self, bet: &PerudoBet) -> bool {
-        let max_correct_bet = PerudoBet {
-            value: bet.value.clone(),
-            quantity: self.num_logical_items(bet.value.clone()),
-        };
-        let is_correct = bet <= &max_correct_bet;
-
-        // Log out the outcome.
-        let actual_amount = self.num_logical_items(bet.value.clone());
-        info!(
-            "Bet was {}, there were {} {:?}s",
-            if is_correct { "correct" } else { "incorrect" },
-            actual_amount,
-            bet.value
-        );
+    fn eliminated(&self) -> &Vec<usize> {
+        &self.eliminated
+    }
 
-        is_correct
+    fn with_eliminated(self, eliminated: Vec<usize>) -> Self {
+        Self {
+            eliminated: eliminated,
+            ..self
+        }
     }
 
-    fn is_exactly_correct(&self, bet: &PerudoBet) -> bool {
-        let is_exactly_correct = self.num_logical_items(bet.value.clone()) == bet.quantity;
-
-        // Log out the outcome.
-        let actual_amount = self.num_logical_items(bet.value.clone());
-        info!(
-            "Bet was {}, there were {} {:?}s",
-            if is_exactly_correct {
-                "exactly correct"
-            } else {
-                "incorrect"
-            },
-            actual_amount,
-            bet.value
-        );
+    fn traded(&self) -> bool {
+        self.traded
+    }
+
+    fn with_traded(self, traded: bool) -> Self {
+        Self {
+            traded: traded,
+            ..self
+        }
+    }
+
+    fn round(&self) -> usize {
+        self.round
+    }
+
+    fn with_round(self, round: usize) -> Self {
+        Self {
+            round: round,
+            ..self
+        }
+    }
+
+    fn honesty(&self) -> &Honesty {
+        &self.honesty
+    }
+
+    fn with_honesty(self, honesty: Honesty) -> Self {
+        Self {
+            honesty: honesty,
+            ..self
+        }
+    }
+
+    fn num_logical_items(&self, val: Tile) -> usize {
+        // TODO: Update if we introduce blanks / wildcards.
+        self.num_items_with(val)
+    }
 
-        is_exactly_correct
+    fn is_correct(&self, bet: &ScrabrudoBet) -> bool {
+        bet.is_correct(&self.all_items(), false)
+    }
+
+    // We say it's exact if no letter goes over, and if we need to use blanks, we use all of them.
+    fn is_exactly_correct(&self, bet: &ScrabrudoBet) -> bool {
+        bet.is_correct(&self.all_items(), true)
+    }
+
+    fn tally(&self, bet: &ScrabrudoBet, exactly: bool) -> Msg {
+        let all_tiles = self.all_items();
+        Msg::Found {
+            correct: bet.is_correct(&all_tiles, exactly),
+            exactly: exactly,
+            bet: bet.as_word().to_string(),
+            items: format!("{:?}", all_tiles),
+        }
     }
 }
 
-pub struct ScrabrudoGame {
-    pub players: Vec<Box<dyn Player<B = ScrabrudoBet, V = Tile>>>,
+pub struct EquationGame {
+    pub players: Vec<Arc<dyn Player<B = EquationBet, V = Symbol>>>,
     pub current_index: usize,
-    pub current_outcome: TurnOutcome<ScrabrudoBet>,
-    pub history: History<ScrabrudoBet>,
+    pub current_outcome: TurnOutcome<EquationBet>,
+    pub history: History<EquationBet>,
+    pub community: Vec<Symbol>,
+    pub constraint: Option<Constraint>,
+    pub config: GameConfig,
+    pub clocks: Clocks,
+    pub eliminated: Vec<usize>,
+    pub traded: bool,
+    pub round: usize,
+    pub honesty: Honesty,
 }
 
-impl fmt::Display for ScrabrudoGame {
+impl fmt::Display for EquationGame {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
@@ -432,24 +1880,26 @@ impl fmt::Display for ScrabrudoGame {
     }
 }
 
-impl Game for ScrabrudoGame {
-    type V = Tile;
-    type B = ScrabrudoBet;
-    type P = ScrabrudoPlayer;
+impl Game for EquationGame {
+    type V = Symbol;
+    type B = EquationBet;
+    type P = EquationPlayer;
 
     fn create_player(
         id: usize,
         items_per_player: usize,
         human: bool,
+        opening: OpeningPolicy,
     ) -> Box<dyn Player<B = Self::B, V = Self::V>> {
-        Box::new(ScrabrudoPlayer {
+        Box::new(EquationPlayer {
             id: id,
             human: human,
-            hand: Hand::<Tile>::new(items_per_player as u32),
+            opening: opening,
+            hand: Hand::<Symbol>::new(items_per_player as u32),
         })
     }
 
-    fn players(&self) -> &Vec<Box<dyn Player<B = Self::B, V = Self::V>>> {
+    fn players(&self) -> &Vec<Arc<dyn Player<B = Self::B, V = Self::V>>> {
         &self.players
     }
 
@@ -465,57 +1915,125 @@ impl Game for ScrabrudoGame {
         &self.history
     }
 
+    fn community(&self) -> &Vec<Self::V> {
+        &self.community
+    }
+
+    fn constraint(&self) -> Option<&Constraint> {
+        self.constraint.as_ref()
+    }
+
+    fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    fn with_config(self, config: GameConfig) -> Self {
+        Self {
+            config: config,
+            ..self
+        }
+    }
+
     fn new_with(
-        players: Vec<Box<dyn Player<B = Self::B, V = Self::V>>>,
+        players: Vec<Arc<dyn Player<B = Self::B, V = Self::V>>>,
         current_index: usize,
         current_outcome: TurnOutcome<Self::B>,
         history: History<Self::B>,
+        community: Vec<Self::V>,
+        constraint: Option<Constraint>,
+        config: GameConfig,
     ) -> Self {
         Self {
             players: players,
             current_index: current_index,
             current_outcome: current_outcome,
             history: history,
+            community: community,
+            constraint: constraint,
+            config: config,
+            clocks: hashmap! {},
+            eliminated: vec![],
+            traded: false,
+            round: 1,
+            honesty: hashmap! {},
         }
     }
 
-    fn num_logical_items(&self, val: Tile) -> usize {
-        // TODO: Update if we introduce blanks / wildcards.
-        self.num_items_with(val)
+    fn clocks(&self) -> &Clocks {
+        &self.clocks
     }
 
-    fn is_correct(&self, bet: &ScrabrudoBet) -> bool {
-        let all_tiles = self.all_items();
-        let is_correct = bet.is_correct(&all_tiles, false);
-
-        // Log out the outcome.
-        info!(
-            "Bet was {}, {} is{}in {:?}",
-            if is_correct { "correct" } else { "incorrect" },
-            bet.as_word(),
-            if is_correct { " " } else { " not " },
-            all_tiles
-        );
+    fn with_clocks(self, clocks: Clocks) -> Self {
+        Self {
+            clocks: clocks,
+            ..self
+        }
+    }
 
-        is_correct
+    fn eliminated(&self) -> &Vec<usize> {
+        &self.eliminated
     }
 
-    // Lots of duplication here - subtle differences inside.
-    // We say it's exact if no letter goes over, and if we need to use blanks, we use all of them.
-    fn is_exactly_correct(&self, bet: &ScrabrudoBet) -> bool {
-        let all_tiles = self.all_items();
-        let is_correct = bet.is_correct(&all_tiles, true);
-
-        // Log out the outcome.
-        info!(
-            "Bet was {}, {} is{}exactly in {:?}",
-            if is_correct { "correct" } else { "incorrect" },
-            bet.as_word(),
-            if is_correct { " " } else { " not " },
-            all_tiles
-        );
+    fn with_eliminated(self, eliminated: Vec<usize>) -> Self {
+        Self {
+            eliminated: eliminated,
+            ..self
+        }
+    }
+
+    fn traded(&self) -> bool {
+        self.traded
+    }
+
+    fn with_traded(self, traded: bool) -> Self {
+        Self {
+            traded: traded,
+            ..self
+        }
+    }
+
+    fn round(&self) -> usize {
+        self.round
+    }
+
+    fn with_round(self, round: usize) -> Self {
+        Self {
+            round: round,
+            ..self
+        }
+    }
+
+    fn honesty(&self) -> &Honesty {
+        &self.honesty
+    }
+
+    fn with_honesty(self, honesty: Honesty) -> Self {
+        Self {
+            honesty: honesty,
+            ..self
+        }
+    }
+
+    fn num_logical_items(&self, val: Symbol) -> usize {
+        self.num_items_with(val)
+    }
+
+    fn is_correct(&self, bet: &EquationBet) -> bool {
+        bet.is_correct(&self.all_items(), false)
+    }
 
-        is_correct
+    fn is_exactly_correct(&self, bet: &EquationBet) -> bool {
+        bet.is_correct(&self.all_items(), true)
+    }
+
+    fn tally(&self, bet: &EquationBet, exactly: bool) -> Msg {
+        let all_symbols = self.all_items();
+        Msg::Found {
+            correct: bet.is_correct(&all_symbols, exactly),
+            exactly: exactly,
+            bet: bet.to_string(),
+            items: format!("{:?}", all_symbols),
+        }
     }
 }
 
@@ -527,20 +2045,24 @@ speculate! {
     it "constrains bet correctness including palafico" {
         let game = ScrabrudoGame {
             players: vec![
-                Box::new(ScrabrudoPlayer {
+                Arc::new(ScrabrudoPlayer {
                     id: 0,
                     human: false,
+                    opening: OpeningPolicy::Likeliest,
                     hand: Hand::<Tile>{
                         items: vec![
                             Tile::Blank,
                             Tile::C,
                             Tile::T,
                         ],
+                        revealed: 0,
+                        exchanged: false,
                     },
                 }),
-                Box::new(ScrabrudoPlayer {
+                Arc::new(ScrabrudoPlayer {
                     id: 1,
                     human: false,
+                    opening: OpeningPolicy::Likeliest,
                     hand: Hand::<Tile>{
                         items: vec![
                             Tile::B,
@@ -550,12 +2072,22 @@ speculate! {
                             Tile::S,
                             Tile::E,
                         ],
+                        revealed: 0,
+                        exchanged: false,
                     },
                 })
             ],
             current_index: 0,
             current_outcome: TurnOutcome::First,
             history: hashmap!{},
+            community: vec![],
+            constraint: None,
+            config: GameConfig::default(),
+            clocks: hashmap!{},
+            eliminated: vec![],
+            traded: false,
+            round: 1,
+            honesty: hashmap!{},
         };
 
         // Cat is there, but has dupes
@@ -577,33 +2109,381 @@ speculate! {
     it "records bets" {
         let game = ScrabrudoGame {
             players: vec![
-                Box::new(ScrabrudoPlayer {
+                Arc::new(ScrabrudoPlayer {
                     id: 0,
                     human: false,
+                    opening: OpeningPolicy::Likeliest,
                     hand: Hand::<Tile>{
                         items: vec![
                             Tile::T,
                             Tile::O,
                         ],
+                        revealed: 0,
+                        exchanged: false,
                     },
                 }),
-                Box::new(ScrabrudoPlayer {
+                Arc::new(ScrabrudoPlayer {
                     id: 1,
                     human: false,
+                    opening: OpeningPolicy::Likeliest,
                     hand: Hand::<Tile>{
                         items: vec![
                             Tile::O,
                         ],
+                        revealed: 0,
+                        exchanged: false,
                     },
                 })
             ],
             current_index: 0,
             current_outcome: TurnOutcome::First,
             history: hashmap!{},
+            community: vec![],
+            constraint: None,
+            config: GameConfig::default(),
+            clocks: hashmap!{},
+            eliminated: vec![],
+            traded: false,
+            round: 1,
+            honesty: hashmap!{},
         };
-        let next_game = game.run_turn();
+        let next_game = game.run_turn().unwrap();
 
         // Whatever the first bet is, there should be one item in the next round.
         assert_eq!(1, next_game.history.len());
     }
+
+    it "records bets by the ID of the player who made them" {
+        let mut position = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, GameConfig::default()).to_json();
+        position["player_ids"] = serde_json::json!([3, 7]);
+        let game = PerudoGame::from_json(&position).unwrap();
+        let next_game = game.apply(TurnOutcome::Bet(PerudoBet { value: Die::Two, quantity: 1 })).unwrap();
+        assert_eq!(vec![3], next_game.history.keys().cloned().collect::<Vec<usize>>());
+        assert_eq!(Some(1), next_game.state().seat_of(7));
+    }
+
+    it "rejects illegal outcomes" {
+        let config = GameConfig::default();
+        let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, config);
+        match game.apply(TurnOutcome::Perudo) {
+            Err(ScrabrudoError::NoBetToChallenge) => (),
+            _ => panic!("Challenged without a bet"),
+        }
+        match game.apply(TurnOutcome::First) {
+            Err(ScrabrudoError::UnexpectedOutcome(_)) => (),
+            _ => panic!("Applied a non-move"),
+        }
+
+        let bet = PerudoBet {
+            value: Die::Three,
+            quantity: 2,
+        };
+        let next_game = game.apply(TurnOutcome::Bet(bet.clone())).unwrap();
+        assert_eq!(bet, next_game.last_bet().unwrap());
+        match next_game.apply(TurnOutcome::Bet(bet.clone())) {
+            Err(ScrabrudoError::InvalidBet(_)) => (),
+            _ => panic!("Repeated a bet"),
+        }
+        match next_game.apply_as(0, TurnOutcome::Bet(PerudoBet { quantity: 3, ..bet })) {
+            Err(ScrabrudoError::OutOfTurn(0)) => (),
+            _ => panic!("Played out of turn"),
+        }
+        assert!(next_game.check_turn(1).is_ok());
+        let config = GameConfig { trades: true, ..GameConfig::default() };
+        let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, config);
+        assert!(game.check_turn(1).is_ok());
+    }
+
+    it "lists the legal actions" {
+        let config = GameConfig::default();
+        let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, config);
+        let actions = game.legal_actions();
+        assert!(actions.challenges.is_empty());
+        assert!(!actions.exchange && !actions.more_bets);
+        assert!(actions.bets.iter().all(|b| game.check_action(&TurnOutcome::Bet(b.clone())).is_ok()));
+
+        let bet = PerudoBet { value: Die::Three, quantity: 2 };
+        let next_game = game.apply(TurnOutcome::Bet(bet.clone())).unwrap();
+        let actions = next_game.legal_actions();
+        assert_eq!(vec![TurnOutcome::Perudo, TurnOutcome::Palafico], actions.challenges);
+        assert!(actions.bets.iter().all(|b| *b > bet));
+        assert_eq!(Some(&PerudoBet { value: Die::Four, quantity: 2 }), actions.bets.first());
+        assert!(next_game.check_action(&TurnOutcome::Perudo).is_ok());
+        assert!(next_game.check_action(&TurnOutcome::Bet(bet)).is_err());
+    }
+
+    it "hands a forfeited seat to the AI" {
+        let config = GameConfig::default();
+        let game = PerudoGame::new(3, 2, hashset!{1}, OpeningPolicy::Likeliest, config);
+        let next_game = game.with_forfeit(1);
+        assert_eq!(3, next_game.players().len());
+        assert!(!next_game.players()[1].human());
+        assert_eq!(game.all_items(), next_game.all_items());
+    }
+
+    it "removes a forfeited seat" {
+        let config = GameConfig { forfeit: Forfeit::Remove, ..GameConfig::default() };
+        let game = PerudoGame::new(3, 2, hashset!{1}, OpeningPolicy::Likeliest, config.clone());
+        let next_game = game.with_forfeit(1);
+        assert_eq!(vec![0, 2], next_game.players().iter().map(|p| p.id()).collect::<Vec<usize>>());
+        assert_eq!(2, next_game.players()[next_game.current_index()].id());
+
+        let game = PerudoGame::new(2, 2, hashset!{0}, OpeningPolicy::Likeliest, config);
+        assert_eq!(Some(1), game.with_forfeit(0).winner());
+    }
+
+    it "ends with final standings" {
+        let config = GameConfig { forfeit: Forfeit::Remove, ..GameConfig::default() };
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config);
+        let next_game = game.apply(TurnOutcome::Quit).unwrap();
+        assert!(!next_game.is_over());
+        assert_eq!(vec![1, 2, 0], next_game.standings());
+        let finished = next_game.apply(TurnOutcome::Quit).unwrap();
+        assert!(finished.is_over());
+        assert_eq!(vec![2, 1, 0], finished.standings());
+        match finished.play_turn() {
+            Err(ScrabrudoError::GameOver) => (),
+            _ => panic!("Played on after the game was over"),
+        }
+        assert_eq!(vec![2, 1, 0], finished.run().unwrap().standings());
+    }
+
+    it "runs a game until told to stop" {
+        let config = GameConfig::default();
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        let mut rounds = hashset!{};
+        let (game, result) = game.run_with_observer(Some(1), |turn| {
+            rounds.insert(turn.round);
+            true
+        }).unwrap();
+        assert_eq!(hashset!{1}, rounds);
+        assert!(!game.is_over());
+        assert_eq!(None, result.winner);
+        assert_eq!(1, result.num_rounds);
+
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        let (_, result) = game.run_with_observer(None, |_| false).unwrap();
+        assert_eq!((0, 1), (result.num_rounds, result.num_turns));
+
+        let game = PerudoGame::new(2, 1, hashset!{}, OpeningPolicy::Likeliest, config);
+        let (game, result) = game.run_with_observer(None, |_| true).unwrap();
+        assert_eq!(game.winner(), result.winner);
+        assert_eq!(1, result.num_rounds);
+        assert_eq!(game.standings(), result.standings);
+    }
+
+    it "plays an equation game to the end" {
+        let game = EquationGame::new(2, 3, hashset!{}, OpeningPolicy::Likeliest, GameConfig::default());
+        let finished = game.run().unwrap();
+        assert!(finished.winner().is_some());
+        assert_eq!(1, finished.players().len());
+    }
+
+    it "counts the rounds as they are dealt" {
+        let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, GameConfig::default());
+        let bet = PerudoBet { value: Die::Three, quantity: 1 };
+        let next_game = game.apply(TurnOutcome::Bet(bet)).unwrap();
+        assert_eq!(1, next_game.round());
+        assert_eq!(2, next_game.apply(TurnOutcome::Perudo).unwrap().round());
+    }
+
+    it "records how the bets held up once challenged" {
+        let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, GameConfig::default());
+        let bet = PerudoBet { value: Die::Three, quantity: 1 };
+        let next_game = game.apply(TurnOutcome::Bet(bet.clone())).unwrap();
+        assert!(next_game.honesty().is_empty());
+        let right = if game.is_correct(&bet) { 1 } else { 0 };
+        let finished = next_game.apply(TurnOutcome::Perudo).unwrap();
+        assert_eq!(&hashmap!{ 0 => (right, 1) }, finished.honesty());
+    }
+
+    it "breaks ties once out of rounds" {
+        let config = GameConfig { max_rounds: Some(1), ..GameConfig::default() };
+        let bet = PerudoBet { value: Die::Three, quantity: 1 };
+        let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        let finished = game.apply(TurnOutcome::Bet(bet.clone())).unwrap().apply(TurnOutcome::Perudo).unwrap();
+        assert!(finished.is_over());
+        assert_eq!(2, finished.players()[0].num_items());
+
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config);
+        let sudden_death = game.apply(TurnOutcome::Bet(bet)).unwrap().apply(TurnOutcome::Perudo).unwrap();
+        assert!(!sudden_death.is_over());
+        assert_eq!(vec![1, 1], sudden_death.num_items_per_player());
+        assert_eq!(1, sudden_death.eliminated().len());
+        let loser = sudden_death.eliminated()[0];
+        let finished = sudden_death.run().unwrap();
+        assert!(finished.is_over());
+        assert_eq!(loser, finished.standings()[2]);
+    }
+
+    it "reveals an item rather than losing one" {
+        let config = GameConfig { reveal_on_loss: true, ..GameConfig::default() };
+        let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, config);
+        let next_game = game.with_end_turn(1, 0);
+        assert_eq!(vec![2, 2], next_game.num_items_per_player());
+        assert_eq!(1, next_game.players()[1].hand().revealed);
+        let state = next_game.state();
+        assert_eq!(next_game.players()[1].items()[..1].to_vec(), state.revealed[1]);
+        assert_eq!(state.revealed[1], GameState { current_index: 0, ..state.clone() }.known_items());
+
+        // Once everything would be shown, the player is out.
+        assert_eq!(Some(0), next_game.with_end_turn(1, 0).winner());
+    }
+
+    it "deals items to the middle each round" {
+        let config = GameConfig { community: 2, ..GameConfig::default() };
+        let game = PerudoGame::new(2, 3, hashset!{}, OpeningPolicy::Likeliest, config);
+        assert_eq!(2, game.community().len());
+        assert_eq!(8, game.all_items().len());
+        assert_eq!(6, game.state().total_num_items);
+        assert_eq!(game.community(), &game.state().known_items());
+
+        let bet = PerudoBet { value: Die::Three, quantity: 1 };
+        let next_game = game.apply(TurnOutcome::Bet(bet)).unwrap();
+        assert_eq!(game.community(), next_game.community());
+        assert_eq!(2, next_game.with_end_turn(0, 1).community().len());
+    }
+
+    it "shows each player only their own hand" {
+        let config = GameConfig::default();
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config);
+        let state = game.state();
+        assert!(!state.is_view_for(1));
+        let view = state.view_for(1);
+        assert!(view.is_view_for(1));
+        assert_eq!(game.players()[1].items(), &view.hands[1]);
+        assert!(view.hands[0].is_empty() && view.hands[2].is_empty());
+        assert_eq!(state.num_items_per_player, view.num_items_per_player);
+    }
+
+    it "exchanges items once a round" {
+        let mut config = GameConfig::default();
+        let game = PerudoGame::new(2, 3, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        let items = game.players()[0].items()[..2].to_vec();
+        match game.apply(TurnOutcome::Exchange(items.clone())) {
+            Err(ScrabrudoError::InvalidExchange(_)) => (),
+            _ => panic!("Exchanged without the rule"),
+        }
+
+        config.exchanges = true;
+        let game = PerudoGame::new(2, 3, hashset!{}, OpeningPolicy::Likeliest, config);
+        let items = game.players()[0].items()[..2].to_vec();
+        let next_game = game.apply(TurnOutcome::Exchange(items.clone())).unwrap();
+        assert_eq!(0, next_game.current_index());
+        assert_eq!(&TurnOutcome::First, next_game.current_outcome());
+        assert_eq!(3, next_game.players()[0].num_items());
+        assert!(next_game.players()[0].hand().exchanged);
+        match next_game.apply(TurnOutcome::Exchange(items)) {
+            Err(ScrabrudoError::InvalidExchange(_)) => (),
+            _ => panic!("Exchanged twice"),
+        }
+
+        // Having exchanged, the player can bet but not challenge.
+        let bet = PerudoBet { value: Die::Three, quantity: 1 };
+        let next_game = next_game.apply(TurnOutcome::Bet(bet.clone())).unwrap();
+        let next_game = next_game.apply(TurnOutcome::Bet(PerudoBet { quantity: 2, ..bet })).unwrap();
+        match next_game.apply(TurnOutcome::Perudo) {
+            Err(ScrabrudoError::NoRightToChallenge) => (),
+            _ => panic!("Challenged after exchanging"),
+        }
+        assert!(!next_game.with_end_turn(0, 1).players()[0].hand().exchanged);
+    }
+
+    it "trades between neighbours once a round" {
+        let config = GameConfig { trades: true, ..GameConfig::default() };
+        let game = PerudoGame::new(3, 3, hashset!{}, OpeningPolicy::Likeliest, config);
+        assert!(game.is_trading());
+        let bet = PerudoBet { value: Die::Three, quantity: 1 };
+        match game.apply(TurnOutcome::Bet(bet.clone())) {
+            Err(ScrabrudoError::InvalidTrade(_)) => (),
+            _ => panic!("Bet before trading"),
+        }
+
+        let given = game.players()[0].items()[0].clone();
+        let received = game.players()[1].items()[0].clone();
+        let actions = vec![
+            TradeAction::Offer(given.clone()),
+            TradeAction::Offer(received.clone()),
+            TradeAction::Pass,
+        ];
+        let next_game = game.apply(TurnOutcome::Trade(actions.clone())).unwrap();
+        assert!(!next_game.is_trading());
+        assert_eq!(&TurnOutcome::First, next_game.current_outcome());
+        assert_eq!(vec![3, 3, 3], next_game.num_items_per_player());
+        assert_eq!(received, next_game.players()[0].items()[0]);
+        assert_eq!(given, next_game.players()[1].items()[0]);
+        assert_eq!(game.players()[2].items(), next_game.players()[2].items());
+        assert!(next_game.apply(TurnOutcome::Trade(actions)).is_err());
+
+        // The next round trades again.
+        let next_game = next_game.apply(TurnOutcome::Bet(bet)).unwrap();
+        assert!(next_game.with_end_turn(0, 1).is_trading());
+    }
+
+    it "chooses who starts the next round" {
+        let mut config = GameConfig::default();
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        assert_eq!(0, game.current_index());
+        assert_eq!(1, game.with_end_turn(1, 0).current_index());
+
+        config.round_starter = RoundStarter::Winner;
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        assert_eq!(0, game.with_end_turn(1, 0).current_index());
+
+        // The loser is knocked out, so the winner moves down a seat.
+        let game = PerudoGame::new(3, 1, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        let next_game = game.with_end_turn(1, 2);
+        assert_eq!(2, next_game.players()[next_game.current_index()].id());
+
+        config.round_starter = RoundStarter::LeftOfLoser;
+        let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, config.clone());
+        assert_eq!(0, game.with_end_turn(2, 1).current_index());
+    }
+    it "plays for a human away from the table" {
+        let presence = |presence: Presence| {
+            let input = InputHandle::new(Box::new(|player_id| Err(ScrabrudoError::Disconnected(player_id.unwrap()))))
+                .with_presence(Box::new(move |_| presence));
+            let config = GameConfig { input: input, ..GameConfig::default() };
+            PerudoGame::new(2, 2, hashset!{0}, OpeningPolicy::Likeliest, config)
+        };
+        let (outcome, next_game) = presence(Presence::Away).play_turn().unwrap();
+        assert!(matches!(outcome, TurnOutcome::Bet(_)));
+        assert!(next_game.players()[0].human());
+
+        let (outcome, next_game) = presence(Presence::Gone).play_turn().unwrap();
+        assert_eq!(TurnOutcome::Quit, outcome);
+        assert!(!next_game.players()[0].human());
+    }
+
+    it "plays for a human out of thinking time" {
+        let config = GameConfig { timebank: Some(Timebank { millis: 0, penalty: Penalty::Safe }), ..GameConfig::default() };
+        let game = PerudoGame::new(2, 2, hashset!{0}, OpeningPolicy::Likeliest, config.clone());
+        assert_eq!(hashmap!{ 0 => 0 }, game.state().clocks);
+        let next_game = game.run_turn().unwrap();
+        assert_eq!(1, next_game.current_index());
+        assert!(next_game.players()[0].human());
+
+        // The clocks carry over from turn to turn.
+        let game = game.with_clocks(hashmap!{ 0 => 5 });
+        let bet = PerudoBet { value: Die::Three, quantity: 1 };
+        assert_eq!(&hashmap!{ 0 => 5 }, game.apply(TurnOutcome::Bet(bet)).unwrap().clocks());
+
+        let config = GameConfig { timebank: Some(Timebank { millis: 0, penalty: Penalty::Forfeit }), ..config };
+        let game = PerudoGame::new(2, 2, hashset!{0}, OpeningPolicy::Likeliest, config);
+        let next_game = game.run_turn().unwrap();
+        assert!(!next_game.players()[0].human());
+        assert!(next_game.state().clocks.is_empty());
+
+        // A move that doesn't come in by the deadline is given up on, and the penalty applied.
+        let input = InputHandle::new(Box::new(|player_id| match move_deadline() {
+            Some(_) => Err(ScrabrudoError::OutOfTime(player_id.unwrap())),
+            None => panic!("Waited for a move without a deadline"),
+        }));
+        let timebank = Timebank { millis: 60000, penalty: Penalty::Safe };
+        let config = GameConfig { input: input, timebank: Some(timebank), ..GameConfig::default() };
+        let game = PerudoGame::new(2, 2, hashset!{0}, OpeningPolicy::Likeliest, config);
+        let next_game = game.run_turn().unwrap();
+        assert_eq!(1, next_game.current_index());
+        assert_eq!(&hashmap!{ 0 => 0 }, next_game.clocks());
+    }
 }
@@ -0,0 +1,113 @@
+/// Golden games: seeded all-AI games played in full and written in game notation, then checked
+/// against a record kept alongside the code, so that any change to how the AI or the rules play
+/// shows up as a difference from the record.
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+use crate::notation::*;
+use crate::opening::*;
+use crate::rng;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::fs;
+
+/// Plays an all-AI game from the given seed, returning every round in game notation, then who
+/// won. The game draws from a generator of its own, so other games may be played meanwhile.
+pub fn play_golden<G: Game>(
+    seed: u64,
+    num_players: usize,
+    items_per_player: usize,
+    opening: &OpeningPolicy,
+    config: &GameConfig,
+) -> Result<String, ScrabrudoError> {
+    rng::with_seed(seed, || {
+        let mut lines = vec![
+            GameNotation::<G::B> {
+                game_id: None,
+                rounds: vec![],
+            }
+            .to_string(),
+            format!("[Seed \"{}\"]", seed),
+        ];
+        let (game, _) = G::new(
+            num_players,
+            items_per_player,
+            hashset! {},
+            opening.clone(),
+            config.clone(),
+        )
+        .run_with_observer(None, |turn| {
+            if turn.outcome == &TurnOutcome::Perudo || turn.outcome == &TurnOutcome::Palafico {
+                lines.push(
+                    RoundNotation::from_game(turn.before, turn.round, turn.outcome).to_string(),
+                );
+            }
+            true
+        })?;
+        lines.push(format!("; Player {} wins", game.winner().unwrap()));
+        Ok(lines.join("\n") + "\n")
+    })
+}
+
+/// Checks a game against the record at the given path, failing at the first line that differs,
+/// or writes the record afresh when blessing a change that was meant.
+pub fn check_golden(path: &str, record: &str, bless: bool) -> Result<(), ScrabrudoError> {
+    if bless {
+        fs::write(path, record)?;
+        info!("Blessed the record at {}", path);
+        return Ok(());
+    }
+    let golden = fs::read_to_string(path)?;
+    let expected = golden.lines().collect::<Vec<&str>>();
+    let actual = record.lines().collect::<Vec<&str>>();
+    for i in 0..expected.len().max(actual.len()) {
+        if expected.get(i) != actual.get(i) {
+            return Err(ScrabrudoError::Drift(format!(
+                "{} line {} was {}, now {}",
+                path,
+                i + 1,
+                expected.get(i).unwrap_or(&"the end"),
+                actual.get(i).unwrap_or(&"the end")
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "golden games" {
+        it "plays the same game from the same seed" {
+            let play = |seed| play_golden::<PerudoGame>(
+                seed, 3, 2, &OpeningPolicy::Likeliest, &GameConfig::default()).unwrap();
+            let record = play(1);
+            assert_eq!(record, play(1));
+            assert!(record.starts_with("[Variant \"perudo\"]\n[Seed \"1\"]\n1. "));
+            assert_eq!(1, GameNotation::<crate::bet::PerudoBet>::parse_all(&record).unwrap().len());
+        }
+
+        it "plays as its golden record says" {
+            let record = play_golden::<PerudoGame>(
+                1, 3, 5, &OpeningPolicy::Likeliest, &GameConfig::default()).unwrap();
+            check_golden("data/golden/perudo_3_players_seed_1.txt", &record, false).unwrap();
+        }
+
+        it "reports where a game drifts" {
+            std::fs::write("/tmp/golden.txt", "[Variant \"perudo\"]\n1. 0:1x2\n").unwrap();
+            match check_golden("/tmp/golden.txt", "[Variant \"perudo\"]\n1. 0:1x3\n", false) {
+                Err(ScrabrudoError::Drift(reason)) => {
+                    assert_eq!("/tmp/golden.txt line 2 was 1. 0:1x2, now 1. 0:1x3", reason);
+                }
+                _ => panic!("Missed a drift"),
+            }
+            assert!(check_golden("/tmp/golden.txt", "[Variant \"perudo\"]\n1. 0:1x2\n", false).is_ok());
+            check_golden("/tmp/golden.txt", "[Variant \"perudo\"]\n", true).unwrap();
+            assert!(check_golden("/tmp/golden.txt", "[Variant \"perudo\"]\n1. 0:1x2\n", false).is_err());
+        }
+    }
+}
@@ -1,12 +1,20 @@
 use crate::die::*;
 /// Logic related to dealing hands.
+use crate::notation::*;
+use crate::schema::*;
 use crate::testing;
 use crate::tile::*;
 
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use speculate::speculate;
+use std::fmt;
+use std::hash::Hash;
 
 /// Anything that can make up a hand.
-pub trait Holdable {
+pub trait Holdable:
+    Clone + Ord + Hash + fmt::Debug + Serialize + DeserializeOwned + Schema + Notation + Send + Sync
+{
     fn get_random() -> Self;
 }
 
@@ -37,6 +45,12 @@ impl<T: Holdable> Dealer<T> for RandomDealer {
 #[derive(Debug, Clone)]
 pub struct Hand<T: Holdable> {
     pub items: Vec<T>,
+
+    /// How many of the items, from the first, are face up for every player to see.
+    pub revealed: usize,
+
+    /// Whether the player has exchanged items this round.
+    pub exchanged: bool,
 }
 
 impl<T: Holdable> Hand<T> {
@@ -44,7 +58,55 @@ impl<T: Holdable> Hand<T> {
         Self {
             // TODO: Inject dealer for testing purposes.
             items: RandomDealer::new().deal_n(n),
+            revealed: 0,
+            exchanged: false,
+        }
+    }
+
+    /// Deals a hand of n items with as many of them face up as the given number, or all of them
+    /// if there are fewer.
+    pub fn new_revealing(n: u32, revealed: usize) -> Self {
+        Self {
+            revealed: revealed.min(n as usize),
+            ..Self::new(n)
+        }
+    }
+
+    /// The items every player can see.
+    pub fn revealed_items(&self) -> &[T] {
+        &self.items[..self.revealed.min(self.items.len())]
+    }
+
+    /// The hand with the given hidden items swapped for freshly dealt ones, or None if they
+    /// aren't all held face down. Items face up stay as they are.
+    pub fn exchanging(&self, items: &[T]) -> Option<Self> {
+        let revealed = self.revealed.min(self.items.len());
+        let mut hidden = self.items[revealed..].to_vec();
+        for item in items {
+            let i = hidden.iter().position(|h| h == item)?;
+            hidden.remove(i);
         }
+        let mut exchanged = self.items[..revealed].to_vec();
+        exchanged.extend(hidden);
+        exchanged.extend(RandomDealer::new().deal_n(items.len() as u32));
+        Some(Self {
+            items: exchanged,
+            revealed: self.revealed,
+            exchanged: true,
+        })
+    }
+
+    /// The hand with the given hidden item given away for another, or None if it isn't held face
+    /// down.
+    pub fn trading(&self, given: &T, received: T) -> Option<Self> {
+        let revealed = self.revealed.min(self.items.len());
+        let i = revealed + self.items[revealed..].iter().position(|h| h == given)?;
+        let mut items = self.items.clone();
+        items[i] = received;
+        Some(Self {
+            items: items,
+            ..self.clone()
+        })
     }
 }
 
@@ -60,6 +122,40 @@ speculate! {
         }
     }
 
+    describe "revealing items" {
+        it "shows the first items dealt" {
+            let hand = Hand::<Die>::new_revealing(5, 2);
+            assert_eq!(&hand.items[..2], hand.revealed_items());
+            assert_eq!(3, Hand::<Die>::new_revealing(3, 4).revealed_items().len());
+        }
+    }
+
+    describe "exchanging items" {
+        it "swaps only hidden items" {
+            let hand = Hand::<Die> {
+                items: vec![Die::Two, Die::Three, Die::Three, Die::Four],
+                revealed: 1,
+                exchanged: false,
+            };
+            let exchanged = hand.exchanging(&[Die::Three, Die::Four]).unwrap();
+            assert_eq!(4, exchanged.items.len());
+            assert_eq!(&[Die::Two, Die::Three], &exchanged.items[..2]);
+            assert!(exchanged.exchanged);
+            assert!(hand.exchanging(&[Die::Two]).is_none());
+            assert!(hand.exchanging(&[Die::Four, Die::Four]).is_none());
+        }
+
+        it "trades only hidden items" {
+            let hand = Hand::<Die> {
+                items: vec![Die::Two, Die::Three],
+                revealed: 1,
+                exchanged: false,
+            };
+            assert_eq!(vec![Die::Two, Die::Six], hand.trading(&Die::Three, Die::Six).unwrap().items);
+            assert!(hand.trading(&Die::Two, Die::Six).is_none());
+        }
+    }
+
     describe "dealing tiles" {
         it "deals a hand of five" {
             let hand = Hand::<Tile>::new(5);
@@ -0,0 +1,168 @@
+/// Heatmaps: how likely a bet is to be right from a given rack, by the length of the word and the
+/// number of its letters the rack is missing, for plotting when tuning rules and dictionaries.
+use crate::dict::*;
+use crate::error::*;
+use crate::multiset::*;
+use crate::tile::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::fs::File;
+use std::io::Write;
+
+/// The average chance of a bet being right, indexed by word length and then by the number of
+/// letters missing from the rack. A cell is None where no word the lookup covers falls in it.
+pub type Heatmap = Vec<Vec<Option<f64>>>;
+
+/// Works out the heatmap for the given rack with the given number of tiles around the table,
+/// from every word in the dictionary. With exact, it is the chance of there being exactly the
+/// word's letters, as for Palafico, otherwise at least them. Blanks in the rack aren't counted on.
+pub fn heatmap(
+    dict: &Dict,
+    lookup: &Lookup,
+    rack: &[Tile],
+    table_size: usize,
+    exact: bool,
+) -> Result<Heatmap, ScrabrudoError> {
+    if exact && !lookup.meta().exact {
+        return Err(ScrabrudoError::InvalidOption(
+            "The lookup has no exact probabilities".into(),
+        ));
+    }
+    let num_unseen = table_size.checked_sub(rack.len()).ok_or_else(|| {
+        ScrabrudoError::InvalidOption(format!(
+            "A rack of {} doesn't fit a table of {}",
+            rack.len(),
+            table_size
+        ))
+    })?;
+    match lookup.max_num_tiles() {
+        Some(max_num_tiles) if max_num_tiles >= num_unseen => (),
+        max_num_tiles => {
+            return Err(ScrabrudoError::InvalidOption(format!(
+                "The lookup goes up to {} tiles unseen, not {}",
+                max_num_tiles.unwrap_or(0),
+                num_unseen
+            )))
+        }
+    }
+    let held = rack
+        .iter()
+        .filter(|t| **t != Tile::Blank)
+        .flat_map(|t| t.spelling().chars().collect::<Vec<char>>())
+        .collect::<Multiset<char>>();
+
+    // Words with the same letters have the same chance, so each set of letters is looked up once
+    // and counted for every word spelled with it.
+    let max_length = table_size;
+    let mut totals = vec![vec![(0.0, 0); max_length + 1]; max_length + 1];
+    for (letters, words) in dict.anagrams().iter() {
+        if letters.len() > max_length {
+            continue;
+        }
+        let missing = letters
+            .chars()
+            .collect::<Multiset<char>>()
+            .difference(&held)
+            .to_vec()
+            .into_iter()
+            .collect::<String>();
+        let p = if missing.len() > num_unseen {
+            Some(0.0)
+        } else if missing.is_empty() && !exact {
+            Some(1.0)
+        } else {
+            let probs = if exact {
+                lookup.exact_probs(&missing)
+            } else {
+                lookup.probs(&missing)
+            };
+            probs.and_then(|ps| ps.get(num_unseen).cloned())
+        };
+        if let Some(p) = p {
+            let cell = &mut totals[letters.len()][missing.len()];
+            cell.0 += p * words.len() as f64;
+            cell.1 += words.len();
+        }
+    }
+    Ok(totals
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(total, n)| if n == 0 { None } else { Some(total / n as f64) })
+                .collect()
+        })
+        .collect())
+}
+
+/// Shows the heatmap as CSV, one row per word length with a column per number of missing
+/// letters, leaving out lengths with no words covered.
+pub fn heatmap_csv(heatmap: &Heatmap) -> String {
+    let mut lines = vec![format!(
+        "length,{}",
+        (0..heatmap.len())
+            .map(|n| format!("missing_{}", n))
+            .collect::<Vec<String>>()
+            .join(",")
+    )];
+    for (length, row) in heatmap.iter().enumerate() {
+        if row.iter().all(|p| p.is_none()) {
+            continue;
+        }
+        let cells = row
+            .iter()
+            .map(|p| p.map_or("".into(), |p| format!("{:.4}", p)))
+            .collect::<Vec<String>>();
+        lines.push(format!("{},{}", length, cells.join(",")));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Writes the heatmap for the given rack and table size, from the current dictionary and lookup,
+/// to the given path as CSV.
+pub fn export_heatmap(
+    path: &str,
+    rack: &[Tile],
+    table_size: usize,
+    exact: bool,
+) -> Result<(), ScrabrudoError> {
+    let heatmap = heatmap(&current_dict(), &current_lookup(), rack, table_size, exact)?;
+    let mut f = File::create(path)?;
+    write!(f, "{}", heatmap_csv(&heatmap))?;
+    info!("Wrote the heatmap to {}", path);
+    Ok(())
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "heatmaps" {
+        it "averages the chance of each cell" {
+            let rack = vec![Tile::C, Tile::A, Tile::T];
+            let heatmap = heatmap(&current_dict(), &current_lookup(), &rack, 7, false).unwrap();
+            assert_eq!(8, heatmap.len());
+            // Every word spelled from the rack alone is certain, and none can miss more letters
+            // than it has.
+            assert_eq!(Some(1.0), heatmap[3][0]);
+            assert_eq!(None, heatmap[3][4]);
+            let p_one = heatmap[3][1].unwrap();
+            let p_two = heatmap[3][2].unwrap();
+            assert!(p_one > p_two && p_two > 0.0);
+            // Words missing more letters than there are tiles unseen can't be made.
+            assert_eq!(Some(0.0), heatmap[5][5]);
+        }
+
+        it "writes CSV" {
+            let heatmap = vec![vec![None, None], vec![Some(1.0), Some(0.25)]];
+            assert_eq!("length,missing_0,missing_1\n1,1.0000,0.2500\n", heatmap_csv(&heatmap));
+        }
+
+        it "refuses tables it can't work out" {
+            assert!(heatmap(&current_dict(), &current_lookup(), &[Tile::A, Tile::B], 1, false).is_err());
+            assert!(heatmap(&current_dict(), &current_lookup(), &[Tile::A], 100, false).is_err());
+        }
+    }
+}
@@ -0,0 +1,252 @@
+/// Where human players' moves come from: the terminal, unless a frontend supplies them instead,
+/// so that nothing in the engine itself needs a terminal. At the terminal, lines can be edited
+/// with the arrow keys, earlier lines recalled, and words completed with tab.
+use crate::error::*;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+#[cfg(test)]
+use speculate::speculate;
+use std::cell::Cell;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+/// A function giving the next line entered for the given player, or by whoever is stepping
+/// through the game if none.
+pub type Source = Box<dyn Fn(Option<usize>) -> Result<String, ScrabrudoError> + Send + Sync>;
+
+/// A function telling whether the player with the given ID is at the table.
+pub type PresenceSource = Box<dyn Fn(usize) -> Presence + Send + Sync>;
+
+/// A function giving the words that complete the given start of one.
+pub type Completions = Box<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+// Each game is played on a thread of its own, so the move awaited on one has its own deadline.
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+lazy_static! {
+    static ref COMPLETIONS: RwLock<Option<Completions>> = RwLock::new(None);
+    static ref EDITOR: Mutex<Option<Editor<WordHelper>>> = Mutex::new(None);
+}
+
+/// Whether a human is at the table to give their moves, for those playing over the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Here,
+
+    /// Gone for now, with the AI playing in their place until they're back.
+    Away,
+
+    /// Gone for good, forfeiting their seat.
+    Gone,
+}
+
+/// Where a game's lines come from. Copies of a game share its source, and a game given none
+/// reads from the terminal, so games played at once each take their own players' moves.
+#[derive(Clone, Default)]
+pub struct InputHandle {
+    source: Option<Arc<Source>>,
+
+    /// Who is at the table, everyone if not given.
+    presence: Option<Arc<PresenceSource>>,
+}
+
+impl InputHandle {
+    pub fn new(source: Source) -> Self {
+        Self {
+            source: Some(Arc::new(source)),
+            presence: None,
+        }
+    }
+
+    /// The same source, telling who is at the table as given.
+    pub fn with_presence(self, presence: PresenceSource) -> Self {
+        Self {
+            presence: Some(Arc::new(presence)),
+            ..self
+        }
+    }
+
+    /// Whether the player with the given ID is at the table.
+    pub fn presence(&self, player_id: usize) -> Presence {
+        match &self.presence {
+            Some(presence) => presence(player_id),
+            None => Presence::Here,
+        }
+    }
+
+    /// Reads the next line entered for the given player, or by whoever is stepping through the
+    /// game if none.
+    pub fn read_line(&self, player_id: Option<usize>) -> Result<String, ScrabrudoError> {
+        match &self.source {
+            Some(source) => source(player_id),
+            None => read_terminal_line(),
+        }
+    }
+}
+
+impl fmt::Debug for InputHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source {
+            Some(_) => write!(f, "InputHandle(source)"),
+            None => write!(f, "InputHandle(terminal)"),
+        }
+    }
+}
+
+/// Handles are the same if they share a source, or both read from the terminal, and tell who is
+/// at the table the same way.
+impl PartialEq for InputHandle {
+    fn eq(&self, other: &Self) -> bool {
+        let same_source = match (&self.source, &other.source) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        let same_presence = match (&self.presence, &other.presence) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_source && same_presence
+    }
+}
+
+impl Eq for InputHandle {}
+
+impl Hash for InputHandle {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Runs the given function with moves read on this thread due by the given deadline, for sources
+/// able to stop waiting for one once it has passed. The terminal isn't, so a move typed there
+/// late is only found to be so once it's in.
+pub fn with_deadline<T>(deadline: Instant, f: impl FnOnce() -> T) -> T {
+    let outer = DEADLINE.with(|due| due.replace(Some(deadline)));
+    let result = f();
+    DEADLINE.with(|due| due.set(outer));
+    result
+}
+
+/// When the move being read on this thread is due, if it has a deadline.
+pub fn move_deadline() -> Option<Instant> {
+    DEADLINE.with(|due| due.get())
+}
+
+/// Completes words typed at the terminal with the given function from now on.
+pub fn init_completion(completions: Completions) {
+    *COMPLETIONS.write().unwrap() = Some(completions);
+}
+
+/// Reads the next line typed at the terminal.
+fn read_terminal_line() -> Result<String, ScrabrudoError> {
+    let mut editor = EDITOR.lock().unwrap();
+    let editor = editor.get_or_insert_with(|| {
+        let mut editor = Editor::new();
+        editor.set_helper(Some(WordHelper));
+        editor
+    });
+    match editor.readline("") {
+        Ok(line) => {
+            if !line.trim().is_empty() {
+                editor.add_history_entry(line.as_str());
+            }
+            Ok(line)
+        }
+        // As with reading stdin directly, the end of input is an empty line.
+        Err(ReadlineError::Eof) => Ok(String::new()),
+        Err(ReadlineError::Io(e)) => Err(ScrabrudoError::Io(e)),
+        Err(e) => Err(ScrabrudoError::Io(io::Error::new(
+            io::ErrorKind::Interrupted,
+            e.to_string(),
+        ))),
+    }
+}
+
+/// The words completing the one under the cursor, and where it starts. Commands, starting with
+/// *, aren't completed.
+fn complete_word(line: &str, pos: usize) -> (usize, Vec<String>) {
+    let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let prefix = &line[start..pos];
+    if prefix.is_empty() || prefix.starts_with('*') {
+        return (start, vec![]);
+    }
+    match COMPLETIONS.read().unwrap().as_ref() {
+        Some(completions) => (start, completions(prefix)),
+        None => (start, vec![]),
+    }
+}
+
+/// Completes words at the terminal, and otherwise leaves lines as typed.
+struct WordHelper;
+
+impl Completer for WordHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_word(line, pos))
+    }
+}
+
+impl Hinter for WordHelper {
+    type Hint = String;
+}
+
+impl Highlighter for WordHelper {}
+
+impl Validator for WordHelper {}
+
+impl Helper for WordHelper {}
+
+#[cfg(test)]
+speculate! {
+    describe "input" {
+        it "reads lines from a game's own source" {
+            let input = InputHandle::new(Box::new(|player_id| Ok(format!("{:?}", player_id))));
+            assert_eq!("Some(1)", input.read_line(Some(1)).unwrap());
+            assert_eq!("None", input.read_line(None).unwrap());
+        }
+
+        it "keeps each game's source to itself" {
+            let a = InputHandle::new(Box::new(|_| Ok("a".into())));
+            let b = InputHandle::new(Box::new(|_| Ok("b".into())));
+            assert_eq!("a", a.clone().read_line(Some(1)).unwrap());
+            assert_eq!("b", b.read_line(Some(1)).unwrap());
+            assert_eq!(a, a.clone());
+            assert_ne!(a, b);
+        }
+
+        it "has everyone at the table unless told otherwise" {
+            let input = InputHandle::new(Box::new(|_| Ok("".into())));
+            assert_eq!(Presence::Here, input.presence(1));
+            let input = input.with_presence(Box::new(|player_id| match player_id {
+                0 => Presence::Away,
+                _ => Presence::Gone,
+            }));
+            assert_eq!(Presence::Away, input.presence(0));
+            assert_eq!(Presence::Gone, input.presence(1));
+        }
+
+        it "completes the word under the cursor" {
+            init_completion(Box::new(|prefix| vec![format!("{}t", prefix)]));
+            assert_eq!((0, vec!["cat".to_string()]), complete_word("ca", 2));
+            assert_eq!((4, vec!["bat".to_string()]), complete_word("cat ba", 6));
+            assert_eq!((0, vec![]), complete_word("*p", 2));
+            assert_eq!((4, vec![]), complete_word("cat ", 4));
+        }
+    }
+}
@@ -0,0 +1,1173 @@
+/// The message catalog: every prompt, table message and error shown to players, in each language
+/// the game can be played in.
+#[cfg(test)]
+use speculate::speculate;
+use std::fmt;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref LANG: Mutex<Lang> = Mutex::new(Lang::En);
+}
+
+/// A language messages can be shown in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    En,
+    Es,
+    De,
+}
+
+impl Lang {
+    /// Parses a language from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "en" => Some(Lang::En),
+            "es" => Some(Lang::Es),
+            "de" => Some(Lang::De),
+            _ => None,
+        }
+    }
+}
+
+/// Sets the language messages are shown in.
+pub fn init_lang(language: Lang) {
+    let mut lang = LANG.lock().unwrap();
+    *lang = language;
+}
+
+/// Gets the language messages are shown in.
+pub fn lang() -> Lang {
+    *LANG.lock().unwrap()
+}
+
+/// How a challenged bet turned out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Correct,
+    ExactlyCorrect,
+    Incorrect,
+}
+
+/// Which items a hint or prompt is about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Items {
+    Dice,
+    Tiles,
+    Symbols,
+}
+
+/// A message shown to players, with whatever it needs to be written out. Items, bets and the like
+/// are passed already written, as they read the same in every language.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Msg {
+    // What happens at the table.
+    Disqualified(usize),
+    Wins(usize),
+    Reveals {
+        player_id: usize,
+        num_shown: usize,
+        num_items: usize,
+    },
+    Loses {
+        player_id: usize,
+        num_items: usize,
+    },
+    WinsPalafico {
+        player_id: usize,
+        num_items: usize,
+    },
+    ForfeitsToAi(usize),
+    ForfeitsAndLeaves(usize),
+    Exchanges {
+        player_id: usize,
+        num_items: usize,
+    },
+    Trade(usize, usize),
+    WinsWithMostLeft(usize),
+    SuddenDeath(Vec<usize>),
+    TimeLeft {
+        player_id: usize,
+        millis: u64,
+    },
+    SwitchedDictionary,
+    OutOfTime(usize),
+    Disconnected(usize),
+    Reconnected(usize),
+    Bets {
+        player_id: usize,
+        bet: String,
+    },
+    CallsPerudo(usize),
+    CallsPalafico(usize),
+    /// How many of a die there were, for a Perudo bet.
+    Counted {
+        verdict: Verdict,
+        count: usize,
+        value: String,
+    },
+    /// Whether a word or identity was found among everything on the table.
+    Found {
+        correct: bool,
+        exactly: bool,
+        bet: String,
+        items: String,
+    },
+
+    // What a human is shown on their turn.
+    ItemsLeft {
+        items: Items,
+        per_player: String,
+        total: usize,
+    },
+    HandFor(String),
+    RackFor {
+        player_id: usize,
+        rack: String,
+    },
+    LettersRemaining(String),
+    CanGuarantee(String),
+    BetPrompt {
+        items: Items,
+        first: bool,
+    },
+    ExchangePrompt(Items),
+    TradePrompt(String),
+    ExpectedUnknownTiles(String),
+    Scores {
+        word: String,
+        score: u32,
+    },
+    SingleWordOnly,
+    NotInDict,
+    ThisRound(String),
+    InTheMiddle(String),
+    Revealed(String),
+    SeatShows {
+        seat: usize,
+        items: String,
+    },
+    LikelyOnTable(String),
+    Deductions(String),
+    RackWords {
+        num_words: usize,
+        num_unknown: usize,
+        words: String,
+    },
+    YourTurn(usize),
+    BetsThisRound(String),
+    SummaryBet {
+        player_id: usize,
+        bet: String,
+        detail: Option<String>,
+    },
+    SummaryCall {
+        player_id: usize,
+        palafico: bool,
+        bet: String,
+    },
+    SummaryExchange {
+        player_id: usize,
+        num_items: usize,
+    },
+    /// A summary with how much of the table its bet claims.
+    SummaryClaim {
+        line: String,
+        size: usize,
+        total: usize,
+        items: Items,
+    },
+    LettersWorth {
+        letters: usize,
+        score: u32,
+    },
+    DemoIntro {
+        num_players: usize,
+        items_per_player: usize,
+        seed: u64,
+    },
+    /// Why an AI chose its move, by how it chose it: greedy, mcts or learned.
+    DemoChose {
+        player_id: usize,
+        chosen: String,
+        method: String,
+    },
+    DemoWeighed(String),
+    DemoPruned(String),
+    DemoAnyRaise {
+        chance: f64,
+        forced: bool,
+    },
+    SeatBets {
+        seat: usize,
+        bets: String,
+    },
+    SeatHas {
+        seat: usize,
+        count: usize,
+    },
+    TileWorth {
+        tile: String,
+        score: u32,
+    },
+    BlankTile,
+    TrackerRow {
+        tile: String,
+        yours: usize,
+        expected: f64,
+        chance: f64,
+    },
+
+    // Why a move isn't allowed.
+    NotHighEnough,
+    AlreadyBet,
+    Banned(String),
+    LettersLonger {
+        letters: usize,
+        bet: String,
+    },
+    ScoresMore {
+        score: u32,
+        bet: String,
+    },
+    EmptyWord(String),
+    NotIdentity(String),
+    ExchangesNotAllowed,
+    AlreadyExchanged,
+    NothingToExchange,
+    NotHeldFaceDown(String),
+    SeatNotHolding {
+        seat: usize,
+        item: String,
+    },
+    ChoicesForPlayers {
+        num_choices: usize,
+        num_players: usize,
+    },
+    TradeBeforeBet,
+    TradeOnlyFirst,
+    NotATile(char),
+    NotADie(String),
+    NoBetToChallenge,
+    NoRightToChallenge,
+    OutOfTurn(usize),
+    UnexpectedOutcome(String),
+    GameOver,
+    ScenarioFailed(String),
+    Drift(String),
+    Leaderboard(String),
+}
+
+impl Msg {
+    /// The message written out in the given language.
+    pub fn text(&self, lang: Lang) -> String {
+        match lang {
+            Lang::En => self.en(),
+            Lang::Es => self.es(),
+            Lang::De => self.de(),
+        }
+    }
+
+    /// The message in English.
+    fn en(&self) -> String {
+        match self {
+            Msg::Disqualified(id) => format!("Player {} is disqualified", id),
+            Msg::Wins(id) => format!("Player {} wins!", id),
+            Msg::Reveals {
+                player_id,
+                num_shown,
+                num_items,
+            } => format!(
+                "Player {} reveals an item, now shows {} of {}",
+                player_id, num_shown, num_items
+            ),
+            Msg::Loses {
+                player_id,
+                num_items,
+            } => format!("Player {} loses an item, now has {}", player_id, num_items),
+            Msg::WinsPalafico {
+                player_id,
+                num_items,
+            } => format!("Player {} wins Palafico, now has {}", player_id, num_items),
+            Msg::ForfeitsToAi(id) => format!("Player {} forfeits, the AI takes over", id),
+            Msg::ForfeitsAndLeaves(id) => format!("Player {} forfeits and leaves the table", id),
+            Msg::Exchanges {
+                player_id,
+                num_items,
+            } => format!("Player {} exchanges {} items", player_id, num_items),
+            Msg::Trade(a, b) => format!("Players {} and {} trade an item each", a, b),
+            Msg::WinsWithMostLeft(id) => format!("Player {} wins with the most items left", id),
+            Msg::SuddenDeath(ids) => format!("Out of rounds, players {:?} play sudden death", ids),
+            Msg::TimeLeft { player_id, millis } => {
+                format!("Player {} has {}ms left", player_id, millis)
+            }
+            Msg::SwitchedDictionary => "Switched dictionary for the new round".into(),
+            Msg::OutOfTime(id) => format!("Player {} is out of time", id),
+            Msg::Disconnected(id) => format!("Player {} has lost their connection", id),
+            Msg::Reconnected(id) => format!("Player {} is back", id),
+            Msg::Bets { player_id, bet } => format!("Player {} bets {}", player_id, bet),
+            Msg::CallsPerudo(id) => format!("Player {} calls Perudo", id),
+            Msg::CallsPalafico(id) => format!("Player {} calls Palafico", id),
+            Msg::Counted {
+                verdict,
+                count,
+                value,
+            } => format!(
+                "Bet was {}, there were {} {}s",
+                match verdict {
+                    Verdict::Correct => "correct",
+                    Verdict::ExactlyCorrect => "exactly correct",
+                    Verdict::Incorrect => "incorrect",
+                },
+                count,
+                value
+            ),
+            Msg::Found {
+                correct,
+                exactly,
+                bet,
+                items,
+            } => format!(
+                "Bet was {}, {} is{}{}in {}",
+                if *correct { "correct" } else { "incorrect" },
+                bet,
+                if *correct { " " } else { " not " },
+                if *exactly { "exactly " } else { "" },
+                items
+            ),
+            Msg::ItemsLeft {
+                items,
+                per_player,
+                total,
+            } => format!(
+                "{} left: {} ({})",
+                match items {
+                    Items::Dice => "Dice",
+                    Items::Tiles => "Tiles",
+                    Items::Symbols => "Symbols",
+                },
+                per_player,
+                total
+            ),
+            Msg::HandFor(player) => format!("Hand for Player {}", player),
+            Msg::RackFor { player_id, rack } => format!("Rack for Player {}: {}", player_id, rack),
+            Msg::LettersRemaining(letters) => format!("Letters remaining:\n{}", letters),
+            Msg::CanGuarantee(bet) => format!("You can guarantee {}", bet),
+            Msg::BetPrompt { items, first } => match (items, first) {
+                (Items::Dice, true) => {
+                    "Enter bet (2.6=two sixes, table=likely dice, quit=forfeit):".into()
+                }
+                (Items::Dice, false) => "Enter bet (2.6=two sixes, p=perudo, pal=palafico, \
+                                         table=likely dice, quit=forfeit):"
+                    .into(),
+                (Items::Tiles, true) => "Enter bet (?word=score, *words [N]=words from rack and \
+                                         N more, *shuffle=reorder rack, *table=likely tiles, \
+                                         *quit=forfeit):"
+                    .into(),
+                (Items::Tiles, false) => "Enter bet (*p=perudo, *pal=palafico, ?word=score, \
+                                          *words [N]=words from rack and N more, \
+                                          *shuffle=reorder rack, *table=likely tiles, \
+                                          *quit=forfeit):"
+                    .into(),
+                (Items::Symbols, true) => {
+                    "Enter bet (2+3=5, table=likely symbols, quit=forfeit):".into()
+                }
+                (Items::Symbols, false) => "Enter bet (2+3=5, p=perudo, pal=palafico, \
+                                            table=likely symbols, quit=forfeit):"
+                    .into(),
+            },
+            Msg::ExchangePrompt(items) => match items {
+                Items::Dice => "Or exchange dice first (x 2 5=redraw a two and a five)",
+                Items::Tiles => "Or exchange tiles first (*x ab=redraw an A and a B)",
+                Items::Symbols => "Or exchange symbols first (x 2 +=redraw a two and a plus)",
+            }
+            .into(),
+            Msg::TradePrompt(hidden) => format!(
+                "Offer one of {} to swap with the player on your left, or nothing to keep your \
+                 hand",
+                hidden
+            ),
+            Msg::ExpectedUnknownTiles(n) => {
+                format!("Expected a number of unknown tiles, got {}", n)
+            }
+            Msg::Scores { word, score } => format!("{} scores {}", word, score),
+            Msg::SingleWordOnly => "Bets must be a single word".into(),
+            Msg::NotInDict => "Bet was not in dict".into(),
+            Msg::ThisRound(constraint) => format!("This round, {}", constraint),
+            Msg::InTheMiddle(items) => format!("In the middle: {}", items),
+            Msg::Revealed(shown) => format!("Revealed: {}", shown),
+            Msg::SeatShows { seat, items } => format!("seat {} shows {}", seat, items),
+            Msg::LikelyOnTable(table) => format!("Likely on the rest of the table: {}", table),
+            Msg::Deductions(deductions) => format!("Deductions:\n{}", deductions),
+            Msg::YourTurn(id) => format!("Your turn, Player {}", id),
+            Msg::BetsThisRound(bets) => format!("Bets this round: {}", bets),
+            Msg::SummaryBet {
+                player_id,
+                bet,
+                detail,
+            } => match detail {
+                Some(detail) => format!("AI {} bets {}: {}", player_id, bet, detail),
+                None => format!("AI {} bets {}", player_id, bet),
+            },
+            Msg::SummaryCall {
+                player_id,
+                palafico,
+                bet,
+            } => format!(
+                "AI {} calls {} on {}",
+                player_id,
+                if *palafico { "Palafico" } else { "Perudo" },
+                bet
+            ),
+            Msg::SummaryExchange {
+                player_id,
+                num_items,
+            } => format!("AI {} exchanges {} items", player_id, num_items),
+            Msg::SummaryClaim {
+                line,
+                size,
+                total,
+                items,
+            } => format!(
+                "{}, claiming {} of the {} {} on the table ({:.0}%)",
+                line,
+                size,
+                total,
+                match items {
+                    Items::Dice => "dice",
+                    Items::Tiles => "tiles",
+                    Items::Symbols => "symbols",
+                },
+                percent(*size, *total)
+            ),
+            Msg::LettersWorth { letters, score } => {
+                format!("{} letters, {} points", letters, score)
+            }
+            Msg::DemoIntro {
+                num_players,
+                items_per_player,
+                seed,
+            } => format!(
+                "A demo game between {} AI players with {} items each, dealt from seed {}. After each move comes why the AI made it.",
+                num_players, items_per_player, seed
+            ),
+            Msg::DemoChose {
+                player_id,
+                chosen,
+                method,
+            } => match method.as_str() {
+                "mcts" => format!(
+                    "  Player {} chose {}, having searched ahead through the games that might follow",
+                    player_id, chosen
+                ),
+                "learned" => format!(
+                    "  Player {} chose {}, as its learned model scored it best",
+                    player_id, chosen
+                ),
+                _ => format!(
+                    "  Player {} chose {}, as the move likeliest to come off",
+                    player_id, chosen
+                ),
+            },
+            Msg::DemoWeighed(candidates) => format!("  It weighed up {}", candidates),
+            Msg::DemoPruned(reasons) => format!("  It ruled out {}", reasons),
+            Msg::DemoAnyRaise { chance, forced } if *forced => format!(
+                "  Any raise had only a {:.0}% chance of being right, so it had to challenge",
+                100.0 * chance
+            ),
+            Msg::DemoAnyRaise { chance, .. } => format!(
+                "  Any raise had a {:.0}% chance of being right",
+                100.0 * chance
+            ),
+            Msg::SeatBets { seat, bets } => format!("seat {} bet {}", seat, bets),
+            Msg::SeatHas { seat, count } => format!("seat {} has {}", seat, count),
+            Msg::TileWorth { tile, score } => format!("{} worth {}", tile, score),
+            Msg::BlankTile => "blank".into(),
+            Msg::TrackerRow {
+                tile,
+                yours,
+                expected,
+                chance,
+            } => format!(
+                "{}: you hold {}, {:.2} expected elsewhere, {:.0}% chance of any elsewhere",
+                tile, yours, expected, chance
+            ),
+            Msg::RackWords {
+                num_words,
+                num_unknown,
+                words,
+            } => format!(
+                "{} words from your rack and {} more tiles, best first:\n{}",
+                num_words, num_unknown, words
+            ),
+            Msg::NotHighEnough => "Bet wasn't high enough".into(),
+            Msg::AlreadyBet => "Bet was already made this round".into(),
+            Msg::Banned(bet) => format!("{} is banned at this table", bet),
+            Msg::LettersLonger { letters, bet } => {
+                format!(
+                    "Bet must be at least {} letters longer than {}",
+                    letters, bet
+                )
+            }
+            Msg::ScoresMore { score, bet } => {
+                format!("Bet must score at least {} more than {}", score, bet)
+            }
+            Msg::EmptyWord(word) => format!("'{}' has an empty word", word),
+            Msg::NotIdentity(identity) => format!("{} isn't a true identity", identity),
+            Msg::ExchangesNotAllowed => "Exchanges aren't allowed in this game".into(),
+            Msg::AlreadyExchanged => "You've already exchanged this round".into(),
+            Msg::NothingToExchange => "Nothing to exchange".into(),
+            Msg::NotHeldFaceDown(items) => format!("You don't hold {} face down", items),
+            Msg::SeatNotHolding { seat, item } => {
+                format!("Seat {} doesn't hold {} face down", seat, item)
+            }
+            Msg::ChoicesForPlayers {
+                num_choices,
+                num_players,
+            } => format!("{} choices for {} players", num_choices, num_players),
+            Msg::TradeBeforeBet => "Every player trades or passes before the first bet".into(),
+            Msg::TradeOnlyFirst => "Trades are only made before a round's first bet".into(),
+            Msg::NotATile(c) => format!("'{}' is not a tile", c),
+            Msg::NotADie(x) => format!("{} is not a die", x),
+            Msg::NoBetToChallenge => "There is no bet to challenge".into(),
+            Msg::NoRightToChallenge => "Having exchanged, you can't challenge this round".into(),
+            Msg::OutOfTurn(seat) => format!("It isn't seat {}'s turn", seat),
+            Msg::UnexpectedOutcome(outcome) => format!("Unexpected outcome: {}", outcome),
+            Msg::GameOver => "The game is over".into(),
+            Msg::ScenarioFailed(reason) => format!("Scenario failed: {}", reason),
+            Msg::Drift(reason) => format!("Game drifted from its record: {}", reason),
+            Msg::Leaderboard(reason) => format!("Leaderboard: {}", reason),
+        }
+    }
+
+    /// The message in Spanish.
+    fn es(&self) -> String {
+        match self {
+            Msg::Disqualified(id) => format!("El jugador {} queda descalificado", id),
+            Msg::Wins(id) => format!("¡Gana el jugador {}!", id),
+            Msg::Reveals {
+                player_id,
+                num_shown,
+                num_items,
+            } => format!(
+                "El jugador {} muestra una pieza, ahora enseña {} de {}",
+                player_id, num_shown, num_items
+            ),
+            Msg::Loses {
+                player_id,
+                num_items,
+            } => format!(
+                "El jugador {} pierde una pieza, ahora tiene {}",
+                player_id, num_items
+            ),
+            Msg::WinsPalafico {
+                player_id,
+                num_items,
+            } => format!(
+                "El jugador {} gana el Palafico, ahora tiene {}",
+                player_id, num_items
+            ),
+            Msg::ForfeitsToAi(id) => format!("El jugador {} abandona y la IA ocupa su lugar", id),
+            Msg::ForfeitsAndLeaves(id) => format!("El jugador {} abandona y deja la mesa", id),
+            Msg::Exchanges {
+                player_id,
+                num_items,
+            } => format!("El jugador {} cambia {} piezas", player_id, num_items),
+            Msg::Trade(a, b) => format!(
+                "Los jugadores {} y {} intercambian una pieza cada uno",
+                a, b
+            ),
+            Msg::WinsWithMostLeft(id) => {
+                format!("Gana el jugador {}, al que más piezas le quedan", id)
+            }
+            Msg::SuddenDeath(ids) => format!(
+                "Sin más rondas, los jugadores {:?} juegan a muerte súbita",
+                ids
+            ),
+            Msg::TimeLeft { player_id, millis } => {
+                format!("Al jugador {} le quedan {}ms", player_id, millis)
+            }
+            Msg::SwitchedDictionary => "Diccionario cambiado para la nueva ronda".into(),
+            Msg::OutOfTime(id) => format!("Al jugador {} se le acabó el tiempo", id),
+            Msg::Disconnected(id) => format!("El jugador {} ha perdido la conexión", id),
+            Msg::Reconnected(id) => format!("El jugador {} ha vuelto", id),
+            Msg::Bets { player_id, bet } => format!("El jugador {} apuesta {}", player_id, bet),
+            Msg::CallsPerudo(id) => format!("El jugador {} dice Perudo", id),
+            Msg::CallsPalafico(id) => format!("El jugador {} dice Palafico", id),
+            Msg::Counted {
+                verdict,
+                count,
+                value,
+            } => format!(
+                "La apuesta era {}, había {} de {}",
+                match verdict {
+                    Verdict::Correct => "correcta",
+                    Verdict::ExactlyCorrect => "exactamente correcta",
+                    Verdict::Incorrect => "incorrecta",
+                },
+                count,
+                value
+            ),
+            Msg::Found {
+                correct,
+                exactly,
+                bet,
+                items,
+            } => format!(
+                "La apuesta era {}, {} {}está{} en {}",
+                if *correct { "correcta" } else { "incorrecta" },
+                bet,
+                if *correct { "" } else { "no " },
+                if *exactly { " exactamente" } else { "" },
+                items
+            ),
+            Msg::ItemsLeft {
+                items,
+                per_player,
+                total,
+            } => format!(
+                "{} restantes: {} ({})",
+                match items {
+                    Items::Dice => "Dados",
+                    Items::Tiles => "Fichas",
+                    Items::Symbols => "Símbolos",
+                },
+                per_player,
+                total
+            ),
+            Msg::HandFor(player) => format!("Mano del jugador {}", player),
+            Msg::RackFor { player_id, rack } => {
+                format!("Atril del jugador {}: {}", player_id, rack)
+            }
+            Msg::LettersRemaining(letters) => format!("Letras restantes:\n{}", letters),
+            Msg::CanGuarantee(bet) => format!("Puedes garantizar {}", bet),
+            Msg::BetPrompt { items, first } => match (items, first) {
+                (Items::Dice, true) => "Introduce tu apuesta (2.6=dos seises, table=dados \
+                                        probables, quit=abandonar):"
+                    .into(),
+                (Items::Dice, false) => "Introduce tu apuesta (2.6=dos seises, p=perudo, \
+                                         pal=palafico, table=dados probables, quit=abandonar):"
+                    .into(),
+                (Items::Tiles, true) => "Introduce tu apuesta (?palabra=puntos, *words \
+                                         [N]=palabras del atril y N más, *shuffle=reordenar \
+                                         atril, *table=fichas probables, *quit=abandonar):"
+                    .into(),
+                (Items::Tiles, false) => "Introduce tu apuesta (*p=perudo, *pal=palafico, \
+                                          ?palabra=puntos, *words [N]=palabras del atril y N \
+                                          más, *shuffle=reordenar atril, *table=fichas \
+                                          probables, *quit=abandonar):"
+                    .into(),
+                (Items::Symbols, true) => "Introduce tu apuesta (2+3=5, table=símbolos \
+                                           probables, quit=abandonar):"
+                    .into(),
+                (Items::Symbols, false) => "Introduce tu apuesta (2+3=5, p=perudo, \
+                                            pal=palafico, table=símbolos probables, \
+                                            quit=abandonar):"
+                    .into(),
+            },
+            Msg::ExchangePrompt(items) => match items {
+                Items::Dice => "O cambia dados antes (x 2 5=volver a tirar un dos y un cinco)",
+                Items::Tiles => "O cambia fichas antes (*x ab=robar de nuevo una A y una B)",
+                Items::Symbols => "O cambia símbolos antes (x 2 +=robar de nuevo un dos y un más)",
+            }
+            .into(),
+            Msg::TradePrompt(hidden) => format!(
+                "Ofrece una de {} para cambiarla con el jugador de tu izquierda, o nada para \
+                 quedarte con tu mano",
+                hidden
+            ),
+            Msg::ExpectedUnknownTiles(n) => {
+                format!("Se esperaba un número de fichas desconocidas, no {}", n)
+            }
+            Msg::Scores { word, score } => format!("{} vale {} puntos", word, score),
+            Msg::SingleWordOnly => "Las apuestas deben ser una sola palabra".into(),
+            Msg::NotInDict => "La apuesta no está en el diccionario".into(),
+            Msg::ThisRound(constraint) => format!("En esta ronda, {}", constraint),
+            Msg::InTheMiddle(items) => format!("En el centro: {}", items),
+            Msg::Revealed(shown) => format!("A la vista: {}", shown),
+            Msg::SeatShows { seat, items } => format!("el asiento {} muestra {}", seat, items),
+            Msg::LikelyOnTable(table) => {
+                format!("Probablemente en el resto de la mesa: {}", table)
+            }
+            Msg::Deductions(deductions) => format!("Deducciones:\n{}", deductions),
+            Msg::YourTurn(id) => format!("Tu turno, jugador {}", id),
+            Msg::BetsThisRound(bets) => format!("Apuestas de esta ronda: {}", bets),
+            Msg::SummaryBet {
+                player_id,
+                bet,
+                detail,
+            } => match detail {
+                Some(detail) => format!("La IA {} apuesta {}: {}", player_id, bet, detail),
+                None => format!("La IA {} apuesta {}", player_id, bet),
+            },
+            Msg::SummaryCall {
+                player_id,
+                palafico,
+                bet,
+            } => format!(
+                "La IA {} dice {} a {}",
+                player_id,
+                if *palafico { "Palafico" } else { "Perudo" },
+                bet
+            ),
+            Msg::SummaryExchange {
+                player_id,
+                num_items,
+            } => format!("La IA {} cambia {} piezas", player_id, num_items),
+            Msg::SummaryClaim {
+                line,
+                size,
+                total,
+                items,
+            } => format!(
+                "{}, y afirma que hay {} de los {} {} de la mesa ({:.0}%)",
+                line,
+                size,
+                total,
+                match items {
+                    Items::Dice => "dados",
+                    Items::Tiles => "fichas",
+                    Items::Symbols => "símbolos",
+                },
+                percent(*size, *total)
+            ),
+            Msg::LettersWorth { letters, score } => {
+                format!("{} letras, {} puntos", letters, score)
+            }
+            Msg::DemoIntro {
+                num_players,
+                items_per_player,
+                seed,
+            } => format!(
+                "Una partida de demostración entre {} jugadores IA con {} piezas cada uno, repartida con la semilla {}. Tras cada jugada se explica por qué la IA la hizo.",
+                num_players, items_per_player, seed
+            ),
+            Msg::DemoChose {
+                player_id,
+                chosen,
+                method,
+            } => match method.as_str() {
+                "mcts" => format!(
+                    "  El jugador {} eligió {} tras explorar las partidas que podrían seguir",
+                    player_id, chosen
+                ),
+                "learned" => format!(
+                    "  El jugador {} eligió {}, la mejor según su modelo aprendido",
+                    player_id, chosen
+                ),
+                _ => format!(
+                    "  El jugador {} eligió {}, la jugada con más probabilidades de salir bien",
+                    player_id, chosen
+                ),
+            },
+            Msg::DemoWeighed(candidates) => format!("  Sopesó {}", candidates),
+            Msg::DemoPruned(reasons) => format!("  Descartó {}", reasons),
+            Msg::DemoAnyRaise { chance, forced } if *forced => format!(
+                "  Cualquier subida tenía solo un {:.0}% de acertar, así que tuvo que desafiar",
+                100.0 * chance
+            ),
+            Msg::DemoAnyRaise { chance, .. } => format!(
+                "  Cualquier subida tenía un {:.0}% de acertar",
+                100.0 * chance
+            ),
+            Msg::SeatBets { seat, bets } => format!("el asiento {} apostó {}", seat, bets),
+            Msg::SeatHas { seat, count } => format!("el asiento {} tiene {}", seat, count),
+            Msg::TileWorth { tile, score } => format!("{} vale {}", tile, score),
+            Msg::BlankTile => "comodín".into(),
+            Msg::TrackerRow {
+                tile,
+                yours,
+                expected,
+                chance,
+            } => format!(
+                "{}: tienes {}, se esperan {:.2} en otras manos, {:.0}% de que haya alguna",
+                tile, yours, expected, chance
+            ),
+            Msg::RackWords {
+                num_words,
+                num_unknown,
+                words,
+            } => format!(
+                "{} palabras de tu atril y {} fichas más, las mejores primero:\n{}",
+                num_words, num_unknown, words
+            ),
+            Msg::NotHighEnough => "La apuesta no era lo bastante alta".into(),
+            Msg::AlreadyBet => "Esa apuesta ya se hizo en esta ronda".into(),
+            Msg::Banned(bet) => format!("{} está prohibida en esta mesa", bet),
+            Msg::LettersLonger { letters, bet } => format!(
+                "La apuesta debe tener al menos {} letras más que {}",
+                letters, bet
+            ),
+            Msg::ScoresMore { score, bet } => format!(
+                "La apuesta debe valer al menos {} puntos más que {}",
+                score, bet
+            ),
+            Msg::EmptyWord(word) => format!("'{}' tiene una palabra vacía", word),
+            Msg::NotIdentity(identity) => format!("{} no es una igualdad cierta", identity),
+            Msg::ExchangesNotAllowed => "No se permiten cambios en esta partida".into(),
+            Msg::AlreadyExchanged => "Ya has cambiado en esta ronda".into(),
+            Msg::NothingToExchange => "No hay nada que cambiar".into(),
+            Msg::NotHeldFaceDown(items) => format!("No tienes {} boca abajo", items),
+            Msg::SeatNotHolding { seat, item } => {
+                format!("El asiento {} no tiene {} boca abajo", seat, item)
+            }
+            Msg::ChoicesForPlayers {
+                num_choices,
+                num_players,
+            } => format!("{} elecciones para {} jugadores", num_choices, num_players),
+            Msg::TradeBeforeBet => {
+                "Todos los jugadores cambian o pasan antes de la primera apuesta".into()
+            }
+            Msg::TradeOnlyFirst => "Solo se cambia antes de la primera apuesta de la ronda".into(),
+            Msg::NotATile(c) => format!("'{}' no es una ficha", c),
+            Msg::NotADie(x) => format!("{} no es un dado", x),
+            Msg::NoBetToChallenge => "No hay ninguna apuesta que desafiar".into(),
+            Msg::NoRightToChallenge => "Como has cambiado, no puedes desafiar en esta ronda".into(),
+            Msg::OutOfTurn(seat) => format!("No es el turno del asiento {}", seat),
+            Msg::UnexpectedOutcome(outcome) => format!("Resultado inesperado: {}", outcome),
+            Msg::GameOver => "La partida ha terminado".into(),
+            Msg::ScenarioFailed(reason) => format!("El escenario falló: {}", reason),
+            Msg::Drift(reason) => format!("La partida se apartó de su registro: {}", reason),
+            Msg::Leaderboard(reason) => format!("Clasificación: {}", reason),
+        }
+    }
+
+    /// The message in German.
+    fn de(&self) -> String {
+        match self {
+            Msg::Disqualified(id) => format!("Spieler {} ist disqualifiziert", id),
+            Msg::Wins(id) => format!("Spieler {} gewinnt!", id),
+            Msg::Reveals {
+                player_id,
+                num_shown,
+                num_items,
+            } => format!(
+                "Spieler {} deckt ein Stück auf und zeigt jetzt {} von {}",
+                player_id, num_shown, num_items
+            ),
+            Msg::Loses {
+                player_id,
+                num_items,
+            } => format!(
+                "Spieler {} verliert ein Stück und hat jetzt {}",
+                player_id, num_items
+            ),
+            Msg::WinsPalafico {
+                player_id,
+                num_items,
+            } => format!(
+                "Spieler {} gewinnt Palafico und hat jetzt {}",
+                player_id, num_items
+            ),
+            Msg::ForfeitsToAi(id) => format!("Spieler {} gibt auf, die KI übernimmt", id),
+            Msg::ForfeitsAndLeaves(id) => {
+                format!("Spieler {} gibt auf und verlässt den Tisch", id)
+            }
+            Msg::Exchanges {
+                player_id,
+                num_items,
+            } => format!("Spieler {} tauscht {} Stücke aus", player_id, num_items),
+            Msg::Trade(a, b) => format!("Spieler {} und {} tauschen je ein Stück", a, b),
+            Msg::WinsWithMostLeft(id) => {
+                format!("Spieler {} gewinnt mit den meisten übrigen Stücken", id)
+            }
+            Msg::SuddenDeath(ids) => format!(
+                "Keine Runden mehr, die Spieler {:?} spielen ein Stechen",
+                ids
+            ),
+            Msg::TimeLeft { player_id, millis } => {
+                format!("Spieler {} bleiben {}ms", player_id, millis)
+            }
+            Msg::SwitchedDictionary => "Wörterbuch für die neue Runde gewechselt".into(),
+            Msg::OutOfTime(id) => format!("Spieler {} hat keine Zeit mehr", id),
+            Msg::Disconnected(id) => format!("Spieler {} hat die Verbindung verloren", id),
+            Msg::Reconnected(id) => format!("Spieler {} ist zurück", id),
+            Msg::Bets { player_id, bet } => format!("Spieler {} setzt auf {}", player_id, bet),
+            Msg::CallsPerudo(id) => format!("Spieler {} ruft Perudo", id),
+            Msg::CallsPalafico(id) => format!("Spieler {} ruft Palafico", id),
+            Msg::Counted {
+                verdict,
+                count,
+                value,
+            } => format!(
+                "Die Wette war {}, es gab {} mal {}",
+                match verdict {
+                    Verdict::Correct => "richtig",
+                    Verdict::ExactlyCorrect => "genau richtig",
+                    Verdict::Incorrect => "falsch",
+                },
+                count,
+                value
+            ),
+            Msg::Found {
+                correct,
+                exactly,
+                bet,
+                items,
+            } => format!(
+                "Die Wette war {}, {} ist {}{}in {}",
+                if *correct { "richtig" } else { "falsch" },
+                bet,
+                if *correct { "" } else { "nicht " },
+                if *exactly { "genau " } else { "" },
+                items
+            ),
+            Msg::ItemsLeft {
+                items,
+                per_player,
+                total,
+            } => format!(
+                "Übrige {}: {} ({})",
+                match items {
+                    Items::Dice => "Würfel",
+                    Items::Tiles => "Steine",
+                    Items::Symbols => "Symbole",
+                },
+                per_player,
+                total
+            ),
+            Msg::HandFor(player) => format!("Hand von Spieler {}", player),
+            Msg::RackFor { player_id, rack } => format!("Bank von Spieler {}: {}", player_id, rack),
+            Msg::LettersRemaining(letters) => format!("Übrige Buchstaben:\n{}", letters),
+            Msg::CanGuarantee(bet) => format!("Du kannst {} garantieren", bet),
+            Msg::BetPrompt { items, first } => match (items, first) {
+                (Items::Dice, true) => "Wette eingeben (2.6=zwei Sechsen, table=wahrscheinliche \
+                                        Würfel, quit=aufgeben):"
+                    .into(),
+                (Items::Dice, false) => "Wette eingeben (2.6=zwei Sechsen, p=perudo, \
+                                         pal=palafico, table=wahrscheinliche Würfel, \
+                                         quit=aufgeben):"
+                    .into(),
+                (Items::Tiles, true) => "Wette eingeben (?wort=Punkte, *words [N]=Wörter aus der \
+                                         Bank und N weiteren, *shuffle=Bank mischen, \
+                                         *table=wahrscheinliche Steine, *quit=aufgeben):"
+                    .into(),
+                (Items::Tiles, false) => "Wette eingeben (*p=perudo, *pal=palafico, \
+                                          ?wort=Punkte, *words [N]=Wörter aus der Bank und N \
+                                          weiteren, *shuffle=Bank mischen, \
+                                          *table=wahrscheinliche Steine, *quit=aufgeben):"
+                    .into(),
+                (Items::Symbols, true) => "Wette eingeben (2+3=5, table=wahrscheinliche \
+                                           Symbole, quit=aufgeben):"
+                    .into(),
+                (Items::Symbols, false) => "Wette eingeben (2+3=5, p=perudo, pal=palafico, \
+                                            table=wahrscheinliche Symbole, quit=aufgeben):"
+                    .into(),
+            },
+            Msg::ExchangePrompt(items) => match items {
+                Items::Dice => {
+                    "Oder zuerst Würfel tauschen (x 2 5=eine Zwei und eine Fünf neu würfeln)"
+                }
+                Items::Tiles => "Oder zuerst Steine tauschen (*x ab=ein A und ein B neu ziehen)",
+                Items::Symbols => {
+                    "Oder zuerst Symbole tauschen (x 2 +=eine Zwei und ein Plus neu ziehen)"
+                }
+            }
+            .into(),
+            Msg::TradePrompt(hidden) => format!(
+                "Biete eins von {} zum Tausch mit dem Spieler links von dir an, oder nichts, um \
+                 deine Hand zu behalten",
+                hidden
+            ),
+            Msg::ExpectedUnknownTiles(n) => {
+                format!("Erwartet wurde eine Anzahl unbekannter Steine, nicht {}", n)
+            }
+            Msg::Scores { word, score } => format!("{} bringt {} Punkte", word, score),
+            Msg::SingleWordOnly => "Wetten müssen ein einzelnes Wort sein".into(),
+            Msg::NotInDict => "Die Wette steht nicht im Wörterbuch".into(),
+            Msg::ThisRound(constraint) => format!("In dieser Runde: {}", constraint),
+            Msg::InTheMiddle(items) => format!("In der Mitte: {}", items),
+            Msg::Revealed(shown) => format!("Aufgedeckt: {}", shown),
+            Msg::SeatShows { seat, items } => format!("Platz {} zeigt {}", seat, items),
+            Msg::LikelyOnTable(table) => {
+                format!("Wahrscheinlich auf dem restlichen Tisch: {}", table)
+            }
+            Msg::Deductions(deductions) => format!("Schlussfolgerungen:\n{}", deductions),
+            Msg::YourTurn(id) => format!("Du bist dran, Spieler {}", id),
+            Msg::BetsThisRound(bets) => format!("Wetten dieser Runde: {}", bets),
+            Msg::SummaryBet {
+                player_id,
+                bet,
+                detail,
+            } => match detail {
+                Some(detail) => format!("KI {} setzt auf {}: {}", player_id, bet, detail),
+                None => format!("KI {} setzt auf {}", player_id, bet),
+            },
+            Msg::SummaryCall {
+                player_id,
+                palafico,
+                bet,
+            } => format!(
+                "KI {} ruft {} gegen {}",
+                player_id,
+                if *palafico { "Palafico" } else { "Perudo" },
+                bet
+            ),
+            Msg::SummaryExchange {
+                player_id,
+                num_items,
+            } => format!("KI {} tauscht {} Stücke aus", player_id, num_items),
+            Msg::SummaryClaim {
+                line,
+                size,
+                total,
+                items,
+            } => format!(
+                "{}, und behauptet {} der {} {} auf dem Tisch ({:.0}%)",
+                line,
+                size,
+                total,
+                match items {
+                    Items::Dice => "Würfel",
+                    Items::Tiles => "Steine",
+                    Items::Symbols => "Symbole",
+                },
+                percent(*size, *total)
+            ),
+            Msg::LettersWorth { letters, score } => {
+                format!("{} Buchstaben, {} Punkte", letters, score)
+            }
+            Msg::DemoIntro {
+                num_players,
+                items_per_player,
+                seed,
+            } => format!(
+                "Ein Demospiel zwischen {} KI-Spielern mit je {} Stücken, ausgeteilt mit Startwert {}. Nach jedem Zug steht, warum die KI ihn machte.",
+                num_players, items_per_player, seed
+            ),
+            Msg::DemoChose {
+                player_id,
+                chosen,
+                method,
+            } => match method.as_str() {
+                "mcts" => format!(
+                    "  Spieler {} wählte {}, nachdem er die möglichen folgenden Spiele durchsucht hatte",
+                    player_id, chosen
+                ),
+                "learned" => format!(
+                    "  Spieler {} wählte {}, den sein gelerntes Modell am besten bewertete",
+                    player_id, chosen
+                ),
+                _ => format!(
+                    "  Spieler {} wählte {}, den Zug mit den besten Aussichten",
+                    player_id, chosen
+                ),
+            },
+            Msg::DemoWeighed(candidates) => format!("  Er wog {} ab", candidates),
+            Msg::DemoPruned(reasons) => format!("  Er schloss aus: {}", reasons),
+            Msg::DemoAnyRaise { chance, forced } if *forced => format!(
+                "  Jede Erhöhung hatte nur {:.0}% Chance, richtig zu sein, also musste er anfechten",
+                100.0 * chance
+            ),
+            Msg::DemoAnyRaise { chance, .. } => format!(
+                "  Jede Erhöhung hatte {:.0}% Chance, richtig zu sein",
+                100.0 * chance
+            ),
+            Msg::SeatBets { seat, bets } => format!("Platz {} setzte auf {}", seat, bets),
+            Msg::SeatHas { seat, count } => format!("Platz {} hat {}", seat, count),
+            Msg::TileWorth { tile, score } => format!("{} zählt {}", tile, score),
+            Msg::BlankTile => "Joker".into(),
+            Msg::TrackerRow {
+                tile,
+                yours,
+                expected,
+                chance,
+            } => format!(
+                "{}: du hast {}, {:.2} anderswo erwartet, {:.0}% Chance auf einen anderswo",
+                tile, yours, expected, chance
+            ),
+            Msg::RackWords {
+                num_words,
+                num_unknown,
+                words,
+            } => format!(
+                "{} Wörter aus deiner Bank und {} weiteren Steinen, die besten zuerst:\n{}",
+                num_words, num_unknown, words
+            ),
+            Msg::NotHighEnough => "Die Wette war nicht hoch genug".into(),
+            Msg::AlreadyBet => "Diese Wette gab es in dieser Runde schon".into(),
+            Msg::Banned(bet) => format!("{} ist an diesem Tisch verboten", bet),
+            Msg::LettersLonger { letters, bet } => format!(
+                "Die Wette muss mindestens {} Buchstaben länger sein als {}",
+                letters, bet
+            ),
+            Msg::ScoresMore { score, bet } => format!(
+                "Die Wette muss mindestens {} Punkte mehr bringen als {}",
+                score, bet
+            ),
+            Msg::EmptyWord(word) => format!("'{}' enthält ein leeres Wort", word),
+            Msg::NotIdentity(identity) => format!("{} ist keine wahre Gleichung", identity),
+            Msg::ExchangesNotAllowed => "In diesem Spiel wird nicht getauscht".into(),
+            Msg::AlreadyExchanged => "Du hast in dieser Runde schon getauscht".into(),
+            Msg::NothingToExchange => "Nichts zu tauschen".into(),
+            Msg::NotHeldFaceDown(items) => format!("Du hast {} nicht verdeckt", items),
+            Msg::SeatNotHolding { seat, item } => {
+                format!("Platz {} hat {} nicht verdeckt", seat, item)
+            }
+            Msg::ChoicesForPlayers {
+                num_choices,
+                num_players,
+            } => format!("{} Entscheidungen für {} Spieler", num_choices, num_players),
+            Msg::TradeBeforeBet => "Alle Spieler tauschen oder passen vor der ersten Wette".into(),
+            Msg::TradeOnlyFirst => "Getauscht wird nur vor der ersten Wette einer Runde".into(),
+            Msg::NotATile(c) => format!("'{}' ist kein Stein", c),
+            Msg::NotADie(x) => format!("{} ist kein Würfel", x),
+            Msg::NoBetToChallenge => "Es gibt keine Wette anzufechten".into(),
+            Msg::NoRightToChallenge => {
+                "Nach dem Tausch kannst du in dieser Runde nicht anfechten".into()
+            }
+            Msg::OutOfTurn(seat) => format!("Platz {} ist nicht am Zug", seat),
+            Msg::UnexpectedOutcome(outcome) => format!("Unerwartetes Ergebnis: {}", outcome),
+            Msg::GameOver => "Das Spiel ist vorbei".into(),
+            Msg::ScenarioFailed(reason) => format!("Szenario fehlgeschlagen: {}", reason),
+            Msg::Drift(reason) => {
+                format!("Das Spiel weicht von seiner Aufzeichnung ab: {}", reason)
+            }
+            Msg::Leaderboard(reason) => format!("Rangliste: {}", reason),
+        }
+    }
+}
+
+/// How much of the given total the given part is, in percent.
+fn percent(part: usize, total: usize) -> f64 {
+    100.0 * part as f64 / total.max(1) as f64
+}
+
+impl fmt::Display for Msg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text(lang()))
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "messages" {
+        it "reads language names" {
+            assert_eq!(Some(Lang::Es), Lang::from_name("es"));
+            assert_eq!(Some(Lang::De), Lang::from_name("de"));
+            assert_eq!(None, Lang::from_name("fr"));
+        }
+
+        it "writes messages in each language" {
+            let msg = Msg::Wins(2);
+            assert_eq!("Player 2 wins!", msg.text(Lang::En));
+            assert_eq!("¡Gana el jugador 2!", msg.text(Lang::Es));
+            assert_eq!("Spieler 2 gewinnt!", msg.text(Lang::De));
+            assert_eq!("'!' is not a tile", Msg::NotATile('!').text(Lang::En));
+            assert_eq!("'!' no es una ficha", Msg::NotATile('!').text(Lang::Es));
+        }
+
+        it "reads results as before in English" {
+            let found = |correct, exactly| Msg::Found {
+                correct: correct,
+                exactly: exactly,
+                bet: "cat".into(),
+                items: "[C, A, T]".into(),
+            };
+            assert_eq!("Bet was correct, cat is in [C, A, T]", found(true, false).text(Lang::En));
+            assert_eq!("Bet was incorrect, cat is not exactly in [C, A, T]", found(false, true).text(Lang::En));
+            assert_eq!("Die Wette war falsch, cat ist nicht genau in [C, A, T]", found(false, true).text(Lang::De));
+            let counted = Msg::Counted { verdict: Verdict::ExactlyCorrect, count: 3, value: "Two".into() };
+            assert_eq!("Bet was exactly correct, there were 3 Twos", counted.text(Lang::En));
+        }
+    }
+}
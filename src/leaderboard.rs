@@ -0,0 +1,349 @@
+/// The leaderboard: a sqlite database of finished games, who sat in them, how they played and who
+/// won, for server deployments to report standings from. The database needs the leaderboard
+/// feature; without it, asking for one is an error.
+use crate::belief::*;
+#[cfg(feature = "leaderboard")]
+use crate::bet::*;
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+use crate::learned;
+use crate::mcts;
+
+#[cfg(feature = "leaderboard")]
+use rusqlite::{params, Connection};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+#[cfg(feature = "leaderboard")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+lazy_static! {
+    static ref NAMES: Mutex<HashMap<usize, String>> = Mutex::new(HashMap::new());
+}
+
+#[cfg(feature = "leaderboard")]
+lazy_static! {
+    static ref LEADERBOARD: Mutex<Option<Leaderboard>> = Mutex::new(None);
+}
+
+/// The tables of the database, created if they don't exist.
+pub const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        finished_at INTEGER NOT NULL,
+        variant TEXT NOT NULL,
+        num_rounds INTEGER NOT NULL,
+        winner INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS participants (
+        game_id INTEGER NOT NULL REFERENCES games(id),
+        player_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        strategy TEXT NOT NULL,
+        won INTEGER NOT NULL
+    );";
+
+/// How far back strategy win rates are bucketed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// Parses a period from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "day" => Some(Period::Day),
+            "week" => Some(Period::Week),
+            "month" => Some(Period::Month),
+            _ => None,
+        }
+    }
+
+    /// The sqlite strftime format labelling the period a time falls in.
+    pub fn format(&self) -> &'static str {
+        match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Week => "%Y-W%W",
+            Period::Month => "%Y-%m",
+        }
+    }
+}
+
+/// How many games someone, or some strategy, has played and won.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    /// Who or what played: a player's name, or a strategy, perhaps within a period.
+    pub label: String,
+    pub played: usize,
+    pub won: usize,
+}
+
+impl Standing {
+    /// The fraction of games played that were won.
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            self.won as f64 / self.played as f64
+        }
+    }
+}
+
+impl fmt::Display for Standing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: won {} of {} ({:.1}%)",
+            self.label,
+            self.won,
+            self.played,
+            100.0 * self.win_rate()
+        )
+    }
+}
+
+/// Records the given player's results under the given name.
+pub fn init_name(player_id: usize, name: &str) {
+    NAMES.lock().unwrap().insert(player_id, name.into());
+}
+
+/// The name the given player's results are recorded under.
+pub fn name(player_id: usize) -> String {
+    match NAMES.lock().unwrap().get(&player_id) {
+        Some(name) => name.clone(),
+        None => format!("player {}", player_id),
+    }
+}
+
+/// Parses the naming of a seat from its command-line form, e.g. "1:alice".
+pub fn parse_name(assignment: &str) -> Option<(usize, String)> {
+    let mut split = assignment.splitn(2, ':');
+    match (split.next().map(|id| id.parse::<usize>()), split.next()) {
+        (Some(Ok(player_id)), Some(name)) if !name.is_empty() => Some((player_id, name.into())),
+        _ => None,
+    }
+}
+
+/// How the given player is playing in a game of the given config, in the form strategies are
+/// compared by, e.g. "greedy", "human" or "belief:none,mcts:iterations:200".
+pub fn strategy_name(config: &GameConfig, player_id: usize, human: bool) -> String {
+    if human {
+        return "human".into();
+    }
+    let mut settings = vec![];
+    match belief(config, player_id) {
+        BeliefPolicy::Fraction(p) if p == DEFAULT_BELIEF_P => (),
+        BeliefPolicy::Fraction(1.0) => settings.push("belief:all".to_string()),
+        BeliefPolicy::Fraction(0.0) => settings.push("belief:none".to_string()),
+        BeliefPolicy::Fraction(p) => settings.push(format!("belief:{}", p)),
+        BeliefPolicy::Cap(k) => settings.push(format!("belief:cap:{}", k)),
+        BeliefPolicy::Honesty => settings.push("belief:honesty".to_string()),
+    }
+    match mcts::budget(config, player_id) {
+        Some(mcts::Budget::Iterations(k)) => settings.push(format!("mcts:iterations:{}", k)),
+        Some(mcts::Budget::Millis(k)) => settings.push(format!("mcts:millis:{}", k)),
+        None => (),
+    }
+    if learned::policy(config, player_id).is_some() {
+        settings.push("learned".to_string());
+    }
+    if settings.is_empty() {
+        "greedy".into()
+    } else {
+        settings.join(",")
+    }
+}
+
+/// A sqlite database of finished games.
+#[cfg(feature = "leaderboard")]
+pub struct Leaderboard {
+    conn: Connection,
+}
+
+#[cfg(feature = "leaderboard")]
+impl Leaderboard {
+    /// Opens the database at the given path, creating it if need be.
+    pub fn open(path: &str) -> Result<Self, ScrabrudoError> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn: conn })
+    }
+
+    /// Records the given finished game, with everyone who sat at it when it started.
+    pub fn record<G: Game>(&self, game: &G, seats: &[(usize, bool)]) -> Result<(), ScrabrudoError> {
+        let winner = game.winner();
+        let finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        self.conn.execute(
+            "INSERT INTO games (finished_at, variant, num_rounds, winner) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                finished_at,
+                G::B::VARIANT,
+                game.round() as i64,
+                winner.map(|id| id as i64)
+            ],
+        )?;
+        let game_id = self.conn.last_insert_rowid();
+        for (player_id, human) in seats {
+            self.conn.execute(
+                "INSERT INTO participants (game_id, player_id, name, strategy, won)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    game_id,
+                    *player_id as i64,
+                    name(*player_id),
+                    strategy_name(game.config(), *player_id, *human),
+                    winner == Some(*player_id)
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The players who have won the most games, best first, as many as given.
+    pub fn top_players(&self, num_players: usize) -> Result<Vec<Standing>, ScrabrudoError> {
+        self.standings(
+            "SELECT name, COUNT(*), SUM(won) FROM participants
+             GROUP BY name ORDER BY SUM(won) DESC, COUNT(*) ASC, name LIMIT ?1",
+            num_players as i64,
+        )
+    }
+
+    /// How often each strategy has won in each period, oldest first.
+    pub fn strategy_win_rates(&self, period: Period) -> Result<Vec<Standing>, ScrabrudoError> {
+        self.standings(
+            "SELECT strftime(?1, finished_at, 'unixepoch') || ' ' || strategy, COUNT(*), SUM(won)
+             FROM participants JOIN games ON games.id = participants.game_id
+             GROUP BY 1 ORDER BY 1",
+            period.format(),
+        )
+    }
+
+    /// The standings a query with one parameter gives, as label, games played and games won.
+    fn standings<P: rusqlite::ToSql>(
+        &self,
+        query: &str,
+        param: P,
+    ) -> Result<Vec<Standing>, ScrabrudoError> {
+        let mut statement = self.conn.prepare(query)?;
+        let rows = statement.query_map(params![param], |row| {
+            Ok(Standing {
+                label: row.get(0)?,
+                played: row.get::<_, i64>(1)? as usize,
+                won: row.get::<_, i64>(2)? as usize,
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<Standing>, rusqlite::Error>>()?)
+    }
+}
+
+/// Starts recording every finished game in the database at the given path.
+#[cfg(feature = "leaderboard")]
+pub fn init_leaderboard(path: &str) -> Result<(), ScrabrudoError> {
+    *LEADERBOARD.lock().unwrap() = Some(Leaderboard::open(path)?);
+    Ok(())
+}
+
+/// Refuses to record games, lacking the leaderboard feature.
+#[cfg(not(feature = "leaderboard"))]
+pub fn init_leaderboard(_path: &str) -> Result<(), ScrabrudoError> {
+    Err(unsupported())
+}
+
+/// Records the given finished game, if recording to a leaderboard.
+#[cfg(feature = "leaderboard")]
+pub fn record_game<G: Game>(game: &G, seats: &[(usize, bool)]) -> Result<(), ScrabrudoError> {
+    match LEADERBOARD.lock().unwrap().as_ref() {
+        Some(leaderboard) => leaderboard.record(game, seats),
+        None => Ok(()),
+    }
+}
+
+/// Records nothing, lacking the leaderboard feature.
+#[cfg(not(feature = "leaderboard"))]
+pub fn record_game<G: Game>(_game: &G, _seats: &[(usize, bool)]) -> Result<(), ScrabrudoError> {
+    Ok(())
+}
+
+/// The standings in the database at the given path, by player or by strategy over time.
+#[cfg(feature = "leaderboard")]
+pub fn stats(
+    path: &str,
+    task: &str,
+    num_players: usize,
+    period: Period,
+) -> Result<Vec<Standing>, ScrabrudoError> {
+    let leaderboard = Leaderboard::open(path)?;
+    match task {
+        "players" => leaderboard.top_players(num_players),
+        "strategies" => leaderboard.strategy_win_rates(period),
+        task => Err(ScrabrudoError::InvalidOption(format!(
+            "Invalid stats: {}",
+            task
+        ))),
+    }
+}
+
+/// Refuses to report standings, lacking the leaderboard feature.
+#[cfg(not(feature = "leaderboard"))]
+pub fn stats(
+    _path: &str,
+    _task: &str,
+    _num_players: usize,
+    _period: Period,
+) -> Result<Vec<Standing>, ScrabrudoError> {
+    Err(unsupported())
+}
+
+/// The error for asking for a leaderboard without the feature.
+#[cfg(not(feature = "leaderboard"))]
+fn unsupported() -> ScrabrudoError {
+    ScrabrudoError::InvalidOption("Build with --features leaderboard to use a leaderboard".into())
+}
+
+#[cfg(test)]
+speculate! {
+    describe "leaderboards" {
+        it "names seats" {
+            assert_eq!(Some((1, "alice".to_string())), parse_name("1:alice"));
+            assert_eq!(None, parse_name("alice"));
+            init_name(995, "bob");
+            assert_eq!("bob", name(995));
+            assert_eq!("player 996", name(996));
+        }
+
+        it "names strategies as they are compared" {
+            let mut config = GameConfig::default();
+            assert_eq!("human", strategy_name(&config, 1, true));
+            config.seat_mut(1).belief = Some(BeliefPolicy::Fraction(0.0));
+            config.seat_mut(1).mcts = Some(Some(mcts::Budget::Iterations(200)));
+            assert_eq!("belief:none,mcts:iterations:200", strategy_name(&config, 1, false));
+            assert_eq!("greedy", strategy_name(&config, 2, false));
+        }
+
+        #[cfg(feature = "leaderboard")]
+        it "ranks players and strategies by games won" {
+            let leaderboard = Leaderboard::open(":memory:").unwrap();
+            let game = PerudoGame::new(
+                2,
+                1,
+                hashset!{},
+                crate::opening::OpeningPolicy::Likeliest,
+                crate::config::GameConfig::default());
+            let game = game.with_end_turn(1, 0);
+            leaderboard.record(&game, &[(0, false), (1, true)]).unwrap();
+            leaderboard.record(&game, &[(0, false), (1, true)]).unwrap();
+            let top = leaderboard.top_players(1).unwrap();
+            assert_eq!(vec![Standing { label: "player 0".into(), played: 2, won: 2 }], top);
+            let rates = leaderboard.strategy_win_rates(Period::Day).unwrap();
+            assert_eq!(2, rates.len());
+            assert!(rates.iter().any(|s| s.label.ends_with(" human") && s.won == 0));
+        }
+    }
+}
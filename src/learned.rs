@@ -0,0 +1,328 @@
+/// Learned policies: AI players that score each outcome open to them with a model trained
+/// elsewhere, for example on the rows written by --turns_csv, rather than by probability alone.
+/// The model is linear, or with the onnx feature, any ONNX model taking the same features.
+use crate::bet::*;
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+#[cfg(feature = "onnx")]
+use std::collections::HashMap;
+use std::fs::File;
+#[cfg(feature = "onnx")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "onnx")]
+use tract_onnx::prelude::*;
+
+/// How many features each outcome is described by.
+pub const NUM_FEATURES: usize = 7;
+
+/// Describes choosing the given outcome, which the player estimates succeeds with the given
+/// probability, for a player holding the given number of items. In order: a constant, the
+/// probability, whether it is a Perudo or Palafico call, the share of the table's items the player
+/// holds, the number of bets this round per player, and the share of the table a bet claims.
+pub fn features<B: Bet>(
+    state: &GameState<B>,
+    outcome: &TurnOutcome<B>,
+    p: f64,
+    num_own_items: usize,
+) -> [f64; NUM_FEATURES] {
+    let total_num_items = state.total_num_items.max(1) as f64;
+    let num_players = state.num_items_per_player.len().max(1) as f64;
+    let num_bets = state.history.values().map(|bets| bets.len()).sum::<usize>() as f64;
+    let claimed = match outcome {
+        TurnOutcome::Bet(bet) => bet.claimed_items().len() as f64,
+        _ => 0.0,
+    };
+    [
+        1.0,
+        p,
+        indicator(outcome == &TurnOutcome::Perudo),
+        indicator(outcome == &TurnOutcome::Palafico),
+        num_own_items as f64 / total_num_items,
+        num_bets / num_players,
+        claimed / total_num_items,
+    ]
+}
+
+#[cfg(feature = "onnx")]
+type OnnxModel = TypedRunnableModel<TypedModel>;
+
+#[cfg(feature = "onnx")]
+lazy_static! {
+    /// The ONNX models loaded so far, by path, so that each is only read and optimised once.
+    static ref ONNX_MODELS: Mutex<HashMap<String, Arc<OnnxModel>>> = Mutex::new(HashMap::new());
+}
+
+fn indicator(b: bool) -> f64 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// A model scoring outcomes by their features: linear, or an ONNX model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearnedPolicy {
+    pub weights: Vec<f64>,
+
+    /// The path of an ONNX model to score with instead of the weights, if any, which takes a
+    /// 1xNUM_FEATURES tensor of an outcome's features and gives its score.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub onnx: Option<String>,
+}
+
+impl LearnedPolicy {
+    /// The policy that scores outcomes by probability alone, as the AI usually does.
+    pub fn greedy() -> Self {
+        let mut weights = vec![0.0; NUM_FEATURES];
+        weights[1] = 1.0;
+        Self {
+            weights: weights,
+            onnx: None,
+        }
+    }
+
+    /// Loads a policy from a JSON file of its weights, e.g. {"weights": [0.0, 1.0, ...]}, or
+    /// from an ONNX model, e.g. policy.onnx, with the onnx feature.
+    pub fn load(path: &str) -> Result<Self, ScrabrudoError> {
+        if path.ends_with(".onnx") {
+            onnx_model(path)?;
+            return Ok(Self {
+                onnx: Some(path.into()),
+                ..Self::greedy()
+            });
+        }
+        let policy: Self = serde_json::from_reader(File::open(path)?)?;
+        if policy.weights.len() != NUM_FEATURES {
+            return Err(ScrabrudoError::Format(format!(
+                "Expected {} weights in {}, found {}",
+                NUM_FEATURES,
+                path,
+                policy.weights.len()
+            )));
+        }
+        Ok(policy)
+    }
+
+    /// Scores the given features, by the weights should the ONNX model fail.
+    pub fn score(&self, features: &[f64; NUM_FEATURES]) -> f64 {
+        let linear = || {
+            self.weights
+                .iter()
+                .zip(features.iter())
+                .map(|(w, f)| w * f)
+                .sum()
+        };
+        match &self.onnx {
+            Some(path) => onnx_score(path, features).unwrap_or_else(|e| {
+                warn!("{}", e);
+                linear()
+            }),
+            None => linear(),
+        }
+    }
+
+    /// Chooses the highest scoring of the given outcomes, each paired with the probability the
+    /// player gives it, or None if there are none.
+    pub fn choose<B: Bet>(
+        &self,
+        state: &GameState<B>,
+        candidates: Vec<(TurnOutcome<B>, f64)>,
+        num_own_items: usize,
+    ) -> Option<TurnOutcome<B>> {
+        candidates
+            .into_iter()
+            .map(|(outcome, p)| {
+                let score = self.score(&features(state, &outcome, p, num_own_items));
+                (outcome, score)
+            })
+            .fold(
+                None,
+                |best: Option<(TurnOutcome<B>, f64)>, (outcome, score)| match best {
+                    Some((_, best_score)) if best_score >= score => best,
+                    _ => Some((outcome, score)),
+                },
+            )
+            .map(|(outcome, _)| outcome)
+    }
+}
+
+/// The ONNX model at the given path, ready to score a row of features, loading it the first time.
+#[cfg(feature = "onnx")]
+fn onnx_model(path: &str) -> Result<Arc<OnnxModel>, ScrabrudoError> {
+    let mut models = ONNX_MODELS.lock().unwrap();
+    if let Some(model) = models.get(path) {
+        return Ok(model.clone());
+    }
+    let model = tract_onnx::onnx()
+        .model_for_path(path)
+        .and_then(|model| model.with_input_fact(0, f32::fact([1, NUM_FEATURES]).into()))
+        .and_then(|model| model.into_optimized())
+        .and_then(|model| model.into_runnable())
+        .map_err(|e| ScrabrudoError::Format(format!("Can't load model {}: {}", path, e)))?;
+    let model = Arc::new(model);
+    models.insert(path.into(), model.clone());
+    Ok(model)
+}
+
+/// Scores the given features with the ONNX model at the given path, by the first value of its
+/// first output.
+#[cfg(feature = "onnx")]
+fn onnx_score(path: &str, features: &[f64; NUM_FEATURES]) -> Result<f64, ScrabrudoError> {
+    let failed = |e: String| ScrabrudoError::Format(format!("Can't run model {}: {}", path, e));
+    let row = features.iter().map(|f| *f as f32).collect::<Vec<f32>>();
+    let input = Tensor::from_shape(&[1, NUM_FEATURES], &row).map_err(|e| failed(e.to_string()))?;
+    let outputs = onnx_model(path)?
+        .run(tvec!(input.into()))
+        .map_err(|e| failed(e.to_string()))?;
+    outputs
+        .first()
+        .and_then(|output| output.as_slice::<f32>().ok()?.first().copied())
+        .map(f64::from)
+        .ok_or_else(|| failed("no score".into()))
+}
+
+#[cfg(not(feature = "onnx"))]
+fn onnx_model(path: &str) -> Result<(), ScrabrudoError> {
+    Err(ScrabrudoError::InvalidOption(format!(
+        "Build with --features onnx to use model {}",
+        path
+    )))
+}
+
+#[cfg(not(feature = "onnx"))]
+fn onnx_score(path: &str, _features: &[f64; NUM_FEATURES]) -> Result<f64, ScrabrudoError> {
+    onnx_model(path).map(|()| 0.0)
+}
+
+/// The policy the given player chooses with in a game of the given config, if they have one.
+pub fn policy(config: &GameConfig, player_id: usize) -> Option<LearnedPolicy> {
+    config.seat(player_id).and_then(|seat| seat.learned.clone())
+}
+
+/// Parses a player and the path to their policy from its command-line form, e.g.
+/// "1:policy.json".
+pub fn parse_learned(assignment: &str) -> Option<(usize, String)> {
+    let mut split = assignment.splitn(2, ':');
+    match (split.next().map(|id| id.parse::<usize>()), split.next()) {
+        (Some(Ok(player_id)), Some(path)) if !path.is_empty() => Some((player_id, path.into())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    fn state() -> GameState<PerudoBet> {
+        crate::testing::game_state(vec![5, 5])
+    }
+
+    fn bet(quantity: usize) -> TurnOutcome<PerudoBet> {
+        TurnOutcome::Bet(PerudoBet { value: crate::die::Die::Two, quantity: quantity })
+    }
+
+    describe "learned policies" {
+        it "describes outcomes" {
+            let f = features(&state(), &TurnOutcome::Perudo, 0.25, 5);
+            assert_eq!([1.0, 0.25, 1.0, 0.0, 0.5, 0.0, 0.0], f);
+            assert_eq!(0.1, features(&state(), &bet(3), 0.5, 5)[6]);
+        }
+
+        it "chooses the highest scoring outcome" {
+            let candidates = vec![(bet(3), 0.6), (TurnOutcome::Perudo, 0.4), (bet(4), 0.5)];
+            assert_eq!(
+                Some(bet(3)),
+                LearnedPolicy::greedy().choose(&state(), candidates.clone(), 5)
+            );
+            let mut weights = LearnedPolicy::greedy().weights;
+            weights[2] = 1.0;
+            assert_eq!(
+                Some(TurnOutcome::Perudo),
+                LearnedPolicy { weights: weights, onnx: None }.choose(&state(), candidates, 5)
+            );
+            assert_eq!(None, LearnedPolicy::greedy().choose(&state(), vec![], 5));
+        }
+
+        it "loads policies" {
+            serde_json::to_writer(File::create("/tmp/policy1.json").unwrap(), &LearnedPolicy::greedy()).unwrap();
+            assert_eq!(LearnedPolicy::greedy(), LearnedPolicy::load("/tmp/policy1.json").unwrap());
+            serde_json::to_writer(File::create("/tmp/policy2.json").unwrap(), &LearnedPolicy { weights: vec![1.0], onnx: None }).unwrap();
+            assert!(LearnedPolicy::load("/tmp/policy2.json").is_err());
+        }
+
+        #[cfg(feature = "onnx")]
+        it "scores with an ONNX model" {
+            use prost::Message;
+            use tract_onnx::pb;
+
+            // A model scoring by its weights, as the linear policy would.
+            let mut weights = LearnedPolicy::greedy().weights;
+            weights[2] = 1.0;
+            let value_info = |name: &str, dims: &[i64]| pb::ValueInfoProto {
+                name: name.into(),
+                r#type: Some(pb::TypeProto {
+                    value: Some(pb::type_proto::Value::TensorType(pb::type_proto::Tensor {
+                        elem_type: pb::tensor_proto::DataType::Float as i32,
+                        shape: Some(pb::TensorShapeProto {
+                            dim: dims.iter().map(|d| pb::tensor_shape_proto::Dimension {
+                                value: Some(pb::tensor_shape_proto::dimension::Value::DimValue(*d)),
+                                ..Default::default()
+                            }).collect(),
+                        }),
+                    })),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let model = pb::ModelProto {
+                ir_version: 7,
+                opset_import: vec![pb::OperatorSetIdProto { domain: "".into(), version: 13 }],
+                graph: Some(pb::GraphProto {
+                    node: vec![pb::NodeProto {
+                        input: vec!["features".into(), "weights".into()],
+                        output: vec!["score".into()],
+                        op_type: "MatMul".into(),
+                        ..Default::default()
+                    }],
+                    initializer: vec![pb::TensorProto {
+                        name: "weights".into(),
+                        dims: vec![NUM_FEATURES as i64, 1],
+                        data_type: pb::tensor_proto::DataType::Float as i32,
+                        float_data: weights.iter().map(|w| *w as f32).collect(),
+                        ..Default::default()
+                    }],
+                    input: vec![value_info("features", &[1, NUM_FEATURES as i64])],
+                    output: vec![value_info("score", &[1, 1])],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            std::fs::write("/tmp/policy3.onnx", model.encode_to_vec()).unwrap();
+
+            let policy = LearnedPolicy::load("/tmp/policy3.onnx").unwrap();
+            assert_eq!(Some("/tmp/policy3.onnx".to_string()), policy.onnx);
+            let f = features(&state(), &TurnOutcome::Perudo, 0.25, 5);
+            assert!((1.25 - policy.score(&f)).abs() < 1e-6);
+            let candidates = vec![(bet(3), 0.6), (TurnOutcome::Perudo, 0.4), (bet(4), 0.5)];
+            assert_eq!(Some(TurnOutcome::Perudo), policy.choose(&state(), candidates, 5));
+
+            std::fs::write("/tmp/policy4.onnx", "not a model").unwrap();
+            assert!(LearnedPolicy::load("/tmp/policy4.onnx").is_err());
+        }
+
+        #[cfg(not(feature = "onnx"))]
+        it "needs the onnx feature for ONNX models" {
+            assert!(LearnedPolicy::load("/tmp/policy3.onnx").is_err());
+        }
+
+        it "parses assignments" {
+            assert_eq!(Some((1, "policy.json".into())), parse_learned("1:policy.json"));
+            assert_eq!(None, parse_learned("policy.json"));
+            assert_eq!(None, parse_learned("1:"));
+        }
+    }
+}
@@ -0,0 +1,450 @@
+/// Lobbies for games played over the network: someone creates one with the rules to play by and
+/// how many seats are for people and how many for the AI, others list those still open and join
+/// one, and once every seat is taken and everyone has readied up, the game starts. The same
+/// commands come as WebSocket messages and HTTP requests alike, and are answered the same way.
+use crate::config::*;
+use crate::dict;
+use crate::error::*;
+
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::BTreeMap;
+
+/// How players may talk at a lobby's table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatRules {
+    /// Whether they may talk at all.
+    pub enabled: bool,
+
+    /// The most messages each player may send in any window, to keep anyone from flooding the
+    /// table.
+    pub max_messages: usize,
+
+    /// How long the window is, in seconds.
+    pub window_secs: u64,
+
+    /// The most characters a message may have.
+    pub max_len: usize,
+}
+
+impl Default for ChatRules {
+    fn default() -> Self {
+        ChatRules {
+            enabled: true,
+            max_messages: 5,
+            window_secs: 10,
+            max_len: 200,
+        }
+    }
+}
+
+/// How many dice or tiles each player starts a lobby's game with.
+pub const NUM_ITEMS: usize = 5;
+
+/// Rules a lobby can't set for itself, as they reach beyond the game, such as where each seat's
+/// hand is logged.
+const FIXED_RULES: &[&str] = &["seats"];
+
+/// A command from someone looking for a game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LobbyCommand {
+    /// Opens a lobby for the given number of people and AI players, with the given rules over
+    /// the server's own, and talking at the table as given.
+    Create {
+        num_humans: usize,
+        #[serde(default)]
+        num_ai: usize,
+        #[serde(default)]
+        rules: Map<String, Value>,
+        #[serde(default)]
+        chat: ChatRules,
+    },
+
+    /// Asks for the lobbies still open.
+    List,
+
+    /// Takes a seat in the given lobby under the given name.
+    Join { lobby: usize, name: String },
+
+    /// Says the holder of the given token is ready to play.
+    Ready { lobby: usize, token: String },
+
+    /// Has every game switch to the given dictionary and lookup from its next round, for whoever
+    /// holds the server's admin token.
+    Reload {
+        admin_token: String,
+        dict: String,
+        lookup: String,
+    },
+}
+
+/// Someone who has taken a seat in a lobby.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub name: String,
+
+    /// The secret given when they joined, which they act on their seat with from then on.
+    pub token: String,
+
+    pub ready: bool,
+}
+
+/// A game waiting for its players.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lobby {
+    pub id: usize,
+
+    /// How many seats are for people, taken in the order they join.
+    pub num_humans: usize,
+
+    /// How many seats are for the AI, after the people's.
+    pub num_ai: usize,
+
+    /// The rules the game is played by.
+    pub config: GameConfig,
+
+    /// How players may talk at the table.
+    pub chat: ChatRules,
+
+    /// Those who have joined, by seat.
+    pub members: Vec<Member>,
+
+    /// Whether the game has started.
+    pub started: bool,
+}
+
+impl Lobby {
+    /// An empty lobby for the given number of people and AI players.
+    pub fn new(
+        id: usize,
+        num_humans: usize,
+        num_ai: usize,
+        config: GameConfig,
+    ) -> Result<Self, ScrabrudoError> {
+        if num_humans == 0 || num_humans + num_ai < 2 {
+            return Err(ScrabrudoError::Lobby(format!(
+                "A game needs at least one person and two players, not {} and {}",
+                num_humans,
+                num_humans + num_ai
+            )));
+        }
+        Ok(Lobby {
+            id: id,
+            num_humans: num_humans,
+            num_ai: num_ai,
+            config: config,
+            chat: ChatRules::default(),
+            members: vec![],
+            started: false,
+        })
+    }
+
+    /// How many seats the game has.
+    pub fn num_players(&self) -> usize {
+        self.num_humans + self.num_ai
+    }
+
+    /// Whether every seat for people is taken.
+    pub fn is_full(&self) -> bool {
+        self.members.len() == self.num_humans
+    }
+
+    /// Gives the next seat to the named player, returning it with the token to act on it with.
+    pub fn join(&mut self, name: &str) -> Result<(usize, String), ScrabrudoError> {
+        if self.started || self.is_full() {
+            return Err(ScrabrudoError::Lobby(format!("Lobby {} is full", self.id)));
+        }
+        let token = format!("{:016x}", thread_rng().gen::<u64>());
+        self.members.push(Member {
+            name: name.into(),
+            token: token.clone(),
+            ready: false,
+        });
+        Ok((self.members.len() - 1, token))
+    }
+
+    /// The seat of whoever holds the given token, if anyone here does.
+    pub fn seat_of(&self, token: &str) -> Option<usize> {
+        self.members.iter().position(|m| m.token == token)
+    }
+
+    /// Readies whoever holds the given token, returning whether the game can now start.
+    pub fn ready(&mut self, token: &str) -> Result<bool, ScrabrudoError> {
+        let seat = self
+            .seat_of(token)
+            .ok_or_else(|| ScrabrudoError::Lobby(format!("No such seat in lobby {}", self.id)))?;
+        self.members[seat].ready = true;
+        Ok(self.ready_to_start())
+    }
+
+    /// Whether every seat is taken and everyone is ready, with the game yet to start.
+    pub fn ready_to_start(&self) -> bool {
+        !self.started && self.is_full() && self.members.iter().all(|m| m.ready)
+    }
+
+    /// What anyone may see of the lobby: everything but the members' tokens, and the rules it
+    /// can't set.
+    pub fn summary(&self) -> Value {
+        let mut rules = serde_json::to_value(&self.config).unwrap_or(Value::Null);
+        if let Some(rules) = rules.as_object_mut() {
+            for rule in FIXED_RULES {
+                rules.remove(*rule);
+            }
+        }
+        json!({
+            "id": self.id,
+            "num_humans": self.num_humans,
+            "num_ai": self.num_ai,
+            "rules": rules,
+            "chat": self.chat,
+            "members": self
+                .members
+                .iter()
+                .map(|m| json!({ "name": m.name, "ready": m.ready }))
+                .collect::<Vec<Value>>(),
+            "started": self.started,
+        })
+    }
+}
+
+/// The server's lobbies, and the rules they start from.
+#[derive(Debug, Default)]
+pub struct Lobbies {
+    lobbies: BTreeMap<usize, Lobby>,
+    next_id: usize,
+
+    /// The server's rules, which each lobby's are set over.
+    defaults: GameConfig,
+
+    /// The secret admin commands must come with, if any are taken at all.
+    admin_token: Option<String>,
+}
+
+impl Lobbies {
+    /// No lobbies yet, starting from the given rules.
+    pub fn new(defaults: GameConfig) -> Self {
+        Lobbies {
+            lobbies: BTreeMap::new(),
+            next_id: 1,
+            defaults: defaults,
+            admin_token: None,
+        }
+    }
+
+    /// The same lobbies, taking admin commands from whoever holds the given secret, if anyone.
+    pub fn with_admin_token(self, admin_token: Option<String>) -> Self {
+        Lobbies {
+            admin_token: admin_token,
+            ..self
+        }
+    }
+
+    /// The lobby with the given ID.
+    pub fn get(&self, id: usize) -> Result<&Lobby, ScrabrudoError> {
+        self.lobbies
+            .get(&id)
+            .ok_or_else(|| ScrabrudoError::Lobby(format!("No such lobby: {}", id)))
+    }
+
+    /// The lobby and seat of whoever holds the given token, if anyone does.
+    pub fn find_seat(&self, token: &str) -> Option<(usize, usize)> {
+        self.lobbies
+            .values()
+            .find_map(|lobby| lobby.seat_of(token).map(|seat| (lobby.id, seat)))
+    }
+
+    /// Carries out the command, returning the reply, and the lobby if it is now to start, which
+    /// is then marked as started.
+    pub fn handle(
+        &mut self,
+        command: LobbyCommand,
+    ) -> Result<(Value, Option<Lobby>), ScrabrudoError> {
+        match command {
+            LobbyCommand::Create {
+                num_humans,
+                num_ai,
+                rules,
+                chat,
+            } => {
+                let config = with_rules(&self.defaults, &rules)?;
+                let lobby = Lobby {
+                    chat: chat,
+                    ..Lobby::new(self.next_id, num_humans, num_ai, config)?
+                };
+                self.next_id += 1;
+                let reply = json!({ "type": "created", "lobby": lobby.summary() });
+                self.lobbies.insert(lobby.id, lobby);
+                Ok((reply, None))
+            }
+            LobbyCommand::List => {
+                let open = self
+                    .lobbies
+                    .values()
+                    .filter(|l| !l.started)
+                    .map(Lobby::summary)
+                    .collect::<Vec<Value>>();
+                Ok((json!({ "type": "lobbies", "lobbies": open }), None))
+            }
+            LobbyCommand::Join { lobby, name } => {
+                let (seat, token) = self.get_mut(lobby)?.join(&name)?;
+                let reply =
+                    json!({ "type": "joined", "lobby": lobby, "seat": seat, "token": token });
+                Ok((reply, None))
+            }
+            LobbyCommand::Ready { lobby, token } => {
+                let lobby = self.get_mut(lobby)?;
+                let starting = lobby.ready(&token)?;
+                if starting {
+                    lobby.started = true;
+                }
+                let reply = json!({ "type": "ready", "lobby": lobby.id, "starting": starting });
+                Ok((reply, if starting { Some(lobby.clone()) } else { None }))
+            }
+            LobbyCommand::Reload {
+                admin_token,
+                dict,
+                lookup,
+            } => {
+                if self.admin_token.as_deref() != Some(admin_token.as_str()) {
+                    return Err(ScrabrudoError::Lobby("Not an admin".into()));
+                }
+                // The lookup has to cover the biggest table, started or not.
+                let num_tiles = self
+                    .lobbies
+                    .values()
+                    .map(|l| l.num_players() * NUM_ITEMS)
+                    .max()
+                    .unwrap_or(0);
+                dict::request_reload(&self.defaults, &dict, &lookup, num_tiles)?;
+                let reply = json!({ "type": "reloading", "dict": dict, "lookup": lookup });
+                Ok((reply, None))
+            }
+        }
+    }
+
+    /// The lobby with the given ID, to change.
+    fn get_mut(&mut self, id: usize) -> Result<&mut Lobby, ScrabrudoError> {
+        self.lobbies
+            .get_mut(&id)
+            .ok_or_else(|| ScrabrudoError::Lobby(format!("No such lobby: {}", id)))
+    }
+}
+
+/// The given rules set over the defaults, keeping the defaults' dictionary, lookup and input.
+fn with_rules(
+    defaults: &GameConfig,
+    rules: &Map<String, Value>,
+) -> Result<GameConfig, ScrabrudoError> {
+    let mut value = serde_json::to_value(defaults)?;
+    for (rule, setting) in rules {
+        if value.get(rule).is_none() || FIXED_RULES.contains(&rule.as_str()) {
+            return Err(ScrabrudoError::Lobby(format!("No such rule: {}", rule)));
+        }
+        value[rule] = setting.clone();
+    }
+    let mut config = serde_json::from_value::<GameConfig>(value)
+        .map_err(|e| ScrabrudoError::Lobby(format!("Invalid rules: {}", e)))?;
+    config.dict = defaults.dict.clone();
+    config.lookup = defaults.lookup.clone();
+    config.input = defaults.input.clone();
+    Ok(config)
+}
+
+#[cfg(test)]
+speculate! {
+    describe "lobbies" {
+        it "creates, lists and joins lobbies" {
+            let mut lobbies = Lobbies::new(GameConfig::default());
+            let create = serde_json::from_value::<LobbyCommand>(
+                json!({ "type": "create", "num_humans": 2, "rules": { "max_rounds": 3 }, "chat": { "max_messages": 2 } })).unwrap();
+            let (reply, _) = lobbies.handle(create).unwrap();
+            assert_eq!("created", reply["type"]);
+            assert_eq!(1, reply["lobby"]["id"]);
+            assert_eq!(Some(3), lobbies.get(1).unwrap().config.max_rounds);
+            assert_eq!(true, reply["lobby"]["chat"]["enabled"]);
+            assert_eq!(2, lobbies.get(1).unwrap().chat.max_messages);
+
+            let (reply, _) = lobbies.handle(LobbyCommand::Join { lobby: 1, name: "ana".into() }).unwrap();
+            assert_eq!(0, reply["seat"]);
+            let (reply, _) = lobbies.handle(LobbyCommand::List).unwrap();
+            assert_eq!(json!([{ "name": "ana", "ready": false }]), reply["lobbies"][0]["members"]);
+            assert!(!reply.to_string().contains(lobbies.get(1).unwrap().members[0].token.as_str()));
+            assert!(reply["lobbies"][0]["rules"]["seats"].is_null());
+        }
+
+        it "starts once everyone is in and ready" {
+            let mut lobbies = Lobbies::new(GameConfig::default());
+            lobbies.handle(LobbyCommand::Create {
+                num_humans: 2,
+                num_ai: 1,
+                rules: Map::new(),
+                chat: ChatRules::default(),
+            }).unwrap();
+            let mut tokens = vec![];
+            for name in &["ana", "ben"] {
+                let (reply, _) = lobbies.handle(LobbyCommand::Join { lobby: 1, name: name.to_string() }).unwrap();
+                tokens.push(reply["token"].as_str().unwrap().to_string());
+            }
+            assert!(lobbies.handle(LobbyCommand::Join { lobby: 1, name: "cai".into() }).is_err());
+
+            let (reply, start) = lobbies.handle(LobbyCommand::Ready { lobby: 1, token: tokens[0].clone() }).unwrap();
+            assert_eq!(false, reply["starting"]);
+            assert!(start.is_none());
+            let (reply, start) = lobbies.handle(LobbyCommand::Ready { lobby: 1, token: tokens[1].clone() }).unwrap();
+            assert_eq!(true, reply["starting"]);
+            assert_eq!(3, start.unwrap().num_players());
+            let (reply, _) = lobbies.handle(LobbyCommand::List).unwrap();
+            assert_eq!(json!([]), reply["lobbies"]);
+        }
+
+        it "refuses what can't be played" {
+            let mut lobbies = Lobbies::new(GameConfig::default());
+            let create = |num_humans, num_ai, rules: Value| LobbyCommand::Create {
+                num_humans: num_humans,
+                num_ai: num_ai,
+                rules: rules.as_object().unwrap().clone(),
+                chat: ChatRules::default(),
+            };
+            assert!(lobbies.handle(create(1, 0, json!({}))).is_err());
+            assert!(lobbies.handle(create(0, 2, json!({}))).is_err());
+            assert!(lobbies.handle(create(2, 0, json!({ "rounds": 3 }))).is_err());
+            assert!(lobbies.handle(create(2, 0, json!({ "seats": {} }))).is_err());
+            assert!(lobbies.handle(create(2, 0, json!({ "max_rounds": "three" }))).is_err());
+            assert!(lobbies.handle(LobbyCommand::Join { lobby: 9, name: "ana".into() }).is_err());
+            assert!(lobbies.handle(LobbyCommand::Ready { lobby: 9, token: "".into() }).is_err());
+        }
+
+        it "switches dictionary for the admin alone" {
+            crate::lookup::create_lookup("/tmp/lookup_admin.sstable", &hashset!{ "an".into() }, 5, 10);
+            let config = GameConfig::default();
+            let mut lobbies = Lobbies::new(config.clone()).with_admin_token(Some("secret".into()));
+            let reload = |admin_token: &str, dict: &str| LobbyCommand::Reload {
+                admin_token: admin_token.into(),
+                dict: dict.into(),
+                lookup: "/tmp/lookup_admin.sstable".into(),
+            };
+            assert!(lobbies.handle(reload("guess", "data/google-10000-english.txt")).is_err());
+            assert!(Lobbies::new(config.clone()).handle(reload("", "data/google-10000-english.txt")).is_err());
+            assert!(lobbies.handle(reload("secret", "/tmp/no_such_dict.txt")).is_err());
+            assert_eq!(None, dict::apply_reload(&config));
+
+            let (reply, _) = lobbies.handle(reload("secret", "data/google-10000-english.txt")).unwrap();
+            assert_eq!("reloading", reply["type"]);
+            assert!(dict::apply_reload(&config).is_some());
+
+            // The lookup has to cover every tile at the biggest table.
+            lobbies.handle(LobbyCommand::Create {
+                num_humans: 1,
+                num_ai: 1,
+                rules: Map::new(),
+                chat: ChatRules::default(),
+            }).unwrap();
+            assert!(lobbies.handle(reload("secret", "data/google-10000-english.txt")).is_err());
+        }
+    }
+}
@@ -0,0 +1,238 @@
+/// Tagging log messages with the game, round and player they're about, and routing each player's
+/// private messages to a sink of their own.
+use crate::config::*;
+use crate::error::*;
+
+use log::{Log, Metadata, Record};
+use rand::{thread_rng, Rng};
+#[cfg(test)]
+use speculate::speculate;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref PLAYER_SINKS: Mutex<HashMap<String, File>> = Mutex::new(HashMap::new());
+}
+
+// Every thread tags its own game, so that games played at once on different threads don't
+// tag each other's messages.
+thread_local! {
+    static CONTEXT: RefCell<LogContext> = RefCell::new(LogContext::default());
+}
+
+/// What the engine is doing, as far as log messages are concerned.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogContext {
+    /// The game being played, if any.
+    pub game_id: Option<u32>,
+
+    /// The round of the game, counting from 1.
+    pub round: usize,
+
+    /// The player whose turn it is, if any.
+    pub player_id: Option<usize>,
+}
+
+impl LogContext {
+    /// The tag to put before messages, if a game is being played.
+    pub fn tag(&self) -> Option<String> {
+        let game_id = self.game_id?;
+        Some(match self.player_id {
+            Some(player_id) => format!(
+                "[game {:08x} round {} player {}]",
+                game_id, self.round, player_id
+            ),
+            None => format!("[game {:08x} round {}]", game_id, self.round),
+        })
+    }
+}
+
+/// Starts tagging messages with a new game.
+pub fn begin_game() {
+    // Not drawn from the game's generator, so that tagging doesn't change what a seed deals.
+    let game_id = thread_rng().gen();
+    CONTEXT.with(|context| {
+        *context.borrow_mut() = LogContext {
+            game_id: Some(game_id),
+            round: 1,
+            player_id: None,
+        }
+    });
+}
+
+/// Moves the tag on to the given round, as the game counts them.
+pub fn set_round(round: usize) {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.round = round;
+        context.player_id = None;
+    });
+}
+
+/// Tags messages with the player whose turn it is.
+pub fn set_player(player_id: usize) {
+    CONTEXT.with(|context| context.borrow_mut().player_id = Some(player_id));
+}
+
+/// What the engine is doing now on this thread.
+pub fn context() -> LogContext {
+    CONTEXT.with(|context| context.borrow().clone())
+}
+
+/// The current tag on this thread, if a game is being played.
+pub fn tag() -> Option<String> {
+    CONTEXT.with(|context| context.borrow().tag())
+}
+
+/// Appends the given player's private messages in games of the given config, such as their hand,
+/// to the file at the given path rather than logging them with everything else.
+pub fn route_player(
+    config: &mut GameConfig,
+    player_id: usize,
+    path: &str,
+) -> Result<(), ScrabrudoError> {
+    open_sink(&mut PLAYER_SINKS.lock().unwrap(), path)?;
+    config.seat_mut(player_id).log = Some(path.into());
+    Ok(())
+}
+
+/// The open file at the given path, opening it for appending if it isn't already.
+fn open_sink<'a>(
+    sinks: &'a mut HashMap<String, File>,
+    path: &str,
+) -> Result<&'a mut File, ScrabrudoError> {
+    if !sinks.contains_key(path) {
+        let f = OpenOptions::new().create(true).append(true).open(path)?;
+        sinks.insert(path.into(), f);
+    }
+    Ok(sinks.get_mut(path).unwrap())
+}
+
+/// Parses a routing from its command-line form, e.g. "1:player1.log".
+pub fn parse_route(route: &str) -> Option<(usize, String)> {
+    let mut split = route.splitn(2, ':');
+    match (split.next().map(|id| id.parse::<usize>()), split.next()) {
+        (Some(Ok(player_id)), Some(path)) if !path.is_empty() => Some((player_id, path.into())),
+        _ => None,
+    }
+}
+
+/// Sends a message only the given player should see to their sink in games of the given config,
+/// or logs it if they have none there.
+pub fn private(config: &GameConfig, player_id: usize, message: &str) {
+    if let Some(path) = config.seat(player_id).and_then(|seat| seat.log.as_ref()) {
+        let line = match tag() {
+            Some(tag) => format!("{} {}", tag, message),
+            None => message.to_string(),
+        };
+        let mut sinks = PLAYER_SINKS.lock().unwrap();
+        let written = open_sink(&mut sinks, path)
+            .and_then(|sink| writeln!(sink, "{}", line).map_err(ScrabrudoError::from));
+        if let Err(e) = written {
+            error!("Couldn't write to the log for player {}: {}", player_id, e);
+        }
+        return;
+    }
+    info!("{}", message);
+}
+
+/// Passes messages on to another logger with the current tag in front.
+struct TaggedLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for TaggedLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        match tag() {
+            Some(tag) => self.inner.log(
+                &Record::builder()
+                    .args(format_args!("{} {}", tag, record.args()))
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .build(),
+            ),
+            None => self.inner.log(record),
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Logs as pretty_env_logger does, configured by RUST_LOG, with every message tagged.
+pub fn init_logging() {
+    let mut builder = pretty_env_logger::formatted_builder();
+    if let Ok(filters) = env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+    let inner = builder.build();
+    let max_level = inner.filter();
+    if log::set_boxed_logger(Box::new(TaggedLogger {
+        inner: Box::new(inner),
+    }))
+    .is_ok()
+    {
+        log::set_max_level(max_level);
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "log tags" {
+        it "tags messages once a game starts" {
+            assert_eq!(None, LogContext::default().tag());
+            let mut context = LogContext {
+                game_id: Some(0xbeef),
+                round: 2,
+                player_id: None,
+            };
+            assert_eq!(Some("[game 0000beef round 2]".into()), context.tag());
+            context.player_id = Some(1);
+            assert_eq!(Some("[game 0000beef round 2 player 1]".into()), context.tag());
+        }
+
+        it "keeps each thread's tags apart" {
+            begin_game();
+            let game_id = context().game_id;
+            std::thread::spawn(|| {
+                assert_eq!(None, context().game_id);
+                begin_game();
+                set_player(1);
+            })
+            .join()
+            .unwrap();
+            assert_eq!(game_id, context().game_id);
+            assert_eq!(None, context().player_id);
+        }
+
+        it "routes each game's players to their own logs" {
+            let path = std::env::temp_dir().join(format!("scrabrudo-route-{}.log", thread_rng().gen::<u32>()));
+            let path = path.to_str().unwrap();
+            let mut config = GameConfig::default();
+            route_player(&mut config, 1, path).unwrap();
+            private(&config, 1, "routed");
+            private(&config, 2, "logged");
+            private(&GameConfig::default(), 1, "logged");
+            assert_eq!("routed\n", std::fs::read_to_string(path).unwrap());
+            std::fs::remove_file(path).unwrap();
+        }
+
+        it "parses routes" {
+            assert_eq!(Some((1, "player1.log".into())), parse_route("1:player1.log"));
+            assert_eq!(Some((0, "/tmp/a:b.log".into())), parse_route("0:/tmp/a:b.log"));
+            assert_eq!(None, parse_route("player1.log"));
+            assert_eq!(None, parse_route("1:"));
+        }
+    }
+}
@@ -0,0 +1,531 @@
+/// Building lookups: the Monte Carlo probabilities of finding every sorted set of letters from
+/// the dictionary's words in each number of tiles, stored in an SSTable for the AI to read.
+use crate::bet::*;
+use crate::caution::*;
+use crate::cli::*;
+use crate::dict;
+use crate::dict::*;
+use crate::error::*;
+use crate::monte_carlo::*;
+use crate::tile::*;
+
+use rayon::prelude::*;
+#[cfg(test)]
+use speculate::speculate;
+use sstable::{Options, TableBuilder};
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::iter;
+
+/// Sorts a word by its chars.
+fn sort_word(word: &str) -> String {
+    let mut chars = word.chars().collect::<Vec<char>>();
+    chars.sort();
+    chars.iter().collect()
+}
+
+/// Counts each distinct char of a word, in sorted order.
+fn char_counts(word: &str) -> Vec<(char, usize)> {
+    let mut counts: Vec<(char, usize)> = vec![];
+    for c in sort_word(word).chars() {
+        match counts.last_mut() {
+            Some((last, count)) if *last == c => *count += 1,
+            _ => counts.push((c, 1)),
+        }
+    }
+    counts
+}
+
+/// Generate the word and all its substrings.
+/// e.g. HATE, ATE, HTE, HA, HT, HE, AT, AE, TE, H, A, T, E
+/// Each word will be sorted to avoid further duplicates:
+/// e.g. AEHT, AET, EHT, AH, HT, EH, AT, AE, ET, H, A, T, E
+///
+/// Rather than taking the powerset of the characters of the word, this chooses how many of each
+/// distinct char to take, so that repeated letters give each sorted substring only once and words
+/// of any length can be expanded. Only the longest choices that fit on the table are made, and
+/// the rest found within them. The empty word is dropped.
+pub fn all_sorted_substrings(word: &String, max_length: usize) -> HashSet<String> {
+    let mut substrings = HashSet::new();
+    add_sorted_substrings(word, None, max_length, &mut substrings);
+    substrings
+}
+
+/// Adds to those already found the sorted substrings of the word that fit on the table, only those
+/// starting with the given letter if there is one. Substrings already found are never expanded
+/// again, so words sharing letters with those before them cost little more.
+pub fn add_sorted_substrings(
+    word: &String,
+    letter: Option<char>,
+    max_length: usize,
+    substrings: &mut HashSet<String>,
+) {
+    let counts = char_counts(word);
+    // Being sorted, substrings starting with the letter take none of the chars before it.
+    let counts = match letter {
+        Some(letter) => match counts.iter().position(|(c, _)| *c == letter) {
+            Some(i) => &counts[i..],
+            None => return,
+        },
+        None => &counts[..],
+    };
+    let length = counts
+        .iter()
+        .map(|(_, count)| count)
+        .sum::<usize>()
+        .min(max_length);
+    let mut longest = vec![];
+    choose(counts, length, &mut String::new(), &mut longest);
+    for chosen in longest {
+        add_with_substrings(&chosen, letter, substrings);
+    }
+}
+
+/// Adds to the chosen every sorted choice of exactly the given number of the chars, in turn.
+fn choose(counts: &[(char, usize)], length: usize, prefix: &mut String, chosen: &mut Vec<String>) {
+    let ((c, count), rest) = match counts.split_first() {
+        Some(split) => split,
+        None => {
+            if length == 0 {
+                chosen.push(prefix.clone());
+            }
+            return;
+        }
+    };
+    let available = rest.iter().map(|(_, count)| count).sum::<usize>();
+    for k in length.saturating_sub(available)..=(*count).min(length) {
+        let start = prefix.len();
+        prefix.extend(iter::repeat_n(*c, k));
+        choose(rest, length - k, prefix, chosen);
+        prefix.truncate(start);
+    }
+}
+
+/// Adds the sorted substring and every non-empty one within it, only those starting with the
+/// given letter if there is one. Every substring is added with all of those within it, so finding
+/// one already added means they are too, and it goes no further.
+fn add_with_substrings(sorted: &str, letter: Option<char>, substrings: &mut HashSet<String>) {
+    if sorted.is_empty()
+        || letter.is_some_and(|letter| !sorted.starts_with(letter))
+        || substrings.contains(sorted)
+    {
+        return;
+    }
+    substrings.insert(sorted.to_string());
+    let chars = sorted.chars().collect::<Vec<char>>();
+    for i in 0..chars.len() {
+        // Dropping any one of a run of the same char leaves the same substring.
+        if i > 0 && chars[i] == chars[i - 1] {
+            continue;
+        }
+        let without = chars[..i].iter().chain(&chars[i + 1..]).collect::<String>();
+        add_with_substrings(&without, letter, substrings);
+    }
+}
+
+/// How many substrings' probabilities are computed at once, and held until written out.
+pub const WRITE_CHUNK_SIZE: usize = 10_000;
+
+/// The letters the keys of a lookup of the given words are sharded by, in order. Each shard
+/// holds the keys whose first letter is its own, and keys are sorted, so writing the shards in
+/// this order writes every key in order.
+fn shard_letters(words: &HashSet<String>) -> Vec<char> {
+    let mut letters = words
+        .par_iter()
+        .flat_map(|w| w.chars().collect::<Vec<char>>())
+        .collect::<HashSet<char>>()
+        .into_iter()
+        .collect::<Vec<char>>();
+    letters.sort();
+    letters
+}
+
+/// The keys of a lookup of the given words that fall in the shard of the given letter.
+fn expand_shard(words: &HashSet<String>, letter: char, max_num_items: usize) -> HashSet<String> {
+    // Each thread gathers keys of its own, skipping those it has already found.
+    words
+        .par_iter()
+        .filter(|w| w.contains(letter))
+        .fold(HashSet::new, |mut keys, w| {
+            add_sorted_substrings(w, Some(letter), max_num_items, &mut keys);
+            keys
+        })
+        .reduce(HashSet::new, |mut keys, more| {
+            keys.extend(more);
+            keys
+        })
+}
+
+/// Creates the lookup a shard at a time, dealing from the current tile set.
+/// For each letter we explode out via flat_map to the substrings starting with it, sort them, and
+/// map these to their Monte Carlo probabilities a chunk at a time, writing each chunk out as it
+/// goes, so that only one shard's substrings and one chunk's probabilities are ever held at
+/// once, however large the dictionary.
+pub fn create_lookup(
+    lookup_path: &str,
+    words: &HashSet<String>,
+    max_num_items: usize,
+    num_trials: u32,
+) {
+    // Keys are spelled in tiles, so where the tile set deals a letter pair it is a single char.
+    let keyed;
+    let words = match tile_set().pairs().is_empty() {
+        true => words,
+        false => {
+            keyed = words
+                .par_iter()
+                .map(|w| tile_key(&ScrabrudoBet::from_word(w).tiles))
+                .collect::<HashSet<String>>();
+            &keyed
+        }
+    };
+
+    // Every word is tested against the same samples, drawn once up front.
+    let reservoir = Reservoir::new(max_num_items, num_trials);
+    info!("Drew {} samples per number of tiles", num_trials);
+
+    let lookup_file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(lookup_path)
+        .unwrap();
+    let mut builder = TableBuilder::new(Options::default(), lookup_file);
+
+    // Record that rows hold exact probabilities too, so that older lookups can be told apart.
+    // The metadata key sorts before every word, so goes first.
+    let meta = LookupMeta {
+        exact: true,
+        tile_set_hash: Some(tile_set().hash()),
+        dict_profile: dict_profile(),
+        num_trials: Some(num_trials),
+    };
+    builder
+        .add(
+            LOOKUP_META_KEY.as_bytes(),
+            &serde_json::to_vec(&meta).unwrap(),
+        )
+        .unwrap();
+
+    let letters = shard_letters(words);
+    let mut num_expanded = 0;
+    for (i, letter) in letters.iter().enumerate() {
+        // Expand out the dict to the subwords in this shard, sorted as the SSTable needs.
+        let mut expanded_words = expand_shard(words, *letter, max_num_items)
+            .into_iter()
+            .collect::<Vec<String>>();
+        expanded_words.sort();
+        info!(
+            "Shard {} / {}: created {} word expansions starting with {}",
+            i + 1,
+            letters.len(),
+            expanded_words.len(),
+            letter
+        );
+
+        // Compute the probabilities a chunk at a time, writing each chunk out in order before
+        // computing the next.
+        let mut num_calculated = 0;
+        for chunk in expanded_words.chunks(WRITE_CHUNK_SIZE) {
+            let probs = chunk
+                .par_iter()
+                .map(|s| {
+                    // Compute probs and encode
+                    bincode::serialize(&probabilities(s, &reservoir)).unwrap()
+                })
+                .collect::<Vec<Vec<u8>>>();
+            for (s, probs) in chunk.iter().zip(probs) {
+                builder.add(s.as_bytes(), &probs).unwrap();
+            }
+            num_calculated += chunk.len();
+            info!(
+                "{} / {} probs calculated starting with {}",
+                num_calculated,
+                expanded_words.len(),
+                letter
+            );
+        }
+        num_expanded += expanded_words.len();
+    }
+    info!("Created {} word expansions", num_expanded);
+    builder.finish().unwrap();
+}
+
+/// Computes the various probabilities of finding the given substring in each possible number of
+/// items, both at least and exactly, against the shared samples in the reservoir.
+/// Each returns a vec where index equates to the number of items we're searching in.
+fn probabilities(s: &str, reservoir: &Reservoir) -> LookupRow {
+    let probs = |exact: bool| {
+        (0..=reservoir.max_num_items())
+            .map(|n| reservoir.probability(n, s, exact))
+            .collect()
+    };
+    LookupRow {
+        probs: probs(false),
+        exact_probs: probs(true),
+    }
+}
+
+/// Builds the lookup asked for on the command line, for the dictionary and tile set given.
+pub fn precompute(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    if let Some(matches) = matches.subcommand_matches("estimate") {
+        return estimate_word(matches);
+    }
+    if let Some(tile_set_path) = matches.value_of("tile_set") {
+        init_tile_set(load_tile_set(tile_set_path)?);
+    }
+    let dict_profile = parse_option(
+        "dictionary profile",
+        matches.value_of("dict_profile").unwrap_or("full"),
+        DictProfile::from_name,
+    )?;
+    if dict_profile.needs_frequencies() {
+        init_frequencies(
+            matches
+                .value_of("frequency_path")
+                .unwrap_or("data/google-10000-english.txt"),
+        )?;
+    }
+    init_dict_profile(dict_profile);
+    init_dict(required_option(matches, "dictionary_path")?)?;
+
+    let num_tiles = parse_option(
+        "number of tiles",
+        required_option(matches, "num_tiles")?,
+        |n| n.parse::<usize>().ok(),
+    )?;
+    let num_trials = parse_option(
+        "number of trials",
+        required_option(matches, "num_trials")?,
+        |n| n.parse::<u32>().ok(),
+    )?;
+    create_lookup(
+        required_option(matches, "lookup_path")?,
+        &dict::dict(),
+        num_tiles,
+        num_trials,
+    );
+    Ok(())
+}
+
+/// Reports the chance of finding a single word among some tiles, sampled as for a lookup, with a
+/// confidence interval around it.
+pub fn estimate_word(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    if let Some(tile_set_path) = matches.value_of("tile_set") {
+        init_tile_set(load_tile_set(tile_set_path)?);
+    }
+    let word = required_option(matches, "word")?.to_lowercase();
+    let key = tile_key(&ScrabrudoBet::parse(&word)?.tiles);
+    // Counts must fit the packed checks.
+    let num_tiles = parse_option("number of tiles", required_option(matches, "tiles")?, |n| {
+        n.parse::<u32>().ok().filter(|n| *n < 128)
+    })?;
+    let num_trials = parse_option(
+        "number of trials",
+        matches.value_of("trials").unwrap_or("100000"),
+        |n| n.parse::<u32>().ok().filter(|n| *n > 0),
+    )?;
+    let level = parse_option(
+        "confidence",
+        matches.value_of("confidence").unwrap_or("95"),
+        |level| {
+            level
+                .parse::<u32>()
+                .ok()
+                .filter(|l| z_for_level(*l).is_some())
+        },
+    )?;
+    let exact = matches.is_present("exact");
+    let estimate = estimate(
+        num_tiles,
+        &key,
+        num_trials,
+        exact,
+        z_for_level(level).unwrap(),
+    );
+    info!(
+        "{}{} in {} tiles: {:.2}% ({}% CI {:.2}% to {:.2}%, {} trials)",
+        if exact { "Exactly " } else { "" },
+        word,
+        num_tiles,
+        100.0 * estimate.p,
+        level,
+        100.0 * estimate.low,
+        100.0 * estimate.high,
+        estimate.num_trials
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "substring generation" {
+        it "sorts words" {
+            assert_eq!("abc", sort_word("abc"));
+            assert_eq!("act", sort_word("cat"));
+            assert_eq!("aeht", sort_word("hate"));
+        }
+
+        it "generates substrings correctly" {
+            let expected = hashset! {
+                "aht".into(),
+                "et".into(),
+                "aet".into(),
+                "aeht".into(),
+                "e".into(),
+                "ah".into(),
+                "t".into(),
+                "eh".into(),
+                "ht".into(),
+                "ae".into(),
+                "at".into(),
+                "aeh".into(),
+                "h".into(),
+                "eht".into(),
+                "a".into()
+            };
+            let actual = all_sorted_substrings(&"hate".into(), 4);
+            assert_eq!(expected, actual);
+        }
+
+        it "takes repeated letters once" {
+            assert_eq!(vec![('a', 2), ('b', 1)], char_counts("aba"));
+            let expected = hashset! {
+                "a".into(),
+                "b".into(),
+                "aa".into(),
+                "ab".into(),
+                "aab".into()
+            };
+            assert_eq!(expected, all_sorted_substrings(&"aba".into(), 3));
+        }
+
+        it "expands words of any length" {
+            let long = format!("{}b", "a".repeat(70));
+            let expected = hashset! {
+                "a".into(),
+                "aa".into(),
+                "aaa".into(),
+                "b".into(),
+                "ab".into(),
+                "aab".into()
+            };
+            assert_eq!(expected, all_sorted_substrings(&long, 3));
+        }
+
+        it "finds substrings within those found before" {
+            let mut substrings = hashset! { "ac".to_string() };
+            add_sorted_substrings(&"ca".into(), None, 2, &mut substrings);
+            // Having found "ac" already, those within it aren't looked for.
+            assert_eq!(hashset! { "ac".into() }, substrings);
+            add_sorted_substrings(&"cat".into(), None, 2, &mut substrings);
+            assert_eq!(
+                hashset! { "ac".into(), "at".into(), "ct".into(), "a".into(), "c".into(), "t".into() },
+                substrings);
+            let mut from_c = HashSet::new();
+            add_sorted_substrings(&"cat".into(), Some('c'), 2, &mut from_c);
+            assert_eq!(hashset! { "c".into(), "ct".into() }, from_c);
+            add_sorted_substrings(&"cat".into(), Some('b'), 2, &mut from_c);
+            assert_eq!(2, from_c.len());
+        }
+
+        it "enforces a max length" {
+            let expected = hashset! {
+                "et".into(),
+                "e".into(),
+                "ah".into(),
+                "t".into(),
+                "eh".into(),
+                "ht".into(),
+                "ae".into(),
+                "at".into(),
+                "h".into(),
+                "a".into()
+            };
+            let actual = all_sorted_substrings(&"hate".into(), 2);
+            assert_eq!(expected, actual);
+        }
+    }
+
+    describe "lookup generation" {
+        it "expands a shard at a time" {
+            let words = hashset!{ "bat".into(), "cat".into(), "tab".into() };
+            assert_eq!(vec!['a', 'b', 'c', 't'], shard_letters(&words));
+            let shard = expand_shard(&words, 'b', 2);
+            assert_eq!(hashset!{ "b".to_string(), "bt".into() }, shard);
+            let all = shard_letters(&words)
+                .into_iter()
+                .flat_map(|letter| expand_shard(&words, letter, 3))
+                .collect::<HashSet<String>>();
+            let expected = words
+                .iter()
+                .flat_map(|w| all_sorted_substrings(w, 3))
+                .collect::<HashSet<String>>();
+            assert_eq!(expected, all);
+        }
+
+        it "creates a small lookup table" {
+            create_lookup("/tmp/lookup1.sstable", &hashset!{ "an".into() }, 5, 10000);
+            dict::init_lookup("/tmp/lookup1.sstable").unwrap();
+
+            assert_eq!(3, dict::lookup_len());
+            assert!(dict::lookup_has("a"));
+            assert!(dict::lookup_has("n"));
+            assert!(dict::lookup_has("an"));
+
+            let probs = dict::lookup_probs("a").unwrap();
+
+            // We should always have for each amount of tiles, plus the zero-case.
+            assert_eq!(6, probs.len());
+
+            // Finding 'a' in 0 dice is always impossible.
+            assert_eq!(0.0, probs[0]);
+
+            // Always monotonically increasing as you add more dice
+            info!("{:?}", probs);
+            for i in 1..5 {
+                assert!(probs[i] > probs[i - 1]);
+            }
+
+            // Finding exactly 'a' is never likelier than finding at least 'a'.
+            assert!(dict::lookup_meta().exact);
+            assert_eq!(Some(TileSet::default().hash()), dict::lookup_meta().tile_set_hash);
+            let exact_probs = dict::lookup_exact_probs("a").unwrap();
+            assert_eq!(6, exact_probs.len());
+            assert_eq!(0.0, exact_probs[0]);
+            for i in 1..5 {
+                assert!(exact_probs[i] <= probs[i] + 0.05);
+            }
+        }
+
+        it "creates a larger lookup table" {
+            create_lookup("/tmp/lookup2.sstable", &hashset!{ "bat".into(), "cat".into() }, 5, 10);
+            dict::init_lookup("/tmp/lookup2.sstable").unwrap();
+            assert_eq!(11, dict::lookup_len());
+        }
+
+        it "checks a lookup to switch to covers the tiles in play" {
+            create_lookup("/tmp/lookup3.sstable", &hashset!{ "an".into() }, 5, 10);
+            let lookup = dict::Lookup::open("/tmp/lookup3.sstable").unwrap();
+            assert_eq!(Some(5), lookup.max_num_tiles());
+            assert_eq!(Some(10), lookup.meta().num_trials);
+            let config = crate::config::GameConfig::default();
+            let other = crate::config::GameConfig::default();
+            assert!(dict::request_reload(&config, "data/google-10000-english.txt", "/tmp/lookup3.sstable", 6).is_err());
+            assert!(dict::request_reload(&config, "/tmp/no_such_dict.txt", "/tmp/lookup3.sstable", 5).is_err());
+            assert_eq!(None, dict::apply_reload(&config));
+            assert!(dict::request_reload(&config, "data/google-10000-english.txt", "/tmp/lookup3.sstable", 5).is_ok());
+            // Only games of the config asked switch, and only once.
+            assert_eq!(None, dict::apply_reload(&other));
+            let reloaded = dict::apply_reload(&config).unwrap();
+            assert!(reloaded.dict.get().words().contains("the"));
+            assert_eq!(Some(5), reloaded.lookup.get().max_num_tiles());
+            assert_eq!(None, dict::apply_reload(&reloaded));
+            assert_eq!(None, dict::apply_reload(&other));
+        }
+    }
+}
@@ -15,65 +15,694 @@ extern crate bincode;
 extern crate lazy_static;
 extern crate clap;
 extern crate rayon;
+extern crate rustyline;
+#[cfg(feature = "leaderboard")]
+extern crate rusqlite;
+#[cfg(any(feature = "dashboard", feature = "net"))]
+extern crate tungstenite;
+#[cfg(feature = "onnx")]
+extern crate tract_onnx;
 #[macro_use]
 extern crate maplit;
+extern crate serde;
+extern crate serde_json;
 extern crate sstable;
+extern crate toml;
+extern crate unicode_normalization;
 
+pub mod analysis;
+pub mod any_raise;
+pub mod background;
+pub mod belief;
 pub mod bet;
+pub mod blitz;
+pub mod bundled;
+pub mod cache;
+pub mod caution;
+pub mod cli;
+pub mod color;
+pub mod compare;
+pub mod config;
+pub mod constraint;
+pub mod crash;
+pub mod daily;
+pub mod dashboard;
+pub mod deduction;
+pub mod demo;
 pub mod dict;
+pub mod dict_stats;
 pub mod die;
+pub mod diff;
+pub mod display;
+pub mod error;
+pub mod explain;
 pub mod game;
+pub mod golden;
 pub mod hand;
+pub mod heatmap;
+pub mod input;
+pub mod lang;
+pub mod leaderboard;
+pub mod learned;
+pub mod lobby;
+pub mod logging;
+pub mod lookup;
+pub mod mcts;
+pub mod metrics;
+pub mod monte_carlo;
+pub mod multiset;
+pub mod net;
+pub mod notation;
+pub mod notify;
+pub mod opening;
+pub mod pacing;
 pub mod player;
+pub mod pool;
+pub mod prob;
+pub mod prob_store;
+pub mod replay;
+pub mod risk;
+pub mod rng;
+pub mod sampler;
+pub mod save;
+pub mod scenario;
+pub mod schema;
+pub mod series;
+pub mod step;
+pub mod summary;
+pub mod symbol;
 pub mod testing;
+pub mod tie_break;
 pub mod tile;
+pub mod timebank;
+pub mod trade;
+pub mod transposition;
+pub mod variant;
+pub mod verify;
 
+use crate::cli::*;
+use crate::config::*;
+use crate::display::*;
+use crate::error::*;
 use crate::game::*;
+use crate::lang::*;
+use crate::opening::*;
+use crate::summary::*;
+use crate::tile::*;
+use crate::timebank::*;
 
-use clap::App;
-use std::collections::HashSet;
-
-fn main() {
-    pretty_env_logger::init();
-
-    let matches = App::new("Scrabrudo")
-        .version("0.1")
-        .about("A mixture of Scrabble and Perudo")
-        .author("Harry Askham")
-        .args_from_usage(
-            "-m, --mode=[MODE] 'perudo or scrabrudo'
-                        -n, --num_players=[NUM_PLAYERS] 'the number of players'
-                        -h, --human_index=[HUMAN_INDEX] 'which, if any, is the human'
-                        -d, --dictionary_path=[DICTIONARY] 'the path to the .txt dict to use'
-                        -l, --lookup_path=[LOOKUP] 'the path to the .bin lookup to write'",
-        )
-        .get_matches();
-
-    let mode = matches.value_of("mode").unwrap_or("scrabrudo");
-    let num_players: usize = matches
-        .value_of("num_players")
-        .unwrap_or("2")
-        .parse::<usize>()
-        .unwrap();
-    let mut human_indices: HashSet<usize> = hashset! {};
-    match matches.value_of("human_index") {
-        Some(x) => {
-            human_indices.insert(x.parse::<usize>().unwrap());
+use std::collections::{BTreeSet, HashSet};
+use std::time::Duration;
+
+fn main() -> Result<(), ScrabrudoError> {
+    logging::init_logging();
+
+    let matches = cli::matches_from(std::env::args().collect())?;
+    crash::install_panic_hook(
+        matches
+            .subcommand()
+            .1
+            .and_then(|matches| matches.value_of("crash_dump"))
+            .unwrap_or(crash::DEFAULT_CRASH_DUMP_PATH),
+    );
+    match matches.subcommand() {
+        ("play", Some(matches)) => play_command(matches),
+        ("simulate", Some(matches)) => simulate_command(matches),
+        ("demo", Some(matches)) => demo_command(matches),
+        ("precompute", Some(matches)) => lookup::precompute(matches),
+        ("analyze", Some(matches)) => analyze_command(matches),
+        ("serve", Some(matches)) => serve_command(matches),
+        ("replay", Some(matches)) => replay_command(matches),
+        ("verify", Some(matches)) => verify_command(matches),
+        ("stats", Some(matches)) => stats_command(matches),
+        ("dict", Some(matches)) => dict_command(matches),
+        (name, _) => Err(ScrabrudoError::InvalidOption(format!(
+            "Invalid subcommand: {}",
+            name
+        ))),
+    }
+}
+
+/// Plays a game or match at the terminal, or the daily puzzle.
+fn play_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    let mut config = game_config(matches)?;
+    let (num_players, opening) = init_ai(matches, &mut config)?;
+    let human_indices = human_indices(matches)?;
+    display::init_rack_order(parse_option(
+        "rack order",
+        matches.value_of("rack_order").unwrap_or("alphabetical"),
+        RackOrder::from_name,
+    )?);
+    lang::init_lang(parse_option(
+        "language",
+        matches.value_of("lang").unwrap_or("en"),
+        Lang::from_name,
+    )?);
+    display::init_practice(matches.is_present("practice"));
+    display::init_tracker(matches.is_present("tracker"));
+    display::init_hints(matches.is_present("hints"));
+    display::init_accessible(matches.is_present("accessible"));
+    // Colour codes would only be read out by a screen reader.
+    color::init_color(matches.is_present("color") && !matches.is_present("accessible"))?;
+    step::init_step(matches.is_present("step"));
+    summary::init_summary(parse_option(
+        "summary",
+        matches.value_of("summary").unwrap_or("full"),
+        Verbosity::from_name,
+    )?);
+    explain::init_coach(matches.is_present("explain"));
+    init_player_logs(matches, &mut config)?;
+    init_pacing(matches)?;
+    if matches.is_present("bell") {
+        notify::register(Box::new(notify::bell));
+    }
+    if let Some(autosave_path) = matches.value_of("autosave") {
+        save::init_autosave(autosave_path);
+    }
+    init_records(matches)?;
+    let match_games = match matches.value_of("match_games") {
+        Some(n) => Some(parse_option("number of games", n, |n| n.parse::<usize>().ok())?),
+        None => None,
+    };
+    let sitting = variant::Sitting {
+        resume: matches.value_of("resume").map(String::from),
+        match_games: match_games,
+        match_file: matches.value_of("match_file").map(String::from),
+    };
+
+    if matches.is_present("daily") {
+        init_scrabrudo(matches, &WordStyle::default())?;
+        input::init_completion(Box::new(dict::words_starting_with));
+        let result = daily::play_daily::<ScrabrudoGame>(daily::today())?;
+        info!("Share your result:\n{}", result);
+        return Ok(());
+    }
+    let variant = variant::variant(matches.value_of("variant").unwrap_or("scrabrudo"))?;
+    // Every player is dealt 5 items.
+    variant.init(matches, &config, Some(num_players * 5))?;
+    if let Some(completions) = variant.completions {
+        input::init_completion(Box::new(completions));
+    }
+    variant.play(num_players, human_indices, opening, config, sitting)?;
+    background::finish_building();
+    Ok(())
+}
+
+/// Plays all-AI games to calibrate the AI, compare strategies or check a golden game.
+fn simulate_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    let mut config = game_config(matches)?;
+    let (num_players, opening) = init_ai(matches, &mut config)?;
+    let variant = variant::variant(matches.value_of("variant").unwrap_or("perudo"))?;
+    variant.init(matches, &config, None)?;
+    match required_option(matches, "TASK")? {
+        "calibrate" => {
+            let num_games = parse_option(
+                "number of games",
+                matches.value_of("calibrate_games").unwrap_or("20"),
+                |n| n.parse::<usize>().ok(),
+            )?;
+            variant.calibrate(num_players, num_games, opening, config)
+        }
+        "compare" => {
+            let num_games = parse_option(
+                "number of games",
+                matches.value_of("compare_games").unwrap_or("100"),
+                |n| n.parse::<usize>().ok(),
+            )?;
+            let strategies = [
+                parse_option(
+                    "strategy",
+                    matches.value_of("strategy_a").unwrap_or("greedy"),
+                    compare::Strategy::from_name,
+                )?,
+                parse_option(
+                    "strategy",
+                    matches.value_of("strategy_b").unwrap_or("greedy"),
+                    compare::Strategy::from_name,
+                )?,
+            ];
+            let comparison =
+                variant.compare(num_players, 5, num_games, &strategies, &opening, &config)?;
+            info!("{}", comparison);
+            Ok(())
+        }
+        "golden" => {
+            let golden_path = required_option(matches, "golden")?;
+            let seed = parse_option(
+                "seed",
+                matches.value_of("seed").unwrap_or("1"),
+                |s| s.parse::<u64>().ok(),
+            )?;
+            let record = variant.golden(seed, num_players, 5, &opening, &config)?;
+            golden::check_golden(golden_path, &record, matches.is_present("bless"))?;
+            info!("The game played as {} says", golden_path);
+            Ok(())
         }
-        None => (),
+        task => Err(ScrabrudoError::InvalidOption(format!(
+            "Invalid simulation: {}",
+            task
+        ))),
+    }
+}
+
+/// Plays a short seeded all-AI game, saying why each move was made.
+fn demo_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    let mut config = game_config(matches)?;
+    let (num_players, opening) = init_ai(matches, &mut config)?;
+    lang::init_lang(parse_option(
+        "language",
+        matches.value_of("lang").unwrap_or("en"),
+        Lang::from_name,
+    )?);
+    let variant = variant::variant(matches.value_of("variant").unwrap_or("perudo"))?;
+    variant.init(matches, &config, None)?;
+    let seed = parse_option("seed", matches.value_of("seed").unwrap_or("1"), |s| {
+        s.parse::<u64>().ok()
+    })?;
+    let items_per_player = parse_option(
+        "number of items",
+        matches.value_of("items").unwrap_or("3"),
+        |n| n.parse::<usize>().ok().filter(|n| *n > 0),
+    )?;
+    variant.demo(seed, num_players, items_per_player, &opening, &config)?;
+    Ok(())
+}
+
+/// Reports on recorded turns, or exports the odds of bets as a heatmap.
+fn analyze_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    match required_option(matches, "TASK")? {
+        "report" => analysis::report(required_option(matches, "analysis_in")?),
+        "heatmap" => {
+            init_scrabrudo(matches, &WordStyle::default())?;
+            let rack = parse_tiles(required_option(matches, "hand")?)?;
+            let table_size = parse_option(
+                "table size",
+                required_option(matches, "table_size")?,
+                |n| n.parse::<usize>().ok(),
+            )?;
+            heatmap::export_heatmap(
+                required_option(matches, "heatmap_out")?,
+                &rack,
+                table_size,
+                matches.is_present("exact"),
+            )
+        }
+        task => Err(ScrabrudoError::InvalidOption(format!(
+            "Invalid analysis: {}",
+            task
+        ))),
+    }
+}
+
+/// Plays many games at once, reporting who won each, or serves lobbies for people to play games
+/// in over the network.
+fn serve_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    let mut config = game_config(matches)?;
+    let (num_players, opening) = init_ai(matches, &mut config)?;
+    let human_indices = human_indices(matches)?;
+    init_player_logs(matches, &mut config)?;
+    init_pacing(matches)?;
+    init_records(matches)?;
+    if let Some(addr) = matches.value_of("metrics_addr") {
+        metrics::serve_metrics(addr)?;
+    }
+    init_dashboard(matches)?;
+    let num_games = parse_option(
+        "number of games",
+        matches.value_of("games").unwrap_or("1"),
+        |n| n.parse::<usize>().ok(),
+    )?;
+    let num_threads = parse_option(
+        "number of threads",
+        matches.value_of("threads").unwrap_or("4"),
+        |n| n.parse::<usize>().ok(),
+    )?;
+    let variant = variant::variant(matches.value_of("variant").unwrap_or("scrabrudo"))?;
+    variant.init(matches, &config, None)?;
+    if let Some(addr) = matches.value_of("lobby_addr") {
+        return variant.host(addr, config, hosting(matches, opening)?);
+    }
+    let serving = variant::Serving {
+        num_games: num_games,
+        num_threads: num_threads,
+        records_dir: matches.value_of("game_records").map(String::from),
     };
+    variant.serve(num_players, human_indices, opening, config, serving)
+}
+
+/// Goes back over games in notation, with what the AI makes of each challenge.
+fn replay_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    let mut config = game_config(matches)?;
+    init_ai(matches, &mut config)?;
+    let path = required_option(matches, "NOTATION_IN")?;
+    let explain = matches.is_present("explain");
+    let variant = variant::variant(matches.value_of("variant").unwrap_or("scrabrudo"))?;
+    variant.init(matches, &config, None)?;
+    variant.replay(path, &config, explain)
+}
+
+/// Plays recorded games again, reporting any that no longer end as they did.
+fn verify_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    // Each game is played again with the seats it recorded, so only defaults apply here.
+    init_ai(matches, &mut GameConfig::default())?;
+    let variant = variant::variant(matches.value_of("variant").unwrap_or("scrabrudo"))?;
+    variant.init(matches, &GameConfig::default(), None)?;
+    let paths = matches.values_of("RECORD").unwrap().collect::<Vec<&str>>();
+    let mut num_failed = 0;
+    for path in &paths {
+        match variant.verify(path) {
+            Ok(()) => info!("{}: verified", path),
+            Err(e) => {
+                warn!("{}: {}", path, e);
+                num_failed += 1;
+            }
+        }
+    }
+    if num_failed > 0 {
+        return Err(ScrabrudoError::Drift(format!(
+            "{} of {} games didn't play out as recorded",
+            num_failed,
+            paths.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Reports the top players or how each strategy has fared over time on a leaderboard.
+fn stats_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    let num_players = parse_option(
+        "number of players",
+        matches.value_of("top").unwrap_or("10"),
+        |n| n.parse::<usize>().ok(),
+    )?;
+    let period = parse_option(
+        "period",
+        matches.value_of("period").unwrap_or("week"),
+        leaderboard::Period::from_name,
+    )?;
+    for standing in leaderboard::stats(
+        required_option(matches, "leaderboard")?,
+        required_option(matches, "TASK")?,
+        num_players,
+        period,
+    )? {
+        info!("{}", standing);
+    }
+    Ok(())
+}
+
+/// Reports on a dictionary: its words by length and letter, and the keys a lookup would hold.
+fn dict_command(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    if let Some(tile_set_path) = matches.value_of("tile_set") {
+        tile::init_tile_set(tile::load_tile_set(tile_set_path)?);
+    }
+    let dict_profile = parse_option(
+        "dictionary profile",
+        matches.value_of("dict_profile").unwrap_or("full"),
+        dict::DictProfile::from_name,
+    )?;
+    if dict_profile.needs_frequencies() {
+        dict::init_frequencies(
+            matches
+                .value_of("frequency_path")
+                .unwrap_or("data/google-10000-english.txt"),
+        )?;
+    }
+    dict::init_dict_profile(dict_profile);
+    dict::init_dict(required_option(matches, "dictionary_path")?)?;
+    let num_tiles = parse_option(
+        "number of tiles",
+        matches.value_of("num_tiles").unwrap_or("5"),
+        |n| n.parse::<usize>().ok(),
+    )?;
+    match required_option(matches, "TASK")? {
+        "stats" => {
+            info!("{}", dict_stats::dict_stats(&dict::dict(), num_tiles));
+            Ok(())
+        }
+        task => Err(ScrabrudoError::InvalidOption(format!(
+            "Invalid dictionary task: {}",
+            task
+        ))),
+    }
+}
+
+/// Sets up how each AI player plays, those given settings of their own in games of the given
+/// config, returning the number of players and the opening policy.
+fn init_ai(
+    matches: &clap::ArgMatches,
+    config: &mut GameConfig,
+) -> Result<(usize, OpeningPolicy), ScrabrudoError> {
+    let num_players = parse_option(
+        "number of players",
+        matches.value_of("num_players").unwrap_or("2"),
+        |n| n.parse::<usize>().ok(),
+    )?;
+    let opening = parse_option(
+        "opening",
+        matches.value_of("opening").unwrap_or("likeliest"),
+        OpeningPolicy::from_name,
+    )?;
 
-    match mode {
-        "perudo" => {
-            PerudoGame::new(num_players, 5, human_indices).run();
+    if let Some(beliefs) = matches.values_of("belief") {
+        for assignment in beliefs {
+            let (player_id, policy) = parse_option("belief", assignment, belief::parse_belief)?;
+            match player_id {
+                Some(player_id) => config.seat_mut(player_id).belief = Some(policy),
+                None => belief::init_belief(policy),
+            }
         }
-        "scrabrudo" => {
-            let dict_path = matches.value_of("dictionary_path").unwrap();
-            let lookup_path = matches.value_of("lookup_path").unwrap();
-            dict::init_dict(dict_path);
-            dict::init_lookup(lookup_path);
-            ScrabrudoGame::new(num_players, 5, human_indices).run();
+    }
+
+    if let Some(tie_breaks) = matches.values_of("tie_break") {
+        for assignment in tie_breaks {
+            let (player_id, policy) =
+                parse_option("tie-break", assignment, tie_break::parse_tie_break)?;
+            match player_id {
+                Some(player_id) => config.seat_mut(player_id).tie_break = Some(policy),
+                None => tie_break::init_tie_break(policy),
+            }
+        }
+    }
+
+    if let Some(budgets) = matches.values_of("mcts") {
+        for assignment in budgets {
+            let (player_id, budget) = parse_option("mcts", assignment, mcts::parse_mcts)?;
+            match player_id {
+                Some(player_id) => config.seat_mut(player_id).mcts = Some(Some(budget)),
+                None => mcts::init_mcts(budget),
+            }
+        }
+    }
+
+    if let Some(limits) = matches.values_of("blitz") {
+        for assignment in limits {
+            let (player_id, millis) = parse_option("blitz", assignment, blitz::parse_blitz)?;
+            match player_id {
+                Some(player_id) => config.seat_mut(player_id).blitz = Some(millis),
+                None => blitz::init_blitz(millis),
+            }
+        }
+    }
+
+    if let Some(levels) = matches.values_of("caution") {
+        for assignment in levels {
+            let (player_id, level) = parse_option("caution", assignment, caution::parse_caution)?;
+            match player_id {
+                Some(player_id) => config.seat_mut(player_id).caution = Some(level),
+                None => caution::init_caution(level),
+            }
+        }
+    }
+
+    if let Some(policies) = matches.values_of("learned") {
+        for assignment in policies {
+            let (player_id, path) = parse_option("learned", assignment, learned::parse_learned)?;
+            match learned::LearnedPolicy::load(&path) {
+                Ok(policy) => config.seat_mut(player_id).learned = Some(policy),
+                Err(e) => warn!(
+                    "No model for player {} ({}), playing greedily",
+                    player_id, e
+                ),
+            }
         }
-        _ => panic!("Invalid mode: {}", mode),
+    }
+    Ok((num_players, opening))
+}
+
+/// The seats humans take.
+fn human_indices(matches: &clap::ArgMatches) -> Result<HashSet<usize>, ScrabrudoError> {
+    let mut human_indices: HashSet<usize> = hashset! {};
+    if let Some(x) = matches.value_of("human_index") {
+        human_indices.insert(parse_option("human index", x, |x| x.parse::<usize>().ok())?);
+    }
+    Ok(human_indices)
+}
+
+/// The house rules given.
+fn game_config(matches: &clap::ArgMatches) -> Result<GameConfig, ScrabrudoError> {
+    Ok(GameConfig {
+        no_repeated_words: matches.is_present("no_repeated_words"),
+        phrases: matches.is_present("phrases"),
+        reveal_on_loss: matches.is_present("reveal_on_loss"),
+        community: match matches.value_of("community") {
+            Some(k) => parse_option("number of community items", k, |k| k.parse::<usize>().ok())?,
+            None => 0,
+        },
+        constraints: matches.is_present("constraints"),
+        exchanges: matches.is_present("exchanges"),
+        trades: matches.is_present("trades"),
+        min_raise: parse_option(
+            "minimum raise",
+            matches.value_of("min_raise").unwrap_or("any"),
+            MinRaise::from_name,
+        )?,
+        first_player: parse_option(
+            "first player",
+            matches.value_of("first_player").unwrap_or("first"),
+            FirstPlayer::from_name,
+        )?,
+        round_starter: parse_option(
+            "round starter",
+            matches.value_of("round_starter").unwrap_or("loser"),
+            RoundStarter::from_name,
+        )?,
+        forfeit: parse_option(
+            "forfeit",
+            matches.value_of("forfeit").unwrap_or("ai"),
+            Forfeit::from_name,
+        )?,
+        style: parse_option(
+            "style",
+            matches.value_of("style").unwrap_or("scrabbly"),
+            WordStyle::from_name,
+        )?,
+        banned_words: match matches.value_of("banned_words") {
+            Some(path) => load_banned_words(path)?,
+            None => BTreeSet::new(),
+        },
+        timebank: match matches.value_of("timebank") {
+            Some(timebank) => Some(parse_option("timebank", timebank, Timebank::from_name)?),
+            None => None,
+        },
+        max_rounds: match matches.value_of("max_rounds") {
+            Some(k) => Some(parse_option("number of rounds", k, |k| {
+                k.parse::<usize>().ok().filter(|k| *k > 0)
+            })?),
+            None => None,
+        },
+        // The dictionary and lookup are loaded for the whole process, once the mode is known.
+        dict: dict::DictHandle::default(),
+        lookup: dict::LookupHandle::default(),
+        // Seats are set up by those given settings of their own.
+        seats: Seats::default(),
+        input: input::InputHandle::default(),
+    })
+}
+
+/// Sends the hands of players given their own logs there, in games of the given config.
+fn init_player_logs(
+    matches: &clap::ArgMatches,
+    config: &mut GameConfig,
+) -> Result<(), ScrabrudoError> {
+    if let Some(routes) = matches.values_of("player_log") {
+        for route in routes {
+            let (player_id, path) = parse_option("player log", route, logging::parse_route)?;
+            logging::route_player(config, player_id, &path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Paces games for those following them, if asked.
+fn init_pacing(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    if let Some(pace) = matches.value_of("pace") {
+        let (turn, deal) = parse_option("pace", pace, pacing::parse_pace)?;
+        pacing::register(pacing::delays(turn, deal));
+    }
+    Ok(())
+}
+
+/// Serves the dashboard if asked, holding back and batching its events as given.
+fn init_dashboard(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    let addr = match matches.value_of("dashboard_addr") {
+        Some(addr) => addr,
+        None => return Ok(()),
+    };
+    let reveal_delay = match matches.value_of("reveal_delay") {
+        Some(secs) => parse_option("reveal delay", secs, |s| s.parse::<u64>().ok())?,
+        None => 0,
+    };
+    let event_batch = match matches.value_of("event_batch") {
+        Some(millis) => parse_option("event batch", millis, |m| m.parse::<u64>().ok())?,
+        None => 0,
+    };
+    dashboard::serve_dashboard(
+        addr,
+        dashboard::Schedule::new(
+            Duration::from_secs(reveal_delay),
+            Duration::from_millis(event_batch),
+        ),
+    )?;
+    Ok(())
+}
+
+/// How lobbies' games are played over the network: opening as given, recorded wherever asked, and
+/// waiting the given grace for anyone whose connection drops, with the AI standing in meanwhile
+/// unless asked to pause, and taking admin commands with the admin token, if one is given.
+fn hosting(
+    matches: &clap::ArgMatches,
+    opening: OpeningPolicy,
+) -> Result<net::Hosting, ScrabrudoError> {
+    let grace = parse_option("grace", matches.value_of("grace").unwrap_or("60"), |s| {
+        s.parse::<u64>().ok()
+    })?;
+    let stand_in = parse_option(
+        "stand-in",
+        matches.value_of("on_disconnect").unwrap_or("ai"),
+        net::parse_stand_in,
+    )?;
+    Ok(net::Hosting {
+        opening: opening,
+        records_dir: matches.value_of("game_records").map(String::from),
+        grace: Duration::from_secs(grace),
+        stand_in: stand_in,
+        admin_token: matches.value_of("admin_token").map(String::from),
+    })
+}
+
+/// Starts recording the games played wherever asked.
+fn init_records(matches: &clap::ArgMatches) -> Result<(), ScrabrudoError> {
+    if let Some(analysis_path) = matches.value_of("analysis_out") {
+        analysis::init_analysis(analysis_path)?;
+    }
+    if let Some(notation_path) = matches.value_of("notation_out") {
+        notation::init_notation(notation_path)?;
+    }
+    if let Some(csv_path) = matches.value_of("turns_csv") {
+        analysis::init_turns_csv(csv_path)?;
+    }
+    if let Some(explain_path) = matches.value_of("explain_out") {
+        explain::init_explain_out(explain_path)?;
+    }
+    if let Some(leaderboard_path) = matches.value_of("leaderboard") {
+        leaderboard::init_leaderboard(leaderboard_path)?;
+    }
+    if let Some(names) = matches.values_of("name") {
+        for assignment in names {
+            let (player_id, name) = parse_option("name", assignment, leaderboard::parse_name)?;
+            leaderboard::init_name(player_id, &name);
+        }
+    }
+    Ok(())
+}
+
+/// Loads the tile set, dictionary and lookup, and word frequencies for the given style or
+/// dictionary profile, that Scrabrudo is played with.
+fn init_scrabrudo(matches: &clap::ArgMatches, style: &WordStyle) -> Result<(), ScrabrudoError> {
+    let config = GameConfig {
+        style: style.clone(),
+        ..GameConfig::default()
     };
+    variant::init_scrabrudo(matches, &config, None)
 }
@@ -0,0 +1,498 @@
+/// Monte Carlo Tree Search: choosing an outcome by playing the round out many times over, with
+/// the hands we can't see dealt afresh each time.
+use crate::belief::*;
+use crate::bet::*;
+use crate::blitz::*;
+use crate::config::*;
+use crate::game::*;
+use crate::hand::*;
+use crate::player::*;
+use crate::prob::*;
+use crate::sampler::*;
+use crate::tie_break::*;
+use crate::transposition::*;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref DEFAULT_BUDGET: Mutex<Option<Budget>> = Mutex::new(None);
+}
+
+/// How many of its likeliest bets the searching player considers making.
+pub const ROOT_WIDTH: usize = 8;
+
+/// How many of the bets above the last the cheap policy chooses between.
+pub const ROLLOUT_WIDTH: usize = 4;
+
+/// How many turns a rollout may take before someone is made to challenge.
+pub const MAX_ROLLOUT_TURNS: usize = 32;
+
+/// How much the search favours trying outcomes it knows little about.
+pub const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// How much searching a player may do each turn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Budget {
+    /// Play the round out this many times.
+    Iterations(usize),
+
+    /// Play the round out for this many milliseconds.
+    Millis(u64),
+}
+
+impl Budget {
+    /// Parses a budget from its command-line form, e.g. "iterations:200" or "millis:500".
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut split = name.split(':');
+        match (split.next(), split.next().map(|k| k.parse::<u64>())) {
+            (Some("iterations"), Some(Ok(k))) => Some(Budget::Iterations(k as usize)),
+            (Some("millis"), Some(Ok(k))) => Some(Budget::Millis(k)),
+            _ => None,
+        }
+    }
+
+    /// Whether the budget is spent, having searched so many times since the given time.
+    fn spent(&self, iterations: usize, start: Instant) -> bool {
+        match self {
+            Budget::Iterations(k) => iterations >= *k,
+            Budget::Millis(k) => start.elapsed() >= Duration::from_millis(*k),
+        }
+    }
+}
+
+/// Has every AI player without a budget of their own in their game's config search within the
+/// given budget each turn.
+pub fn init_mcts(budget: Budget) {
+    *DEFAULT_BUDGET.lock().unwrap() = Some(budget);
+}
+
+/// The budget the given player searches within in a game of the given config, if they play by
+/// search at all.
+pub fn budget(config: &GameConfig, player_id: usize) -> Option<Budget> {
+    match config.seat(player_id).and_then(|seat| seat.mcts.clone()) {
+        Some(budget) => budget,
+        None => DEFAULT_BUDGET.lock().unwrap().clone(),
+    }
+}
+
+/// Parses a budget for one player or for all from its command-line form, e.g.
+/// "1:iterations:200" or "millis:500".
+pub fn parse_mcts(assignment: &str) -> Option<(Option<usize>, Budget)> {
+    let mut split = assignment.splitn(2, ':');
+    match (split.next().map(|id| id.parse::<usize>()), split.next()) {
+        (Some(Ok(player_id)), Some(name)) => Some((Some(player_id), Budget::from_name(name)?)),
+        _ => Some((None, Budget::from_name(assignment)?)),
+    }
+}
+
+/// A point in the round: what everyone knows, without the hands.
+struct Position<B: Bet> {
+    state: GameState<B>,
+    outcome: TurnOutcome<B>,
+}
+
+impl<B: Bet> Position<B> {
+    /// The position once the player to move makes the given bet.
+    fn with_bet(&self, bet: B) -> Self {
+        let mut history = self.state.history.clone();
+        history
+            .entry(self.state.player_ids[self.state.current_index])
+            .or_default()
+            .push(bet.clone());
+        Self {
+            state: GameState {
+                total_num_items: self.state.total_num_items,
+                num_items_per_player: self.state.num_items_per_player.clone(),
+                current_index: (self.state.current_index + 1)
+                    % self.state.num_items_per_player.len(),
+                player_ids: self.state.player_ids.clone(),
+                history: history,
+                config: self.state.config.clone(),
+                revealed: self.state.revealed.clone(),
+                community: self.state.community.clone(),
+                constraint: self.state.constraint.clone(),
+                hands: self.state.hands.clone(),
+                clocks: hashmap! {},
+                honesty: self.state.honesty.clone(),
+            },
+            outcome: TurnOutcome::Bet(bet),
+        }
+    }
+}
+
+/// How an outcome has fared from a position.
+#[derive(Debug, Clone, Default)]
+struct Stats {
+    /// The number of times it was chosen.
+    visits: usize,
+
+    /// The total reward to the player who chose it.
+    reward: f64,
+
+    /// The number of times it could have been chosen. The outcomes open to a player depend on
+    /// the hand they were dealt, so this stands in for the visits to the position.
+    available: usize,
+}
+
+/// The outcomes tried from a position, keyed by what everyone knows of it.
+struct Node<B: Bet> {
+    stats: HashMap<TurnOutcome<B>, Stats>,
+}
+
+impl<B: Bet> Default for Node<B> {
+    fn default() -> Self {
+        Self {
+            stats: HashMap::new(),
+        }
+    }
+}
+
+impl<B: Bet> Node<B> {
+    /// Picks one of the given outcomes to try by UCB1, returning it and whether it's new here.
+    fn select(&mut self, candidates: &[TurnOutcome<B>]) -> (TurnOutcome<B>, bool) {
+        for candidate in candidates {
+            self.stats.entry(candidate.clone()).or_default().available += 1;
+        }
+        if let Some(untried) = candidates.iter().find(|c| self.stats[*c].visits == 0) {
+            return (untried.clone(), true);
+        }
+        let ucb = |outcome: &TurnOutcome<B>| {
+            let stats = &self.stats[outcome];
+            stats.reward / stats.visits as f64
+                + EXPLORATION * ((stats.available as f64).ln() / stats.visits as f64).sqrt()
+        };
+        let best = candidates
+            .iter()
+            .max_by(|a, b| ucb(a).partial_cmp(&ucb(b)).unwrap_or(Ordering::Equal))
+            .unwrap();
+        (best.clone(), false)
+    }
+}
+
+/// A search from the point of view of the player to move.
+struct Search<B: Bet> {
+    /// The player searching.
+    player: Box<dyn Player<V = B::V, B = B>>,
+
+    /// The outcomes open to the player searching.
+    root_candidates: Vec<TurnOutcome<B>>,
+
+    /// The tree so far, keyed by the canonical hash of each position.
+    nodes: HashMap<u64, Node<B>>,
+
+    /// What the cheap policy makes of each position and hand: the chance the standing bet is
+    /// wrong, and the bet it would raise to.
+    policy: TranspositionTable<(f64, Option<B>)>,
+}
+
+impl<B: Bet> Search<B> {
+    /// The player with the given ID holding the given hand, playing as the searching player does.
+    fn seat(&self, player_id: usize, hand: &[B::V]) -> Box<dyn Player<V = B::V, B = B>> {
+        self.player.copy_with(
+            Some(player_id),
+            Some(false),
+            Some(Hand::<B::V> {
+                items: hand.to_vec(),
+                revealed: 0,
+                exchanged: false,
+            }),
+        )
+    }
+
+    /// Deals everyone but the player searching a hand they might hold, as the searching player's
+    /// belief policy sees it.
+    fn deal(&self, root: &Position<B>) -> Vec<Vec<B::V>> {
+        sample_hands(
+            &root.state,
+            root.state.current_index,
+            self.player.items(),
+            Some(&belief(&root.state.config, self.player.id())),
+        )
+    }
+
+    /// What the cheap policy makes of the position for the player to move.
+    fn evaluate(&mut self, position: &Position<B>, hands: &[Vec<B::V>]) -> (f64, Option<B>) {
+        let index = position.state.current_index;
+        let hash = position.state.canonical_hash(&hands[index]);
+        let player = self.seat(position.state.player_ids[index], &hands[index]);
+        let state = &position.state;
+        let outcome = &position.outcome;
+        self.policy.get_or_insert_with(hash, || match outcome {
+            TurnOutcome::Bet(bet) => {
+                let perudo_p = bet.prob(state, ProbVariant::Perudo, player.cloned());
+                let bets = bet
+                    .all_above(state)
+                    .take(ROLLOUT_WIDTH)
+                    .map(|b| {
+                        let p = b.prob(state, ProbVariant::Bet, player.cloned());
+                        (b, p)
+                    })
+                    .collect::<Vec<(Box<B>, f64)>>();
+                let best =
+                    B::best_bet_from(rank_outcomes(bets), &tie_break(&state.config, player.id()));
+                (perudo_p, best.map(|b| *b))
+            }
+            _ => (0.0, None),
+        })
+    }
+
+    /// The outcomes open to a player below the root: either challenge, or the cheap policy's bet.
+    fn candidates(&mut self, position: &Position<B>, hands: &[Vec<B::V>]) -> Vec<TurnOutcome<B>> {
+        let mut candidates = vec![TurnOutcome::Perudo, TurnOutcome::Palafico];
+        if let (_, Some(bet)) = self.evaluate(position, hands) {
+            candidates.push(TurnOutcome::Bet(bet));
+        }
+        candidates
+    }
+
+    /// The cheap policy: challenge if the standing bet is more likely wrong than right, otherwise
+    /// raise to the likeliest of the next few bets.
+    fn rollout_outcome(&mut self, position: &Position<B>, hands: &[Vec<B::V>]) -> TurnOutcome<B> {
+        match self.evaluate(position, hands) {
+            (perudo_p, Some(bet)) if perudo_p <= 0.5 => TurnOutcome::Bet(bet),
+            _ => TurnOutcome::Perudo,
+        }
+    }
+
+    /// The reward to each seat once the player to move makes the given challenge: nothing for
+    /// whoever loses an item, and one for everyone else.
+    fn rewards(
+        &self,
+        position: &Position<B>,
+        challenge: &TurnOutcome<B>,
+        hands: &[Vec<B::V>],
+    ) -> Vec<f64> {
+        let num_players = hands.len();
+        let challenger = position.state.current_index;
+        let bettor = (challenger + num_players - 1) % num_players;
+        let mut all_items = hands.concat();
+        all_items.extend(position.state.community.iter().cloned());
+        let loser = match (&position.outcome, challenge) {
+            (TurnOutcome::Bet(bet), TurnOutcome::Perudo) if bet.is_correct(&all_items, false) => {
+                Some(challenger)
+            }
+            (TurnOutcome::Bet(_), TurnOutcome::Perudo) => Some(bettor),
+            (TurnOutcome::Bet(bet), TurnOutcome::Palafico) if bet.is_correct(&all_items, true) => {
+                None
+            }
+            _ => Some(challenger),
+        };
+        (0..num_players)
+            .map(|i| if Some(i) == loser { 0.0 } else { 1.0 })
+            .collect()
+    }
+
+    /// Deals the hidden hands, follows the tree down to a new outcome, plays the round out from
+    /// there and passes the rewards back up.
+    fn iterate(&mut self, root: &Position<B>) {
+        let hands = self.deal(root);
+        let mut position = Position {
+            state: root.state.clone(),
+            outcome: root.outcome.clone(),
+        };
+        let mut path = vec![];
+        let mut in_tree = true;
+        let mut turns = 0;
+        let rewards = loop {
+            let index = position.state.current_index;
+            let outcome = if in_tree {
+                let key = position.state.canonical_hash(&[]);
+                let candidates = if path.is_empty() {
+                    self.root_candidates.clone()
+                } else {
+                    self.candidates(&position, &hands)
+                };
+                let (outcome, untried) = self.nodes.entry(key).or_default().select(&candidates);
+                path.push((key, outcome.clone(), index));
+                in_tree = !untried;
+                outcome
+            } else if turns >= MAX_ROLLOUT_TURNS {
+                TurnOutcome::Perudo
+            } else {
+                self.rollout_outcome(&position, &hands)
+            };
+            turns += 1;
+            match outcome {
+                TurnOutcome::Bet(bet) => position = position.with_bet(bet),
+                challenge => break self.rewards(&position, &challenge, &hands),
+            }
+        };
+        for (key, outcome, index) in path {
+            if let Some(stats) = self
+                .nodes
+                .get_mut(&key)
+                .and_then(|node| node.stats.get_mut(&outcome))
+            {
+                stats.visits += 1;
+                stats.reward += rewards[index];
+            }
+        }
+    }
+}
+
+/// A search from the root within a budget, which can be stopped early by a deadline.
+struct RootSearch<B: Bet> {
+    search: Search<B>,
+    root: Position<B>,
+    budget: Budget,
+    start: Instant,
+    iterations: usize,
+}
+
+impl<B: Bet> AnytimeSearch for RootSearch<B> {
+    type Answer = Option<TurnOutcome<B>>;
+
+    fn step(&mut self) -> bool {
+        if self.budget.spent(self.iterations, self.start) {
+            return false;
+        }
+        self.search.iterate(&self.root);
+        self.iterations += 1;
+        true
+    }
+
+    /// The outcome tried most so far, if any has been tried.
+    fn answer(self) -> Self::Answer {
+        let search = self.search;
+        let root_node = search.nodes.get(&self.root.state.canonical_hash(&[]))?;
+        let (best, stats) = search
+            .root_candidates
+            .iter()
+            .filter_map(|c| root_node.stats.get(c).map(|s| (c, s)))
+            .max_by_key(|(_, s)| s.visits)?;
+        info!(
+            "Player {} searched {} rollouts and chose {:?}, won {:.3} of {}, cache hit rate {:.3}",
+            search.player.id(),
+            self.iterations,
+            best,
+            stats.reward / stats.visits as f64,
+            stats.visits,
+            search.policy.hit_rate()
+        );
+        Some(best.clone())
+    }
+}
+
+/// Chooses an outcome for the given player by searching within the budget, or until the
+/// deadline if that comes first, or None if there is nothing to choose between.
+pub fn search<B: Bet>(
+    state: &GameState<B>,
+    player: Box<dyn Player<V = B::V, B = B>>,
+    current_outcome: &TurnOutcome<B>,
+    budget: &Budget,
+    deadline: &Deadline,
+) -> Option<TurnOutcome<B>> {
+    let mut root_candidates = vec![];
+    let ranked = match current_outcome {
+        TurnOutcome::Bet(bet) => {
+            if may_challenge(state, player.hand(), bet) {
+                root_candidates.push(TurnOutcome::Perudo);
+                root_candidates.push(TurnOutcome::Palafico);
+            }
+            let ranking = Ranking::new(bet.all_above(state), |b| {
+                b.prob(state, ProbVariant::Bet, player.cloned())
+            });
+            search_until(ranking, deadline)
+        }
+        _ => B::ranked_bets_within(state, player.cloned(), deadline),
+    };
+    root_candidates.extend(
+        ranked
+            .into_iter()
+            .rev()
+            .take(ROOT_WIDTH)
+            .map(|(b, _)| TurnOutcome::Bet(*b)),
+    );
+    if root_candidates.is_empty() {
+        return None;
+    }
+
+    let root = Position {
+        state: state.clone(),
+        outcome: current_outcome.clone(),
+    };
+    let search = RootSearch {
+        search: Search {
+            player: player,
+            root_candidates: root_candidates,
+            nodes: HashMap::new(),
+            policy: TranspositionTable::default(),
+        },
+        root: root,
+        budget: budget.clone(),
+        start: Instant::now(),
+        iterations: 0,
+    };
+    search_until(search, deadline)
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::*;
+    use crate::opening::*;
+    use crate::testing::*;
+
+    describe "budgets" {
+        it "parses budgets" {
+            assert_eq!(Some(Budget::Iterations(200)), Budget::from_name("iterations:200"));
+            assert_eq!(Some(Budget::Millis(500)), Budget::from_name("millis:500"));
+            assert_eq!(None, Budget::from_name("iterations"));
+            assert_eq!(Some((Some(1), Budget::Millis(50))), parse_mcts("1:millis:50"));
+            assert_eq!(Some((None, Budget::Iterations(10))), parse_mcts("iterations:10"));
+            assert_eq!(None, parse_mcts("1:forever"));
+        }
+    }
+
+    describe "search" {
+        fn player(items: Vec<Die>) -> Box<dyn Player<V = Die, B = PerudoBet>> {
+            Box::new(PerudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Die> { items: items, revealed: 0, exchanged: false },
+            })
+        }
+
+        it "opens with a bet" {
+            let outcome = search(
+                &game_state(vec![2, 2]),
+                player(vec![Die::Three, Die::Three]),
+                &TurnOutcome::First,
+                &Budget::Iterations(50),
+                &Deadline::none());
+            match outcome {
+                Some(TurnOutcome::Bet(_)) => (),
+                outcome => panic!("Expected a bet, got {:?}", outcome),
+            }
+        }
+
+        it "calls an impossible bet" {
+            let mut state = game_state::<PerudoBet>(vec![2, 2]);
+            let bet = PerudoBet { value: Die::Two, quantity: 4 };
+            state.history = hashmap!{ 1 => vec![bet.clone()] };
+            let outcome = search(
+                &state,
+                player(vec![Die::Five, Die::Six]),
+                &TurnOutcome::Bet(bet),
+                &Budget::Iterations(50),
+                &Deadline::none());
+            assert_eq!(Some(TurnOutcome::Perudo), outcome);
+        }
+
+        it "answers by the deadline whatever the budget" {
+            let outcome = search(
+                &game_state(vec![2, 2]),
+                player(vec![Die::Three, Die::Three]),
+                &TurnOutcome::First,
+                &Budget::Iterations(usize::MAX),
+                &Deadline::after_millis(20));
+            assert!(outcome.is_some());
+        }
+    }
+}
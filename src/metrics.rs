@@ -0,0 +1,324 @@
+/// Metrics: counts of what the engine has been doing, from games in play to how often the lookup
+/// has the answer, served over HTTP in the Prometheus text format for watching a server.
+use crate::error::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    // When the first game began, or metrics were first served, to measure rates from.
+    static ref STARTED: Instant = Instant::now();
+
+    // The hits and misses of each probability store read, by name.
+    static ref STORE_READS: Mutex<BTreeMap<String, (u64, u64)>> = Mutex::new(BTreeMap::new());
+}
+
+static GAMES_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+static GAMES_FINISHED: AtomicU64 = AtomicU64::new(0);
+static TURNS: AtomicU64 = AtomicU64::new(0);
+static AI_TURNS: AtomicU64 = AtomicU64::new(0);
+static AI_TURN_MICROS: AtomicU64 = AtomicU64::new(0);
+static LOOKUP_HITS: AtomicU64 = AtomicU64::new(0);
+static LOOKUP_MISSES: AtomicU64 = AtomicU64::new(0);
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+static CHALLENGES: AtomicU64 = AtomicU64::new(0);
+static CHALLENGES_RIGHT: AtomicU64 = AtomicU64::new(0);
+
+/// A game being played, counted as active until it is dropped, however it ends.
+pub struct ActiveGame;
+
+impl ActiveGame {
+    /// Counts a game as started.
+    pub fn begin() -> Self {
+        lazy_static::initialize(&STARTED);
+        GAMES_ACTIVE.fetch_add(1, Ordering::Relaxed);
+        ActiveGame
+    }
+
+    /// Counts the game as finished, having been won.
+    pub fn finish(self) {
+        GAMES_FINISHED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ActiveGame {
+    fn drop(&mut self) {
+        GAMES_ACTIVE.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a turn taken, and how long the AI took over it if it wasn't a human's.
+pub fn record_turn(human: bool, elapsed: Duration) {
+    TURNS.fetch_add(1, Ordering::Relaxed);
+    if !human {
+        AI_TURNS.fetch_add(1, Ordering::Relaxed);
+        AI_TURN_MICROS.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Counts a challenge, and whether the challenger was right.
+pub fn record_challenge(right: bool) {
+    CHALLENGES.fetch_add(1, Ordering::Relaxed);
+    if right {
+        CHALLENGES_RIGHT.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a read of the lookup, and whether it held the letters asked for.
+pub fn record_lookup(hit: bool) {
+    if hit {
+        LOOKUP_HITS.fetch_add(1, Ordering::Relaxed);
+    } else {
+        LOOKUP_MISSES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a read of the probability cache, and whether it held the letters asked for.
+pub fn record_cache(hit: bool) {
+    if hit {
+        CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+    } else {
+        CACHE_MISSES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a read of the named probability store, and whether it held the letters asked for.
+pub fn record_store(store: &str, hit: bool) {
+    let mut reads = STORE_READS.lock().unwrap();
+    let (hits, misses) = reads.entry(store.to_string()).or_insert((0, 0));
+    if hit {
+        *hits += 1;
+    } else {
+        *misses += 1;
+    }
+}
+
+/// The hits and misses of the named probability store so far.
+pub fn store_reads(store: &str) -> (u64, u64) {
+    STORE_READS
+        .lock()
+        .unwrap()
+        .get(store)
+        .cloned()
+        .unwrap_or((0, 0))
+}
+
+/// The given part of a whole, or 0 if there is no whole.
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Every metric so far, in the Prometheus text format.
+pub fn render() -> String {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    let turns = load(&TURNS);
+    let ai_turns = load(&AI_TURNS);
+    let lookups = load(&LOOKUP_HITS) + load(&LOOKUP_MISSES);
+    let cache_reads = load(&CACHE_HITS) + load(&CACHE_MISSES);
+    let challenges = load(&CHALLENGES);
+    let metrics: Vec<(&str, &str, &str, f64)> = vec![
+        (
+            "games_active",
+            "gauge",
+            "Games being played",
+            GAMES_ACTIVE.load(Ordering::Relaxed) as f64,
+        ),
+        (
+            "games_finished_total",
+            "counter",
+            "Games played to a win",
+            load(&GAMES_FINISHED) as f64,
+        ),
+        ("turns_total", "counter", "Turns taken", turns as f64),
+        (
+            "turns_per_second",
+            "gauge",
+            "Turns taken a second since starting",
+            turns as f64 / STARTED.elapsed().as_secs_f64().max(1e-3),
+        ),
+        (
+            "ai_turns_total",
+            "counter",
+            "Turns the AI took",
+            ai_turns as f64,
+        ),
+        (
+            "ai_turn_seconds_total",
+            "counter",
+            "Time the AI spent over its turns",
+            load(&AI_TURN_MICROS) as f64 / 1e6,
+        ),
+        (
+            "ai_turn_seconds_average",
+            "gauge",
+            "Average time the AI spent over a turn",
+            ratio(load(&AI_TURN_MICROS), ai_turns) / 1e6,
+        ),
+        (
+            "lookup_hits_total",
+            "counter",
+            "Lookup reads finding the letters",
+            load(&LOOKUP_HITS) as f64,
+        ),
+        (
+            "lookup_misses_total",
+            "counter",
+            "Lookup reads missing the letters",
+            load(&LOOKUP_MISSES) as f64,
+        ),
+        (
+            "lookup_hit_rate",
+            "gauge",
+            "Fraction of lookup reads finding the letters",
+            ratio(load(&LOOKUP_HITS), lookups),
+        ),
+        (
+            "prob_cache_hits_total",
+            "counter",
+            "Probability cache reads finding the letters",
+            load(&CACHE_HITS) as f64,
+        ),
+        (
+            "prob_cache_misses_total",
+            "counter",
+            "Probability cache reads left to estimate",
+            load(&CACHE_MISSES) as f64,
+        ),
+        (
+            "prob_cache_hit_rate",
+            "gauge",
+            "Fraction of probability cache reads finding the letters",
+            ratio(load(&CACHE_HITS), cache_reads),
+        ),
+        (
+            "challenges_total",
+            "counter",
+            "Challenges called",
+            challenges as f64,
+        ),
+        (
+            "challenges_right_total",
+            "counter",
+            "Challenges that were right",
+            load(&CHALLENGES_RIGHT) as f64,
+        ),
+        (
+            "challenge_accuracy",
+            "gauge",
+            "Fraction of challenges that were right",
+            ratio(load(&CHALLENGES_RIGHT), challenges),
+        ),
+    ];
+    let mut text = metrics
+        .into_iter()
+        .map(|(name, kind, help, value)| {
+            format!(
+                "# HELP scrabrudo_{name} {help}\n# TYPE scrabrudo_{name} {kind}\nscrabrudo_{name} {value}\n",
+                name = name,
+                help = help,
+                kind = kind,
+                value = value
+            )
+        })
+        .collect::<String>();
+    let store_reads = STORE_READS.lock().unwrap();
+    if !store_reads.is_empty() {
+        text.push_str(
+            "# HELP scrabrudo_prob_store_reads_total Probability store reads, by whether the store held the letters\n\
+             # TYPE scrabrudo_prob_store_reads_total counter\n",
+        );
+        for (store, (hits, misses)) in store_reads.iter() {
+            for (result, reads) in &[("hit", hits), ("miss", misses)] {
+                text.push_str(&format!(
+                    "scrabrudo_prob_store_reads_total{{store=\"{}\",result=\"{}\"}} {}\n",
+                    store, result, reads
+                ));
+            }
+        }
+    }
+    text
+}
+
+/// Serves the metrics at /metrics on the given address, e.g. 127.0.0.1:9100, from a thread of
+/// its own, returning the address bound.
+pub fn serve_metrics(addr: &str) -> Result<SocketAddr, ScrabrudoError> {
+    lazy_static::initialize(&STARTED);
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    info!("Serving metrics at http://{}/metrics", bound);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if let Err(e) = stream.and_then(respond) {
+                warn!("Couldn't serve metrics: {}", e);
+            }
+        }
+    });
+    Ok(bound)
+}
+
+/// Answers a request for the metrics, and nothing else.
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", render())
+    } else {
+        ("404 Not Found", "Not found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+speculate! {
+    use std::io::Read;
+
+    describe "metrics" {
+        it "counts challenges and games" {
+            record_challenge(true);
+            let game = ActiveGame::begin();
+            assert!(GAMES_ACTIVE.load(Ordering::Relaxed) >= 1);
+            game.finish();
+            let text = render();
+            assert!(text.contains("# TYPE scrabrudo_challenges_total counter\n"));
+            assert!(CHALLENGES_RIGHT.load(Ordering::Relaxed) >= 1);
+            assert!(GAMES_FINISHED.load(Ordering::Relaxed) >= 1);
+        }
+
+        it "counts reads of each probability store" {
+            record_store("test", true);
+            record_store("test", false);
+            record_store("test", false);
+            assert_eq!((1, 2), store_reads("test"));
+            assert!(render().contains(
+                "scrabrudo_prob_store_reads_total{store=\"test\",result=\"miss\"} 2\n"));
+        }
+
+        it "serves the metrics over HTTP" {
+            let addr = serve_metrics("127.0.0.1:0").unwrap();
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.contains("\nscrabrudo_games_active "));
+        }
+    }
+}
@@ -0,0 +1,320 @@
+/// Monte Carlo estimates of the chance of finding a word among the tiles on the table.
+/// Tiles are counted into fixed arrays rather than dealt into hands, so that each trial is a
+/// single pass over the alphabet.
+use crate::compare::*;
+use crate::rng::*;
+use crate::tile::*;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::RngCore;
+#[cfg(test)]
+use speculate::speculate;
+
+/// The number of kinds of tile, blanks and letter pairs included.
+pub const NUM_TILE_KINDS: usize = 29;
+
+/// The length of a LetterCounts, padded past the kinds of tile to a whole number of u64 words so
+/// that counts can be checked a word at a time.
+pub const LETTER_COUNTS_LEN: usize = 32;
+
+/// How many of each kind of tile there are, indexed by Tile::as_usize. The padding is always
+/// zero. Counts must stay below 128 for the packed checks to hold.
+pub type LetterCounts = [u8; LETTER_COUNTS_LEN];
+
+/// Counts the given tiles.
+pub fn letter_counts(tiles: &[Tile]) -> LetterCounts {
+    let mut counts = [0; LETTER_COUNTS_LEN];
+    for tile in tiles {
+        counts[tile.as_usize()] += 1;
+    }
+    counts
+}
+
+/// Whether the word can be made from the drawn tiles, with blanks standing in for any missing
+/// letters. If 'exact' is true then no more of any of the word's letters may have been drawn
+/// than it has, as for a Palafico.
+/// With the fast-math feature this checks eight letters at a time.
+#[cfg(feature = "fast-math")]
+pub fn is_formable(word: &LetterCounts, drawn: &LetterCounts, exact: bool) -> bool {
+    is_formable_packed(word, drawn, exact)
+}
+
+/// Whether the word can be made from the drawn tiles, with blanks standing in for any missing
+/// letters. If 'exact' is true then no more of any of the word's letters may have been drawn
+/// than it has, as for a Palafico.
+#[cfg(not(feature = "fast-math"))]
+pub fn is_formable(word: &LetterCounts, drawn: &LetterCounts, exact: bool) -> bool {
+    is_formable_scalar(word, drawn, exact)
+}
+
+/// Checks is_formable a letter at a time.
+pub fn is_formable_scalar(word: &LetterCounts, drawn: &LetterCounts, exact: bool) -> bool {
+    let blank = Tile::Blank.as_usize();
+    let mut num_missing = 0;
+    for i in 0..NUM_TILE_KINDS {
+        if i == blank || word[i] == 0 {
+            continue;
+        }
+        if exact && drawn[i] > word[i] {
+            return false;
+        }
+        num_missing += word[i].saturating_sub(drawn[i]) as usize;
+    }
+    num_missing <= drawn[blank] as usize
+}
+
+/// One in the lowest bit of every byte of a u64.
+const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+
+/// One in the highest bit of every byte of a u64.
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Checks is_formable eight letters at a time, treating each u64 of counts as eight byte lanes.
+/// Setting the high bit of every word lane before subtracting the drawn counts stops borrows
+/// crossing lanes, and leaves the high bit set exactly where the word needs at least as many as
+/// were drawn.
+pub fn is_formable_packed(word: &LetterCounts, drawn: &LetterCounts, exact: bool) -> bool {
+    let blank = Tile::Blank.as_usize();
+    let mut num_missing = 0;
+    for (lane, (w, d)) in word.chunks(8).zip(drawn.chunks(8)).enumerate() {
+        let mut w = u64::from_le_bytes([w[0], w[1], w[2], w[3], w[4], w[5], w[6], w[7]]);
+        let d = u64::from_le_bytes([d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]]);
+        if lane == blank / 8 {
+            // Blanks drawn stand in for letters rather than being sought themselves.
+            w &= !(0xff << (8 * (blank % 8)));
+        }
+        let diff = (w | HIGH_BITS) - d;
+        let enough = diff & HIGH_BITS;
+        if exact {
+            let in_word = ((w | HIGH_BITS) - LOW_BITS) & HIGH_BITS;
+            if !enough & in_word != 0 {
+                return false;
+            }
+        }
+        // Keep the shortfall in lanes where the word needs at least as many as were drawn, then
+        // add the lanes up in pairs so that the total can't overflow a byte.
+        let short = diff & !HIGH_BITS & ((enough >> 7) * 0xff);
+        let pairs = (short & 0x00ff_00ff_00ff_00ff) + ((short >> 8) & 0x00ff_00ff_00ff_00ff);
+        num_missing += (pairs.wrapping_mul(0x0001_0001_0001_0001) >> 48) as usize;
+    }
+    num_missing <= drawn[blank] as usize
+}
+
+/// Draws tiles from the current tile set straight into letter counts.
+pub struct TileSampler {
+    distribution: WeightedIndex<u32>,
+}
+
+impl Default for TileSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TileSampler {
+    /// Creates a sampler for the tile set tiles are currently dealt from.
+    pub fn new() -> Self {
+        Self {
+            // Loading a tile set ensures it has some tiles, so this can't fail.
+            distribution: WeightedIndex::new(tile_set().weights()).unwrap(),
+        }
+    }
+
+    /// Draws n tiles into the given counts, replacing what they held. n must fit in a u8.
+    pub fn draw<R: RngCore>(&self, rng: &mut R, n: usize, counts: &mut LetterCounts) {
+        *counts = [0; LETTER_COUNTS_LEN];
+        for i in self.distribution.sample_iter(rng).take(n) {
+            counts[i] += 1;
+        }
+    }
+}
+
+/// Runs MC simulation to get rough probability of success.
+/// If 'exact' is true then it finds the probability of a Palafico.
+pub fn monte_carlo(n: u32, word: &str, num_trials: u32, exact: bool) -> f64 {
+    if n == 0 {
+        // Cannot find a word in no tiles.
+        return 0.0;
+    }
+
+    let word = letter_counts(&key_tiles(word).unwrap());
+    let sampler = TileSampler::new();
+    let mut rng = rng_for("monte_carlo");
+    let mut drawn = [0; LETTER_COUNTS_LEN];
+    let successes = (0..num_trials)
+        .filter(|_| {
+            sampler.draw(&mut rng, n as usize, &mut drawn);
+            is_formable(&word, &drawn, exact)
+        })
+        .count();
+    successes as f64 / num_trials as f64
+}
+
+/// The chance of finding a word as estimated by Monte Carlo, with the interval it likely lies in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    pub p: f64,
+
+    /// The Wilson interval around p.
+    pub low: f64,
+    pub high: f64,
+
+    pub num_trials: u32,
+}
+
+/// Estimates the chance of finding the word among n tiles as monte_carlo does, along with the
+/// Wilson interval around it spanning z standard deviations.
+pub fn estimate(n: u32, word: &str, num_trials: u32, exact: bool, z: f64) -> Estimate {
+    let p = monte_carlo(n, word, num_trials, exact);
+    let successes = (p * num_trials as f64).round() as usize;
+    let (low, high) = wilson_interval(successes, num_trials as usize, z);
+    Estimate {
+        p: p,
+        low: low,
+        high: high,
+        num_trials: num_trials,
+    }
+}
+
+/// A shared pool of table samples, so that every word is tested against the same draws rather
+/// than drawing afresh for each. Each sample grows one tile at a time, the draw of n + 1 tiles
+/// being the draw of n with one more added, so the chance of finding a word never falls as
+/// tiles are added.
+pub struct Reservoir {
+    /// The samples of each number of tiles, from none up to the most asked for.
+    samples: Vec<Vec<LetterCounts>>,
+}
+
+impl Reservoir {
+    /// Draws the given number of samples for each number of tiles up to max_num_items.
+    pub fn new(max_num_items: usize, num_trials: u32) -> Self {
+        let sampler = TileSampler::new();
+        let mut rng = rng_for("monte_carlo");
+        let mut samples = vec![vec![[0; LETTER_COUNTS_LEN]; num_trials as usize]];
+        for n in 1..=max_num_items {
+            let mut next = samples[n - 1].clone();
+            let mut tile = [0; LETTER_COUNTS_LEN];
+            for counts in next.iter_mut() {
+                sampler.draw(&mut rng, 1, &mut tile);
+                for i in 0..NUM_TILE_KINDS {
+                    counts[i] += tile[i];
+                }
+            }
+            samples.push(next);
+        }
+        Self { samples: samples }
+    }
+
+    /// The most tiles this reservoir has samples of.
+    pub fn max_num_items(&self) -> usize {
+        self.samples.len() - 1
+    }
+
+    /// The share of the samples of n tiles in which the word can be found.
+    /// If 'exact' is true then it finds the probability of a Palafico.
+    pub fn probability(&self, n: usize, word: &str, exact: bool) -> f64 {
+        let samples = &self.samples[n];
+        if n == 0 || samples.is_empty() {
+            return 0.0;
+        }
+        let word = letter_counts(&key_tiles(word).unwrap());
+        let successes = samples
+            .iter()
+            .filter(|drawn| is_formable(&word, drawn, exact))
+            .count();
+        successes as f64 / samples.len() as f64
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::bet::*;
+
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "letter counts" {
+        it "finds words with blanks standing in" {
+            let cat = letter_counts(&[Tile::C, Tile::A, Tile::T]);
+            assert!(is_formable(&cat, &letter_counts(&[Tile::T, Tile::A, Tile::C, Tile::E]), false));
+            assert!(is_formable(&cat, &letter_counts(&[Tile::C, Tile::Blank, Tile::T]), false));
+            assert!(!is_formable(&cat, &letter_counts(&[Tile::C, Tile::T]), false));
+            assert!(is_formable(&cat, &letter_counts(&[Tile::C, Tile::A, Tile::T, Tile::E]), true));
+            assert!(!is_formable(&cat, &letter_counts(&[Tile::C, Tile::A, Tile::T, Tile::T]), true));
+        }
+
+        it "checks packed counts as it checks one letter at a time" {
+            let sampler = TileSampler::new();
+            let mut drawn = [0; LETTER_COUNTS_LEN];
+            for word in &["cat", "attack", "zoo", "a", "razzmatazz"] {
+                let counts = letter_counts(&ScrabrudoBet::from_word(&word.to_string()).tiles);
+                for n in &[0, 3, 8, 30, 120] {
+                    sampler.draw(&mut rng(), *n, &mut drawn);
+                    for exact in &[false, true] {
+                        assert_eq!(
+                            is_formable_scalar(&counts, &drawn, *exact),
+                            is_formable_packed(&counts, &drawn, *exact));
+                    }
+                }
+            }
+        }
+
+        it "draws the number of tiles asked for" {
+            let mut counts = [1; LETTER_COUNTS_LEN];
+            TileSampler::new().draw(&mut rng(), 12, &mut counts);
+            assert_eq!(12, counts.iter().map(|c| *c as usize).sum::<usize>());
+        }
+    }
+
+    describe "monte carlo" {
+        it "approximates the chance of a bet" {
+            let p = monte_carlo(20, "cat", 10000, false);
+
+            // We should definitely find it a bunch of times in 20 die.
+            assert!(p > 0.0);
+        }
+
+        it "approximates the chance of a palafico" {
+            // In a single tile, finding exactly 'a' is the same as finding it at all.
+            let exact = monte_carlo(1, "a", 10000, true);
+            let at_least = monte_carlo(1, "a", 10000, false);
+            assert!((exact - at_least).abs() < 0.05);
+            assert_eq!(0.0, monte_carlo(0, "a", 1000, true));
+        }
+
+        it "bounds its estimates" {
+            let estimate = estimate(20, "attack", 10000, false, 1.96);
+            assert!(estimate.low <= estimate.p && estimate.p <= estimate.high);
+            assert!(estimate.high - estimate.low < 0.05);
+            assert_eq!(10000, estimate.num_trials);
+        }
+    }
+
+    describe "reservoirs" {
+        it "grows each sample a tile at a time" {
+            let reservoir = Reservoir::new(6, 500);
+            assert_eq!(6, reservoir.max_num_items());
+            for n in 0..=6 {
+                for drawn in &reservoir.samples[n] {
+                    assert_eq!(n, drawn.iter().map(|c| *c as usize).sum::<usize>());
+                }
+            }
+            for n in 1..=6 {
+                assert!(reservoir.probability(n, "e", false) >= reservoir.probability(n - 1, "e", false));
+            }
+        }
+
+        it "agrees with fresh draws" {
+            let reservoir = Reservoir::new(8, 10000);
+            assert_eq!(0.0, reservoir.probability(0, "a", false));
+            for word in &["e", "at"] {
+                for exact in &[false, true] {
+                    let shared = reservoir.probability(8, word, *exact);
+                    let fresh = monte_carlo(8, word, 10000, *exact);
+                    assert!((shared - fresh).abs() < 0.05);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/// Multisets: counts of dice, tiles or letters, with the operations for working out how far one
+/// collection of them covers another, as correctness checks, probabilities and belief all need.
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+
+/// A collection of items where only how many there are of each matters. They're kept in order,
+/// so that anything worked out from them comes out the same every time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Multiset<T: Ord> {
+    counts: BTreeMap<T, usize>,
+    len: usize,
+}
+
+impl<T: Ord> Default for Multiset<T> {
+    fn default() -> Self {
+        Self {
+            counts: BTreeMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T: Ord + Clone> Multiset<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The given items, counted.
+    pub fn of(items: &[T]) -> Self {
+        items.iter().cloned().collect()
+    }
+
+    /// Adds one of the given item.
+    pub fn add(&mut self, item: T) {
+        *self.counts.entry(item).or_insert(0) += 1;
+        self.len += 1;
+    }
+
+    /// Takes away one of the given item, returning whether there was one to take.
+    pub fn remove(&mut self, item: &T) -> bool {
+        match self.counts.get_mut(item) {
+            Some(count) => {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(item);
+                }
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How many of the given item there are.
+    pub fn count(&self, item: &T) -> usize {
+        *self.counts.get(item).unwrap_or(&0)
+    }
+
+    /// How many items there are in all.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the other holds at least as many of every item as this does.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.counts.iter().all(|(item, n)| other.count(item) >= *n)
+    }
+
+    /// What's left of this once one of it is taken away for each item of the other, where there
+    /// is one to take.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.counts
+            .iter()
+            .flat_map(|(item, n)| {
+                let left = n.saturating_sub(other.count(item));
+                std::iter::repeat_n(item.clone(), left)
+            })
+            .collect()
+    }
+
+    /// Each item and how many of it there are, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.counts.iter().map(|(item, n)| (item, *n))
+    }
+
+    /// Each different item once, in order.
+    pub fn distinct(&self) -> Vec<T> {
+        self.counts.keys().cloned().collect()
+    }
+
+    /// Every item, as many times as there are of it, in order.
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter()
+            .flat_map(|(item, n)| std::iter::repeat_n(item.clone(), n))
+            .collect()
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for Multiset<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Self {
+        let mut multiset = Self::new();
+        for item in items {
+            multiset.add(item);
+        }
+        multiset
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "multisets" {
+        it "counts items" {
+            let mut letters = "banana".chars().collect::<Multiset<char>>();
+            assert_eq!(6, letters.len());
+            assert_eq!(3, letters.count(&'a'));
+            assert_eq!(0, letters.count(&'z'));
+            assert!(letters.remove(&'b'));
+            assert!(!letters.remove(&'b'));
+            letters.add('z');
+            assert_eq!(vec!['a', 'n', 'z'], letters.distinct());
+            assert_eq!(vec!['a', 'a', 'a', 'n', 'n', 'z'], letters.to_vec());
+        }
+
+        it "takes one from another" {
+            let word = "attack".chars().collect::<Multiset<char>>();
+            let hand = "xxak".chars().collect::<Multiset<char>>();
+            assert_eq!(vec!['a', 'c', 't', 't'], word.difference(&hand).to_vec());
+            assert!(Multiset::of(&['t', 'a']).is_subset(&word));
+            assert!(!Multiset::of(&['k', 'k']).is_subset(&word));
+            assert!(Multiset::<char>::new().is_subset(&hand));
+        }
+    }
+}
@@ -0,0 +1,1149 @@
+/// Games played over the network: people find a game in a lobby, over HTTP at /lobbies or as
+/// messages over a WebSocket at /play, and once it starts play their seats over the WebSocket,
+/// sent what their seat can see whenever it's their move and every turn as it's played. Serving
+/// needs the net feature; without it, asking to is an error, though the protocol itself is the
+/// same either way.
+use crate::config::*;
+use crate::dashboard;
+use crate::error::*;
+use crate::game::*;
+use crate::input::*;
+use crate::lang::*;
+use crate::lobby::*;
+use crate::opening::*;
+use crate::verify::{self, ChatLine};
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+#[cfg(test)]
+use speculate::speculate;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+#[cfg(feature = "net")]
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::SocketAddr;
+#[cfg(feature = "net")]
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "net")]
+use tungstenite::{Message, WebSocket};
+
+/// How long a connection waits for a message before sending on any for it.
+#[cfg(feature = "net")]
+const POLL: Duration = Duration::from_millis(20);
+
+/// The most an HTTP request's body may be.
+#[cfg(feature = "net")]
+const MAX_BODY: usize = 64 * 1024;
+
+/// How often a seat waiting for a move checks whether its player has gone.
+const CHECK: Duration = Duration::from_millis(100);
+
+/// The most moves a seat may send within MOVE_WINDOW, to keep it from flooding the game.
+const MAX_MOVES: usize = 5;
+
+const MOVE_WINDOW: Duration = Duration::from_secs(1);
+
+static NEXT_SESSION: AtomicUsize = AtomicUsize::new(1);
+
+// Every thread hosts at most one game, so that games hosted at once are kept apart.
+thread_local! {
+    static HOSTING: RefCell<Option<Arc<Table>>> = const { RefCell::new(None) };
+}
+
+/// A command played at a table, rather than to find one.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TableCommand {
+    /// The line the player would otherwise type for their move, such as "2 Fours" or "p", and
+    /// an ID for it, if given, so that sending it again doesn't play it twice.
+    Move {
+        line: String,
+        #[serde(default)]
+        id: Option<u64>,
+    },
+
+    /// Takes back the seat of the given token after a dropped connection.
+    Resume { token: String },
+
+    /// Says something to everyone at the table.
+    Chat { text: String },
+
+    /// Stops showing what the player in the given seat says.
+    Mute { seat: usize },
+
+    /// Shows what the player in the given seat says again.
+    Unmute { seat: usize },
+}
+
+/// Anything a player may send over the WebSocket.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Command {
+    Lobby(LobbyCommand),
+    Table(TableCommand),
+}
+
+/// A player's connection, and the seat it plays once it has one.
+#[derive(Debug)]
+pub struct Session {
+    id: usize,
+
+    /// The token of the seat taken, once joined.
+    pub token: Option<String>,
+
+    /// Where messages for the player go.
+    outbox: Sender<String>,
+}
+
+impl Session {
+    /// A new connection, sending its messages to the given outbox.
+    pub fn new(outbox: Sender<String>) -> Self {
+        Session {
+            id: NEXT_SESSION.fetch_add(1, Ordering::Relaxed),
+            token: None,
+            outbox: outbox,
+        }
+    }
+
+    /// Sends the message to the player.
+    fn send(&self, message: &Value) {
+        // A player who has gone is caught leaving by their connection.
+        let _ = self.outbox.send(message.to_string());
+    }
+}
+
+/// A seat at a table taken by someone playing over the network.
+#[derive(Debug, Default)]
+struct Seat {
+    /// The session connected to the seat, if any, and where its messages go.
+    outbox: Option<(usize, Sender<String>)>,
+
+    /// When the seat was last left without a connection, if it is.
+    left: Option<Instant>,
+
+    /// Moves sent that are yet to be played.
+    moves: VecDeque<String>,
+
+    /// Whether it's the seat's move, so that moves sent out of turn are turned away.
+    to_move: bool,
+
+    /// The IDs of the moves sent, so that one sent again is dropped.
+    acted: HashSet<u64>,
+
+    /// When the seat's recent moves were sent, to keep it from flooding the game.
+    sent: VecDeque<Instant>,
+
+    /// The game as the seat last saw it.
+    view: Value,
+
+    /// Whether the seat has been asked for a move since the last turn, so that being asked again
+    /// means the last was refused.
+    asked: bool,
+
+    /// The seats whose chat isn't shown to this one.
+    muted: HashSet<usize>,
+
+    /// When the seat's recent messages were sent, to keep it from flooding the table.
+    said: VecDeque<Instant>,
+}
+
+impl Seat {
+    /// Sends the message to whoever is connected to the seat, if anyone is.
+    fn send(&self, message: &Value) {
+        if let Some((_, outbox)) = &self.outbox {
+            let _ = outbox.send(message.to_string());
+        }
+    }
+
+    /// The message asking for the seat's move.
+    fn your_move(&self, rejected: bool) -> Value {
+        json!({ "type": "your_move", "state": self.view, "rejected": rejected })
+    }
+}
+
+/// What happens to the seat of someone whose connection drops, while they have time to come back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StandIn {
+    /// The AI plays for them.
+    Ai,
+
+    /// The game waits for them.
+    Pause,
+}
+
+/// Reads what happens to the seat of someone whose connection drops: ai or pause.
+pub fn parse_stand_in(stand_in: &str) -> Option<StandIn> {
+    match stand_in {
+        "ai" => Some(StandIn::Ai),
+        "pause" => Some(StandIn::Pause),
+        _ => None,
+    }
+}
+
+/// A lobby's game being played, and the people playing it.
+#[derive(Debug)]
+pub struct Table {
+    pub lobby: Lobby,
+
+    /// The people's seats, in order.
+    seats: Mutex<Vec<Seat>>,
+
+    /// Signalled whenever a move is sent, or someone leaves.
+    moved: Condvar,
+
+    /// How long someone whose connection drops has to come back before forfeiting.
+    grace: Duration,
+
+    stand_in: StandIn,
+
+    /// How many moves have been made.
+    num_moves: AtomicUsize,
+
+    /// What has been said at the table, in order.
+    chat: Mutex<Vec<ChatLine>>,
+}
+
+impl Table {
+    /// A table for the lobby's game, with no one connected yet, each given the grace the server
+    /// gives to come back to it.
+    pub fn new(lobby: Lobby, hosting: &Hosting) -> Arc<Self> {
+        let now = Instant::now();
+        let seats = (0..lobby.num_humans)
+            .map(|_| Seat {
+                left: Some(now),
+                ..Seat::default()
+            })
+            .collect();
+        Arc::new(Table {
+            lobby: lobby,
+            seats: Mutex::new(seats),
+            moved: Condvar::new(),
+            grace: hosting.grace,
+            stand_in: hosting.stand_in,
+            num_moves: AtomicUsize::new(0),
+            chat: Mutex::new(vec![]),
+        })
+    }
+
+    /// Where the game's moves come from: the people's seats, each waiting for its move to be
+    /// sent while its player is there to send it.
+    pub fn input(self: &Arc<Self>) -> InputHandle {
+        let table = Arc::clone(self);
+        let present = Arc::clone(self);
+        InputHandle::new(Box::new(move |player_id| match player_id {
+            Some(seat) => table.next_move(seat),
+            None => Ok(String::new()),
+        }))
+        .with_presence(Box::new(move |player_id| present.presence(player_id)))
+    }
+
+    /// Whether the player in the given seat is at the table. Someone whose connection has
+    /// dropped is still there while the game waits for them, and gone for good once out of
+    /// grace.
+    pub fn presence(&self, seat: usize) -> Presence {
+        match self.seats.lock().unwrap().get(seat) {
+            Some(seat) => self.presence_of(seat),
+            None => Presence::Here,
+        }
+    }
+
+    /// Whether the player in the given seat is at the table.
+    fn presence_of(&self, seat: &Seat) -> Presence {
+        match (seat.left, self.stand_in) {
+            (None, _) => Presence::Here,
+            (Some(left), _) if left.elapsed() >= self.grace => Presence::Gone,
+            (Some(_), StandIn::Ai) => Presence::Away,
+            (Some(_), StandIn::Pause) => Presence::Here,
+        }
+    }
+
+    /// Connects the session to the given seat, catching it up with the game as the seat sees it
+    /// and any move it's waiting for.
+    pub fn attach(&self, seat: usize, session_id: usize, outbox: Sender<String>) {
+        let mut seats = self.seats.lock().unwrap();
+        let back = seats[seat].left.is_some() && !seats[seat].view.is_null();
+        seats[seat].outbox = Some((session_id, outbox));
+        seats[seat].left = None;
+        if back {
+            info!("{}", Msg::Reconnected(seat));
+            let refresh = json!({ "type": "state", "seat": seat, "state": seats[seat].view });
+            seats[seat].send(&refresh);
+            for other in seats.iter() {
+                other.send(&json!({ "type": "back", "seat": seat }));
+            }
+        }
+        if seats[seat].asked && seats[seat].moves.is_empty() {
+            let message = seats[seat].your_move(false);
+            seats[seat].send(&message);
+        }
+    }
+
+    /// Disconnects the session from the given seat, if it is still the one connected, leaving
+    /// the seat to wait for its player to come back.
+    pub fn detach(&self, seat: usize, session_id: usize) {
+        let mut seats = self.seats.lock().unwrap();
+        if matches!(&seats[seat].outbox, Some((id, _)) if *id == session_id) {
+            info!("{}", Msg::Disconnected(seat));
+            seats[seat].outbox = None;
+            seats[seat].left = Some(Instant::now());
+            for other in seats.iter() {
+                other.send(&json!({ "type": "left", "seat": seat }));
+            }
+            self.moved.notify_all();
+        }
+    }
+
+    /// Sends the message to everyone at the table.
+    pub fn broadcast(&self, message: &Value) {
+        for seat in self.seats.lock().unwrap().iter() {
+            seat.send(message);
+        }
+    }
+
+    /// Queues a move sent for the given seat, so long as it's the seat's move and it isn't
+    /// sending too many, dropping any with the ID of one already sent.
+    pub fn push_move(
+        &self,
+        seat: usize,
+        line: &str,
+        id: Option<u64>,
+    ) -> Result<(), ScrabrudoError> {
+        let mut seats = self.seats.lock().unwrap();
+        let sender = &mut seats[seat];
+        if id.is_some_and(|id| sender.acted.contains(&id)) {
+            return Ok(());
+        }
+        if !sender.to_move {
+            return Err(ScrabrudoError::OutOfTurn(seat));
+        }
+        let now = Instant::now();
+        while matches!(sender.sent.front(), Some(at) if now.duration_since(*at) >= MOVE_WINDOW) {
+            sender.sent.pop_front();
+        }
+        if sender.sent.len() >= MAX_MOVES {
+            return Err(ScrabrudoError::Lobby(format!(
+                "At most {} moves every {} seconds",
+                MAX_MOVES,
+                MOVE_WINDOW.as_secs()
+            )));
+        }
+        sender.sent.push_back(now);
+        sender.acted.extend(id);
+        sender.moves.push_back(line.into());
+        self.moved.notify_all();
+        Ok(())
+    }
+
+    /// The next move sent for the given seat, asking for it and waiting if none has been, until
+    /// its player is no longer at the table or the move's deadline, if it has one, has passed.
+    fn next_move(&self, seat: usize) -> Result<String, ScrabrudoError> {
+        let deadline = move_deadline();
+        let mut seats = self.seats.lock().unwrap();
+        if seats[seat].moves.is_empty() {
+            let message = seats[seat].your_move(seats[seat].asked);
+            seats[seat].send(&message);
+        }
+        seats[seat].asked = true;
+        loop {
+            if let Some(line) = seats[seat].moves.pop_front() {
+                return Ok(line);
+            }
+            if self.presence_of(&seats[seat]) != Presence::Here {
+                return Err(ScrabrudoError::Disconnected(seat));
+            }
+            let wait = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) if !left.is_zero() => left.min(CHECK),
+                    _ => return Err(ScrabrudoError::OutOfTime(seat)),
+                },
+                None => CHECK,
+            };
+            seats = self.moved.wait_timeout(seats, wait).unwrap().0;
+        }
+    }
+
+    /// Says the text to everyone at the table from the given seat, but those who have muted it,
+    /// so long as the table's chat rules allow it.
+    pub fn say(&self, seat: usize, text: &str) -> Result<(), ScrabrudoError> {
+        let rules = &self.lobby.chat;
+        let text = text.trim();
+        if !rules.enabled {
+            return Err(ScrabrudoError::Lobby("Chat is off at this table".into()));
+        }
+        if text.is_empty() || text.chars().count() > rules.max_len {
+            return Err(ScrabrudoError::Lobby(format!(
+                "Messages must have 1 to {} characters",
+                rules.max_len
+            )));
+        }
+        let mut seats = self.seats.lock().unwrap();
+        let now = Instant::now();
+        let window = Duration::from_secs(rules.window_secs);
+        let said = &mut seats[seat].said;
+        while matches!(said.front(), Some(at) if now.duration_since(*at) >= window) {
+            said.pop_front();
+        }
+        if said.len() >= rules.max_messages {
+            return Err(ScrabrudoError::Lobby(format!(
+                "At most {} messages every {} seconds",
+                rules.max_messages, rules.window_secs
+            )));
+        }
+        said.push_back(now);
+        self.chat.lock().unwrap().push(ChatLine {
+            after_move: self.num_moves.load(Ordering::Relaxed),
+            player_id: seat,
+            text: text.into(),
+        });
+        let message = json!({
+            "type": "chat",
+            "seat": seat,
+            "name": self.lobby.members[seat].name,
+            "text": text,
+        });
+        for other in seats.iter().filter(|other| !other.muted.contains(&seat)) {
+            other.send(&message);
+        }
+        Ok(())
+    }
+
+    /// Stops showing the given seat what the other says, or shows it again.
+    pub fn mute(&self, seat: usize, other: usize, muted: bool) -> Result<(), ScrabrudoError> {
+        if other >= self.lobby.num_humans {
+            return Err(ScrabrudoError::Lobby(format!(
+                "No one is chatting in seat {}",
+                other
+            )));
+        }
+        let mut seats = self.seats.lock().unwrap();
+        if muted {
+            seats[seat].muted.insert(other);
+        } else {
+            seats[seat].muted.remove(&other);
+        }
+        seats[seat].send(&json!({ "type": "muted", "seat": other, "muted": muted }));
+        Ok(())
+    }
+
+    /// What has been said at the table so far.
+    pub fn chat_log(&self) -> Vec<ChatLine> {
+        self.chat.lock().unwrap().clone()
+    }
+
+    /// Notes what each seat can see of the game, and whether it's theirs to move, as a new turn
+    /// starts. Moves sent for the last turn and left unplayed are dropped.
+    fn track_game<G: Game>(&self, game: &G) {
+        let state = game.state();
+        for (player_id, seat) in self.seats.lock().unwrap().iter_mut().enumerate() {
+            seat.view = state
+                .seat_of(player_id)
+                .map_or(Value::Null, |i| state.view_for(i).to_json());
+            seat.to_move = state
+                .seat_of(player_id)
+                .is_some_and(|i| game.check_turn(i).is_ok());
+            seat.asked = false;
+            seat.moves.clear();
+        }
+    }
+}
+
+/// How the server plays its games.
+#[derive(Debug, Clone)]
+pub struct Hosting {
+    pub opening: OpeningPolicy,
+
+    /// A directory to record each game in, if any.
+    pub records_dir: Option<String>,
+
+    /// How long someone whose connection drops has to come back before forfeiting.
+    pub grace: Duration,
+
+    /// What happens to their seat meanwhile.
+    pub stand_in: StandIn,
+
+    /// The secret admin commands must come with, if any are taken at all.
+    pub admin_token: Option<String>,
+}
+
+/// Lobbies, and the games started from them, shared by every connection.
+pub struct Server {
+    lobbies: Mutex<Lobbies>,
+
+    /// The games being played, by lobby.
+    tables: Mutex<BTreeMap<usize, Arc<Table>>>,
+
+    /// Where messages go for those who have joined a lobby whose game is yet to start, by token.
+    waiting: Mutex<HashMap<String, (usize, Sender<String>)>>,
+
+    hosting: Hosting,
+
+    /// Plays a table's game to the end.
+    host: fn(Arc<Table>, &Hosting) -> Result<Option<usize>, ScrabrudoError>,
+}
+
+impl Server {
+    /// A server with no lobbies yet, playing games of the given type by the given rules unless a
+    /// lobby sets its own.
+    pub fn new<G: Game>(defaults: GameConfig, hosting: Hosting) -> Arc<Self> {
+        Arc::new(Server {
+            lobbies: Mutex::new(
+                Lobbies::new(defaults).with_admin_token(hosting.admin_token.clone()),
+            ),
+            tables: Mutex::new(BTreeMap::new()),
+            waiting: Mutex::new(HashMap::new()),
+            hosting: hosting,
+            host: host::<G>,
+        })
+    }
+
+    /// Carries out the lobby command, starting the lobby's game if it's now ready, and returns
+    /// the reply.
+    pub fn handle(self: &Arc<Self>, command: LobbyCommand) -> Result<Value, ScrabrudoError> {
+        let (reply, start) = self.lobbies.lock().unwrap().handle(command)?;
+        if let Some(lobby) = start {
+            self.start(lobby);
+        }
+        Ok(reply)
+    }
+
+    /// Acts on a message sent over the WebSocket, sending the reply back.
+    pub fn receive(self: &Arc<Self>, text: &str, session: &mut Session) {
+        let command = serde_json::from_str::<Command>(text)
+            .map_err(|_| ScrabrudoError::Lobby(format!("Unknown command: {}", text)));
+        if let Err(e) = command.and_then(|command| self.act(command, session)) {
+            session.send(&error_reply(&e));
+        }
+    }
+
+    /// Carries out a command sent over the WebSocket, sending the reply back.
+    fn act(
+        self: &Arc<Self>,
+        command: Command,
+        session: &mut Session,
+    ) -> Result<(), ScrabrudoError> {
+        match command {
+            Command::Lobby(command) => {
+                // Sit first, so that the game can be played from here if this starts it.
+                if let LobbyCommand::Ready { token, .. } = &command {
+                    self.sit(token, session);
+                }
+                let reply = self.handle(command)?;
+                if let (Some(token), None) = (reply["token"].as_str(), &session.token) {
+                    self.sit(token, session);
+                }
+                session.send(&reply);
+            }
+            Command::Table(TableCommand::Move { line, id }) => {
+                let (table, seat) = self.seated(session)?;
+                table.push_move(seat, &line, id)?;
+            }
+            Command::Table(TableCommand::Chat { text }) => {
+                let (table, seat) = self.seated(session)?;
+                table.say(seat, &text)?;
+            }
+            Command::Table(TableCommand::Mute { seat: other }) => {
+                let (table, seat) = self.seated(session)?;
+                table.mute(seat, other, true)?;
+            }
+            Command::Table(TableCommand::Unmute { seat: other }) => {
+                let (table, seat) = self.seated(session)?;
+                table.mute(seat, other, false)?;
+            }
+            Command::Table(TableCommand::Resume { token }) => {
+                let (lobby, seat) = self
+                    .lobbies
+                    .lock()
+                    .unwrap()
+                    .find_seat(&token)
+                    .ok_or_else(|| ScrabrudoError::Lobby("No such seat".into()))?;
+                self.leave(session);
+                session.send(&json!({ "type": "resumed", "lobby": lobby, "seat": seat }));
+                self.sit(&token, session);
+            }
+        }
+        Ok(())
+    }
+
+    /// Plays the seat of the given token from the session from now on.
+    fn sit(&self, token: &str, session: &mut Session) {
+        session.token = Some(token.into());
+        let outbox = (session.id, session.outbox.clone());
+        match self.seat(token) {
+            Some((table, seat)) => table.attach(seat, outbox.0, outbox.1),
+            None => {
+                self.waiting.lock().unwrap().insert(token.into(), outbox);
+            }
+        }
+    }
+
+    /// Lets go of the session, as its connection has closed.
+    pub fn leave(&self, session: &Session) {
+        if let Some(token) = &session.token {
+            let mut waiting = self.waiting.lock().unwrap();
+            if matches!(waiting.get(token), Some((id, _)) if *id == session.id) {
+                waiting.remove(token);
+            }
+            drop(waiting);
+            if let Some((table, seat)) = self.seat(token) {
+                table.detach(seat, session.id);
+            }
+        }
+    }
+
+    /// The table and seat the session plays, if it's seated at a game.
+    fn seated(&self, session: &Session) -> Result<(Arc<Table>, usize), ScrabrudoError> {
+        session
+            .token
+            .as_ref()
+            .and_then(|token| self.seat(token))
+            .ok_or_else(|| ScrabrudoError::Lobby("Not seated at a game".into()))
+    }
+
+    /// The table and seat of the given token, if its game has started.
+    fn seat(&self, token: &str) -> Option<(Arc<Table>, usize)> {
+        self.tables.lock().unwrap().values().find_map(|table| {
+            table
+                .lobby
+                .seat_of(token)
+                .map(|seat| (Arc::clone(table), seat))
+        })
+    }
+
+    /// Starts the lobby's game on a thread of its own, connecting those waiting for it.
+    fn start(self: &Arc<Self>, lobby: Lobby) {
+        let id = lobby.id;
+        let table = Table::new(lobby, &self.hosting);
+        let mut waiting = self.waiting.lock().unwrap();
+        for (seat, member) in table.lobby.members.iter().enumerate() {
+            if let Some((session_id, outbox)) = waiting.remove(&member.token) {
+                table.attach(seat, session_id, outbox);
+            }
+        }
+        drop(waiting);
+        for (seat, member) in table.seats.lock().unwrap().iter().enumerate() {
+            member
+                .send(&json!({ "type": "started", "lobby": table.lobby.summary(), "seat": seat }));
+        }
+        self.tables.lock().unwrap().insert(id, Arc::clone(&table));
+        info!("Lobby {} started", id);
+        let server = Arc::clone(self);
+        thread::spawn(move || {
+            match (server.host)(table, &server.hosting) {
+                Ok(Some(winner)) => info!("Lobby {}: player {} won", id, winner),
+                Ok(None) => info!("Lobby {}: no winner", id),
+                Err(e) => warn!("Lobby {}: {}", id, e),
+            }
+            server.tables.lock().unwrap().remove(&id);
+        });
+    }
+}
+
+/// Plays the table's game to the end, people in the first seats and the AI in the rest,
+/// recording it if asked, and tells the table who won.
+fn host<G: Game>(table: Arc<Table>, hosting: &Hosting) -> Result<Option<usize>, ScrabrudoError> {
+    let lobby = &table.lobby;
+    let mut config = lobby.config.clone();
+    config.input = table.input();
+    let humans = (0..lobby.num_humans).collect::<HashSet<usize>>();
+    HOSTING.with(|hosting| *hosting.borrow_mut() = Some(Arc::clone(&table)));
+    let result = match &hosting.records_dir {
+        Some(dir) => verify::play_recorded::<G>(
+            rand::random(),
+            lobby.num_players(),
+            NUM_ITEMS,
+            &humans,
+            &hosting.opening,
+            &config,
+        )
+        .and_then(|(game, record)| {
+            let record = verify::GameRecord {
+                chat: table.chat_log(),
+                ..record
+            };
+            verify::save_record(&record, &format!("{}/lobby_{}.json", dir, lobby.id))?;
+            Ok(game)
+        }),
+        None => G::new(
+            lobby.num_players(),
+            NUM_ITEMS,
+            humans,
+            hosting.opening.clone(),
+            config,
+        )
+        .run(),
+    };
+    HOSTING.with(|hosting| *hosting.borrow_mut() = None);
+    let winner = result?.winner();
+    table.broadcast(&json!({ "type": "over", "winner": winner }));
+    Ok(winner)
+}
+
+/// Notes what each person can see of the game on this thread as a turn starts, if it's hosted
+/// here.
+pub fn track_game<G: Game>(game: &G) {
+    HOSTING.with(|hosting| {
+        if let Some(table) = hosting.borrow().as_ref() {
+            table.track_game(game);
+        }
+    });
+}
+
+/// Sends the turn to everyone at the table of the game on this thread, if it's hosted here.
+pub fn track_turn<G: Game>(turn: &PlayedTurn<G>) {
+    HOSTING.with(|hosting| {
+        if let Some(table) = hosting.borrow().as_ref() {
+            table.num_moves.fetch_add(1, Ordering::Relaxed);
+            table.broadcast(&json!({ "type": "turn", "turn": dashboard::turn_event(turn) }));
+        }
+    });
+}
+
+/// The lobby command an HTTP request makes, if it makes one: GET /lobbies lists them, POST
+/// /lobbies creates one, POST /lobbies/ID/join and /lobbies/ID/ready join and ready up, and POST
+/// /admin/reload switches dictionary, the rest of each command given as a JSON body.
+pub fn http_command(
+    method: &str,
+    path: &str,
+    body: &str,
+) -> Result<Option<LobbyCommand>, ScrabrudoError> {
+    let parts = path.trim_matches('/').split('/').collect::<Vec<&str>>();
+    let (kind, lobby) = match (method, parts.as_slice()) {
+        ("GET", ["lobbies"]) => ("list", None),
+        ("POST", ["lobbies"]) => ("create", None),
+        ("POST", ["admin", "reload"]) => ("reload", None),
+        ("POST", ["lobbies", lobby, kind @ ("join" | "ready")]) => (*kind, Some(*lobby)),
+        _ => return Ok(None),
+    };
+    let mut command = match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(fields)) => fields,
+        _ if body.trim().is_empty() => Map::new(),
+        _ => return Err(ScrabrudoError::Lobby("Expected a JSON object".into())),
+    };
+    command.insert("type".into(), kind.into());
+    if let Some(lobby) = lobby {
+        let id = lobby
+            .parse::<usize>()
+            .map_err(|_| ScrabrudoError::Lobby(format!("No such lobby: {}", lobby)))?;
+        command.insert("lobby".into(), id.into());
+    }
+    serde_json::from_value(Value::Object(command))
+        .map(Some)
+        .map_err(|e| ScrabrudoError::Lobby(format!("Invalid command: {}", e)))
+}
+
+/// The reply telling a player what went wrong.
+fn error_reply(e: &ScrabrudoError) -> Value {
+    json!({ "type": "error", "message": e.to_string() })
+}
+
+/// Serves lobbies at the given address, e.g. 127.0.0.1:8090, for people to find and play games
+/// of the given type in, from threads of their own, returning the address bound.
+#[cfg(feature = "net")]
+pub fn serve_lobbies<G: Game>(
+    addr: &str,
+    defaults: GameConfig,
+    hosting: Hosting,
+) -> Result<SocketAddr, ScrabrudoError> {
+    let server = Server::new::<G>(defaults, hosting);
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    info!("Serving lobbies at http://{}/lobbies", bound);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let server = Arc::clone(&server);
+            // Players stay connected for whole games, so each has a thread of their own.
+            thread::spawn(move || {
+                if let Err(e) = stream
+                    .map_err(|e| e.to_string())
+                    .and_then(|s| respond(&server, s))
+                {
+                    warn!("Couldn't serve a player: {}", e);
+                }
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// Refuses to serve lobbies, lacking the net feature.
+#[cfg(not(feature = "net"))]
+pub fn serve_lobbies<G: Game>(
+    _addr: &str,
+    _defaults: GameConfig,
+    _hosting: Hosting,
+) -> Result<SocketAddr, ScrabrudoError> {
+    Err(ScrabrudoError::InvalidOption(
+        "Build with --features net to serve lobbies".into(),
+    ))
+}
+
+/// Answers a lobby command over HTTP, or takes on a player connecting to play.
+#[cfg(feature = "net")]
+fn respond(server: &Arc<Server>, mut stream: TcpStream) -> Result<(), String> {
+    // Only look at the request line, leaving the handshake to be read in full.
+    let mut start = [0; 10];
+    let peeked = stream.peek(&mut start).map_err(|e| e.to_string())?;
+    if start[..peeked].starts_with(b"GET /play ") {
+        let socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+        return play(server, socket);
+    }
+    let (method, path, body) = read_request(&stream).map_err(|e| e.to_string())?;
+    let (status, reply) = match http_command(&method, &path, &body) {
+        Ok(Some(command)) => match server.handle(command) {
+            Ok(reply) => ("200 OK", reply),
+            Err(e) => ("400 Bad Request", error_reply(&e)),
+        },
+        Ok(None) => (
+            "404 Not Found",
+            error_reply(&ScrabrudoError::Lobby("Not found".into())),
+        ),
+        Err(e) => ("400 Bad Request", error_reply(&e)),
+    };
+    let body = reply.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Reads an HTTP request's method, path and body.
+#[cfg(feature = "net")]
+fn read_request(stream: &TcpStream) -> io::Result<(String, String, String)> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().unwrap_or(0).min(MAX_BODY);
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let mut parts = request_line.split_whitespace();
+    Ok((
+        parts.next().unwrap_or("").into(),
+        parts.next().unwrap_or("").into(),
+        String::from_utf8_lossy(&body).into(),
+    ))
+}
+
+/// Plays out a player's connection: acting on what they send, and sending on what's for them.
+#[cfg(feature = "net")]
+fn play(server: &Arc<Server>, mut socket: WebSocket<TcpStream>) -> Result<(), String> {
+    socket
+        .get_mut()
+        .set_read_timeout(Some(POLL))
+        .map_err(|e| e.to_string())?;
+    let (outbox, inbox) = std::sync::mpsc::channel();
+    let mut session = Session::new(outbox);
+    let result = loop {
+        let sent = inbox.try_iter().try_for_each(|text| {
+            socket
+                .write_message(Message::Text(text))
+                .map_err(|e| e.to_string())
+        });
+        if let Err(e) = sent {
+            break Err(e);
+        }
+        match socket.read_message() {
+            Ok(Message::Text(text)) => server.receive(&text, &mut session),
+            Ok(Message::Close(_)) => break Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => break Err(e.to_string()),
+        }
+    };
+    server.leave(&session);
+    result
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::timebank::{Penalty, Timebank};
+    use std::sync::mpsc::{channel, Receiver};
+
+    before {
+        crate::testing::set_up();
+    }
+
+    fn hosting(grace: Duration, stand_in: StandIn) -> Hosting {
+        Hosting {
+            opening: OpeningPolicy::Likeliest,
+            records_dir: None,
+            grace: grace,
+            stand_in: stand_in,
+            admin_token: None,
+        }
+    }
+
+    fn next(inbox: &Receiver<String>) -> Value {
+        serde_json::from_str(&inbox.recv_timeout(Duration::from_secs(30)).unwrap()).unwrap()
+    }
+
+    describe "the network protocol" {
+        it "reads lobby commands from HTTP requests" {
+            assert_eq!(Some(LobbyCommand::List), http_command("GET", "/lobbies", "").unwrap());
+            assert_eq!(
+                Some(LobbyCommand::Reload { admin_token: "abc".into(), dict: "d.txt".into(), lookup: "l.sstable".into() }),
+                http_command("POST", "/admin/reload", "{\"admin_token\": \"abc\", \"dict\": \"d.txt\", \"lookup\": \"l.sstable\"}").unwrap());
+            assert_eq!(
+                Some(LobbyCommand::Join { lobby: 2, name: "ana".into() }),
+                http_command("POST", "/lobbies/2/join", "{\"name\": \"ana\"}").unwrap());
+            assert_eq!(
+                Some(LobbyCommand::Ready { lobby: 2, token: "abc".into() }),
+                http_command("POST", "/lobbies/2/ready", "{\"token\": \"abc\"}").unwrap());
+            assert!(http_command("GET", "/", "").unwrap().is_none());
+            assert!(http_command("POST", "/lobbies/two/join", "{\"name\": \"ana\"}").is_err());
+            assert!(http_command("POST", "/lobbies/2/join", "[]").is_err());
+        }
+
+        it "plays a lobby's game over the WebSocket's messages" {
+            let config = GameConfig { max_rounds: Some(1), ..GameConfig::default() };
+            let server = Server::new::<PerudoGame>(config, hosting(Duration::from_secs(60), StandIn::Ai));
+            let (outbox, inbox) = channel();
+            let mut session = Session::new(outbox);
+
+            server.receive("{\"type\": \"create\", \"num_humans\": 1, \"num_ai\": 1}", &mut session);
+            assert_eq!("created", next(&inbox)["type"]);
+            server.receive("{\"type\": \"join\", \"lobby\": 1, \"name\": \"ana\"}", &mut session);
+            let token = next(&inbox)["token"].as_str().unwrap().to_string();
+            assert_eq!(Some(token.clone()), session.token);
+            server.receive(&format!("{{\"type\": \"ready\", \"lobby\": 1, \"token\": \"{}\"}}", token), &mut session);
+            assert_eq!("started", next(&inbox)["type"]);
+
+            // Quitting hands the seat to the AI, which plays the game out.
+            let mut num_turns = 0;
+            loop {
+                let message = next(&inbox);
+                match message["type"].as_str().unwrap() {
+                    "ready" => assert_eq!(true, message["starting"]),
+                    "your_move" => {
+                        assert_eq!(json!([0, 1]), message["state"]["player_ids"]);
+                        server.receive("{\"type\": \"move\", \"line\": \"quit\"}", &mut session);
+                    }
+                    "turn" => num_turns += 1,
+                    "over" => break,
+                    other => panic!("Unexpected message: {}", other),
+                }
+            }
+            assert!(num_turns > 0);
+
+            server.receive("{\"type\": \"bet\"}", &mut session);
+            assert_eq!("error", next(&inbox)["type"]);
+        }
+
+        it "chats at the table within its rules" {
+            let mut lobby = Lobby::new(1, 2, 0, GameConfig::default()).unwrap();
+            lobby.chat = ChatRules { max_messages: 2, window_secs: 60, ..ChatRules::default() };
+            lobby.join("ana").unwrap();
+            lobby.join("ben").unwrap();
+            let table = Table::new(lobby.clone(), &hosting(Duration::from_secs(60), StandIn::Ai));
+            let (outbox, ana) = channel();
+            table.attach(0, 1, outbox);
+            let (outbox, ben) = channel();
+            table.attach(1, 2, outbox);
+
+            table.say(0, " hola ").unwrap();
+            let said = json!({ "type": "chat", "seat": 0, "name": "ana", "text": "hola" });
+            assert_eq!(said, next(&ana));
+            assert_eq!(said, next(&ben));
+            table.mute(1, 0, true).unwrap();
+            assert_eq!(json!({ "type": "muted", "seat": 0, "muted": true }), next(&ben));
+            table.say(0, "¿quién miente?").unwrap();
+            assert_eq!("¿quién miente?", next(&ana)["text"]);
+            assert!(ben.try_recv().is_err());
+            assert!(table.say(0, "too much").is_err());
+            assert!(table.say(1, &"a".repeat(201)).is_err());
+            assert!(table.mute(1, 2, true).is_err());
+            assert_eq!(2, table.chat_log().len());
+            assert_eq!(0, table.chat_log()[1].after_move);
+
+            lobby.chat.enabled = false;
+            let table = Table::new(lobby, &hosting(Duration::from_secs(60), StandIn::Ai));
+            assert!(table.say(0, "hola").is_err());
+        }
+
+        it "turns away moves sent out of turn, too often or twice" {
+            let lobby = Lobby::new(1, 2, 0, GameConfig::default()).unwrap();
+            let table = Table::new(lobby, &hosting(Duration::from_secs(60), StandIn::Ai));
+            assert!(matches!(table.push_move(0, "p", None), Err(ScrabrudoError::OutOfTurn(0))));
+            let game = PerudoGame::new(2, 2, hashset!{0, 1}, OpeningPolicy::Likeliest, GameConfig::default());
+            table.track_game(&game);
+            assert!(matches!(table.push_move(1, "p", None), Err(ScrabrudoError::OutOfTurn(1))));
+
+            table.push_move(0, "1 Threes", Some(7)).unwrap();
+            table.push_move(0, "1 Threes", Some(7)).unwrap();
+            assert_eq!(1, table.seats.lock().unwrap()[0].moves.len());
+            assert_eq!("1 Threes", table.input().read_line(Some(0)).unwrap());
+            for id in 0..(MAX_MOVES as u64 - 1) {
+                table.push_move(0, "1 Threes", Some(id)).unwrap();
+            }
+            assert!(table.push_move(0, "1 Threes", Some(9)).is_err());
+            table.track_game(&game);
+            assert!(table.seats.lock().unwrap()[0].moves.is_empty());
+        }
+
+        it "stops waiting for a stalled seat once its time is up" {
+            let lobby = Lobby::new(1, 1, 1, GameConfig::default()).unwrap();
+            let table = Table::new(lobby, &hosting(Duration::from_secs(60), StandIn::Ai));
+            let (outbox, _inbox) = channel();
+            table.attach(0, 1, outbox);
+            let due = Instant::now() + Duration::from_millis(50);
+            let line = with_deadline(due, || table.input().read_line(Some(0)));
+            assert!(matches!(line, Err(ScrabrudoError::OutOfTime(0))));
+            assert!(Instant::now() >= due);
+
+            // The game goes on without the seat, as the timebank's penalty has it.
+            let timebank = Timebank { millis: 100, penalty: Penalty::Forfeit };
+            let config = GameConfig { max_rounds: Some(1), timebank: Some(timebank), ..GameConfig::default() };
+            let server = Server::new::<PerudoGame>(config, hosting(Duration::from_secs(60), StandIn::Ai));
+            let (outbox, inbox) = channel();
+            let mut session = Session::new(outbox);
+            server.receive("{\"type\": \"create\", \"num_humans\": 1, \"num_ai\": 1}", &mut session);
+            server.receive("{\"type\": \"join\", \"lobby\": 1, \"name\": \"ana\"}", &mut session);
+            let token = session.token.clone().unwrap();
+            server.receive(&format!("{{\"type\": \"ready\", \"lobby\": 1, \"token\": \"{}\"}}", token), &mut session);
+            let mut num_asked = 0;
+            loop {
+                let message = next(&inbox);
+                match message["type"].as_str().unwrap() {
+                    "your_move" => num_asked += 1,
+                    "over" => break,
+                    _ => {}
+                }
+            }
+            assert_eq!(1, num_asked);
+        }
+
+        it "records what's said at the table with the game" {
+            let dir = std::env::temp_dir().join(format!("scrabrudo-lobby-{}", rand::random::<u32>()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let hosting = Hosting {
+                records_dir: Some(dir.to_string_lossy().into()),
+                ..hosting(Duration::from_secs(60), StandIn::Ai)
+            };
+            let config = GameConfig { max_rounds: Some(1), ..GameConfig::default() };
+            let server = Server::new::<PerudoGame>(config, hosting);
+            let (outbox, inbox) = channel();
+            let mut session = Session::new(outbox);
+            server.receive("{\"type\": \"create\", \"num_humans\": 1, \"num_ai\": 1}", &mut session);
+            server.receive("{\"type\": \"join\", \"lobby\": 1, \"name\": \"ana\"}", &mut session);
+            let token = session.token.clone().unwrap();
+            server.receive(&format!("{{\"type\": \"ready\", \"lobby\": 1, \"token\": \"{}\"}}", token), &mut session);
+            server.receive("{\"type\": \"chat\", \"text\": \"I never bluff\"}", &mut session);
+            loop {
+                let message = next(&inbox);
+                match message["type"].as_str().unwrap() {
+                    "your_move" => server.receive("{\"type\": \"move\", \"line\": \"quit\"}", &mut session),
+                    "over" => break,
+                    _ => {}
+                }
+            }
+
+            let record = std::fs::read_to_string(dir.join("lobby_1.json")).unwrap();
+            let record = serde_json::from_str::<verify::GameRecord<crate::bet::PerudoBet>>(&record).unwrap();
+            assert_eq!(1, record.chat.len());
+            assert_eq!("I never bluff", record.chat[0].text);
+            assert!(verify::verify::<PerudoGame>(&record).is_ok());
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+
+        it "keeps a dropped player's seat until they're out of grace" {
+            let lobby = Lobby::new(1, 1, 1, GameConfig::default()).unwrap();
+            let table = Table::new(lobby.clone(), &hosting(Duration::from_secs(60), StandIn::Ai));
+            assert_eq!(Presence::Away, table.presence(0));
+            let (outbox, _inbox) = channel();
+            table.attach(0, 1, outbox);
+            assert_eq!(Presence::Here, table.presence(0));
+            table.detach(0, 2);
+            assert_eq!(Presence::Here, table.presence(0));
+            table.detach(0, 1);
+            assert_eq!(Presence::Away, table.input().presence(0));
+            assert!(matches!(table.input().read_line(Some(0)), Err(ScrabrudoError::Disconnected(0))));
+            assert_eq!(Presence::Here, table.presence(1));
+
+            let table = Table::new(lobby.clone(), &hosting(Duration::from_secs(60), StandIn::Pause));
+            assert_eq!(Presence::Here, table.presence(0));
+            let table = Table::new(lobby, &hosting(Duration::from_millis(0), StandIn::Pause));
+            assert_eq!(Presence::Gone, table.presence(0));
+        }
+
+        it "resumes a dropped player's seat with the game as they left it" {
+            let config = GameConfig { max_rounds: Some(1), ..GameConfig::default() };
+            let server = Server::new::<PerudoGame>(config, hosting(Duration::from_secs(60), StandIn::Pause));
+            let (outbox, inbox) = channel();
+            let mut session = Session::new(outbox);
+            server.receive("{\"type\": \"create\", \"num_humans\": 1, \"num_ai\": 1}", &mut session);
+            server.receive("{\"type\": \"join\", \"lobby\": 1, \"name\": \"ana\"}", &mut session);
+            let token = session.token.clone().unwrap();
+            server.receive(&format!("{{\"type\": \"ready\", \"lobby\": 1, \"token\": \"{}\"}}", token), &mut session);
+            while !matches!(next(&inbox)["type"].as_str(), Some("turn") | Some("your_move")) {}
+            server.leave(&session);
+
+            // The game waits for the seat, and catches whoever resumes it up.
+            let (outbox, inbox) = channel();
+            let mut session = Session::new(outbox);
+            server.receive(&format!("{{\"type\": \"resume\", \"token\": \"{}\"}}", token), &mut session);
+            assert_eq!(json!({ "type": "resumed", "lobby": 1, "seat": 0 }), next(&inbox));
+            let refresh = next(&inbox);
+            assert_eq!("state", refresh["type"]);
+            assert_eq!(json!([0, 1]), refresh["state"]["player_ids"]);
+            loop {
+                let message = next(&inbox);
+                match message["type"].as_str().unwrap() {
+                    "your_move" => server.receive("{\"type\": \"move\", \"line\": \"quit\"}", &mut session),
+                    "over" => break,
+                    _ => {}
+                }
+            }
+
+            server.receive("{\"type\": \"resume\", \"token\": \"nobody\"}", &mut session);
+            assert_eq!("error", next(&inbox)["type"]);
+        }
+
+        #[cfg(feature = "net")]
+        it "serves lobbies over HTTP and the WebSocket alike" {
+            let hosting = hosting(Duration::from_secs(60), StandIn::Ai);
+            let addr = serve_lobbies::<PerudoGame>("127.0.0.1:0", GameConfig::default(), hosting).unwrap();
+            let body = "{\"num_humans\": 2}";
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "POST /lobbies HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.contains("\"type\":\"created\""));
+
+            let stream = TcpStream::connect(addr).unwrap();
+            let (mut socket, _) = tungstenite::client(format!("ws://{}/play", addr), stream).unwrap();
+            socket.write_message(Message::Text("{\"type\": \"list\"}".into())).unwrap();
+            match socket.read_message().unwrap() {
+                Message::Text(text) => {
+                    let reply = serde_json::from_str::<Value>(&text).unwrap();
+                    assert_eq!(2, reply["lobbies"][0]["num_humans"]);
+                }
+                other => panic!("Unexpected message: {:?}", other),
+            }
+        }
+    }
+}
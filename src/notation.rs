@@ -0,0 +1,461 @@
+/// Game notation: a compact text form of each round, like chess's PGN, for sharing games,
+/// reading them back in for analysis and pasting into bug reports. A round reads e.g.
+/// "3. 0:cat 1:chart 0:perudo! reveal 0=catse 1=hr_xa mid=e": its number, each move as the ID of
+/// the player and their bet or challenge, then every hand as it was when the challenge was made,
+/// and any items in the middle.
+use crate::bet::*;
+use crate::config::*;
+use crate::die::*;
+use crate::error::*;
+use crate::game::*;
+use crate::logging;
+use crate::symbol::*;
+use crate::tile::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref NOTATION_OUT: Mutex<Option<File>> = Mutex::new(None);
+    static ref LAST_GAME_ID: Mutex<Option<u32>> = Mutex::new(None);
+}
+
+/// Anything with a written form in game notation.
+pub trait Notation: Sized {
+    /// The written form of this.
+    fn notate(&self) -> String;
+
+    /// Reads this back from its written form.
+    fn from_notation(s: &str) -> Result<Self, ScrabrudoError>;
+}
+
+impl Notation for Die {
+    fn notate(&self) -> String {
+        self.int().to_string()
+    }
+
+    fn from_notation(s: &str) -> Result<Self, ScrabrudoError> {
+        Die::from_usize(s.parse::<usize>().map_err(|_| invalid(s))?)
+    }
+}
+
+impl Notation for Tile {
+    fn notate(&self) -> String {
+        match self {
+            Tile::Blank => "_".into(),
+            tile => tile.char().to_string(),
+        }
+    }
+
+    fn from_notation(s: &str) -> Result<Self, ScrabrudoError> {
+        let chars = s.chars().collect::<Vec<char>>();
+        match chars.as_slice() {
+            ['_'] => Ok(Tile::Blank),
+            [c] => Tile::from_char(*c),
+            _ => Err(invalid(s)),
+        }
+    }
+}
+
+impl Notation for PerudoBet {
+    fn notate(&self) -> String {
+        format!("{}x{}", self.quantity, self.value.int())
+    }
+
+    fn from_notation(s: &str) -> Result<Self, ScrabrudoError> {
+        let mut split = s.splitn(2, 'x');
+        match (split.next().map(|q| q.parse::<usize>()), split.next()) {
+            (Some(Ok(quantity)), Some(value)) => Ok(PerudoBet {
+                value: Die::from_notation(value)?,
+                quantity: quantity,
+            }),
+            _ => Err(invalid(s)),
+        }
+    }
+}
+
+impl Notation for ScrabrudoBet {
+    fn notate(&self) -> String {
+        self.as_word()
+    }
+
+    fn from_notation(s: &str) -> Result<Self, ScrabrudoError> {
+        ScrabrudoBet::parse(s)
+    }
+}
+
+impl Notation for Symbol {
+    fn notate(&self) -> String {
+        self.char().to_string()
+    }
+
+    fn from_notation(s: &str) -> Result<Self, ScrabrudoError> {
+        match s.chars().collect::<Vec<char>>().as_slice() {
+            [c] => Symbol::from_char(*c),
+            _ => Err(invalid(s)),
+        }
+    }
+}
+
+impl Notation for EquationBet {
+    fn notate(&self) -> String {
+        self.as_identity()
+    }
+
+    fn from_notation(s: &str) -> Result<Self, ScrabrudoError> {
+        EquationBet::parse(s)
+    }
+}
+
+fn invalid(token: &str) -> ScrabrudoError {
+    ScrabrudoError::Format(format!("Invalid notation: {}", token))
+}
+
+fn notate_items<V: Notation>(items: &[V]) -> String {
+    items.iter().map(|i| i.notate()).collect()
+}
+
+/// Reads items written together, e.g. "catse" or "2316".
+pub fn items_from_notation<V: Notation>(s: &str) -> Result<Vec<V>, ScrabrudoError> {
+    s.chars()
+        .map(|c| V::from_notation(&c.to_string()))
+        .collect()
+}
+
+/// Reads a move: a bet, or "perudo!" or "palafico!" for a challenge.
+pub fn outcome_from_notation<B: Bet>(s: &str) -> Result<TurnOutcome<B>, ScrabrudoError> {
+    match s {
+        "perudo!" => Ok(TurnOutcome::Perudo),
+        "palafico!" => Ok(TurnOutcome::Palafico),
+        bet => Ok(TurnOutcome::Bet(B::from_notation(bet)?)),
+    }
+}
+
+fn parse_id(s: &str) -> Result<usize, ScrabrudoError> {
+    s.parse::<usize>().map_err(|_| invalid(s))
+}
+
+/// A round as written in game notation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundNotation<B: Bet> {
+    /// The round of the game, counting from 1.
+    pub number: usize,
+
+    /// Each move in turn: the ID of the player and the bet or challenge they made.
+    pub moves: Vec<(usize, TurnOutcome<B>)>,
+
+    /// Each player's hand when the challenge was made, by seat, with their ID.
+    pub hands: Vec<(usize, Vec<B::V>)>,
+
+    /// The items in the middle of the table.
+    pub community: Vec<B::V>,
+}
+
+impl<B: Bet> RoundNotation<B> {
+    /// The round the game is in, ending with the given challenge by the current player.
+    pub fn from_game<G: Game<B = B, V = B::V>>(
+        game: &G,
+        number: usize,
+        challenge: &TurnOutcome<B>,
+    ) -> Self {
+        let mut moves = game
+            .history()
+            .iter()
+            .flat_map(|(player_id, bets)| {
+                bets.iter()
+                    .map(move |bet| (*player_id, TurnOutcome::Bet(bet.clone())))
+            })
+            .collect::<Vec<(usize, TurnOutcome<B>)>>();
+        // Bets only ever rise, so sorting them gives the order they were made in.
+        moves.sort_by(|a, b| match (&a.1, &b.1) {
+            (TurnOutcome::Bet(x), TurnOutcome::Bet(y)) => x.cmp(y),
+            _ => a.0.cmp(&b.0),
+        });
+        moves.push((game.players()[game.current_index()].id(), challenge.clone()));
+        Self {
+            number: number,
+            moves: moves,
+            hands: game
+                .players()
+                .iter()
+                .map(|p| (p.id(), p.items().clone()))
+                .collect(),
+            community: game.community().clone(),
+        }
+    }
+
+    /// Reads a round from a line of game notation.
+    pub fn parse(line: &str) -> Result<Self, ScrabrudoError> {
+        let mut tokens = line.split_whitespace();
+        let number = match tokens.next() {
+            Some(n) if n.ends_with('.') => parse_id(n.trim_end_matches('.'))?,
+            _ => return Err(invalid(line)),
+        };
+        let mut moves = vec![];
+        let mut hands = vec![];
+        let mut community = vec![];
+        let mut revealing = false;
+        for token in tokens {
+            if token == "reveal" {
+                revealing = true;
+                continue;
+            }
+            let separator = if revealing { '=' } else { ':' };
+            let mut split = token.splitn(2, separator);
+            match (split.next(), split.next()) {
+                (Some("mid"), Some(items)) if revealing => {
+                    community = items_from_notation(items)?;
+                }
+                (Some(id), Some(items)) if revealing => {
+                    hands.push((parse_id(id)?, items_from_notation(items)?));
+                }
+                (Some(id), Some(outcome)) => {
+                    moves.push((parse_id(id)?, outcome_from_notation(outcome)?))
+                }
+                _ => return Err(invalid(token)),
+            }
+        }
+        Ok(Self {
+            number: number,
+            moves: moves,
+            hands: hands,
+            community: community,
+        })
+    }
+
+    /// The position just before the last move, with every hand, for analysing the round.
+    pub fn position(&self, config: &GameConfig) -> Result<GameState<B>, ScrabrudoError> {
+        let (last_id, _) = self.moves.last().ok_or_else(|| invalid("empty round"))?;
+        let current_index = self
+            .hands
+            .iter()
+            .position(|(id, _)| id == last_id)
+            .ok_or_else(|| invalid(&format!("no hand for player {}", last_id)))?;
+        let mut history: HashMap<usize, Vec<B>> = HashMap::new();
+        for (player_id, outcome) in &self.moves[..self.moves.len() - 1] {
+            match outcome {
+                TurnOutcome::Bet(bet) => history
+                    .entry(*player_id)
+                    .or_insert(vec![])
+                    .push(bet.clone()),
+                _ => return Err(invalid("challenge before the end of the round")),
+            }
+        }
+        let num_items_per_player = self
+            .hands
+            .iter()
+            .map(|(_, items)| items.len())
+            .collect::<Vec<usize>>();
+        Ok(GameState {
+            total_num_items: num_items_per_player.iter().sum(),
+            num_items_per_player: num_items_per_player,
+            current_index: current_index,
+            player_ids: self.hands.iter().map(|(id, _)| *id).collect(),
+            history: history,
+            config: config.clone(),
+            revealed: vec![vec![]; self.hands.len()],
+            community: self.community.clone(),
+            constraint: None,
+            hands: self.hands.iter().map(|(_, items)| items.clone()).collect(),
+            clocks: hashmap! {},
+            honesty: hashmap! {},
+        })
+    }
+}
+
+impl<B: Bet> fmt::Display for RoundNotation<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.", self.number)?;
+        for (player_id, outcome) in &self.moves {
+            match outcome {
+                TurnOutcome::Bet(bet) => write!(f, " {}:{}", player_id, bet.notate())?,
+                TurnOutcome::Perudo => write!(f, " {}:perudo!", player_id)?,
+                TurnOutcome::Palafico => write!(f, " {}:palafico!", player_id)?,
+                _ => (),
+            }
+        }
+        write!(f, " reveal")?;
+        for (player_id, items) in &self.hands {
+            write!(f, " {}={}", player_id, notate_items(items))?;
+        }
+        if !self.community.is_empty() {
+            write!(f, " mid={}", notate_items(&self.community))?;
+        }
+        Ok(())
+    }
+}
+
+/// A game as written in game notation: a header, then a round on each line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameNotation<B: Bet> {
+    /// The ID the game was tagged with in the log, if known.
+    pub game_id: Option<u32>,
+
+    pub rounds: Vec<RoundNotation<B>>,
+}
+
+impl<B: Bet> GameNotation<B> {
+    /// Reads every game from text in game notation, failing if any was of another variant.
+    /// Blank lines and lines starting with ';' are comments.
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, ScrabrudoError> {
+        let mut games: Vec<Self> = vec![];
+        for line in text.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if let Some(tag) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let mut split = tag.splitn(2, ' ');
+                let (name, value) = match (split.next(), split.next()) {
+                    (Some(name), Some(value)) => (name, value.trim_matches('"')),
+                    _ => return Err(invalid(line)),
+                };
+                match name {
+                    "Game" => games.push(Self {
+                        game_id: Some(u32::from_str_radix(value, 16).map_err(|_| invalid(line))?),
+                        rounds: vec![],
+                    }),
+                    "Variant" if value != B::VARIANT => {
+                        return Err(ScrabrudoError::Format(format!(
+                            "Expected a {} game, found {}",
+                            B::VARIANT,
+                            value
+                        )))
+                    }
+                    _ => (),
+                }
+                continue;
+            }
+            if games.is_empty() {
+                games.push(Self {
+                    game_id: None,
+                    rounds: vec![],
+                });
+            }
+            games
+                .last_mut()
+                .unwrap()
+                .rounds
+                .push(RoundNotation::parse(line)?);
+        }
+        Ok(games)
+    }
+
+    /// The header a game starts with.
+    fn header(game_id: Option<u32>) -> String {
+        match game_id {
+            Some(id) => format!("[Game \"{:08x}\"]\n[Variant \"{}\"]", id, B::VARIANT),
+            None => format!("[Variant \"{}\"]", B::VARIANT),
+        }
+    }
+}
+
+impl<B: Bet> fmt::Display for GameNotation<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Self::header(self.game_id))?;
+        for round in &self.rounds {
+            write!(f, "\n{}", round)?;
+        }
+        Ok(())
+    }
+}
+
+/// Starts writing every round played to the given path in game notation.
+pub fn init_notation(notation_path: &str) -> Result<(), ScrabrudoError> {
+    *NOTATION_OUT.lock().unwrap() = Some(File::create(notation_path)?);
+    Ok(())
+}
+
+/// Writes the round ending with the given challenge by the current player, if writing notation,
+/// starting with a header if it's the first round written from this game.
+pub fn write_round<G: Game>(game: &G, challenge: &TurnOutcome<G::B>) -> Result<(), ScrabrudoError> {
+    let mut out = NOTATION_OUT.lock().unwrap();
+    let f = match out.as_mut() {
+        Some(f) => f,
+        None => return Ok(()),
+    };
+    let context = logging::context();
+    let mut last_game_id = LAST_GAME_ID.lock().unwrap();
+    if *last_game_id != context.game_id {
+        writeln!(f, "{}", GameNotation::<G::B>::header(context.game_id))?;
+        *last_game_id = context.game_id;
+    }
+    writeln!(
+        f,
+        "{}",
+        RoundNotation::from_game(game, game.round(), challenge)
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+speculate! {
+    fn bet(quantity: usize, value: Die) -> TurnOutcome<PerudoBet> {
+        TurnOutcome::Bet(PerudoBet { value: value, quantity: quantity })
+    }
+
+    describe "game notation" {
+        it "writes and reads items and bets" {
+            assert_eq!("3x5", PerudoBet { value: Die::Five, quantity: 3 }.notate());
+            assert_eq!(PerudoBet { value: Die::Five, quantity: 3 }, PerudoBet::from_notation("3x5").unwrap());
+            assert!(PerudoBet::from_notation("3x7").is_err());
+            assert_eq!("cat+dog", ScrabrudoBet::parse("cat+dog").unwrap().notate());
+            assert_eq!("_", Tile::Blank.notate());
+            assert_eq!(Tile::Blank, Tile::from_notation("_").unwrap());
+        }
+
+        it "writes and reads a round" {
+            let round = RoundNotation::<PerudoBet> {
+                number: 2,
+                moves: vec![(0, bet(2, Die::Three)), (1, bet(3, Die::Three)), (0, TurnOutcome::Perudo)],
+                hands: vec![(0, vec![Die::Three, Die::One]), (1, vec![Die::Six])],
+                community: vec![Die::Two],
+            };
+            let line = "2. 0:2x3 1:3x3 0:perudo! reveal 0=31 1=6 mid=2";
+            assert_eq!(line, format!("{}", round));
+            assert_eq!(round, RoundNotation::parse(line).unwrap());
+            assert!(RoundNotation::<PerudoBet>::parse("0:2x3").is_err());
+            assert!(RoundNotation::<PerudoBet>::parse("1. 0:cat").is_err());
+        }
+
+        it "reads a scrabrudo round" {
+            let round = RoundNotation::<ScrabrudoBet>::parse(
+                "1. 0:cat 1:chart 0:palafico! reveal 0=catse 1=hr_xa").unwrap();
+            assert_eq!((1, TurnOutcome::Bet(ScrabrudoBet::parse("chart").unwrap())), round.moves[1]);
+            assert_eq!((0, TurnOutcome::Palafico), round.moves[2]);
+            assert_eq!(Tile::Blank, round.hands[1].1[2]);
+        }
+
+        it "reads games" {
+            let text = "[Game \"000000ff\"]\n[Variant \"perudo\"]\n1. 0:1x2 1:perudo! reveal 0=2 1=3\n\n; A comment\n2. 1:1x4 0:perudo! reveal 0=5 1=4\n[Game \"00000100\"]\n1. 0:1x6 1:perudo! reveal 0=6 1=1";
+            let games = GameNotation::<PerudoBet>::parse_all(text).unwrap();
+            assert_eq!(2, games.len());
+            assert_eq!(Some(255), games[0].game_id);
+            assert_eq!(2, games[0].rounds.len());
+            assert_eq!(text.lines().take(3).collect::<Vec<&str>>().join("\n"), format!("{}", GameNotation { game_id: games[0].game_id, rounds: games[0].rounds[..1].to_vec() }));
+            assert!(GameNotation::<ScrabrudoBet>::parse_all(text).is_err());
+        }
+
+        it "reads a round back as a position" {
+            let round = RoundNotation::<PerudoBet>::parse("4. 1:2x3 0:3x3 1:perudo! reveal 0=31 1=63").unwrap();
+            let state = round.position(&GameConfig::default()).unwrap();
+            assert_eq!(1, state.current_index);
+            assert_eq!(4, state.total_num_items);
+            assert_eq!(Some(&PerudoBet { value: Die::Three, quantity: 3 }), state.standing_bet());
+            assert_eq!(vec![vec![Die::Three, Die::One], vec![Die::Six, Die::Three]], state.hands);
+        }
+
+        it "notates a round from a game" {
+            let game = PerudoGame::new(2, 2, hashset!{}, crate::opening::OpeningPolicy::Likeliest, GameConfig::default())
+                .apply(bet(1, Die::Two))
+                .unwrap();
+            let round = RoundNotation::from_game(&game, 1, &TurnOutcome::Perudo);
+            assert_eq!(vec![(0, bet(1, Die::Two)), (1, TurnOutcome::Perudo)], round.moves);
+            assert_eq!(game.players()[1].items(), &round.hands[1].1);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+/// Notifications of key moments in a game, so that frontends can alert their players.
+#[cfg(test)]
+use speculate::speculate;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// Something a player may want to be alerted to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    /// It's a human player's turn.
+    YourTurn { player_id: usize },
+
+    /// A player challenged the last bet, calling Palafico if not Perudo.
+    ChallengeCalled { player_id: usize, palafico: bool },
+
+    /// A player lost an item, and is knocked out if they have none left.
+    LostItem {
+        player_id: usize,
+        human: bool,
+        items_left: usize,
+    },
+
+    /// Two neighbours swapped a hidden item each before the round.
+    Traded { player_ids: (usize, usize) },
+
+    /// The game is over, with the IDs of the players from first place to last.
+    GameFinished { standings: Vec<usize> },
+}
+
+/// A function called with every notification.
+/// Callbacks are called while the registry is locked, so mustn't register or notify themselves.
+pub type Callback = Box<dyn Fn(&Notification) + Send>;
+
+lazy_static! {
+    static ref CALLBACKS: Mutex<Vec<Callback>> = Mutex::new(vec![]);
+}
+
+/// Calls the given callback with every notification from now on.
+pub fn register(callback: Callback) {
+    CALLBACKS.lock().unwrap().push(callback);
+}
+
+/// Passes the notification to every registered callback.
+pub fn notify(notification: &Notification) {
+    debug!("Notifying {:?}", notification);
+    for callback in CALLBACKS.lock().unwrap().iter() {
+        callback(notification);
+    }
+}
+
+/// Rings the terminal bell when it's a human's turn, on a challenge, and when a human loses an
+/// item.
+pub fn bell(notification: &Notification) {
+    let ring = match notification {
+        Notification::YourTurn { .. } | Notification::ChallengeCalled { .. } => true,
+        Notification::LostItem { human, .. } => *human,
+        Notification::Traded { .. } | Notification::GameFinished { .. } => false,
+    };
+    if ring {
+        // The bell is a nicety, so failing to ring it isn't worth stopping the game for.
+        let mut stderr = io::stderr();
+        let _ = stderr.write_all(b"\x07").and_then(|_| stderr.flush());
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use std::sync::Arc;
+
+    describe "notifications" {
+        it "passes notifications to registered callbacks" {
+            // Other tests notify too, so only look at a player no game has.
+            let recorded = Arc::new(Mutex::new(vec![]));
+            let recorder = recorded.clone();
+            register(Box::new(move |n| {
+                if let Notification::YourTurn { player_id: 999 } = n {
+                    recorder.lock().unwrap().push(n.clone());
+                }
+            }));
+            notify(&Notification::YourTurn { player_id: 999 });
+            notify(&Notification::YourTurn { player_id: 998 });
+            assert_eq!(vec![Notification::YourTurn { player_id: 999 }], *recorded.lock().unwrap());
+        }
+    }
+}
@@ -0,0 +1,144 @@
+/// Opening book: policies for choosing the first bet of a round.
+use crate::bet::*;
+use crate::config::*;
+use crate::rng::*;
+use crate::tie_break::*;
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+
+/// How many of the likeliest bets the information-hiding policy chooses between.
+pub const HIDDEN_TOP_K: usize = 5;
+
+/// The lowest probability the bluffing policy will still open with.
+pub const BLUFF_MIN_PROB: f64 = 0.5;
+
+/// A policy for picking the opening bet from all bets ranked by probability.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OpeningPolicy {
+    /// One of the likeliest bets.
+    Likeliest,
+
+    /// The lowest of the likeliest bets, leaving the most room for the rest of the round.
+    Safe,
+
+    /// A random bet from the top k by probability, so as not to give away the hand.
+    Hidden(usize),
+
+    /// The highest bet that is still at least this likely.
+    Bluff(f64),
+}
+
+impl OpeningPolicy {
+    /// Parses a policy from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "likeliest" => Some(OpeningPolicy::Likeliest),
+            "safe" => Some(OpeningPolicy::Safe),
+            "hidden" => Some(OpeningPolicy::Hidden(HIDDEN_TOP_K)),
+            "bluff" => Some(OpeningPolicy::Bluff(BLUFF_MIN_PROB)),
+            _ => None,
+        }
+    }
+
+    /// Chooses an opening bet from bets ranked by ascending probability, preferring words in the
+    /// given style among the likeliest, and breaking ties between them as given. Gives None if
+    /// there are no bets to choose from, as when the round's constraint rules them all out.
+    pub fn choose<B: Bet>(
+        &self,
+        ranked: Vec<(Box<B>, f64)>,
+        style: &WordStyle,
+        tie_break: &TieBreak,
+    ) -> Option<Box<B>> {
+        match self {
+            OpeningPolicy::Likeliest => {
+                tie_break.pick_bet(style.best_outcomes(ranked, |b| b.word_rank()))
+            }
+            OpeningPolicy::Safe => style
+                .best_outcomes(ranked, |b| b.word_rank())
+                .into_iter()
+                .min(),
+            OpeningPolicy::Hidden(k) => {
+                let skip = ranked.len().saturating_sub(*k);
+                let top_k = ranked
+                    .into_iter()
+                    .skip(skip)
+                    .map(|b| b.0)
+                    .collect::<Vec<Box<B>>>();
+                let mut rng = rng_for("opening");
+                top_k.choose(&mut rng).cloned()
+            }
+            OpeningPolicy::Bluff(min_prob) => {
+                match ranked
+                    .iter()
+                    .filter(|b| b.1 >= *min_prob)
+                    .map(|b| &b.0)
+                    .max()
+                {
+                    Some(bet) => Some(bet.clone()),
+                    None => B::best_bet_from(ranked, tie_break),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::*;
+    use crate::prob::*;
+
+    describe "opening policies" {
+        fn bet(v: Die, q: usize) -> Box<PerudoBet> {
+            Box::new(PerudoBet {
+                value: v,
+                quantity: q,
+            })
+        }
+
+        fn ranked() -> Vec<(Box<PerudoBet>, f64)> {
+            rank_outcomes(vec![
+                (bet(Die::Six, 4), 0.1),
+                (bet(Die::Four, 3), 0.6),
+                (bet(Die::Three, 2), 0.9),
+                (bet(Die::Two, 1), 1.0),
+                (bet(Die::Five, 1), 1.0),
+            ])
+        }
+
+        it "parses policy names" {
+            assert_eq!(Some(OpeningPolicy::Safe), OpeningPolicy::from_name("safe"));
+            assert_eq!(Some(OpeningPolicy::Hidden(HIDDEN_TOP_K)), OpeningPolicy::from_name("hidden"));
+            assert_eq!(None, OpeningPolicy::from_name("reckless"));
+        }
+
+        it "picks a likeliest bet" {
+            let chosen = OpeningPolicy::Likeliest.choose(ranked(), &WordStyle::Scrabbly, &TieBreak::Random).unwrap();
+            assert!(*chosen == *bet(Die::Two, 1) || *chosen == *bet(Die::Five, 1));
+        }
+
+        it "picks the lowest of the likeliest bets when safe" {
+            assert_eq!(Some(bet(Die::Two, 1)), OpeningPolicy::Safe.choose(ranked(), &WordStyle::Scrabbly, &TieBreak::Random));
+        }
+
+        it "picks from the top k when hiding" {
+            for _ in 0..20 {
+                let chosen = OpeningPolicy::Hidden(3).choose(ranked(), &WordStyle::Scrabbly, &TieBreak::Random).unwrap();
+                assert!(*chosen != *bet(Die::Six, 4) && *chosen != *bet(Die::Four, 3));
+            }
+        }
+
+        it "picks the highest plausible bet when bluffing" {
+            assert_eq!(Some(bet(Die::Four, 3)), OpeningPolicy::Bluff(0.5).choose(ranked(), &WordStyle::Scrabbly, &TieBreak::Random));
+            assert_eq!(Some(bet(Die::Six, 4)), OpeningPolicy::Bluff(0.0).choose(ranked(), &WordStyle::Scrabbly, &TieBreak::Random));
+        }
+
+        it "chooses nothing when there is nothing to choose from" {
+            for policy in &[OpeningPolicy::Likeliest, OpeningPolicy::Safe, OpeningPolicy::Hidden(3), OpeningPolicy::Bluff(0.5)] {
+                assert_eq!(None, policy.choose::<PerudoBet>(vec![], &WordStyle::Common, &TieBreak::Canonical));
+            }
+        }
+    }
+}
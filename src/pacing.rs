@@ -0,0 +1,85 @@
+/// Pacing: pauses after each AI move and each deal, so that a game played or served to humans
+/// doesn't flash by faster than they can follow. A policy decides how long each pause lasts, and
+/// without one there are none.
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+
+/// A moment in the game that those following it may need time to take in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Moment {
+    /// A player has chosen their move, which is about to be played.
+    Moved { player_id: usize, human: bool },
+
+    /// The hands for a round have been dealt.
+    Dealt { round: usize },
+}
+
+/// How long to pause after each moment.
+pub type Policy = Box<dyn Fn(&Moment) -> Duration + Send + Sync>;
+
+lazy_static! {
+    static ref POLICY: RwLock<Option<Policy>> = RwLock::new(None);
+}
+
+/// Paces every game from now on by the given policy.
+pub fn register(policy: Policy) {
+    *POLICY.write().unwrap() = Some(policy);
+}
+
+/// Pauses after the given moment for as long as the policy says, saying so while dealing.
+pub fn pace(moment: &Moment) {
+    let pause = match POLICY.read().unwrap().as_ref() {
+        Some(policy) => policy(moment),
+        None => return,
+    };
+    if pause == Duration::from_millis(0) {
+        return;
+    }
+    if let Moment::Dealt { .. } = moment {
+        info!("Dealing...");
+    }
+    thread::sleep(pause);
+}
+
+/// The usual policy, pausing for the given times after each AI move and after each deal. Humans
+/// have taken their own time over their moves, so aren't kept waiting after them.
+pub fn delays(turn: Duration, deal: Duration) -> Policy {
+    Box::new(move |moment| match moment {
+        Moment::Moved { human: true, .. } => Duration::from_millis(0),
+        Moment::Moved { .. } => turn,
+        Moment::Dealt { .. } => deal,
+    })
+}
+
+/// Parses the pauses after each AI move and each deal from their command-line form, e.g. "800"
+/// or "800:1500" in milliseconds. Without a pause given for deals, they pause as long as moves.
+pub fn parse_pace(pace: &str) -> Option<(Duration, Duration)> {
+    let mut split = pace.splitn(2, ':');
+    let turn = split.next()?.parse::<u64>().ok()?;
+    let deal = match split.next() {
+        Some(deal) => deal.parse::<u64>().ok()?,
+        None => turn,
+    };
+    Some((Duration::from_millis(turn), Duration::from_millis(deal)))
+}
+
+#[cfg(test)]
+speculate! {
+    describe "pacing" {
+        it "pauses after AI moves and deals" {
+            let policy = delays(Duration::from_millis(800), Duration::from_millis(1500));
+            assert_eq!(Duration::from_millis(800), policy(&Moment::Moved { player_id: 1, human: false }));
+            assert_eq!(Duration::from_millis(0), policy(&Moment::Moved { player_id: 0, human: true }));
+            assert_eq!(Duration::from_millis(1500), policy(&Moment::Dealt { round: 2 }));
+        }
+
+        it "parses pauses" {
+            assert_eq!(Some((Duration::from_millis(800), Duration::from_millis(800))), parse_pace("800"));
+            assert_eq!(Some((Duration::from_millis(800), Duration::from_millis(1500))), parse_pace("800:1500"));
+            assert_eq!(None, parse_pace("slow"));
+        }
+    }
+}
@@ -1,24 +1,48 @@
 /// Player definitions and human/CPU behaviour.
+use crate::any_raise::*;
+use crate::belief::*;
 use crate::bet::*;
-use crate::dict;
-use crate::dict::*;
+use crate::blitz;
+use crate::blitz::*;
+use crate::color::*;
+use crate::config::*;
+use crate::deduction::*;
 use crate::die::*;
+use crate::display::*;
+use crate::error::*;
+use crate::explain;
+use crate::explain::*;
 use crate::game::*;
 use crate::hand::*;
+use crate::input::*;
+use crate::lang::*;
+use crate::learned;
+use crate::logging;
+use crate::mcts;
+use crate::multiset::*;
+use crate::opening::*;
+use crate::prob::*;
+use crate::risk::*;
+use crate::rng::*;
+use crate::sampler::*;
+use crate::symbol::*;
 use crate::testing;
+use crate::tie_break::*;
 use crate::tile::*;
+use crate::trade::*;
 
 use rand::seq::SliceRandom;
-use rand::thread_rng;
 use rand::Rng;
 use speculate::speculate;
-use std::cmp::Ord;
+use std::cmp::{Ord, Reverse};
 use std::collections::HashMap;
 use std::fmt;
-use std::io;
+
+/// How much likelier exchanging must make a bet before the AI gives up challenging for it.
+pub const EXCHANGE_GAIN: f64 = 0.1;
 
 /// Common behaviour for players of any ruleset.
-pub trait Player: fmt::Debug + fmt::Display {
+pub trait Player: fmt::Debug + fmt::Display + Send + Sync {
     /// The type of thing this player holds.
     type V: Holdable;
 
@@ -39,6 +63,9 @@ pub trait Player: fmt::Debug + fmt::Display {
     /// Is the player human?
     fn human(&self) -> bool;
 
+    /// The policy this player uses to open a round.
+    fn opening(&self) -> &OpeningPolicy;
+
     /// The player's hand.
     fn hand(&self) -> &Hand<Self::V>;
 
@@ -49,7 +76,9 @@ pub trait Player: fmt::Debug + fmt::Display {
     fn items(&self) -> &Vec<Self::V>;
 
     /// The total number of dice with the given explicit value (no wildcards).
-    fn num_items_with(&self, val: Self::V) -> usize;
+    fn num_items_with(&self, val: Self::V) -> usize {
+        Multiset::of(self.items()).count(&val)
+    }
 
     /// Gets the actual number of dice around the table, allowing for wildcards.
     fn num_logical_items(&self, val: Self::V) -> usize;
@@ -59,7 +88,10 @@ pub trait Player: fmt::Debug + fmt::Display {
         self.copy_with(
             None,
             None,
-            Some(Hand::<Self::V>::new(self.num_items() as u32 - 1)),
+            Some(Hand::<Self::V>::new_revealing(
+                self.num_items() as u32 - 1,
+                self.hand().revealed,
+            )),
         )
     }
 
@@ -68,16 +100,36 @@ pub trait Player: fmt::Debug + fmt::Display {
         self.copy_with(
             None,
             None,
-            Some(Hand::<Self::V>::new(self.num_items() as u32 + 1)),
+            Some(Hand::<Self::V>::new_revealing(
+                self.num_items() as u32 + 1,
+                self.hand().revealed,
+            )),
+        )
+    }
+
+    /// A fresh instance of player with a new hand, showing one more item than before.
+    fn revealing_one(&self) -> Box<dyn Player<B = Self::B, V = Self::V>> {
+        self.copy_with(
+            None,
+            None,
+            Some(Hand::<Self::V>::new_revealing(
+                self.num_items() as u32,
+                self.hand().revealed + 1,
+            )),
         )
     }
 
     /// A fresh instance of player with a new hand.
+    /// Hands are dealt afresh each round, so a player who has shown items shows the first of
+    /// their new hand instead.
     fn refresh(&self) -> Box<Player<B = Self::B, V = Self::V>> {
         self.copy_with(
             None,
             None,
-            Some(Hand::<Self::V>::new(self.num_items() as u32)),
+            Some(Hand::<Self::V>::new_revealing(
+                self.num_items() as u32,
+                self.hand().revealed,
+            )),
         )
     }
 
@@ -92,52 +144,348 @@ pub trait Player: fmt::Debug + fmt::Display {
         state: &GameState<Self::B>,
         bet: &Self::B,
     ) -> TurnOutcome<Self::B> {
-        // Create pairs of all possible outcomes sorted by probability.
-        let mut outcomes = vec![
-            (
-                TurnOutcome::Perudo,
-                bet.prob(state, ProbVariant::Perudo, self.cloned()),
-            ),
-            (
-                TurnOutcome::Palafico,
-                bet.prob(state, ProbVariant::Palafico, self.cloned()),
-            ),
-        ];
+        self.best_outcome_above_within(state, bet, &Deadline::none())
+    }
+
+    /// Gets the best turn outcome above a certain bet of those there was time to weigh up by the
+    /// deadline.
+    fn best_outcome_above_within(
+        &self,
+        state: &GameState<Self::B>,
+        bet: &Self::B,
+        deadline: &Deadline,
+    ) -> TurnOutcome<Self::B> {
+        choose_outcome(
+            &state.config.style,
+            &tie_break(&state.config, self.id()),
+            self.ranked_outcomes_above_within(state, bet, deadline),
+        )
+    }
+
+    /// Ranks the turn outcomes above a certain bet, lowest first, of those there was time to
+    /// weigh up by the deadline.
+    fn ranked_outcomes_above_within(
+        &self,
+        state: &GameState<Self::B>,
+        bet: &Self::B,
+        deadline: &Deadline,
+    ) -> Vec<(TurnOutcome<Self::B>, f64)> {
+        // Raises are weighed up likeliest-looking first, so that those which can't beat the best
+        // so far by the lookup's reckoning need never be worked out.
+        let mut outcomes = self.challenge_outcomes(state, bet);
+        // A raise already covered by what the player can see is certain, so nothing that can't
+        // be certain too need be weighed up.
+        let guaranteed = self
+            .best_guaranteed_bet(state)
+            .filter(|g| g > bet && g.check_raise(bet, &state.config.min_raise).is_ok());
+        let floor = outcomes
+            .iter()
+            .map(|o| o.1)
+            .fold(guaranteed.as_ref().map_or(0.0, |_| 1.0), f64::max);
+        let mut seen = self.items().clone();
+        seen.extend(state.known_items());
+        let mut candidates = bet
+            .all_above(state)
+            .map(|b| {
+                let bound = b.prob_upper_bound(state, self.cloned());
+                (b, bound)
+            })
+            .collect::<Vec<(Box<Self::B>, f64)>>();
+        candidates.sort_by_key(|(b, _)| raise_order(&**b, &seen));
+        // Should no raise be likely to be right at all, there's nothing to do but challenge, so
+        // none need be weighed up.
+        if guaranteed.is_none() && !outcomes.is_empty() {
+            let raises = candidates
+                .iter()
+                .map(|(b, _)| b.clone())
+                .collect::<Vec<Box<Self::B>>>();
+            let any_raise = estimate_any_raise(
+                state,
+                self.items(),
+                Some(&belief(&state.config, self.id())),
+                &raises,
+                NUM_RAISE_SAMPLES,
+            );
+            if is_forced(any_raise) {
+                return rank_outcomes(outcomes);
+            }
+        }
+        let ranking = BoundedRanking::new(
+            candidates,
+            |b| b.prob(state, ProbVariant::Bet, self.cloned()),
+            floor,
+            state.config.style.tolerance(),
+        );
         outcomes.extend(
-            bet.all_above(state)
+            search_until(ranking, deadline)
                 .into_iter()
-                .map(|b| {
-                    (
-                        TurnOutcome::Bet(*b.clone()),
-                        b.prob(state, ProbVariant::Bet, self.cloned()),
-                    )
-                })
-                .collect::<Vec<(TurnOutcome<Self::B>, f64)>>(),
+                .map(|(b, p)| (TurnOutcome::Bet(*b), p)),
         );
-        outcomes.sort_by(|a, b| ((a.1 * 1000000.0) as u64).cmp(&((b.1 * 1000000.0) as u64)));
-        let best_p = outcomes[outcomes.len() - 1].1;
-        let best_outcomes = outcomes
+        // Even out of time, the certain raise is there to be made.
+        if let Some(guaranteed) = guaranteed.map(TurnOutcome::Bet) {
+            if !outcomes.iter().any(|(o, _)| *o == guaranteed) {
+                outcomes.push((guaranteed, 1.0));
+            }
+        }
+        rank_outcomes(outcomes)
+    }
+
+    /// The highest bet that may be made next which the player's own items, those face up and
+    /// those they take on trust already make right, if any, found without working out any
+    /// probabilities.
+    fn best_guaranteed_bet(&self, state: &GameState<Self::B>) -> Option<Self::B> {
+        Self::B::guaranteed_bets(state, self.cloned())
             .into_iter()
-            .filter(|a| a.1 == best_p)
-            .map(|a| a.0)
-            .collect::<Vec<TurnOutcome<Self::B>>>();
-        let mut rng = thread_rng();
-        best_outcomes.choose(&mut rng).unwrap().clone()
+            .max()
+            .map(|b| *b)
+    }
+
+    /// The chance that any raise the rules allow above a certain bet is right, as the player might
+    /// estimate it by dealing the hands they can't see.
+    fn any_raise_prob(&self, state: &GameState<Self::B>, bet: &Self::B) -> f64 {
+        let mut seen = self.items().clone();
+        seen.extend(state.known_items());
+        let mut raises = bet.all_above(state).collect::<Vec<Box<Self::B>>>();
+        raises.sort_by_key(|b| raise_order(&**b, &seen));
+        estimate_any_raise(
+            state,
+            self.items(),
+            Some(&belief(&state.config, self.id())),
+            &raises,
+            NUM_RAISE_SAMPLES,
+        )
     }
 
-    /// Given the game state, return this player's chosen outcome.
+    /// Pairs every outcome open to the player above a certain bet with its probability.
+    /// Challenges are weighted by what is at stake should they succeed or fail, and are always
+    /// weighed up; bets only until the deadline.
+    fn outcomes_above(
+        &self,
+        state: &GameState<Self::B>,
+        bet: &Self::B,
+        deadline: &Deadline,
+    ) -> Vec<(TurnOutcome<Self::B>, f64)> {
+        let mut outcomes = self.challenge_outcomes(state, bet);
+        let ranking = Ranking::new(bet.all_above(state), |b| {
+            b.prob(state, ProbVariant::Bet, self.cloned())
+        });
+        outcomes.extend(
+            search_until(ranking, deadline)
+                .into_iter()
+                .map(|(b, p)| (TurnOutcome::Bet(*b), p)),
+        );
+        outcomes
+    }
+
+    /// Pairs the challenges open to the player against a certain bet with their odds, weighted
+    /// by what is at stake should they succeed or fail.
+    fn challenge_outcomes(
+        &self,
+        state: &GameState<Self::B>,
+        bet: &Self::B,
+    ) -> Vec<(TurnOutcome<Self::B>, f64)> {
+        let risk = Risk::new(state);
+        if !may_challenge(state, self.hand(), bet) {
+            vec![]
+        } else {
+            vec![
+                (
+                    TurnOutcome::Perudo,
+                    risk.perudo_odds(bet.prob(state, ProbVariant::Perudo, self.cloned())),
+                ),
+                (
+                    TurnOutcome::Palafico,
+                    risk.palafico_odds(bet.prob(state, ProbVariant::Palafico, self.cloned())),
+                ),
+            ]
+        }
+    }
+
+    /// Chooses an outcome with the given learned policy, or None if there is nothing to choose
+    /// between.
+    fn learned_outcome(
+        &self,
+        state: &GameState<Self::B>,
+        current_outcome: &TurnOutcome<Self::B>,
+        policy: &learned::LearnedPolicy,
+        deadline: &Deadline,
+    ) -> Option<TurnOutcome<Self::B>> {
+        let candidates = match current_outcome {
+            TurnOutcome::First => Self::B::ranked_bets_within(state, self.cloned(), deadline)
+                .into_iter()
+                .map(|(b, p)| (TurnOutcome::Bet(*b), p))
+                .collect(),
+            TurnOutcome::Bet(bet) => self.outcomes_above(state, bet, deadline),
+            _ => return None,
+        };
+        policy.choose(state, candidates, self.num_items())
+    }
+
+    /// The hidden items worth exchanging before making the given bet, if exchanges are allowed
+    /// and swapping the items that do nothing for it makes it likelier by enough to be worth
+    /// giving up challenging for the rest of the round.
+    fn exchange_for(&self, state: &GameState<Self::B>, bet: &Self::B) -> Option<Vec<Self::V>> {
+        let hand = self.hand();
+        if !can_exchange(&state.config, hand) {
+            return None;
+        }
+        let revealed = hand.revealed.min(hand.items.len());
+        let dead = bet.dead_items(&hand.items[revealed..]);
+        if dead.is_empty() {
+            return None;
+        }
+
+        // Once swapped, the dead items are as good as any other we can't see.
+        let mut kept = hand.items.clone();
+        for item in &dead {
+            let i = revealed + kept[revealed..].iter().position(|k| k == item).unwrap();
+            kept.remove(i);
+        }
+        let kept_hand = Hand {
+            items: kept,
+            revealed: hand.revealed,
+            exchanged: true,
+        };
+        let p_now = bet.prob(state, ProbVariant::Bet, self.cloned());
+        let p_exchanged = bet.prob(
+            state,
+            ProbVariant::Bet,
+            self.copy_with(None, None, Some(kept_hand)),
+        );
+        debug!(
+            "Exchanging {:?} takes {} from {} to {}",
+            dead, bet, p_now, p_exchanged
+        );
+        if p_exchanged - p_now >= EXCHANGE_GAIN {
+            Some(dead)
+        } else {
+            None
+        }
+    }
+
+    /// Given the game as this player sees it, return their chosen outcome.
     fn play(
         &self,
         state: &GameState<Self::B>,
         current_outcome: &TurnOutcome<Self::B>,
-    ) -> TurnOutcome<Self::B> {
+    ) -> Result<TurnOutcome<Self::B>, ScrabrudoError> {
+        // Players see only their own hand.
+        debug_assert!(state.is_view_for(state.current_index));
         if self.human() {
             return self.human_play(state, current_outcome);
         }
-        match current_outcome {
-            TurnOutcome::First => TurnOutcome::Bet(*Self::B::best_first_bet(state, self.cloned())),
-            TurnOutcome::Bet(current_bet) => self.best_outcome_above(state, current_bet),
-            _ => panic!(),
+        let deadline = blitz::deadline(&state.config, self.id());
+        let explaining = explain::explaining();
+        // How likely it is that any raise is right, for coaching, however the move is chosen.
+        let any_raise = match current_outcome {
+            TurnOutcome::Bet(bet) if explaining => Some(self.any_raise_prob(state, bet)),
+            _ => None,
+        };
+        if let Some(budget) = mcts::budget(&state.config, self.id()) {
+            let searched = mcts::search(state, self.cloned(), current_outcome, &budget, &deadline);
+            if let Some(outcome) = searched {
+                self.explain(state, "mcts", &outcome, vec![], vec![], any_raise)?;
+                return Ok(outcome);
+            }
+        }
+        if let Some(policy) = learned::policy(&state.config, self.id()) {
+            let chosen = self.learned_outcome(state, current_outcome, &policy, &deadline);
+            if let Some(outcome) = chosen {
+                self.explain(state, "learned", &outcome, vec![], vec![], any_raise)?;
+                return Ok(outcome);
+            }
+        }
+        let (outcome, candidates, pruned) = match current_outcome {
+            TurnOutcome::First => {
+                let ranked = Self::B::ranked_first_bets_within(state, self.cloned(), &deadline);
+                let candidates = if explaining {
+                    let best = &ranked[ranked.len().saturating_sub(explain::NUM_CANDIDATES)..];
+                    explain::top_candidates(
+                        &best
+                            .iter()
+                            .map(|(b, p)| (TurnOutcome::Bet((**b).clone()), *p))
+                            .collect::<Vec<(TurnOutcome<Self::B>, f64)>>(),
+                    )
+                } else {
+                    vec![]
+                };
+                let bet = Self::B::opening_bet_from(state, ranked, self.opening(), self.id());
+                (TurnOutcome::Bet(*bet), candidates, vec![])
+            }
+            TurnOutcome::Bet(current_bet) => {
+                let ranked = self.ranked_outcomes_above_within(state, current_bet, &deadline);
+                let (candidates, pruned) = if explaining {
+                    let num_weighed = ranked
+                        .iter()
+                        .filter(|(o, _)| matches!(o, TurnOutcome::Bet(_)))
+                        .count();
+                    let mut pruned = explain::pruned_raises(state, current_bet, num_weighed);
+                    if !may_challenge(state, self.hand(), current_bet) {
+                        pruned.push("challenging, having exchanged this round".into());
+                    } else if any_raise.is_some_and(is_forced) {
+                        pruned.push("raising, as no raise is likely to be right".into());
+                    }
+                    (explain::top_candidates(&ranked), pruned)
+                } else {
+                    (vec![], vec![])
+                };
+                let outcome = choose_outcome(
+                    &state.config.style,
+                    &tie_break(&state.config, self.id()),
+                    ranked,
+                );
+                (outcome, candidates, pruned)
+            }
+            outcome => {
+                return Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome)));
+            }
+        };
+        let exchange = match &outcome {
+            TurnOutcome::Bet(bet) => self.exchange_for(state, bet),
+            _ => None,
+        };
+        let outcome = match exchange {
+            Some(items) => TurnOutcome::Exchange(items),
+            None => outcome,
+        };
+        self.explain(state, "greedy", &outcome, candidates, pruned, any_raise)?;
+        Ok(outcome)
+    }
+
+    /// Explains the given move, if AI moves are being explained.
+    fn explain(
+        &self,
+        state: &GameState<Self::B>,
+        method: &str,
+        outcome: &TurnOutcome<Self::B>,
+        candidates: Vec<(String, f64)>,
+        pruned: Vec<String>,
+        any_raise: Option<f64>,
+    ) -> Result<(), ScrabrudoError> {
+        if !explain::explaining() {
+            return Ok(());
+        }
+        explain::emit(&ExplainedDecision::new(
+            &state.config,
+            self.id(),
+            method,
+            outcome,
+            candidates,
+            pruned,
+            any_raise,
+        ))
+    }
+
+    /// What the player chooses in the trading phase before a round's first bet, the AI choosing
+    /// for a human away from the table.
+    fn trade(&self, config: &GameConfig) -> Result<TradeAction<Self::V>, ScrabrudoError> {
+        if self.human() && config.input.presence(self.id()) == Presence::Here {
+            match human_trade(config, self.id(), self.hand()) {
+                Err(ScrabrudoError::Disconnected(_)) => Ok(ai_trade::<Self::B>(self.hand())),
+                result => result,
+            }
+        } else {
+            Ok(ai_trade::<Self::B>(self.hand()))
         }
     }
 
@@ -146,13 +494,99 @@ pub trait Player: fmt::Debug + fmt::Display {
         &self,
         state: &GameState<Self::B>,
         current_outcome: &TurnOutcome<Self::B>,
-    ) -> TurnOutcome<Self::B>;
+    ) -> Result<TurnOutcome<Self::B>, ScrabrudoError>;
+}
+
+/// Chooses among the best of the given outcomes, ranked lowest first, as the table's word style
+/// prefers, breaking ties as given.
+fn choose_outcome<B: Bet>(
+    style: &WordStyle,
+    tie_break: &TieBreak,
+    ranked: Vec<(TurnOutcome<B>, f64)>,
+) -> TurnOutcome<B> {
+    let word_rank = |o: &TurnOutcome<B>| match o {
+        TurnOutcome::Bet(b) => b.word_rank(),
+        _ => None,
+    };
+    tie_break
+        .pick_outcome(style.best_outcomes(ranked, word_rank))
+        .unwrap_or(TurnOutcome::Perudo)
+}
+
+/// Makes the given challenge, so long as there is a bet to challenge and the player holding the
+/// given hand may.
+fn challenge<B: Bet>(
+    state: &GameState<B>,
+    hand: &Hand<B::V>,
+    current_outcome: &TurnOutcome<B>,
+    challenge: TurnOutcome<B>,
+) -> Result<TurnOutcome<B>, ScrabrudoError> {
+    match current_outcome {
+        TurnOutcome::Bet(bet) if !may_challenge(state, hand, bet) => {
+            Err(ScrabrudoError::NoRightToChallenge)
+        }
+        TurnOutcome::Bet(_) => Ok(challenge),
+        _ => Err(ScrabrudoError::NoBetToChallenge),
+    }
+}
+
+/// Whether the player holding the given hand may challenge the given bet.
+/// Exchanging gives up the right for the round, unless there is no higher bet left to make.
+pub fn may_challenge<B: Bet>(state: &GameState<B>, hand: &Hand<B::V>, bet: &B) -> bool {
+    !hand.exchanged || bet.all_above(state).next().is_none()
+}
+
+/// The order in which to weigh up a raise: those the items seen go furthest towards first, then
+/// the most common words, then the smallest.
+pub fn raise_order<B: Bet>(bet: &B, seen: &[B::V]) -> (Reverse<usize>, usize, usize) {
+    let claimed = Multiset::of(&bet.claimed_items());
+    let covered = claimed.len() - claimed.difference(&Multiset::of(seen)).len();
+    (
+        Reverse(covered),
+        bet.word_rank().unwrap_or(usize::MAX),
+        bet.size(),
+    )
+}
+
+/// Whether the player holding the given hand may exchange items now.
+pub fn can_exchange<V: Holdable>(config: &GameConfig, hand: &Hand<V>) -> bool {
+    config.exchanges && !hand.exchanged
+}
+
+/// The given hand with the given items exchanged, giving the reason if they can't be.
+pub fn exchanged_hand<V: Holdable>(
+    config: &GameConfig,
+    hand: &Hand<V>,
+    items: &[V],
+) -> Result<Hand<V>, ScrabrudoError> {
+    if !config.exchanges {
+        return Err(ScrabrudoError::InvalidExchange(
+            Msg::ExchangesNotAllowed.to_string(),
+        ));
+    }
+    if hand.exchanged {
+        return Err(ScrabrudoError::InvalidExchange(
+            Msg::AlreadyExchanged.to_string(),
+        ));
+    }
+    if items.is_empty() {
+        return Err(ScrabrudoError::InvalidExchange(
+            Msg::NothingToExchange.to_string(),
+        ));
+    }
+    match hand.exchanging(items) {
+        Some(hand) => Ok(hand),
+        None => Err(ScrabrudoError::InvalidExchange(
+            Msg::NotHeldFaceDown(format!("{:?}", items)).to_string(),
+        )),
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PerudoPlayer {
     pub id: usize,
     pub human: bool,
+    pub opening: OpeningPolicy,
     pub hand: Hand<Die>,
 }
 
@@ -198,6 +632,7 @@ impl Player for PerudoPlayer {
                 Some(human) => human,
                 None => self.human(),
             },
+            opening: self.opening.clone(),
             hand: match hand {
                 Some(hand) => hand,
                 None => self.hand().clone(),
@@ -213,6 +648,10 @@ impl Player for PerudoPlayer {
         self.human
     }
 
+    fn opening(&self) -> &OpeningPolicy {
+        &self.opening
+    }
+
     fn hand(&self) -> &Hand<Self::V> {
         &self.hand
     }
@@ -225,13 +664,6 @@ impl Player for PerudoPlayer {
         &self.hand.items
     }
 
-    fn num_items_with(&self, val: Die) -> usize {
-        (&self.hand.items)
-            .into_iter()
-            .filter(|&d| d == &val)
-            .count()
-    }
-
     fn num_logical_items(&self, val: Die) -> usize {
         if val == Die::One {
             self.num_items_with(Die::One)
@@ -244,30 +676,95 @@ impl Player for PerudoPlayer {
         &self,
         state: &GameState<Self::B>,
         current_outcome: &TurnOutcome<Self::B>,
-    ) -> TurnOutcome<Self::B> {
+    ) -> Result<TurnOutcome<Self::B>, ScrabrudoError> {
+        announce_turn(self.id);
         loop {
             info!(
-                "Dice left: {:?} ({})",
-                state.num_items_per_player, state.total_num_items
+                "{}",
+                Msg::ItemsLeft {
+                    items: Items::Dice,
+                    per_player: format_counts(&state.num_items_per_player),
+                    total: state.total_num_items,
+                }
             );
-            info!("Hand for Player {}", self);
+            show_face_up(state);
+            logging::private(
+                &state.config,
+                self.id,
+                &Msg::HandFor(format_hand(self, self.id, &self.hand.items)).to_string(),
+            );
+            if hints() {
+                show_deductions(state, self);
+            }
             match current_outcome {
-                TurnOutcome::First => info!("Enter bet (2.6=two sixes):"),
-                TurnOutcome::Bet(_) => info!("Enter bet (2.6=two sixes, p=perudo, pal=palafico):"),
-                _ => panic!(),
+                TurnOutcome::First => info!(
+                    "{}",
+                    Msg::BetPrompt {
+                        items: Items::Dice,
+                        first: true,
+                    }
+                ),
+                TurnOutcome::Bet(_) => {
+                    info!("{}", Risk::new(state));
+                    info!(
+                        "{}",
+                        Msg::BetPrompt {
+                            items: Items::Dice,
+                            first: false,
+                        }
+                    )
+                }
+                outcome => {
+                    return Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome)));
+                }
             };
+            if can_exchange(&state.config, &self.hand) {
+                info!("{}", Msg::ExchangePrompt(Items::Dice));
+            }
 
-            let mut line = String::new();
-            io::stdin()
-                .read_line(&mut line)
-                .expect("Failed to read input");
-            let line = line.trim();
+            let line = read_human_line(&state.config, self.id, "")?;
+            let line = line.as_str();
 
-            if line == "p" {
-                return TurnOutcome::Perudo;
+            let requested_challenge = match line {
+                "p" => Some(TurnOutcome::Perudo),
+                "pal" => Some(TurnOutcome::Palafico),
+                _ => None,
+            };
+            if let Some(requested_challenge) = requested_challenge {
+                match challenge(state, &self.hand, current_outcome, requested_challenge) {
+                    Ok(outcome) => return Ok(outcome),
+                    Err(e) => {
+                        info!("{}", e);
+                        continue;
+                    }
+                }
+            }
+            if line == "quit" {
+                return Ok(TurnOutcome::Quit);
+            }
+            if let Some(values) = line.strip_prefix("x ") {
+                let dice = values
+                    .split_whitespace()
+                    .map(|v| match v.parse::<usize>() {
+                        Ok(v) => Die::from_usize(v),
+                        Err(_) => Err(ScrabrudoError::InvalidExchange(
+                            Msg::NotADie(v.into()).to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<Die>, ScrabrudoError>>();
+                match dice.and_then(|dice| {
+                    exchanged_hand(&state.config, &self.hand, &dice).map(|_| dice)
+                }) {
+                    Ok(dice) => return Ok(TurnOutcome::Exchange(dice)),
+                    Err(e) => {
+                        info!("{}", e);
+                        continue;
+                    }
+                }
             }
-            if line == "pal" {
-                return TurnOutcome::Palafico;
+            if line == "table" {
+                show_table(state, self);
+                continue;
             }
 
             // Parse input, repeat on error.
@@ -297,20 +794,22 @@ impl Player for PerudoPlayer {
 
             // Either return a valid bet or take input again.
             let bet = PerudoBet {
-                value: Die::from_usize(value),
+                value: match Die::from_usize(value) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        info!("{}", e);
+                        continue;
+                    }
+                },
                 quantity: quantity,
             };
 
-            return match current_outcome {
-                TurnOutcome::First => TurnOutcome::Bet(bet),
-                TurnOutcome::Bet(current_bet) => {
-                    if bet > *current_bet {
-                        return TurnOutcome::Bet(bet);
-                    } else {
-                        continue;
-                    }
+            return match bet.validate(state) {
+                Ok(()) => Ok(TurnOutcome::Bet(bet)),
+                Err(e) => {
+                    info!("{}", e);
+                    continue;
                 }
-                _ => panic!(),
             };
         }
     }
@@ -320,6 +819,7 @@ impl Player for PerudoPlayer {
 pub struct ScrabrudoPlayer {
     pub id: usize,
     pub human: bool,
+    pub opening: OpeningPolicy,
     pub hand: Hand<Tile>,
 }
 
@@ -357,6 +857,7 @@ impl Player for ScrabrudoPlayer {
                 Some(human) => human,
                 None => self.human(),
             },
+            opening: self.opening.clone(),
             hand: match hand {
                 Some(hand) => hand,
                 None => self.hand().clone(),
@@ -372,6 +873,10 @@ impl Player for ScrabrudoPlayer {
         self.human
     }
 
+    fn opening(&self) -> &OpeningPolicy {
+        &self.opening
+    }
+
     fn hand(&self) -> &Hand<Self::V> {
         &self.hand
     }
@@ -384,13 +889,6 @@ impl Player for ScrabrudoPlayer {
         &self.hand.items
     }
 
-    fn num_items_with(&self, val: Tile) -> usize {
-        (&self.hand.items)
-            .into_iter()
-            .filter(|&d| d == &val)
-            .count()
-    }
-
     fn num_logical_items(&self, val: Tile) -> usize {
         // TODO: Update here if we end up having blank tiles.
         self.num_items_with(val)
@@ -400,71 +898,543 @@ impl Player for ScrabrudoPlayer {
         &self,
         state: &GameState<Self::B>,
         current_outcome: &TurnOutcome<Self::B>,
-    ) -> TurnOutcome<Self::B> {
+    ) -> Result<TurnOutcome<Self::B>, ScrabrudoError> {
+        announce_turn(self.id);
+        // The rack is only reordered for display, so it can be shuffled freely.
+        let mut rack = self.hand.items.clone();
+        rack_order().sort(&mut rack);
         loop {
             info!(
-                "Tiles left: {:?} ({})",
-                state.num_items_per_player, state.total_num_items
+                "{}",
+                Msg::ItemsLeft {
+                    items: Items::Tiles,
+                    per_player: format_counts(&state.num_items_per_player),
+                    total: state.total_num_items,
+                }
+            );
+            show_face_up(state);
+            logging::private(
+                &state.config,
+                self.id,
+                &Msg::RackFor {
+                    player_id: self.id,
+                    rack: if accessible() {
+                        describe_rack(&rack)
+                    } else {
+                        format_rack(&rack)
+                    },
+                }
+                .to_string(),
             );
-            info!("Hand for Player {}", self);
+            if tracker() {
+                let num_unseen = state.total_num_items - self.num_items();
+                logging::private(
+                    &state.config,
+                    self.id,
+                    &Msg::LettersRemaining(if accessible() {
+                        describe_tracker(&self.hand.items, num_unseen)
+                    } else {
+                        format_tracker(&self.hand.items, num_unseen)
+                    })
+                    .to_string(),
+                );
+            }
+            if hints() {
+                show_deductions(state, self);
+            }
+            if practice() {
+                show_words(&state.config, self.id, &self.hand.items, 0);
+                if let Some(bet) = self.best_guaranteed_bet(state) {
+                    logging::private(
+                        &state.config,
+                        self.id,
+                        &Msg::CanGuarantee(bet.to_string()).to_string(),
+                    );
+                }
+            }
             match current_outcome {
-                TurnOutcome::First => info!("Enter bet (?word=score):"),
-                TurnOutcome::Bet(_) => info!("Enter bet (*p=perudo, *pal=palafico, ?word=score):"),
-                _ => panic!(),
+                TurnOutcome::First => info!(
+                    "{}",
+                    Msg::BetPrompt {
+                        items: Items::Tiles,
+                        first: true,
+                    }
+                ),
+                TurnOutcome::Bet(_) => {
+                    info!("{}", Risk::new(state));
+                    info!(
+                        "{}",
+                        Msg::BetPrompt {
+                            items: Items::Tiles,
+                            first: false,
+                        }
+                    )
+                }
+                outcome => {
+                    return Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome)));
+                }
             };
+            if can_exchange(&state.config, &self.hand) {
+                info!("{}", Msg::ExchangePrompt(Items::Tiles));
+            }
 
-            let mut line = String::new();
-            io::stdin()
-                .read_line(&mut line)
-                .expect("Failed to read input");
-            let line: String = line.trim().into();
+            let line = read_human_line(&state.config, self.id, "*")?;
 
-            if line == "*p" {
-                return TurnOutcome::Perudo;
+            let requested_challenge = match line.as_str() {
+                "*p" => Some(TurnOutcome::Perudo),
+                "*pal" => Some(TurnOutcome::Palafico),
+                _ => None,
+            };
+            if let Some(requested_challenge) = requested_challenge {
+                match challenge(state, &self.hand, current_outcome, requested_challenge) {
+                    Ok(outcome) => return Ok(outcome),
+                    Err(e) => {
+                        info!("{}", e);
+                        continue;
+                    }
+                }
             }
-            if line == "*pal" {
-                return TurnOutcome::Palafico;
+            if line == "*quit" {
+                return Ok(TurnOutcome::Quit);
             }
-            if &line[0..1] == "?" {
-                let query = &line[1..];
-                info!(
-                    "'{}' scores {}",
-                    query,
-                    ScrabrudoBet::from_word(&query.into()).score()
-                );
+            if let Some(tiles) = line.strip_prefix("*x ") {
+                let tiles = parse_tiles(tiles.trim());
+                match tiles.and_then(|tiles| {
+                    exchanged_hand(&state.config, &self.hand, &tiles).map(|_| tiles)
+                }) {
+                    Ok(tiles) => return Ok(TurnOutcome::Exchange(tiles)),
+                    Err(e) => {
+                        info!("{}", e);
+                        continue;
+                    }
+                }
+            }
+            if line == "*table" {
+                show_table(state, self);
+                continue;
+            }
+            if line == "*shuffle" {
+                rack.shuffle(&mut rng_for("shuffle"));
+                continue;
+            }
+            if line == "*words" || line.starts_with("*words ") {
+                match line["*words".len()..].trim() {
+                    "" => show_words(&state.config, self.id, &self.hand.items, 0),
+                    n => match n.parse::<usize>() {
+                        Ok(num_unknown) => {
+                            show_words(&state.config, self.id, &self.hand.items, num_unknown)
+                        }
+                        Err(_) => info!("{}", Msg::ExpectedUnknownTiles(n.into())),
+                    },
+                }
+                continue;
+            }
+            if let Some(query) = line.strip_prefix('?') {
+                match ScrabrudoBet::parse(query) {
+                    Ok(query) => info!(
+                        "{}",
+                        Msg::Scores {
+                            word: query.to_string(),
+                            score: query.score(),
+                        }
+                    ),
+                    Err(e) => info!("{}", e),
+                }
                 continue;
             }
 
             // Parse input, repeat on error.
             // Either return a valid bet or take input again.
-            let bet = ScrabrudoBet::from_word(&line);
+            let bet = match ScrabrudoBet::parse(&line) {
+                Ok(bet) => bet,
+                Err(e) => {
+                    info!("{}", e);
+                    continue;
+                }
+            };
 
-            return match current_outcome {
-                TurnOutcome::First => {
-                    if !dict::has_word(&line) {
-                        info!("Bet was not in dict");
-                        continue;
-                    } else {
-                        TurnOutcome::Bet(bet)
+            if bet.is_phrase() && !state.config.phrases {
+                info!("{}", Msg::SingleWordOnly);
+                continue;
+            }
+            let dict = state.config.dict.get();
+            if !bet.words().iter().all(|w| dict.has_word(w)) {
+                info!("{}", Msg::NotInDict);
+                continue;
+            }
+            return match bet.validate(state) {
+                Ok(()) => Ok(TurnOutcome::Bet(bet)),
+                Err(e) => {
+                    info!("{}", e);
+                    continue;
+                }
+            };
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EquationPlayer {
+    pub id: usize,
+    pub human: bool,
+    pub opening: OpeningPolicy,
+    pub hand: Hand<Symbol>,
+}
+
+impl PartialEq for EquationPlayer {
+    fn eq(&self, other: &EquationPlayer) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for EquationPlayer {}
+
+impl fmt::Display for EquationPlayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            self.id,
+            (&self.hand.items)
+                .into_iter()
+                .map(|s| s.char().to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+    }
+}
+
+impl Player for EquationPlayer {
+    type V = Symbol;
+    type B = EquationBet;
+
+    fn copy_with(
+        &self,
+        id: Option<usize>,
+        human: Option<bool>,
+        hand: Option<Hand<Self::V>>,
+    ) -> Box<dyn Player<B = EquationBet, V = Symbol>> {
+        Box::new(EquationPlayer {
+            id: match id {
+                Some(id) => id,
+                None => self.id(),
+            },
+            human: match human {
+                Some(human) => human,
+                None => self.human(),
+            },
+            opening: self.opening.clone(),
+            hand: match hand {
+                Some(hand) => hand,
+                None => self.hand().clone(),
+            },
+        })
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn human(&self) -> bool {
+        self.human
+    }
+
+    fn opening(&self) -> &OpeningPolicy {
+        &self.opening
+    }
+
+    fn hand(&self) -> &Hand<Self::V> {
+        &self.hand
+    }
+
+    fn num_items(&self) -> usize {
+        self.hand.items.len()
+    }
+
+    fn items(&self) -> &Vec<Self::V> {
+        &self.hand.items
+    }
+
+    fn num_logical_items(&self, val: Symbol) -> usize {
+        self.num_items_with(val)
+    }
+
+    fn human_play(
+        &self,
+        state: &GameState<Self::B>,
+        current_outcome: &TurnOutcome<Self::B>,
+    ) -> Result<TurnOutcome<Self::B>, ScrabrudoError> {
+        announce_turn(self.id);
+        loop {
+            info!(
+                "{}",
+                Msg::ItemsLeft {
+                    items: Items::Symbols,
+                    per_player: format_counts(&state.num_items_per_player),
+                    total: state.total_num_items,
+                }
+            );
+            show_face_up(state);
+            logging::private(
+                &state.config,
+                self.id,
+                &Msg::HandFor(format_hand(self, self.id, &self.hand.items)).to_string(),
+            );
+            if hints() {
+                show_deductions(state, self);
+            }
+            match current_outcome {
+                TurnOutcome::First => info!(
+                    "{}",
+                    Msg::BetPrompt {
+                        items: Items::Symbols,
+                        first: true,
                     }
+                ),
+                TurnOutcome::Bet(_) => {
+                    info!("{}", Risk::new(state));
+                    info!(
+                        "{}",
+                        Msg::BetPrompt {
+                            items: Items::Symbols,
+                            first: false,
+                        }
+                    )
+                }
+                outcome => {
+                    return Err(ScrabrudoError::UnexpectedOutcome(format!("{:?}", outcome)));
                 }
-                TurnOutcome::Bet(current_bet) => {
-                    if !dict::has_word(&line) {
-                        info!("Bet was not in dict");
+            };
+            if can_exchange(&state.config, &self.hand) {
+                info!("{}", Msg::ExchangePrompt(Items::Symbols));
+            }
+
+            let line = read_human_line(&state.config, self.id, "")?;
+            let line = line.as_str();
+
+            let requested_challenge = match line {
+                "p" => Some(TurnOutcome::Perudo),
+                "pal" => Some(TurnOutcome::Palafico),
+                _ => None,
+            };
+            if let Some(requested_challenge) = requested_challenge {
+                match challenge(state, &self.hand, current_outcome, requested_challenge) {
+                    Ok(outcome) => return Ok(outcome),
+                    Err(e) => {
+                        info!("{}", e);
                         continue;
-                    } else if bet <= *current_bet {
-                        info!("Bet wasn't high enough");
+                    }
+                }
+            }
+            if line == "quit" {
+                return Ok(TurnOutcome::Quit);
+            }
+            if let Some(symbols) = line.strip_prefix("x ") {
+                let symbols = symbols
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(Symbol::from_char)
+                    .collect::<Result<Vec<Symbol>, ScrabrudoError>>();
+                match symbols.and_then(|symbols| {
+                    exchanged_hand(&state.config, &self.hand, &symbols).map(|_| symbols)
+                }) {
+                    Ok(symbols) => return Ok(TurnOutcome::Exchange(symbols)),
+                    Err(e) => {
+                        info!("{}", e);
                         continue;
-                    } else {
-                        TurnOutcome::Bet(bet)
                     }
                 }
-                _ => panic!(),
+            }
+            if line == "table" {
+                show_table(state, self);
+                continue;
+            }
+
+            // Either return a valid bet or take input again.
+            let bet = match EquationBet::parse(line) {
+                Ok(bet) => bet,
+                Err(e) => {
+                    info!("{}", e);
+                    continue;
+                }
+            };
+            return match bet.validate(state) {
+                Ok(()) => Ok(TurnOutcome::Bet(bet)),
+                Err(e) => {
+                    info!("{}", e);
+                    continue;
+                }
             };
         }
     }
 }
 
+/// Reads a line from the given human, trimmed and, in accessible mode, with anything typed out in
+/// full read as the shorthand for it, commands taking the given prefix.
+fn read_human_line(
+    config: &GameConfig,
+    player_id: usize,
+    prefix: &str,
+) -> Result<String, ScrabrudoError> {
+    let line = config.input.read_line(Some(player_id))?;
+    if accessible() {
+        Ok(expand_alias(line.trim(), prefix))
+    } else {
+        Ok(line.trim().into())
+    }
+}
+
+/// Shows some items as a list, or names them in turn in accessible mode.
+fn format_items<V: fmt::Debug>(items: &[V]) -> String {
+    if accessible() {
+        describe_items(items)
+    } else {
+        format!("{:?}", items)
+    }
+}
+
+/// Shows how many items each seat has as a list, or seat by seat in accessible mode.
+fn format_counts(counts: &[usize]) -> String {
+    if accessible() {
+        describe_counts(counts)
+    } else {
+        format!("{:?}", counts)
+    }
+}
+
+/// Shows a player with their hand, or names each item in turn in accessible mode.
+fn format_hand<V: fmt::Debug>(player: &dyn fmt::Display, player_id: usize, items: &[V]) -> String {
+    if accessible() {
+        format!("{}: {}", player_id, describe_items(items))
+    } else {
+        player.to_string()
+    }
+}
+
+/// Announces a human's turn in accessible mode, where the prompt alone may not make it clear.
+fn announce_turn(player_id: usize) {
+    if accessible() {
+        info!("{}", Msg::YourTurn(player_id));
+    }
+}
+
+/// Shows the round's constraint card, the items in the middle of the table, everything players
+/// have revealed and the bets made so far this round, by seat, if anything.
+fn show_face_up<B: Bet>(state: &GameState<B>) {
+    if let Some(constraint) = &state.constraint {
+        info!("{}", Msg::ThisRound(constraint.to_string()));
+    }
+    if !state.community.is_empty() {
+        info!("{}", Msg::InTheMiddle(format_items(&state.community)));
+    }
+    let shown = state
+        .revealed
+        .iter()
+        .enumerate()
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(i, items)| {
+            Msg::SeatShows {
+                seat: i,
+                items: format_items(items),
+            }
+            .to_string()
+        })
+        .collect::<Vec<String>>();
+    if !shown.is_empty() {
+        info!("{}", Msg::Revealed(shown.join(", ")));
+    }
+    // The history is kept by player ID, but the table is shown by seat.
+    let mut seats = state
+        .history
+        .iter()
+        .filter_map(|(player_id, bets)| Some((state.seat_of(*player_id)?, bets)))
+        .collect::<Vec<(usize, &Vec<B>)>>();
+    seats.sort_by_key(|(seat, _)| *seat);
+    let bets = seats
+        .into_iter()
+        .map(|(seat, bets)| {
+            let bets = bets
+                .iter()
+                .map(|bet| bet.to_string())
+                .collect::<Vec<String>>();
+            let seat_bets = Msg::SeatBets {
+                seat: seat,
+                bets: bets.join(", "),
+            };
+            paint_seat(seat, &seat_bets.to_string())
+        })
+        .collect::<Vec<String>>();
+    if !bets.is_empty() {
+        info!("{}", Msg::BetsThisRound(bets.join("; ")));
+    }
+}
+
+/// Shows the given player what the others likely hold between them, believing their bets as
+/// the player's belief policy would.
+fn show_table<P: Player>(state: &GameState<P::B>, player: &P) {
+    let table = likely_table(
+        state,
+        state.current_index,
+        player.items(),
+        Some(&belief(&state.config, player.id())),
+        NUM_SAMPLES,
+    );
+    logging::private(
+        &state.config,
+        player.id(),
+        &Msg::LikelyOnTable(format_table(&table)).to_string(),
+    );
+}
+
+/// Shows the given player what they can deduce about the other hands, if anything.
+fn show_deductions<P: Player>(state: &GameState<P::B>, player: &P) {
+    let deductions = deductions(state, player.id(), player.items());
+    if !deductions.is_empty() {
+        logging::private(
+            &state.config,
+            player.id(),
+            &Msg::Deductions(deductions.join("\n")).to_string(),
+        );
+    }
+}
+
+/// How many suggested words to show at once.
+const MAX_WORDS_SHOWN: usize = 20;
+
+/// Lists the highest scoring words of the given dictionary that can be made from the given rack
+/// together with some number of unknown tiles from the rest of the table.
+fn show_words(config: &GameConfig, player_id: usize, rack: &[Tile], num_unknown: usize) {
+    let letters = rack
+        .iter()
+        .filter(|t| *t != &Tile::Blank)
+        .map(|t| t.spelling())
+        .collect::<String>();
+    let num_blanks = rack.iter().filter(|t| *t == &Tile::Blank).count();
+    let mut bets = config
+        .dict
+        .get()
+        .formable_words(&letters, num_blanks + num_unknown)
+        .iter()
+        .filter_map(|w| ScrabrudoBet::parse(w).ok())
+        .collect::<Vec<ScrabrudoBet>>();
+    bets.sort_by(|a, b| b.score().cmp(&a.score()).then(a.cmp(b)));
+    let shown = bets
+        .iter()
+        .take(MAX_WORDS_SHOWN)
+        .map(|b| format!("{} ({})", b, b.score()))
+        .collect::<Vec<String>>();
+    logging::private(
+        config,
+        player_id,
+        &Msg::RackWords {
+            num_words: bets.len(),
+            num_unknown: num_unknown,
+            words: shown.join(", "),
+        }
+        .to_string(),
+    );
+}
+
 speculate! {
     before {
         testing::set_up();
@@ -475,6 +1445,7 @@ speculate! {
             let player = &PerudoPlayer {
                 id: 0,
                 human: false,
+                opening: OpeningPolicy::Likeliest,
                 hand: Hand::<Die> {
                     items: vec![
                         Die::Six,
@@ -483,13 +1454,11 @@ speculate! {
                         Die::Six,
                         Die::Six
                     ],
+                    revealed: 0,
+                    exchanged: false,
                 },
             };
-            let state = &GameState::<PerudoBet> {
-                total_num_items: 5,
-                num_items_per_player: vec![5],
-                history: hashmap!{},
-            };
+            let state = &testing::game_state::<PerudoBet>(vec![5]);
             let opponent_bet = &PerudoBet {
                 quantity: 4,
                 value: Die::Six,
@@ -505,17 +1474,16 @@ speculate! {
             let player = &PerudoPlayer {
                 id: 0,
                 human: false,
+                opening: OpeningPolicy::Likeliest,
                 hand: Hand::<Die> {
                     items: vec![
                         Die::Six
                     ],
+                    revealed: 0,
+                    exchanged: false,
                 },
             };
-            let state = &GameState::<PerudoBet> {
-                total_num_items: 2,
-                num_items_per_player: vec![1, 1],
-                history: hashmap!{},
-            };
+            let state = &testing::game_state::<PerudoBet>(vec![1, 1]);
             let opponent_bet = &PerudoBet {
                 quantity: 1,
                 value: Die::Six,
@@ -523,6 +1491,51 @@ speculate! {
             let best_outcome_above = player.best_outcome_above(state, opponent_bet);
             assert_eq!(best_outcome_above, TurnOutcome::Palafico);
         }
+
+        it "weighs up raises on what it holds first" {
+            let seen = vec![Die::Six, Die::Two];
+            let mut bets = vec![
+                PerudoBet { value: Die::Five, quantity: 2 },
+                PerudoBet { value: Die::Six, quantity: 3 },
+                PerudoBet { value: Die::Four, quantity: 1 },
+                PerudoBet { value: Die::Six, quantity: 2 },
+            ];
+            bets.sort_by_key(|b| raise_order(b, &seen));
+            assert_eq!(
+                vec![
+                    PerudoBet { value: Die::Six, quantity: 2 },
+                    PerudoBet { value: Die::Six, quantity: 3 },
+                    PerudoBet { value: Die::Four, quantity: 1 },
+                    PerudoBet { value: Die::Five, quantity: 2 },
+                ],
+                bets);
+        }
+
+        it "exchanges dice that do nothing for its bet" {
+            let player = &PerudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Die> {
+                    items: vec![Die::Two, Die::Two, Die::Six, Die::Six, Die::Six],
+                    revealed: 0,
+                    exchanged: false,
+                },
+            };
+            let mut state = testing::game_state::<PerudoBet>(vec![5, 5]);
+            let bet = PerudoBet {
+                quantity: 5,
+                value: Die::Six,
+            };
+            assert_eq!(None, player.exchange_for(&state, &bet));
+
+            // Two more sixes are much likelier in seven unknown dice than in five.
+            state.config.exchanges = true;
+            assert_eq!(Some(vec![Die::Two, Die::Two]), player.exchange_for(&state, &bet));
+
+            let exchanged = player.copy_with(None, None, Some(player.hand.exchanging(&[Die::Two]).unwrap()));
+            assert_eq!(None, exchanged.exchange_for(&state, &bet));
+        }
     }
 
     describe "scrabrudo player" {
@@ -530,18 +1543,17 @@ speculate! {
             let player = ScrabrudoPlayer {
                 id: 0,
                 human: false,
+                opening: OpeningPolicy::Likeliest,
                 hand: Hand::<Tile> {
                     items: vec![
                         Tile::H,
                         Tile::I
                     ],
+                    revealed: 0,
+                    exchanged: false,
                 },
             };
-            let state = &GameState::<ScrabrudoBet> {
-                total_num_items: 3,
-                num_items_per_player: vec![2, 1],
-                history: hashmap!{},
-            };
+            let state = &testing::game_state::<ScrabrudoBet>(vec![2, 1]);
 
             assert_eq!(
                 ScrabrudoBet::from_word(&"hi".into()),
@@ -552,6 +1564,7 @@ speculate! {
             let player = &ScrabrudoPlayer {
                 id: 0,
                 human: false,
+                opening: OpeningPolicy::Likeliest,
                 hand: Hand::<Tile> {
                     items: vec![
                         Tile::C,
@@ -559,19 +1572,42 @@ speculate! {
                         Tile::A,
                         Tile::T
                     ],
+                    revealed: 0,
+                    exchanged: false,
                 },
             };
             let state = &GameState::<ScrabrudoBet> {
-                total_num_items: 9,
-                num_items_per_player: vec![4, 5],
-                history: hashmap!{ 1 => vec![ScrabrudoBet::from_word(&"zzz".into())] }
+                history: hashmap!{ 1 => vec![ScrabrudoBet::from_word(&"zzz".into())] },
+                ..testing::game_state(vec![4, 5])
             };
 
             // We can guarantee 'chat' and so it should play as the only word with the highest P.
             // We will never call Perudo as we have all the letters
             let current_outcome = TurnOutcome::Bet::<ScrabrudoBet>(ScrabrudoBet::from_word(&"zzz".into()));
-            let next_outcome = player.play(state, &current_outcome);
+            let next_outcome = player.play(state, &current_outcome).unwrap();
             assert_eq!(next_outcome, TurnOutcome::Bet(ScrabrudoBet::from_word(&"chat".into())));
         }
+
+        it "finds the best bet it can guarantee" {
+            let player = &ScrabrudoPlayer {
+                id: 0,
+                human: false,
+                opening: OpeningPolicy::Likeliest,
+                hand: Hand::<Tile> {
+                    items: vec![Tile::C, Tile::H, Tile::A, Tile::T],
+                    revealed: 0,
+                    exchanged: false,
+                },
+            };
+            let mut state = GameState::<ScrabrudoBet> {
+                history: hashmap!{ 1 => vec![ScrabrudoBet::from_word(&"zzz".into())] },
+                ..testing::game_state(vec![4, 5])
+            };
+            assert_eq!(Some(ScrabrudoBet::from_word(&"chat".into())), player.best_guaranteed_bet(&state));
+
+            // Nothing the rack covers beats a bet on all of it.
+            state.history = hashmap!{ 1 => vec![ScrabrudoBet::from_word(&"chat".into())] };
+            assert_eq!(None, player.best_guaranteed_bet(&state));
+        }
     }
 }
@@ -0,0 +1,71 @@
+/// Playing many games at once, as a server would. Each game is played from start to finish on a
+/// thread of its own, so that what it logs is tagged with it alone, while the AI's own parallel
+/// work is shared out as usual.
+use crate::error::*;
+use crate::game::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::Mutex;
+use std::thread;
+
+/// Plays the given games to completion across the given number of threads, returning each
+/// finished game, or why it couldn't be finished, in the order given.
+/// Each game takes its human players' moves from its own config's input source, and plays each
+/// seat by that config's settings for it, such as beliefs, so games never set each other's.
+pub fn run_all<G: Game + Send>(
+    games: Vec<G>,
+    num_threads: usize,
+) -> Vec<Result<G, ScrabrudoError>> {
+    map_all(games, num_threads, |game| game.run())
+}
+
+/// Applies the given function to every item across the given number of threads, each call on a
+/// thread of its own, returning the results in the order given.
+pub fn map_all<T: Send, R: Send>(
+    items: Vec<T>,
+    num_threads: usize,
+    f: impl Fn(T) -> R + Sync,
+) -> Vec<R> {
+    let num_items = items.len();
+    // Items are taken from the back, so start with the first there.
+    let queue = Mutex::new(items.into_iter().enumerate().rev().collect::<Vec<(usize, T)>>());
+    let finished = Mutex::new(Vec::with_capacity(num_items));
+    thread::scope(|scope| {
+        for _ in 0..num_threads.max(1) {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().pop();
+                match next {
+                    Some((i, item)) => {
+                        let result = f(item);
+                        finished.lock().unwrap().push((i, result));
+                    }
+                    None => break,
+                }
+            });
+        }
+    });
+    let mut finished = finished.into_inner().unwrap();
+    finished.sort_by_key(|(i, _)| *i);
+    finished.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+speculate! {
+    describe "running games at once" {
+        it "finishes every game" {
+            let games = (0..8)
+                .map(|i| PerudoGame::new(
+                    2 + i % 2,
+                    1,
+                    hashset!{},
+                    crate::opening::OpeningPolicy::Likeliest,
+                    crate::config::GameConfig::default()))
+                .collect::<Vec<PerudoGame>>();
+            let finished = run_all(games, 4);
+            assert_eq!(8, finished.len());
+            assert!(finished.iter().all(|g| g.as_ref().unwrap().winner().is_some()));
+            assert!(run_all(Vec::<PerudoGame>::new(), 4).is_empty());
+        }
+    }
+}
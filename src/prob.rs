@@ -0,0 +1,93 @@
+/// Utilities for ordering and ranking probabilities.
+#[cfg(test)]
+use speculate::speculate;
+use std::cmp::Ordering;
+
+/// Probabilities closer together than this are considered tied.
+pub const TIE_EPSILON: f64 = 1e-9;
+
+/// A totally ordered probability, so that outcomes can be sorted without lossy integer casts.
+/// NaN sorts below every other value.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderedProb(pub f64);
+
+impl Ord for OrderedProb {
+    fn cmp(&self, other: &OrderedProb) -> Ordering {
+        match self.0.partial_cmp(&other.0) {
+            Some(ordering) => ordering,
+            None => other.0.is_nan().cmp(&self.0.is_nan()),
+        }
+    }
+}
+
+impl PartialOrd for OrderedProb {
+    fn partial_cmp(&self, other: &OrderedProb) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for OrderedProb {
+    fn eq(&self, other: &OrderedProb) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedProb {}
+
+/// Whether two probabilities should be treated as the same.
+pub fn is_tied(a: f64, b: f64) -> bool {
+    (a - b).abs() <= TIE_EPSILON
+}
+
+/// Sorts outcomes by ascending probability.
+/// The sort is stable, so tied outcomes keep the order in which they were given.
+pub fn rank_outcomes<T>(mut outcomes: Vec<(T, f64)>) -> Vec<(T, f64)> {
+    outcomes.sort_by_key(|o| OrderedProb(o.1));
+    outcomes
+}
+
+/// Gets all outcomes tied for the highest probability from a ranked list, in ranked order.
+pub fn best_outcomes<T>(ranked: Vec<(T, f64)>) -> Vec<T> {
+    let best_p = match ranked.last() {
+        Some(o) => o.1,
+        None => return vec![],
+    };
+    ranked
+        .into_iter()
+        .filter(|o| is_tied(o.1, best_p))
+        .map(|o| o.0)
+        .collect()
+}
+
+#[cfg(test)]
+speculate! {
+    describe "ordered probabilities" {
+        it "orders probabilities" {
+            assert!(OrderedProb(0.1) < OrderedProb(0.2));
+            assert!(OrderedProb(1.0) > OrderedProb(0.999999));
+            assert_eq!(OrderedProb(0.5), OrderedProb(0.5));
+        }
+
+        it "sorts NaN lowest" {
+            assert!(OrderedProb(f64::NAN) < OrderedProb(0.0));
+            assert_eq!(OrderedProb(f64::NAN), OrderedProb(f64::NAN));
+        }
+    }
+
+    describe "ranking outcomes" {
+        it "keeps near-ties distinct" {
+            let ranked = rank_outcomes(vec![("a", 0.500001), ("b", 0.500002), ("c", 0.1)]);
+            assert_eq!(vec!["c", "a", "b"], ranked.iter().map(|o| o.0).collect::<Vec<&str>>());
+            assert_eq!(vec!["b"], best_outcomes(ranked));
+        }
+
+        it "keeps ties in the given order" {
+            let ranked = rank_outcomes(vec![("a", 0.7), ("b", 0.2), ("c", 0.7)]);
+            assert_eq!(vec!["a", "c"], best_outcomes(ranked));
+        }
+
+        it "handles no outcomes" {
+            assert!(best_outcomes::<usize>(rank_outcomes(vec![])).is_empty());
+        }
+    }
+}
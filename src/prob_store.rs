@@ -0,0 +1,358 @@
+/// Probability stores: where the chances of finding some letters among the tiles on the table
+/// come from, whether read from a lookup on disk, held in memory, worked out exactly or
+/// remembered from any of these. Every read is counted, by store, in the metrics.
+use crate::dict::*;
+use crate::metrics;
+use crate::monte_carlo::*;
+use crate::tile::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+
+lazy_static! {
+    static ref STORE: RwLock<Option<Arc<dyn ProbStore>>> = RwLock::new(None);
+}
+
+/// How many probabilities a caching store holds before starting afresh.
+pub const CACHING_STORE_SIZE: usize = 100_000;
+
+/// A probability, with how many Monte Carlo trials it was estimated from if it was sampled
+/// rather than worked out.
+pub type StoredProb = (f64, Option<u32>);
+
+/// A source of the chances of finding sorted letters in some number of tiles.
+pub trait ProbStore: fmt::Debug + Send + Sync {
+    /// The name the store's reads are counted under.
+    fn name(&self) -> &str;
+
+    /// The chance of finding at least the given sorted letters in n tiles, or if exact exactly
+    /// them as for a Palafico, or None if the store doesn't hold it.
+    fn fetch(&self, letters: &str, n: usize, exact: bool) -> Option<StoredProb>;
+
+    /// Whether the store holds any exact probabilities at all, without which Palafico can't be
+    /// judged.
+    fn has_exact(&self) -> bool {
+        true
+    }
+
+    /// Fetches the chance as for fetch, counting whether the store held it.
+    fn get(&self, letters: &str, n: usize, exact: bool) -> Option<StoredProb> {
+        let found = self.fetch(letters, n, exact);
+        metrics::record_store(self.name(), found.is_some());
+        found
+    }
+}
+
+/// The kinds of store the process can be told to read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbStoreKind {
+    /// Read the lookup on disk for every probability.
+    Sstable,
+
+    /// Read the whole lookup into memory once.
+    Memory,
+
+    /// Work every probability out exactly, with no lookup at all.
+    Exact,
+
+    /// Remember what the given store gives.
+    Cached(Box<ProbStoreKind>),
+}
+
+impl ProbStoreKind {
+    /// Parses a kind of store from its command-line name, e.g. "memory" or "cached:exact".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sstable" => Some(ProbStoreKind::Sstable),
+            "memory" => Some(ProbStoreKind::Memory),
+            "exact" => Some(ProbStoreKind::Exact),
+            _ if name.starts_with("cached:") => Self::from_name(&name["cached:".len()..])
+                .map(|inner| ProbStoreKind::Cached(Box::new(inner))),
+            _ => None,
+        }
+    }
+
+    /// Opens a store of this kind over the given lookup.
+    pub fn open(&self, lookup: &LookupHandle) -> Arc<dyn ProbStore> {
+        match self {
+            ProbStoreKind::Sstable => Arc::new(SstableStore::new(lookup.clone())),
+            ProbStoreKind::Memory => Arc::new(MemoryStore::new(lookup.clone())),
+            ProbStoreKind::Exact => Arc::new(ExactStore),
+            ProbStoreKind::Cached(inner) => Arc::new(CachingStore::new(inner.open(lookup))),
+        }
+    }
+}
+
+/// Reads the lookup a game is played with, row by row from disk.
+#[derive(Debug)]
+pub struct SstableStore {
+    lookup: LookupHandle,
+}
+
+impl SstableStore {
+    pub fn new(lookup: LookupHandle) -> Self {
+        Self { lookup: lookup }
+    }
+}
+
+impl ProbStore for SstableStore {
+    fn name(&self) -> &str {
+        "sstable"
+    }
+
+    fn fetch(&self, letters: &str, n: usize, exact: bool) -> Option<StoredProb> {
+        let lookup = self.lookup.get();
+        let probs = if exact {
+            lookup.exact_probs(letters)?
+        } else {
+            lookup.probs(letters)?
+        };
+        probs.get(n).map(|p| (*p, lookup.meta().num_trials))
+    }
+
+    fn has_exact(&self) -> bool {
+        self.lookup.get().meta().exact
+    }
+}
+
+/// Every row of a lookup, keyed by its letters, with how many trials they were estimated from.
+type Rows = (HashMap<String, (Vec<f64>, Vec<f64>)>, Option<u32>);
+
+/// Holds the whole of the lookup a game is played with in memory, reading it again should the
+/// lookup be switched for another.
+#[derive(Debug)]
+pub struct MemoryStore {
+    lookup: LookupHandle,
+    loaded: Mutex<Option<(Arc<Lookup>, Arc<Rows>)>>,
+}
+
+impl MemoryStore {
+    pub fn new(lookup: LookupHandle) -> Self {
+        Self {
+            lookup: lookup,
+            loaded: Mutex::new(None),
+        }
+    }
+
+    /// The rows of the lookup as it is now, read on first asking.
+    fn rows(&self) -> Arc<Rows> {
+        let lookup = self.lookup.get();
+        let mut loaded = self.loaded.lock().unwrap();
+        match &*loaded {
+            Some((loaded_lookup, rows)) if Arc::ptr_eq(loaded_lookup, &lookup) => rows.clone(),
+            _ => {
+                info!("Reading the whole lookup into memory...");
+                let rows = lookup
+                    .rows()
+                    .into_iter()
+                    .map(|(letters, probs, exact_probs)| (letters, (probs, exact_probs)))
+                    .collect();
+                let rows = Arc::new((rows, lookup.meta().num_trials));
+                *loaded = Some((lookup, rows.clone()));
+                rows
+            }
+        }
+    }
+}
+
+impl ProbStore for MemoryStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn fetch(&self, letters: &str, n: usize, exact: bool) -> Option<StoredProb> {
+        let rows = self.rows();
+        let (probs, exact_probs) = rows.0.get(letters)?;
+        let probs = if exact { exact_probs } else { probs };
+        probs.get(n).map(|p| (*p, rows.1))
+    }
+
+    fn has_exact(&self) -> bool {
+        self.lookup.get().meta().exact
+    }
+}
+
+/// Works every probability out exactly from the tile set, holding everything and needing no
+/// lookup, at the cost of working each out afresh.
+#[derive(Debug)]
+pub struct ExactStore;
+
+impl ProbStore for ExactStore {
+    fn name(&self) -> &str {
+        "exact"
+    }
+
+    fn fetch(&self, letters: &str, n: usize, exact: bool) -> Option<StoredProb> {
+        Some((exact_prob(letters, n, exact), None))
+    }
+}
+
+/// Remembers what another store gives, counting its own hits and misses, and forgetting
+/// everything once it holds CACHING_STORE_SIZE probabilities.
+#[derive(Debug)]
+pub struct CachingStore {
+    inner: Arc<dyn ProbStore>,
+    cached: Mutex<HashMap<(String, usize, bool), Option<StoredProb>>>,
+}
+
+impl CachingStore {
+    pub fn new(inner: Arc<dyn ProbStore>) -> Self {
+        Self {
+            inner: inner,
+            cached: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl ProbStore for CachingStore {
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    fn fetch(&self, letters: &str, n: usize, exact: bool) -> Option<StoredProb> {
+        self.get(letters, n, exact)
+    }
+
+    fn has_exact(&self) -> bool {
+        self.inner.has_exact()
+    }
+
+    /// Counts a hit when the probability was remembered, and a miss when the store beneath had
+    /// to be asked, whatever it gave.
+    fn get(&self, letters: &str, n: usize, exact: bool) -> Option<StoredProb> {
+        let key = (letters.to_string(), n, exact);
+        if let Some(found) = self.cached.lock().unwrap().get(&key) {
+            metrics::record_store(self.name(), true);
+            return *found;
+        }
+        metrics::record_store(self.name(), false);
+        let found = self.inner.get(letters, n, exact);
+        let mut cached = self.cached.lock().unwrap();
+        if cached.len() >= CACHING_STORE_SIZE {
+            cached.clear();
+        }
+        cached.insert(key, found);
+        found
+    }
+}
+
+/// Reads probabilities from the given store from now on, whichever lookup a game is played
+/// with.
+pub fn init_prob_store(store: Arc<dyn ProbStore>) {
+    *STORE.write().unwrap() = Some(store);
+}
+
+/// The store to read probabilities from for a game played with the given lookup: the one the
+/// process was told to read from, or else the lookup itself.
+pub fn prob_store(lookup: &LookupHandle) -> Arc<dyn ProbStore> {
+    match STORE.read().unwrap().as_ref() {
+        Some(store) => store.clone(),
+        None => Arc::new(SstableStore::new(lookup.clone())),
+    }
+}
+
+/// The chance of finding the given sorted letters among n tiles dealt from the current tile set,
+/// at least or if exact exactly, with blanks standing in for any missing letters. Deals a tile
+/// at a time, keeping the chance of every count of each letter found so far, up to as many as
+/// are sought.
+pub fn exact_prob(letters: &str, n: usize, exact: bool) -> f64 {
+    let tiles = letters
+        .chars()
+        .filter_map(|c| Tile::from_char(c).ok())
+        .collect::<Vec<Tile>>();
+    let counts = letter_counts(&tiles);
+    let blank = Tile::Blank.as_usize();
+    let sought = (0..NUM_TILE_KINDS)
+        .filter(|i| *i != blank && counts[*i] > 0)
+        .map(|i| (i, counts[i]))
+        .collect::<Vec<(usize, u8)>>();
+    let num_sought = sought.iter().map(|(_, count)| *count).sum::<u8>();
+    let weights = tile_set().weights();
+    let total = weights.iter().sum::<u32>() as f64;
+    let p = |i: usize| weights[i] as f64 / total;
+    let p_other = 1.0 - p(blank) - sought.iter().map(|(i, _)| p(*i)).sum::<f64>();
+
+    // Each state counts the tiles found of each letter sought, then the blanks.
+    let mut states = hashmap! { vec![0; sought.len() + 1] => 1.0 };
+    for _ in 0..n {
+        let mut next = HashMap::new();
+        for (state, mass) in states {
+            for (j, (i, count)) in sought.iter().enumerate() {
+                let mut found = state.clone();
+                if found[j] < *count {
+                    found[j] += 1;
+                } else if exact {
+                    // Too many of the letter for a Palafico.
+                    continue;
+                }
+                *next.entry(found).or_insert(0.0) += mass * p(*i);
+            }
+            let mut found = state.clone();
+            found[sought.len()] = (found[sought.len()] + 1).min(num_sought);
+            *next.entry(found).or_insert(0.0) += mass * p(blank);
+            *next.entry(state).or_insert(0.0) += mass * p_other;
+        }
+        states = next;
+    }
+    states
+        .into_iter()
+        .filter(|(found, _)| {
+            let num_missing = sought
+                .iter()
+                .zip(found.iter())
+                .map(|((_, count), f)| count - f)
+                .sum::<u8>();
+            num_missing <= found[sought.len()]
+        })
+        .map(|(_, mass)| mass)
+        .sum()
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "probability stores" {
+        it "parses kinds of store" {
+            assert_eq!(Some(ProbStoreKind::Memory), ProbStoreKind::from_name("memory"));
+            assert_eq!(
+                Some(ProbStoreKind::Cached(Box::new(ProbStoreKind::Exact))),
+                ProbStoreKind::from_name("cached:exact"));
+            assert_eq!(None, ProbStoreKind::from_name("cached:"));
+        }
+
+        it "reads the same from memory as from disk" {
+            let lookup = LookupHandle::default();
+            let sstable = ProbStoreKind::Sstable.open(&lookup);
+            let memory = ProbStoreKind::Memory.open(&lookup);
+            for letters in &["a", "act", "eht"] {
+                assert_eq!(sstable.get(letters, 4, false), memory.get(letters, 4, false));
+                assert_eq!(sstable.get(letters, 4, true), memory.get(letters, 4, true));
+            }
+            assert_eq!(None, memory.get("a", 100, false));
+        }
+
+        it "works probabilities out exactly" {
+            let p_a = Tile::A.prob() + Tile::Blank.prob();
+            assert!((p_a - exact_prob("a", 1, false)).abs() < 1e-9);
+            assert!((1.0 - (1.0 - p_a).powi(3) - exact_prob("a", 3, false)).abs() < 1e-9);
+            assert_eq!(0.0, exact_prob("aa", 1, false));
+            assert!(exact_prob("a", 3, true) < exact_prob("a", 3, false));
+            let sampled = monte_carlo(10, "cat", 20000, false);
+            assert!((sampled - exact_prob("act", 10, false)).abs() < 0.02);
+        }
+
+        it "remembers what it read" {
+            let store = CachingStore::new(Arc::new(ExactStore));
+            let before = metrics::store_reads("cache");
+            assert_eq!(store.get("zzz", 4, false), store.get("zzz", 4, false));
+            let (hits, misses) = metrics::store_reads("cache");
+            assert!(hits > before.0 && misses > before.1);
+        }
+    }
+}
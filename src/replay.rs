@@ -0,0 +1,120 @@
+/// Replaying: going back over games in notation a round at a time, setting up the position before
+/// each challenge to show how likely the AI in the challenger's seat thought it was to succeed,
+/// and whether it did.
+use crate::bet::*;
+use crate::config::*;
+use crate::error::*;
+use crate::explain;
+use crate::game::*;
+use crate::notation::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::fs;
+
+/// What happened at the end of every round of the given games in notation, a line to a round.
+/// With explain, each line goes on to explain what the AI would have done in the challenger's
+/// place.
+pub fn replay<G: Game>(
+    text: &str,
+    config: &GameConfig,
+    explain: bool,
+) -> Result<Vec<String>, ScrabrudoError> {
+    let mut lines = vec![];
+    for (i, game) in GameNotation::<G::B>::parse_all(text)?.iter().enumerate() {
+        for round in &game.rounds {
+            lines.push(format!(
+                "Game {} round {}: {}",
+                i + 1,
+                round.number,
+                replay_round::<G>(round, config, explain)?
+            ));
+        }
+    }
+    Ok(lines)
+}
+
+/// Replays every game in the notation file at the given path, logging each round.
+pub fn replay_file<G: Game>(
+    path: &str,
+    config: &GameConfig,
+    explain: bool,
+) -> Result<(), ScrabrudoError> {
+    for line in replay::<G>(&fs::read_to_string(path)?, config, explain)? {
+        info!("{}", line);
+    }
+    Ok(())
+}
+
+/// The challenge ending the given round, whether it was right, and the chance the AI gave it.
+fn replay_round<G: Game>(
+    round: &RoundNotation<G::B>,
+    config: &GameConfig,
+    explain: bool,
+) -> Result<String, ScrabrudoError> {
+    let position = round.position(config)?.to_json();
+    let game = G::from_json(&position)?;
+    let state = game.state();
+    let (challenger_id, challenge) = &round.moves[round.moves.len() - 1];
+    let bet = state.standing_bet().ok_or_else(|| {
+        ScrabrudoError::Format(format!("Round {} has no bet to challenge", round.number))
+    })?;
+    let (variant, exact, call) = match challenge {
+        TurnOutcome::Perudo => (ProbVariant::Perudo, false, "perudo!"),
+        TurnOutcome::Palafico => (ProbVariant::Palafico, true, "palafico!"),
+        _ => {
+            return Err(ScrabrudoError::Format(format!(
+                "Round {} doesn't end with a challenge",
+                round.number
+            )))
+        }
+    };
+    // Perudo is right when the bet is wrong, and Palafico when it is exactly right.
+    let right = bet.is_correct(&game.all_items(), exact) == exact;
+    let view = state.view_for(state.current_index);
+    let player = game.players()[game.current_index()].cloned();
+    let mut line = format!(
+        "player {} called {} on {} and was {}, which the AI gave {:.0}%",
+        challenger_id,
+        call,
+        bet,
+        if right { "right" } else { "wrong" },
+        100.0 * bet.prob(&view, variant, player.cloned())
+    );
+    if explain {
+        explain::init_collection();
+        player.play(&view, &TurnOutcome::Bet(bet.clone()))?;
+        for decision in explain::take_collected() {
+            line.push_str(&format!("\n{}", decision));
+        }
+    }
+    Ok(line)
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "replays" {
+        it "replays each round's challenge" {
+            let text = "[Variant \"perudo\"]\n1. 0:1x2 1:perudo! reveal 0=23 1=45\n2. 1:2x5 0:perudo! reveal 0=3 1=45\n";
+            let lines = replay::<PerudoGame>(text, &GameConfig::default(), false).unwrap();
+            assert_eq!(2, lines.len());
+            assert!(lines[0].starts_with("Game 1 round 1: player 1 called perudo! on 1 Twos and was wrong, "));
+            assert!(lines[1].starts_with("Game 1 round 2: player 0 called perudo! on 2 Fives and was right, "));
+        }
+
+        it "explains what the AI would have done" {
+            let text = "[Variant \"perudo\"]\n1. 0:1x2 1:perudo! reveal 0=23 1=45\n";
+            let lines = replay::<PerudoGame>(text, &GameConfig::default(), true).unwrap();
+            assert!(lines[0].contains("\nPlayer 1 chose "));
+        }
+
+        it "refuses rounds that don't end with a challenge" {
+            let text = "[Variant \"perudo\"]\n1. 0:1x2 1:2x2 reveal 0=23 1=45\n";
+            assert!(replay::<PerudoGame>(text, &GameConfig::default(), false).is_err());
+        }
+    }
+}
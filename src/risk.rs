@@ -0,0 +1,218 @@
+/// Reasoning about how close each player is to elimination.
+use crate::bet::*;
+use crate::config::*;
+use crate::game::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::fmt;
+
+/// How much worse than losing a single item it is to be knocked out of the game.
+pub const ELIMINATION_WEIGHT: f64 = 2.0;
+
+/// The stakes of a challenge from the point of view of the player whose turn it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Risk {
+    /// The number of items left with each player, by index.
+    pub num_items_per_player: Vec<usize>,
+
+    /// The index of the player who would make the challenge.
+    pub caller_index: usize,
+
+    /// The index of the player whose bet would be challenged.
+    pub bettor_index: usize,
+
+    /// Who starts the round after the challenge.
+    pub round_starter: RoundStarter,
+}
+
+impl Risk {
+    pub fn new<B: Bet>(state: &GameState<B>) -> Self {
+        let num_players = state.num_items_per_player.len();
+        Self {
+            num_items_per_player: state.num_items_per_player.clone(),
+            caller_index: state.current_index,
+            bettor_index: (state.current_index + num_players - 1) % num_players,
+            round_starter: state.config.round_starter.clone(),
+        }
+    }
+
+    /// Whether losing an item would knock the given player out.
+    pub fn eliminated_on_loss(&self, index: usize) -> bool {
+        self.num_items_per_player[index] <= 1
+    }
+
+    /// Whether a wrong call knocks the caller out.
+    pub fn caller_eliminated_on_loss(&self) -> bool {
+        self.eliminated_on_loss(self.caller_index)
+    }
+
+    /// Whether a correct call knocks the bettor out.
+    pub fn bettor_eliminated_on_loss(&self) -> bool {
+        self.eliminated_on_loss(self.bettor_index)
+    }
+
+    /// Whether the given player losing an item ends the game.
+    pub fn game_over_on_loss(&self, index: usize) -> bool {
+        let num_remaining = self.num_items_per_player.iter().filter(|n| **n > 0).count();
+        self.eliminated_on_loss(index) && num_remaining <= 2
+    }
+
+    /// The index of the player who starts the next round if the given player loses the
+    /// challenge. Indices are as they are now, before any player is removed.
+    pub fn next_to_act_on_loss(&self, index: usize) -> usize {
+        let winner_index = if index == self.caller_index {
+            self.bettor_index
+        } else {
+            self.caller_index
+        };
+        self.round_starter.next_index(
+            index,
+            winner_index,
+            self.num_items_per_player.len(),
+            self.eliminated_on_loss(index),
+        )
+    }
+
+    /// Describes what happens when the given player loses an item.
+    fn describe_loss(&self, index: usize) -> String {
+        if self.game_over_on_loss(index) {
+            format!("ends the game for {}", index)
+        } else if self.eliminated_on_loss(index) {
+            format!(
+                "knocks out {} and {} starts next",
+                index,
+                self.next_to_act_on_loss(index)
+            )
+        } else {
+            format!(
+                "costs {} an item and {} starts next",
+                index,
+                self.next_to_act_on_loss(index)
+            )
+        }
+    }
+
+    /// The weight of losing an item for the given player.
+    fn loss_weight(&self, index: usize) -> f64 {
+        if self.eliminated_on_loss(index) {
+            ELIMINATION_WEIGHT
+        } else {
+            1.0
+        }
+    }
+
+    /// Adjusts the probability of a Perudo call succeeding by the pot odds of the challenge.
+    /// Knocking out the bettor is worth more than taking an item, and being knocked out costs
+    /// more than losing one, so the result is the success probability that makes the weighted
+    /// outcomes balance. With nobody at risk of elimination this is the probability unchanged.
+    pub fn perudo_odds(&self, p: f64) -> f64 {
+        pot_odds(
+            p,
+            self.loss_weight(self.bettor_index),
+            self.loss_weight(self.caller_index),
+        )
+    }
+
+    /// As for Perudo, but a successful Palafico only ever gains the caller an item.
+    pub fn palafico_odds(&self, p: f64) -> f64 {
+        pot_odds(p, 1.0, self.loss_weight(self.caller_index))
+    }
+}
+
+/// Weights the chance of winning and losing by what is won and lost.
+fn pot_odds(p: f64, win: f64, loss: f64) -> f64 {
+    let weighted_win = p * win;
+    let weighted_loss = (1.0 - p) * loss;
+    if weighted_win + weighted_loss == 0.0 {
+        return 0.0;
+    }
+    weighted_win / (weighted_win + weighted_loss)
+}
+
+impl fmt::Display for Risk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let holdings = self
+            .num_items_per_player
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                format!(
+                    "{}: {}{}",
+                    i,
+                    n,
+                    if self.eliminated_on_loss(i) { " (at risk)" } else { "" }
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" | ");
+        write!(
+            f,
+            "Items left: {} - a wrong call {}, a right call {}",
+            holdings,
+            self.describe_loss(self.caller_index),
+            self.describe_loss(self.bettor_index),
+        )
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    fn state(num_items_per_player: Vec<usize>, current_index: usize) -> GameState<ScrabrudoBet> {
+        GameState {
+            current_index: current_index,
+            ..crate::testing::game_state(num_items_per_player)
+        }
+    }
+
+    describe "risk" {
+        it "finds the caller and bettor" {
+            let risk = Risk::new(&state(vec![5, 1, 3], 0));
+            assert_eq!(0, risk.caller_index);
+            assert_eq!(2, risk.bettor_index);
+        }
+
+        it "detects elimination" {
+            let risk = Risk::new(&state(vec![1, 3, 2], 1));
+            assert!(!risk.caller_eliminated_on_loss());
+            assert!(risk.bettor_eliminated_on_loss());
+            assert!(!risk.game_over_on_loss(0));
+            assert_eq!(1, risk.next_to_act_on_loss(0));
+            assert_eq!(1, risk.next_to_act_on_loss(1));
+        }
+
+        it "follows the round starter policy" {
+            let mut s = state(vec![1, 3, 2], 1);
+            s.config.round_starter = RoundStarter::Winner;
+            let risk = Risk::new(&s);
+            assert_eq!(0, risk.next_to_act_on_loss(1));
+            assert_eq!(1, risk.next_to_act_on_loss(0));
+        }
+
+        it "detects the end of the game" {
+            let risk = Risk::new(&state(vec![1, 4], 0));
+            assert!(risk.caller_eliminated_on_loss());
+            assert!(risk.game_over_on_loss(0));
+            assert!(!risk.game_over_on_loss(1));
+        }
+
+        it "leaves odds alone when nobody is at risk" {
+            let risk = Risk::new(&state(vec![3, 4], 0));
+            assert!((0.3 - risk.perudo_odds(0.3)).abs() < 1e-9);
+            assert!((0.3 - risk.palafico_odds(0.3)).abs() < 1e-9);
+        }
+
+        it "weights odds by elimination" {
+            // Knocking out the bettor makes calling more attractive.
+            assert!(Risk::new(&state(vec![3, 1], 0)).perudo_odds(0.5) > 0.5);
+
+            // Risking our own elimination makes calling less attractive.
+            assert!(Risk::new(&state(vec![1, 3], 0)).perudo_odds(0.5) < 0.5);
+            assert!(Risk::new(&state(vec![1, 3], 0)).palafico_odds(0.5) < 0.5);
+
+            // Certainties are unaffected.
+            assert_eq!(1.0, Risk::new(&state(vec![1, 3], 0)).perudo_odds(1.0));
+            assert_eq!(0.0, Risk::new(&state(vec![1, 3], 0)).perudo_odds(0.0));
+        }
+    }
+}
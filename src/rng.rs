@@ -0,0 +1,262 @@
+/// The source of randomness for dealing and decisions, which can be seeded for repeatable games.
+/// Built with the rng-audit feature, every draw can be noted with what it was for, so that a game
+/// record shows exactly what was drawn and when.
+#[cfg(feature = "rng-audit")]
+use crate::logging;
+
+use rand::rngs::StdRng;
+use rand::{thread_rng, Error, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref SEEDED_RNG: Mutex<Option<(u64, StdRng)>> = Mutex::new(None);
+}
+
+thread_local! {
+    // A generator for this thread alone, which takes precedence over the process's.
+    static THREAD_SEEDED_RNG: RefCell<Option<(u64, StdRng)>> = const { RefCell::new(None) };
+
+    // What the draws being made on this thread are for, if anything has said.
+    static LABEL: RefCell<Option<&'static str>> = const { RefCell::new(None) };
+}
+
+#[cfg(feature = "rng-audit")]
+thread_local! {
+    // The draws made on this thread since the audit was last taken, if auditing.
+    static AUDIT: RefCell<Option<Vec<RngDraw>>> = const { RefCell::new(None) };
+}
+
+// Checked before locking, so that unseeded runs such as the precompute don't contend on the lock.
+static SEEDED: AtomicBool = AtomicBool::new(false);
+
+/// Draws everything from a generator seeded with the given seed from now on.
+pub fn init_seed(seed: u64) {
+    let mut seeded_rng = SEEDED_RNG.lock().unwrap();
+    *seeded_rng = Some((seed, StdRng::seed_from_u64(seed)));
+    SEEDED.store(true, Ordering::SeqCst);
+}
+
+/// Runs the given function drawing everything on this thread from a generator seeded with the
+/// given seed, leaving other threads drawing as they were, so that a seeded game can be played
+/// alongside others.
+pub fn with_seed<T>(seed: u64, f: impl FnOnce() -> T) -> T {
+    let previous = THREAD_SEEDED_RNG.with(|rng| {
+        rng.borrow_mut()
+            .replace((seed, StdRng::seed_from_u64(seed)))
+    });
+    let result = f();
+    THREAD_SEEDED_RNG.with(|rng| *rng.borrow_mut() = previous);
+    result
+}
+
+/// The seed randomness is being drawn from on this thread, if any.
+pub fn seed() -> Option<u64> {
+    match THREAD_SEEDED_RNG.with(|rng| rng.borrow().as_ref().map(|r| r.0)) {
+        Some(seed) => Some(seed),
+        None => SEEDED_RNG.lock().unwrap().as_ref().map(|r| r.0),
+    }
+}
+
+/// A single draw from the generator, as noted in the audit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngDraw {
+    /// What the draw was for, such as "deal" or "tie_break", or "other" if nothing said.
+    pub label: String,
+
+    /// The round it was drawn in, counting from 1, or 0 if before the game began.
+    pub round: usize,
+
+    /// The player whose turn it was, if any.
+    pub player_id: Option<usize>,
+
+    /// The bits drawn, eight bytes at most.
+    pub value: u64,
+}
+
+impl fmt::Display for RngDraw {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:x} for {} in round {}",
+            self.value, self.label, self.round
+        )
+    }
+}
+
+/// Runs the given function noting every draw it makes as for the given purpose, unless it is
+/// run by a function noting them for one already, so that each draw is put down to what it was
+/// ultimately for: dice dealt to imagine a hand are sampled, not dealt.
+pub fn labelled<T>(label: &'static str, f: impl FnOnce() -> T) -> T {
+    let outermost = LABEL.with(|current| {
+        let mut current = current.borrow_mut();
+        let unlabelled = current.is_none();
+        if unlabelled {
+            *current = Some(label);
+        }
+        unlabelled
+    });
+    let result = f();
+    if outermost {
+        LABEL.with(|current| *current.borrow_mut() = None);
+    }
+    result
+}
+
+/// Starts noting every draw made on this thread for the audit.
+#[cfg(feature = "rng-audit")]
+pub fn begin_audit() {
+    AUDIT.with(|audit| *audit.borrow_mut() = Some(vec![]));
+}
+
+/// Notes nothing, lacking the rng-audit feature.
+#[cfg(not(feature = "rng-audit"))]
+pub fn begin_audit() {}
+
+/// Takes the draws noted on this thread since the audit began or was last taken, carrying on
+/// noting them.
+#[cfg(feature = "rng-audit")]
+pub fn take_audit() -> Vec<RngDraw> {
+    AUDIT.with(|audit| audit.borrow_mut().as_mut().map_or(vec![], std::mem::take))
+}
+
+/// Has nothing to take, lacking the rng-audit feature.
+#[cfg(not(feature = "rng-audit"))]
+pub fn take_audit() -> Vec<RngDraw> {
+    vec![]
+}
+
+/// Stops noting draws on this thread.
+#[cfg(feature = "rng-audit")]
+pub fn end_audit() {
+    AUDIT.with(|audit| *audit.borrow_mut() = None);
+}
+
+/// Has nothing to stop, lacking the rng-audit feature.
+#[cfg(not(feature = "rng-audit"))]
+pub fn end_audit() {}
+
+/// Notes the given draw made for the given purpose, if auditing on this thread, unless it's being
+/// made for another.
+#[cfg(feature = "rng-audit")]
+fn note(label: Option<&'static str>, value: u64) {
+    AUDIT.with(|audit| {
+        if let Some(draws) = audit.borrow_mut().as_mut() {
+            let context = logging::context();
+            let label = LABEL.with(|outer| outer.borrow().or(label));
+            draws.push(RngDraw {
+                label: label.unwrap_or("other").into(),
+                round: context.round,
+                player_id: context.player_id,
+                value: value,
+            });
+        }
+    });
+}
+
+/// Notes nothing, lacking the rng-audit feature.
+#[cfg(not(feature = "rng-audit"))]
+fn note(_label: Option<&'static str>, _value: u64) {}
+
+/// Gets the source of randomness: the seeded generator if there is one, otherwise the thread's.
+pub fn rng() -> GameRng {
+    GameRng { label: None }
+}
+
+/// Gets the source of randomness as rng does, noting its draws as for the given purpose.
+pub fn rng_for(label: &'static str) -> GameRng {
+    GameRng { label: Some(label) }
+}
+
+/// Draws from the seeded generator if there is one, otherwise from the thread's.
+pub struct GameRng {
+    /// What the draws are for, as noted in the audit.
+    label: Option<&'static str>,
+}
+
+impl GameRng {
+    fn with<T>(&mut self, draw: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        let mut draw = Some(draw);
+        let drawn = THREAD_SEEDED_RNG.with(|rng| {
+            rng.borrow_mut()
+                .as_mut()
+                .map(|(_, rng)| (draw.take().unwrap())(rng))
+        });
+        if let Some(drawn) = drawn {
+            return drawn;
+        }
+        let draw = draw.unwrap();
+        if SEEDED.load(Ordering::SeqCst) {
+            if let Some((_, rng)) = SEEDED_RNG.lock().unwrap().as_mut() {
+                return draw(rng);
+            }
+        }
+        draw(&mut thread_rng())
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        let drawn = self.with(|r| r.next_u32());
+        note(self.label, drawn as u64);
+        drawn
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let drawn = self.with(|r| r.next_u64());
+        note(self.label, drawn);
+        drawn
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.with(|r| r.fill_bytes(dest));
+        note_bytes(self.label, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.with(|r| r.try_fill_bytes(dest))?;
+        note_bytes(self.label, dest);
+        Ok(())
+    }
+}
+
+/// Notes the given bytes drawn for the given purpose, eight at a time.
+fn note_bytes(label: Option<&'static str>, bytes: &[u8]) {
+    for chunk in bytes.chunks(8) {
+        let mut value = [0; 8];
+        value[..chunk.len()].copy_from_slice(chunk);
+        note(label, u64::from_le_bytes(value));
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "seeding" {
+        it "draws the same on a thread seeded alike" {
+            let draw = || (0..4).map(|_| rng().next_u32()).collect::<Vec<u32>>();
+            let first = with_seed(7, draw);
+            assert_eq!(first, with_seed(7, draw));
+            assert_ne!(first, with_seed(8, draw));
+            assert_eq!(Some(7), with_seed(7, seed));
+        }
+
+        it "notes each draw with what it was for" {
+            begin_audit();
+            labelled("deal", || labelled("sample", || rng_for("tie_break").next_u32()));
+            rng_for("opening").next_u64();
+            rng().next_u32();
+            let labels = take_audit().into_iter().map(|d| d.label).collect::<Vec<String>>();
+            end_audit();
+            if cfg!(feature = "rng-audit") {
+                assert_eq!(vec!["deal", "opening", "other"], labels);
+            } else {
+                assert!(labels.is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+/// Sampling the hands a player can't see, consistent with what they know of the table.
+use crate::belief::*;
+use crate::bet::*;
+use crate::game::*;
+use crate::hand::*;
+use crate::rng::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::{BTreeMap, HashSet};
+
+/// How many samples to average over when estimating what's on the table.
+pub const NUM_SAMPLES: usize = 200;
+
+/// Deals a hand to every seat as the player at the given seat might imagine them: their own hand
+/// as it is, and for everyone else a hand of the size they hold, dealt as the game deals.
+/// Each opponent holds whatever they have shown, and given a belief policy, whatever of their
+/// claims this round the policy believes, so far as their hand allows. Only the rest is dealt
+/// at random.
+pub fn sample_hands<B: Bet>(
+    state: &GameState<B>,
+    index: usize,
+    hand: &[B::V],
+    belief: Option<&BeliefPolicy>,
+) -> Vec<Vec<B::V>> {
+    let claims = state
+        .history
+        .iter()
+        .filter(|(id, _)| matches!(state.seat_of(**id), Some(seat) if seat != index))
+        .map(|(id, bets)| {
+            let claimed = bets
+                .iter()
+                .flat_map(|b| b.claimed_items())
+                .collect::<HashSet<B::V>>();
+            (*id, claimed.into_iter().collect::<Vec<B::V>>())
+        })
+        .collect::<Vec<(usize, Vec<B::V>)>>();
+    let believed = match belief {
+        Some(belief) => belief.believe_each(claims, &state.honesty),
+        None => vec![],
+    };
+    state
+        .num_items_per_player
+        .iter()
+        .enumerate()
+        .map(|(i, num_items)| {
+            if i == index {
+                return hand.to_vec();
+            }
+            let mut items = state.revealed.get(i).cloned().unwrap_or_default();
+            let believed = believed
+                .iter()
+                .filter(|(id, _)| state.player_ids.get(i) == Some(id))
+                .flat_map(|(_, items)| items.iter().cloned())
+                .take(num_items.saturating_sub(items.len()))
+                .collect::<Vec<B::V>>();
+            items.extend(believed);
+            let num_dealt = (num_items - items.len()) as u32;
+            items.extend(labelled("sample", || Hand::<B::V>::new(num_dealt)).items);
+            items
+        })
+        .collect()
+}
+
+/// The expected number of each item among everyone else's hands, as the player at the given seat
+/// might estimate it from the given number of samples.
+pub fn likely_table<B: Bet>(
+    state: &GameState<B>,
+    index: usize,
+    hand: &[B::V],
+    belief: Option<&BeliefPolicy>,
+    num_samples: usize,
+) -> BTreeMap<B::V, f64> {
+    let mut counts = BTreeMap::new();
+    for _ in 0..num_samples {
+        let hands = sample_hands(state, index, hand, belief);
+        for (i, items) in hands.into_iter().enumerate() {
+            if i == index {
+                continue;
+            }
+            for item in items {
+                *counts.entry(item).or_insert(0.0) += 1.0;
+            }
+        }
+    }
+    for count in counts.values_mut() {
+        *count /= num_samples.max(1) as f64;
+    }
+    counts
+}
+
+/// Shows the likeliest items on the table, most first, e.g. "E 1.8, A 1.2".
+pub fn format_table<V: Holdable>(counts: &BTreeMap<V, f64>) -> String {
+    let mut counts = counts.iter().collect::<Vec<(&V, &f64)>>();
+    counts.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap_or(std::cmp::Ordering::Equal));
+    counts
+        .into_iter()
+        .filter(|(_, n)| **n >= 0.05)
+        .map(|(v, n)| format!("{:?} {:.1}", v, n))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::*;
+    use crate::testing::*;
+
+    describe "sampling hands" {
+        fn state() -> GameState<PerudoBet> {
+            GameState {
+                history: hashmap!{ 1 => vec![perudo_bet(2, Die::Five)] },
+                ..game_state(vec![2, 3, 1])
+            }
+        }
+
+        it "deals hands the size each player holds" {
+            let hands = sample_hands(&state(), 0, &[Die::Two, Die::Three], None);
+            assert_eq!(vec![Die::Two, Die::Three], hands[0]);
+            assert_eq!(vec![2, 3, 1], hands.iter().map(|h| h.len()).collect::<Vec<usize>>());
+        }
+
+        it "deals opponents what they are believed to hold" {
+            let belief = BeliefPolicy::Fraction(1.0);
+            for _ in 0..20 {
+                let hands = sample_hands(&state(), 0, &[Die::Two, Die::Three], Some(&belief));
+                assert!(hands[1].contains(&Die::Five));
+                assert_eq!(3, hands[1].len());
+            }
+        }
+
+        it "deals opponents what they have shown" {
+            let mut state = state();
+            state.revealed = vec![vec![], vec![Die::Six, Die::Six], vec![]];
+            for _ in 0..20 {
+                let hands = sample_hands(&state, 0, &[Die::Two, Die::Three], None);
+                assert_eq!(&[Die::Six, Die::Six], &hands[1][..2]);
+                assert_eq!(3, hands[1].len());
+            }
+        }
+
+        it "estimates what's on the table" {
+            let belief = BeliefPolicy::Fraction(1.0);
+            let table = likely_table(&state(), 0, &[Die::Two, Die::Three], Some(&belief), 50);
+            assert_eq!(4.0, table.values().sum::<f64>().round());
+            assert!(table[&Die::Five] >= 1.0);
+        }
+
+        it "shows the table" {
+            let table = btreemap!{ Die::Two => 0.5, Die::Five => 1.3, Die::Six => 0.0 };
+            assert_eq!("Five 1.3, Two 0.5", format_table(&table));
+        }
+    }
+}
@@ -0,0 +1,209 @@
+/// Saving games part-way through so that they can be resumed.
+use crate::belief::*;
+use crate::bet::*;
+use crate::config::*;
+use crate::constraint::*;
+use crate::error::*;
+use crate::game::*;
+use crate::hand::*;
+use crate::opening::*;
+use crate::timebank::*;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+use std::fs::File;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref AUTOSAVE_PATH: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Starts autosaving games to the given path.
+pub fn init_autosave(autosave_path: &str) {
+    let mut path = AUTOSAVE_PATH.lock().unwrap();
+    *path = Some(autosave_path.into());
+}
+
+/// A player as saved, with their hand.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PlayerSave<V: Holdable> {
+    pub id: usize,
+    pub human: bool,
+    pub opening: OpeningPolicy,
+    pub items: Vec<V>,
+
+    /// How many of the items are face up, as in Hand.
+    #[serde(default)]
+    pub revealed: usize,
+
+    /// Whether they've exchanged items this round.
+    #[serde(default)]
+    pub exchanged: bool,
+}
+
+/// Everything needed to pick a game up where it was left.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct GameSave<B: Bet> {
+    pub players: Vec<PlayerSave<B::V>>,
+    pub current_index: usize,
+    pub current_outcome: TurnOutcome<B>,
+    pub history: HashMap<usize, Vec<B>>,
+    #[serde(default)]
+    pub community: Vec<B::V>,
+    #[serde(default)]
+    pub constraint: Option<Constraint>,
+    pub config: GameConfig,
+    #[serde(default)]
+    pub clocks: Clocks,
+    #[serde(default)]
+    pub eliminated: Vec<usize>,
+
+    /// Whether the trading phase has been played this round.
+    #[serde(default)]
+    pub traded: bool,
+
+    /// The round being played, counting from 1.
+    #[serde(default = "first_round")]
+    pub round: usize,
+
+    /// How often each player's bets have held up when challenged.
+    #[serde(default)]
+    pub honesty: Honesty,
+}
+
+/// The round games saved before rounds were counted are taken to be in.
+fn first_round() -> usize {
+    1
+}
+
+impl<B: Bet> GameSave<B> {
+    /// Snapshots the given game.
+    pub fn new<G: Game<B = B, V = B::V>>(game: &G) -> Self {
+        Self {
+            players: game
+                .players()
+                .iter()
+                .map(|p| PlayerSave {
+                    id: p.id(),
+                    human: p.human(),
+                    opening: p.opening().clone(),
+                    items: p.items().clone(),
+                    revealed: p.hand().revealed,
+                    exchanged: p.hand().exchanged,
+                })
+                .collect(),
+            current_index: game.current_index(),
+            current_outcome: game.current_outcome().clone(),
+            history: game.history().clone(),
+            community: game.community().clone(),
+            constraint: game.constraint().cloned(),
+            config: game.config().clone(),
+            clocks: game.clocks().clone(),
+            eliminated: game.eliminated().clone(),
+            traded: game.traded(),
+            round: game.round(),
+            honesty: game.honesty().clone(),
+        }
+    }
+
+    /// Restores the saved game.
+    pub fn restore<G: Game<B = B, V = B::V>>(self) -> G {
+        let players = self
+            .players
+            .into_iter()
+            .map(|p| {
+                G::create_player(p.id, p.items.len(), p.human, p.opening)
+                    .copy_with(
+                        None,
+                        None,
+                        Some(Hand::<B::V> {
+                            items: p.items,
+                            revealed: p.revealed,
+                            exchanged: p.exchanged,
+                        }),
+                    )
+                    .into()
+            })
+            .collect();
+        G::new_with(
+            players,
+            self.current_index,
+            self.current_outcome,
+            self.history,
+            self.community,
+            self.constraint,
+            self.config,
+        )
+        .with_clocks(self.clocks)
+        .with_eliminated(self.eliminated)
+        .with_traded(self.traded)
+        .with_round(self.round)
+        .with_honesty(self.honesty)
+    }
+}
+
+/// Saves the given game to the given path.
+pub fn save_game<G: Game>(game: &G, path: &str) -> Result<(), ScrabrudoError> {
+    let f = File::create(path)?;
+    serde_json::to_writer_pretty(f, &GameSave::new(game))?;
+    Ok(())
+}
+
+/// Loads a game from the given path.
+pub fn load_game<G: Game>(path: &str) -> Result<G, ScrabrudoError> {
+    let f = File::open(path)?;
+    let save: GameSave<G::B> = serde_json::from_reader(f)?;
+    Ok(save.restore())
+}
+
+/// Saves the game if autosaving is enabled.
+/// Failing to autosave shouldn't stop the game, so errors are only logged.
+pub fn autosave<G: Game>(game: &G) {
+    if let Some(path) = AUTOSAVE_PATH.lock().unwrap().as_ref() {
+        match save_game(game, path) {
+            Ok(()) => info!("Game saved to {}", path),
+            Err(e) => error!("Couldn't save game to {}: {}", path, e),
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "saving games" {
+        it "saves and loads a game" {
+            let game = PerudoGame::new(
+                3,
+                2,
+                hashset!{1},
+                OpeningPolicy::Safe,
+                GameConfig::default(),
+            )
+            .with_first_player(2);
+            save_game(&game, "/tmp/game1.json").unwrap();
+            let loaded: PerudoGame = load_game("/tmp/game1.json").unwrap();
+            assert_eq!(2, loaded.current_index());
+            assert_eq!(game.all_items(), loaded.all_items());
+            assert!(loaded.players()[1].human());
+            assert_eq!(&OpeningPolicy::Safe, loaded.players()[0].opening());
+            assert_eq!(game.current_outcome(), loaded.current_outcome());
+        }
+
+        it "carries on from the round the game was saved in" {
+            let game = PerudoGame::new(
+                3,
+                2,
+                hashset!{},
+                OpeningPolicy::Safe,
+                GameConfig::default(),
+            )
+            .with_round(4);
+            save_game(&game, "/tmp/game2.json").unwrap();
+            let loaded: PerudoGame = load_game("/tmp/game2.json").unwrap();
+            assert_eq!(4, loaded.round());
+        }
+    }
+}
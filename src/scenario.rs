@@ -0,0 +1,282 @@
+/// Scenarios: rule cases written down as data, each a position, the moves made from it and what
+/// should follow each move, so that tricky cases are kept as regression tests without writing
+/// code for each. A scenario file is JSON, e.g.
+///
+/// {
+///   "name": "ones lift a bet",
+///   "position": {"version": 1, "variant": "perudo", ...},
+///   "steps": [
+///     {"play": "2x1", "expect": {"next_player": 1}},
+///     {"play": "3x3", "expect": {"rejected": true}},
+///     {"play": "perudo!", "expect": {"num_items_per_player": [2, 1]}}
+///   ]
+/// }
+///
+/// where the position is in the form described by schema::SCHEMA, with every hand, though its
+/// config need only give the house rules that differ from the standard game. Each move is in game
+/// notation, or "quit".
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+use crate::hand::*;
+use crate::notation::*;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(test)]
+use speculate::speculate;
+use std::fs;
+use std::fs::File;
+
+/// A rule case: a position and the moves made from it, with what should follow each.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    /// What the scenario is of, for reporting failures.
+    pub name: String,
+
+    /// The position to start from, with every hand.
+    pub position: Value,
+
+    /// The moves made from the position, in turn.
+    pub steps: Vec<ScenarioStep>,
+}
+
+/// A move made in a scenario, and what should follow it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    /// The hands to deal each seat before the move, in game notation, if not those held.
+    #[serde(default)]
+    pub deal: Option<Vec<String>>,
+
+    /// The move made by the player whose turn it is, in game notation, or "quit".
+    pub play: String,
+
+    #[serde(default)]
+    pub expect: Expectation,
+}
+
+/// What should follow a move in a scenario. Anything left out isn't checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Expectation {
+    /// Whether the move should be refused, leaving the game as it was.
+    #[serde(default)]
+    pub rejected: bool,
+
+    /// The IDs of the players left at the table, by seat.
+    pub players: Option<Vec<usize>>,
+
+    /// The number of items each seat holds.
+    pub num_items_per_player: Option<Vec<usize>>,
+
+    /// The ID of the player whose turn it is next.
+    pub next_player: Option<usize>,
+
+    /// The ID of the player who has won the game.
+    pub winner: Option<usize>,
+}
+
+impl Scenario {
+    /// Loads a scenario from a file.
+    pub fn load(path: &str) -> Result<Self, ScrabrudoError> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// The variant the scenario is played in.
+    pub fn variant(&self) -> Option<&str> {
+        self.position.get("variant").and_then(|v| v.as_str())
+    }
+
+    /// The position to start from, taking any house rules its config leaves out as standard.
+    pub fn full_position(&self) -> Result<Value, ScrabrudoError> {
+        let mut config = serde_json::to_value(GameConfig::default())?;
+        if let (Some(config), Some(Value::Object(given))) =
+            (config.as_object_mut(), self.position.get("config"))
+        {
+            for (rule, value) in given {
+                config.insert(rule.clone(), value.clone());
+            }
+        }
+        let mut position = self.position.clone();
+        position["config"] = config;
+        Ok(position)
+    }
+
+    /// Plays the scenario through, failing at the first step that doesn't go as expected.
+    pub fn run<G: Game>(&self) -> Result<(), ScrabrudoError> {
+        let mut game = G::from_json(&self.full_position()?)?;
+        for (i, step) in self.steps.iter().enumerate() {
+            let fail = |reason: String| {
+                ScrabrudoError::ScenarioFailed(format!(
+                    "{}, step {} ({}): {}",
+                    self.name,
+                    i + 1,
+                    step.play,
+                    reason
+                ))
+            };
+            if let Some(hands) = &step.deal {
+                game = with_hands(&game, hands)?;
+            }
+            let outcome = match step.play.as_str() {
+                "quit" => TurnOutcome::Quit,
+                play => outcome_from_notation(play)?,
+            };
+            match (game.apply(outcome), step.expect.rejected) {
+                (Ok(_), true) => return Err(fail("the move was allowed".into())),
+                (Err(e), false) => return Err(fail(format!("the move was refused: {}", e))),
+                (Err(_), true) => continue,
+                (Ok(next_game), false) => game = next_game,
+            }
+            check(&step.expect, &game).map_err(fail)?;
+        }
+        Ok(())
+    }
+}
+
+/// The game with the given hands dealt to each seat, in game notation.
+fn with_hands<G: Game>(game: &G, hands: &[String]) -> Result<G, ScrabrudoError> {
+    if hands.len() != game.players().len() {
+        return Err(ScrabrudoError::Format(format!(
+            "Dealt {} hands to {} seats",
+            hands.len(),
+            game.players().len()
+        )));
+    }
+    let mut players = vec![];
+    for (player, items) in game.players().iter().zip(hands.iter()) {
+        let items = items_from_notation(items)?;
+        if items.len() != player.num_items() {
+            return Err(ScrabrudoError::Format(format!(
+                "Dealt {} items to player {}, who holds {}",
+                items.len(),
+                player.id(),
+                player.num_items()
+            )));
+        }
+        let hand = Hand::<G::V> {
+            items: items,
+            revealed: player.hand().revealed,
+            exchanged: player.hand().exchanged,
+        };
+        players.push(player.copy_with(None, None, Some(hand)).into());
+    }
+    Ok(G::new_with(
+        players,
+        game.current_index(),
+        game.current_outcome().clone(),
+        game.history().clone(),
+        game.community().clone(),
+        game.constraint().cloned(),
+        game.config().clone(),
+    )
+    .with_clocks(game.clocks().clone()))
+}
+
+/// Whether the game is as expected, and if not, how it differs.
+fn check<G: Game>(expect: &Expectation, game: &G) -> Result<(), String> {
+    let player_ids = game
+        .players()
+        .iter()
+        .map(|p| p.id())
+        .collect::<Vec<usize>>();
+    let num_items_per_player = game.num_items_per_player();
+    let next_player = player_ids[game.current_index()];
+    let mismatch = |what: &str, expected: String, actual: String| {
+        Err(format!("expected {} {}, found {}", what, expected, actual))
+    };
+    if let Some(expected) = &expect.players {
+        if expected != &player_ids {
+            return mismatch(
+                "players",
+                format!("{:?}", expected),
+                format!("{:?}", player_ids),
+            );
+        }
+    }
+    if let Some(expected) = &expect.num_items_per_player {
+        if expected != &num_items_per_player {
+            return mismatch(
+                "items",
+                format!("{:?}", expected),
+                format!("{:?}", num_items_per_player),
+            );
+        }
+    }
+    if let Some(expected) = expect.next_player {
+        if expected != next_player {
+            return mismatch("next player", expected.to_string(), next_player.to_string());
+        }
+    }
+    if let Some(expected) = expect.winner {
+        if Some(expected) != game.winner() {
+            return mismatch(
+                "winner",
+                expected.to_string(),
+                format!("{:?}", game.winner()),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Loads and plays a scenario from a file, in whichever variant it is written for.
+pub fn run_file(path: &str) -> Result<(), ScrabrudoError> {
+    let scenario = Scenario::load(path)?;
+    match scenario.variant() {
+        Some("perudo") => scenario.run::<PerudoGame>(),
+        Some("scrabrudo") => scenario.run::<ScrabrudoGame>(),
+        variant => Err(ScrabrudoError::Format(format!(
+            "{}: unknown variant {:?}",
+            path, variant
+        ))),
+    }
+}
+
+/// Plays every scenario file in a directory, in order of name, returning how many there were.
+pub fn run_dir(dir: &str) -> Result<usize, ScrabrudoError> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|e| e == "json"));
+    paths.sort();
+    for path in &paths {
+        run_file(&path.to_string_lossy())?;
+    }
+    Ok(paths.len())
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "scenarios" {
+        it "plays every scenario in the data directory" {
+            assert!(run_dir("data/scenarios").unwrap() > 0);
+        }
+
+        it "fails when the game goes otherwise" {
+            let scenario: Scenario = serde_json::from_value(serde_json::json!({
+                "name": "a wrong expectation",
+                "position": {
+                    "version": 1,
+                    "variant": "perudo",
+                    "current_index": 0,
+                    "num_items_per_player": [1, 1],
+                    "history": [],
+                    "hands": [[2], [3]]
+                },
+                "steps": [
+                    {"play": "1x2", "expect": {"next_player": 0}}
+                ]
+            })).unwrap();
+            match scenario.run::<PerudoGame>() {
+                Err(ScrabrudoError::ScenarioFailed(reason)) => {
+                    assert_eq!("a wrong expectation, step 1 (1x2): expected next player 0, found 1", reason);
+                }
+                _ => panic!("Passed a wrong expectation"),
+            }
+        }
+    }
+}
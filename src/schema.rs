@@ -0,0 +1,436 @@
+/// A stable, versioned JSON form of positions, shared by everything that reads or writes them,
+/// with tiles as letters, dice as numbers and bets as words or quantities.
+use crate::bet::*;
+use crate::die::*;
+use crate::error::*;
+use crate::game::*;
+use crate::hand::*;
+use crate::opening::*;
+use crate::symbol::*;
+use crate::tile::*;
+
+use serde_json::{json, Value};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+
+/// The version of the position format written by to_json. Positions of any version up to this
+/// one can be read.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// The position format, as a JSON Schema.
+pub const SCHEMA: &str = r##"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Scrabrudo position",
+  "type": "object",
+  "required": ["version", "variant", "current_index", "num_items_per_player", "history", "config"],
+  "properties": {
+    "version": {"type": "integer", "description": "The schema version, currently 1"},
+    "variant": {"enum": ["perudo", "scrabrudo"]},
+    "current_index": {"type": "integer", "description": "The seat whose turn it is"},
+    "player_ids": {"type": "array", "items": {"type": "integer"}, "description": "The ID of the player in each seat, if not the seat itself"},
+    "num_items_per_player": {"type": "array", "items": {"type": "integer"}},
+    "history": {
+      "type": "array",
+      "description": "The bets made this round, lowest first",
+      "items": {
+        "type": "object",
+        "required": ["player", "bet"],
+        "properties": {
+          "player": {"type": "integer", "description": "The ID of the player who bet"},
+          "bet": {"$ref": "#/definitions/bet"}
+        }
+      }
+    },
+    "config": {"type": "object", "description": "The house rules, as saved games hold them"},
+    "revealed": {"type": "array", "items": {"$ref": "#/definitions/items"}, "description": "The items each seat has shown to everyone"},
+    "community": {"$ref": "#/definitions/items"},
+    "constraint": {"description": "The constraint card drawn for the round, or null"},
+    "hands": {"type": "array", "items": {"$ref": "#/definitions/items"}, "description": "Each seat's hand, empty where unseen"},
+    "clocks": {"type": "object", "additionalProperties": {"type": "integer"}, "description": "The milliseconds of thinking time each timed player has left, by player ID"},
+    "honesty": {"type": "object", "additionalProperties": {"type": "array", "items": {"type": "integer"}, "minItems": 2, "maxItems": 2}, "description": "How many of each player's bets have held up when challenged this game, and how many were challenged, by player ID"}
+  },
+  "definitions": {
+    "item": {
+      "oneOf": [
+        {"type": "integer", "minimum": 1, "maximum": 6, "description": "A die"},
+        {"type": "string", "pattern": "^[a-z_]$", "description": "A tile, with _ for a blank"}
+      ]
+    },
+    "items": {"type": "array", "items": {"$ref": "#/definitions/item"}},
+    "bet": {
+      "oneOf": [
+        {
+          "type": "object",
+          "required": ["quantity", "value"],
+          "properties": {"quantity": {"type": "integer"}, "value": {"type": "integer", "minimum": 1, "maximum": 6}},
+          "description": "A dice bet, e.g. three fives"
+        },
+        {"type": "string", "pattern": "^[a-z]+(\\+[a-z]+)*$", "description": "A word, or words joined by +"}
+      ]
+    }
+  }
+}"##;
+
+/// Anything with a form in the position schema.
+pub trait Schema: Sized {
+    /// The schema form of this.
+    fn to_schema(&self) -> Value;
+
+    /// Reads this back from its schema form.
+    fn from_schema(value: &Value) -> Result<Self, ScrabrudoError>;
+}
+
+impl Schema for Die {
+    fn to_schema(&self) -> Value {
+        json!(self.int())
+    }
+
+    fn from_schema(value: &Value) -> Result<Self, ScrabrudoError> {
+        Die::from_usize(as_usize(value, "die")?)
+    }
+}
+
+impl Schema for Tile {
+    fn to_schema(&self) -> Value {
+        match self {
+            Tile::Blank => json!("_"),
+            tile => json!(tile.char().to_string()),
+        }
+    }
+
+    fn from_schema(value: &Value) -> Result<Self, ScrabrudoError> {
+        let letter = value
+            .as_str()
+            .ok_or_else(|| invalid("tile", value))?
+            .chars()
+            .collect::<Vec<char>>();
+        match letter.as_slice() {
+            ['_'] => Ok(Tile::Blank),
+            [c] => Tile::from_char(*c),
+            _ => Err(invalid("tile", value)),
+        }
+    }
+}
+
+impl Schema for PerudoBet {
+    fn to_schema(&self) -> Value {
+        json!({"quantity": self.quantity, "value": self.value.int()})
+    }
+
+    fn from_schema(value: &Value) -> Result<Self, ScrabrudoError> {
+        Ok(PerudoBet {
+            value: Die::from_schema(field(value, "value")?)?,
+            quantity: as_usize(field(value, "quantity")?, "quantity")?,
+        })
+    }
+}
+
+impl Schema for ScrabrudoBet {
+    fn to_schema(&self) -> Value {
+        json!(self.as_word())
+    }
+
+    fn from_schema(value: &Value) -> Result<Self, ScrabrudoError> {
+        ScrabrudoBet::parse(value.as_str().ok_or_else(|| invalid("word", value))?)
+    }
+}
+
+impl Schema for Symbol {
+    fn to_schema(&self) -> Value {
+        json!(self.char().to_string())
+    }
+
+    fn from_schema(value: &Value) -> Result<Self, ScrabrudoError> {
+        let symbol = value
+            .as_str()
+            .ok_or_else(|| invalid("symbol", value))?
+            .chars()
+            .collect::<Vec<char>>();
+        match symbol.as_slice() {
+            [c] => Symbol::from_char(*c),
+            _ => Err(invalid("symbol", value)),
+        }
+    }
+}
+
+impl Schema for EquationBet {
+    fn to_schema(&self) -> Value {
+        json!(self.as_identity())
+    }
+
+    fn from_schema(value: &Value) -> Result<Self, ScrabrudoError> {
+        EquationBet::parse(value.as_str().ok_or_else(|| invalid("identity", value))?)
+    }
+}
+
+fn invalid(what: &str, value: &Value) -> ScrabrudoError {
+    ScrabrudoError::Format(format!("Invalid {} in position: {}", what, value))
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, ScrabrudoError> {
+    value
+        .get(name)
+        .ok_or_else(|| ScrabrudoError::Format(format!("Position has no {}", name)))
+}
+
+fn as_usize(value: &Value, what: &str) -> Result<usize, ScrabrudoError> {
+    value
+        .as_u64()
+        .map(|x| x as usize)
+        .ok_or_else(|| invalid(what, value))
+}
+
+fn list<T>(
+    value: &Value,
+    what: &str,
+    read: impl Fn(&Value) -> Result<T, ScrabrudoError>,
+) -> Result<Vec<T>, ScrabrudoError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(what, value))?
+        .iter()
+        .map(read)
+        .collect()
+}
+
+fn items_to_schema<T: Schema>(items: &[T]) -> Value {
+    Value::Array(items.iter().map(|i| i.to_schema()).collect())
+}
+
+/// Reads a list of hands, one per seat, defaulting to empty ones if missing.
+fn hands_from_schema<V: Holdable>(
+    value: &Value,
+    name: &str,
+    num_seats: usize,
+) -> Result<Vec<Vec<V>>, ScrabrudoError> {
+    match value.get(name) {
+        Some(hands) => list(hands, name, |hand| list(hand, name, V::from_schema)),
+        None => Ok(vec![vec![]; num_seats]),
+    }
+}
+
+impl<B: Bet> GameState<B> {
+    /// The position in the versioned JSON form described by SCHEMA.
+    pub fn to_json(&self) -> Value {
+        let mut history = self
+            .history
+            .iter()
+            .flat_map(|(player_id, bets)| bets.iter().map(move |bet| (*player_id, bet)))
+            .collect::<Vec<(usize, &B)>>();
+        history.sort_by(|a, b| a.1.cmp(b.1));
+        json!({
+            "version": SCHEMA_VERSION,
+            "variant": B::VARIANT,
+            "current_index": self.current_index,
+            "player_ids": self.player_ids,
+            "num_items_per_player": self.num_items_per_player,
+            "history": history
+                .into_iter()
+                .map(|(player_id, bet)| json!({"player": player_id, "bet": bet.to_schema()}))
+                .collect::<Vec<Value>>(),
+            "config": self.config,
+            "revealed": self.revealed.iter().map(|r| items_to_schema(r)).collect::<Vec<Value>>(),
+            "community": items_to_schema(&self.community),
+            "constraint": self.constraint,
+            "hands": self.hands.iter().map(|h| items_to_schema(h)).collect::<Vec<Value>>(),
+            "clocks": self.clocks,
+            "honesty": self.honesty,
+        })
+    }
+
+    /// Reads a position written by to_json, by this or an earlier version.
+    pub fn from_json(value: &Value) -> Result<Self, ScrabrudoError> {
+        let version = as_usize(field(value, "version")?, "version")? as u64;
+        if version > SCHEMA_VERSION {
+            return Err(ScrabrudoError::Format(format!(
+                "Position is version {}, but only up to {} can be read",
+                version, SCHEMA_VERSION
+            )));
+        }
+        let variant = field(value, "variant")?;
+        if variant.as_str() != Some(B::VARIANT) {
+            return Err(ScrabrudoError::Format(format!(
+                "Expected a {} position, found {}",
+                B::VARIANT,
+                variant
+            )));
+        }
+        let num_items_per_player = list(
+            field(value, "num_items_per_player")?,
+            "number of items",
+            |n| as_usize(n, "number of items"),
+        )?;
+        let num_seats = num_items_per_player.len();
+        let player_ids = match value.get("player_ids") {
+            Some(ids) => list(ids, "player ID", |id| as_usize(id, "player ID"))?,
+            None => (0..num_seats).collect(),
+        };
+        if player_ids.len() != num_seats {
+            return Err(invalid("player IDs", value));
+        }
+        let mut history: HashMap<usize, Vec<B>> = HashMap::new();
+        for entry in field(value, "history")?
+            .as_array()
+            .ok_or_else(|| invalid("history", value))?
+        {
+            let player_id = as_usize(field(entry, "player")?, "player")?;
+            let bet = B::from_schema(field(entry, "bet")?)?;
+            history.entry(player_id).or_insert(vec![]).push(bet);
+        }
+        Ok(GameState {
+            total_num_items: num_items_per_player.iter().sum(),
+            num_items_per_player: num_items_per_player,
+            current_index: as_usize(field(value, "current_index")?, "current index")?,
+            player_ids: player_ids,
+            history: history,
+            config: serde_json::from_value(field(value, "config")?.clone())?,
+            revealed: hands_from_schema(value, "revealed", num_seats)?,
+            community: match value.get("community") {
+                Some(community) => list(community, "community", B::V::from_schema)?,
+                None => vec![],
+            },
+            constraint: match value.get("constraint") {
+                Some(constraint) => serde_json::from_value(constraint.clone())?,
+                None => None,
+            },
+            hands: hands_from_schema(value, "hands", num_seats)?,
+            clocks: match value.get("clocks") {
+                Some(clocks) => serde_json::from_value(clocks.clone())?,
+                None => hashmap! {},
+            },
+            honesty: match value.get("honesty") {
+                Some(honesty) => serde_json::from_value(honesty.clone())?,
+                None => hashmap! {},
+            },
+        })
+    }
+}
+
+/// Sets up a game of AI players from a position, which must hold every hand. Seats are taken by
+/// the players given, or by players numbered by seat, and the standing bet is the last made.
+pub fn game_from_json<G: Game>(value: &Value) -> Result<G, ScrabrudoError> {
+    let state = GameState::<G::B>::from_json(value)?;
+    if state.hands.len() != state.player_ids.len()
+        || state
+            .hands
+            .iter()
+            .zip(state.num_items_per_player.iter())
+            .any(|(hand, n)| hand.len() != *n)
+    {
+        return Err(ScrabrudoError::Format(
+            "A game needs every seat's hand".into(),
+        ));
+    }
+    let players = state
+        .player_ids
+        .iter()
+        .enumerate()
+        .map(|(seat, id)| {
+            G::create_player(
+                *id,
+                state.hands[seat].len(),
+                false,
+                OpeningPolicy::Likeliest,
+            )
+            .copy_with(
+                None,
+                None,
+                Some(Hand::<G::V> {
+                    items: state.hands[seat].clone(),
+                    revealed: state.revealed.get(seat).map_or(0, |r| r.len()),
+                    exchanged: false,
+                }),
+            )
+            .into()
+        })
+        .collect();
+    let current_outcome = match state.standing_bet() {
+        Some(bet) => TurnOutcome::Bet(bet.clone()),
+        None => TurnOutcome::First,
+    };
+    Ok(G::new_with(
+        players,
+        state.current_index,
+        current_outcome,
+        state.history,
+        state.community,
+        state.constraint,
+        state.config,
+    ))
+}
+
+#[cfg(test)]
+speculate! {
+    describe "the position schema" {
+        it "writes items and bets in their schema form" {
+            assert_eq!(json!(5), Die::Five.to_schema());
+            assert_eq!(json!("q"), Tile::Q.to_schema());
+            assert_eq!(json!("_"), Tile::Blank.to_schema());
+            assert_eq!(
+                json!({"quantity": 3, "value": 2}),
+                PerudoBet { value: Die::Two, quantity: 3 }.to_schema()
+            );
+            assert_eq!(json!("cat+dog"), ScrabrudoBet::parse("cat+dog").unwrap().to_schema());
+            assert_eq!(Tile::Blank, Tile::from_schema(&json!("_")).unwrap());
+            assert!(Die::from_schema(&json!(7)).is_err());
+            assert!(Tile::from_schema(&json!("ab")).is_err());
+        }
+
+        it "round-trips a position" {
+            let state = GameState::<PerudoBet> {
+                total_num_items: 4,
+                num_items_per_player: vec![3, 1],
+                current_index: 1,
+                player_ids: vec![0, 4],
+                history: hashmap!{
+                    0 => vec![PerudoBet { value: Die::Two, quantity: 1 }],
+                    4 => vec![PerudoBet { value: Die::Two, quantity: 2 }],
+                },
+                config: crate::config::GameConfig::default(),
+                revealed: vec![vec![Die::Six], vec![]],
+                community: vec![Die::One],
+                constraint: None,
+                hands: vec![vec![Die::Six, Die::Two, Die::Three], vec![]],
+                clocks: hashmap!{ 1 => 4500 },
+                honesty: hashmap!{ 4 => (1, 2) },
+            };
+            let value = state.to_json();
+            assert_eq!(json!(1), value["version"]);
+            assert_eq!(json!("perudo"), value["variant"]);
+            assert_eq!(json!({"quantity": 1, "value": 2}), value["history"][0]["bet"]);
+            let read = GameState::<PerudoBet>::from_json(&value).unwrap();
+            assert_eq!(state.player_ids, read.player_ids);
+            assert_eq!(state.history, read.history);
+            assert_eq!(state.hands, read.hands);
+            assert_eq!(state.revealed, read.revealed);
+            assert_eq!(state.community, read.community);
+            assert_eq!(state.total_num_items, read.total_num_items);
+            assert_eq!(state.clocks, read.clocks);
+            assert_eq!(state.honesty, read.honesty);
+            assert_eq!(value, read.to_json());
+        }
+
+        it "refuses positions it can't read" {
+            let value = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, crate::config::GameConfig::default()).to_json();
+            assert!(GameState::<ScrabrudoBet>::from_json(&value).is_err());
+            let mut future = value.clone();
+            future["version"] = json!(SCHEMA_VERSION + 1);
+            assert!(GameState::<PerudoBet>::from_json(&future).is_err());
+            let view = GameState::<PerudoBet>::from_json(&value).unwrap().view_for(0).to_json();
+            assert!(PerudoGame::from_json(&view).is_err());
+        }
+
+        it "round-trips a game" {
+            let game = PerudoGame::new(3, 2, hashset!{}, OpeningPolicy::Likeliest, crate::config::GameConfig::default())
+                .with_first_player(2)
+                .run_turn()
+                .unwrap();
+            let read = PerudoGame::from_json(&game.to_json()).unwrap();
+            assert_eq!(game.current_index(), read.current_index());
+            assert_eq!(game.current_outcome(), read.current_outcome());
+            assert_eq!(game.all_items(), read.all_items());
+            assert_eq!(game.to_json(), read.to_json());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+/// Matches: series of games played by the same seats.
+use crate::config::*;
+use crate::error::*;
+use crate::game::*;
+use crate::opening::*;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+/// A best-of-N series of games, persisted between games so that it can be resumed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    /// The most games the match can last.
+    pub num_games: usize,
+
+    /// The number of games played so far.
+    pub games_played: usize,
+
+    /// The number of games won by each player, keyed by player ID.
+    pub scores: Vec<usize>,
+}
+
+impl Match {
+    pub fn new(num_players: usize, num_games: usize) -> Self {
+        Self {
+            num_games: num_games,
+            games_played: 0,
+            scores: vec![0; num_players],
+        }
+    }
+
+    /// Loads a match from the given path.
+    pub fn load(path: &str) -> Result<Self, ScrabrudoError> {
+        let f = File::open(path)?;
+        Ok(serde_json::from_reader(f)?)
+    }
+
+    /// Loads the match at the given path if there is one, or starts a new one.
+    pub fn load_or_new(
+        path: &str,
+        num_players: usize,
+        num_games: usize,
+    ) -> Result<Self, ScrabrudoError> {
+        if !Path::new(path).exists() {
+            return Ok(Self::new(num_players, num_games));
+        }
+        let loaded = Self::load(path)?;
+        if loaded.scores.len() != num_players {
+            return Err(ScrabrudoError::InvalidOption(format!(
+                "Match at {} has {} players, not {}",
+                path,
+                loaded.scores.len(),
+                num_players
+            )));
+        }
+        info!("Resuming match after {} games", loaded.games_played);
+        Ok(loaded)
+    }
+
+    /// Saves the match to the given path.
+    pub fn save(&self, path: &str) -> Result<(), ScrabrudoError> {
+        let f = File::create(path)?;
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+
+    /// The number of games needed to win the match outright.
+    pub fn games_to_win(&self) -> usize {
+        self.num_games / 2 + 1
+    }
+
+    /// The index of the player who makes the first bet in the next game.
+    /// This rotates around the table so that no seat keeps the advantage.
+    pub fn first_player(&self) -> usize {
+        self.games_played % self.scores.len()
+    }
+
+    /// Whether all games have been played or a player has already won a majority.
+    pub fn is_over(&self) -> bool {
+        self.games_played >= self.num_games
+            || self.scores.iter().any(|s| *s >= self.games_to_win())
+    }
+
+    /// Records a game won by the given player.
+    pub fn record_win(&mut self, winner_id: usize) {
+        self.scores[winner_id] += 1;
+        self.games_played += 1;
+    }
+
+    /// The IDs of the players with the most wins.
+    pub fn leaders(&self) -> Vec<usize> {
+        let best = self.scores.iter().max().cloned().unwrap_or(0);
+        (0..self.scores.len())
+            .filter(|i| self.scores[*i] == best)
+            .collect()
+    }
+
+    /// Plays out the rest of the match, saving after every game if a path is given.
+    pub fn play<G: Game>(
+        &mut self,
+        items_per_player: usize,
+        human_indices: &HashSet<usize>,
+        opening: &OpeningPolicy,
+        config: &GameConfig,
+        save_path: Option<&str>,
+    ) -> Result<(), ScrabrudoError> {
+        while !self.is_over() {
+            info!(
+                "Game {} of up to {}, player {} starts",
+                self.games_played + 1,
+                self.num_games,
+                self.first_player()
+            );
+            let game = G::new(
+                self.scores.len(),
+                items_per_player,
+                human_indices.clone(),
+                opening.clone(),
+                config.clone(),
+            )
+            .with_first_player(self.first_player())
+            .run()?;
+            self.record_win(game.winner().unwrap());
+            info!("{}", self);
+            if let Some(path) = save_path {
+                self.save(path)?;
+            }
+        }
+        info!("Match over, won by {:?}", self.leaders());
+        Ok(())
+    }
+}
+
+impl fmt::Display for Match {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Match score after {} of {} games: {}",
+            self.games_played,
+            self.num_games,
+            self.scores
+                .iter()
+                .enumerate()
+                .map(|(i, s)| format!("{}: {}", i, s))
+                .collect::<Vec<String>>()
+                .join(" | ")
+        )
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "matches" {
+        it "rotates the first player" {
+            let mut m = Match::new(3, 5);
+            assert_eq!(0, m.first_player());
+            m.record_win(2);
+            assert_eq!(1, m.first_player());
+            m.record_win(2);
+            m.record_win(0);
+            assert_eq!(0, m.first_player());
+        }
+
+        it "ends once a majority is won" {
+            let mut m = Match::new(2, 5);
+            m.record_win(1);
+            m.record_win(1);
+            assert!(!m.is_over());
+            m.record_win(1);
+            assert!(m.is_over());
+            assert_eq!(vec![1], m.leaders());
+        }
+
+        it "ends once all games are played" {
+            let mut m = Match::new(3, 2);
+            m.record_win(0);
+            m.record_win(1);
+            assert!(m.is_over());
+            assert_eq!(vec![0, 1], m.leaders());
+        }
+
+        it "saves and resumes" {
+            let mut m = Match::new(2, 3);
+            m.record_win(1);
+            m.save("/tmp/match1.json").unwrap();
+            assert_eq!(m, Match::load_or_new("/tmp/match1.json", 2, 3).unwrap());
+        }
+
+        it "plays a short perudo match" {
+            let mut m = Match::new(2, 3);
+            m.play::<PerudoGame>(
+                1,
+                &hashset!{},
+                &OpeningPolicy::Likeliest,
+                &GameConfig::default(),
+                None).unwrap();
+            assert!(m.is_over());
+            assert_eq!(m.games_played, m.scores.iter().sum::<usize>());
+            assert!(m.games_played >= 2);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+/// Stepping through games a turn at a time, to debug the engine and the AI.
+use crate::bet::*;
+use crate::dict;
+use crate::error::*;
+use crate::game::*;
+use crate::prob::*;
+use crate::save::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref STEPPING: Mutex<bool> = Mutex::new(false);
+}
+
+/// How many of the likeliest bets to show.
+pub const NUM_BETS_SHOWN: usize = 10;
+
+/// Turns pausing before every turn on or off.
+pub fn init_step(on: bool) {
+    let mut stepping = STEPPING.lock().unwrap();
+    *stepping = on;
+}
+
+/// Whether games pause before every turn.
+pub fn stepping() -> bool {
+    *STEPPING.lock().unwrap()
+}
+
+/// A command given while paused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepCommand {
+    /// Play the next turn.
+    Continue,
+
+    /// Stop pausing and play the game out.
+    Run,
+
+    /// Show every player's hand.
+    ShowHands,
+
+    /// Show how likely the player to move thinks each choice is to be right.
+    ShowProbabilities,
+
+    /// Save the game to the given path.
+    Dump(String),
+
+    /// Switch to the dictionary and lookup at the given paths from the next round.
+    Reload(String, String),
+}
+
+impl StepCommand {
+    /// Parses a command as typed, where an empty line continues.
+    pub fn parse(line: &str) -> Option<Self> {
+        match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            [] | ["c"] => Some(StepCommand::Continue),
+            ["r"] => Some(StepCommand::Run),
+            ["h"] => Some(StepCommand::ShowHands),
+            ["p"] => Some(StepCommand::ShowProbabilities),
+            ["d", path] => Some(StepCommand::Dump(path.to_string())),
+            ["reload", dict_path, lookup_path] => Some(StepCommand::Reload(
+                dict_path.to_string(),
+                lookup_path.to_string(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The probabilities the player to move sees for challenging and for the likeliest bets they
+/// could make, likeliest first.
+pub fn probabilities<G: Game>(game: &G) -> Vec<(String, f64)> {
+    let state = game.state();
+    let player = game.players()[game.current_index()].cloned();
+    let mut choices = vec![];
+    let bets = match game.current_outcome() {
+        TurnOutcome::Bet(bet) => {
+            choices.push((
+                "Perudo".to_string(),
+                bet.prob(&state, ProbVariant::Perudo, player.cloned()),
+            ));
+            choices.push((
+                "Palafico".to_string(),
+                bet.prob(&state, ProbVariant::Palafico, player.cloned()),
+            ));
+            bet.all_above(&state)
+                .map(|b| {
+                    let p = b.prob(&state, ProbVariant::Bet, player.cloned());
+                    (b, p)
+                })
+                .collect()
+        }
+        _ => G::B::ranked_bets(&state, player.cloned()),
+    };
+    let mut bets = rank_outcomes(bets);
+    bets.reverse();
+    choices.extend(
+        bets.into_iter()
+            .take(NUM_BETS_SHOWN)
+            .map(|(b, p)| (format!("{}", b), p)),
+    );
+    choices
+}
+
+/// Waits for commands before the next turn, until told to carry on.
+pub fn pause<G: Game>(game: &G) -> Result<(), ScrabrudoError> {
+    loop {
+        info!(
+            "Player {} to move after {:?}",
+            game.players()[game.current_index()].id(),
+            game.current_outcome()
+        );
+        info!("Enter=continue, r=run to the end, h=hands, p=probabilities, d <path>=dump state, reload <dict> <lookup>=switch dictionary:");
+        let line = game.config().input.read_line(None)?;
+        match StepCommand::parse(&line) {
+            Some(StepCommand::Continue) => return Ok(()),
+            Some(StepCommand::Run) => {
+                init_step(false);
+                return Ok(());
+            }
+            Some(StepCommand::ShowHands) => info!("{}", game),
+            Some(StepCommand::ShowProbabilities) => {
+                for (choice, p) in probabilities(game) {
+                    info!("{}: {:.3}", choice, p);
+                }
+            }
+            Some(StepCommand::Dump(path)) => match save_game(game, &path) {
+                Ok(()) => info!("Game saved to {}", path),
+                Err(e) => info!("Couldn't save game to {}: {}", path, e),
+            },
+            Some(StepCommand::Reload(dict_path, lookup_path)) => {
+                match dict::request_reload(
+                    game.config(),
+                    &dict_path,
+                    &lookup_path,
+                    game.state().total_num_items,
+                ) {
+                    Ok(()) => info!("Switching to {} from the next round", dict_path),
+                    Err(e) => info!("Couldn't switch to {}: {}", dict_path, e),
+                }
+            }
+            None => info!("Unknown command: {}", line.trim()),
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::config::*;
+    use crate::die::*;
+    use crate::opening::*;
+
+    describe "step commands" {
+        it "parses commands" {
+            assert_eq!(Some(StepCommand::Continue), StepCommand::parse("\n"));
+            assert_eq!(Some(StepCommand::Continue), StepCommand::parse("c"));
+            assert_eq!(Some(StepCommand::Run), StepCommand::parse("r"));
+            assert_eq!(Some(StepCommand::ShowHands), StepCommand::parse("h"));
+            assert_eq!(Some(StepCommand::ShowProbabilities), StepCommand::parse("p"));
+            assert_eq!(Some(StepCommand::Dump("/tmp/game.json".into())), StepCommand::parse("d /tmp/game.json"));
+            assert_eq!(
+                Some(StepCommand::Reload("words.txt".into(), "words.sstable".into())),
+                StepCommand::parse("reload words.txt words.sstable"));
+            assert_eq!(None, StepCommand::parse("reload words.txt"));
+            assert_eq!(None, StepCommand::parse("d"));
+            assert_eq!(None, StepCommand::parse("x"));
+        }
+    }
+
+    describe "probabilities" {
+        it "shows challenges and bets once there is a bet" {
+            let game = PerudoGame::new(2, 2, hashset!{}, OpeningPolicy::Likeliest, GameConfig::default())
+                .apply(TurnOutcome::Bet(PerudoBet { value: Die::Two, quantity: 1 }))
+                .unwrap();
+            let choices = probabilities(&game);
+            assert_eq!("Perudo", choices[0].0);
+            assert_eq!("Palafico", choices[1].0);
+            assert!(choices.len() > 2 && choices.len() <= 2 + NUM_BETS_SHOWN);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+/// Turn summaries: a line after each AI move saying what it did, and how much of the table its
+/// bet lays claim to, so that a game can be followed without reading hands out of the debug log.
+use crate::bet::*;
+use crate::game::*;
+use crate::lang::*;
+
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref SUMMARY: Mutex<Verbosity> = Mutex::new(Verbosity::Off);
+}
+
+/// How much is said after each AI move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verbosity {
+    /// Nothing, as in simulations.
+    Off,
+
+    /// The move, with a Scrabrudo word's length and score.
+    Brief,
+
+    /// The move, and how much of the table the bet it makes or challenges claims.
+    Full,
+}
+
+impl Verbosity {
+    /// Parses a verbosity from its command-line name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Verbosity::Off),
+            "brief" => Some(Verbosity::Brief),
+            "full" => Some(Verbosity::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Sets how much is said after each AI move.
+pub fn init_summary(verbosity: Verbosity) {
+    let mut summary = SUMMARY.lock().unwrap();
+    *summary = verbosity;
+}
+
+/// Gets how much is said after each AI move.
+pub fn summary() -> Verbosity {
+    *SUMMARY.lock().unwrap()
+}
+
+/// The items bets of the given kind are about.
+fn items_of<B: Bet>() -> Items {
+    match B::VARIANT {
+        "perudo" => Items::Dice,
+        "equation" => Items::Symbols,
+        _ => Items::Tiles,
+    }
+}
+
+/// The line summing up the given move by the given player in the given state, as it was before
+/// the move, at the given verbosity, or None if there's nothing to say.
+pub fn summarize<B: Bet>(
+    state: &GameState<B>,
+    player_id: usize,
+    outcome: &TurnOutcome<B>,
+    verbosity: Verbosity,
+) -> Option<String> {
+    if verbosity == Verbosity::Off {
+        return None;
+    }
+    let (line, claimed) = match outcome {
+        TurnOutcome::Bet(bet) => (
+            Msg::SummaryBet {
+                player_id: player_id,
+                bet: bet.to_string(),
+                detail: bet.detail(),
+            },
+            bet,
+        ),
+        TurnOutcome::Perudo | TurnOutcome::Palafico => {
+            let bet = state.standing_bet()?;
+            (
+                Msg::SummaryCall {
+                    player_id: player_id,
+                    palafico: *outcome == TurnOutcome::Palafico,
+                    bet: bet.to_string(),
+                },
+                bet,
+            )
+        }
+        TurnOutcome::Exchange(items) => {
+            let line = Msg::SummaryExchange {
+                player_id: player_id,
+                num_items: items.len(),
+            };
+            return Some(line.to_string());
+        }
+        _ => return None,
+    };
+    if verbosity == Verbosity::Brief {
+        return Some(line.to_string());
+    }
+    let claim = Msg::SummaryClaim {
+        line: line.to_string(),
+        size: claimed.size(),
+        total: state.total_num_items,
+        items: items_of::<B>(),
+    };
+    Some(claim.to_string())
+}
+
+/// Shows the line summing up the given AI move, if summaries are on.
+pub fn summarize_move<B: Bet>(state: &GameState<B>, player_id: usize, outcome: &TurnOutcome<B>) {
+    if let Some(line) = summarize(state, player_id, outcome, summary()) {
+        info!("{}", line);
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::*;
+    use crate::testing::*;
+
+    describe "turn summaries" {
+        fn state() -> GameState<PerudoBet> {
+            GameState {
+                current_index: 1,
+                history: hashmap!{ 0 => vec![perudo_bet(3, Die::Four)] },
+                ..game_state(vec![5, 5])
+            }
+        }
+
+        it "reads verbosity names" {
+            assert_eq!(Some(Verbosity::Brief), Verbosity::from_name("brief"));
+            assert_eq!(None, Verbosity::from_name("loud"));
+        }
+
+        it "sums up moves" {
+            let bet = TurnOutcome::Bet(PerudoBet { value: Die::Six, quantity: 4 });
+            assert_eq!(None, summarize(&state(), 1, &bet, Verbosity::Off));
+            assert_eq!(Some("AI 1 bets 4 Sixs".into()), summarize(&state(), 1, &bet, Verbosity::Brief));
+            assert_eq!(
+                Some("AI 1 bets 4 Sixs, claiming 4 of the 10 dice on the table (40%)".into()),
+                summarize(&state(), 1, &bet, Verbosity::Full));
+            assert_eq!(
+                Some("AI 1 calls Perudo on 3 Fours, claiming 3 of the 10 dice on the table (30%)".into()),
+                summarize(&state(), 1, &TurnOutcome::Perudo, Verbosity::Full));
+            assert_eq!(
+                Some("AI 1 exchanges 2 items".into()),
+                summarize(&state(), 1, &TurnOutcome::Exchange(vec![Die::Two, Die::Five]), Verbosity::Full));
+        }
+
+        it "gives a word's length and score" {
+            let bet = TurnOutcome::Bet(ScrabrudoBet::from_word(&"quiz".into()));
+            let state = game_state::<ScrabrudoBet>(vec![4, 4]);
+            assert_eq!(
+                Some("AI 0 bets 'quiz': 4 letters, 22 points, claiming 4 of the 8 tiles on the table (50%)".into()),
+                summarize(&state, 0, &bet, Verbosity::Full));
+        }
+    }
+}
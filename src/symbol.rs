@@ -0,0 +1,273 @@
+/// Definition of a single equation tile, a digit or an operator, the identities a row of them can
+/// make, and the exact chance of finding some among tiles unseen.
+use crate::error::*;
+use crate::hand::*;
+use crate::rng::*;
+
+use rand::distributions::Standard;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+
+/// How many of each symbol are in the bag, in the order of Symbol::all. Every identity needs an
+/// equals sign and an operator, so there are more of those than of any one digit.
+const SYMBOL_COUNTS: [u32; 14] = [2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 2, 5];
+
+/// The largest number either side of an operator may be.
+pub const MAX_OPERAND: u32 = 9;
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Hash, Serialize, Deserialize)]
+pub enum Symbol {
+    Zero,
+    One,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Plus,
+    Minus,
+    Times,
+    Equals,
+}
+
+impl Holdable for Symbol {
+    fn get_random() -> Self {
+        rng_for("deal").gen()
+    }
+}
+
+impl Symbol {
+    /// Every symbol, digits first.
+    pub fn all() -> Vec<Symbol> {
+        vec![
+            Symbol::Zero,
+            Symbol::One,
+            Symbol::Two,
+            Symbol::Three,
+            Symbol::Four,
+            Symbol::Five,
+            Symbol::Six,
+            Symbol::Seven,
+            Symbol::Eight,
+            Symbol::Nine,
+            Symbol::Plus,
+            Symbol::Minus,
+            Symbol::Times,
+            Symbol::Equals,
+        ]
+    }
+
+    /// Where the symbol comes in Symbol::all.
+    pub fn as_usize(&self) -> usize {
+        Symbol::all().iter().position(|s| s == self).unwrap()
+    }
+
+    /// The symbol for a single digit.
+    pub fn digit(d: u32) -> Symbol {
+        Symbol::all()[d as usize].clone()
+    }
+
+    /// The digit this symbol stands for, if it is one.
+    pub fn value(&self) -> Option<u32> {
+        match self.as_usize() {
+            i if i < 10 => Some(i as u32),
+            _ => None,
+        }
+    }
+
+    /// The character the symbol is written as.
+    pub fn char(&self) -> char {
+        match self {
+            Symbol::Plus => '+',
+            Symbol::Minus => '-',
+            Symbol::Times => '*',
+            Symbol::Equals => '=',
+            digit => std::char::from_digit(digit.value().unwrap(), 10).unwrap(),
+        }
+    }
+
+    /// Reads a symbol from its character, taking x for times as well as *.
+    pub fn from_char(c: char) -> Result<Self, ScrabrudoError> {
+        Ok(match c {
+            '+' => Symbol::Plus,
+            '-' => Symbol::Minus,
+            '*' | 'x' => Symbol::Times,
+            '=' => Symbol::Equals,
+            c => match c.to_digit(10) {
+                Some(d) => Symbol::digit(d),
+                None => return Err(ScrabrudoError::InvalidTile(c)),
+            },
+        })
+    }
+
+    /// The chance of any one symbol dealt being this one.
+    pub fn prob(&self) -> f64 {
+        let total: u32 = SYMBOL_COUNTS.iter().sum();
+        SYMBOL_COUNTS[self.as_usize()] as f64 / total as f64
+    }
+}
+
+// Make it possible to generate random Symbols, as often as they are in the bag.
+impl rand::distributions::Distribution<Symbol> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Symbol {
+        let total: u32 = SYMBOL_COUNTS.iter().sum();
+        let mut bound = rng.gen_range(0, total);
+        for (i, count) in SYMBOL_COUNTS.iter().enumerate() {
+            if bound < *count {
+                return Symbol::all()[i].clone();
+            }
+            bound -= count;
+        }
+        panic!("Should not reach here, we covered every case above");
+    }
+}
+
+/// The symbols writing out the given number.
+pub fn number_symbols(n: u32) -> Vec<Symbol> {
+    n.to_string()
+        .chars()
+        .map(|c| Symbol::from_char(c).unwrap())
+        .collect()
+}
+
+/// Reads a number written without leading zeros, or None if the symbols aren't one.
+fn read_number(symbols: &[Symbol]) -> Option<u32> {
+    if symbols.is_empty() || (symbols.len() > 1 && symbols[0] == Symbol::Zero) {
+        return None;
+    }
+    symbols
+        .iter()
+        .try_fold(0u32, |n, s| n.checked_mul(10)?.checked_add(s.value()?))
+}
+
+/// Whether the symbols make a true identity, written as a+b=c, a-b=c or a*b=c with no leading
+/// zeros. There are no negative numbers, so nothing less than zero can be on the right.
+pub fn is_identity(symbols: &[Symbol]) -> bool {
+    let equals = match symbols.iter().position(|s| *s == Symbol::Equals) {
+        Some(equals) => equals,
+        None => return false,
+    };
+    let (left, right) = (&symbols[..equals], &symbols[equals + 1..]);
+    let operator = match left.iter().position(|s| s.value().is_none()) {
+        Some(operator) => operator,
+        None => return false,
+    };
+    let (a, b, c) = match (
+        read_number(&left[..operator]),
+        read_number(&left[operator + 1..]),
+        read_number(right),
+    ) {
+        (Some(a), Some(b), Some(c)) => (a, b, c),
+        _ => return false,
+    };
+    let result = match left[operator] {
+        Symbol::Plus => a.checked_add(b),
+        Symbol::Minus => a.checked_sub(b),
+        Symbol::Times => a.checked_mul(b),
+        _ => None,
+    };
+    result == Some(c)
+}
+
+/// Every identity of operands up to MAX_OPERAND. Even the shortest needs five symbols, so they
+/// aren't limited to the number on the table, to leave a bet to make once there are fewer.
+pub fn identities() -> Vec<Vec<Symbol>> {
+    let operators = vec![Symbol::Plus, Symbol::Minus, Symbol::Times];
+    iproduct!(0..=MAX_OPERAND, operators.into_iter(), 0..=MAX_OPERAND)
+        .filter_map(|(a, operator, b)| {
+            let c = match operator {
+                Symbol::Plus => a + b,
+                Symbol::Minus => a.checked_sub(b)?,
+                _ => a * b,
+            };
+            let mut symbols = number_symbols(a);
+            symbols.push(operator);
+            symbols.extend(number_symbols(b));
+            symbols.push(Symbol::Equals);
+            symbols.extend(number_symbols(c));
+            Some(symbols)
+        })
+        .collect()
+}
+
+/// The chance of finding at least, or if exact exactly, the given count of each symbol among n
+/// dealt from the bag, a count of zero asking for none of the symbol if exact. Deals a symbol at
+/// a time, keeping the chance of every count of each symbol found so far, up to as many as are
+/// sought.
+pub fn symbol_prob(sought: &[(Symbol, usize)], n: usize, exact: bool) -> f64 {
+    let p_other = 1.0 - sought.iter().map(|(symbol, _)| symbol.prob()).sum::<f64>();
+    let mut states = hashmap! { vec![0; sought.len()] => 1.0 };
+    for _ in 0..n {
+        let mut next = HashMap::new();
+        for (state, mass) in states {
+            for (j, (symbol, count)) in sought.iter().enumerate() {
+                let mut found = state.clone();
+                if found[j] < *count {
+                    found[j] += 1;
+                } else if exact {
+                    // Too many of the symbol for a Palafico.
+                    continue;
+                }
+                *next.entry(found).or_insert(0.0) += mass * symbol.prob();
+            }
+            *next.entry(state).or_insert(0.0) += mass * p_other;
+        }
+        states = next;
+    }
+    states
+        .into_iter()
+        .filter(|(found, _)| found.iter().zip(sought).all(|(f, (_, count))| f == count))
+        .map(|(_, mass)| mass)
+        .sum()
+}
+
+#[cfg(test)]
+speculate! {
+    fn symbols(s: &str) -> Vec<Symbol> {
+        s.chars().map(|c| Symbol::from_char(c).unwrap()).collect()
+    }
+
+    describe "symbols" {
+        it "reads and writes symbols" {
+            assert_eq!("2+3=5", symbols("2+3=5").iter().map(|s| s.char()).collect::<String>());
+            assert_eq!(Symbol::Times, Symbol::from_char('x').unwrap());
+            assert_eq!(Some(7), Symbol::Seven.value());
+            assert!(Symbol::from_char('a').is_err());
+        }
+
+        it "checks identities" {
+            assert!(is_identity(&symbols("2+3=5")));
+            assert!(is_identity(&symbols("7*8=56")));
+            assert!(is_identity(&symbols("9-9=0")));
+            assert!(!is_identity(&symbols("2+3=6")));
+            assert!(!is_identity(&symbols("3-5=2")));
+            assert!(!is_identity(&symbols("2+3=05")));
+            assert!(!is_identity(&symbols("2+3")));
+            assert!(!is_identity(&symbols("2+=2")));
+        }
+
+        it "lists every identity" {
+            let all = identities();
+            assert!(all.contains(&symbols("7*8=56")));
+            assert!(all.iter().all(|i| is_identity(i)));
+            assert!(!all.contains(&symbols("3-5=2")));
+        }
+
+        it "finds symbols exactly" {
+            let sought = vec![(Symbol::One, 2), (Symbol::Plus, 1)];
+            let p_one = Symbol::One.prob();
+            let p_plus = Symbol::Plus.prob();
+            assert!((symbol_prob(&sought, 3, false) - 3.0 * p_one * p_one * p_plus).abs() < 1e-12);
+            assert!(symbol_prob(&sought, 10, true) < symbol_prob(&sought, 10, false));
+            assert_eq!(0.0, symbol_prob(&sought, 2, false));
+            let none = vec![(Symbol::Equals, 0)];
+            assert!((symbol_prob(&none, 2, true) - (1.0 - Symbol::Equals.prob()).powi(2)).abs() < 1e-12);
+        }
+    }
+}
@@ -1,5 +1,13 @@
 /// Test utils.
+#[cfg(test)]
+use crate::bet::*;
+#[cfg(test)]
+use crate::config::*;
 use crate::dict;
+#[cfg(test)]
+use crate::die::*;
+#[cfg(test)]
+use crate::game::*;
 use std::sync::Mutex;
 
 lazy_static! {
@@ -10,8 +18,37 @@ pub fn set_up() {
     let mut state = SET_UP_DONE.lock().unwrap();
     if !*state {
         pretty_env_logger::try_init();
-        dict::init_dict("data/google-10000-english.txt");
-        dict::init_lookup("data/simple_5_1000.sstable");
+        dict::init_dict("data/google-10000-english.txt").unwrap();
+        dict::init_lookup("data/simple_5_1000.sstable").unwrap();
+        dict::init_frequencies("data/google-10000-english.txt").unwrap();
         *state = true;
     }
 }
+
+/// A fresh game state with the given items in each seat, for tests to update as they need.
+#[cfg(test)]
+pub fn game_state<B: Bet>(num_items_per_player: Vec<usize>) -> GameState<B> {
+    GameState {
+        total_num_items: num_items_per_player.iter().sum(),
+        revealed: num_items_per_player.iter().map(|_| vec![]).collect(),
+        player_ids: (0..num_items_per_player.len()).collect(),
+        num_items_per_player: num_items_per_player,
+        current_index: 0,
+        history: hashmap! {},
+        config: GameConfig::default(),
+        community: vec![],
+        constraint: None,
+        hands: vec![],
+        clocks: hashmap! {},
+        honesty: hashmap! {},
+    }
+}
+
+/// A Perudo bet on the given quantity of the given value.
+#[cfg(test)]
+pub fn perudo_bet(quantity: usize, value: Die) -> PerudoBet {
+    PerudoBet {
+        value: value,
+        quantity: quantity,
+    }
+}
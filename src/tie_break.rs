@@ -0,0 +1,135 @@
+/// Tie-breaking: how the AI chooses between moves it rates equally, either at random, from the
+/// seeded generator if there is one, or always the same way, so that a game can be played again
+/// move for move whatever else draws from the generator.
+use crate::bet::*;
+use crate::config::*;
+use crate::game::*;
+use crate::rng::*;
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref DEFAULT_TIE_BREAK: Mutex<TieBreak> = Mutex::new(TieBreak::default());
+}
+
+/// A policy for choosing between equally good moves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum TieBreak {
+    /// Any of them, at random.
+    #[default]
+    Random,
+
+    /// The first of them in canonical order: challenges before bets, and lower bets first.
+    Canonical,
+}
+
+impl TieBreak {
+    /// Parses a policy from its command-line name, "random" or "canonical".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(TieBreak::Random),
+            "canonical" => Some(TieBreak::Canonical),
+            _ => None,
+        }
+    }
+
+    /// Chooses one of the given tied items, ordered canonically by the given key, if there are
+    /// any.
+    pub fn pick_by<T: Clone, K: Ord>(&self, tied: Vec<T>, key: impl Fn(&T) -> K) -> Option<T> {
+        match self {
+            TieBreak::Random => {
+                let mut rng = rng_for("tie_break");
+                tied.choose(&mut rng).cloned()
+            }
+            TieBreak::Canonical => tied.into_iter().min_by_key(key),
+        }
+    }
+
+    /// Chooses one of the given tied bets, if there are any.
+    pub fn pick_bet<B: Bet>(&self, tied: Vec<Box<B>>) -> Option<Box<B>> {
+        self.pick_by(tied, |b| b.clone())
+    }
+
+    /// Chooses one of the given tied outcomes, if there are any.
+    pub fn pick_outcome<B: Bet>(&self, tied: Vec<TurnOutcome<B>>) -> Option<TurnOutcome<B>> {
+        self.pick_by(tied, canonical_order)
+    }
+}
+
+/// Where an outcome comes in canonical order: challenges first, then bets from lowest to highest,
+/// then anything else.
+fn canonical_order<B: Bet>(outcome: &TurnOutcome<B>) -> (usize, Option<B>) {
+    match outcome {
+        TurnOutcome::Perudo => (0, None),
+        TurnOutcome::Palafico => (1, None),
+        TurnOutcome::Bet(bet) => (2, Some(bet.clone())),
+        _ => (3, None),
+    }
+}
+
+/// Sets the tie-break for every player without one of their own in their game's config.
+pub fn init_tie_break(policy: TieBreak) {
+    *DEFAULT_TIE_BREAK.lock().unwrap() = policy;
+}
+
+/// Gets the tie-break the given player plays by in a game of the given config.
+pub fn tie_break(config: &GameConfig, player_id: usize) -> TieBreak {
+    match config
+        .seat(player_id)
+        .and_then(|seat| seat.tie_break.clone())
+    {
+        Some(policy) => policy,
+        None => DEFAULT_TIE_BREAK.lock().unwrap().clone(),
+    }
+}
+
+/// Parses a tie-break for one player or for all from its command-line form, e.g. "1:canonical"
+/// or "random".
+pub fn parse_tie_break(assignment: &str) -> Option<(Option<usize>, TieBreak)> {
+    let mut split = assignment.splitn(2, ':');
+    match (split.next().map(|id| id.parse::<usize>()), split.next()) {
+        (Some(Ok(player_id)), Some(name)) => Some((Some(player_id), TieBreak::from_name(name)?)),
+        _ => Some((None, TieBreak::from_name(assignment)?)),
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::bet::PerudoBet;
+    use crate::die::Die;
+
+    describe "tie-breaks" {
+        it "parses tie-breaks" {
+            assert_eq!(Some((None, TieBreak::Canonical)), parse_tie_break("canonical"));
+            assert_eq!(Some((Some(1), TieBreak::Random)), parse_tie_break("1:random"));
+            assert_eq!(None, parse_tie_break("1:coin"));
+        }
+
+        it "breaks ties the same way every time when canonical" {
+            let bet = |q| PerudoBet { value: Die::Three, quantity: q };
+            let tied = vec![
+                TurnOutcome::Bet(bet(2)),
+                TurnOutcome::Perudo,
+                TurnOutcome::Bet(bet(1)),
+            ];
+            assert_eq!(Some(TurnOutcome::Perudo), TieBreak::Canonical.pick_outcome(tied.clone()));
+            assert_eq!(
+                Some(TurnOutcome::Bet(bet(1))),
+                TieBreak::Canonical.pick_outcome(tied[..1].iter().chain(&tied[2..]).cloned().collect()));
+            assert_eq!(Some(Box::new(bet(1))), TieBreak::Canonical.pick_bet(vec![Box::new(bet(2)), Box::new(bet(1))]));
+            assert_eq!(None, TieBreak::Canonical.pick_bet::<PerudoBet>(vec![]));
+        }
+
+        it "keeps each player's tie-break to their game" {
+            let mut config = GameConfig::default();
+            config.seat_mut(1).tie_break = Some(TieBreak::Canonical);
+            assert_eq!(TieBreak::Canonical, tie_break(&config, 1));
+            assert_eq!(TieBreak::Random, tie_break(&config, 2));
+            assert_eq!(TieBreak::Random, tie_break(&GameConfig::default(), 1));
+        }
+    }
+}
@@ -1,13 +1,27 @@
 /// Definition of a single tile.
+use crate::error::*;
 use crate::hand::*;
+use crate::rng::*;
 use crate::testing;
 
 use rand::distributions::Standard;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use speculate::speculate;
+use std::borrow::Cow;
 use std::cmp::Ord;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::sync::RwLock;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
+lazy_static! {
+    // Read for every tile dealt, from many threads at once when precomputing.
+    static ref TILE_SET: RwLock<TileSet> = RwLock::new(TileSet::default());
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Hash, Serialize, Deserialize)]
 pub enum Tile {
     A,
     B,
@@ -36,17 +50,25 @@ pub enum Tile {
     Y,
     Z,
     Blank,
+
+    /// QU on a single tile, as in some UK variants.
+    Qu,
+
+    /// TH on a single tile.
+    Th,
 }
 
 impl Holdable for Tile {
     fn get_random() -> Self {
-        rand::random()
+        rng_for("deal").gen()
     }
 }
 
 impl Tile {
-    pub fn from_char(c: char) -> Self {
-        match c {
+    /// Reads a tile from the char it is written as, letter pairs being written as the upper case
+    /// of their first letter.
+    pub fn from_char(c: char) -> Result<Self, ScrabrudoError> {
+        Ok(match c {
             'a' => Tile::A,
             'b' => Tile::B,
             'c' => Tile::C,
@@ -73,10 +95,14 @@ impl Tile {
             'x' => Tile::X,
             'y' => Tile::Y,
             'z' => Tile::Z,
-            _ => panic!(),
-        }
+            'Q' => Tile::Qu,
+            'T' => Tile::Th,
+            _ => return Err(ScrabrudoError::InvalidTile(c)),
+        })
     }
 
+    /// The char the tile is written as in notation and lookup keys, which for a letter pair is
+    /// the upper case of its first letter, so that every tile is a single char.
     pub fn char(&self) -> char {
         match &self {
             Tile::A => 'a',
@@ -105,22 +131,41 @@ impl Tile {
             Tile::X => 'x',
             Tile::Y => 'y',
             Tile::Z => 'z',
+            Tile::Qu => 'Q',
+            Tile::Th => 'T',
             Tile::Blank => panic!("Shouldn't be asking for a blank as a char"),
         }
     }
 
+    /// The letters the tile spells, two of them for a letter pair.
+    pub fn spelling(&self) -> String {
+        match self {
+            Tile::Qu => "qu".into(),
+            Tile::Th => "th".into(),
+            tile => tile.char().to_string(),
+        }
+    }
+
     pub fn as_usize(&self) -> usize {
-        if self == &Tile::Blank {
-            return 27;
+        match self {
+            Tile::Blank => 26,
+            Tile::Qu => 27,
+            Tile::Th => 28,
+            tile => (tile.char() as u32 - 'a' as u32) as usize,
         }
-        (self.char() as u32 - 'a' as u32) as usize
     }
 
     pub fn from_usize(u: usize) -> Tile {
-        if u == 26 {
-            return Tile::Blank;
+        match u {
+            26 => return Tile::Blank,
+            27 => return Tile::Qu,
+            28 => return Tile::Th,
+            _ => (),
+        }
+        match Tile::from_char((u as u8 + 'a' as u8) as char) {
+            Ok(tile) => tile,
+            Err(e) => panic!("No tile at index {}: {}", u, e),
         }
-        Tile::from_char((u as u8 + 'a' as u8) as char)
     }
 
     pub fn all() -> Vec<Tile> {
@@ -152,9 +197,23 @@ impl Tile {
             Tile::Y,
             Tile::Z,
             Tile::Blank,
+            Tile::Qu,
+            Tile::Th,
         ]
     }
 
+    /// The tiles spelling two letters at once.
+    pub fn pairs() -> Vec<Tile> {
+        vec![Tile::Qu, Tile::Th]
+    }
+
+    /// How likely this tile is to be dealt from the current tile set.
+    pub fn prob(&self) -> f64 {
+        let weights = tile_set().weights();
+        let total: u32 = weights.iter().sum();
+        weights[self.as_usize()] as f64 / total as f64
+    }
+
     pub fn score(&self) -> u32 {
         match &self {
             Tile::A => 1,
@@ -183,6 +242,8 @@ impl Tile {
             Tile::X => 8,
             Tile::Y => 4,
             Tile::Z => 10,
+            Tile::Qu => 10,
+            Tile::Th => 4,
             Tile::Blank => 0,
         }
     }
@@ -202,21 +263,190 @@ By hand, that's
 [9, 2, 2, 4, 12, 2, 3, 2, 9, 1, 1, 4, 2, 6, 8, 2, 1, 6, 4, 6, 4, 2, 2, 1, 2, 1, 2]
 */
 
+/// The relative number of each tile dealt in the standard English tile set, A to Z and then
+/// blanks.
+pub const DISTRIBUTION: [u32; 27] = [
+    9, 2, 2, 4, 12, 2, 3, 2, 9, 1, 1, 4, 2, 6, 8, 2, 1, 6, 4, 6, 4, 2, 2, 1, 2, 1,
+    10, // Number of blanks - TODO: Modulate.
+       // IF YOU CHANGE THIS YOU NEED TO RUN A NEW MONTE CARLO.
+];
+
+/// How many of each tile are dealt, relative to one another. Letter pairs are only dealt if given.
+/// Lookups are only valid for the tile set they were computed with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileSet {
+    /// The relative number of each tile, with any not given never being dealt.
+    pub counts: BTreeMap<Tile, u32>,
+
+    /// How letters other than A to Z are spelled in tiles, e.g. "ß" as "ss". Accents on letters
+    /// not given here are dropped.
+    #[serde(default = "default_letters")]
+    pub letters: BTreeMap<char, String>,
+}
+
+impl Default for TileSet {
+    fn default() -> Self {
+        Self {
+            counts: Tile::all()
+                .into_iter()
+                .zip(DISTRIBUTION.iter().cloned())
+                .collect(),
+            letters: default_letters(),
+        }
+    }
+}
+
+/// The spellings of letters that have no accent to drop.
+fn default_letters() -> BTreeMap<char, String> {
+    btreemap! {
+        'ß' => "ss".into(),
+        'æ' => "ae".into(),
+        'œ' => "oe".into(),
+        'ø' => "o".into(),
+        'ł' => "l".into(),
+    }
+}
+
+impl TileSet {
+    /// The relative number of each tile, A to Z, then blanks, then letter pairs.
+    pub fn weights(&self) -> Vec<u32> {
+        Tile::all()
+            .iter()
+            .map(|t| *self.counts.get(t).unwrap_or(&0))
+            .collect()
+    }
+
+    /// A fingerprint of the tile set that stays the same from build to build, so that lookups can
+    /// record which tile set they were computed with.
+    pub fn hash(&self) -> u64 {
+        // FNV-1a over the weights in tile order. Letter pairs only count once dealt, so tile sets
+        // without them keep the fingerprint they had before there were any.
+        let weights = self.weights();
+        let num_kinds = match self.pairs().is_empty() {
+            true => Tile::Blank.as_usize() + 1,
+            false => weights.len(),
+        };
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for weight in &weights[..num_kinds] {
+            for byte in weight.to_le_bytes().iter() {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    /// The letter pairs this tile set deals.
+    pub fn pairs(&self) -> Vec<Tile> {
+        Tile::pairs()
+            .into_iter()
+            .filter(|t| self.counts.get(t).is_some_and(|count| *count > 0))
+            .collect()
+    }
+
+    /// The tiles spelling a word already spelled as it is on tiles. Where this tile set deals a
+    /// letter pair, its letters are always taken as that one tile, so that "quit" is QU, I, T.
+    /// No pair ends with a letter another starts with, so a word splits into tiles only one way.
+    pub fn spell(&self, word: &str) -> Result<Vec<Tile>, ScrabrudoError> {
+        let pairs = self.pairs();
+        let chars = word.chars().collect::<Vec<char>>();
+        let mut tiles = Vec::with_capacity(chars.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let pair = pairs.iter().find(|pair| {
+                chars.len() > i + 1 && pair.spelling() == chars[i..i + 2].iter().collect::<String>()
+            });
+            match pair {
+                Some(pair) => {
+                    tiles.push(pair.clone());
+                    i += 2;
+                }
+                None => {
+                    tiles.push(Tile::from_char(chars[i])?);
+                    i += 1;
+                }
+            }
+        }
+        Ok(tiles)
+    }
+}
+
+/// Deals tiles from the given tile set from now on.
+pub fn init_tile_set(set: TileSet) {
+    let mut tile_set = TILE_SET.write().unwrap();
+    *tile_set = set;
+}
+
+/// The tile set tiles are currently dealt from.
+pub fn tile_set() -> TileSet {
+    TILE_SET.read().unwrap().clone()
+}
+
+/// Loads a tile set from a JSON file of tile counts.
+pub fn load_tile_set(tile_set_path: &str) -> Result<TileSet, ScrabrudoError> {
+    let f = File::open(tile_set_path)?;
+    let set: TileSet = serde_json::from_reader(f)?;
+    if set.weights().iter().sum::<u32>() == 0 {
+        return Err(ScrabrudoError::InvalidOption(format!(
+            "Tile set {} has no tiles",
+            tile_set_path
+        )));
+    }
+    Ok(set)
+}
+
+/// Spells a word as it is on tiles: in lower case, with letters other than A to Z spelled as the
+/// current tile set says, so that "CAFÉ" is "cafe". Words already spelled so are left as they are.
+pub fn normalize_word(word: &str) -> Cow<'_, str> {
+    if word.bytes().all(|b| b.is_ascii_lowercase() || b == b'+') {
+        return Cow::Borrowed(word);
+    }
+    let tile_set = TILE_SET.read().unwrap();
+    let mut normalized = String::with_capacity(word.len());
+    for c in word.nfc().flat_map(char::to_lowercase) {
+        match tile_set.letters.get(&c) {
+            Some(spelling) => normalized.push_str(spelling),
+            None => normalized.extend(c.to_string().nfd().filter(|c| !is_combining_mark(*c))),
+        }
+    }
+    Cow::Owned(normalized)
+}
+
+/// The tiles spelling a word however it was typed or written, failing on any character that
+/// still isn't a tile once normalized.
+pub fn parse_tiles(word: &str) -> Result<Vec<Tile>, ScrabrudoError> {
+    spell_tiles(&normalize_word(word))
+}
+
+/// The tiles spelling a word already spelled as it is on tiles, in the current tile set.
+pub fn spell_tiles(word: &str) -> Result<Vec<Tile>, ScrabrudoError> {
+    TILE_SET.read().unwrap().spell(word)
+}
+
+/// The key the given tiles are looked up by, their chars sorted, e.g. "act" for C, A, T. A letter
+/// pair is a single upper case char, so it can't be mistaken for its letters and sorts first, e.g.
+/// "Qit" for QU, I, T.
+pub fn tile_key(tiles: &[Tile]) -> String {
+    let mut chars = tiles.iter().map(|t| t.char()).collect::<Vec<char>>();
+    chars.sort();
+    chars.into_iter().collect()
+}
+
+/// The tiles of a lookup key, in the order they are keyed.
+pub fn key_tiles(key: &str) -> Result<Vec<Tile>, ScrabrudoError> {
+    key.chars().map(Tile::from_char).collect()
+}
+
 impl rand::distributions::Distribution<Tile> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tile {
-        //
-        let mut distribution: Vec<u32> = vec![
-            9, 2, 2, 4, 12, 2, 3, 2, 9, 1, 1, 4, 2, 6, 8, 2, 1, 6, 4, 6, 4, 2, 2, 1, 2, 1,
-            10, // Number of blanks - TODO: Modulate.
-               // IF YOU CHANGE THIS YOU NEED TO RUN A NEW MONTE CARLO.
-        ];
+        let mut distribution = TILE_SET.read().unwrap().weights();
         for i in 1..distribution.len() {
             distribution[i] += distribution[i - 1]
         }
 
         let bound = rng.gen_range(0, distribution.last().unwrap());
         for i in 0..distribution.len() {
-            if distribution[i] >= bound {
+            if distribution[i] > bound {
                 return Tile::from_usize(i);
             }
         }
@@ -233,11 +463,67 @@ speculate! {
         it "represents tiles as usize" {
             assert_eq!(0, Tile::A.as_usize());
             assert_eq!(25, Tile::Z.as_usize());
+            assert_eq!(26, Tile::Blank.as_usize());
         }
 
         it "creates tiles from usize" {
             assert_eq!(Tile::A, Tile::from_usize(0));
             assert_eq!(Tile::Z, Tile::from_usize(25));
+            assert_eq!(Tile::Blank, Tile::from_usize(26));
+        }
+
+        it "fingerprints tile sets" {
+            let set = TileSet { counts: btreemap!{ Tile::A => 1 }, letters: default_letters() };
+            assert_eq!(1, set.weights().iter().sum::<u32>());
+            assert_ne!(TileSet::default().hash(), set.hash());
+            assert_eq!(TileSet::default().hash(), TileSet::default().hash());
+        }
+
+        it "loads tile sets" {
+            std::fs::write("/tmp/tile_set1.json", r#"{"counts": {"A": 2, "Blank": 1}}"#).unwrap();
+            let set = load_tile_set("/tmp/tile_set1.json").unwrap();
+            assert_eq!(2, set.weights()[0]);
+            assert_eq!(1, set.weights()[26]);
+            std::fs::write("/tmp/tile_set2.json", r#"{"counts": {}}"#).unwrap();
+            assert!(load_tile_set("/tmp/tile_set2.json").is_err());
+        }
+
+        it "knows how likely each tile is" {
+            let total: f64 = Tile::all().iter().map(|t| t.prob()).sum();
+            assert!((total - 1.0).abs() < 1e-9);
+            assert!(Tile::E.prob() > Tile::Q.prob());
+        }
+
+        it "normalizes words to tiles" {
+            assert_eq!("cat", normalize_word("CAT"));
+            assert_eq!("cafe", normalize_word("café"));
+            assert_eq!("cafe", normalize_word("cafe\u{301}"));
+            assert_eq!("strasse", normalize_word("Straße"));
+            assert_eq!(vec![Tile::C, Tile::A, Tile::T], parse_tiles("Cat").unwrap());
+            assert!(parse_tiles("c4t").is_err());
+        }
+
+        it "spells words with letter pairs" {
+            let set = tile_set();
+            let mut with_pairs = set.clone();
+            with_pairs.counts.insert(Tile::Qu, 1);
+            let mut without_pairs = set.clone();
+            without_pairs.counts.insert(Tile::Th, 0);
+            assert_eq!(set.hash(), without_pairs.hash());
+            assert_ne!(set.hash(), with_pairs.hash());
+            assert_eq!(vec![Tile::Qu], with_pairs.pairs());
+            assert_eq!("Qit", tile_key(&[Tile::Qu, Tile::I, Tile::T]));
+            assert_eq!(vec![Tile::Qu, Tile::I, Tile::T], key_tiles("Qit").unwrap());
+            assert_eq!("qu", Tile::Qu.spelling());
+            assert_eq!(vec![Tile::Qu, Tile::I, Tile::T], with_pairs.spell("quit").unwrap());
+            assert_eq!(vec![Tile::Q, Tile::A, Tile::T], with_pairs.spell("qat").unwrap());
+            // Pairs are only taken as one tile when the tile set deals them.
+            assert_eq!(4, set.spell("quit").unwrap().len());
+        }
+
+        it "parses tiles from chars" {
+            assert_eq!(Tile::Q, Tile::from_char('q').unwrap());
+            assert!(Tile::from_char('?').is_err());
         }
     }
 }
@@ -0,0 +1,91 @@
+/// Timebanks: holding human players to a bank of thinking time for the whole game, drawn down by
+/// every turn they take, with a penalty once it runs out.
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The thinking time each timed player has left, in milliseconds, keyed by player ID. A player
+/// missing from it still has their full bank.
+pub type Clocks = HashMap<usize, u64>;
+
+/// What happens to a player whose bank runs out.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Penalty {
+    /// The AI plays their turns for them from then on, as safely as it can.
+    Safe,
+
+    /// They forfeit their seat, as if they had quit.
+    Forfeit,
+}
+
+/// A bank of thinking time given to every human seat at the table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Timebank {
+    /// The thinking time each player starts the game with, in milliseconds.
+    pub millis: u64,
+
+    /// What happens once a player has used it all.
+    pub penalty: Penalty,
+}
+
+impl Timebank {
+    /// Parses a timebank from its command-line form, the milliseconds optionally followed by the
+    /// penalty, e.g. "60000", "60000:safe" or "60000:forfeit". The penalty is safe by default.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut split = name.splitn(2, ':');
+        let millis = split.next()?.parse().ok()?;
+        let penalty = match split.next() {
+            None | Some("safe") => Penalty::Safe,
+            Some("forfeit") => Penalty::Forfeit,
+            _ => return None,
+        };
+        Some(Self {
+            millis: millis,
+            penalty: penalty,
+        })
+    }
+
+    /// The time the given player has left.
+    pub fn remaining(&self, clocks: &Clocks, player_id: usize) -> u64 {
+        *clocks.get(&player_id).unwrap_or(&self.millis)
+    }
+
+    /// The clocks after the given player has spent the given time thinking.
+    pub fn draw_down(&self, clocks: &Clocks, player_id: usize, elapsed: Duration) -> Clocks {
+        let spent = elapsed.as_millis().min(u64::MAX as u128) as u64;
+        let mut clocks = clocks.clone();
+        clocks.insert(
+            player_id,
+            self.remaining(&clocks, player_id).saturating_sub(spent),
+        );
+        clocks
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "timebanks" {
+        it "parses timebanks" {
+            assert_eq!(
+                Some(Timebank { millis: 60000, penalty: Penalty::Safe }),
+                Timebank::from_name("60000"));
+            assert_eq!(
+                Some(Timebank { millis: 500, penalty: Penalty::Forfeit }),
+                Timebank::from_name("500:forfeit"));
+            assert_eq!(Some(Penalty::Safe), Timebank::from_name("500:safe").map(|t| t.penalty));
+            assert_eq!(None, Timebank::from_name("500:never"));
+            assert_eq!(None, Timebank::from_name("slow"));
+        }
+
+        it "draws down each player's bank" {
+            let timebank = Timebank { millis: 1000, penalty: Penalty::Safe };
+            let clocks = timebank.draw_down(&hashmap!{}, 1, Duration::from_millis(300));
+            assert_eq!(700, timebank.remaining(&clocks, 1));
+            assert_eq!(1000, timebank.remaining(&clocks, 0));
+            let clocks = timebank.draw_down(&clocks, 1, Duration::from_millis(900));
+            assert_eq!(0, timebank.remaining(&clocks, 1));
+        }
+    }
+}
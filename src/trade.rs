@@ -0,0 +1,177 @@
+/// Trading: an optional phase before each round's first bet in which neighbours may swap a
+/// hidden item each. Every player offers an item or passes without seeing anyone else's choice,
+/// and a player swaps with the player on their left only if both made an offer.
+use crate::bet::*;
+use crate::config::*;
+use crate::error::*;
+use crate::hand::*;
+use crate::lang::*;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::cmp::Ordering;
+
+/// A player's choice in the trading phase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TradeAction<V> {
+    /// Offers the given hidden item to the player on the left, for one of theirs.
+    Offer(V),
+
+    /// Keeps the hand as it is.
+    Pass,
+}
+
+/// The seats that swap, each with the seat on its left, given every seat's choice. Seats are
+/// paired in turn from the one starting the round, so that nobody trades twice.
+pub fn trading_seats<V>(actions: &[TradeAction<V>], first_index: usize) -> Vec<(usize, usize)> {
+    let num_seats = actions.len();
+    if num_seats < 2 {
+        return vec![];
+    }
+    let offered = |seat: usize| match actions[seat] {
+        TradeAction::Offer(_) => true,
+        TradeAction::Pass => false,
+    };
+    let mut paired = vec![false; num_seats];
+    let mut seats = vec![];
+    for k in 0..num_seats {
+        let seat = (first_index + k) % num_seats;
+        let left = (seat + 1) % num_seats;
+        if !paired[seat] && !paired[left] && offered(seat) && offered(left) {
+            paired[seat] = true;
+            paired[left] = true;
+            seats.push((seat, left));
+        }
+    }
+    seats
+}
+
+/// Checks that each seat offers only an item it holds face down, giving the reason if not.
+pub fn check_offers<V: Holdable>(
+    hands: &[&Hand<V>],
+    actions: &[TradeAction<V>],
+) -> Result<(), ScrabrudoError> {
+    if hands.len() != actions.len() {
+        return Err(ScrabrudoError::InvalidTrade(
+            Msg::ChoicesForPlayers {
+                num_choices: actions.len(),
+                num_players: hands.len(),
+            }
+            .to_string(),
+        ));
+    }
+    for (seat, (hand, action)) in hands.iter().zip(actions).enumerate() {
+        if let TradeAction::Offer(item) = action {
+            if hand.trading(item, item.clone()).is_none() {
+                return Err(ScrabrudoError::InvalidTrade(
+                    Msg::SeatNotHolding {
+                        seat: seat,
+                        item: format!("{:?}", item),
+                    }
+                    .to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What the AI holding the given hand offers: the hidden item it values least, so long as it's
+/// worth less than the rest of the hand on average and so likely less than whatever comes back.
+/// Nothing is drawn at random, so trading leaves a seeded game's deals as they were.
+pub fn ai_trade<B: Bet>(hand: &Hand<B::V>) -> TradeAction<B::V> {
+    let hidden = &hand.items[hand.revealed.min(hand.items.len())..];
+    let value = |item: &B::V| B::trade_value(&hand.items, item);
+    let worst = match hidden
+        .iter()
+        .min_by(|a, b| value(a).partial_cmp(&value(b)).unwrap_or(Ordering::Equal))
+    {
+        Some(worst) => worst,
+        None => return TradeAction::Pass,
+    };
+    let mut rest = hand.items.clone();
+    rest.remove(rest.iter().position(|item| item == worst).unwrap());
+    if rest.is_empty() {
+        return TradeAction::Pass;
+    }
+    let average = rest.iter().map(value).sum::<f64>() / rest.len() as f64;
+    if value(worst) < average {
+        TradeAction::Offer(worst.clone())
+    } else {
+        TradeAction::Pass
+    }
+}
+
+/// Asks the human holding the given hand what they offer, until they offer an item they hold
+/// face down or pass.
+pub fn human_trade<V: Holdable>(
+    config: &GameConfig,
+    player_id: usize,
+    hand: &Hand<V>,
+) -> Result<TradeAction<V>, ScrabrudoError> {
+    let hidden = &hand.items[hand.revealed.min(hand.items.len())..];
+    loop {
+        info!(
+            "{}",
+            Msg::TradePrompt(
+                hidden
+                    .iter()
+                    .map(|item| item.notate())
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            )
+        );
+        let line: String = config.input.read_line(Some(player_id))?.trim().into();
+        if line.is_empty() {
+            return Ok(TradeAction::Pass);
+        }
+        match V::from_notation(&line) {
+            Ok(item) if hidden.contains(&item) => return Ok(TradeAction::Offer(item)),
+            Ok(_) => info!("{}", Msg::NotHeldFaceDown(line)),
+            Err(e) => info!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::die::Die;
+    use crate::tile::Tile;
+
+    fn hand<V: Holdable>(items: Vec<V>, revealed: usize) -> Hand<V> {
+        Hand { items: items, revealed: revealed, exchanged: false }
+    }
+
+    describe "trading" {
+        it "pairs neighbours who both offer" {
+            let offer = TradeAction::Offer(Die::Two);
+            let pass = TradeAction::Pass;
+            assert_eq!(vec![(0, 1)], trading_seats(&[offer.clone(), offer.clone(), pass.clone()], 0));
+            assert_eq!(Vec::<(usize, usize)>::new(), trading_seats(&[offer.clone(), pass.clone(), offer.clone(), pass.clone()], 0));
+            // Nobody trades twice, so the first to start the round gets first pick.
+            assert_eq!(vec![(1, 2)], trading_seats(&[offer.clone(), offer.clone(), offer.clone()], 1));
+            assert_eq!(vec![(2, 0)], trading_seats(&[offer.clone(), pass.clone(), offer.clone()], 2));
+            assert_eq!(vec![(1, 0)], trading_seats(&[offer.clone(), offer.clone()], 1));
+        }
+
+        it "offers what the hand needs least" {
+            assert_eq!(
+                TradeAction::Offer(Die::Five),
+                ai_trade::<PerudoBet>(&hand(vec![Die::One, Die::Three, Die::Three, Die::Five], 0)));
+            assert_eq!(TradeAction::Pass, ai_trade::<PerudoBet>(&hand(vec![Die::Four, Die::Four], 0)));
+            assert_eq!(
+                TradeAction::Offer(Tile::Q),
+                ai_trade::<ScrabrudoBet>(&hand(vec![Tile::E, Tile::Q, Tile::Blank], 0)));
+            // Items face up stay where they are.
+            assert_ne!(TradeAction::Offer(Tile::Q), ai_trade::<ScrabrudoBet>(&hand(vec![Tile::Q, Tile::E, Tile::A], 1)));
+        }
+
+        it "only takes offers of hidden items" {
+            let hand = hand(vec![Die::Two, Die::Three], 1);
+            assert!(check_offers(&[&hand], &[TradeAction::Offer(Die::Three)]).is_ok());
+            assert!(check_offers(&[&hand], &[TradeAction::Offer(Die::Two)]).is_err());
+            assert!(check_offers(&[&hand, &hand], &[TradeAction::Pass]).is_err());
+        }
+    }
+}
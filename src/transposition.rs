@@ -0,0 +1,174 @@
+/// A transposition table, remembering what positions were worth so that planning doesn't
+/// evaluate the same position twice.
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashMap;
+
+/// How many positions a table holds unless told otherwise.
+pub const DEFAULT_CAPACITY: usize = 1 << 16;
+
+/// Values of positions keyed by their canonical hash, as given by GameState::canonical_hash.
+#[derive(Debug, Clone)]
+pub struct TranspositionTable<T: Clone> {
+    entries: HashMap<u64, T>,
+    capacity: usize,
+    hits: usize,
+    misses: usize,
+}
+
+impl<T: Clone> Default for TranspositionTable<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<T: Clone> TranspositionTable<T> {
+    /// Creates an empty table holding up to the given number of positions.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Gets the value of the position with the given hash, if it has been stored.
+    pub fn get(&mut self, hash: u64) -> Option<T> {
+        match self.entries.get(&hash) {
+            Some(value) => {
+                self.hits += 1;
+                Some(value.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores the value of the position with the given hash.
+    /// Once full, the table is emptied rather than growing, as old positions from earlier in a
+    /// search are the least likely to come up again.
+    pub fn insert(&mut self, hash: u64, value: T) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&hash) {
+            debug!(
+                "Transposition table full at {} positions, clearing",
+                self.capacity
+            );
+            self.entries.clear();
+        }
+        self.entries.insert(hash, value);
+    }
+
+    /// Gets the value of the position with the given hash, evaluating and storing it if it
+    /// hasn't been seen.
+    pub fn get_or_insert_with(&mut self, hash: u64, evaluate: impl FnOnce() -> T) -> T {
+        match self.get(hash) {
+            Some(value) => value,
+            None => {
+                let value = evaluate();
+                self.insert(hash, value.clone());
+                value
+            }
+        }
+    }
+
+    /// The number of positions stored.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no positions are stored.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The share of lookups that found a stored position.
+    pub fn hit_rate(&self) -> f64 {
+        if self.hits + self.misses == 0 {
+            0.0
+        } else {
+            self.hits as f64 / (self.hits + self.misses) as f64
+        }
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    use crate::bet::*;
+    use crate::die::*;
+    use crate::game::*;
+    use crate::testing::*;
+
+    describe "canonical hashing" {
+        fn state(history: HashMap<usize, Vec<PerudoBet>>) -> GameState<PerudoBet> {
+            GameState { history: history, ..game_state(vec![2, 2]) }
+        }
+
+        fn bet(q: usize) -> PerudoBet {
+            PerudoBet { value: Die::Three, quantity: q }
+        }
+
+        it "ignores the order of the hand" {
+            let state = state(hashmap!{});
+            assert_eq!(
+                state.canonical_hash(&[Die::One, Die::Six]),
+                state.canonical_hash(&[Die::Six, Die::One]));
+            assert_ne!(
+                state.canonical_hash(&[Die::One, Die::Six]),
+                state.canonical_hash(&[Die::One, Die::Five]));
+        }
+
+        it "normalizes the history" {
+            let hand = [Die::Two, Die::Two];
+            assert_eq!(
+                state(hashmap!{}).canonical_hash(&hand),
+                state(hashmap!{ 1 => vec![] }).canonical_hash(&hand));
+            assert_eq!(
+                state(hashmap!{ 0 => vec![bet(1), bet(3)], 1 => vec![bet(2)] }).canonical_hash(&hand),
+                state(hashmap!{ 1 => vec![bet(2)], 0 => vec![bet(3), bet(1)] }).canonical_hash(&hand));
+            assert_ne!(
+                state(hashmap!{ 0 => vec![bet(1)] }).canonical_hash(&hand),
+                state(hashmap!{ 1 => vec![bet(1)] }).canonical_hash(&hand));
+        }
+
+        it "hashes anagram bets the same" {
+            let hash = |word: &str| {
+                GameState::<ScrabrudoBet> {
+                    history: hashmap!{ 0 => vec![ScrabrudoBet::from_word(&word.into())] },
+                    ..game_state(vec![2, 2])
+                }.canonical_hash(&[])
+            };
+            assert_eq!(hash("cat"), hash("act"));
+            assert_ne!(hash("cat"), hash("cot"));
+        }
+    }
+
+    describe "transposition tables" {
+        it "evaluates each position once" {
+            let mut table = TranspositionTable::new(10);
+            let mut evaluations = 0;
+            for hash in &[1, 2, 1, 1] {
+                table.get_or_insert_with(*hash, || {
+                    evaluations += 1;
+                    *hash * 10
+                });
+            }
+            assert_eq!(2, evaluations);
+            assert_eq!(Some(10), table.get(1));
+            assert_eq!(0.6, table.hit_rate());
+        }
+
+        it "clears once full" {
+            let mut table = TranspositionTable::new(2);
+            table.insert(1, ());
+            table.insert(2, ());
+            table.insert(2, ());
+            assert_eq!(2, table.len());
+            table.insert(3, ());
+            assert_eq!(1, table.len());
+            assert_eq!(None, table.get(1));
+        }
+    }
+}
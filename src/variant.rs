@@ -0,0 +1,538 @@
+/// Variants: each game that can be played, registered by the name it's chosen with, with the
+/// arguments only it takes, what has to be loaded to play it, and how it's played in each mode.
+/// Adding a variant means registering it here, with no mode needing to know of it.
+use crate::analysis;
+use crate::background;
+use crate::bundled;
+use crate::cache;
+use crate::cli::*;
+use crate::compare::{self, Comparison, Strategy};
+use crate::config::*;
+use crate::demo;
+use crate::dict;
+use crate::error::*;
+use crate::game::*;
+use crate::golden;
+use crate::net;
+use crate::opening::*;
+use crate::pool;
+use crate::prob_store;
+use crate::replay;
+use crate::save::*;
+use crate::series::*;
+use crate::tile;
+use crate::verify;
+
+use clap::ArgMatches;
+#[cfg(test)]
+use speculate::speculate;
+use std::collections::HashSet;
+use std::fs;
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+lazy_static! {
+    static ref VARIANTS: RwLock<Vec<Arc<Variant>>> = RwLock::new(vec![
+        Arc::new(Variant::of::<PerudoGame>("perudo", "", init_nothing, None)),
+        Arc::new(Variant::of::<ScrabrudoGame>(
+            "scrabrudo",
+            WORD_ARGS,
+            init_scrabrudo,
+            Some(dict::words_starting_with)
+        )),
+        Arc::new(Variant::of::<EquationGame>(
+            "equation",
+            "",
+            init_nothing,
+            None
+        )),
+    ]);
+}
+
+/// Loads what a variant is played with from its arguments, for games of the given config, and for
+/// a game of the given number of items if known.
+pub type Init = fn(&ArgMatches, &GameConfig, Option<usize>) -> Result<(), ScrabrudoError>;
+
+/// Plays a game, or a match of them, with the given players by the given rules.
+pub type Play =
+    fn(usize, HashSet<usize>, OpeningPolicy, GameConfig, Sitting) -> Result<(), ScrabrudoError>;
+
+/// Compares two strategies over the given numbers of players, items and games.
+pub type Compare = fn(
+    usize,
+    usize,
+    usize,
+    &[Strategy; 2],
+    &OpeningPolicy,
+    &GameConfig,
+) -> Result<Comparison, ScrabrudoError>;
+
+/// Plays a seeded game for people to watch, returning what was shown of each turn.
+pub type Demo =
+    fn(u64, usize, usize, &OpeningPolicy, &GameConfig) -> Result<Vec<String>, ScrabrudoError>;
+
+/// Plays many games at once with the given players by the given rules.
+pub type Serve =
+    fn(usize, HashSet<usize>, OpeningPolicy, GameConfig, Serving) -> Result<(), ScrabrudoError>;
+
+/// What to play rather than a single new game, if anything: a saved game, or a match.
+#[derive(Debug, Clone)]
+pub struct Sitting {
+    /// A saved game to carry on with.
+    pub resume: Option<String>,
+
+    /// The number of games in a match to play.
+    pub match_games: Option<usize>,
+
+    /// The file a match is kept in between games, if any.
+    pub match_file: Option<String>,
+}
+
+/// How many games are served at once, and where they're recorded.
+#[derive(Debug, Clone)]
+pub struct Serving {
+    pub num_games: usize,
+    pub num_threads: usize,
+
+    /// A directory to record each game in, if any.
+    pub records_dir: Option<String>,
+}
+
+/// A game that can be played, and how to play it in each mode.
+pub struct Variant {
+    /// The name the variant is chosen with, e.g. perudo.
+    pub name: &'static str,
+
+    /// The arguments only this variant takes, in the form the subcommands' arguments are given.
+    pub args: &'static str,
+
+    /// Completes bets typed at the terminal, if the variant's bets can be completed.
+    pub completions: Option<fn(&str) -> Vec<String>>,
+
+    init: Init,
+    play: Play,
+    calibrate: fn(usize, usize, OpeningPolicy, GameConfig) -> Result<(), ScrabrudoError>,
+    compare: Compare,
+    golden: fn(u64, usize, usize, &OpeningPolicy, &GameConfig) -> Result<String, ScrabrudoError>,
+    demo: Demo,
+    serve: Serve,
+    host: fn(&str, GameConfig, net::Hosting) -> Result<(), ScrabrudoError>,
+    replay: fn(&str, &GameConfig, bool) -> Result<(), ScrabrudoError>,
+    verify: fn(&str) -> Result<(), ScrabrudoError>,
+}
+
+impl Variant {
+    /// The variant playing games of the given type, loading what it's played with as given.
+    pub fn of<G: Game + Send>(
+        name: &'static str,
+        args: &'static str,
+        init: Init,
+        completions: Option<fn(&str) -> Vec<String>>,
+    ) -> Self {
+        Variant {
+            name: name,
+            args: args,
+            completions: completions,
+            init: init,
+            play: play::<G>,
+            calibrate: calibrate::<G>,
+            compare: compare::compare::<G>,
+            golden: golden::play_golden::<G>,
+            demo: demo::play_demo::<G>,
+            serve: serve::<G>,
+            host: host::<G>,
+            replay: replay::replay_file::<G>,
+            verify: verify::verify_file::<G>,
+        }
+    }
+
+    /// Loads what the variant is played with, for games of the given config, and for a game of
+    /// the given number of items if known.
+    pub fn init(
+        &self,
+        matches: &ArgMatches,
+        config: &GameConfig,
+        num_items: Option<usize>,
+    ) -> Result<(), ScrabrudoError> {
+        (self.init)(matches, config, num_items)
+    }
+
+    /// Plays either a single game or, if a number of games is given, a match, unless resuming a
+    /// saved game.
+    pub fn play(
+        &self,
+        num_players: usize,
+        human_indices: HashSet<usize>,
+        opening: OpeningPolicy,
+        config: GameConfig,
+        sitting: Sitting,
+    ) -> Result<(), ScrabrudoError> {
+        (self.play)(num_players, human_indices, opening, config, sitting)
+    }
+
+    /// Plays the given number of all-AI games and reports how well calibrated the AI's estimates
+    /// were.
+    pub fn calibrate(
+        &self,
+        num_players: usize,
+        num_games: usize,
+        opening: OpeningPolicy,
+        config: GameConfig,
+    ) -> Result<(), ScrabrudoError> {
+        (self.calibrate)(num_players, num_games, opening, config)
+    }
+
+    /// Plays the given number of all-AI games between two strategies, as compare::compare does.
+    pub fn compare(
+        &self,
+        num_players: usize,
+        items_per_player: usize,
+        num_games: usize,
+        strategies: &[Strategy; 2],
+        opening: &OpeningPolicy,
+        config: &GameConfig,
+    ) -> Result<Comparison, ScrabrudoError> {
+        (self.compare)(
+            num_players,
+            items_per_player,
+            num_games,
+            strategies,
+            opening,
+            config,
+        )
+    }
+
+    /// Plays an all-AI game from the given seed in game notation, as golden::play_golden does.
+    pub fn golden(
+        &self,
+        seed: u64,
+        num_players: usize,
+        items_per_player: usize,
+        opening: &OpeningPolicy,
+        config: &GameConfig,
+    ) -> Result<String, ScrabrudoError> {
+        (self.golden)(seed, num_players, items_per_player, opening, config)
+    }
+
+    /// Plays an all-AI game from the given seed with commentary on each move, as demo::play_demo
+    /// does.
+    pub fn demo(
+        &self,
+        seed: u64,
+        num_players: usize,
+        items_per_player: usize,
+        opening: &OpeningPolicy,
+        config: &GameConfig,
+    ) -> Result<Vec<String>, ScrabrudoError> {
+        (self.demo)(seed, num_players, items_per_player, opening, config)
+    }
+
+    /// Plays the given number of games at once across the given number of threads, reporting who
+    /// won each, and recording each in a directory of its own if given one.
+    pub fn serve(
+        &self,
+        num_players: usize,
+        human_indices: HashSet<usize>,
+        opening: OpeningPolicy,
+        config: GameConfig,
+        serving: Serving,
+    ) -> Result<(), ScrabrudoError> {
+        (self.serve)(num_players, human_indices, opening, config, serving)
+    }
+
+    /// Serves lobbies at the given address for people to find and play games in over the network,
+    /// hosting them as given, until stopped.
+    pub fn host(
+        &self,
+        addr: &str,
+        config: GameConfig,
+        hosting: net::Hosting,
+    ) -> Result<(), ScrabrudoError> {
+        (self.host)(addr, config, hosting)
+    }
+
+    /// Goes back over the games in notation at the given path, as replay::replay_file does.
+    pub fn replay(
+        &self,
+        path: &str,
+        config: &GameConfig,
+        explain: bool,
+    ) -> Result<(), ScrabrudoError> {
+        (self.replay)(path, config, explain)
+    }
+
+    /// Plays the recorded game at the given path again, as verify::verify_file does.
+    pub fn verify(&self, path: &str) -> Result<(), ScrabrudoError> {
+        (self.verify)(path)
+    }
+}
+
+/// Registers a variant, in place of any of the same name.
+pub fn register_variant(variant: Variant) {
+    let mut variants = VARIANTS.write().unwrap();
+    variants.retain(|v| v.name != variant.name);
+    variants.push(Arc::new(variant));
+}
+
+/// Every variant registered, in the order they were.
+pub fn variants() -> Vec<Arc<Variant>> {
+    VARIANTS.read().unwrap().clone()
+}
+
+/// The variant of the given name.
+pub fn variant(name: &str) -> Result<Arc<Variant>, ScrabrudoError> {
+    VARIANTS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|v| v.name == name)
+        .cloned()
+        .ok_or_else(|| ScrabrudoError::InvalidOption(format!("Invalid variant: {}", name)))
+}
+
+/// Loads nothing, for a variant played without anything loaded.
+fn init_nothing(
+    _matches: &ArgMatches,
+    _config: &GameConfig,
+    _num_items: Option<usize>,
+) -> Result<(), ScrabrudoError> {
+    Ok(())
+}
+
+/// Loads the tile set, dictionary and lookup, and word frequencies for the style of games of the
+/// given config or dictionary profile, that Scrabrudo is played with, for a game of the given
+/// number of tiles if known. Asked to, a lookup that can't be played with for them is built in
+/// the background, playing with a stand-in meanwhile, and games of the config switch to it once
+/// it's built.
+pub fn init_scrabrudo(
+    matches: &ArgMatches,
+    config: &GameConfig,
+    num_tiles: Option<usize>,
+) -> Result<(), ScrabrudoError> {
+    // Either path not given is played with from the bundled data, if built with it.
+    let bundled = match (
+        matches.value_of("dictionary_path"),
+        matches.value_of("lookup_path"),
+    ) {
+        (Some(_), Some(_)) => None,
+        _ => bundled::bundled_paths()?,
+    };
+    let (dict_path, lookup_path) = match &bundled {
+        Some((bundled_dict_path, bundled_lookup_path)) => (
+            matches
+                .value_of("dictionary_path")
+                .unwrap_or(bundled_dict_path),
+            matches
+                .value_of("lookup_path")
+                .unwrap_or(bundled_lookup_path),
+        ),
+        None => (
+            required_option(matches, "dictionary_path")?,
+            required_option(matches, "lookup_path")?,
+        ),
+    };
+    if let Some(tile_set_path) = matches.value_of("tile_set") {
+        tile::init_tile_set(tile::load_tile_set(tile_set_path)?);
+    }
+    let dict_profile = parse_option(
+        "dictionary profile",
+        matches.value_of("dict_profile").unwrap_or("full"),
+        dict::DictProfile::from_name,
+    )?;
+    let build_lookup = matches.is_present("build_lookup");
+    // The stand-in played with while the lookup is built keeps the commonest words.
+    if config.style == WordStyle::Common || dict_profile.needs_frequencies() || build_lookup {
+        dict::init_frequencies(
+            matches
+                .value_of("frequency_path")
+                .unwrap_or("data/google-10000-english.txt"),
+        )?;
+    }
+    dict::init_dict_profile(dict_profile);
+    let stale = match num_tiles {
+        Some(num_tiles) if build_lookup => {
+            background::stale_reason(lookup_path, num_tiles).map(|reason| (num_tiles, reason))
+        }
+        _ => None,
+    };
+    match stale {
+        Some((num_tiles, reason)) => {
+            info!("Can't play with lookup {}: {}", lookup_path, reason);
+            background::build_in_background(config, dict_path, lookup_path, num_tiles)?;
+        }
+        None => {
+            dict::init_dict(dict_path)?;
+            dict::init_lookup(lookup_path).map_err(|e| match num_tiles {
+                Some(_) => ScrabrudoError::InvalidOption(format!(
+                    "Couldn't open lookup {}: {}. Pass --build_lookup to build it while playing",
+                    lookup_path, e
+                )),
+                None => e,
+            })?;
+        }
+    }
+    let prob_cache_size = match matches.value_of("prob_cache_size") {
+        Some(size) => parse_option("cache size", size, |s| s.parse::<usize>().ok())?,
+        None => cache::DEFAULT_CACHE_SIZE,
+    };
+    cache::init_prob_cache(matches.value_of("prob_cache"), prob_cache_size)?;
+    if let Some(name) = matches.value_of("prob_store") {
+        let kind = parse_option(
+            "probability store",
+            name,
+            prob_store::ProbStoreKind::from_name,
+        )?;
+        prob_store::init_prob_store(kind.open(&dict::LookupHandle::default()));
+    }
+    Ok(())
+}
+
+/// Plays either a single game or, if a number of games is given, a match, unless resuming a saved
+/// game.
+fn play<G: Game>(
+    num_players: usize,
+    human_indices: HashSet<usize>,
+    opening: OpeningPolicy,
+    config: GameConfig,
+    sitting: Sitting,
+) -> Result<(), ScrabrudoError> {
+    if let Some(path) = &sitting.resume {
+        // What's never saved is taken from the config given, so the game switches dictionary
+        // and takes input as if it had never stopped.
+        let game = load_game::<G>(path)?;
+        let resumed = GameConfig {
+            dict: config.dict,
+            lookup: config.lookup,
+            input: config.input,
+            ..game.config().clone()
+        };
+        game.with_config(resumed).run()?;
+        return Ok(());
+    }
+    match sitting.match_games {
+        Some(num_games) => {
+            let match_file = sitting.match_file.as_deref();
+            let mut series = match match_file {
+                Some(path) => Match::load_or_new(path, num_players, num_games)?,
+                None => Match::new(num_players, num_games),
+            };
+            series.play::<G>(5, &human_indices, &opening, &config, match_file)
+        }
+        None => {
+            G::new(num_players, 5, human_indices, opening, config).run()?;
+            Ok(())
+        }
+    }
+}
+
+/// Plays the given number of all-AI games and reports how well calibrated the AI's estimates
+/// were.
+fn calibrate<G: Game>(
+    num_players: usize,
+    num_games: usize,
+    opening: OpeningPolicy,
+    config: GameConfig,
+) -> Result<(), ScrabrudoError> {
+    analysis::init_collection();
+    for game in 0..num_games {
+        info!("Calibration game {} of {}", game + 1, num_games);
+        G::new(num_players, 5, hashset! {}, opening.clone(), config.clone()).run()?;
+    }
+    analysis::report_records(&analysis::take_collected());
+    Ok(())
+}
+
+/// Plays the given number of games at once across the given number of threads, reporting who won
+/// each, and recording each in a directory of its own if given one.
+fn serve<G: Game + Send>(
+    num_players: usize,
+    human_indices: HashSet<usize>,
+    opening: OpeningPolicy,
+    config: GameConfig,
+    serving: Serving,
+) -> Result<(), ScrabrudoError> {
+    let (num_games, num_threads) = (serving.num_games, serving.num_threads);
+    let results = match &serving.records_dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            // Each game is seeded afresh, so that it can be played again from its record.
+            let seeds = (0..num_games)
+                .map(|i| (i, rand::random::<u64>()))
+                .collect::<Vec<(usize, u64)>>();
+            pool::map_all(seeds, num_threads, |(i, seed)| {
+                let (game, record) = verify::play_recorded::<G>(
+                    seed,
+                    num_players,
+                    5,
+                    &human_indices,
+                    &opening,
+                    &config,
+                )?;
+                verify::save_record(&record, &format!("{}/game_{}.json", dir, i + 1))?;
+                Ok(game)
+            })
+        }
+        None => {
+            let games = (0..num_games)
+                .map(|_| {
+                    G::new(
+                        num_players,
+                        5,
+                        human_indices.clone(),
+                        opening.clone(),
+                        config.clone(),
+                    )
+                })
+                .collect::<Vec<G>>();
+            pool::run_all(games, num_threads)
+        }
+    };
+    for (i, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(game) => match game.winner() {
+                Some(winner) => info!("Game {}: player {} won", i + 1, winner),
+                None => info!("Game {}: no winner", i + 1),
+            },
+            Err(e) => warn!("Game {}: {}", i + 1, e),
+        }
+    }
+    Ok(())
+}
+
+/// Serves lobbies at the given address for people to find and play games in over the network,
+/// hosting them as given, until stopped.
+fn host<G: Game>(
+    addr: &str,
+    config: GameConfig,
+    hosting: net::Hosting,
+) -> Result<(), ScrabrudoError> {
+    if let Some(dir) = &hosting.records_dir {
+        fs::create_dir_all(dir)?;
+    }
+    net::serve_lobbies::<G>(addr, config, hosting)?;
+    // The lobbies are served from threads of their own, for as long as the process runs.
+    loop {
+        thread::park();
+    }
+}
+
+#[cfg(test)]
+speculate! {
+    describe "variants" {
+        it "finds each variant by name" {
+            assert_eq!("perudo", variant("perudo").unwrap().name);
+            assert_eq!(WORD_ARGS, variant("scrabrudo").unwrap().args);
+            assert!(variant("scrabrudo").unwrap().completions.is_some());
+            assert!(variant("chess").is_err());
+        }
+
+        it "takes on new variants" {
+            register_variant(Variant::of::<PerudoGame>("perudo_again", "", init_nothing, None));
+            let again = variant("perudo_again").unwrap();
+            let game_config = GameConfig { max_rounds: Some(1), ..GameConfig::default() };
+            let record = again.golden(1, 2, 2, &OpeningPolicy::Likeliest, &game_config).unwrap();
+            assert!(record.contains("[Seed \"1\"]"));
+            assert!(variants().iter().any(|v| v.name == "perudo"));
+        }
+    }
+}
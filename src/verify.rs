@@ -0,0 +1,348 @@
+/// Verifying served games: each game's seed, dealt hands and every move are recorded as it is
+/// played, so that it can later be played again headlessly and checked to end the same way. A
+/// game that doesn't shows that the engine has changed since, or that the record was tampered
+/// with, which settles disputes over what happened. Built with the rng-audit feature, every random
+/// draw is recorded too, move by move, and checked when the game is played again.
+use crate::bet::*;
+use crate::config::*;
+use crate::error::*;
+use crate::explain::*;
+use crate::game::*;
+use crate::logging;
+use crate::opening::*;
+use crate::rng;
+use crate::rng::RngDraw;
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use speculate::speculate;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::{self, File};
+
+// Every thread records its own game, so that games served at once are kept apart.
+thread_local! {
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+/// What has been recorded so far of the game on this thread, encoded as it goes.
+#[derive(Debug, Default)]
+struct Recording {
+    hands: Vec<serde_json::Value>,
+    moves: Vec<serde_json::Value>,
+    draws: Vec<Vec<RngDraw>>,
+}
+
+/// A message sent to the table during a game played over the network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatLine {
+    /// How many moves had been made when it was sent.
+    pub after_move: usize,
+
+    pub player_id: usize,
+    pub text: String,
+}
+
+/// Everything needed to play a game again as it was played.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct GameRecord<B: Bet> {
+    /// The seed every deal and AI decision was drawn from.
+    pub seed: u64,
+
+    pub num_players: usize,
+    pub items_per_player: usize,
+
+    /// The IDs of the seats humans took, whose moves are taken from the record.
+    pub humans: Vec<usize>,
+
+    pub opening: OpeningPolicy,
+    pub config: GameConfig,
+
+    /// Each player's hand as dealt at the start of every round, by seat, with their ID.
+    pub hands: Vec<Vec<(usize, Vec<B::V>)>>,
+
+    /// Every move in turn, exchanges and quits included, with the ID of the player who made it.
+    pub moves: Vec<(usize, TurnOutcome<B>)>,
+
+    /// Every random draw made for each move, with what it was for, those dealing the first hands
+    /// counting towards the first move. Only audited games have any.
+    #[serde(default)]
+    pub draws: Vec<Vec<RngDraw>>,
+
+    /// What was said at the table, for games played over the network, in order. Playing the game
+    /// again leaves it be.
+    #[serde(default)]
+    pub chat: Vec<ChatLine>,
+
+    pub winner: Option<usize>,
+}
+
+/// Whether the game on this thread is being recorded.
+pub fn recording() -> bool {
+    RECORDING.with(|recording| recording.borrow().is_some())
+}
+
+/// Notes the hands of the game on this thread if it has just started a round.
+pub fn track_hands<G: Game>(game: &G) {
+    RECORDING.with(|recording| {
+        if let Some(recording) = recording.borrow_mut().as_mut() {
+            if recording.hands.len() < game.round() {
+                let hands = game
+                    .players()
+                    .iter()
+                    .map(|p| (p.id(), p.items().clone()))
+                    .collect::<Vec<(usize, Vec<G::V>)>>();
+                recording
+                    .hands
+                    .push(serde_json::to_value(hands).unwrap_or(serde_json::Value::Null));
+            }
+        }
+    });
+}
+
+/// Notes a move made in the game on this thread.
+pub fn track_move<G: Game>(player_id: usize, outcome: &TurnOutcome<G::B>) {
+    RECORDING.with(|recording| {
+        if let Some(recording) = recording.borrow_mut().as_mut() {
+            recording.moves.push(
+                serde_json::to_value((player_id, outcome)).unwrap_or(serde_json::Value::Null),
+            );
+            if cfg!(feature = "rng-audit") {
+                recording.draws.push(rng::take_audit());
+            }
+        }
+    });
+}
+
+/// Plays a game from the given seed to the end, recording it.
+pub fn play_recorded<G: Game>(
+    seed: u64,
+    num_players: usize,
+    items_per_player: usize,
+    human_indices: &HashSet<usize>,
+    opening: &OpeningPolicy,
+    config: &GameConfig,
+) -> Result<(G, GameRecord<G::B>), ScrabrudoError> {
+    rng::with_seed(seed, || {
+        RECORDING.with(|recording| *recording.borrow_mut() = Some(Recording::default()));
+        rng::begin_audit();
+        let result = G::new(
+            num_players,
+            items_per_player,
+            human_indices.clone(),
+            opening.clone(),
+            config.clone(),
+        )
+        .run();
+        let recording = RECORDING
+            .with(|recording| recording.borrow_mut().take())
+            .unwrap_or_default();
+        rng::end_audit();
+        let game = result?;
+        let mut humans = human_indices.iter().cloned().collect::<Vec<usize>>();
+        humans.sort();
+        let record = GameRecord {
+            seed: seed,
+            num_players: num_players,
+            items_per_player: items_per_player,
+            humans: humans,
+            opening: opening.clone(),
+            config: config.clone(),
+            hands: recording
+                .hands
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<_, _>>()?,
+            moves: recording
+                .moves
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<_, _>>()?,
+            draws: recording.draws,
+            chat: vec![],
+            winner: game.winner(),
+        };
+        Ok((game, record))
+    })
+}
+
+/// Plays the recorded game again from its seed, the AI choosing for itself and humans making
+/// their recorded moves, failing at the first deal, move or result that differs from the record.
+/// The AI must be set up as it was, and without time limits, to choose as it did.
+pub fn verify<G: Game>(record: &GameRecord<G::B>) -> Result<(), ScrabrudoError> {
+    rng::with_seed(record.seed, || {
+        logging::begin_game();
+        rng::begin_audit();
+        let mut game = G::new(
+            record.num_players,
+            record.items_per_player,
+            record.humans.iter().cloned().collect(),
+            record.opening.clone(),
+            record.config.clone(),
+        );
+        let mut rounds_checked = 0;
+        for (i, (player_id, recorded)) in record.moves.iter().enumerate() {
+            let round = game.round();
+            if rounds_checked < round {
+                let dealt = game
+                    .players()
+                    .iter()
+                    .map(|p| (p.id(), p.items().clone()))
+                    .collect::<Vec<(usize, Vec<G::V>)>>();
+                if record.hands.get(round - 1) != Some(&dealt) {
+                    return Err(ScrabrudoError::Drift(format!(
+                        "round {} was dealt {:?}, now {:?}",
+                        round,
+                        record.hands.get(round - 1),
+                        dealt
+                    )));
+                }
+                rounds_checked = round;
+            }
+            let player = &game.players()[game.current_index()];
+            if player.id() != *player_id {
+                return Err(ScrabrudoError::Drift(format!(
+                    "move {} was player {}'s, now player {}'s",
+                    i + 1,
+                    player_id,
+                    player.id()
+                )));
+            }
+            // Humans' trades can't be chosen again, so a trade at a table with any is as recorded.
+            let is_trade = matches!(recorded, TurnOutcome::Trade(_));
+            let has_humans = game.players().iter().any(|p| p.human());
+            game = if player.human() || (is_trade && has_humans) {
+                let next = game.apply(recorded.clone()).map_err(|e| {
+                    ScrabrudoError::Drift(format!("move {} can't be made: {}", i + 1, e))
+                })?;
+                logging::set_round(next.round());
+                next
+            } else {
+                let (outcome, next) = game.play_turn()?;
+                if &outcome != recorded {
+                    return Err(ScrabrudoError::Drift(format!(
+                        "move {} was {}, now {}",
+                        i + 1,
+                        describe(recorded),
+                        describe(&outcome)
+                    )));
+                }
+                next
+            };
+            // Without the audit, nothing is drawn to check.
+            if let (true, Some(recorded)) = (cfg!(feature = "rng-audit"), record.draws.get(i)) {
+                check_draws(i, recorded, &rng::take_audit())?;
+            }
+        }
+        rng::end_audit();
+        if game.winner() != record.winner {
+            let winner =
+                |id: Option<usize>| id.map_or("no one".into(), |id| format!("player {}", id));
+            return Err(ScrabrudoError::Drift(format!(
+                "{} won, now {}",
+                winner(record.winner),
+                winner(game.winner())
+            )));
+        }
+        Ok(())
+    })
+}
+
+/// Checks the draws made for the given move are those recorded, failing at the first that differs
+/// in what was drawn or what for. Where in the game each was drawn is only there to read.
+fn check_draws(i: usize, recorded: &[RngDraw], drawn: &[RngDraw]) -> Result<(), ScrabrudoError> {
+    for (j, (recorded, drawn)) in recorded.iter().zip(drawn).enumerate() {
+        if (&recorded.label, recorded.value) != (&drawn.label, drawn.value) {
+            return Err(ScrabrudoError::Drift(format!(
+                "draw {} of move {} was {}, now {}",
+                j + 1,
+                i + 1,
+                recorded,
+                drawn
+            )));
+        }
+    }
+    if recorded.len() != drawn.len() {
+        return Err(ScrabrudoError::Drift(format!(
+            "move {} made {} draws, now {}",
+            i + 1,
+            recorded.len(),
+            drawn.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Writes the record to the given path.
+pub fn save_record<B: Bet>(record: &GameRecord<B>, path: &str) -> Result<(), ScrabrudoError> {
+    serde_json::to_writer_pretty(File::create(path)?, record)?;
+    Ok(())
+}
+
+/// Verifies the game recorded at the given path.
+pub fn verify_file<G: Game>(path: &str) -> Result<(), ScrabrudoError> {
+    verify::<G>(&serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+speculate! {
+    before {
+        crate::testing::set_up();
+    }
+
+    describe "verifying games" {
+        fn record() -> GameRecord<crate::bet::PerudoBet> {
+            let (game, record) = play_recorded::<PerudoGame>(
+                7, 3, 2, &hashset!{}, &OpeningPolicy::Likeliest, &GameConfig::default()).unwrap();
+            assert_eq!(game.winner(), record.winner);
+            record
+        }
+
+        it "plays a recorded game the same again" {
+            let record = record();
+            assert_eq!(vec![2, 2, 2], record.hands[0].iter().map(|(_, h)| h.len()).collect::<Vec<usize>>());
+            assert!(record.moves.len() > record.hands.len());
+            verify::<PerudoGame>(&record).unwrap();
+        }
+
+        it "catches a record that was changed" {
+            let mut changed = record();
+            changed.winner = None;
+            match verify::<PerudoGame>(&changed) {
+                Err(ScrabrudoError::Drift(reason)) => assert!(reason.starts_with("no one won, now player ")),
+                _ => panic!("Missed a changed winner"),
+            }
+
+            let mut changed = record();
+            changed.moves[0].1 = TurnOutcome::Quit;
+            match verify::<PerudoGame>(&changed) {
+                Err(ScrabrudoError::Drift(reason)) => assert!(reason.starts_with("move 1 was Quit, now ")),
+                _ => panic!("Missed a changed move"),
+            }
+
+            let mut changed = record();
+            changed.seed += 1;
+            match verify::<PerudoGame>(&changed) {
+                Err(ScrabrudoError::Drift(reason)) => assert!(reason.starts_with("round 1 was dealt ")),
+                _ => panic!("Missed a changed seed"),
+            }
+        }
+
+        it "checks every draw when audited" {
+            let record = record();
+            if !cfg!(feature = "rng-audit") {
+                assert!(record.draws.is_empty());
+                return;
+            }
+            assert_eq!(record.moves.len(), record.draws.len());
+            assert_eq!("deal", record.draws[0][0].label);
+            let mut changed = record.clone();
+            changed.draws[0][0].value += 1;
+            match verify::<PerudoGame>(&changed) {
+                Err(ScrabrudoError::Drift(reason)) => assert!(reason.starts_with("draw 1 of move 1 was ")),
+                _ => panic!("Missed a changed draw"),
+            }
+        }
+    }
+}